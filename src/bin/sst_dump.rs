@@ -0,0 +1,49 @@
+// sst_dump: print an SSTable's footer, meta block, properties, bloom
+// filter stats and index, and optionally every key-value pair.
+//
+// Usage: sst_dump [--json] [--entries] <file.sst>...
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use lsm_engine::tools::sst_dump::{DumpFormat, DumpOptions, dump_file};
+
+const USAGE: &str = "usage: sst_dump [--json] [--entries] <file.sst>...";
+
+fn main() -> ExitCode {
+    let mut options = DumpOptions::default();
+    let mut paths = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => options.format = DumpFormat::Json,
+            "--entries" => options.entries = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown option {flag}\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for path in &paths {
+        if paths.len() > 1 && options.format == DumpFormat::Text {
+            let _ = writeln!(out, "== {} ==", path.display());
+        }
+        if let Err(e) = dump_file(path, &options, &mut out) {
+            eprintln!("{}: {e}", path.display());
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
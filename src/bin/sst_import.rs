@@ -0,0 +1,61 @@
+// sst_import: convert a LevelDB or RocksDB table file into this engine's
+// SSTable format, for ingestion without going through the write path.
+//
+// Usage: sst_import [--id N] <source.sst|.ldb> <dest.sst>
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use lsm_engine::sstable::builder::TableOptions;
+use lsm_engine::tools::sst_import::convert_file;
+
+const USAGE: &str = "usage: sst_import [--id N] <source.sst|.ldb> <dest.sst>";
+
+fn main() -> ExitCode {
+    let mut sst_id = 1;
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--id" => match args.next().and_then(|id| id.parse().ok()) {
+                Some(id) => sst_id = id,
+                None => {
+                    eprintln!("--id needs a number\n{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown option {flag}\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    let [src, dest] = paths.as_slice() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    match convert_file(src, dest, sst_id, &TableOptions::default()) {
+        Ok(summary) => {
+            println!(
+                "{}: {:?}, {} entries read, {} older versions dropped, {} written to {}",
+                src.display(),
+                summary.format,
+                summary.entries_read,
+                summary.shadowed,
+                summary.meta.entry_count,
+                dest.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", src.display());
+            ExitCode::FAILURE
+        }
+    }
+}
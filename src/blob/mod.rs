@@ -0,0 +1,116 @@
+pub mod reader;
+pub mod writer;
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+// Key-value separation (WiscKey): large values live in append-only blob
+// files, and the SSTable entry for such a key holds a BlobIndex pointing
+// at the value instead of the value itself. Compaction rewrites SSTables
+// but only copies the 32-byte pointers, so big values are written once
+// (at flush) instead of once per level.
+//
+// Blob files are named `{:06}.blob` and hold a sequence of records:
+//
+//   [key_len(4B)][value_len(4B)][key][value][crc32(4B)]
+//
+// The CRC covers everything before it. Keys are kept so a blob file can
+// be scanned without the SSTables that point into it.
+
+/// Prefix of every encoded BlobIndex. SSTable values starting with it are
+/// always blob pointers: flush moves user values that happen to start
+/// with it into a blob file too, whatever their size.
+pub const BLOB_INDEX_MAGIC: [u8; 8] = *b"\xffBLOBIDX";
+
+/// Size of an encoded BlobIndex.
+pub const BLOB_INDEX_SIZE: usize = BLOB_INDEX_MAGIC.len() + 24;
+
+/// Bytes before the key in a blob record: key_len + value_len.
+pub const BLOB_RECORD_HEADER_SIZE: usize = 8;
+
+/// Location of a value in a blob file, stored in its SSTable entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlobIndex {
+    /// Number of the blob file holding the value.
+    pub file_number: u64,
+    /// Byte offset of the value's record in the file.
+    pub offset: u64,
+    /// Value length in bytes.
+    pub size: u64,
+}
+
+impl BlobIndex {
+    /// Encode as [magic(8)][file_number(8)][offset(8)][size(8)].
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BLOB_INDEX_SIZE);
+        buf.extend_from_slice(&BLOB_INDEX_MAGIC);
+        buf.extend_from_slice(&self.file_number.to_le_bytes());
+        buf.extend_from_slice(&self.offset.to_le_bytes());
+        buf.extend_from_slice(&self.size.to_le_bytes());
+        buf
+    }
+
+    /// Decode a value written by encode().
+    pub fn decode(value: &[u8]) -> Result<Self> {
+        if value.len() != BLOB_INDEX_SIZE || !is_blob_index(value) {
+            return Err(Error::Corruption("bad blob index".into()));
+        }
+        let field = |i: usize| {
+            let start = BLOB_INDEX_MAGIC.len() + i * 8;
+            u64::from_le_bytes(value[start..start + 8].try_into().unwrap())
+        };
+        Ok(Self {
+            file_number: field(0),
+            offset: field(1),
+            size: field(2),
+        })
+    }
+}
+
+/// A blob file as recorded in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobFileMeta {
+    /// Blob file number; the file is `{:06}.blob`.
+    pub file_number: u64,
+    /// Records (values) in the file.
+    pub record_count: u64,
+    /// Sum of the value sizes, before any became garbage.
+    pub value_bytes: u64,
+    /// File size in bytes.
+    pub file_size: u64,
+}
+
+/// Whether an SSTable value is a blob pointer rather than the value itself.
+pub fn is_blob_index(value: &[u8]) -> bool {
+    value.starts_with(&BLOB_INDEX_MAGIC)
+}
+
+/// Whether flush should move `value` into a blob file: it's at least
+/// `min_blob_size` bytes (None = separation off), or it would otherwise
+/// be mistaken for a blob pointer.
+pub(crate) fn should_separate(value: &[u8], min_blob_size: Option<usize>) -> bool {
+    is_blob_index(value) || min_blob_size.is_some_and(|min| !value.is_empty() && value.len() >= min)
+}
+
+/// Path of blob file `file_number` in `dir`.
+pub fn blob_file_path(dir: &Path, file_number: u64) -> PathBuf {
+    dir.join(format!("{:06}.blob", file_number))
+}
+
+/// Numbers of the blob files in `dir`, ascending.
+pub(crate) fn find_blob_files(dir: &Path) -> Vec<u64> {
+    let mut numbers = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if let Some(filename) = entry.file_name().to_str()
+                && let Some(num_str) = filename.strip_suffix(".blob")
+                && let Ok(num) = num_str.parse::<u64>()
+            {
+                numbers.push(num);
+            }
+        }
+    }
+    numbers.sort_unstable();
+    numbers
+}
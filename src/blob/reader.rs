@@ -0,0 +1,223 @@
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use bytes::Bytes;
+
+use crate::blob::{
+    BLOB_RECORD_HEADER_SIZE, BlobFileMeta, BlobIndex, blob_file_path, is_blob_index,
+};
+use crate::error::{Error, Result};
+
+/// Read the value `index` points to from its blob file in `dir`,
+/// verifying the record's checksum.
+pub fn read_blob_value(dir: &Path, index: &BlobIndex) -> Result<Vec<u8>> {
+    let file = File::open(blob_file_path(dir, index.file_number))?;
+
+    let mut header = [0u8; BLOB_RECORD_HEADER_SIZE];
+    file.read_exact_at(&mut header, index.offset)?;
+    let key_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let value_len = u32::from_le_bytes(header[4..].try_into().unwrap()) as usize;
+    if value_len as u64 != index.size {
+        return Err(Error::Corruption(format!(
+            "blob record at {}:{} holds {value_len} bytes, index says {}",
+            index.file_number, index.offset, index.size
+        )));
+    }
+
+    // [key][value][crc32]
+    let mut record = vec![0u8; key_len + value_len + 4];
+    file.read_exact_at(&mut record, index.offset + BLOB_RECORD_HEADER_SIZE as u64)?;
+    let crc_start = key_len + value_len;
+    let stored_crc = u32::from_le_bytes(record[crc_start..].try_into().unwrap());
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header);
+    hasher.update(&record[..crc_start]);
+    if hasher.finalize() != stored_crc {
+        return Err(Error::Corruption(format!(
+            "blob record checksum mismatch at {}:{}",
+            index.file_number, index.offset
+        )));
+    }
+
+    record.truncate(crc_start);
+    record.drain(..key_len);
+    Ok(record)
+}
+
+/// The user value for an SSTable value: the blob it points to if it's a
+/// blob pointer, otherwise the value itself.
+pub(crate) fn resolve_value(dir: &Path, value: Bytes) -> Result<Bytes> {
+    if !is_blob_index(&value) {
+        return Ok(value);
+    }
+    let index = BlobIndex::decode(&value)?;
+    Ok(Bytes::from(read_blob_value(dir, &index)?))
+}
+
+/// Scans a blob file's records in order, yielding each key and where its
+/// value is. Values are skipped, not read or checksummed; read_blob_value
+/// does that for the ones that are needed.
+pub struct BlobFileReader {
+    reader: BufReader<File>,
+    file_number: u64,
+    /// Offset of the next record.
+    offset: u64,
+}
+
+impl BlobFileReader {
+    /// Open blob file `file_number` in `dir`.
+    pub fn open(dir: &Path, file_number: u64) -> Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(blob_file_path(dir, file_number))?),
+            file_number,
+            offset: 0,
+        })
+    }
+
+    /// The next record's key and BlobIndex, or None at the end of the file.
+    /// A torn record at the end (from a crash mid-flush) also ends it.
+    pub fn next_record(&mut self) -> Result<Option<(Vec<u8>, BlobIndex)>> {
+        let mut header = [0u8; BLOB_RECORD_HEADER_SIZE];
+        if !read_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+        let key_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let value_len = u32::from_le_bytes(header[4..].try_into().unwrap()) as u64;
+
+        let mut key = vec![0u8; key_len];
+        if !read_or_eof(&mut self.reader, &mut key)? {
+            return Ok(None);
+        }
+        // Skip [value][crc32].
+        self.reader.seek_relative(value_len as i64 + 4)?;
+
+        let index = BlobIndex {
+            file_number: self.file_number,
+            offset: self.offset,
+            size: value_len,
+        };
+        self.offset += BLOB_RECORD_HEADER_SIZE as u64 + key_len as u64 + value_len + 4;
+        Ok(Some((key, index)))
+    }
+}
+
+/// Fill `buf`, or return false if the file ends first.
+fn read_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Metadata for blob file `file_number` in `dir`, counted by scanning it;
+/// for files the manifest doesn't know about.
+pub(crate) fn scan_blob_file_meta(dir: &Path, file_number: u64) -> Result<BlobFileMeta> {
+    let mut reader = BlobFileReader::open(dir, file_number)?;
+    let mut meta = BlobFileMeta {
+        file_number,
+        record_count: 0,
+        value_bytes: 0,
+        file_size: std::fs::metadata(blob_file_path(dir, file_number))?.len(),
+    };
+    while let Some((_, index)) = reader.next_record()? {
+        meta.record_count += 1;
+        meta.value_bytes += index.size;
+    }
+    Ok(meta)
+}
+
+/// Streams one value out of its blob file, checking the record's checksum
+/// once the last byte has been read.
+///
+/// Holds the file open, so the value stays readable even if garbage
+/// collection deletes the file meanwhile.
+pub struct BlobValueReader {
+    file: File,
+    /// Offset of the next value byte to read.
+    position: u64,
+    /// Value bytes not yet read.
+    remaining: u64,
+    /// CRC of the record so far: header, key, and the value bytes read.
+    hasher: crc32fast::Hasher,
+    /// Offset of the record's CRC.
+    crc_offset: u64,
+    /// Whether the CRC has been checked.
+    verified: bool,
+}
+
+impl BlobValueReader {
+    /// Open the value `index` points to in its blob file in `dir`.
+    pub fn open(dir: &Path, index: &BlobIndex) -> Result<Self> {
+        let file = File::open(blob_file_path(dir, index.file_number))?;
+
+        let mut header = [0u8; BLOB_RECORD_HEADER_SIZE];
+        file.read_exact_at(&mut header, index.offset)?;
+        let key_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let value_len = u32::from_le_bytes(header[4..].try_into().unwrap()) as u64;
+        if value_len != index.size {
+            return Err(Error::Corruption(format!(
+                "blob record at {}:{} holds {value_len} bytes, index says {}",
+                index.file_number, index.offset, index.size
+            )));
+        }
+        let mut key = vec![0u8; key_len];
+        let key_offset = index.offset + BLOB_RECORD_HEADER_SIZE as u64;
+        file.read_exact_at(&mut key, key_offset)?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header);
+        hasher.update(&key);
+        let position = key_offset + key_len as u64;
+        Ok(Self {
+            file,
+            position,
+            remaining: value_len,
+            hasher,
+            crc_offset: position + value_len,
+            verified: false,
+        })
+    }
+
+    /// Value bytes not yet read.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    fn verify(&mut self) -> std::io::Result<()> {
+        let mut stored = [0u8; 4];
+        self.file.read_exact_at(&mut stored, self.crc_offset)?;
+        if self.hasher.clone().finalize() != u32::from_le_bytes(stored) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "blob record checksum mismatch",
+            ));
+        }
+        self.verified = true;
+        Ok(())
+    }
+}
+
+impl Read for BlobValueReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            if !self.verified {
+                self.verify()?;
+            }
+            return Ok(0);
+        }
+        let len = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.file.read_at(&mut buf[..len], self.position)?;
+        if n == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        self.hasher.update(&buf[..n]);
+        self.position += n as u64;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
@@ -0,0 +1,98 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::blob::{BLOB_RECORD_HEADER_SIZE, BlobFileMeta, BlobIndex, blob_file_path};
+use crate::error::{Error, Result};
+
+/// Appends values to a new blob file, handing back a BlobIndex for each.
+///
+/// Nothing is durable until finish(), which must complete before any
+/// SSTable pointing into the file is made visible.
+pub struct BlobFileWriter {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    file_number: u64,
+    /// Bytes written so far; the offset of the next record.
+    offset: u64,
+    /// Records written so far.
+    num_records: u64,
+    /// Sum of the value sizes written so far.
+    value_bytes: u64,
+}
+
+impl BlobFileWriter {
+    /// Create blob file `file_number` in `dir`.
+    pub fn create(dir: &Path, file_number: u64) -> Result<Self> {
+        let path = blob_file_path(dir, file_number);
+        Ok(Self {
+            writer: BufWriter::new(File::create(&path)?),
+            path,
+            file_number,
+            offset: 0,
+            num_records: 0,
+            value_bytes: 0,
+        })
+    }
+
+    /// Append `key` and `value` as one record; returns where the value is.
+    pub fn add(&mut self, key: &[u8], value: &[u8]) -> Result<BlobIndex> {
+        let (Ok(key_len), Ok(value_len)) = (u32::try_from(key.len()), u32::try_from(value.len()))
+        else {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "blob record key or value exceeds 4GB",
+            )));
+        };
+
+        let mut header = [0u8; BLOB_RECORD_HEADER_SIZE];
+        header[..4].copy_from_slice(&key_len.to_le_bytes());
+        header[4..].copy_from_slice(&value_len.to_le_bytes());
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header);
+        hasher.update(key);
+        hasher.update(value);
+
+        self.writer.write_all(&header)?;
+        self.writer.write_all(key)?;
+        self.writer.write_all(value)?;
+        self.writer.write_all(&hasher.finalize().to_le_bytes())?;
+
+        let index = BlobIndex {
+            file_number: self.file_number,
+            offset: self.offset,
+            size: value.len() as u64,
+        };
+        self.offset += (BLOB_RECORD_HEADER_SIZE + key.len() + value.len() + 4) as u64;
+        self.num_records += 1;
+        self.value_bytes += value.len() as u64;
+        Ok(index)
+    }
+
+    /// Number of the file being written.
+    pub fn file_number(&self) -> u64 {
+        self.file_number
+    }
+
+    /// Records added so far.
+    pub fn num_records(&self) -> u64 {
+        self.num_records
+    }
+
+    /// Path of the file being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flush and fsync the file. Returns its metadata for the manifest.
+    pub fn finish(mut self) -> Result<BlobFileMeta> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+        Ok(BlobFileMeta {
+            file_number: self.file_number,
+            record_count: self.num_records,
+            value_bytes: self.value_bytes,
+            file_size: self.offset,
+        })
+    }
+}
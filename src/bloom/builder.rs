@@ -17,6 +17,13 @@ impl BloomFilterBuilder {
         }
     }
 
+    /// Create a builder for `estimated_keys` keys at `bits_per_key` bits
+    /// each (10 ≈ 1% FPR), inverting bits_per_key = -1.44 * log2(FPR).
+    pub fn with_bits_per_key(estimated_keys: usize, bits_per_key: usize) -> Self {
+        let fpr = 2f64.powf(-(bits_per_key.max(1) as f64) / 1.44);
+        Self::new(estimated_keys, fpr)
+    }
+
     /// Add a key to the bloom filter being built.
     pub fn add_key(&mut self, key: &[u8]) {
         self.filter.insert(key);
@@ -20,6 +20,7 @@ use xxhash_rust::xxh3::xxh3_128;
 /// Hash trick: don't need k independent hash functions.
 /// Use double hashing: h_i(key) = h1(key) + i * h2(key) (mod m)
 /// where h1, h2 come from splitting a 128-bit hash into two 64-bit halves.
+#[derive(Clone)]
 pub struct BloomFilter {
     bits: Vec<u64>,
     num_hashes: u32,
@@ -66,6 +67,18 @@ impl BloomFilter {
         }
     }
 
+    /// Create an empty bloom filter of `num_bits` bits (at least 64)
+    /// probed `num_hashes` times per key, for when its memory budget
+    /// rather than its key count is known.
+    pub fn with_num_bits(num_bits: u32, num_hashes: u32) -> Self {
+        let num_bits = num_bits.max(64);
+        Self {
+            bits: vec![0u64; (num_bits as usize).div_ceil(64)],
+            num_hashes: num_hashes.max(1),
+            num_bits,
+        }
+    }
+
     /// Add a key to the bloom filter.
     pub fn insert(&mut self, key: &[u8]) {
         let (h1, h2) = self.hash_key(key);
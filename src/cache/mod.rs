@@ -1,4 +1,5 @@
 pub mod lru;
+pub mod table_cache;
 
 use std::sync::Arc;
 
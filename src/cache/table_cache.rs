@@ -0,0 +1,109 @@
+use std::sync::{Arc, Mutex};
+
+use crate::cache::BlockCache;
+use crate::cache::lru::LRUCache;
+use crate::error::Result;
+use crate::sstable::footer::SSTableMeta;
+use crate::sstable::paths::TablePaths;
+use crate::sstable::reader::{FileAccess, SSTable};
+
+/// Open SSTables shared by every reader of a DB.
+///
+/// Opening a table reads and parses its footer, index, filter and meta
+/// blocks, so doing it on every get or iterator costs more than the lookup
+/// itself. Tables are opened lazily on first use and kept in an LRU of at
+/// most `max_open_files` entries (each one a file handle or mapping);
+/// an evicted table closes once the last reader holding it drops its Arc.
+pub struct TableCache {
+    /// Directories holding the SSTable files.
+    paths: TablePaths,
+    /// Block cache every table opened here reads through.
+    block_cache: Arc<Mutex<BlockCache>>,
+    /// How tables are opened (Options::use_mmap_reads, use_direct_reads).
+    file_access: FileAccess,
+    /// Open tables by SSTable id; each entry counts 1 toward the capacity.
+    tables: Mutex<LRUCache<u64, Arc<SSTable>>>,
+}
+
+impl std::fmt::Debug for TableCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TableCache")
+            .field("paths", &self.paths)
+            .field("open_tables", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TableCache {
+    /// Create a cache keeping at most `max_open_files` tables open (at
+    /// least one).
+    pub fn new(
+        paths: TablePaths,
+        max_open_files: usize,
+        block_cache: Arc<Mutex<BlockCache>>,
+        file_access: FileAccess,
+    ) -> Self {
+        Self {
+            paths,
+            block_cache,
+            file_access,
+            tables: Mutex::new(LRUCache::new(max_open_files.max(1))),
+        }
+    }
+
+    /// The table with SSTable id `id`, opening it from path `path_id` if
+    /// it isn't cached.
+    pub fn get(&self, id: u64, path_id: u32) -> Result<Arc<SSTable>> {
+        if let Some(sst) = self.tables.lock().unwrap().get(&id) {
+            return Ok(Arc::clone(sst));
+        }
+
+        // Open without holding the lock, so a slow open doesn't stall
+        // readers of other tables. Two readers racing on the same table
+        // both open it and the second insert wins.
+        let path = self.paths.table_file(id, path_id);
+        let sst = Arc::new(SSTable::open_cached(
+            &path,
+            Some(&self.block_cache),
+            self.file_access,
+        )?);
+        self.tables.lock().unwrap().insert(id, Arc::clone(&sst), 1);
+        Ok(sst)
+    }
+
+    /// Drop table `id`, e.g. once compaction has replaced it.
+    pub fn evict(&self, id: u64) {
+        self.tables.lock().unwrap().remove(&id);
+    }
+
+    /// Number of tables currently open in the cache.
+    pub fn len(&self) -> usize {
+        self.tables.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Directories the tables are opened from.
+    pub fn paths(&self) -> &TablePaths {
+        &self.paths
+    }
+}
+
+/// The table of `meta` from `table_cache`, or opened from `paths` on its
+/// own with `file_access` when there is no cache (e.g. Snapshot::get()).
+pub(crate) fn open_table(
+    table_cache: Option<&TableCache>,
+    paths: &TablePaths,
+    meta: &SSTableMeta,
+    file_access: FileAccess,
+) -> Result<Arc<SSTable>> {
+    match table_cache {
+        Some(cache) => cache.get(meta.id, meta.path_id),
+        None => {
+            let path = paths.table_file(meta.id, meta.path_id);
+            Ok(Arc::new(SSTable::open_with(&path, file_access)?))
+        }
+    }
+}
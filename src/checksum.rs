@@ -0,0 +1,86 @@
+use xxhash_rust::xxh64::Xxh64;
+
+/// Checksum algorithm for WAL records and SSTable data blocks.
+///
+/// Every algorithm stores 32 bits, so switching doesn't change the size
+/// of anything on disk. The one in use is recorded in each file (the WAL
+/// header, the SSTable footer), so files written with different ones can
+/// be read side by side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecksumType {
+    /// CRC32 (IEEE). What files from before the choice existed use.
+    #[default]
+    Crc32,
+    /// CRC32C (Castagnoli), with the SSE4.2 or ARMv8 CRC instructions
+    /// where the CPU has them: several times faster than CRC32 over large
+    /// values.
+    Crc32c,
+    /// xxHash64, truncated to its low 32 bits. Fastest without CRC
+    /// instructions.
+    XxHash64,
+}
+
+impl ChecksumType {
+    pub fn to_byte(self) -> u8 {
+        match self {
+            ChecksumType::Crc32 => 0,
+            ChecksumType::Crc32c => 1,
+            ChecksumType::XxHash64 => 2,
+        }
+    }
+
+    /// None for a byte no ChecksumType encodes to.
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(ChecksumType::Crc32),
+            1 => Some(ChecksumType::Crc32c),
+            2 => Some(ChecksumType::XxHash64),
+            _ => None,
+        }
+    }
+
+    /// Checksum of `data`.
+    pub fn checksum(self, data: &[u8]) -> u32 {
+        let mut hasher = ChecksumHasher::new(self);
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+/// Checksum computed over several pieces of data, as if concatenated.
+pub struct ChecksumHasher {
+    state: State,
+}
+
+enum State {
+    Crc32(crc32fast::Hasher),
+    Crc32c(u32),
+    XxHash64(Xxh64),
+}
+
+impl ChecksumHasher {
+    pub fn new(checksum: ChecksumType) -> Self {
+        let state = match checksum {
+            ChecksumType::Crc32 => State::Crc32(crc32fast::Hasher::new()),
+            ChecksumType::Crc32c => State::Crc32c(0),
+            ChecksumType::XxHash64 => State::XxHash64(Xxh64::new(0)),
+        };
+        ChecksumHasher { state }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            State::Crc32(hasher) => hasher.update(data),
+            State::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+            State::XxHash64(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> u32 {
+        match self.state {
+            State::Crc32(hasher) => hasher.finalize(),
+            State::Crc32c(crc) => crc,
+            State::XxHash64(hasher) => hasher.digest() as u32,
+        }
+    }
+}
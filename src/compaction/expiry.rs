@@ -0,0 +1,18 @@
+use std::time::SystemTime;
+
+/// Hook that tells compaction when entries expire, for data written with
+/// a time to live.
+///
+/// Compaction asks it about the newest value of every key it writes, as
+/// of the time the compaction started, and drops the expired ones: a
+/// tombstone is written in their place while a deeper level may still
+/// hold an older version, as for CompactionFilter's Remove. Tombstones
+/// aren't passed; values stored in blob files are read back first.
+///
+/// As with a CompactionFilter, an expired key stays readable until its
+/// SSTables are compacted.
+pub trait ExpiryPolicy: Send + Sync {
+    /// When `key`, whose newest value is `value`, expires; None if it
+    /// never does.
+    fn expires_at(&self, key: &[u8], value: &[u8]) -> Option<SystemTime>;
+}
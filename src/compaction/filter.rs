@@ -0,0 +1,30 @@
+/// What a CompactionFilter decides for one entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterDecision {
+    /// Write the entry unchanged.
+    Keep,
+    /// Delete the key: a tombstone is written in its place, so older
+    /// versions in deeper levels stay hidden, and dropped like any other
+    /// tombstone once compaction reaches the bottom.
+    Remove,
+    /// Write this value instead. An empty value is a tombstone, as with
+    /// Remove.
+    ChangeValue(Vec<u8>),
+}
+
+/// Hook that lets the application rewrite or purge data as compaction
+/// passes over it, e.g. to drop expired sessions or soft-deleted records
+/// without a separate scan-and-delete pass.
+///
+/// Called once for every key a compaction writes, with the newest value
+/// among its inputs. Tombstones aren't passed; values stored in blob
+/// files are read back first, so the filter always sees the user value.
+///
+/// Keys only reach the filter when their SSTables are compacted, so a
+/// key may be read long after the filter would have removed it. Readers
+/// that must not see expired data still need to check it themselves.
+pub trait CompactionFilter: Send + Sync {
+    /// Decide what to do with `key`, whose newest value is `value`, as it
+    /// is written to `level`.
+    fn filter(&self, level: u32, key: &[u8], value: &[u8]) -> FilterDecision;
+}
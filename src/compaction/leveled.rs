@@ -1,13 +1,18 @@
-use crate::compaction::{CompactionStrategy, CompactionTask, find_overlapping_sstables};
+use crate::compaction::{
+    CompactionReason, CompactionStrategy, CompactionTask, find_overlapping_sstables,
+};
 use crate::sstable::footer::SSTableMeta;
 
-// TODO [M21]: Implement leveled compaction
-
 /// Leveled compaction strategy (what LevelDB/RocksDB use).
 ///
-/// Each level has a size limit: L1=10MB, L2=100MB, L3=1GB (10x ratio).
+/// L0 holds freshly flushed, overlapping SSTables; once it has
+/// `level0_file_num_trigger` of them, they are all merged with the
+/// overlapping part of L1.
+///
+/// Each level below has a size limit: L1=10MB, L2=100MB, L3=1GB (10x ratio).
 /// When a level exceeds its limit:
-///   1. Pick one SSTable from that level (round-robin or by key range)
+///   1. Pick the SSTable of that level overlapping the most bytes of the
+///      next one
 ///   2. Find all overlapping SSTables in the next level
 ///   3. Merge-sort: picked SSTable + overlapping next-level SSTables
 ///   4. Write new SSTables to the next level
@@ -19,47 +24,151 @@ pub struct LeveledStrategy {
     level_size_multiplier: usize,
     base_level_size: usize,
     max_levels: usize,
+    level0_file_num_trigger: usize,
 }
 
 impl LeveledStrategy {
+    /// Default number of L0 SSTables that triggers an L0 -> L1 compaction.
+    pub const DEFAULT_LEVEL0_TRIGGER: usize = 4;
+
     pub fn new(base_level_size: usize, multiplier: usize, max_levels: usize) -> Self {
         Self {
             level_size_multiplier: multiplier,
             base_level_size,
             max_levels,
+            level0_file_num_trigger: Self::DEFAULT_LEVEL0_TRIGGER,
         }
     }
+
+    /// Compact L0 into L1 once it holds `trigger` SSTables.
+    pub fn with_level0_trigger(mut self, trigger: usize) -> Self {
+        self.level0_file_num_trigger = trigger.max(1);
+        self
+    }
+
+    /// Size limit of `level` (1+): base, times the multiplier per level
+    /// below L1.
+    pub fn level_target(&self, level: usize) -> u64 {
+        let mut target = self.base_level_size as u64;
+        for _ in 1..level {
+            target = target.saturating_mul(self.level_size_multiplier as u64);
+        }
+        target
+    }
+
+    /// All of L0, plus the L1 SSTables overlapping it.
+    fn pick_level0(&self, levels: &[Vec<SSTableMeta>]) -> Option<CompactionTask> {
+        let l0 = levels.first()?;
+        if l0.len() < self.level0_file_num_trigger || self.max_levels < 2 {
+            return None;
+        }
+        let min = l0.iter().map(|s| s.min_key.as_slice()).min()?;
+        let max = l0.iter().map(|s| s.max_key.as_slice()).max()?;
+        let mut inputs = l0.clone();
+        if let Some(l1) = levels.get(1) {
+            inputs.extend(find_overlapping_sstables(l1, min, max));
+        }
+        Some(CompactionTask {
+            inputs,
+            output_level: 1,
+            reason: CompactionReason::Level0FilesNum,
+        })
+    }
+}
+
+/// Bytes of `next_level` overlapping `sst`'s key range.
+fn overlap_bytes(sst: &SSTableMeta, next_level: &[SSTableMeta]) -> u64 {
+    find_overlapping_sstables(next_level, &sst.min_key, &sst.max_key)
+        .iter()
+        .map(|s| s.file_size)
+        .sum()
 }
 
 impl CompactionStrategy for LeveledStrategy {
     fn pick_compaction(&self, levels: &[Vec<SSTableMeta>]) -> Option<CompactionTask> {
-        let mut budget = self.base_level_size as u64;
+        if let Some(task) = self.pick_level0(levels) {
+            return Some(task);
+        }
 
         for level_idx in 1..self.max_levels {
             if let Some(level_ssts) = levels.get(level_idx) {
                 let total_size: u64 = level_ssts.iter().map(|sst| sst.file_size).sum();
 
                 let next_level = level_idx + 1;
-                if total_size > budget && next_level < self.max_levels {
-                    let picked = &level_ssts[0];
+                if total_size > self.level_target(level_idx) && next_level < self.max_levels {
+                    // The file overlapping the most of the next level: its
+                    // keys are the likeliest to shadow older versions
+                    // there, so merging it reclaims the most space. Ties
+                    // go to the earliest in the level.
+                    let next_ssts = levels.get(next_level).map_or(&[][..], Vec::as_slice);
+                    let picked = level_ssts
+                        .iter()
+                        .rev()
+                        .max_by_key(|sst| overlap_bytes(sst, next_ssts))?;
                     let mut inputs = vec![picked.clone()];
-
-                    if let Some(next_ssts) = levels.get(next_level) {
-                        let overlapping =
-                            find_overlapping_sstables(next_ssts, &picked.min_key, &picked.max_key);
-                        inputs.extend(overlapping);
-                    }
+                    inputs.extend(find_overlapping_sstables(
+                        next_ssts,
+                        &picked.min_key,
+                        &picked.max_key,
+                    ));
 
                     return Some(CompactionTask {
                         inputs,
                         output_level: next_level as u32,
+                        reason: CompactionReason::LevelMaxBytes,
                     });
                 }
             }
-
-            budget *= self.level_size_multiplier as u64;
         }
 
         None
     }
+
+    /// L0: SSTables over the trigger. L1+: size over the level's target.
+    /// The last level never compacts and scores 0.
+    fn level_scores(&self, levels: &[Vec<SSTableMeta>]) -> Vec<f64> {
+        levels
+            .iter()
+            .enumerate()
+            .map(|(level, ssts)| {
+                if level + 1 >= self.max_levels {
+                    0.0
+                } else if level == 0 {
+                    ssts.len() as f64 / self.level0_file_num_trigger as f64
+                } else {
+                    let size: u64 = ssts.iter().map(|sst| sst.file_size).sum();
+                    size as f64 / self.level_target(level) as f64
+                }
+            })
+            .collect()
+    }
+
+    /// A due L0 is merged whole with L1. Then each level over its target
+    /// passes the excess down: that costs the excess plus the roughly
+    /// `multiplier` times as many next-level bytes it's merged with, and
+    /// may push the next level over in turn.
+    fn estimated_pending_bytes(&self, levels: &[Vec<SSTableMeta>]) -> u64 {
+        let level_size = |level: usize| -> u64 {
+            levels
+                .get(level)
+                .map_or(0, |ssts| ssts.iter().map(|sst| sst.file_size).sum())
+        };
+        let mut pending = 0u64;
+        // Bytes compacted into the level from above
+        let mut incoming = 0u64;
+        for level in 0..self.max_levels.saturating_sub(1) {
+            if level == 0 {
+                if levels.first().map_or(0, Vec::len) >= self.level0_file_num_trigger {
+                    incoming = level_size(0);
+                    pending += incoming + level_size(1);
+                }
+                continue;
+            }
+            let size = level_size(level) + incoming;
+            let target = self.level_target(level);
+            incoming = size.saturating_sub(target);
+            pending += incoming.saturating_mul(self.level_size_multiplier as u64 + 1);
+        }
+        pending
+    }
 }
@@ -1,7 +1,20 @@
+pub mod expiry;
+pub mod filter;
 pub mod leveled;
+pub mod output;
+pub mod partitioner;
+pub mod remote;
 pub mod scheduler;
 pub mod size_tiered;
+pub mod throttle;
+pub mod universal;
 
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
 use crate::sstable::footer::SSTableMeta;
 
 // TODO [M19]: Implement compaction core (k-way merge sort)
@@ -21,8 +34,70 @@ use crate::sstable::footer::SSTableMeta;
 /// Benchmark both on the same workload.
 #[derive(Debug, Clone, Copy)]
 pub enum CompactionStyle {
+    /// Merge all of L0 into L1 on every compaction.
     SizeTiered,
     Leveled,
+    /// Size-tiered over sorted runs: merge runs of similar size (see
+    /// universal::UniversalStrategy), for write-heavy workloads.
+    Universal,
+}
+
+/// Why a compaction ran. Recorded in the MANIFEST with its result and
+/// counted in Stats::compaction_reasons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CompactionReason {
+    /// Compaction records written before reasons were.
+    Unknown,
+    /// L0 reached its file count trigger.
+    Level0FilesNum,
+    /// A level below L0 outgrew its target size.
+    LevelMaxBytes,
+    /// Universal: the newer runs outgrew the oldest past
+    /// max_size_amplification_percent.
+    UniversalSizeAmplification,
+    /// Universal: a window of similarly sized runs.
+    UniversalSizeRatio,
+    /// Universal: more sorted runs than the trigger.
+    UniversalSortedRunNum,
+    /// DB::compact_range().
+    ManualCompaction,
+    /// Files past their time to live. The built-in strategies don't pick
+    /// these or the next; they're for custom CompactionStrategy types.
+    Ttl,
+    /// Files holding mostly tombstones.
+    DeletionTriggered,
+}
+
+impl CompactionReason {
+    /// MANIFEST byte.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            CompactionReason::Unknown => 0,
+            CompactionReason::Level0FilesNum => 1,
+            CompactionReason::LevelMaxBytes => 2,
+            CompactionReason::UniversalSizeAmplification => 3,
+            CompactionReason::UniversalSizeRatio => 4,
+            CompactionReason::UniversalSortedRunNum => 5,
+            CompactionReason::ManualCompaction => 6,
+            CompactionReason::Ttl => 7,
+            CompactionReason::DeletionTriggered => 8,
+        }
+    }
+
+    /// Parse a MANIFEST byte; bytes from a newer version read as Unknown.
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            1 => CompactionReason::Level0FilesNum,
+            2 => CompactionReason::LevelMaxBytes,
+            3 => CompactionReason::UniversalSizeAmplification,
+            4 => CompactionReason::UniversalSizeRatio,
+            5 => CompactionReason::UniversalSortedRunNum,
+            6 => CompactionReason::ManualCompaction,
+            7 => CompactionReason::Ttl,
+            8 => CompactionReason::DeletionTriggered,
+            _ => CompactionReason::Unknown,
+        }
+    }
 }
 
 /// A compaction task: which SSTables to merge and where.
@@ -32,6 +107,122 @@ pub struct CompactionTask {
     pub inputs: Vec<SSTableMeta>,
     /// Target level for the output SSTables.
     pub output_level: u32,
+    /// What made the strategy pick it.
+    pub reason: CompactionReason,
+}
+
+/// Runs the tasks of another strategy as manual compactions: the same
+/// inputs, with CompactionReason::ManualCompaction.
+pub struct ManualCompaction(pub Arc<dyn CompactionStrategy>);
+
+impl CompactionStrategy for ManualCompaction {
+    fn pick_compaction(&self, levels: &[Vec<SSTableMeta>]) -> Option<CompactionTask> {
+        let mut task = self.0.pick_compaction(levels)?;
+        task.reason = CompactionReason::ManualCompaction;
+        Some(task)
+    }
+
+    fn level_scores(&self, levels: &[Vec<SSTableMeta>]) -> Vec<f64> {
+        self.0.level_scores(levels)
+    }
+
+    fn estimated_pending_bytes(&self, levels: &[Vec<SSTableMeta>]) -> u64 {
+        self.0.estimated_pending_bytes(levels)
+    }
+}
+
+/// What a finished compaction read, wrote and dropped.
+#[derive(Debug, Clone)]
+pub struct CompactionJobInfo {
+    pub output_level: u32,
+    pub reason: CompactionReason,
+    /// SSTables merged, from the output level and the levels above it.
+    pub input_files: Vec<SSTableMeta>,
+    /// SSTables written to the output level, in key order.
+    pub output_files: Vec<SSTableMeta>,
+    /// Bytes of input SSTables from levels above the output level.
+    pub bytes_read_upper: u64,
+    /// Bytes of input SSTables already in the output level.
+    pub bytes_read_output_level: u64,
+    /// Bytes of the SSTables written to the output level.
+    pub bytes_written: u64,
+    /// Entries read from the inputs.
+    pub input_records: u64,
+    /// Entries written to the outputs.
+    pub output_records: u64,
+    /// Input entries that didn't make it to the outputs, by reason.
+    pub keys_dropped: DroppedKeys,
+    /// Wall time from picking the task to installing the result.
+    pub duration: Duration,
+}
+
+/// Entries a compaction dropped, by reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DroppedKeys {
+    /// Older versions of keys a newer input also holds.
+    pub overwritten: u64,
+    /// Entries deleted by a newer input's range tombstone.
+    pub range_deleted: u64,
+    /// Tombstones with nothing left below them to delete.
+    pub obsolete_tombstones: u64,
+    /// Entries the compaction filter removed. Each is still written as a
+    /// tombstone if a deeper level may hold an older version.
+    pub filtered: u64,
+    /// Entries the expiry policy found expired. Like filtered ones, each
+    /// is still written as a tombstone if a deeper level may hold an
+    /// older version.
+    pub expired: u64,
+}
+
+impl DroppedKeys {
+    fn add(&mut self, other: &DroppedKeys) {
+        self.overwritten += other.overwritten;
+        self.range_deleted += other.range_deleted;
+        self.obsolete_tombstones += other.obsolete_tombstones;
+        self.filtered += other.filtered;
+        self.expired += other.expired;
+    }
+}
+
+/// Asks running compactions to stop. Clones share one flag.
+///
+/// A compaction checks it between the entries it merges, so it stops
+/// within a block's worth of work, deletes the tables it has written and
+/// fails with an Interrupted I/O error, leaving its inputs live.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop compactions checking this token, now and until reset().
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Let compactions run again.
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Err(Interrupted) once cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "compaction cancelled",
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// Trait for compaction strategy implementations.
@@ -39,6 +230,21 @@ pub trait CompactionStrategy: Send + Sync {
     /// Decide if compaction is needed and which SSTables to compact.
     /// Returns None if no compaction needed.
     fn pick_compaction(&self, levels: &[Vec<SSTableMeta>]) -> Option<CompactionTask>;
+
+    /// How close each level is to needing compaction, one score per
+    /// level: past 1.0 the level is due. All zero for strategies that
+    /// don't score levels.
+    fn level_scores(&self, levels: &[Vec<SSTableMeta>]) -> Vec<f64> {
+        vec![0.0; levels.len()]
+    }
+
+    /// Estimated bytes compaction still has to rewrite before no level
+    /// needs compacting. By default, the inputs of the task it would
+    /// pick next.
+    fn estimated_pending_bytes(&self, levels: &[Vec<SSTableMeta>]) -> u64 {
+        self.pick_compaction(levels)
+            .map_or(0, |task| task.inputs.iter().map(|sst| sst.file_size).sum())
+    }
 }
 
 /// Given a slice of SSTables and a key range [range_min, range_max],
@@ -0,0 +1,212 @@
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::manifest::version::VersionSet;
+use crate::sstable::builder::{SSTableBuilder, TableOptions};
+use crate::sstable::footer::SSTableMeta;
+use crate::sstable::range_del::RangeTombstones;
+
+/// Writes the sorted output of a flush or compaction job as a run of
+/// SSTables, each cut once it reaches the level's target file size or
+/// where TableOptions::sst_partitioner asks for a cut.
+///
+/// Tables are only cut between keys, so consecutive outputs cover
+/// disjoint key ranges; range tombstones are split at the same points.
+/// Nothing is created until the first add(), so a job whose entries were
+/// all dropped (and that carries no range tombstones) produces no table.
+pub struct TableOutput<'a> {
+    db_path: &'a Path,
+    version_set: &'a VersionSet,
+    options: TableOptions,
+    level: u32,
+    target_file_size: u64,
+    /// Bytes the caller expects the output to take, to pick a path with
+    /// room for each table.
+    expected_bytes: u64,
+    /// Entries the caller still expects to add, for bloom filter sizing.
+    remaining_entries: usize,
+    builder: Option<SSTableBuilder>,
+    /// Path id of the current table.
+    path_id: u32,
+    /// File of the current table.
+    file: Option<PathBuf>,
+    /// Where the current table's key range starts: the key the previous
+    /// table was cut at, or the start of the output's range for the first.
+    lower: Option<Vec<u8>>,
+    /// Where the output's key range ends; None is unbounded.
+    upper: Option<Vec<u8>>,
+    range_tombstones: RangeTombstones,
+    /// Last key added, for the partitioner; kept only if there is one.
+    last_key: Vec<u8>,
+    /// Sequence numbers recorded in every table written.
+    seqno_range: (u64, u64),
+    finished: Vec<SSTableMeta>,
+}
+
+impl<'a> TableOutput<'a> {
+    /// Output to `level`, taking table ids from `version_set` and writing
+    /// to its table paths, or to `db_path` if it has none.
+    /// `estimated_entries` is the number of entries the job expects to add.
+    pub fn new(
+        db_path: &'a Path,
+        version_set: &'a VersionSet,
+        table_options: &TableOptions,
+        level: u32,
+        estimated_entries: usize,
+    ) -> Self {
+        TableOutput {
+            db_path,
+            version_set,
+            options: table_options.for_level(level),
+            level,
+            target_file_size: table_options.target_file_size(level),
+            expected_bytes: u64::MAX,
+            remaining_entries: estimated_entries,
+            builder: None,
+            path_id: 0,
+            file: None,
+            lower: None,
+            upper: None,
+            range_tombstones: RangeTombstones::default(),
+            last_key: Vec::new(),
+            seqno_range: (0, 0),
+            finished: Vec::new(),
+        }
+    }
+
+    /// Range tombstones to write alongside the entries; each table gets
+    /// the parts within its key range. Call before finish().
+    pub fn add_range_tombstones(&mut self, tombstones: &RangeTombstones) {
+        self.range_tombstones.extend(tombstones);
+    }
+
+    /// Restrict the output to [lower, upper), for a job writing one of
+    /// several adjacent key ranges: range tombstones are clipped to it.
+    /// None is unbounded. Call before the first add().
+    pub fn set_key_range(&mut self, lower: Option<&[u8]>, upper: Option<&[u8]>) {
+        self.lower = lower.map(<[u8]>::to_vec);
+        self.upper = upper.map(<[u8]>::to_vec);
+    }
+
+    /// Sequence numbers of the job's input, from `smallest` to `largest`,
+    /// recorded in each table. Call before the first add().
+    pub fn set_seqno_range(&mut self, smallest: u64, largest: u64) {
+        self.seqno_range = (smallest, largest);
+    }
+
+    /// About how many bytes the job will write, e.g. its inputs' size.
+    /// Each table's path needs room for the target file size or what is
+    /// left of this, whichever is smaller. Unset, it's the target size.
+    pub fn set_expected_bytes(&mut self, bytes: u64) {
+        self.expected_bytes = bytes;
+    }
+
+    /// Add the next entry, starting a new table first if the current one
+    /// has reached the target size or the partitioner cuts before `key`.
+    /// `seqno` is the sequence number of the write it comes from, if known.
+    pub fn add(&mut self, key: &[u8], value: &[u8], seqno: Option<u64>) -> Result<()> {
+        if let Some(ref builder) = self.builder {
+            let cut = builder.estimated_file_size() >= self.target_file_size
+                || self
+                    .options
+                    .sst_partitioner
+                    .as_ref()
+                    .is_some_and(|p| p.should_partition(self.level, &self.last_key, key));
+            if cut {
+                self.finish_table(Some(key))?;
+            }
+        }
+        if self.options.sst_partitioner.is_some() {
+            self.last_key.clear();
+            self.last_key.extend_from_slice(key);
+        }
+        let builder = match self.builder {
+            Some(ref mut builder) => builder,
+            None => {
+                let builder = self.start_table()?;
+                self.builder.insert(builder)
+            }
+        };
+        match seqno {
+            Some(seqno) => builder.add_with_seqno(key, value, seqno)?,
+            None => builder.add(key, value)?,
+        }
+        self.remaining_entries = self.remaining_entries.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Finish the last table. Returns the metadata of every table written,
+    /// in key order, with `level` set. On failure every table is deleted,
+    /// as by abandon().
+    pub fn finish(mut self) -> Result<Vec<SSTableMeta>> {
+        let upper = self.upper.take();
+        if let Err(e) = self.finish_table(upper.as_deref()) {
+            self.abandon();
+            return Err(e);
+        }
+        Ok(self.finished)
+    }
+
+    /// Delete every table written so far, the unfinished one included,
+    /// for a job that failed or was cancelled before recording them.
+    pub fn abandon(mut self) {
+        drop(self.builder.take());
+        if let Some(file) = self.file.take() {
+            let _ = std::fs::remove_file(file);
+        }
+        for meta in &self.finished {
+            let file = self
+                .version_set
+                .table_file(self.db_path, meta.id, meta.path_id);
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    /// Start a table in the path for the output level: the cold tier, or
+    /// the first path with room left, counting the tables this output has
+    /// already written, and with space free on disk for the table.
+    fn start_table(&mut self) -> Result<SSTableBuilder> {
+        let id = self.version_set.next_sst_id();
+        self.path_id = match self.version_set.table_paths() {
+            Some(paths) => {
+                let current = self.version_set.current();
+                let version = current.read().unwrap();
+                let written: u64 = self.finished.iter().map(|meta| meta.file_size).sum();
+                let table_size = self
+                    .target_file_size
+                    .min(self.expected_bytes.saturating_sub(written));
+                let tables = version.levels.iter().flatten().chain(&self.finished);
+                paths.choose(self.level, tables, table_size)
+            }
+            None => 0,
+        };
+        let path = self.version_set.table_file(self.db_path, id, self.path_id);
+        self.file = Some(path.clone());
+        let mut builder =
+            SSTableBuilder::with_options(&path, id, self.remaining_entries, &self.options)?;
+        builder.set_seqno_range(self.seqno_range.0, self.seqno_range.1);
+        Ok(builder)
+    }
+
+    /// Finish the current table, giving it the range tombstones below
+    /// `upper` (the next table's first key). Range tombstones with no
+    /// entries around them still get a table of their own.
+    fn finish_table(&mut self, upper: Option<&[u8]>) -> Result<()> {
+        let tombstones = self.range_tombstones.clipped(self.lower.as_deref(), upper);
+        if self.builder.is_none() && !tombstones.is_empty() {
+            self.builder = Some(self.start_table()?);
+        }
+        if let Some(mut builder) = self.builder.take() {
+            for tombstone in &tombstones {
+                builder.add_range_tombstone(&tombstone.start, &tombstone.end);
+            }
+            let mut meta = builder.finish()?;
+            meta.level = self.level;
+            meta.path_id = self.path_id;
+            self.finished.push(meta);
+            self.file = None;
+        }
+        self.lower = upper.map(<[u8]>::to_vec);
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+/// Hook that decides where flush and compaction cut their output into
+/// SSTables, on top of the target file size.
+///
+/// Cutting at application boundaries, e.g. between tenants, keeps each
+/// table within one shard, so a shard's tables can later be dropped or
+/// replaced as whole files without rewriting their neighbours.
+///
+/// Tables are only ever cut between two keys; a partitioner that never
+/// returns true leaves the output as the target file size alone cuts it.
+pub trait SstPartitioner: Send + Sync {
+    /// Whether the table being written to `level`, whose last key is
+    /// `prev_key`, should end before `key`.
+    fn should_partition(&self, level: u32, prev_key: &[u8], key: &[u8]) -> bool;
+}
+
+/// Cuts between keys whose first `len` bytes differ, so no table spans
+/// two prefixes. Keys shorter than `len` are their own prefix.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedPrefixPartitioner {
+    len: usize,
+}
+
+impl FixedPrefixPartitioner {
+    pub fn new(len: usize) -> Self {
+        FixedPrefixPartitioner { len }
+    }
+}
+
+impl SstPartitioner for FixedPrefixPartitioner {
+    fn should_partition(&self, _level: u32, prev_key: &[u8], key: &[u8]) -> bool {
+        prev_key[..prev_key.len().min(self.len)] != key[..key.len().min(self.len)]
+    }
+}
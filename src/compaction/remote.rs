@@ -0,0 +1,392 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::compaction::expiry::ExpiryPolicy;
+use crate::compaction::filter::CompactionFilter;
+use crate::compaction::scheduler::{MergeJob, RecordCounts, merge_inputs, remove_outputs};
+use crate::compaction::{CancellationToken, DroppedKeys};
+use crate::error::{Error, Result};
+use crate::manifest::version::{Version, VersionSet};
+use crate::sstable::builder::TableOptions;
+use crate::sstable::footer::SSTableMeta;
+use crate::sstable::reader::SSTable;
+
+/// Runs compactions somewhere else, e.g. on another machine, so the DB's
+/// own CPU and disk bandwidth stay with foreground work.
+///
+/// The DB picks the compaction and hands it over encoded as a
+/// CompactionServiceJob; the executor runs it with run_compaction_job()
+/// and returns the encoded result. The executor reads the input tables
+/// and writes the outputs at the paths the job names, so it needs the
+/// DB's storage mounted at the same paths. The DB then moves the outputs
+/// into place and records them itself.
+///
+/// If run() fails, or the result doesn't check out, the compaction runs
+/// locally instead.
+pub trait CompactionService: Send + Sync {
+    /// Run the encoded CompactionServiceJob `job` and return the encoded
+    /// CompactionServiceResult.
+    fn run(&self, job: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Subdirectory of the DB where executors write a job's outputs.
+pub const REMOTE_OUTPUT_DIR: &str = "remote_compaction";
+
+/// A compaction handed to a CompactionService.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionServiceJob {
+    /// Input tables and their files: only the id and level are sent, the
+    /// rest is read from the files.
+    pub inputs: Vec<(PathBuf, u64, u32)>,
+    /// Directory of the blob files the inputs point into.
+    pub blob_dir: PathBuf,
+    pub output_level: u32,
+    /// Whether no level below the output overlaps the inputs.
+    pub is_bottommost: bool,
+    /// Key ranges of the tables below the output level: tombstones for
+    /// keys in them are kept.
+    pub deeper_ranges: Vec<(Vec<u8>, Vec<u8>)>,
+    pub max_subcompactions: u32,
+    /// Where to write the output tables, as `{id:06}.sst`.
+    pub output_dir: PathBuf,
+    /// Output tables take ids from `first_output_id` up, and no more
+    /// than `max_output_files` of them.
+    pub first_output_id: u64,
+    pub max_output_files: u64,
+}
+
+/// What a CompactionService wrote for a job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionServiceResult {
+    /// Ids of the tables written to the job's output_dir, in key order.
+    pub output_ids: Vec<u64>,
+    pub input_records: u64,
+    pub output_records: u64,
+    pub keys_dropped: DroppedKeys,
+}
+
+/// Run an encoded CompactionServiceJob on the executor: merge its inputs
+/// into its output_dir, through `expiry` and `filter` if given, and
+/// return the encoded CompactionServiceResult. The tables are built with
+/// `table_options`, which should match the DB's.
+pub fn run_compaction_job(
+    job: &[u8],
+    table_options: &TableOptions,
+    expiry: Option<&dyn ExpiryPolicy>,
+    filter: Option<&dyn CompactionFilter>,
+) -> Result<Vec<u8>> {
+    let job = CompactionServiceJob::decode(job)?;
+    let mut inputs = Vec::new();
+    for (path, id, level) in &job.inputs {
+        let mut meta = SSTable::open(path)?.meta().clone();
+        meta.id = *id;
+        meta.level = *level;
+        inputs.push((path.clone(), meta));
+    }
+    let deeper = job
+        .deeper_ranges
+        .iter()
+        .map(|(min_key, max_key)| SSTableMeta {
+            id: 0,
+            level: job.output_level + 1,
+            min_key: min_key.clone(),
+            max_key: max_key.clone(),
+            file_size: 0,
+            entry_count: 0,
+            creation_time: 0,
+            smallest_seqno: 0,
+            largest_seqno: 0,
+            path_id: 0,
+        })
+        .collect();
+    let merge_job = MergeJob {
+        inputs,
+        blob_dir: job.blob_dir.clone(),
+        output_level: job.output_level,
+        is_bottommost: job.is_bottommost,
+        deeper_levels: vec![deeper],
+        max_subcompactions: job.max_subcompactions as usize,
+    };
+
+    std::fs::create_dir_all(&job.output_dir)?;
+    let version_set = VersionSet::new_from(Version::new(1), job.first_output_id);
+    let (outputs, counts) = merge_inputs(
+        &merge_job,
+        &job.output_dir,
+        &version_set,
+        table_options,
+        expiry,
+        filter,
+        None,
+    )?;
+    if outputs.len() as u64 > job.max_output_files {
+        remove_outputs(&version_set, &job.output_dir, &outputs);
+        return Err(Error::Io(io::Error::other(format!(
+            "compaction job wrote {} tables, only {} ids were reserved",
+            outputs.len(),
+            job.max_output_files
+        ))));
+    }
+    Ok(CompactionServiceResult {
+        output_ids: outputs.iter().map(|meta| meta.id).collect(),
+        input_records: counts.input,
+        output_records: counts.output,
+        keys_dropped: counts.dropped,
+    }
+    .encode())
+}
+
+/// Run `job` through `service` and move its outputs into the DB: each is
+/// opened, checked against the ids reserved for it, and moved to the
+/// table path of the output level. On any failure the outputs are
+/// deleted and the error returned, for the caller to compact locally.
+pub(crate) fn compact_remotely(
+    service: &dyn CompactionService,
+    job: &MergeJob,
+    db_path: &Path,
+    version_set: &VersionSet,
+    table_options: &TableOptions,
+    cancel: Option<&CancellationToken>,
+) -> Result<(Vec<SSTableMeta>, RecordCounts)> {
+    // Every subrange may end in a table of its own, each input may be
+    // cut at the target size, and the target size cuts the rest
+    let input_bytes: u64 = job.inputs.iter().map(|(_, meta)| meta.file_size).sum();
+    let max_output_files = (job.inputs.len() + job.max_subcompactions) as u64 * 2
+        + input_bytes / table_options.target_file_size(job.output_level).max(1);
+    let first_output_id = version_set.reserve_sst_ids(max_output_files);
+    let output_dir = db_path
+        .join(REMOTE_OUTPUT_DIR)
+        .join(format!("{:06}", first_output_id));
+    let request = CompactionServiceJob {
+        inputs: job
+            .inputs
+            .iter()
+            .map(|(path, meta)| (path.clone(), meta.id, meta.level))
+            .collect(),
+        blob_dir: job.blob_dir.clone(),
+        output_level: job.output_level,
+        is_bottommost: job.is_bottommost,
+        deeper_ranges: job
+            .deeper_levels
+            .iter()
+            .flatten()
+            .map(|meta| (meta.min_key.clone(), meta.max_key.clone()))
+            .collect(),
+        max_subcompactions: job.max_subcompactions as u32,
+        output_dir: output_dir.clone(),
+        first_output_id,
+        max_output_files,
+    };
+
+    let mut installed = Vec::new();
+    let result = service
+        .run(&request.encode())
+        .and_then(|result| CompactionServiceResult::decode(&result))
+        .and_then(|result| {
+            for &id in &result.output_ids {
+                installed.push(install_output(
+                    &request,
+                    id,
+                    db_path,
+                    version_set,
+                    &installed,
+                )?);
+            }
+            if let Some(cancel) = cancel {
+                cancel.check()?;
+            }
+            Ok(result)
+        });
+    let _ = std::fs::remove_dir_all(&output_dir);
+    match result {
+        Ok(result) => Ok((
+            installed,
+            RecordCounts {
+                input: result.input_records,
+                output: result.output_records,
+                dropped: result.keys_dropped,
+            },
+        )),
+        Err(e) => {
+            remove_outputs(version_set, db_path, &installed);
+            Err(e)
+        }
+    }
+}
+
+/// Move output `id` of `job` from its output_dir to the table path it
+/// belongs in, counting the outputs `installed` before it.
+fn install_output(
+    job: &CompactionServiceJob,
+    id: u64,
+    db_path: &Path,
+    version_set: &VersionSet,
+    installed: &[SSTableMeta],
+) -> Result<SSTableMeta> {
+    if !(job.first_output_id..job.first_output_id + job.max_output_files).contains(&id) {
+        return Err(Error::Corruption(format!(
+            "compaction service returned table {id}, outside the ids reserved for it"
+        )));
+    }
+    let file = job.output_dir.join(format!("{:06}.sst", id));
+    let mut meta = SSTable::open(&file)?.meta().clone();
+    if meta.id != id {
+        return Err(Error::Corruption(format!(
+            "compaction service output {id} holds table {}",
+            meta.id
+        )));
+    }
+    meta.level = job.output_level;
+    meta.path_id = match version_set.table_paths() {
+        Some(paths) => {
+            let current = version_set.current();
+            let version = current.read().unwrap();
+            let tables = version.levels.iter().flatten().chain(installed);
+            paths.choose(meta.level, tables, meta.file_size)
+        }
+        None => 0,
+    };
+    let dest = version_set.table_file(db_path, id, meta.path_id);
+    if std::fs::rename(&file, &dest).is_err() {
+        // Another filesystem: copy it over instead
+        std::fs::copy(&file, &dest)?;
+        std::fs::File::open(&dest)?.sync_all()?;
+    }
+    Ok(meta)
+}
+
+impl CompactionServiceJob {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        put_u32(&mut buf, self.inputs.len() as u32);
+        for (path, id, level) in &self.inputs {
+            put_path(&mut buf, path);
+            put_u64(&mut buf, *id);
+            put_u32(&mut buf, *level);
+        }
+        put_path(&mut buf, &self.blob_dir);
+        put_u32(&mut buf, self.output_level);
+        buf.push(self.is_bottommost as u8);
+        put_u32(&mut buf, self.deeper_ranges.len() as u32);
+        for (min_key, max_key) in &self.deeper_ranges {
+            put_bytes(&mut buf, min_key);
+            put_bytes(&mut buf, max_key);
+        }
+        put_u32(&mut buf, self.max_subcompactions);
+        put_path(&mut buf, &self.output_dir);
+        put_u64(&mut buf, self.first_output_id);
+        put_u64(&mut buf, self.max_output_files);
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = Reader(data);
+        let inputs = (0..r.u32()?)
+            .map(|_| Ok((r.path()?, r.u64()?, r.u32()?)))
+            .collect::<Result<_>>()?;
+        let blob_dir = r.path()?;
+        let output_level = r.u32()?;
+        let is_bottommost = r.take(1)?[0] != 0;
+        let deeper_ranges = (0..r.u32()?)
+            .map(|_| Ok((r.bytes()?.to_vec(), r.bytes()?.to_vec())))
+            .collect::<Result<_>>()?;
+        Ok(CompactionServiceJob {
+            inputs,
+            blob_dir,
+            output_level,
+            is_bottommost,
+            deeper_ranges,
+            max_subcompactions: r.u32()?,
+            output_dir: r.path()?,
+            first_output_id: r.u64()?,
+            max_output_files: r.u64()?,
+        })
+    }
+}
+
+impl CompactionServiceResult {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        put_u32(&mut buf, self.output_ids.len() as u32);
+        for &id in &self.output_ids {
+            put_u64(&mut buf, id);
+        }
+        put_u64(&mut buf, self.input_records);
+        put_u64(&mut buf, self.output_records);
+        put_u64(&mut buf, self.keys_dropped.overwritten);
+        put_u64(&mut buf, self.keys_dropped.range_deleted);
+        put_u64(&mut buf, self.keys_dropped.obsolete_tombstones);
+        put_u64(&mut buf, self.keys_dropped.filtered);
+        put_u64(&mut buf, self.keys_dropped.expired);
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = Reader(data);
+        Ok(CompactionServiceResult {
+            output_ids: (0..r.u32()?).map(|_| r.u64()).collect::<Result<_>>()?,
+            input_records: r.u64()?,
+            output_records: r.u64()?,
+            keys_dropped: DroppedKeys {
+                overwritten: r.u64()?,
+                range_deleted: r.u64()?,
+                obsolete_tombstones: r.u64()?,
+                filtered: r.u64()?,
+                expired: r.u64()?,
+            },
+        })
+    }
+}
+
+fn put_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    put_u32(buf, bytes.len() as u32);
+    buf.extend_from_slice(bytes);
+}
+
+fn put_path(buf: &mut Vec<u8>, path: &Path) {
+    put_bytes(buf, path.to_string_lossy().as_bytes());
+}
+
+/// Reads the fields put_*() wrote, in order.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(Error::Corruption(
+                "compaction service message too short".into(),
+            ));
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn path(&mut self) -> Result<PathBuf> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec())
+            .map(PathBuf::from)
+            .map_err(|_| Error::Corruption("compaction service path is not UTF-8".into()))
+    }
+}
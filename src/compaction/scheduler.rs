@@ -1,16 +1,26 @@
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::compaction::CompactionStrategy;
-use crate::error::Result;
+use crate::blob::reader::read_blob_value;
+use crate::blob::{BlobIndex, is_blob_index};
+use crate::compaction::expiry::ExpiryPolicy;
+use crate::compaction::filter::{CompactionFilter, FilterDecision};
+use crate::compaction::output::TableOutput;
+use crate::compaction::remote::{CompactionService, compact_remotely};
+use crate::compaction::{CancellationToken, CompactionJobInfo, CompactionStrategy, DroppedKeys};
+use crate::error::{Error, Result};
 use crate::iterator::StorageIterator;
 use crate::iterator::merge::MergeIterator;
 use crate::iterator::vec_iter::VecIterator;
+use crate::manifest::Manifest;
 use crate::manifest::version::{Version, VersionSet};
-use crate::sstable::builder::SSTableBuilder;
+use crate::sstable::builder::TableOptions;
+use crate::sstable::footer::SSTableMeta;
+use crate::sstable::range_del::RangeTombstones;
 use crate::sstable::reader::SSTable;
 
 enum CompactionMessage {
@@ -38,7 +48,16 @@ impl CompactionScheduler {
             loop {
                 match receiver.recv() {
                     Ok(CompactionMessage::Flush) => {
-                        let _ = run_compaction(&version_set, &*strategy, &db_path, block_size);
+                        let _ = run_compaction(
+                            &version_set,
+                            &*strategy,
+                            &db_path,
+                            &TableOptions {
+                                block_size,
+                                ..TableOptions::default()
+                            },
+                            &CompactionJobOptions::default(),
+                        );
                     }
                     Ok(CompactionMessage::Shutdown) => break,
                     Err(_) => break,
@@ -62,19 +81,452 @@ impl CompactionScheduler {
     }
 }
 
-/// Path to an SSTable file given its ID.
-fn sst_path(db_path: &Path, id: u64) -> PathBuf {
-    db_path.join(format!("{:06}.sst", id))
+/// Keys splitting the inputs' key range into at most `n` subranges:
+/// evenly spaced picks among the input files' first and last keys.
+fn subcompaction_boundaries(inputs: &[SSTableMeta], n: usize) -> Vec<Vec<u8>> {
+    let mut keys: Vec<&[u8]> = inputs
+        .iter()
+        .flat_map(|meta| [meta.min_key.as_slice(), meta.max_key.as_slice()])
+        .collect();
+    keys.sort();
+    keys.dedup();
+    // The smallest key starts the first subrange, not a later one
+    let candidates = keys.get(1..).unwrap_or_default();
+    let n = n.clamp(1, candidates.len() + 1);
+    let mut boundaries: Vec<Vec<u8>> = (1..n)
+        .map(|i| candidates[i * candidates.len() / n].to_vec())
+        .collect();
+    boundaries.dedup();
+    boundaries
 }
 
-/// Run one round of compaction if the strategy picks a task.
-/// Returns Ok(true) if compaction was performed, Ok(false) if nothing to do.
+/// Entries a subcompaction read, wrote and dropped.
+#[derive(Default)]
+pub(crate) struct RecordCounts {
+    pub(crate) input: u64,
+    pub(crate) output: u64,
+    pub(crate) dropped: DroppedKeys,
+}
+
+impl RecordCounts {
+    fn add(&mut self, other: &RecordCounts) {
+        self.input += other.input;
+        self.output += other.output;
+        self.dropped.add(&other.dropped);
+    }
+}
+
+/// What every subcompaction of one compaction shares.
+struct Subcompaction<'a> {
+    /// Input tables, newest first, each with the range tombstones of the
+    /// inputs newer than it.
+    inputs: &'a [(SSTable, RangeTombstones)],
+    db_path: &'a Path,
+    /// Directory of the blob files the inputs point into.
+    blob_dir: &'a Path,
+    version_set: &'a VersionSet,
+    table_options: &'a TableOptions,
+    output_level: u32,
+    /// No level below the output overlaps the compaction's key range.
+    is_bottommost: bool,
+    /// The levels below the output level, as of the compaction's start.
+    deeper_levels: &'a [Vec<SSTableMeta>],
+    /// Entries each subcompaction expects to write, for bloom filter sizing.
+    estimated_entries: usize,
+    /// Range tombstones of all inputs.
+    range_tombstones: &'a RangeTombstones,
+    seqno_range: (u64, u64),
+    expiry: Option<&'a dyn ExpiryPolicy>,
+    /// When the compaction started: entries expiring by then are dropped.
+    now: SystemTime,
+    filter: Option<&'a dyn CompactionFilter>,
+    cancel: Option<&'a CancellationToken>,
+}
+
+impl Subcompaction<'_> {
+    /// Whether no level below the output can hold an older version of
+    /// `key`. Then a tombstone for it has nothing left to delete: the
+    /// merge already dropped the versions it shadows among the inputs.
+    /// Readers of older versions hold on to the input tables themselves,
+    /// so no snapshot needs it either.
+    fn is_last_version(&self, key: &[u8]) -> bool {
+        self.is_bottommost
+            || !self
+                .deeper_levels
+                .iter()
+                .flatten()
+                .any(|meta| meta.min_key.as_slice() <= key && key <= meta.max_key.as_slice())
+    }
+
+    /// Err(Interrupted) once the compaction's token is cancelled.
+    fn check_cancelled(&self) -> Result<()> {
+        self.cancel.map_or(Ok(()), CancellationToken::check)
+    }
+
+    /// Merge the inputs' entries in [lower, upper) and write them to
+    /// SSTables of their own. None is unbounded. Returns the tables and
+    /// how many entries were read, written and dropped; on failure,
+    /// including cancellation, no table is left behind.
+    fn run(
+        &self,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Result<(Vec<SSTableMeta>, RecordCounts)> {
+        // Read each input's entries in range into a VecIterator, dropping
+        // those deleted by a newer input's range tombstones. Sequence
+        // numbers come along for inputs that store them
+        let mut counts = RecordCounts::default();
+        let mut merged_in = 0;
+        let mut merged_bytes = 0;
+        let mut iters: Vec<Box<dyn StorageIterator>> = Vec::new();
+        for (sst, newer_tombstones) in self.inputs {
+            let mut entries = Vec::new();
+            let mut seqnos = Vec::new();
+            let mut iter = sst.iter()?.with_seqnos()?;
+            match lower {
+                Some(lower) => iter.seek(lower)?,
+                None => iter.seek_to_first()?,
+            }
+            while iter.is_valid() && upper.is_none_or(|upper| iter.key() < upper) {
+                self.check_cancelled()?;
+                counts.input += 1;
+                if newer_tombstones.covers(iter.key()) {
+                    counts.dropped.range_deleted += 1;
+                } else {
+                    merged_bytes += (iter.key().len() + iter.value().len()) as u64;
+                    entries.push((iter.key().to_vec(), iter.value().to_vec()));
+                    seqnos.extend(iter.seqno());
+                }
+                iter.next()?;
+            }
+            merged_in += entries.len() as u64;
+            if seqnos.len() != entries.len() {
+                seqnos.clear();
+            }
+            iters.push(Box::new(VecIterator::with_seqnos(entries, seqnos)));
+        }
+        let mut merge = MergeIterator::new(iters)?;
+
+        let mut output = TableOutput::new(
+            self.db_path,
+            self.version_set,
+            self.table_options,
+            self.output_level,
+            self.estimated_entries,
+        );
+        output.set_key_range(lower, upper);
+        output.set_expected_bytes(merged_bytes);
+        if !self.is_bottommost {
+            output.add_range_tombstones(self.range_tombstones);
+        }
+        output.set_seqno_range(self.seqno_range.0, self.seqno_range.1);
+        match self.write(&mut merge, &mut output, &mut counts) {
+            Ok(merged_out) => {
+                // Whatever the merge didn't yield was overwritten
+                counts.dropped.overwritten = merged_in - merged_out;
+                Ok((output.finish()?, counts))
+            }
+            Err(e) => {
+                output.abandon();
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the merged entries, dropping expired ones, and the rest
+    /// through the compaction filter if any.
+    /// Range tombstones are dropped if bottommost, point tombstones
+    /// wherever no deeper level may hold their key. Adds what it writes
+    /// and drops to `counts`; returns the number of entries merged.
+    fn write(
+        &self,
+        merge: &mut MergeIterator,
+        output: &mut TableOutput,
+        counts: &mut RecordCounts,
+    ) -> Result<u64> {
+        let mut merged = 0;
+        while merge.is_valid() {
+            self.check_cancelled()?;
+            merged += 1;
+            let key = merge.key();
+            let mut value = merge.value().to_vec();
+            if (self.expiry.is_some() || self.filter.is_some()) && !value.is_empty() {
+                let user_value = if is_blob_index(&value) {
+                    read_blob_value(self.blob_dir, &BlobIndex::decode(&value)?)?
+                } else {
+                    value.clone()
+                };
+                let expired = self
+                    .expiry
+                    .and_then(|expiry| expiry.expires_at(key, &user_value))
+                    .is_some_and(|at| at <= self.now);
+                if expired {
+                    counts.dropped.expired += 1;
+                    value = Vec::new();
+                } else if let Some(filter) = self.filter {
+                    match filter.filter(self.output_level, key, &user_value) {
+                        FilterDecision::Keep => {}
+                        FilterDecision::Remove => {
+                            counts.dropped.filtered += 1;
+                            value = Vec::new();
+                        }
+                        FilterDecision::ChangeValue(new_value) => value = new_value,
+                    }
+                }
+            }
+            // Skip tombstones with nothing left below them to delete
+            if !(value.is_empty() && self.is_last_version(key)) {
+                output.add(key, &value, merge.seqno())?;
+                counts.output += 1;
+            } else if merge.value().is_empty() {
+                counts.dropped.obsolete_tombstones += 1;
+            }
+            merge.next()?;
+        }
+        Ok(merged)
+    }
+}
+
+/// Delete the tables of a compaction that won't be recorded.
+pub(crate) fn remove_outputs(version_set: &VersionSet, db_path: &Path, outputs: &[SSTableMeta]) {
+    for meta in outputs {
+        let _ = std::fs::remove_file(version_set.table_file(db_path, meta.id, meta.path_id));
+    }
+}
+
+/// Re-read the tables a compaction wrote, in key order, before they're
+/// committed: each must pass SSTable::verify_integrity(), match the
+/// metadata it was written with, and follow the previous table's keys,
+/// and together they must hold the `written` entries the merge produced
+/// from `input_entries`.
+fn verify_outputs(
+    version_set: &VersionSet,
+    db_path: &Path,
+    outputs: &[SSTableMeta],
+    written: u64,
+    input_entries: u64,
+) -> Result<()> {
+    let mut entries = 0;
+    let mut prev_max_key: Option<&[u8]> = None;
+    for meta in outputs {
+        let sst = SSTable::open(&version_set.table_file(db_path, meta.id, meta.path_id))?;
+        let report = sst.verify_integrity()?;
+        if let Some(issue) = report.issues.first() {
+            return Err(Error::Corruption(format!(
+                "compaction output {}: {:?}",
+                meta.id, issue
+            )));
+        }
+        let read = sst.meta();
+        if (read.entry_count, &read.min_key, &read.max_key)
+            != (meta.entry_count, &meta.min_key, &meta.max_key)
+        {
+            return Err(Error::Corruption(format!(
+                "compaction output {}: metadata differs from what was written",
+                meta.id
+            )));
+        }
+        if meta.entry_count > 0 {
+            if prev_max_key.is_some_and(|prev| meta.min_key.as_slice() <= prev) {
+                return Err(Error::Corruption(format!(
+                    "compaction output {}: keys overlap the previous output",
+                    meta.id
+                )));
+            }
+            prev_max_key = Some(&meta.max_key);
+        }
+        entries += report.entries_checked;
+    }
+    if entries != written || written > input_entries {
+        return Err(Error::Corruption(format!(
+            "compaction outputs hold {entries} entries, expected {written} \
+             from {input_entries} input entries"
+        )));
+    }
+    Ok(())
+}
+
+/// A compaction's inputs, where they're read from, and what its output
+/// level needs to know: everything the merge needs besides where the
+/// output goes.
+pub(crate) struct MergeJob {
+    /// Input tables and their files.
+    pub(crate) inputs: Vec<(PathBuf, SSTableMeta)>,
+    /// Directory of the blob files the inputs point into.
+    pub(crate) blob_dir: PathBuf,
+    pub(crate) output_level: u32,
+    /// No level below the output overlaps the inputs' key range.
+    pub(crate) is_bottommost: bool,
+    /// Tables of the levels below the output level; only their key
+    /// ranges are used.
+    pub(crate) deeper_levels: Vec<Vec<SSTableMeta>>,
+    pub(crate) max_subcompactions: usize,
+}
+
+/// Merge the inputs of `job` and write them to SSTables in `db_path`,
+/// with ids and paths from `version_set`. Returns the tables and what
+/// was read, written and dropped; on failure, including cancellation,
+/// no table is left behind.
+pub(crate) fn merge_inputs(
+    job: &MergeJob,
+    db_path: &Path,
+    version_set: &VersionSet,
+    table_options: &TableOptions,
+    expiry: Option<&dyn ExpiryPolicy>,
+    filter: Option<&dyn CompactionFilter>,
+    cancel: Option<&CancellationToken>,
+) -> Result<(Vec<SSTableMeta>, RecordCounts)> {
+    // Open the input SSTables, newest first: MergeIterator keeps the
+    // first source's value for duplicate keys. Lower levels are newer,
+    // and within a level later (higher) ids are. Each input's entries
+    // deleted by a newer input's range tombstones are dropped later, so
+    // note which tombstones are newer than each.
+    let mut files: Vec<&(PathBuf, SSTableMeta)> = job.inputs.iter().collect();
+    files.sort_by_key(|(_, meta)| (meta.level, std::cmp::Reverse(meta.id)));
+    let mut inputs = Vec::new();
+    let mut range_tombstones = RangeTombstones::default();
+    for (path, _) in files {
+        let sst = if table_options.use_direct_io {
+            SSTable::open_direct(path)?
+        } else {
+            SSTable::open(path)?
+        };
+        let newer_tombstones = range_tombstones.clone();
+        range_tombstones.extend(sst.range_tombstones());
+        inputs.push((sst, newer_tombstones));
+    }
+
+    // Merge and write each subrange of the key range, in parallel.
+    // Outputs span the sequence numbers of all inputs.
+    let metas: Vec<SSTableMeta> = job.inputs.iter().map(|(_, meta)| meta.clone()).collect();
+    let boundaries = subcompaction_boundaries(&metas, job.max_subcompactions);
+    let total_entries: u64 = metas.iter().map(|s| s.entry_count).sum();
+    let subcompaction = Subcompaction {
+        inputs: &inputs,
+        db_path,
+        blob_dir: &job.blob_dir,
+        version_set,
+        table_options,
+        output_level: job.output_level,
+        is_bottommost: job.is_bottommost,
+        deeper_levels: &job.deeper_levels,
+        estimated_entries: (total_entries as usize).div_ceil(boundaries.len() + 1),
+        range_tombstones: &range_tombstones,
+        seqno_range: (
+            metas.iter().map(|s| s.smallest_seqno).min().unwrap_or(0),
+            metas.iter().map(|s| s.largest_seqno).max().unwrap_or(0),
+        ),
+        expiry,
+        now: SystemTime::now(),
+        filter,
+        cancel,
+    };
+    let mut bounds: Vec<Option<&[u8]>> = vec![None];
+    bounds.extend(boundaries.iter().map(|key| Some(key.as_slice())));
+    bounds.push(None);
+    let outputs: Vec<Result<(Vec<SSTableMeta>, RecordCounts)>> = std::thread::scope(|s| {
+        let subcompaction = &subcompaction;
+        let handles: Vec<_> = bounds
+            .windows(2)
+            .skip(1)
+            .map(|range| {
+                let (lower, upper) = (range[0], range[1]);
+                s.spawn(move || subcompaction.run(lower, upper))
+            })
+            .collect();
+        // The first subrange runs on this thread
+        let mut outputs = vec![subcompaction.run(bounds[0], bounds[1])];
+        outputs.extend(handles.into_iter().map(|handle| {
+            handle
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }));
+        outputs
+    });
+    let mut new_metas = Vec::new();
+    let mut counts = RecordCounts::default();
+    let mut failure = None;
+    for output in outputs {
+        match output {
+            Ok((metas, subcompaction_counts)) => {
+                new_metas.extend(metas);
+                counts.add(&subcompaction_counts);
+            }
+            Err(e) => {
+                failure.get_or_insert(e);
+            }
+        }
+    }
+    // Subranges that did finish are dropped along with the failed one
+    if let Some(e) = failure {
+        remove_outputs(version_set, db_path, &new_metas);
+        return Err(e);
+    }
+    Ok((new_metas, counts))
+}
+
+/// How run_compaction() runs a compaction and what it does with the
+/// entries, besides merging them.
+#[derive(Clone, Copy)]
+pub struct CompactionJobOptions<'a> {
+    /// Where the result is recorded before the new version is installed
+    /// and the inputs deleted, so a reopen sees the outputs. Default:
+    /// None.
+    pub manifest: Option<&'a Mutex<Manifest>>,
+    /// Drops the entries expired by the time the compaction starts.
+    /// Default: None.
+    pub expiry: Option<&'a dyn ExpiryPolicy>,
+    /// Every other live entry written goes through it first. Default:
+    /// None.
+    pub filter: Option<&'a dyn CompactionFilter>,
+    /// The key range is split into up to this many subranges at input
+    /// file boundaries, merged and written by parallel threads into
+    /// separate SSTables. Default: 1.
+    pub max_subcompactions: usize,
+    /// Once cancelled the merge stops, every table written is deleted
+    /// and the inputs stay live. Default: None.
+    pub cancel: Option<&'a CancellationToken>,
+    /// The merge is handed to it (see remote::CompactionService) and its
+    /// outputs installed here; if the service fails, the compaction runs
+    /// locally instead. Default: None.
+    pub service: Option<&'a dyn CompactionService>,
+}
+
+impl Default for CompactionJobOptions<'_> {
+    fn default() -> Self {
+        CompactionJobOptions {
+            manifest: None,
+            expiry: None,
+            filter: None,
+            max_subcompactions: 1,
+            cancel: None,
+            service: None,
+        }
+    }
+}
+
+/// Run one round of compaction if the strategy picks a task, as set by
+/// `options`. Returns what the compaction read, wrote and dropped, or
+/// Ok(None) if there was nothing to do.
+///
+/// Whether it fails or is cancelled, a compaction leaves no output
+/// behind.
 pub fn run_compaction(
     version_set: &VersionSet,
     strategy: &dyn CompactionStrategy,
     db_path: &Path,
-    block_size: usize,
-) -> Result<bool> {
+    table_options: &TableOptions,
+    options: &CompactionJobOptions,
+) -> Result<Option<CompactionJobInfo>> {
+    let CompactionJobOptions {
+        manifest,
+        expiry,
+        filter,
+        max_subcompactions,
+        cancel,
+        service,
+    } = *options;
+    let start = Instant::now();
     // 1. Read current levels (clone to release lock quickly)
     let levels = {
         let current = version_set.current();
@@ -85,50 +537,18 @@ pub fn run_compaction(
     // 2. Ask strategy if compaction is needed
     let task = match strategy.pick_compaction(&levels) {
         Some(task) => task,
-        None => return Ok(false),
+        None => return Ok(None),
     };
 
-    // 3. Read input SSTables into VecIterators
-    let mut iters: Vec<Box<dyn StorageIterator>> = Vec::new();
-    for meta in &task.inputs {
-        let path = sst_path(db_path, meta.id);
-        let sst = SSTable::open(&path)?;
-        let mut entries = Vec::new();
-        let mut iter = sst.iter()?;
-        while iter.is_valid() {
-            entries.push((iter.key().to_vec(), iter.value().to_vec()));
-            iter.next()?;
-        }
-        iters.push(Box::new(VecIterator::new(entries)));
-    }
-
-    // 4. Merge all iterators
-    let mut merge = MergeIterator::new(iters)?;
-
-    // 5. Collect min/max keys from merged output to detect bottommost
-    let mut min_key: Option<Vec<u8>> = None;
-    let mut max_key: Option<Vec<u8>> = None;
-
-    // Scan through merge once to find key range (tombstones and non-tombstones)
-    let mut entries_to_write: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
-    while merge.is_valid() {
-        let key = merge.key().to_vec();
-        let value = merge.value().to_vec();
-
-        if min_key.is_none() {
-            min_key = Some(key.clone());
-        }
-        max_key = Some(key.clone());
-
-        entries_to_write.push((key, value));
-        merge.next()?;
-    }
+    // 3. The key range of the inputs, range tombstones included
+    let min_key = task.inputs.iter().map(|s| s.min_key.as_slice()).min();
+    let max_key = task.inputs.iter().map(|s| s.max_key.as_slice()).max();
 
-    // 6. Determine if this compaction is bottommost
+    // 4. Determine if this compaction is bottommost
     let is_bottommost = if task.output_level as usize >= levels.len() - 1 {
         // Already at last level
         true
-    } else if let (Some(min), Some(max)) = (&min_key, &max_key) {
+    } else if let (Some(min), Some(max)) = (min_key, max_key) {
         // Check all deeper levels for overlaps
         let mut has_deeper_overlap = false;
         for level in levels.iter().skip(task.output_level as usize + 1) {
@@ -140,46 +560,117 @@ pub fn run_compaction(
         }
         !has_deeper_overlap
     } else {
-        // No keys in merge (shouldn't happen, but safe)
+        // No inputs (shouldn't happen, but safe)
         true
     };
 
-    // 7. Write output SSTable, filtering tombstones if bottommost
-    let new_id = version_set.next_sst_id();
-    let output_path = sst_path(db_path, new_id);
-    let mut builder = SSTableBuilder::new(&output_path, new_id, block_size)?;
+    // 5. Merge the inputs and write the outputs, here or through the
+    // service
+    let job = MergeJob {
+        inputs: task
+            .inputs
+            .iter()
+            .map(|meta| {
+                let path = version_set.table_file(db_path, meta.id, meta.path_id);
+                (path, meta.clone())
+            })
+            .collect(),
+        blob_dir: db_path.to_path_buf(),
+        output_level: task.output_level,
+        is_bottommost,
+        deeper_levels: levels
+            .get(task.output_level as usize + 1..)
+            .unwrap_or_default()
+            .to_vec(),
+        max_subcompactions,
+    };
+    let remote = service.and_then(|service| {
+        compact_remotely(service, &job, db_path, version_set, table_options, cancel).ok()
+    });
+    let (new_metas, counts) = match remote {
+        Some(outputs) => outputs,
+        None => merge_inputs(
+            &job,
+            db_path,
+            version_set,
+            table_options,
+            expiry,
+            filter,
+            cancel,
+        )?,
+    };
+    let total_entries: u64 = task.inputs.iter().map(|s| s.entry_count).sum();
 
-    for (key, value) in entries_to_write {
-        // Skip tombstones only if bottommost compaction
-        if value.is_empty() && is_bottommost {
-            continue;
+    // 6. With paranoid checks, read the outputs back. If they don't hold
+    // what was written, drop them and leave the inputs in place.
+    if table_options.paranoid_file_checks
+        && let Err(e) = verify_outputs(
+            version_set,
+            db_path,
+            &new_metas,
+            counts.output,
+            total_entries,
+        )
+    {
+        remove_outputs(version_set, db_path, &new_metas);
+        return Err(e);
+    }
+    let input_ids: HashSet<u64> = task.inputs.iter().map(|s| s.id).collect();
+    let mut info = CompactionJobInfo {
+        output_level: task.output_level,
+        reason: task.reason,
+        input_files: task.inputs.clone(),
+        output_files: new_metas.clone(),
+        bytes_read_upper: 0,
+        bytes_read_output_level: 0,
+        bytes_written: new_metas.iter().map(|s| s.file_size).sum(),
+        input_records: counts.input,
+        output_records: counts.output,
+        keys_dropped: counts.dropped,
+        duration: Duration::ZERO,
+    };
+    for input in &task.inputs {
+        if input.level == task.output_level {
+            info.bytes_read_output_level += input.file_size;
+        } else {
+            info.bytes_read_upper += input.file_size;
         }
-        builder.add(&key, &value)?;
     }
 
-    let mut new_meta = builder.finish()?;
-    new_meta.level = task.output_level;
-
-    // 8. Install new version
+    // 7. Record the result in the manifest, every subcompaction's outputs
+    // in one edit, then install the new version. The manifest lock is
+    // held through the install, so a flush finishing meanwhile can't
+    // install a version this one would overwrite.
+    let manifest = match manifest {
+        Some(manifest) => {
+            let mut manifest = manifest.lock().unwrap();
+            manifest.record_compaction(
+                new_metas.clone(),
+                input_ids.iter().copied().collect(),
+                task.reason,
+            )?;
+            Some(manifest)
+        }
+        None => None,
+    };
     {
         let current = version_set.current();
         let old_v = current.read().unwrap();
         let mut new_levels = old_v.levels.clone();
         drop(old_v); // release read lock before write lock
 
-        let input_ids: HashSet<u64> = task.inputs.iter().map(|s| s.id).collect();
         for level in &mut new_levels {
             level.retain(|sst| !input_ids.contains(&sst.id));
         }
-        new_levels[task.output_level as usize].push(new_meta);
+        new_levels[task.output_level as usize].extend(new_metas);
 
         version_set.install(Version { levels: new_levels });
     }
+    drop(manifest);
 
-    // 9. Delete old SSTable files
-    for meta in &task.inputs {
-        let _ = std::fs::remove_file(sst_path(db_path, meta.id));
-    }
+    // 8. Delete old SSTable files no reader still holds a version of
+    version_set.delete_obsolete_files(db_path);
 
-    Ok(true)
+    info.duration = start.elapsed();
+    Ok(Some(info))
 }
@@ -1,4 +1,6 @@
-use crate::compaction::{CompactionStrategy, CompactionTask, find_overlapping_sstables};
+use crate::compaction::{
+    CompactionReason, CompactionStrategy, CompactionTask, find_overlapping_sstables,
+};
 use crate::sstable::footer::SSTableMeta;
 
 /// Size-tiered compaction strategy.
@@ -49,6 +51,16 @@ impl CompactionStrategy for SizeTieredStrategy {
         Some(CompactionTask {
             inputs,
             output_level: 1,
+            reason: CompactionReason::Level0FilesNum,
         })
     }
+
+    /// L0: SSTables over the threshold. Deeper levels never trigger.
+    fn level_scores(&self, levels: &[Vec<SSTableMeta>]) -> Vec<f64> {
+        let mut scores = vec![0.0; levels.len()];
+        if let Some(l0) = levels.first() {
+            scores[0] = l0.len() as f64 / self.level0_threshold.max(1) as f64;
+        }
+        scores
+    }
 }
@@ -0,0 +1,89 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Overrides for the compactions starting now. Fields left None keep the
+/// DB's own settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionLimits {
+    /// Replaces Options::max_subcompactions: threads one compaction may
+    /// use.
+    pub max_subcompactions: Option<usize>,
+    /// Paces compaction writes at this rate instead of through
+    /// Options::rate_limiter; 0 lifts the limit. Flushes keep using
+    /// Options::rate_limiter.
+    pub rate_bytes_per_sec: Option<u64>,
+}
+
+/// Hook the DB asks for limits before every compaction, so compaction can
+/// back off while the application is busy and catch up while it's quiet.
+///
+/// Implement it for limits computed on the fly, e.g. from load, or use
+/// TimeWindowThrottle for a fixed daily schedule. A compaction keeps the
+/// limits it started with; new ones apply from the next.
+pub trait CompactionThrottle: Send + Sync {
+    fn limits(&self, now: SystemTime) -> CompactionLimits;
+}
+
+/// Limits by time of day: each window applies its limits from `start` to
+/// `end`, both measured from midnight. The first window containing the
+/// time wins; outside every window the DB's own settings apply.
+#[derive(Debug, Clone, Default)]
+pub struct TimeWindowThrottle {
+    /// Seconds the schedule's clock is ahead of UTC.
+    utc_offset_secs: i64,
+    windows: Vec<TimeWindow>,
+}
+
+#[derive(Debug, Clone)]
+struct TimeWindow {
+    start: i64,
+    end: i64,
+    limits: CompactionLimits,
+}
+
+impl TimeWindowThrottle {
+    /// A schedule on UTC time, without windows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read times of day on a clock `secs` ahead of UTC (negative for
+    /// behind), e.g. 3600 for UTC+1.
+    pub fn with_utc_offset(mut self, secs: i64) -> Self {
+        self.utc_offset_secs = secs;
+        self
+    }
+
+    /// Apply `limits` from `start` until `end` each day. A window whose
+    /// end is before its start runs over midnight.
+    pub fn with_window(mut self, start: Duration, end: Duration, limits: CompactionLimits) -> Self {
+        self.windows.push(TimeWindow {
+            start: start.as_secs() as i64 % SECONDS_PER_DAY,
+            end: end.as_secs() as i64 % SECONDS_PER_DAY,
+            limits,
+        });
+        self
+    }
+}
+
+impl CompactionThrottle for TimeWindowThrottle {
+    fn limits(&self, now: SystemTime) -> CompactionLimits {
+        let secs = match now.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        let time_of_day = (secs + self.utc_offset_secs).rem_euclid(SECONDS_PER_DAY);
+        self.windows
+            .iter()
+            .find(|window| {
+                if window.start <= window.end {
+                    (window.start..window.end).contains(&time_of_day)
+                } else {
+                    time_of_day >= window.start || time_of_day < window.end
+                }
+            })
+            .map(|window| window.limits)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,210 @@
+use crate::compaction::{CompactionReason, CompactionStrategy, CompactionTask};
+use crate::sstable::footer::SSTableMeta;
+
+/// Universal (size-tiered) compaction strategy, after RocksDB's.
+///
+/// The data is a list of sorted runs, newest first: each L0 SSTable is a
+/// run, then each non-empty level below is one. Once there are
+/// `run_trigger` runs, consecutive runs of similar size are merged into
+/// one, so every byte is rewritten about once per size tier instead of
+/// once per level as in leveled compaction:
+///   1. If the runs newer than the oldest add up to more than
+///      `max_size_amplification_percent` of it, merge everything
+///   2. Otherwise merge the first window of runs (newest first) where
+///      each next run is at most `size_ratio` percent larger than the
+///      runs before it combined, if at least `min_merge_width` long
+///   3. Otherwise merge the newest runs, enough to get back under the
+///      trigger
+///
+/// Merged runs are written to the level of the oldest run merged, or,
+/// when only L0 files are merged, to the empty level just above the next
+/// run, so levels fill from the bottom and runs stay ordered by age.
+pub struct UniversalStrategy {
+    run_trigger: usize,
+    size_ratio: u64,
+    min_merge_width: usize,
+    max_size_amplification_percent: u64,
+    max_levels: usize,
+}
+
+/// A sorted run: one L0 SSTable, or a whole level below.
+struct SortedRun<'a> {
+    level: usize,
+    files: Vec<&'a SSTableMeta>,
+    size: u64,
+}
+
+impl UniversalStrategy {
+    /// Compact once there are `run_trigger` sorted runs, across at most
+    /// `max_levels` levels.
+    pub fn new(run_trigger: usize, max_levels: usize) -> Self {
+        Self {
+            run_trigger: run_trigger.max(2),
+            size_ratio: 1,
+            min_merge_width: 2,
+            max_size_amplification_percent: 200,
+            max_levels,
+        }
+    }
+
+    /// Percent by which a run may be larger than the runs picked before it
+    /// and still count as similarly sized. Default: 1.
+    pub fn with_size_ratio(mut self, percent: u64) -> Self {
+        self.size_ratio = percent;
+        self
+    }
+
+    /// Fewest runs a size-ratio merge may pick. Default: 2.
+    pub fn with_min_merge_width(mut self, width: usize) -> Self {
+        self.min_merge_width = width.max(2);
+        self
+    }
+
+    /// Space overhead, as a percent of the oldest run, at which all runs
+    /// are merged into one. Default: 200.
+    pub fn with_max_size_amplification_percent(mut self, percent: u64) -> Self {
+        self.max_size_amplification_percent = percent;
+        self
+    }
+
+    /// The sorted runs of `levels`, newest first.
+    fn sorted_runs<'a>(&self, levels: &'a [Vec<SSTableMeta>]) -> Vec<SortedRun<'a>> {
+        let mut runs: Vec<SortedRun> = levels
+            .first()
+            .into_iter()
+            .flat_map(|l0| l0.iter().rev())
+            .map(|meta| SortedRun {
+                level: 0,
+                files: vec![meta],
+                size: meta.file_size,
+            })
+            .collect();
+        for (level, files) in levels.iter().enumerate().take(self.max_levels).skip(1) {
+            if !files.is_empty() {
+                runs.push(SortedRun {
+                    level,
+                    files: files.iter().collect(),
+                    size: files.iter().map(|f| f.file_size).sum(),
+                });
+            }
+        }
+        runs
+    }
+
+    /// Where merging `runs[start..end]` writes its output, if anywhere:
+    /// the oldest run's level, or for L0 files only (which must include
+    /// the oldest L0 file), the level above the next run.
+    fn output_level(
+        &self,
+        levels: &[Vec<SSTableMeta>],
+        runs: &[SortedRun],
+        end: usize,
+    ) -> Option<usize> {
+        let last = &runs[end - 1];
+        if last.level > 0 {
+            return Some(last.level);
+        }
+        if runs.get(end).is_some_and(|next| next.level == 0) {
+            return None;
+        }
+        let below = runs
+            .get(end)
+            .map_or(self.max_levels.min(levels.len()), |next| next.level);
+        (below > 1).then_some(below - 1)
+    }
+
+    fn task(
+        runs: &[SortedRun],
+        start: usize,
+        end: usize,
+        output_level: usize,
+        reason: CompactionReason,
+    ) -> CompactionTask {
+        CompactionTask {
+            reason,
+            inputs: runs[start..end]
+                .iter()
+                .flat_map(|run| run.files.iter().map(|&f| f.clone()))
+                .collect(),
+            output_level: output_level as u32,
+        }
+    }
+}
+
+impl CompactionStrategy for UniversalStrategy {
+    fn pick_compaction(&self, levels: &[Vec<SSTableMeta>]) -> Option<CompactionTask> {
+        let runs = self.sorted_runs(levels);
+        if runs.len() < self.run_trigger {
+            return None;
+        }
+
+        // 1. Space amplification: everything newer vs. the oldest run
+        let (oldest, newer) = runs.split_last()?;
+        let newer_size: u64 = newer.iter().map(|run| run.size).sum();
+        if newer_size.saturating_mul(100)
+            > oldest
+                .size
+                .saturating_mul(self.max_size_amplification_percent)
+            && let Some(level) = self.output_level(levels, &runs, runs.len())
+        {
+            return Some(Self::task(
+                &runs,
+                0,
+                runs.len(),
+                level,
+                CompactionReason::UniversalSizeAmplification,
+            ));
+        }
+
+        // 2. Size ratio: the first window of similarly sized runs
+        for start in 0..runs.len() {
+            let mut candidate_size = runs[start].size;
+            let mut end = start + 1;
+            while end < runs.len()
+                && runs[end].size.saturating_mul(100)
+                    <= candidate_size.saturating_mul(100 + self.size_ratio)
+            {
+                candidate_size += runs[end].size;
+                end += 1;
+            }
+            if end - start >= self.min_merge_width
+                && let Some(level) = self.output_level(levels, &runs, end)
+            {
+                return Some(Self::task(
+                    &runs,
+                    start,
+                    end,
+                    level,
+                    CompactionReason::UniversalSizeRatio,
+                ));
+            }
+        }
+
+        // 3. Too many runs: merge the newest ones, widening the window
+        // until its output has somewhere to go
+        let mut end = (runs.len() + 1 - self.run_trigger).max(self.min_merge_width);
+        while end <= runs.len() {
+            if let Some(level) = self.output_level(levels, &runs, end) {
+                return Some(Self::task(
+                    &runs,
+                    0,
+                    end,
+                    level,
+                    CompactionReason::UniversalSortedRunNum,
+                ));
+            }
+            end += 1;
+        }
+        None
+    }
+
+    /// Sorted runs over the trigger, reported on L0: runs span levels,
+    /// so deeper levels score 0.
+    fn level_scores(&self, levels: &[Vec<SSTableMeta>]) -> Vec<f64> {
+        let mut scores = vec![0.0; levels.len()];
+        if let Some(score) = scores.first_mut() {
+            *score = self.sorted_runs(levels).len() as f64 / self.run_trigger as f64;
+        }
+        scores
+    }
+}
@@ -0,0 +1,208 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use crate::error::{Error, Result};
+
+/// Background work a DB schedules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Job {
+    /// Write the immutable memtable to L0. Runs before any compaction:
+    /// writers stall until it's done.
+    Flush,
+    /// Run one compaction round, if the strategy picks one.
+    Compaction,
+}
+
+/// Which jobs a worker takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    /// Flushes only, so one is free whenever a memtable fills up.
+    Flush,
+    /// Flushes first, then compactions.
+    Any,
+}
+
+/// Jobs waiting for a worker, in priority order.
+///
+/// Each kind is queued at most once: a flush flushes whatever memtable is
+/// immutable when it runs, and a compaction round re-queues itself while
+/// there's more to do.
+pub(crate) struct JobQueue {
+    state: Mutex<QueueState>,
+    /// Signalled when a job is queued or shutdown starts.
+    work: Condvar,
+    /// Signalled when a job finishes.
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    flush: bool,
+    compaction: bool,
+    /// Jobs being run by workers.
+    running: usize,
+    shutting_down: bool,
+    /// First error a background job hit.
+    error: Option<Error>,
+}
+
+impl JobQueue {
+    pub(crate) fn new() -> Self {
+        JobQueue {
+            state: Mutex::new(QueueState::default()),
+            work: Condvar::new(),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Queue `job` unless it's already queued. Ignored once shutting down.
+    pub(crate) fn schedule(&self, job: Job) {
+        let mut state = self.state.lock().unwrap();
+        if state.shutting_down {
+            return;
+        }
+        match job {
+            Job::Flush => state.flush = true,
+            Job::Compaction => state.compaction = true,
+        }
+        // Not just one: it may be a flush worker that can't take the job
+        self.work.notify_all();
+    }
+
+    /// The first error a background job hit, if any.
+    pub(crate) fn error(&self) -> Result<()> {
+        match &self.state.lock().unwrap().error {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Block until `ready` holds, re-checking whenever a job finishes.
+    /// Fails with the background error if a job has failed.
+    pub(crate) fn wait_until(&self, mut ready: impl FnMut() -> bool) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(e) = &state.error {
+                return Err(e.clone());
+            }
+            if ready() {
+                return Ok(());
+            }
+            state = self.done.wait(state).unwrap();
+        }
+    }
+
+    /// Wake the threads blocked in wait_until() to re-check, after a
+    /// change made outside a background job.
+    pub(crate) fn notify(&self) {
+        let _state = self.state.lock().unwrap();
+        self.done.notify_all();
+    }
+
+    /// Block until no job is queued or running.
+    pub(crate) fn wait_idle(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        while (state.flush || state.compaction || state.running > 0) && !state.shutting_down {
+            state = self.done.wait(state).unwrap();
+        }
+        match &state.error {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for the next job `role` takes, flushes first. None once
+    /// shutting down.
+    fn next(&self, role: Role) -> Option<Job> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.shutting_down {
+                return None;
+            }
+            let job = if state.flush {
+                state.flush = false;
+                Job::Flush
+            } else if state.compaction && role == Role::Any {
+                state.compaction = false;
+                Job::Compaction
+            } else {
+                state = self.work.wait(state).unwrap();
+                continue;
+            };
+            state.running += 1;
+            return Some(job);
+        }
+    }
+
+    fn finish(&self, result: Result<()>) {
+        let mut state = self.state.lock().unwrap();
+        state.running -= 1;
+        if let Err(e) = result {
+            state.error.get_or_insert(e);
+        }
+        self.done.notify_all();
+    }
+}
+
+/// Worker threads running a DB's background jobs: some that only flush,
+/// so a long compaction never delays freeing the immutable memtable, and
+/// the rest taking any job.
+///
+/// Jobs queued when shutdown starts are dropped, which is safe: an
+/// unflushed memtable's writes are still in its WAL, and compaction only
+/// ever tidies up. Running jobs finish first.
+pub(crate) struct BackgroundPool {
+    queue: Arc<JobQueue>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl BackgroundPool {
+    /// Start `flush_threads` workers taking flushes and `threads` taking
+    /// any job from `queue`, handing them to `run`.
+    pub(crate) fn start(
+        flush_threads: usize,
+        threads: usize,
+        queue: Arc<JobQueue>,
+        run: Arc<dyn Fn(Job) -> Result<()> + Send + Sync>,
+    ) -> Self {
+        let flushers = (0..flush_threads).map(|i| (format!("lsm-flush-{i}"), Role::Flush));
+        let others = (0..threads.max(1)).map(|i| (format!("lsm-bg-{i}"), Role::Any));
+        let workers = flushers
+            .chain(others)
+            .map(|(name, role)| {
+                let queue = Arc::clone(&queue);
+                let run = Arc::clone(&run);
+                std::thread::Builder::new()
+                    .name(name)
+                    .spawn(move || {
+                        while let Some(job) = queue.next(role) {
+                            queue.finish(run(job));
+                        }
+                    })
+                    .expect("failed to spawn background thread")
+            })
+            .collect();
+        BackgroundPool { queue, workers }
+    }
+
+    /// Stop taking jobs and wait for the running ones. Idempotent.
+    pub(crate) fn shutdown(&mut self) {
+        {
+            let mut state = self.queue.state.lock().unwrap();
+            state.shutting_down = true;
+            state.flush = false;
+            state.compaction = false;
+            self.queue.work.notify_all();
+            self.queue.done.notify_all();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for BackgroundPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
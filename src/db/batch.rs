@@ -0,0 +1,40 @@
+use crate::wal::record::WALRecord;
+
+/// Puts and deletes applied together, in order, by DB::write() or
+/// DB::write_prepared().
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    writes: Vec<WALRecord>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a put of `key` = `value`.
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> &mut Self {
+        self.writes
+            .push(WALRecord::put(key.to_vec(), value.to_vec()));
+        self
+    }
+
+    /// Add a delete of `key`.
+    pub fn delete(&mut self, key: &[u8]) -> &mut Self {
+        self.writes.push(WALRecord::delete(key.to_vec()));
+        self
+    }
+
+    /// Number of writes in the batch.
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    pub(crate) fn into_writes(self) -> Vec<WALRecord> {
+        self.writes
+    }
+}
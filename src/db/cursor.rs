@@ -0,0 +1,50 @@
+use crate::error::{Error, Result};
+
+/// Encoding version byte, bumped if the cursor layout ever changes.
+const CURSOR_VERSION: u8 = 1;
+
+/// Opaque scan position, returned by DBIterator::cursor().
+///
+/// Records the key the iterator is positioned on — the first key not yet
+/// consumed. Passing it to DB::iter_from_cursor() resumes at that key:
+/// only SSTables at or past it are opened, so later pages never rescan
+/// earlier ones.
+///
+/// Cursors are plain bytes when encoded, so they can be handed to clients
+/// (e.g. as a pagination token) and decoded on a later request:
+///
+///   [version: u8][key bytes]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    key: Vec<u8>,
+}
+
+impl Cursor {
+    pub(crate) fn at(key: &[u8]) -> Self {
+        Cursor { key: key.to_vec() }
+    }
+
+    /// The key the cursor resumes at.
+    pub(crate) fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Serialize the cursor into an opaque token.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1 + self.key.len());
+        buf.push(CURSOR_VERSION);
+        buf.extend_from_slice(&self.key);
+        buf
+    }
+
+    /// Parse a token produced by encode().
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        match data.split_first() {
+            Some((&CURSOR_VERSION, key)) => Ok(Cursor::at(key)),
+            Some((version, _)) => Err(Error::Corruption(format!(
+                "unknown cursor version {version}"
+            ))),
+            None => Err(Error::Corruption("empty cursor".into())),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::blob::writer::BlobFileWriter;
+use crate::blob::{BlobFileMeta, should_separate};
+use crate::compaction::output::TableOutput;
+use crate::error::Result;
+use crate::iterator::StorageIterator;
+use crate::iterator::vec_iter::VecIterator;
+use crate::manifest::version::VersionSet;
+use crate::memtable::MemTable;
+use crate::sstable::builder::TableOptions;
+use crate::sstable::footer::SSTableMeta;
+
+/// Writes immutable memtables to L0 SSTables: the flush half of getting
+/// a memtable to disk, without the manifest edit and WAL cleanup that
+/// follow it. DB::flush() and the background flush both run one.
+///
+/// Each key's newest version is written, tombstones included, so they
+/// keep hiding older values in lower levels. Tables are cut at the L0
+/// target file size, and their bloom filters are sized for the exact
+/// number of entries. Table ids come from the VersionSet.
+pub struct FlushJob<'a> {
+    db_path: &'a Path,
+    version_set: &'a VersionSet,
+    table_options: &'a TableOptions,
+    /// Oldest first: where several hold a key, the last one's entry wins.
+    memtables: &'a [Arc<MemTable>],
+    /// Values at least this large go to a blob file; None keeps them all
+    /// inline.
+    min_blob_size: Option<usize>,
+    /// Source of the blob file's number, taken only if one is created.
+    next_blob_file_number: Option<&'a AtomicU64>,
+}
+
+/// What a FlushJob wrote, for the manifest.
+#[derive(Debug)]
+pub struct FlushOutput {
+    /// New L0 tables, in key order; empty if the memtables were.
+    pub tables: Vec<SSTableMeta>,
+    /// Blob file the tables point into, if any value was separated.
+    pub blob_file: Option<BlobFileMeta>,
+}
+
+impl<'a> FlushJob<'a> {
+    /// A job flushing `memtables`, oldest first, to tables in `db_path`
+    /// (or the VersionSet's table paths) built with `table_options`.
+    pub fn new(
+        db_path: &'a Path,
+        version_set: &'a VersionSet,
+        table_options: &'a TableOptions,
+        memtables: &'a [Arc<MemTable>],
+    ) -> Self {
+        FlushJob {
+            db_path,
+            version_set,
+            table_options,
+            memtables,
+            min_blob_size: None,
+            next_blob_file_number: None,
+        }
+    }
+
+    /// Write values of at least `min_blob_size` bytes to a blob file, as
+    /// Options::min_blob_size, numbered from `next_blob_file_number`.
+    pub fn with_blob_files(
+        mut self,
+        min_blob_size: Option<usize>,
+        next_blob_file_number: &'a AtomicU64,
+    ) -> Self {
+        self.min_blob_size = min_blob_size;
+        self.next_blob_file_number = Some(next_blob_file_number);
+        self
+    }
+
+    /// Write the tables and blob file, synced, and return their metadata.
+    /// Nothing is recorded anywhere: installing them is up to the caller.
+    pub fn run(self) -> Result<FlushOutput> {
+        // Frozen without it, so as not to wait for readers under the WAL
+        // lock; sealed here, with exact stats, before anything is read
+        for memtable in self.memtables {
+            memtable.mark_read_only();
+        }
+        // Sized up before a single memtable's read lock is taken below
+        let expected_bytes = self.memtables.iter().map(|mt| mt.size() as u64).sum();
+        let seqno_range = self
+            .memtables
+            .iter()
+            .filter_map(|mt| mt.seqno_range())
+            .reduce(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)));
+
+        // A single memtable is read in place; several are merged first,
+        // the newest one's entry winning. Either way the number of
+        // entries is known exactly, to size the bloom filter.
+        let single = match self.memtables {
+            [memtable] => Some(memtable.read()),
+            _ => None,
+        };
+        let (mut iter, num_entries): (Box<dyn StorageIterator + '_>, usize) = match &single {
+            Some(memtable) => (Box::new(memtable.iter()), memtable.stats().num_entries),
+            None => {
+                let mut merged = BTreeMap::new();
+                for memtable in self.memtables {
+                    let memtable = memtable.read();
+                    let mut iter = memtable.iter();
+                    while iter.is_valid() {
+                        merged.insert(
+                            iter.key().to_vec(),
+                            (iter.value().to_vec(), iter.sequence()),
+                        );
+                        iter.advance();
+                    }
+                }
+                let num_entries = merged.len();
+                let (entries, seqnos) = merged
+                    .into_iter()
+                    .map(|(key, (value, seqno))| ((key, value), seqno))
+                    .unzip();
+                (
+                    Box::new(VecIterator::with_seqnos(entries, seqnos)),
+                    num_entries,
+                )
+            }
+        };
+
+        // Build SSTables from the memtables, cut at the target size
+        let mut output = TableOutput::new(
+            self.db_path,
+            self.version_set,
+            self.table_options,
+            0,
+            num_entries,
+        );
+        output.set_expected_bytes(expected_bytes);
+        if let Some((smallest, largest)) = seqno_range {
+            output.set_seqno_range(smallest, largest);
+        }
+
+        // Large values go to a blob file, their SSTable entries point there
+        let mut blob_writer: Option<BlobFileWriter> = None;
+        while iter.is_valid() {
+            if let Some(next_number) = self.next_blob_file_number
+                && should_separate(iter.value(), self.min_blob_size)
+            {
+                let writer = match blob_writer {
+                    Some(ref mut writer) => writer,
+                    None => blob_writer.insert(BlobFileWriter::create(
+                        self.db_path,
+                        next_number.fetch_add(1, Ordering::SeqCst),
+                    )?),
+                };
+                let index = writer.add(iter.key(), iter.value())?;
+                output.add(iter.key(), &index.encode(), iter.seqno())?;
+            } else {
+                output.add(iter.key(), iter.value(), iter.seqno())?;
+            }
+            iter.next()?;
+        }
+        drop(iter);
+        // The blob file is synced before the SSTables pointing into it
+        let blob_file = blob_writer.map(BlobFileWriter::finish).transpose()?;
+        let tables = output.finish()?;
+        Ok(FlushOutput { tables, blob_file })
+    }
+}
@@ -0,0 +1,726 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, RwLock};
+
+use crate::blob::reader::read_blob_value;
+use crate::blob::{BlobIndex, is_blob_index};
+use crate::cache::table_cache::{TableCache, open_table};
+use crate::db::cursor::Cursor;
+use crate::db::prefix::SliceTransform;
+use crate::db::{ReadOptions, active_at_sequence, memtable_source};
+use crate::error::{Error, Result};
+use crate::iterator::StorageIterator;
+use crate::iterator::level::LevelIterator;
+use crate::iterator::merge::MergeIterator;
+use crate::manifest::version::{PinnedVersion, Version, VersionSet};
+use crate::memtable::MemTable;
+use crate::memtable::iterator::SharedVersionIterator;
+use crate::sstable::paths::TablePaths;
+use crate::sstable::range_del::RangeTombstones;
+use crate::sstable::reader::BlockReadOptions;
+use crate::types::{InternalKey, Value, ValueType};
+
+/// Iterator over the whole database, returned by DB::iter().
+///
+/// Merges every data source, ordered newest to oldest:
+///   active memtable → immutable memtables → L0 (newest-first) → L1+
+///
+/// MergeIterator already hides older versions of a key (the newest source
+/// wins). On top of that, DBIterator hides tombstones in both directions,
+/// so callers only ever see live key-value pairs.
+///
+/// Optional bounds from ReadOptions restrict the view to [lower, upper):
+/// seeks are clamped into the range and iteration stops at the first key
+/// outside it instead of walking the rest of the merged stream.
+///
+/// Iterators returned by DB pin the Version and sequence number they were
+/// built from: SSTables in that version aren't deleted by compaction until
+/// the iterator is dropped (or refreshed), so long scans never lose files.
+///
+/// They also keep handles to the DB's data sources, so refresh() can swap
+/// in the latest memtable and SSTable set without losing the current
+/// position.
+///
+/// A read error from any source (an SSTable that can't be opened, a
+/// corrupted block, ...) is returned from the call that hit it and also
+/// kept as the iterator's status(): the iterator turns invalid, next() and
+/// prev() keep returning the error, and only a seek tries again.
+///
+/// With a prefix extractor set (ReadOptions::prefix_same_as_start), each
+/// seek also pins the prefix of its target, and the iterator becomes
+/// invalid at the first key with a different prefix.
+///
+/// SSTable values that are blob pointers are resolved as the iterator
+/// lands on them, so value() always returns the stored value; a blob read
+/// error fails the positioning call like any other read error.
+pub struct DBIterator {
+    merge: MergeIterator,
+    /// Inclusive lower bound, if any.
+    lower: Option<Vec<u8>>,
+    /// Exclusive upper bound, if any.
+    upper: Option<Vec<u8>>,
+    /// Prefix extractor for prefix_same_as_start mode.
+    prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    /// Prefix of the last seek target that every yielded key must share.
+    prefix: Option<Vec<u8>>,
+    /// Where refresh() rebuilds from. None for frozen views (snapshots).
+    read_sources: Option<ReadSources>,
+    /// Version whose SSTables the sources read from, kept alive until drop.
+    version: Option<PinnedVersion>,
+    /// Sequence number the iterator reads at.
+    sequence: u64,
+    /// ReadOptions::keys_only: value() is always empty.
+    keys_only: bool,
+    /// First read error hit since the last seek, if any.
+    status: Option<Error>,
+    /// Directory of the blob files SSTable values may point into, and the
+    /// index of the first SSTable source (memtable values are never
+    /// pointers). None until resolve_blobs().
+    blobs: Option<(PathBuf, usize)>,
+    /// The current entry's value, read from its blob file.
+    blob_value: Option<Vec<u8>>,
+}
+
+impl DBIterator {
+    /// Build a DBIterator over sources ordered newest-first.
+    /// Positions at the first live entry inside the bounds.
+    pub(crate) fn new(
+        sources: Vec<Box<dyn StorageIterator>>,
+        read_options: &ReadOptions,
+    ) -> Result<Self> {
+        let merge = MergeIterator::new(sources)?;
+        let mut iter = DBIterator {
+            merge,
+            lower: read_options.iterate_lower_bound.clone(),
+            upper: read_options.iterate_upper_bound.clone(),
+            prefix_extractor: None,
+            prefix: None,
+            read_sources: None,
+            version: None,
+            sequence: 0,
+            keys_only: read_options.keys_only,
+            status: None,
+            blobs: None,
+            blob_value: None,
+        };
+        iter.seek_to_first()?;
+        Ok(iter)
+    }
+
+    /// Save the current position so a later DB::iter_from_cursor() can
+    /// resume from it. Call after the last next() of a page: the current
+    /// key is the first one the next page returns. None once the iterator
+    /// is exhausted — there is nothing left to resume.
+    pub fn cursor(&self) -> Option<Cursor> {
+        self.is_valid().then(|| Cursor::at(self.key()))
+    }
+
+    /// Pick up the latest LSM state — new memtable contents and the
+    /// current SSTable set — while keeping the current position.
+    ///
+    /// A valid iterator stays on the same key, or moves to the next live
+    /// key if that one was deleted in the meantime. An exhausted iterator
+    /// stays exhausted until the next seek. No-op for iterators without
+    /// DB sources (e.g. snapshot scans, which are frozen by design).
+    ///
+    /// The previously pinned version is released, so SSTables compacted
+    /// away since creation can be deleted.
+    pub fn refresh(&mut self) -> Result<()> {
+        let Some(ref read_sources) = self.read_sources else {
+            return Ok(());
+        };
+        let (sources, version, sequence) =
+            read_sources.collect(self.lower.as_deref(), self.upper.as_deref())?;
+        let position = self.is_valid().then(|| self.key().to_vec());
+        let num_memtable_sources = read_sources.num_memtable_sources();
+
+        self.merge = MergeIterator::new(sources)?;
+        self.pin(version, sequence);
+        if let Some((_, ref mut first_sstable_source)) = self.blobs {
+            *first_sstable_source = num_memtable_sources;
+        }
+        match position {
+            // seek_clamped keeps the pinned prefix as it is
+            Some(key) => self.seek_clamped(&key)?,
+            None => {
+                self.merge.seek_to_last()?;
+                if self.merge.is_valid() {
+                    self.merge.next()?;
+                }
+            }
+        }
+        self.load_blob_value()
+    }
+
+    /// Ok unless a read error has invalidated the iterator since the last
+    /// seek. Check after a scan ends to tell "no more keys" from "failed".
+    pub fn status(&self) -> Result<()> {
+        match &self.status {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Run a positioning step and resolve the value it lands on,
+    /// recording any error as the status.
+    fn track(&mut self, step: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let result = step(self).and_then(|()| self.load_blob_value());
+        if let Err(ref e) = result {
+            self.status = Some(e.clone());
+        }
+        result
+    }
+
+    /// Resolve blob pointers in SSTable values from the blob files in
+    /// `dir`. Sources from index `first_sstable_source` on are SSTables.
+    pub(crate) fn resolve_blobs(
+        &mut self,
+        dir: PathBuf,
+        first_sstable_source: usize,
+    ) -> Result<()> {
+        self.blobs = Some((dir, first_sstable_source));
+        let result = self.load_blob_value();
+        if let Err(ref e) = result {
+            self.status = Some(e.clone());
+        }
+        result
+    }
+
+    /// Read the current entry's value from its blob file if it's a blob
+    /// pointer from an SSTable.
+    fn load_blob_value(&mut self) -> Result<()> {
+        self.blob_value = None;
+        let Some((ref dir, first_sstable_source)) = self.blobs else {
+            return Ok(());
+        };
+        if self.keys_only
+            || !self.is_valid()
+            || self.merge.current_source() < Some(first_sstable_source)
+            || !is_blob_index(self.merge.value())
+        {
+            return Ok(());
+        }
+        let index = BlobIndex::decode(self.merge.value())?;
+        self.blob_value = Some(read_blob_value(dir, &index)?);
+        Ok(())
+    }
+
+    /// Sequence number the iterator reads at: the DB's sequence when the
+    /// iterator was created (or last refreshed), or the snapshot's.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Hold `version` until drop (or the next refresh) and record the
+    /// sequence number it was captured at.
+    pub(crate) fn pin(&mut self, version: PinnedVersion, sequence: u64) {
+        self.version = Some(version);
+        self.sequence = sequence;
+    }
+
+    /// Attach the DB sources refresh() rebuilds from.
+    pub(crate) fn set_read_sources(&mut self, read_sources: ReadSources) {
+        self.read_sources = Some(read_sources);
+    }
+
+    /// Enable prefix_same_as_start mode. Takes effect from the next seek.
+    pub(crate) fn set_prefix_extractor(&mut self, extractor: Option<Arc<dyn SliceTransform>>) {
+        self.prefix_extractor = extractor;
+    }
+
+    /// Pin the prefix of a seek target, if prefix mode is on and the
+    /// target has a prefix. Otherwise iteration is unrestricted.
+    fn pin_prefix(&mut self, target: &[u8]) {
+        self.prefix = match self.prefix_extractor {
+            Some(ref extractor) if extractor.in_domain(target) => {
+                Some(extractor.transform(target).to_vec())
+            }
+            _ => None,
+        };
+    }
+
+    /// True if `key` lies outside the pinned prefix.
+    fn outside_prefix(&self, key: &[u8]) -> bool {
+        matches!(self.prefix, Some(ref prefix) if !key.starts_with(prefix))
+    }
+
+    /// Position at the first live entry >= `key`, clamped to the lower bound.
+    fn seek_clamped(&mut self, key: &[u8]) -> Result<()> {
+        match self.lower {
+            Some(ref lower) if key < lower.as_slice() => {
+                let lower = lower.clone();
+                self.merge.seek(&lower)?;
+            }
+            _ => self.merge.seek(key)?,
+        }
+        self.skip_tombstones()
+    }
+
+    /// Position at the last live entry <= `key`, kept strictly below the
+    /// upper bound.
+    fn seek_for_prev_clamped(&mut self, key: &[u8]) -> Result<()> {
+        match self.upper {
+            Some(ref upper) if key >= upper.as_slice() => {
+                let upper = upper.clone();
+                self.merge.seek_for_prev(&upper)?;
+                if self.merge.is_valid() && self.merge.key() == upper.as_slice() {
+                    self.merge.prev()?;
+                }
+            }
+            _ => self.merge.seek_for_prev(key)?,
+        }
+        self.skip_tombstones_backward()
+    }
+
+    /// True if `key` is at or past the exclusive upper bound.
+    fn past_upper(&self, key: &[u8]) -> bool {
+        matches!(self.upper, Some(ref upper) if key >= upper.as_slice())
+    }
+
+    /// True if `key` is below the inclusive lower bound.
+    fn before_lower(&self, key: &[u8]) -> bool {
+        matches!(self.lower, Some(ref lower) if key < lower.as_slice())
+    }
+
+    /// Skip forward past any tombstone entries, stopping at the upper bound.
+    fn skip_tombstones(&mut self) -> Result<()> {
+        while self.merge.is_valid()
+            && !self.past_upper(self.merge.key())
+            && !self.outside_prefix(self.merge.key())
+            && self.merge.value().is_empty()
+        {
+            self.merge.next()?;
+        }
+        Ok(())
+    }
+
+    /// Skip backward past any tombstone entries, stopping at the lower bound.
+    fn skip_tombstones_backward(&mut self) -> Result<()> {
+        while self.merge.is_valid()
+            && !self.before_lower(self.merge.key())
+            && !self.outside_prefix(self.merge.key())
+            && self.merge.value().is_empty()
+        {
+            self.merge.prev()?;
+        }
+        Ok(())
+    }
+}
+
+impl StorageIterator for DBIterator {
+    fn key(&self) -> &[u8] {
+        self.merge.key()
+    }
+
+    fn value(&self) -> &[u8] {
+        if self.keys_only {
+            // Sources hold KEYS_ONLY_VALUE placeholders, not real values
+            return &[];
+        }
+        match self.blob_value {
+            Some(ref value) => value,
+            None => self.merge.value(),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.status.is_none()
+            && self.merge.is_valid()
+            && !self.past_upper(self.merge.key())
+            && !self.before_lower(self.merge.key())
+            && !self.outside_prefix(self.merge.key())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.status()?;
+        self.track(|it| {
+            it.merge.next()?;
+            it.skip_tombstones()
+        })
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.status = None;
+        self.pin_prefix(key);
+        self.track(|it| it.seek_clamped(key))
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        self.status()?;
+        self.track(|it| {
+            it.merge.prev()?;
+            it.skip_tombstones_backward()
+        })
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.status = None;
+        self.pin_prefix(key);
+        self.track(|it| it.seek_for_prev_clamped(key))
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.status = None;
+        // A full scan isn't confined to any prefix
+        self.prefix = None;
+        self.track(|it| match it.lower.clone() {
+            Some(lower) => it.seek_clamped(&lower),
+            None => {
+                it.merge.seek_to_first()?;
+                it.skip_tombstones()
+            }
+        })
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.status = None;
+        self.prefix = None;
+        self.track(|it| match it.upper.clone() {
+            Some(upper) => it.seek_for_prev_clamped(&upper),
+            None => {
+                it.merge.seek_to_last()?;
+                it.skip_tombstones_backward()
+            }
+        })
+    }
+}
+
+/// Iterator over every stored version of every key, returned by
+/// DB::raw_iter().
+///
+/// Unlike DBIterator nothing is hidden: tombstones come out as
+/// ValueType::Delete and shadowed older versions follow the newer ones.
+/// Entries are ordered by InternalKey — user_key ascending, then sequence
+/// descending — so the live version of a key is always first.
+///
+/// Every version carries the sequence number it was written with. A
+/// flush keeps only the newest version of each key in its memtables, so
+/// the versions it shadows are gone from then on. Tables without their
+/// entries' sequence numbers (written before format version 10, or
+/// imported) carry the table's largest_seqno instead, never below the
+/// real one.
+///
+/// Versions are streamed, not collected up front: like DBIterator it
+/// merges one iterator per source — each memtable's versions, newest
+/// memtable first, then one per L0 table, newest first, then one per L1+
+/// level — through a heap, versions tied on InternalKey coming out in
+/// that source order. It pins the Version it reads, so its SSTables stay
+/// readable until it's dropped. A read error ends the iteration and is
+/// kept as status().
+pub struct RawIterator {
+    /// Versions of each memtable, newest first.
+    memtables: Vec<SharedVersionIterator>,
+    /// One per L0 table, newest first, then one per non-empty L1+ level.
+    tables: Vec<LevelIterator>,
+    /// Next version of every source not yet exhausted, smallest first,
+    /// by InternalKey and then source index (memtables before tables).
+    heap: BinaryHeap<Reverse<(InternalKey, usize)>>,
+    /// Keeps the SSTables being read from being deleted.
+    _version: PinnedVersion,
+    /// Directory holding the blob files.
+    path: PathBuf,
+    /// First read error, after which the iteration ends.
+    status: Option<Error>,
+}
+
+impl RawIterator {
+    /// Iterate over every version in `sources`: the active memtable, then
+    /// the immutable ones, then each SSTable of the current version. Blob
+    /// pointers are resolved from the blob files as versions are
+    /// returned, except those into blob files already garbage collected.
+    ///
+    /// The memtables are read as of the current sequence number, taken
+    /// before the version is pinned, as in ReadSources::collect().
+    pub(crate) fn new(sources: &ReadSources) -> Result<Self> {
+        let (active, sequence) =
+            active_at_sequence(&sources.active_memtable, &sources.next_sequence);
+        let mut memtables = vec![active.shared_versions_at(sequence)];
+        let immutables = sources.immutable_memtables.read().unwrap().clone();
+        for memtable in immutables.iter().rev() {
+            memtables.push(memtable.shared_versions_at(sequence));
+        }
+
+        let pinned = PinnedVersion::current(Arc::clone(&sources.version_set), sources.path.clone());
+        let mut tables = Vec::new();
+        {
+            let version = pinned.version().read().unwrap();
+            // Each L0 table on its own is a sorted run, like a level
+            let runs = version
+                .level(0)
+                .iter()
+                .rev()
+                .map(std::slice::from_ref)
+                .chain(version.levels.iter().skip(1).map(Vec::as_slice));
+            for metas in runs.filter(|metas| !metas.is_empty()) {
+                let table_iter = LevelIterator::new_with_options(
+                    metas,
+                    sources.table_cache.paths().clone(),
+                    None,
+                    None,
+                    sources.block_read_options,
+                    Some(Arc::clone(&sources.table_cache)),
+                    RangeTombstones::default(),
+                )?;
+                tables.push(table_iter.with_seqnos()?);
+            }
+        }
+
+        let mut iter = RawIterator {
+            memtables,
+            tables,
+            heap: BinaryHeap::new(),
+            _version: pinned,
+            path: sources.path.clone(),
+            status: None,
+        };
+        for index in 0..iter.memtables.len() + iter.tables.len() {
+            iter.push(index);
+        }
+        Ok(iter)
+    }
+
+    /// Ok unless a read error has ended the iteration early. Check after
+    /// it ends to tell "no more versions" from "failed".
+    pub fn status(&self) -> Result<()> {
+        match &self.status {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Source `index`: a memtable's versions, or past those a table's.
+    fn source(&mut self, index: usize) -> &mut dyn StorageIterator {
+        match index.checked_sub(self.memtables.len()) {
+            None => &mut self.memtables[index],
+            Some(table) => &mut self.tables[table],
+        }
+    }
+
+    /// Put source `index`'s current version, if any, on the heap.
+    fn push(&mut self, index: usize) {
+        let (source, value_type): (&dyn StorageIterator, _) =
+            match index.checked_sub(self.memtables.len()) {
+                None => {
+                    let memtable = &self.memtables[index];
+                    if !memtable.is_valid() {
+                        return;
+                    }
+                    (memtable, memtable.value_type())
+                }
+                Some(table) => {
+                    let table = &self.tables[table];
+                    if !table.is_valid() {
+                        return;
+                    }
+                    let value_type = if table.value().is_empty() {
+                        ValueType::Delete
+                    } else {
+                        ValueType::Put
+                    };
+                    (table, value_type)
+                }
+            };
+        // Both kinds of source always know it
+        let Some(sequence) = source.seqno() else {
+            return;
+        };
+        let key = InternalKey {
+            user_key: source.key().to_vec(),
+            sequence,
+            value_type,
+        };
+        self.heap.push(Reverse((key, index)));
+    }
+
+    /// Take the value of source `index`'s current version, resolving a
+    /// table's blob pointer, and step the source on to its next version.
+    fn take(&mut self, index: usize) -> Result<Value> {
+        let source = self.source(index);
+        let value = source.value().to_vec();
+        source.next()?;
+        self.push(index);
+
+        if index < self.memtables.len() || !is_blob_index(&value) {
+            return Ok(value);
+        }
+        let blob_index = BlobIndex::decode(&value)?;
+        match read_blob_value(&self.path, &blob_index) {
+            // A shadowed version whose blob file was garbage collected:
+            // show the pointer itself
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(value),
+            result => result,
+        }
+    }
+}
+
+impl Iterator for RawIterator {
+    type Item = (InternalKey, Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((key, index)) = self.heap.pop()?;
+        match self.take(index) {
+            Ok(value) => Some((key, value)),
+            Err(e) => {
+                self.status = Some(e);
+                self.heap.clear();
+                None
+            }
+        }
+    }
+}
+
+/// Iterators over a DB's data sources, newest first.
+type Sources = Vec<Box<dyn StorageIterator>>;
+
+/// Shared handles to every data source of a DB.
+///
+/// Cloned from the DB when an iterator is created, so the iterator can
+/// re-read the latest state later without borrowing the DB.
+pub(crate) struct ReadSources {
+    pub(crate) active_memtable: Arc<RwLock<Arc<MemTable>>>,
+    pub(crate) immutable_memtables: Arc<RwLock<Vec<Arc<MemTable>>>>,
+    pub(crate) version_set: Arc<VersionSet>,
+    pub(crate) next_sequence: Arc<AtomicU64>,
+    pub(crate) path: PathBuf,
+    pub(crate) table_cache: Arc<TableCache>,
+    /// Block read behavior from the ReadOptions the iterator was built with.
+    pub(crate) block_read_options: BlockReadOptions,
+}
+
+impl ReadSources {
+    /// Number of memtable sources collect() puts before the SSTables.
+    pub(crate) fn num_memtable_sources(&self) -> usize {
+        2
+    }
+
+    /// One iterator per data source in [lower, upper), newest first:
+    /// active memtable → immutable memtables, merged into one (empty if
+    /// there are none) → L0 (newest-first) → L1+, with SSTables from the
+    /// current version. The memtables are read in place, not copied.
+    /// Returns them with that version pinned, paired with the current
+    /// sequence number.
+    ///
+    /// The memtables are read as of that sequence number, so writes racing
+    /// with the capture stay out, and before the version is pinned: a
+    /// flush installs its SSTables before it retires the immutable
+    /// memtables, so every entry is in one or the other.
+    pub(crate) fn collect(
+        &self,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Result<(Sources, PinnedVersion, u64)> {
+        let mut sources: Sources = Vec::new();
+
+        // Newest first: active memtable, captured under read lock, with
+        // the sequence number read under it too, so every write already in
+        // it is visible
+        let (active, sequence) = active_at_sequence(&self.active_memtable, &self.next_sequence);
+        sources.push(Box::new(active.shared_iter_at(lower, upper, sequence)));
+
+        // Then the immutable memtables waiting to be flushed
+        let immutables = self.immutable_memtables.read().unwrap().clone();
+        sources.push(memtable_source(&immutables, lower, upper, sequence)?);
+
+        let version = PinnedVersion::current(Arc::clone(&self.version_set), self.path.clone());
+        // Then every overlapping SSTable: L0 newest-first, then L1+
+        sources.extend(sstable_sources(
+            &version.version().read().unwrap(),
+            self.table_cache.paths(),
+            lower,
+            upper,
+            &self.block_read_options,
+            Some(&self.table_cache),
+        )?);
+
+        Ok((sources, version, sequence))
+    }
+}
+
+/// Iterators over the live SSTables in `version`, in merge priority order:
+/// one per L0 file, newest-first (overlapping ranges), then one
+/// LevelIterator per non-empty L1+ level (non-overlapping, opened lazily).
+/// Values are read in place from the tables' blocks.
+///
+/// SSTables whose [min_key, max_key] can't intersect [lower, upper) are
+/// skipped without being opened, and only in-bound entries are read from
+/// the rest. The caller pins `version`, so every SSTable it lists must
+/// still be readable; failures are returned, not skipped. Entries deleted
+/// by a newer table's range tombstones are left out.
+/// Blocks are read per `read_options`, from tables in `table_cache` if
+/// given.
+pub(crate) fn sstable_sources(
+    version: &Version,
+    paths: &TablePaths,
+    lower: Option<&[u8]>,
+    upper: Option<&[u8]>,
+    read_options: &BlockReadOptions,
+    table_cache: Option<&Arc<TableCache>>,
+) -> Result<Vec<Box<dyn StorageIterator>>> {
+    let mut iters: Vec<Box<dyn StorageIterator>> = Vec::new();
+    // Range tombstones of the tables visited so far: they delete matching
+    // entries of every older table, so those entries are left out
+    let mut deleted = RangeTombstones::default();
+
+    // L0: iterate newest-first (higher index = newer in the levels vec)
+    for meta in version.level(0).iter().rev() {
+        let overlaps = lower.is_none_or(|lower| meta.max_key.as_slice() >= lower)
+            && upper.is_none_or(|upper| meta.min_key.as_slice() < upper);
+        if !overlaps {
+            continue;
+        }
+        // On its own, each table is a sorted run like a level
+        let table_iter = LevelIterator::new_with_options(
+            std::slice::from_ref(meta),
+            paths.clone(),
+            lower,
+            upper,
+            *read_options,
+            table_cache.cloned(),
+            deleted.clone(),
+        )?;
+        let sst = open_table(
+            table_cache.map(Arc::as_ref),
+            paths,
+            meta,
+            read_options.file_access,
+        )?;
+        deleted.extend(sst.range_tombstones());
+        iters.push(Box::new(table_iter));
+    }
+
+    // L1+: each level is one sorted run, so one source per level
+    for (level_idx, level) in version.levels.iter().enumerate().skip(1) {
+        let level_iter = LevelIterator::new_with_options(
+            level,
+            paths.clone(),
+            lower,
+            upper,
+            *read_options,
+            table_cache.cloned(),
+            deleted.clone(),
+        )?;
+        // The level's own range tombstones only matter to deeper levels,
+        // and only for tables within the bounds
+        if version.levels[level_idx + 1..]
+            .iter()
+            .any(|l| !l.is_empty())
+        {
+            for meta in level_iter.metas() {
+                let sst = open_table(
+                    table_cache.map(Arc::as_ref),
+                    paths,
+                    meta,
+                    read_options.file_access,
+                )?;
+                deleted.extend(sst.range_tombstones());
+            }
+        }
+        if level_iter.num_files() > 0 {
+            iters.push(Box::new(level_iter));
+        }
+    }
+
+    Ok(iters)
+}
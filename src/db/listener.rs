@@ -0,0 +1,11 @@
+use crate::compaction::CompactionJobInfo;
+
+/// Callbacks for a DB's background work, for monitoring and post-mortems.
+/// Every method does nothing by default; implement the ones you need.
+///
+/// Listeners are called on the thread that did the work, once its result
+/// is installed. Keep them quick: the next compaction waits for them.
+pub trait EventListener: Send + Sync {
+    /// A compaction finished and its outputs replaced its inputs.
+    fn on_compaction_completed(&self, _info: &CompactionJobInfo) {}
+}
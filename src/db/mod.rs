@@ -1,193 +1,1220 @@
+mod background;
+pub mod batch;
+pub mod cursor;
+pub mod flush;
+pub mod iterator;
+pub mod listener;
+pub mod prefix;
 pub mod snapshot;
+pub mod tailing;
+pub mod updates;
+pub mod value_reader;
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
 
+use bytes::Bytes;
+
+use crate::blob::reader::{
+    BlobFileReader, BlobValueReader, read_blob_value, resolve_value, scan_blob_file_meta,
+};
+use crate::blob::{BlobFileMeta, BlobIndex, find_blob_files, is_blob_index};
 use crate::cache::BlockCache;
-use crate::compaction::CompactionStyle;
-use crate::error::Result;
+use crate::cache::table_cache::TableCache;
+use crate::checksum::ChecksumType;
+use crate::compaction::expiry::ExpiryPolicy;
+use crate::compaction::filter::CompactionFilter;
+use crate::compaction::leveled::LeveledStrategy;
+use crate::compaction::partitioner::SstPartitioner;
+use crate::compaction::remote::CompactionService;
+use crate::compaction::scheduler::{CompactionJobOptions, run_compaction};
+use crate::compaction::size_tiered::SizeTieredStrategy;
+use crate::compaction::throttle::CompactionThrottle;
+use crate::compaction::universal::UniversalStrategy;
+use crate::compaction::{
+    CancellationToken, CompactionJobInfo, CompactionReason, CompactionStrategy, CompactionStyle,
+    ManualCompaction,
+};
+use crate::db::background::{BackgroundPool, Job, JobQueue};
+use crate::db::flush::{FlushJob, FlushOutput};
+use crate::db::listener::EventListener;
+use crate::db::prefix::SliceTransform;
+use crate::delete_scheduler::DeleteScheduler;
+use crate::error::{Error, Result};
 use crate::iterator::StorageIterator;
+use crate::iterator::merge::MergeIterator;
 use crate::manifest::Manifest;
-use crate::manifest::version::{Version, VersionSet};
-use crate::memtable::MemTable;
-use crate::sstable::builder::SSTableBuilder;
-use crate::sstable::reader::SSTable;
-use crate::wal::SyncPolicy;
+use crate::manifest::version::{PinnedVersion, Version, VersionSet};
+use crate::memtable::filter::MemTableFilter;
+use crate::memtable::rep::{MemTableRepFactory, SkipListFactory};
+use crate::memtable::{MemTable, MemTableStats};
+use crate::rate_limiter::RateLimiter;
+use crate::sstable::builder::TableOptions;
+use crate::sstable::compression::CompressionType;
+use crate::sstable::footer::SSTableMeta;
+use crate::sstable::paths::{DbPath, TablePaths};
+use crate::sstable::properties::TablePropertiesCollectorFactory;
+use crate::sstable::reader::{BlockReadOptions, FileAccess, SSTable};
+use crate::types::ValueType;
+use crate::wal::archive::WALArchive;
 use crate::wal::reader::WALReader;
 use crate::wal::record::{RecordType, WALRecord};
-use crate::wal::writer::WALManager;
+use crate::wal::writer::{WALManager, WALSyncedHook};
+use crate::wal::{SyncPolicy, WALOptions, WALSyncStats};
+use crate::write_buffer_manager::{FlushTarget, WriteBufferManager};
 
-fn find_wal_files(dir: &Path) -> Vec<u64> {
-    let mut wal_numbers = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(dir) {
+/// The WALs in `dirs` by number, oldest first.
+fn find_wal_files(dirs: &[PathBuf]) -> Vec<(u64, PathBuf)> {
+    let mut wals = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
         for entry in entries.flatten() {
             if let Some(filename) = entry.file_name().to_str()
                 && let Some(num_str) = filename.strip_suffix(".wal")
                 && let Ok(num) = num_str.parse::<u64>()
             {
-                wal_numbers.push(num);
+                wals.push((num, entry.path()));
             }
         }
     }
-    wal_numbers.sort_unstable();
-    wal_numbers
+    wals.sort_unstable();
+    wals
+}
+
+/// One source over `memtables`, oldest first, yielding their entries
+/// (tombstones included) in [lower, upper) as of sequence number
+/// `sequence` (see MemTable::shared_iter_at); where several hold a key,
+/// the newest one's. `None` leaves that side of the range open.
+pub(crate) fn memtable_source(
+    memtables: &[Arc<MemTable>],
+    lower: Option<&[u8]>,
+    upper: Option<&[u8]>,
+    sequence: u64,
+) -> Result<Box<dyn StorageIterator>> {
+    let mut iters: Vec<Box<dyn StorageIterator>> = memtables
+        .iter()
+        .rev()
+        .map(|memtable| {
+            Box::new(memtable.shared_iter_at(lower, upper, sequence)) as Box<dyn StorageIterator>
+        })
+        .collect();
+    if iters.len() == 1 {
+        return Ok(iters.remove(0));
+    }
+    Ok(Box::new(MergeIterator::new(iters)?))
+}
+
+/// The active memtable in `slot`, with the next sequence number loaded
+/// under its read lock. Writers take that lock before their sequence
+/// number, so every write before the returned one is in the memtable or
+/// in the immutable ones read after it, and every later write to it comes
+/// after. A freeze swapping the memtable out in between makes it start
+/// over.
+pub(crate) fn active_at_sequence(
+    slot: &RwLock<Arc<MemTable>>,
+    next_sequence: &AtomicU64,
+) -> (Arc<MemTable>, u64) {
+    loop {
+        let active = Arc::clone(&slot.read().unwrap());
+        let sequence = {
+            let _memtable = active.read();
+            next_sequence.load(Ordering::SeqCst)
+        };
+        if Arc::ptr_eq(&active, &slot.read().unwrap()) {
+            return (active, sequence);
+        }
+    }
 }
 
 /// Configuration options for the storage engine.
 pub struct Options {
     /// Memtable flush threshold in bytes. Default: 4MB.
     pub memtable_size: usize,
+    /// Memtables kept in memory: the active one, plus up to this many
+    /// minus one full ones waiting to be flushed. Writes stall only once
+    /// all are full. A flush writes every waiting memtable and records
+    /// their SSTables in one MANIFEST edit, so recovery never sees part
+    /// of the set. At least 2. Default: 2.
+    pub max_write_buffer_number: usize,
     /// Target block size in bytes. Default: 4KB.
     pub block_size: usize,
     /// Bloom filter bits per key. Default: 10 (~1% FPR).
     pub bloom_bits_per_key: usize,
+    /// One bloom filter per data block, loaded on demand, instead of one
+    /// per SSTable kept in memory while it's open. Default: false.
+    pub partition_filters: bool,
+    /// Append a small hash index to each data block so point lookups skip
+    /// the binary search within the block. Costs about 1 byte per key;
+    /// worth it for get-heavy workloads with small entries. Default: false.
+    pub data_block_hash_index: bool,
     /// Maximum number of levels. Default: 7.
     pub max_levels: usize,
     /// Size ratio between adjacent levels. Default: 10.
     pub level_size_multiplier: usize,
+    /// Leveled compaction: size limit of L1 in bytes; each deeper level
+    /// may hold level_size_multiplier times more. Default: 10MB.
+    pub max_bytes_for_level_base: usize,
+    /// Leveled and size-tiered compaction: number of L0 SSTables that
+    /// triggers merging L0 into L1. Universal compaction: number of sorted runs (L0 SSTables
+    /// plus non-empty levels) that triggers a compaction. Default: 4.
+    pub level0_file_num_compaction_trigger: usize,
+    /// Universal compaction: percent by which a sorted run may be larger
+    /// than the newer runs picked before it and still be merged with
+    /// them. Default: 1.
+    pub universal_size_ratio: u64,
+    /// Universal compaction: fewest sorted runs merged by size ratio.
+    /// Default: 2.
+    pub universal_min_merge_width: usize,
+    /// Universal compaction: once the newer runs add up to more than this
+    /// percent of the oldest run, everything is merged into one run.
+    /// Default: 200.
+    pub universal_max_size_amplification_percent: u64,
+    /// Flush and compaction cut their output into SSTables of about this
+    /// many bytes for L0 and L1, so later compactions pick up evenly sized
+    /// pieces instead of one file per job. Default: 64MB.
+    pub target_file_size_base: u64,
+    /// Each level below L1 targets files this many times larger than the
+    /// level above it. Default: 1 (same size at every level).
+    pub target_file_size_multiplier: u64,
     /// Block cache capacity in bytes. Default: 8MB.
     pub block_cache_size: usize,
+    /// Most SSTables kept open at once. Tables are opened on first read
+    /// and the least recently used is closed past this limit, so the
+    /// open file count stays bounded as levels grow. Default: 1000.
+    pub max_open_files: usize,
+    /// Read SSTables through memory maps instead of seek+read on a file
+    /// handle. Saves a syscall and a copy per block read on read-heavy
+    /// workloads; costs address space per open table. Default: false.
+    pub use_mmap_reads: bool,
+    /// Read SSTables with O_DIRECT, bypassing the OS page cache (the
+    /// block cache still applies). Can't be combined with use_mmap_reads.
+    /// Linux only. Default: false.
+    pub use_direct_reads: bool,
+    /// Write flushed and compacted SSTables with O_DIRECT, and read
+    /// compaction inputs with it, so background I/O doesn't evict the
+    /// page cache other readers depend on. Linux only. Default: false.
+    pub use_direct_io_for_flush_and_compaction: bool,
+    /// Re-read every SSTable a compaction writes before the MANIFEST
+    /// records it: block checksums, key order, and entry counts against
+    /// what the merge of the inputs produced. On a mismatch the outputs
+    /// are deleted, the inputs stay live and compaction returns
+    /// Error::Corruption. Costs a full read of the output. Default: false.
+    pub paranoid_file_checks: bool,
+    /// Tells compaction when entries expire, so it drops them (see
+    /// compaction::expiry::ExpiryPolicy). Default: None.
+    pub expiry_policy: Option<Arc<dyn ExpiryPolicy>>,
+    /// Called for every live entry compaction writes, to keep, drop or
+    /// rewrite it (see compaction::filter::CompactionFilter). Default: None.
+    pub compaction_filter: Option<Arc<dyn CompactionFilter>>,
+    /// Runs compactions elsewhere, e.g. on another machine, with their
+    /// outputs installed here; a compaction it fails runs locally (see
+    /// compaction::remote::CompactionService). Default: None.
+    pub compaction_service: Option<Arc<dyn CompactionService>>,
+    /// Cuts flush and compaction output into separate SSTables at the
+    /// keys it picks, e.g. between tenants (see
+    /// compaction::partitioner::SstPartitioner). Default: None.
+    pub sst_partitioner: Option<Arc<dyn SstPartitioner>>,
+    /// Told about background work as it finishes, e.g. every compaction
+    /// (see listener::EventListener). Default: none.
+    pub listeners: Vec<Arc<dyn EventListener>>,
+    /// Finished compactions kept for DB::compaction_history().
+    /// Default: 100.
+    pub max_compaction_history: usize,
+    /// Threads one compaction may use: its key range is split into up to
+    /// this many subranges, merged in parallel into separate SSTables and
+    /// installed together. Speeds up large compactions, such as a full
+    /// merge into the bottom level. Default: 1.
+    pub max_subcompactions: usize,
+    /// Caps the rate at which flushes and compactions write SSTables, so
+    /// they leave disk bandwidth for foreground reads. Share one limiter
+    /// between DBs on the same disk. Default: None (unlimited).
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Asked for limits before every compaction, to lower or raise its
+    /// subcompactions and write rate by time of day or load (see
+    /// throttle::TimeWindowThrottle). Default: None.
+    pub compaction_throttle: Option<Arc<dyn CompactionThrottle>>,
+    /// Caps the rate at which obsolete SSTables, blob files and WALs are
+    /// deleted: they're moved to a `trash` directory beside them and
+    /// removed in the background at this many bytes per second (see
+    /// DeleteScheduler). Default: 0 (deleted right away).
+    pub delete_rate_bytes_per_sec: u64,
+    /// Worker threads for background compactions, which also take
+    /// flushes before any compaction, so a full memtable never waits
+    /// behind a queue of compactions. Default: 2.
+    pub max_background_jobs: usize,
+    /// Extra worker threads that only flush, so a full memtable isn't
+    /// stuck behind long compactions holding every other worker, stalling
+    /// writes. 0 leaves flushes to the max_background_jobs workers.
+    /// Default: 1.
+    pub max_background_flushes: usize,
+    /// Don't compact after flushes; only compact_range() compacts.
+    /// Default: false.
+    pub disable_auto_compactions: bool,
+    /// Once compaction is estimated to be this many bytes behind, every
+    /// write is delayed by 1ms, so background work catches up before the
+    /// hard limit is hit. 0 disables. Default: 64GB.
+    pub soft_pending_compaction_bytes_limit: u64,
+    /// Once compaction is estimated to be this many bytes behind, writes
+    /// stop until it catches up. 0 disables. Neither limit applies with
+    /// disable_auto_compactions. Default: 256GB.
+    pub hard_pending_compaction_bytes_limit: u64,
+    /// Directories to spread SSTables over, each with a target size: a
+    /// new SSTable goes to the first path whose live SSTables are below
+    /// its target, the last path taking the overflow. The manifest
+    /// records each SSTable's path, so reopen with the same list. The
+    /// MANIFEST, WALs and blob files stay in the DB directory.
+    /// Default: empty (SSTables in the DB directory).
+    pub db_paths: Vec<DbPath>,
+    /// Keep the SSTables of cold_tier_level and the levels below it in
+    /// this directory, e.g. on slow, cheap storage, and only the levels
+    /// above in the DB directory or db_paths. Compaction writes each
+    /// output to the tier of its level, so data moves down as it's
+    /// compacted. Reopen with the same setting. Default: None.
+    pub cold_tier_path: Option<PathBuf>,
+    /// First level kept in cold_tier_path. Default: 6, the last level
+    /// with the default max_levels.
+    pub cold_tier_level: u32,
     /// WAL sync policy. Default: EveryWrite.
     pub sync_policy: SyncPolicy,
+    /// Disk space reserved for each WAL up front (see
+    /// WALOptions::preallocate_size). Default: 0.
+    pub wal_preallocate_size: u64,
+    /// Flushed WALs kept to be overwritten by later WALs instead of
+    /// deleted (see WALOptions::recycle_log_file_num). Default: 0.
+    pub recycle_log_file_num: usize,
+    /// Size at which writes switch to a new WAL, even if the memtable
+    /// isn't full, keeping each WAL quick to replay. 0 never switches.
+    /// Default: 0.
+    pub max_wal_size: u64,
+    /// Keep flushed WALs in an `archive` directory (see WALArchive) for
+    /// this long instead of deleting them. Setting this or
+    /// wal_size_limit_bytes turns archiving on, and WAL recycling off.
+    /// Default: zero.
+    pub wal_ttl: Duration,
+    /// Prune the oldest archived WALs while the archive is bigger than
+    /// this. Default: 0 (no limit).
+    pub wal_size_limit_bytes: u64,
+    /// Directories to write WALs in, e.g. on separate devices: each new
+    /// WAL goes to the next one round-robin, spreading fsyncs over them.
+    /// WALs already in the DB directory or any of these are found on
+    /// open, so the list can change between runs. Default: empty (WALs
+    /// in the DB directory).
+    pub wal_dirs: Vec<PathBuf>,
+    /// Write WALs framed in 32KB blocks, so recovery skips a block a torn
+    /// write damaged and replays the rest, instead of stopping there (see
+    /// WALOptions::block_format). Default: false.
+    pub wal_block_format: bool,
+    /// Leave logged writes buffered in the process until flush_wal() or
+    /// the next sync, instead of handing each to the OS as it's made
+    /// (see WALOptions::manual_flush). Saves a write call per write, but
+    /// a process crash loses what's still buffered, and
+    /// get_updates_since() doesn't see it yet. Default: false.
+    pub manual_wal_flush: bool,
     /// Compaction strategy. Default: Leveled.
     pub compaction_style: CompactionStyle,
+    /// Prefix extractor for ReadOptions::prefix_same_as_start. Default: None.
+    pub prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    /// Size of a bloom filter kept with each memtable, as a fraction of
+    /// memtable_size (0.1 gives a 4MB memtable a 400KB filter), so get()
+    /// skips searching memtables that never saw the key. It holds key
+    /// prefixes per prefix_extractor, or whole keys with
+    /// memtable_whole_key_filtering or without an extractor. Capped at
+    /// 0.25; 0 keeps no filter. Default: 0.
+    pub memtable_prefix_bloom_size_ratio: f64,
+    /// Put whole keys in the memtable bloom filter even with a
+    /// prefix_extractor, so lookups within a prefix that was written can
+    /// still be ruled out. Default: false.
+    pub memtable_whole_key_filtering: bool,
+    /// Builds the store behind each memtable, trading insert speed
+    /// against lookup and scan speed: rep::SkipListFactory,
+    /// BTreeFactory, HashSkipListFactory (prefix-hashed skip lists for
+    /// point-lookup workloads) or VectorFactory (appends, sorted once at
+    /// freeze, for bulk loads). SeededSkipListFactory makes skip list
+    /// layouts reproducible. Default: SkipListFactory.
+    pub memtable_factory: Arc<dyn MemTableRepFactory>,
+    /// Caps the memory of the memtables of every DB sharing it, flushing
+    /// the largest active one when over budget rather than only each
+    /// memtable at memtable_size. Default: None (no global cap).
+    pub write_buffer_manager: Option<Arc<WriteBufferManager>>,
+    /// Data block compression for new SSTables (flush and compaction).
+    /// Existing tables stay readable whatever they were written with.
+    /// Default: None.
+    pub compression: CompressionType,
+    /// Compression by level, overriding `compression`: entry n applies to
+    /// tables written to level n, the last entry to every deeper level.
+    /// E.g. `[None, None, Zstd]` keeps hot L0/L1 cheap to write and read
+    /// and compresses the large cold levels. Default: empty.
+    pub compression_per_level: Vec<CompressionType>,
+    /// Checksum of new WAL records and SSTable data blocks. Each file
+    /// records its own, so changing it leaves existing ones readable.
+    /// Default: CRC32.
+    pub checksum: ChecksumType,
+    /// With CompressionType::Zstd, train a dictionary of up to this many
+    /// bytes per SSTable and compress its blocks against it. Helps small
+    /// blocks of similar values. 0 disables it. Default: 0.
+    pub zstd_max_dict_bytes: usize,
+    /// Factories for collectors that gather custom properties for every
+    /// new SSTable, readable via SSTable::properties(). Default: none.
+    pub table_properties_collectors: Vec<Arc<dyn TablePropertiesCollectorFactory>>,
+    /// Key-value separation: on flush, values of at least min_blob_size
+    /// bytes go to append-only blob files and SSTables keep only a pointer,
+    /// so compaction no longer rewrites them. Reads resolve pointers
+    /// transparently. Default: false.
+    pub enable_blob_files: bool,
+    /// Smallest value moved to a blob file when enable_blob_files is set.
+    /// Default: 4KB.
+    pub min_blob_size: usize,
+    /// Run garbage_collect_blobs() at the end of every compact_range(),
+    /// once compaction has dropped the overwritten pointers. Default: false.
+    pub enable_blob_garbage_collection: bool,
+    /// Blob files whose live ratio (bytes of values still current / bytes
+    /// written) falls below this are rewritten by blob garbage collection.
+    /// Default: 0.5.
+    pub blob_gc_threshold: f64,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             memtable_size: 4 * 1024 * 1024, // 4 MB
-            block_size: 4 * 1024,           // 4 KB
-            bloom_bits_per_key: 10,         // ~1% FPR
+            max_write_buffer_number: 2,
+            block_size: 4 * 1024,   // 4 KB
+            bloom_bits_per_key: 10, // ~1% FPR
+            partition_filters: false,
+            data_block_hash_index: false,
             max_levels: 7,
             level_size_multiplier: 10,
+            max_bytes_for_level_base: 10 * 1024 * 1024, // 10 MB
+            level0_file_num_compaction_trigger: LeveledStrategy::DEFAULT_LEVEL0_TRIGGER,
+            universal_size_ratio: 1,
+            universal_min_merge_width: 2,
+            universal_max_size_amplification_percent: 200,
+            target_file_size_base: 64 * 1024 * 1024, // 64 MB
+            target_file_size_multiplier: 1,
             block_cache_size: 8 * 1024 * 1024, // 8 MB
+            max_open_files: 1000,
+            use_mmap_reads: false,
+            use_direct_reads: false,
+            use_direct_io_for_flush_and_compaction: false,
+            paranoid_file_checks: false,
+            expiry_policy: None,
+            compaction_filter: None,
+            compaction_service: None,
+            sst_partitioner: None,
+            listeners: Vec::new(),
+            max_compaction_history: 100,
+            max_subcompactions: 1,
+            rate_limiter: None,
+            compaction_throttle: None,
+            delete_rate_bytes_per_sec: 0,
+            max_background_jobs: 2,
+            max_background_flushes: 1,
+            disable_auto_compactions: false,
+            soft_pending_compaction_bytes_limit: 64 << 30,
+            hard_pending_compaction_bytes_limit: 256 << 30,
+            db_paths: Vec::new(),
+            cold_tier_path: None,
+            cold_tier_level: 6,
             sync_policy: SyncPolicy::EveryWrite,
+            wal_preallocate_size: 0,
+            recycle_log_file_num: 0,
+            max_wal_size: 0,
+            wal_ttl: Duration::ZERO,
+            wal_size_limit_bytes: 0,
+            wal_dirs: Vec::new(),
+            wal_block_format: false,
+            manual_wal_flush: false,
             compaction_style: CompactionStyle::Leveled,
+            prefix_extractor: None,
+            memtable_prefix_bloom_size_ratio: 0.0,
+            memtable_whole_key_filtering: false,
+            memtable_factory: Arc::new(SkipListFactory),
+            write_buffer_manager: None,
+            compression: CompressionType::None,
+            compression_per_level: Vec::new(),
+            checksum: ChecksumType::Crc32,
+            zstd_max_dict_bytes: 0,
+            table_properties_collectors: Vec::new(),
+            enable_blob_files: false,
+            min_blob_size: 4 * 1024,
+            enable_blob_garbage_collection: false,
+            blob_gc_threshold: 0.5,
+        }
+    }
+}
+
+/// Per-read options for get and iterators.
+///
+/// Bounds follow the usual convention: lower is inclusive, upper is
+/// exclusive. SSTables whose key range falls entirely outside the bounds
+/// are skipped without being opened.
+#[derive(Debug, Clone)]
+pub struct ReadOptions {
+    /// Inclusive lower bound for iteration. Default: None (unbounded).
+    pub iterate_lower_bound: Option<Vec<u8>>,
+    /// Exclusive upper bound for iteration. Default: None (unbounded).
+    pub iterate_upper_bound: Option<Vec<u8>>,
+    /// After a seek, stop once keys no longer share the seek target's
+    /// prefix (per Options::prefix_extractor). Default: false.
+    pub prefix_same_as_start: bool,
+    /// Insert data blocks read from disk into the block cache. Turn off
+    /// for bulk scans so they don't evict the working set. Default: true.
+    pub fill_cache: bool,
+    /// Verify each data block's CRC32 before using it; a mismatch fails
+    /// the read with Error::Corruption. Default: true.
+    pub verify_checksums: bool,
+    /// Read as of this snapshot instead of the latest state.
+    /// Default: None (read the latest state).
+    pub snapshot: Option<Arc<snapshot::Snapshot>>,
+    /// Iterators skip reading values: value() returns an empty slice for
+    /// every key. For counting or sampling keys. Default: false.
+    pub keys_only: bool,
+    /// Once an SSTable scan is reading blocks sequentially, fetch this many
+    /// bytes per file read instead of one block at a time. 0 disables
+    /// readahead. Default: 0.
+    pub readahead_size: usize,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            iterate_lower_bound: None,
+            iterate_upper_bound: None,
+            prefix_same_as_start: false,
+            fill_cache: true,
+            verify_checksums: true,
+            snapshot: None,
+            keys_only: false,
+            readahead_size: 0,
+        }
+    }
+}
+
+impl ReadOptions {
+    /// The subset of options that applies to SSTable block reads.
+    pub(crate) fn block_read_options(&self) -> BlockReadOptions {
+        BlockReadOptions {
+            verify_checksums: self.verify_checksums,
+            fill_cache: self.fill_cache,
+            keys_only: self.keys_only,
+            readahead_size: self.readahead_size,
+            ..BlockReadOptions::default()
+        }
+    }
+}
+
+/// Per-write options for put and delete.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Skip the WAL: the write only goes to the memtable, so a crash
+    /// before the memtable is flushed loses it. For bulk loads that can be
+    /// redone; flush() or close() makes such writes durable. Default: false.
+    pub disable_wal: bool,
+    /// fsync the WAL before the write returns, whatever the SyncPolicy:
+    /// for writes that must survive a crash (e.g. commit markers) amid
+    /// bulk traffic synced less often. Can't be combined with
+    /// disable_wal. Default: false.
+    pub sync: bool,
+}
+
+impl WriteOptions {
+    fn validate(&self) -> Result<()> {
+        if self.sync && self.disable_wal {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "sync and disable_wal can't both be set",
+            )));
         }
+        Ok(())
     }
 }
 
 /// Internal engine statistics.
 pub struct Stats {
     pub memtable_size: usize,
+    /// What the active memtable holds.
+    pub memtable: MemTableStats,
     pub num_sstables_per_level: Vec<usize>,
+    /// Fraction of get()'s SSTable bloom checks that ruled the table out.
     pub bloom_filter_hit_rate: f64,
     pub block_cache_hit_rate: f64,
+    /// SSTables held open by the table cache (at most max_open_files).
+    pub open_tables: usize,
     pub bytes_written: u64,
     pub bytes_read: u64,
     /// bytes_written_to_disk / bytes_written_by_user
     pub write_amplification: f64,
     pub compaction_count: u64,
     pub compaction_bytes: u64,
+    /// Entries compactions dropped as expired (see Options::expiry_policy).
+    pub dropped_expired: u64,
+    /// Per-level breakdown, L0 first.
+    pub levels: Vec<LevelStats>,
+    /// Compactions since open, by why they ran.
+    pub compaction_reasons: BTreeMap<CompactionReason, CompactionReasonStats>,
+    /// WAL fsyncs since open, by trigger, and their latency.
+    pub wal_syncs: WALSyncStats,
+}
+
+/// Statistics of the compactions run for one reason.
+#[derive(Debug, Clone, Default)]
+pub struct CompactionReasonStats {
+    pub compaction_count: u64,
+    /// Time spent in those compactions.
+    pub compaction_time: Duration,
+    /// Bytes the compactions read, from both levels.
+    pub bytes_read: u64,
+    /// Bytes of SSTables the compactions wrote.
+    pub bytes_written: u64,
+}
+
+/// Statistics of one level. Flushes count as writes into L0, reading
+/// the memtable as the level above.
+#[derive(Debug, Clone, Default)]
+pub struct LevelStats {
+    pub num_files: usize,
+    pub size_bytes: u64,
+    /// How close the level is to compaction by the background strategy;
+    /// past 1.0 it is due (see CompactionStrategy::level_scores).
+    pub score: f64,
+    /// Compactions that wrote into this level.
+    pub compaction_count: u64,
+    /// Time spent in those compactions.
+    pub compaction_time: Duration,
+    /// Bytes flushes and compactions into this level read from above it.
+    pub bytes_read_upper: u64,
+    /// Bytes compactions into this level read from the level itself.
+    pub bytes_read_level: u64,
+    /// Bytes of SSTables written into this level.
+    pub bytes_written: u64,
+    /// (bytes_read_upper + bytes_read_level) / bytes_read_upper
+    pub read_amplification: f64,
+    /// bytes_written / bytes_read_upper
+    pub write_amplification: f64,
+}
+
+/// Where the newest stored value for a key was found.
+enum StoredValue {
+    /// A memtable: always the user value itself.
+    Memtable(Bytes),
+    /// An SSTable: the user value or a blob pointer.
+    Table(Bytes),
 }
 
 /// The main database handle. Thread-safe.
 ///
 /// Coordinates all components: memtable, WAL, SSTables, compaction,
-/// manifest, block cache.
+/// manifest, block cache. Flushes and compactions run on a pool of
+/// Options::max_background_jobs worker threads, next to
+/// Options::max_background_flushes that only flush; all stop when the
+/// handle is closed or dropped.
 pub struct DB {
+    inner: Arc<DBInner>,
+    background: BackgroundPool,
+}
+
+/// State of an open database, shared by its handle and the background
+/// workers. Reached through DB, which derefs to it.
+pub struct DBInner {
     /// Database directory path.
     path: PathBuf,
+    /// Where WALs may be: the DB directory and Options::wal_dirs.
+    wal_dirs: Vec<PathBuf>,
     /// Memtable size limit (cached from Options for flush).
     memtable_size: usize,
-    /// Block size (cached from Options for SSTable building).
-    block_size: usize,
+    /// Empty bloom filter each new memtable gets a copy of, if
+    /// Options::memtable_prefix_bloom_size_ratio is set.
+    memtable_filter: Option<MemTableFilter>,
+    /// Options::memtable_factory.
+    memtable_factory: Arc<dyn MemTableRepFactory>,
+    /// Options::write_buffer_manager, charged for every memtable byte.
+    write_buffer_manager: Option<Arc<WriteBufferManager>>,
+    /// SSTable build settings (cached from Options for flush and compaction).
+    table_options: TableOptions,
+    /// Smallest value flush moves to a blob file; None when
+    /// Options::enable_blob_files is off.
+    min_blob_size: Option<usize>,
+    /// Number of the next blob file flush creates.
+    next_blob_file_number: AtomicU64,
+    /// Whether compact_range() ends with blob garbage collection.
+    enable_blob_gc: bool,
+    /// Live ratio below which a blob file is garbage collected.
+    blob_gc_threshold: f64,
     // M24: Read path sources
-    pub active_memtable: Arc<RwLock<MemTable>>,
-    pub immutable_memtable: Option<Arc<MemTable>>,
+    /// The memtable taking writes. The lock is only held to clone the Arc
+    /// or, when it's frozen, to swap in a new one.
+    pub active_memtable: Arc<RwLock<Arc<MemTable>>>,
+    /// Memtables frozen for flushing, oldest first, still read until
+    /// their SSTables are installed. Shared with iterators, like the
+    /// active memtable.
+    pub immutable_memtables: Arc<RwLock<Vec<Arc<MemTable>>>>,
+    /// Most immutable memtables before writers stall
+    /// (Options::max_write_buffer_number - 1).
+    max_immutable_memtables: usize,
     pub version_set: Arc<VersionSet>,
+    /// Deletes obsolete files, shared with version_set.
+    delete_scheduler: Arc<DeleteScheduler>,
     /// Next sequence number for writes (monotonic)
     pub next_sequence: Arc<AtomicU64>,
     /// Manifest for recording structural changes (flush, compaction).
-    manifest: Mutex<Manifest>,
+    manifest: Arc<Mutex<Manifest>>,
     /// WAL manager for durable writes.
     wal_manager: Mutex<WALManager>,
-    /// Compaction strategy style.
-    compaction_style: CompactionStyle,
-    /// Block cache for SSTable data blocks.
-    block_cache: Mutex<BlockCache>,
+    /// WALs of each immutable memtable, deleted once it's flushed, and
+    /// the id of the WAL that replaced them.
+    immutable_wals: Mutex<Vec<(Vec<PathBuf>, u64)>>,
+    /// Where flushed WALs go instead of being deleted, if archiving is on
+    /// (Options::wal_ttl, wal_size_limit_bytes).
+    wal_archive: Option<WALArchive>,
+    /// Batches given to write_prepared() awaiting their commit or
+    /// rollback, by transaction id. Locked after wal_manager.
+    prepared: Mutex<BTreeMap<Vec<u8>, Vec<WALRecord>>>,
+    /// Flushes and compactions waiting for a background worker.
+    jobs: Arc<JobQueue>,
+    /// Whether flushes queue a compaction (!Options::disable_auto_compactions).
+    auto_compactions: bool,
+    /// Options::soft_pending_compaction_bytes_limit.
+    soft_pending_compaction_bytes_limit: u64,
+    /// Options::hard_pending_compaction_bytes_limit.
+    hard_pending_compaction_bytes_limit: u64,
+    /// Bytes compaction_strategy estimates it still has to rewrite,
+    /// updated whenever a flush or compaction installs a version.
+    pending_compaction_bytes: AtomicU64,
+    /// Held for a whole flush, so flushes run one at a time.
+    flush_lock: Mutex<()>,
+    /// Held for a whole compaction round: two at once could pick the
+    /// same inputs.
+    compaction_lock: Mutex<()>,
+    /// Cancelled on close and by pause_background_work(), stopping the
+    /// running compaction and any started until it's reset.
+    compaction_cancel: CancellationToken,
+    /// Picks background compactions, per Options::compaction_style.
+    compaction_strategy: Arc<dyn CompactionStrategy>,
+    /// Picks the compactions compact_range() runs: the same, except that
+    /// size-tiered compacts L0 whatever its file count.
+    manual_compaction_strategy: Arc<dyn CompactionStrategy>,
+    /// Options::expiry_policy, applied by every compaction.
+    expiry_policy: Option<Arc<dyn ExpiryPolicy>>,
+    /// Options::compaction_filter, applied by every compaction.
+    compaction_filter: Option<Arc<dyn CompactionFilter>>,
+    /// Options::compaction_service, handed every compaction first.
+    compaction_service: Option<Arc<dyn CompactionService>>,
+    /// Options::listeners.
+    listeners: Vec<Arc<dyn EventListener>>,
+    /// The last Options::max_compaction_history compactions, oldest first.
+    compaction_history: Mutex<VecDeque<CompactionJobInfo>>,
+    max_compaction_history: usize,
+    /// Subranges a compaction is split into (Options::max_subcompactions).
+    max_subcompactions: usize,
+    /// Options::compaction_throttle.
+    compaction_throttle: Option<Arc<dyn CompactionThrottle>>,
+    /// Paces compactions the throttle gives a rate, replaced when the
+    /// rate changes.
+    throttle_rate_limiter: Mutex<Option<Arc<RateLimiter>>>,
+    /// Prefix extractor used by prefix_same_as_start iterators.
+    prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    /// Block cache for SSTable data blocks, shared with open iterators.
+    block_cache: Arc<Mutex<BlockCache>>,
+    /// Open SSTables, reading through `block_cache`; shared with open
+    /// iterators.
+    table_cache: Arc<TableCache>,
+    /// How SSTables are opened for reads (Options::use_mmap_reads,
+    /// use_direct_reads).
+    file_access: FileAccess,
     /// Stats: bytes written by user (put key+value, delete key).
     bytes_written_user: AtomicU64,
-    /// Stats: bytes written to disk (SSTable file sizes from flush).
+    /// Stats: bytes written to disk (SSTable and blob file sizes from flush).
     bytes_written_disk: AtomicU64,
     /// Stats: bytes read from get() hits.
     bytes_read: AtomicU64,
+    /// Stats: SSTable bloom filter checks made by get().
+    bloom_checks: AtomicU64,
+    /// Stats: bloom checks that ruled a table out, saving its block read.
+    bloom_useful: AtomicU64,
     /// Stats: number of compactions completed.
     compaction_count: AtomicU64,
+    /// Stats: entries compaction dropped as expired.
+    dropped_expired: AtomicU64,
     /// Stats: total bytes processed by compaction.
     compaction_bytes: AtomicU64,
+    /// Stats: cumulative flush and compaction counters of each level.
+    level_stats: Mutex<Vec<LevelStats>>,
+    compaction_reasons: Mutex<BTreeMap<CompactionReason, CompactionReasonStats>>,
 }
 
 impl DB {
+    /// Open or create a database at the given path, and start its
+    /// background workers.
+    pub fn open(path: &Path, options: Options) -> Result<Self> {
+        let flush_threads = options.max_background_flushes;
+        let threads = options.max_background_jobs;
+        let inner = Arc::new(DBInner::open(path, options)?);
+        if let Some(manager) = &inner.write_buffer_manager {
+            let target: Arc<dyn FlushTarget> = inner.clone();
+            manager.register(Arc::downgrade(&target));
+            manager.reserve(inner.active_memtable().size());
+        }
+        let worker = Arc::clone(&inner);
+        let background = BackgroundPool::start(
+            flush_threads,
+            threads,
+            Arc::clone(&inner.jobs),
+            Arc::new(move |job| worker.run_job(job)),
+        );
+        // Archived WALs may have expired while the DB was closed
+        inner.purge_wal_archive()?;
+        // Recovered SSTables may already call for a compaction
+        inner.schedule_compaction();
+        Ok(DB { inner, background })
+    }
+
+    /// Block until no flush or compaction is queued or running.
+    ///
+    /// Fails with the error of the first background job that failed.
+    pub fn wait_for_background_jobs(&self) -> Result<()> {
+        self.jobs.wait_idle()
+    }
+
+    /// Close the database gracefully.
+    ///
+    /// Flushes any remaining memtable data, cancels a running compaction
+    /// and waits for the other running background jobs (queued ones are
+    /// dropped), and syncs the WAL. Fails with the first error a
+    /// background job hit, if any.
+    pub fn close(mut self) -> Result<()> {
+        self.flush()?;
+        self.compaction_cancel.cancel();
+        self.background.shutdown();
+
+        // Sync the active WAL
+        self.wal_manager.lock().unwrap().active_writer().sync()?;
+
+        self.jobs.error()
+    }
+}
+
+impl Drop for DB {
+    /// Dropping the handle doesn't wait for a compaction to finish either.
+    fn drop(&mut self) {
+        self.compaction_cancel.cancel();
+        self.background.shutdown();
+    }
+}
+
+impl Deref for DB {
+    type Target = DBInner;
+
+    fn deref(&self) -> &DBInner {
+        &self.inner
+    }
+}
+
+impl FlushTarget for DBInner {
+    fn active_memtable_size(&self) -> usize {
+        self.active_memtable().size()
+    }
+
+    fn request_flush(&self) {
+        // A failed WAL rotation leaves the memtable active, to be frozen
+        // again when it fills
+        if self.freeze_memtable(false).unwrap_or(false) {
+            self.jobs.schedule(Job::Flush);
+        }
+    }
+}
+
+impl Drop for DBInner {
+    /// Credit the write buffer manager for the memtables going away.
+    fn drop(&mut self) {
+        if let Some(manager) = &self.write_buffer_manager {
+            let active = self.active_memtable().size();
+            let immutable: usize = self
+                .immutable_memtables
+                .read()
+                .unwrap()
+                .iter()
+                .map(|mt| mt.size())
+                .sum();
+            manager.schedule_free(active);
+            manager.free(active + immutable);
+        }
+    }
+}
+
+impl DBInner {
     /// Open or create a database at the given path.
     ///
     /// Recovery sequence:
     /// 1. Create directory if needed
     /// 2. Read manifest → reconstruct Version + log_number + next_sst_id
-    /// 3. Find WAL files with id >= log_number, replay into memtable
+    /// 3. Replay the WALs the manifest lists as live into memtable
     /// 4. Create new WALManager for future writes
     /// 5. Ready to serve
-    pub fn open(path: &Path, options: Options) -> Result<Self> {
+    fn open(path: &Path, options: Options) -> Result<Self> {
+        let file_access = match (options.use_mmap_reads, options.use_direct_reads) {
+            (true, true) => {
+                return Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "use_mmap_reads and use_direct_reads can't both be set",
+                )));
+            }
+            (true, false) => FileAccess::Mmap,
+            (false, true) => FileAccess::Direct,
+            (false, false) => FileAccess::Buffered,
+        };
+
         // 1. Ensure the database directory exists
         std::fs::create_dir_all(path)?;
 
         // 2. Open manifest — replays all records to reconstruct Version
-        let manifest = Manifest::open(&path.join("MANIFEST"))?;
+        let mut manifest = Manifest::open(&path.join("MANIFEST"))?;
         let log_number = manifest.log_number();
         let next_sst_id = manifest.next_sst_id();
         let version = manifest.current_version().clone();
 
-        // 3. Build VersionSet from recovered state
-        let version_set = Arc::new(VersionSet::new_from(version, next_sst_id));
+        // 3. Build VersionSet from recovered state, with SSTables in the
+        // configured paths
+        let table_paths = if options.db_paths.is_empty() {
+            TablePaths::single(path)
+        } else {
+            for db_path in &options.db_paths {
+                std::fs::create_dir_all(&db_path.path)?;
+            }
+            TablePaths::new(options.db_paths.clone())
+        };
+        let table_paths = match &options.cold_tier_path {
+            Some(cold_path) => {
+                std::fs::create_dir_all(cold_path)?;
+                table_paths.with_cold_tier(cold_path, options.cold_tier_level)
+            }
+            None => table_paths,
+        };
+        if let Some(meta) = version
+            .levels
+            .iter()
+            .flatten()
+            .find(|meta| meta.path_id as usize >= table_paths.len())
+        {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "SSTable {} is in db path {}, but only {} are configured",
+                    meta.id,
+                    meta.path_id,
+                    table_paths.len()
+                ),
+            )));
+        }
+        let wal_archive = (!options.wal_ttl.is_zero() || options.wal_size_limit_bytes > 0)
+            .then(|| WALArchive::new(path, options.wal_ttl, options.wal_size_limit_bytes));
+        // Files a previous run trashed but didn't get to delete are queued
+        // again
+        let delete_scheduler = Arc::new(DeleteScheduler::new(options.delete_rate_bytes_per_sec));
+        // WALs are looked for in the DB directory and every WAL directory
+        let mut wal_dirs = vec![path.to_path_buf()];
+        for dir in &options.wal_dirs {
+            std::fs::create_dir_all(dir)?;
+            if !wal_dirs.contains(dir) {
+                wal_dirs.push(dir.clone());
+            }
+        }
+        let mut dirs: Vec<&Path> = wal_dirs.iter().map(PathBuf::as_path).collect();
+        if let Some(archive) = &wal_archive {
+            dirs.push(archive.dir());
+        }
+        for i in 0..table_paths.len() {
+            let dir = table_paths.dir(i as u32);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        for dir in dirs {
+            delete_scheduler.recover_trash(dir)?;
+        }
+        let version_set = Arc::new(
+            VersionSet::new_from(version.clone(), next_sst_id)
+                .with_table_paths(table_paths.clone())
+                .with_delete_scheduler(Arc::clone(&delete_scheduler)),
+        );
+
+        // 4. Replay the WALs the manifest lists as live. Manifests from
+        // before WALs were listed leave it to the WAL files >= log_number.
+        let wals = find_wal_files(&wal_dirs);
+        let live_wals = manifest.live_wals().cloned();
+        if let Some(missing) = live_wals
+            .iter()
+            .flatten()
+            .find(|wal_id| !wals.iter().any(|(id, _)| id == *wal_id))
+        {
+            return Err(Error::Corruption(format!(
+                "WAL {missing:06} listed in the manifest is missing"
+            )));
+        }
+        // Replayed writes are numbered after everything already flushed
+        let last_flushed_seqno = version
+            .levels
+            .iter()
+            .flatten()
+            .map(|meta| meta.largest_seqno)
+            .max()
+            .unwrap_or(0);
+        let memtable_filter = (options.memtable_prefix_bloom_size_ratio > 0.0).then(|| {
+            let ratio = options.memtable_prefix_bloom_size_ratio.min(0.25);
+            MemTableFilter::new(
+                (options.memtable_size as f64 * ratio * 8.0) as u32,
+                options.prefix_extractor.clone(),
+                options.memtable_whole_key_filtering,
+            )
+        });
+        let memtable = MemTable::with_rep(
+            options.memtable_size,
+            options.memtable_factory.create(),
+            memtable_filter.clone(),
+        );
+        // Each WAL says where its writes start; ones from before Sequence
+        // records carry on from the last write replayed
+        let mut last_seqno = last_flushed_seqno;
 
-        // 4. Find and replay WAL files >= log_number
-        let wal_ids = find_wal_files(path);
-        let mut memtable = MemTable::new(options.memtable_size);
-        let mut record_count: u64 = 0;
+        // Batches prepared but not yet committed or rolled back
+        let mut prepared = BTreeMap::new();
 
-        for wal_id in wal_ids {
+        let mut flushed_wals = Vec::new();
+        let mut replayed_wals = Vec::new();
+        for (wal_id, wal_path) in wals {
             if wal_id < log_number {
-                continue; // this WAL's data is already in SSTables
+                // this WAL's data is already in SSTables
+                flushed_wals.push(wal_path);
+                continue;
+            }
+            if live_wals
+                .as_ref()
+                .is_some_and(|live| !live.contains(&wal_id))
+            {
+                // Created but never listed: the crash came before its
+                // manifest record, so before any write to it
+                version_set.delete_wal(&wal_path)?;
+                continue;
             }
-            let wal_path = path.join(format!("{:06}.wal", wal_id));
             let reader = WALReader::new(&wal_path)?;
             for record_result in reader.iter() {
                 let record = record_result?;
-                match record.record_type {
-                    RecordType::Put => memtable.put(record.key, record.value),
-                    RecordType::Delete => memtable.delete(record.key),
+                let writes = match record.record_type {
+                    RecordType::Put | RecordType::Delete => vec![record],
+                    RecordType::Batch => record.batch()?,
+                    RecordType::Sequence => {
+                        if let Some(first) = record.sequence_number() {
+                            last_seqno = first.saturating_sub(1);
+                        }
+                        continue;
+                    }
+                    RecordType::Prepare => {
+                        prepared.insert(record.key.clone(), record.batch()?);
+                        continue;
+                    }
+                    RecordType::Commit => {
+                        prepared.remove(&record.key);
+                        record.batch()?
+                    }
+                    RecordType::Rollback => {
+                        prepared.remove(&record.key);
+                        continue;
+                    }
+                };
+                for write in writes {
+                    last_seqno += 1;
+                    memtable.add(last_seqno, write.value_type(), write.key, write.value);
                 }
-                record_count += 1;
             }
+            replayed_wals.push((wal_id, wal_path));
         }
 
-        // 5. Create new WALManager for future writes
-        let wal_manager = WALManager::new(path, options.sync_policy)?;
+        // 5. Create new WALManager for future writes, reusing the WALs a
+        // previous run flushed if it recycles them
+        let mut wal_manager = WALManager::with_dirs(
+            if options.wal_dirs.is_empty() {
+                &wal_dirs
+            } else {
+                &options.wal_dirs
+            },
+            &wal_dirs,
+            WALOptions {
+                sync_policy: options.sync_policy,
+                preallocate_size: options.wal_preallocate_size,
+                recycle_log_file_num: if wal_archive.is_some() {
+                    0
+                } else {
+                    options.recycle_log_file_num
+                },
+                max_wal_size: options.max_wal_size,
+                checksum: options.checksum,
+                block_format: options.wal_block_format,
+                manual_flush: options.manual_wal_flush,
+            },
+        )?;
+        // The rest are archived or deleted, as the flush that covered
+        // them would have done had it not crashed first
+        for wal in flushed_wals {
+            match &wal_archive {
+                Some(archive) => {
+                    archive.archive(&wal)?;
+                }
+                None => {
+                    if !wal_manager.recycle_wal(&wal) {
+                        version_set.delete_wal(&wal)?;
+                    }
+                }
+            }
+        }
+        // The replayed WALs are flushed with the memtable they were
+        // replayed into, and stay live in the manifest until then
+        for (wal_id, _) in &replayed_wals {
+            if !live_wals.as_ref().is_some_and(|live| live.contains(wal_id)) {
+                manifest.record_wal_added(*wal_id)?;
+            }
+        }
+        manifest.record_wal_added(wal_manager.active_wal_id())?;
+        wal_manager.adopt_wals(replayed_wals.into_iter().map(|(_, path)| path).collect());
 
         // 6. Assemble DB
         let memtable_size = options.memtable_size;
-        let block_size = options.block_size;
-        let compaction_style = options.compaction_style;
+        let table_options = TableOptions {
+            block_size: options.block_size,
+            compression: options.compression,
+            checksum: options.checksum,
+            compression_per_level: options.compression_per_level,
+            zstd_max_dict_bytes: options.zstd_max_dict_bytes,
+            bloom_bits_per_key: options.bloom_bits_per_key,
+            partition_filters: options.partition_filters,
+            data_block_hash_index: options.data_block_hash_index,
+            properties_collectors: options.table_properties_collectors,
+            use_direct_io: options.use_direct_io_for_flush_and_compaction,
+            target_file_size_base: options.target_file_size_base,
+            target_file_size_multiplier: options.target_file_size_multiplier,
+            rate_limiter: options.rate_limiter,
+            paranoid_file_checks: options.paranoid_file_checks,
+            sst_partitioner: options.sst_partitioner,
+        };
+        let compaction_strategy: Arc<dyn CompactionStrategy> = match options.compaction_style {
+            CompactionStyle::SizeTiered => Arc::new(SizeTieredStrategy::new(
+                options.level0_file_num_compaction_trigger,
+            )),
+            CompactionStyle::Leveled => Arc::new(
+                LeveledStrategy::new(
+                    options.max_bytes_for_level_base,
+                    options.level_size_multiplier,
+                    options.max_levels,
+                )
+                .with_level0_trigger(options.level0_file_num_compaction_trigger),
+            ),
+            CompactionStyle::Universal => Arc::new(
+                UniversalStrategy::new(
+                    options.level0_file_num_compaction_trigger,
+                    options.max_levels,
+                )
+                .with_size_ratio(options.universal_size_ratio)
+                .with_min_merge_width(options.universal_min_merge_width)
+                .with_max_size_amplification_percent(
+                    options.universal_max_size_amplification_percent,
+                ),
+            ),
+        };
+        let manual_compaction_strategy: Arc<dyn CompactionStrategy> =
+            Arc::new(ManualCompaction(match options.compaction_style {
+                CompactionStyle::SizeTiered => Arc::new(SizeTieredStrategy::new(1)), // threshold=1 to force compaction
+                _ => Arc::clone(&compaction_strategy),
+            }));
+        let min_blob_size = options.enable_blob_files.then_some(options.min_blob_size);
+        // Blob files the manifest doesn't list (from a flush that crashed
+        // before recording its SSTable, or collected but not yet deleted)
+        // are adopted, so garbage collection reclaims them.
+        for file_number in find_blob_files(path) {
+            if !manifest.blob_files().contains_key(&file_number) {
+                manifest.record_blob_file(scan_blob_file_meta(path, file_number)?)?;
+            }
+        }
+        let next_blob_file_number = manifest.blob_files().keys().last().map_or(1, |n| n + 1);
+        let block_cache = Arc::new(Mutex::new(BlockCache::new(options.block_cache_size)));
+        let table_cache = Arc::new(TableCache::new(
+            table_paths,
+            options.max_open_files,
+            Arc::clone(&block_cache),
+            file_access,
+        ));
+        let pending_compaction_bytes = {
+            let current = version_set.current();
+            let v = current.read().unwrap();
+            compaction_strategy.estimated_pending_bytes(&v.levels)
+        };
+        // Every WAL is listed in the manifest before it's written to
+        let manifest = Arc::new(Mutex::new(manifest));
+        wal_manager.set_wal_created_hook({
+            let manifest = Arc::clone(&manifest);
+            Box::new(move |wal_id| manifest.lock().unwrap().record_wal_added(wal_id))
+        });
 
-        Ok(DB {
+        Ok(DBInner {
             path: path.to_path_buf(),
+            wal_dirs,
             memtable_size,
-            block_size,
-            active_memtable: Arc::new(RwLock::new(memtable)),
-            immutable_memtable: None,
+            memtable_filter,
+            memtable_factory: options.memtable_factory,
+            write_buffer_manager: options.write_buffer_manager,
+            table_options,
+            min_blob_size,
+            next_blob_file_number: AtomicU64::new(next_blob_file_number),
+            enable_blob_gc: options.enable_blob_garbage_collection,
+            blob_gc_threshold: options.blob_gc_threshold,
+            active_memtable: Arc::new(RwLock::new(Arc::new(memtable))),
+            immutable_memtables: Arc::new(RwLock::new(Vec::new())),
+            max_immutable_memtables: options.max_write_buffer_number.max(2) - 1,
             version_set,
-            next_sequence: Arc::new(AtomicU64::new(record_count + 1)),
-            manifest: Mutex::new(manifest),
+            delete_scheduler,
+            next_sequence: Arc::new(AtomicU64::new(last_seqno.max(last_flushed_seqno) + 1)),
+            manifest,
             wal_manager: Mutex::new(wal_manager),
-            compaction_style,
-            block_cache: Mutex::new(BlockCache::new(options.block_cache_size)),
+            immutable_wals: Mutex::new(Vec::new()),
+            wal_archive,
+            prepared: Mutex::new(prepared),
+            jobs: Arc::new(JobQueue::new()),
+            auto_compactions: !options.disable_auto_compactions,
+            soft_pending_compaction_bytes_limit: options.soft_pending_compaction_bytes_limit,
+            hard_pending_compaction_bytes_limit: options.hard_pending_compaction_bytes_limit,
+            pending_compaction_bytes: AtomicU64::new(pending_compaction_bytes),
+            flush_lock: Mutex::new(()),
+            compaction_lock: Mutex::new(()),
+            compaction_cancel: CancellationToken::new(),
+            compaction_strategy,
+            manual_compaction_strategy,
+            expiry_policy: options.expiry_policy,
+            compaction_filter: options.compaction_filter,
+            compaction_service: options.compaction_service,
+            listeners: options.listeners,
+            compaction_history: Mutex::new(VecDeque::new()),
+            max_compaction_history: options.max_compaction_history,
+            max_subcompactions: options.max_subcompactions,
+            compaction_throttle: options.compaction_throttle,
+            throttle_rate_limiter: Mutex::new(None),
+            prefix_extractor: options.prefix_extractor,
+            block_cache,
+            table_cache,
+            file_access,
             bytes_written_user: AtomicU64::new(0),
             bytes_written_disk: AtomicU64::new(0),
             bytes_read: AtomicU64::new(0),
+            bloom_checks: AtomicU64::new(0),
+            bloom_useful: AtomicU64::new(0),
             compaction_count: AtomicU64::new(0),
+            dropped_expired: AtomicU64::new(0),
             compaction_bytes: AtomicU64::new(0),
+            level_stats: Mutex::new(vec![LevelStats::default(); options.max_levels]),
+            compaction_reasons: Mutex::new(BTreeMap::new()),
         })
     }
 
@@ -195,18 +1222,41 @@ impl DB {
     ///
     /// WAL-first: write to WAL for durability, then insert into memtable.
     pub fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
-        let _seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        self.put_with_options(key, value, &WriteOptions::default())
+    }
+
+    /// Insert or update a key-value pair, with per-write options.
+    pub fn put_with_options(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        write_options: &WriteOptions,
+    ) -> Result<()> {
+        write_options.validate()?;
+        self.make_room_for_write()?;
 
         // WAL first — guarantees durability before acknowledging
-        {
-            let mut wal = self.wal_manager.lock().unwrap();
-            let record = WALRecord::put(key.to_vec(), value.to_vec());
-            wal.active_writer().append(&record)?;
+        let mut wal = self.wal_manager.lock().unwrap();
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        if write_options.disable_wal {
+            wal.skip(sequence);
+        } else {
+            wal.append(sequence, &WALRecord::put(key.to_vec(), value.to_vec()))?;
+            if write_options.sync {
+                wal.flush(true)?;
+            }
         }
 
-        // Then memtable
-        let mut active = self.active_memtable.write().unwrap();
-        active.put(key.to_vec(), value.to_vec());
+        // Then memtable, still under the WAL lock so writes reach the
+        // memtable in WAL order
+        let active = self.active_memtable();
+        let mut memtable = active.write();
+        let seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        let before = memtable.size();
+        memtable.add(seq, ValueType::Put, key.to_vec(), value.to_vec());
+        self.charge_write_buffer(memtable.size() - before);
+        drop(memtable);
+        drop(wal);
 
         // Stats
         self.bytes_written_user
@@ -220,19 +1270,94 @@ impl DB {
     /// Search order: active memtable → immutable memtable → L0 → L1 → ...
     /// Returns the newest version of the key, or None if not found.
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        // Check active memtable
-        {
-            let memtable = self.active_memtable.read().unwrap();
-            if let Some(value) = memtable.get(key) {
-                return Ok(Some(value.to_vec()));
+        Ok(self.get_pinned(key)?.map(Vec::from))
+    }
+
+    /// Like get(), but returns the value as a pinned slice instead of an
+    /// owned copy.
+    ///
+    /// Memtable hits share the memtable's buffer; SSTable hits share the
+    /// decoded block. Use this for large values to avoid the extra copy.
+    pub fn get_pinned(&self, key: &[u8]) -> Result<Option<Bytes>> {
+        self.get_pinned_with_options(key, &ReadOptions::default())
+    }
+
+    /// Like get(), honoring fill_cache, verify_checksums and snapshot from
+    /// `read_options`. Bounds and prefix options don't apply to point reads.
+    pub fn get_with_options(
+        &self,
+        key: &[u8],
+        read_options: &ReadOptions,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .get_pinned_with_options(key, read_options)?
+            .map(Vec::from))
+    }
+
+    /// Like get_pinned(), with explicit read options.
+    pub fn get_pinned_with_options(
+        &self,
+        key: &[u8],
+        read_options: &ReadOptions,
+    ) -> Result<Option<Bytes>> {
+        let block_read_options = self.block_read_options(read_options);
+
+        if let Some(snapshot) = &read_options.snapshot {
+            let value = snapshot.get_with(key, &block_read_options, Some(&self.table_cache))?;
+            return Ok(value.map(Bytes::from));
+        }
+
+        Ok(match self.get_stored(key, &block_read_options)? {
+            Some(StoredValue::Memtable(value)) => Some(value),
+            Some(StoredValue::Table(value)) => Some(resolve_value(&self.path, value)?),
+            None => None,
+        })
+    }
+
+    /// Like get(), but returns a reader that streams the value instead of
+    /// the value itself.
+    ///
+    /// Values in blob files are read from disk as the reader is consumed,
+    /// so multi-megabyte payloads can be served (e.g. copied to a socket)
+    /// without being allocated whole.
+    pub fn get_reader(&self, key: &[u8]) -> Result<Option<value_reader::ValueReader>> {
+        let block_read_options = self.block_read_options(&ReadOptions::default());
+        Ok(match self.get_stored(key, &block_read_options)? {
+            Some(StoredValue::Table(value)) if is_blob_index(&value) => {
+                let index = BlobIndex::decode(&value)?;
+                Some(value_reader::ValueReader::blob(BlobValueReader::open(
+                    &self.path, &index,
+                )?))
+            }
+            Some(StoredValue::Memtable(value) | StoredValue::Table(value)) => {
+                Some(value_reader::ValueReader::in_memory(value))
             }
+            None => None,
+        })
+    }
+
+    /// The newest stored value for `key`, with blob pointers unresolved.
+    /// None if the key is absent or deleted.
+    fn get_stored(
+        &self,
+        key: &[u8],
+        block_read_options: &BlockReadOptions,
+    ) -> Result<Option<StoredValue>> {
+        // Check active memtable; a tombstone there shadows older data
+        match self.active_memtable().get_version(key, u64::MAX) {
+            Some((ValueType::Put, value)) => return Ok(Some(StoredValue::Memtable(value))),
+            Some((ValueType::Delete, _)) => return Ok(None),
+            None => {}
         }
 
-        // Check immutable memtable
-        if let Some(immutable) = &self.immutable_memtable
-            && let Some(value) = immutable.get(key)
-        {
-            return Ok(Some(value.to_vec()));
+        // Check immutable memtables, newest first
+        let immutables = self.immutable_memtables.read().unwrap().clone();
+        for immutable in immutables.iter().rev() {
+            match immutable.get_version(key, u64::MAX) {
+                Some((ValueType::Put, value)) => return Ok(Some(StoredValue::Memtable(value))),
+                Some((ValueType::Delete, _)) => return Ok(None),
+                None => {}
+            }
         }
 
         // Check SSTables via Version (L0 newest-first, then L1+)
@@ -241,27 +1366,29 @@ impl DB {
 
         // L0: check all SSTables, newest first (overlapping key ranges)
         for meta in version.level(0).iter().rev() {
-            let sst_path = self.path.join(format!("{:06}.sst", meta.id));
-            let sst = SSTable::open(&sst_path)?;
-            if let Some(value) = sst.get(key)? {
+            let Some(sst) = self.open_if_may_contain(meta, key)? else {
+                continue;
+            };
+            if let Some(value) = sst.get_with_options(key, block_read_options)? {
                 // Empty value = tombstone → key is deleted, stop searching
                 if value.is_empty() {
                     return Ok(None);
                 }
-                return Ok(Some(value));
+                return Ok(Some(StoredValue::Table(value)));
             }
         }
 
         // L1+: no overlaps, at most one SSTable contains the key
         for level in 1..version.levels.len() {
             for meta in version.level(level) {
-                let sst_path = self.path.join(format!("{:06}.sst", meta.id));
-                let sst = SSTable::open(&sst_path)?;
-                if let Some(value) = sst.get(key)? {
+                let Some(sst) = self.open_if_may_contain(meta, key)? else {
+                    continue;
+                };
+                if let Some(value) = sst.get_with_options(key, block_read_options)? {
                     if value.is_empty() {
                         return Ok(None);
                     }
-                    return Ok(Some(value));
+                    return Ok(Some(StoredValue::Table(value)));
                 }
             }
         }
@@ -269,22 +1396,55 @@ impl DB {
         Ok(None)
     }
 
+    /// Open the SSTable for `meta` if it may hold `key`: tables whose key
+    /// range excludes it aren't opened at all, and the rest are ruled out
+    /// by their bloom filter before any data block is read.
+    fn open_if_may_contain(&self, meta: &SSTableMeta, key: &[u8]) -> Result<Option<Arc<SSTable>>> {
+        if key < meta.min_key.as_slice() || key > meta.max_key.as_slice() {
+            return Ok(None);
+        }
+        let sst = self.table_cache.get(meta.id, meta.path_id)?;
+        self.bloom_checks.fetch_add(1, Ordering::Relaxed);
+        if !sst.may_contain(key)? {
+            self.bloom_useful.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+        Ok(Some(sst))
+    }
+
     /// Delete a key (writes a tombstone).
     ///
     /// WAL-first: write tombstone to WAL, then to memtable.
     pub fn delete(&self, key: &[u8]) -> Result<()> {
-        let _seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        self.delete_with_options(key, &WriteOptions::default())
+    }
+
+    /// Delete a key, with per-write options.
+    pub fn delete_with_options(&self, key: &[u8], write_options: &WriteOptions) -> Result<()> {
+        write_options.validate()?;
+        self.make_room_for_write()?;
 
         // WAL first
-        {
-            let mut wal = self.wal_manager.lock().unwrap();
-            let record = WALRecord::delete(key.to_vec());
-            wal.active_writer().append(&record)?;
+        let mut wal = self.wal_manager.lock().unwrap();
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        if write_options.disable_wal {
+            wal.skip(sequence);
+        } else {
+            wal.append(sequence, &WALRecord::delete(key.to_vec()))?;
+            if write_options.sync {
+                wal.flush(true)?;
+            }
         }
 
-        // Then memtable
-        let mut active = self.active_memtable.write().unwrap();
-        active.delete(key.to_vec());
+        // Then memtable, under the WAL lock as in put()
+        let active = self.active_memtable();
+        let mut memtable = active.write();
+        let seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        let before = memtable.size();
+        memtable.add(seq, ValueType::Delete, key.to_vec(), Vec::new());
+        self.charge_write_buffer(memtable.size() - before);
+        drop(memtable);
+        drop(wal);
 
         // Stats
         self.bytes_written_user
@@ -293,173 +1453,969 @@ impl DB {
         Ok(())
     }
 
+    /// Apply `batch`'s puts and deletes in order, atomically: they're
+    /// logged as a single WAL record, so recovery replays all of them or
+    /// none, and each takes the next sequence number.
+    pub fn write(&self, batch: batch::WriteBatch) -> Result<()> {
+        self.write_with_options(batch, &WriteOptions::default())
+    }
+
+    /// Apply `batch` as in write(), with per-write options.
+    pub fn write_with_options(
+        &self,
+        batch: batch::WriteBatch,
+        write_options: &WriteOptions,
+    ) -> Result<()> {
+        write_options.validate()?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.make_room_for_write()?;
+
+        let writes = batch.into_writes();
+        let mut wal = self.wal_manager.lock().unwrap();
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        if write_options.disable_wal {
+            wal.skip(sequence);
+        } else {
+            wal.append(sequence, &WALRecord::write_batch(&writes))?;
+            if write_options.sync {
+                wal.flush(true)?;
+            }
+        }
+
+        // Then memtable, under the WAL lock as in put()
+        self.apply_writes(writes);
+        drop(wal);
+        Ok(())
+    }
+
+    /// Prepare `batch` as transaction `xid`, the first phase of a
+    /// two-phase commit: the batch is logged and synced, but not applied
+    /// or visible to reads until commit_prepared(`xid`). Once this
+    /// returns the batch survives a crash, so the participant can vote
+    /// yes; on reopen, prepared_transactions() lists the batches still
+    /// awaiting the coordinator's decision.
+    ///
+    /// Fails with InvalidInput if `xid` is already prepared.
+    pub fn write_prepared(&self, xid: &[u8], batch: batch::WriteBatch) -> Result<()> {
+        let mut wal = self.wal_manager.lock().unwrap();
+        let mut prepared = self.prepared.lock().unwrap();
+        if prepared.contains_key(xid) {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "transaction is already prepared",
+            )));
+        }
+        let writes = batch.into_writes();
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        wal.append(sequence, &WALRecord::prepare(xid.to_vec(), &writes))?;
+        wal.flush(true)?;
+        prepared.insert(xid.to_vec(), writes);
+        Ok(())
+    }
+
+    /// Commit the batch prepared as `xid`: its writes are logged, synced
+    /// and applied in order, each taking the next sequence number.
+    ///
+    /// Fails with Error::NotFound if no batch is prepared as `xid`.
+    pub fn commit_prepared(&self, xid: &[u8]) -> Result<()> {
+        self.make_room_for_write()?;
+
+        let mut wal = self.wal_manager.lock().unwrap();
+        let Some(writes) = self.prepared.lock().unwrap().remove(xid) else {
+            return Err(Error::NotFound);
+        };
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        let logged = wal
+            .append(sequence, &WALRecord::commit(xid.to_vec(), &writes))
+            .and_then(|()| wal.flush(true));
+        if let Err(e) = logged {
+            self.prepared.lock().unwrap().insert(xid.to_vec(), writes);
+            return Err(e);
+        }
+
+        // Then memtable, under the WAL lock as in put()
+        self.apply_writes(writes);
+        drop(wal);
+        Ok(())
+    }
+
+    /// Apply logged `writes` to the active memtable in order, each taking
+    /// the next sequence number. Call with the WAL locked.
+    fn apply_writes(&self, writes: Vec<WALRecord>) {
+        let active = self.active_memtable();
+        let mut memtable = active.write();
+        let before = memtable.size();
+        let mut bytes = 0;
+        for write in writes {
+            bytes += write.key.len() + write.value.len();
+            let seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+            memtable.add(seq, write.value_type(), write.key, write.value);
+        }
+        self.charge_write_buffer(memtable.size() - before);
+        drop(memtable);
+
+        self.bytes_written_user
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Discard the batch prepared as `xid`, logging and syncing the
+    /// decision.
+    ///
+    /// Fails with Error::NotFound if no batch is prepared as `xid`.
+    pub fn rollback_prepared(&self, xid: &[u8]) -> Result<()> {
+        let mut wal = self.wal_manager.lock().unwrap();
+        let mut prepared = self.prepared.lock().unwrap();
+        if !prepared.contains_key(xid) {
+            return Err(Error::NotFound);
+        }
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        wal.append(sequence, &WALRecord::rollback(xid.to_vec()))?;
+        wal.flush(true)?;
+        prepared.remove(xid);
+        Ok(())
+    }
+
+    /// Ids of the transactions prepared but not yet committed or rolled
+    /// back, including those recovered from the WAL at open, in id order.
+    pub fn prepared_transactions(&self) -> Vec<Vec<u8>> {
+        self.prepared.lock().unwrap().keys().cloned().collect()
+    }
+
     /// Iterate over a range of keys [start, end).
     ///
     /// Merges data from active memtable + immutable memtable + all SSTable
     /// levels. Tombstones are filtered and range bounds are enforced.
     pub fn scan(&self, start: &[u8], end: &[u8]) -> Result<snapshot::Scanner> {
-        // Capture memtable entries under read lock
-        let memtable_entries = {
-            let mt = self.active_memtable.read().unwrap();
-            let mut entries = Vec::new();
-            let mut iter = mt.iter();
-            while iter.is_valid() {
-                entries.push((iter.key().to_vec(), iter.value().to_vec()));
-                iter.next()?;
-            }
-            entries
+        let (sequence, memtables) = self.memtables_at_sequence();
+        let version = self.version_set.current();
+
+        let mut scanner = snapshot::Scanner::build(
+            memtable_source(&memtables, Some(start), Some(end), sequence)?,
+            &version,
+            &self.path,
+            self.table_cache.paths(),
+            Some(&self.table_cache),
+            start,
+            end,
+        )?;
+        scanner.pin(self.pin_version(version), sequence);
+        Ok(scanner)
+    }
+
+    /// Iterate over the whole database in key order.
+    ///
+    /// Builds a MergeIterator over the active memtable, the immutable
+    /// memtable (if any), and every live SSTable in the current Version.
+    /// Tombstones and shadowed older versions are hidden.
+    pub fn iter(&self) -> Result<iterator::DBIterator> {
+        self.iter_with_options(&ReadOptions::default())
+    }
+
+    /// Like iter(), restricted to the bounds in `read_options`.
+    ///
+    /// With a snapshot set, the iterator reads the snapshot's memtables
+    /// and Version as of its sequence number, and refresh() leaves it
+    /// pinned there.
+    pub fn iter_with_options(&self, read_options: &ReadOptions) -> Result<iterator::DBIterator> {
+        let mut iter = match &read_options.snapshot {
+            Some(snapshot) => {
+                let mut iter = snapshot.iter_with(
+                    read_options,
+                    &self.block_read_options(read_options),
+                    Some(&self.table_cache),
+                )?;
+                iter.pin(
+                    self.pin_version(Arc::clone(&snapshot.version)),
+                    snapshot.seq,
+                );
+                iter
+            }
+            None => {
+                let read_sources = self.read_sources(read_options);
+                let (sources, version, sequence) = read_sources.collect(
+                    read_options.iterate_lower_bound.as_deref(),
+                    read_options.iterate_upper_bound.as_deref(),
+                )?;
+                let mut iter = iterator::DBIterator::new(sources, read_options)?;
+                iter.pin(version, sequence);
+                iter.resolve_blobs(self.path.clone(), read_sources.num_memtable_sources())?;
+                iter.set_read_sources(read_sources);
+                iter
+            }
         };
+        if read_options.prefix_same_as_start {
+            iter.set_prefix_extractor(self.prefix_extractor.clone());
+        }
+        Ok(iter)
+    }
 
-        let version = self.version_set.current();
+    /// Resume a scan from the position saved in `cursor`.
+    ///
+    /// Equivalent to iter_with_options() with the lower bound raised to
+    /// the cursor's key, so SSTables entirely before it are skipped.
+    pub fn iter_from_cursor(
+        &self,
+        cursor: &cursor::Cursor,
+        read_options: &ReadOptions,
+    ) -> Result<iterator::DBIterator> {
+        let key = cursor.key();
+        let mut read_options = read_options.clone();
+        if read_options
+            .iterate_lower_bound
+            .as_deref()
+            .is_none_or(|lower| key > lower)
+        {
+            read_options.iterate_lower_bound = Some(key.to_vec());
+        }
+        self.iter_with_options(&read_options)
+    }
+
+    /// Iterate over every stored version of every key, for debugging and
+    /// external tooling.
+    ///
+    /// Yields (InternalKey, value) pairs including tombstones and versions
+    /// shadowed by newer writes, streamed from the data sources as it's
+    /// advanced. See RawIterator for where `sequence` comes from, and
+    /// check its status() once it ends.
+    pub fn raw_iter(&self) -> Result<iterator::RawIterator> {
+        iterator::RawIterator::new(&self.read_sources(&ReadOptions::default()))
+    }
+
+    /// The immutable memtables and then the active one, oldest first,
+    /// with the sequence number to read them as of, taken under the
+    /// active memtable's lock as in ReadSources::collect().
+    ///
+    /// Taken before the version a reader pairs them with: a flush installs
+    /// its SSTables before it retires the immutable memtables, so every
+    /// entry is in one or the other.
+    fn memtables_at_sequence(&self) -> (u64, Vec<Arc<MemTable>>) {
+        let (active, sequence) = active_at_sequence(&self.active_memtable, &self.next_sequence);
+        let mut memtables = self.immutable_memtables.read().unwrap().clone();
+        memtables.push(active);
+        (sequence, memtables)
+    }
+
+    /// Keep the SSTables of `version` from being deleted while the
+    /// returned pin is alive.
+    fn pin_version(&self, version: Arc<RwLock<Version>>) -> PinnedVersion {
+        PinnedVersion::new(Arc::clone(&self.version_set), version, self.path.clone())
+    }
+
+    /// Block read options for `read_options` on this DB's file access path.
+    fn block_read_options(&self, read_options: &ReadOptions) -> BlockReadOptions {
+        BlockReadOptions {
+            file_access: self.file_access,
+            ..read_options.block_read_options()
+        }
+    }
+
+    /// Handles to every data source, for building (and later refreshing)
+    /// iterators.
+    fn read_sources(&self, read_options: &ReadOptions) -> iterator::ReadSources {
+        iterator::ReadSources {
+            active_memtable: Arc::clone(&self.active_memtable),
+            immutable_memtables: Arc::clone(&self.immutable_memtables),
+            version_set: Arc::clone(&self.version_set),
+            next_sequence: Arc::clone(&self.next_sequence),
+            path: self.path.clone(),
+            table_cache: Arc::clone(&self.table_cache),
+            block_read_options: self.block_read_options(read_options),
+        }
+    }
 
-        snapshot::Scanner::build(&memtable_entries, &version, &self.path, start, end)
+    /// Forward iterator that picks up keys written after it was created.
+    ///
+    /// Behaves like iter_with_options(), except that next() past the end
+    /// re-checks the DB for keys beyond the last one yielded instead of
+    /// staying invalid.
+    pub fn tailing_iter(&self, read_options: &ReadOptions) -> Result<tailing::TailingIterator<'_>> {
+        tailing::TailingIterator::new(self, read_options)
+    }
+
+    /// Writes logged from sequence number `sequence` on, oldest first,
+    /// read from the archived WALs (see Options::wal_ttl) and the DB's
+    /// own. Each put() or delete() is one update, as is each write of a
+    /// batch committed with commit_prepared().
+    ///
+    /// Only writes still in a WAL can be returned: without an archive,
+    /// that's the ones not yet flushed. Asking for older ones yields
+    /// Error::NotFound rather than skipping them. Writes made with
+    /// WriteOptions::disable_wal were never logged and have no update.
+    pub fn get_updates_since(&self, sequence: u64) -> Result<updates::UpdateIterator> {
+        // Listed under the WAL lock so no WAL is renamed into the archive
+        // between the two listings and missed
+        let wal = self.wal_manager.lock().unwrap();
+        let mut wals = match &self.wal_archive {
+            Some(archive) => archive.wal_files()?,
+            None => Vec::new(),
+        };
+        wals.extend(find_wal_files(&self.wal_dirs));
+        drop(wal);
+        wals.sort_unstable();
+        Ok(updates::UpdateIterator::new(
+            wals.into_iter().map(|(_, path)| path).collect(),
+            self.wal_archive.as_ref().map(|a| a.dir().to_path_buf()),
+            sequence,
+        ))
+    }
+
+    /// Iterate over every key starting with `prefix`.
+    ///
+    /// Equivalent to iter_with_options() bounded to
+    /// [prefix, prefix_successor(prefix)): SSTables whose key range can't
+    /// contain the prefix are never opened, and iteration ends at the
+    /// first key past the prefix.
+    pub fn prefix_iter(&self, prefix: &[u8]) -> Result<iterator::DBIterator> {
+        self.iter_with_options(&ReadOptions {
+            iterate_lower_bound: Some(prefix.to_vec()),
+            iterate_upper_bound: prefix::prefix_successor(prefix),
+            ..ReadOptions::default()
+        })
+    }
+
+    /// Approximate number of keys in [start, end), without a full scan.
+    ///
+    /// Sums live memtable entries in the range with each overlapping
+    /// SSTable's estimate (see SSTable::estimate_keys_in_range). Keys
+    /// with versions in several sources are counted once per source and
+    /// deletions aren't subtracted, so the result tends to overcount on
+    /// update- or delete-heavy data.
+    pub fn estimate_num_keys_in_range(&self, start: &[u8], end: &[u8]) -> Result<u64> {
+        let mut estimate = self.active_memtable().count_in_range(start, end) as u64;
+        for immutable in self.immutable_memtables.read().unwrap().iter() {
+            estimate += immutable.count_in_range(start, end) as u64;
+        }
+
+        let current = self.version_set.current();
+        let version = current.read().unwrap();
+        for meta in version.levels.iter().flatten() {
+            if meta.max_key.as_slice() < start || meta.min_key.as_slice() >= end {
+                continue;
+            }
+            let sst = self.table_cache.get(meta.id, meta.path_id)?;
+            estimate += sst.estimate_keys_in_range(start, end)?;
+        }
+
+        Ok(estimate)
     }
 
     /// Create a consistent snapshot of the database.
     ///
-    /// Captures a point-in-time copy of the memtable entries and a reference
-    /// to the current Version (SSTable set). Subsequent writes and compaction
-    /// won't affect reads through this snapshot.
+    /// Takes the next sequence number, the memtables and the current
+    /// Version (SSTable set), copying nothing: reads through the snapshot
+    /// see only the versions written before that sequence number, so
+    /// subsequent writes and compaction won't affect them. The memtables
+    /// stay in memory while the snapshot lives, even once flushed.
     pub fn snapshot(&self) -> snapshot::Snapshot {
-        let seq = self.next_sequence.load(Ordering::SeqCst);
+        let (seq, memtables) = self.memtables_at_sequence();
         let version = self.version_set.current();
 
-        // Capture memtable entries under read lock
-        let memtable_entries = {
-            let mt = self.active_memtable.read().unwrap();
-            let mut entries = Vec::new();
-            let mut iter = mt.iter();
-            while iter.is_valid() {
-                entries.push((iter.key().to_vec(), iter.value().to_vec()));
-                iter.next().unwrap();
-            }
-            entries
-        };
-
         snapshot::Snapshot {
             seq,
             version,
             path: self.path.clone(),
-            memtable_entries,
+            table_paths: self.table_cache.paths().clone(),
+            memtables,
+            table_cache: Some(Arc::clone(&self.table_cache)),
         }
     }
 
+    /// Write out the active WAL's buffered records now, and fsync it if
+    /// `sync`, whatever the SyncPolicy. With a relaxed policy this bounds
+    /// what a crash can lose at chosen points, e.g. the end of a batch.
+    /// Writes are only left buffered with Options::manual_wal_flush:
+    /// flush_wal(false) then hands them to the OS, so they survive the
+    /// process crashing.
+    pub fn flush_wal(&self, sync: bool) -> Result<()> {
+        self.wal_manager.lock().unwrap().flush(sync)
+    }
+
+    /// Sync the WAL by `sync_policy` from now on, in place of
+    /// Options::sync_policy, e.g. to sync every write through a critical
+    /// period. Takes effect between two writes; the ones already logged
+    /// are synced first. Lasts until changed again or the DB is closed.
+    pub fn set_sync_policy(&self, sync_policy: SyncPolicy) -> Result<()> {
+        self.wal_manager
+            .lock()
+            .unwrap()
+            .set_sync_policy(sync_policy)
+    }
+
+    /// Hand every write logged from now on to `hook` as soon as the WAL
+    /// holding it is fsync'd, with the sequence number get_updates_since()
+    /// would give it: a replication agent can ship the log to followers
+    /// as it's made durable, without polling the WAL files. Writes with
+    /// WriteOptions::disable_wal aren't logged, so aren't delivered. The
+    /// hook must not write to the DB (see
+    /// WALManager::set_wal_synced_hook). None removes it.
+    pub fn set_wal_synced_hook(&self, hook: Option<WALSyncedHook>) {
+        self.wal_manager.lock().unwrap().set_wal_synced_hook(hook);
+    }
+
     /// Force flush the active memtable to disk as an SSTable.
     ///
-    /// Crash-safe ordering:
-    /// 1. Swap active memtable → frozen, create new empty active
-    /// 2. Rotate WAL (new WAL for future writes)
-    /// 3. Build SSTable from frozen memtable
-    /// 4. Update manifest: record_flush + record_log_number
-    /// 5. Install new Version in VersionSet
-    /// 6. Delete old WAL (safe: SSTable is fsync'd, manifest updated)
+    /// Waits for a flush already under way, then freezes the active
+    /// memtable and flushes it (see flush_immutable). Everything written
+    /// before the call is in SSTables when it returns.
     pub fn flush(&self) -> Result<()> {
-        // 1. Freeze: swap active memtable with a fresh empty one
-        let frozen = {
-            let mut active = self.active_memtable.write().unwrap();
-            if active.is_empty() {
+        loop {
+            self.flush_immutable()?;
+            if self.active_memtable().is_empty() {
                 return Ok(()); // nothing to flush
             }
-            std::mem::replace(&mut *active, MemTable::new(self.memtable_size))
-        };
+            // Fails only if a writer froze a full memtable first; flush
+            // that one, then try again
+            if self.freeze_memtable(false)? {
+                return self.flush_immutable();
+            }
+        }
+    }
 
-        // 2. Rotate WAL — old WAL is now frozen alongside the memtable
-        let (old_wal_path, new_wal_id) = {
-            let mut wal = self.wal_manager.lock().unwrap();
-            let old_path = wal.rotate()?;
-            let new_id = wal.active_wal_id();
-            (old_path, new_id)
+    /// Make sure the active memtable has room before a write.
+    ///
+    /// A full memtable is frozen and its flush queued for a background
+    /// worker. If max_write_buffer_number - 1 are already waiting to be
+    /// flushed, the write stalls until they are. Writes are also held back while compaction
+    /// is behind (see throttle_writes).
+    fn make_room_for_write(&self) -> Result<()> {
+        self.throttle_writes()?;
+        if let Some(manager) = &self.write_buffer_manager
+            && manager.should_flush()
+        {
+            manager.flush_largest();
+        }
+        if !self.active_memtable().is_full() {
+            return Ok(());
+        }
+        self.jobs.wait_until(|| {
+            self.immutable_memtables.read().unwrap().len() < self.max_immutable_memtables
+        })?;
+        if self.freeze_memtable(true)? {
+            self.jobs.schedule(Job::Flush);
+        }
+        Ok(())
+    }
+
+    /// Delay a write by 1ms past the soft pending compaction bytes limit,
+    /// and block it past the hard limit until background compactions
+    /// bring the estimate back under.
+    fn throttle_writes(&self) -> Result<()> {
+        if !self.auto_compactions {
+            return Ok(());
+        }
+        let over = |limit: u64| {
+            limit > 0 && self.pending_compaction_bytes.load(Ordering::Relaxed) >= limit
         };
+        if over(self.hard_pending_compaction_bytes_limit) {
+            self.schedule_compaction();
+            self.jobs
+                .wait_until(|| !over(self.hard_pending_compaction_bytes_limit))?;
+        } else if over(self.soft_pending_compaction_bytes_limit) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+
+    /// Re-estimate pending compaction bytes after a version change.
+    fn update_pending_compaction_bytes(&self) {
+        let current = self.version_set.current();
+        let v = current.read().unwrap();
+        let pending = self.compaction_strategy.estimated_pending_bytes(&v.levels);
+        self.pending_compaction_bytes
+            .store(pending, Ordering::Relaxed);
+    }
 
-        // 3. Build SSTable from frozen memtable
-        let sst_id = self.version_set.next_sst_id();
-        let sst_path = self.path.join(format!("{:06}.sst", sst_id));
-        let mut builder = SSTableBuilder::new(&sst_path, sst_id, self.block_size)?;
+    /// Swap the active memtable for a fresh empty one and queue it as the
+    /// newest immutable memtable, rotating the WAL so its writes stay in a
+    /// WAL of their own.
+    ///
+    /// Both happen under the WAL lock, so every write lands in the old
+    /// memtable and WAL or in the new ones. Readers aren't held up: the
+    /// memtables are swapped as Arcs, and one mid-read of the old memtable
+    /// keeps it. The old memtable is marked read-only by the FlushJob that
+    /// writes it out, not here, where that would wait for such readers.
+    ///
+    /// Returns false, changing nothing, if max_immutable_memtables are
+    /// already waiting, the active memtable is empty, or `only_if_full` is
+    /// set and it isn't full.
+    fn freeze_memtable(&self, only_if_full: bool) -> Result<bool> {
+        let mut wal = self.wal_manager.lock().unwrap();
+        let active = self.active_memtable();
+        if self.immutable_memtables.read().unwrap().len() >= self.max_immutable_memtables
+            || active.is_empty()
+            || (only_if_full && !active.is_full())
+        {
+            return Ok(false);
+        }
 
-        let mut iter = frozen.iter();
-        while iter.is_valid() {
-            builder.add(iter.key(), iter.value())?;
-            iter.next()?;
+        let mut old_wal_paths = wal.take_size_rotated();
+        old_wal_paths.push(wal.rotate()?);
+        // The old WALs go once the memtable is flushed; batches still
+        // prepared are logged again so recovery keeps finding them
+        let sequence = self.next_sequence.load(Ordering::SeqCst);
+        for (xid, writes) in self.prepared.lock().unwrap().iter() {
+            wal.append(sequence, &WALRecord::prepare(xid.clone(), writes))?;
+        }
+        self.immutable_wals
+            .lock()
+            .unwrap()
+            .push((old_wal_paths, wal.active_wal_id()));
+        if let Some(manager) = &self.write_buffer_manager {
+            manager.schedule_free(active.size());
         }
-        let meta = builder.finish()?;
+        // Queued before the new memtable is swapped in, so a reader that
+        // finds that one finds this one among the immutable memtables
+        self.immutable_memtables
+            .write()
+            .unwrap()
+            .push(Arc::clone(&active));
+        *self.active_memtable.write().unwrap() = Arc::new(self.new_memtable());
+        Ok(true)
+    }
+
+    /// The memtable taking writes right now.
+    fn active_memtable(&self) -> Arc<MemTable> {
+        Arc::clone(&self.active_memtable.read().unwrap())
+    }
+
+    /// Charge the write buffer manager, if any, for `bytes` just written
+    /// to the active memtable.
+    fn charge_write_buffer(&self, bytes: usize) {
+        if let Some(manager) = &self.write_buffer_manager {
+            manager.reserve(bytes);
+        }
+    }
+
+    /// An empty memtable to become the active one.
+    fn new_memtable(&self) -> MemTable {
+        MemTable::with_rep(
+            self.memtable_size,
+            self.memtable_factory.create(),
+            self.memtable_filter.clone(),
+        )
+    }
+
+    /// Write every immutable memtable waiting to be flushed to L0.
+    ///
+    /// Crash-safe ordering:
+    /// 1. Build SSTables from the frozen memtables, merged (large values
+    ///    to a blob file)
+    /// 2. Update manifest: one record_atomic_flush for all of them
+    /// 3. Install new Version in VersionSet
+    /// 4. Retire the immutable memtables, waking stalled writers
+    /// 5. Delete old WALs (safe: SSTables are fsync'd, manifest updated)
+    fn flush_immutable(&self) -> Result<()> {
+        let _flushing = self.flush_lock.lock().unwrap();
+        let frozen = self.immutable_memtables.read().unwrap().clone();
+        if frozen.is_empty() {
+            return Ok(());
+        }
+        let old_wals = self.immutable_wals.lock().unwrap()[..frozen.len()].to_vec();
+        let new_wal_id = old_wals
+            .last()
+            .expect("immutable memtable without its WAL")
+            .1;
+
+        // 1. Build SSTables from the frozen memtables
+        let FlushOutput {
+            tables: metas,
+            blob_file,
+        } = FlushJob::new(&self.path, &self.version_set, &self.table_options, &frozen)
+            .with_blob_files(self.min_blob_size, &self.next_blob_file_number)
+            .run()?;
 
         // Stats: track bytes written to disk
+        let blob_file_size = blob_file.as_ref().map_or(0, |blob| blob.file_size);
+        let sst_size: u64 = metas.iter().map(|meta| meta.file_size).sum();
         self.bytes_written_disk
-            .fetch_add(meta.file_size, Ordering::Relaxed);
-
-        // 4. Update manifest: record the new SSTable, then the new log_number
+            .fetch_add(sst_size + blob_file_size, Ordering::Relaxed);
         {
-            let mut manifest = self.manifest.lock().unwrap();
-            manifest.record_flush(meta.clone())?;
-            manifest.record_log_number(new_wal_id)?;
+            let mut level_stats = self.level_stats.lock().unwrap();
+            level_stats[0].bytes_read_upper +=
+                frozen.iter().map(|mt| mt.size() as u64).sum::<u64>();
+            level_stats[0].bytes_written += sst_size;
         }
 
-        // 5. Install new Version with the SSTable added to L0
+        // 2. Update manifest: the blob file, the new SSTables pointing
+        // into it and the new log_number, in one record so recovery sees
+        // all of the memtables flushed or none. The lock is held through
+        // the install so a concurrent compaction installs on top of this
+        // version, not beside it.
         {
+            let mut manifest = self.manifest.lock().unwrap();
+            manifest.record_atomic_flush(
+                blob_file.into_iter().collect(),
+                metas.clone(),
+                new_wal_id,
+            )?;
+
+            // 3. Install new Version with the SSTables added to L0
             let current = self.version_set.current();
             let old_version = current.read().unwrap();
             let mut new_levels = old_version.levels.clone();
-            new_levels[0].push(meta);
+            new_levels[0].extend(metas);
             drop(old_version);
             self.version_set.install(Version { levels: new_levels });
         }
+        self.update_pending_compaction_bytes();
+
+        // 4. Readers find the data in L0 now. Memtables frozen since the
+        // snapshot stay queued for the next flush.
+        self.immutable_memtables
+            .write()
+            .unwrap()
+            .drain(..frozen.len());
+        if let Some(manager) = &self.write_buffer_manager {
+            manager.free(frozen.iter().map(|mt| mt.size()).sum());
+        }
+        self.immutable_wals.lock().unwrap().drain(..frozen.len());
+        self.jobs.notify();
 
-        // 6. Delete old WAL — safe because SSTable is fsync'd and manifest updated
-        let _ = WALManager::delete_wal(&old_wal_path);
+        // Garbage-collected blob files may have been waiting on the old version
+        self.version_set.delete_obsolete_files(&self.path);
 
+        // 5. Delete old WALs, or keep them to be recycled or archived —
+        // safe because SSTables are fsync'd and manifest updated
+        for old_wal_path in old_wals.iter().flat_map(|(paths, _)| paths) {
+            match &self.wal_archive {
+                Some(archive) => {
+                    archive.archive(old_wal_path)?;
+                }
+                None => {
+                    if !self.wal_manager.lock().unwrap().recycle_wal(old_wal_path) {
+                        self.version_set.delete_wal(old_wal_path)?;
+                    }
+                }
+            }
+        }
+        self.purge_wal_archive()?;
+
+        self.schedule_compaction();
         Ok(())
     }
+    /// Delete the archived WALs past Options::wal_ttl or beyond
+    /// Options::wal_size_limit_bytes.
+    fn purge_wal_archive(&self) -> Result<()> {
+        if let Some(archive) = &self.wal_archive {
+            for expired in archive.expired(SystemTime::now())? {
+                self.version_set.delete_file(&expired);
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue a compaction round, unless auto compactions are disabled.
+    fn schedule_compaction(&self) {
+        if self.auto_compactions {
+            self.jobs.schedule(Job::Compaction);
+        }
+    }
+
+    /// Run a job a background worker took from the queue.
+    fn run_job(&self, job: Job) -> Result<()> {
+        match job {
+            Job::Flush => self.flush_immutable(),
+            Job::Compaction => {
+                // One round per job, so a queued flush gets the next worker
+                match self.compact_once(&*self.compaction_strategy) {
+                    Ok(true) => self.jobs.schedule(Job::Compaction),
+                    Ok(false) => {}
+                    // Paused or closing: nothing was installed, so the
+                    // inputs are still there to compact on resume
+                    Err(_) if self.compaction_cancel.is_cancelled() => {}
+                    Err(e) => return Err(e),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Stop compacting until continue_background_work(): a running
+    /// compaction is cancelled, deleting what it wrote, and this returns
+    /// once it has stopped. compact_range() fails while paused.
+    ///
+    /// Flushes keep running, as writers wait on them. Writes held back
+    /// past hard_pending_compaction_bytes_limit wait for the resume.
+    pub fn pause_background_work(&self) {
+        self.compaction_cancel.cancel();
+        drop(self.compaction_lock.lock().unwrap());
+    }
+
+    /// Resume compacting after pause_background_work().
+    pub fn continue_background_work(&self) {
+        self.compaction_cancel.reset();
+        self.schedule_compaction();
+    }
+
+    /// Block until every obsolete file waiting in the trash has been
+    /// deleted (see Options::delete_rate_bytes_per_sec).
+    pub fn wait_for_empty_trash(&self) {
+        self.delete_scheduler.wait_for_empty_trash();
+    }
+
+    /// The most recent compactions, oldest first: up to
+    /// Options::max_compaction_history of them since the DB was opened.
+    pub fn compaction_history(&self) -> Vec<CompactionJobInfo> {
+        self.compaction_history
+            .lock()
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
 
     /// Manually trigger compaction.
     ///
     /// With `(None, None)`: runs compaction repeatedly until no more work.
     /// With `(Some(start), Some(end))`: compacts SSTables overlapping that range.
     pub fn compact_range(&self, _start: Option<&[u8]>, _end: Option<&[u8]>) -> Result<()> {
-        use crate::compaction::leveled::LeveledStrategy;
-        use crate::compaction::scheduler::run_compaction;
-        use crate::compaction::size_tiered::SizeTieredStrategy;
-
-        // Build strategy matching the DB's configured style
-        let strategy: Box<dyn crate::compaction::CompactionStrategy> = match self.compaction_style {
-            CompactionStyle::SizeTiered => Box::new(SizeTieredStrategy::new(1)), // threshold=1 to force compaction
-            CompactionStyle::Leveled => Box::new(LeveledStrategy::new(
-                10 * 1024 * 1024, // 10MB base
-                10,               // 10x multiplier
-                7,                // max levels
-            )),
+        // Run compaction in a loop until nothing more to do
+        while self.compact_once(&*self.manual_compaction_strategy)? {}
+
+        if self.enable_blob_gc {
+            self.garbage_collect_blobs()?;
+        }
+
+        Ok(())
+    }
+
+    /// Run one round of compaction picked by `strategy`. Returns whether
+    /// there was one.
+    fn compact_once(&self, strategy: &dyn CompactionStrategy) -> Result<bool> {
+        let _compacting = self.compaction_lock.lock().unwrap();
+        self.compaction_cancel.check()?;
+        // Snapshot file sizes before compaction to measure bytes processed
+        let size_before = self.total_sst_size();
+        let live_before = self.live_sst_ids();
+        let limits = self
+            .compaction_throttle
+            .as_ref()
+            .map(|throttle| throttle.limits(SystemTime::now()))
+            .unwrap_or_default();
+        let table_options = match limits.rate_bytes_per_sec {
+            None => Cow::Borrowed(&self.table_options),
+            Some(rate) => Cow::Owned(TableOptions {
+                rate_limiter: (rate > 0).then(|| self.throttle_rate_limiter(rate)),
+                ..self.table_options.clone()
+            }),
+        };
+        let Some(info) = run_compaction(
+            &self.version_set,
+            strategy,
+            &self.path,
+            &table_options,
+            &CompactionJobOptions {
+                manifest: Some(&*self.manifest),
+                expiry: self.expiry_policy.as_deref(),
+                filter: self.compaction_filter.as_deref(),
+                max_subcompactions: limits.max_subcompactions.unwrap_or(self.max_subcompactions),
+                cancel: Some(&self.compaction_cancel),
+                service: self.compaction_service.as_deref(),
+            },
+        )?
+        else {
+            return Ok(false);
         };
 
-        // Run compaction in a loop until nothing more to do
-        loop {
-            // Snapshot file sizes before compaction to measure bytes processed
-            let size_before = self.total_sst_size();
-            match run_compaction(&self.version_set, &*strategy, &self.path, self.block_size)? {
-                true => {
-                    self.compaction_count.fetch_add(1, Ordering::Relaxed);
-                    let size_after = self.total_sst_size();
-                    // Track bytes involved (approximate: max of before/after)
-                    let bytes = size_before.max(size_after);
-                    self.compaction_bytes.fetch_add(bytes, Ordering::Relaxed);
-                    continue;
+        // Close the inputs compaction replaced
+        for id in live_before.difference(&self.live_sst_ids()) {
+            self.table_cache.evict(*id);
+        }
+        self.compaction_count.fetch_add(1, Ordering::Relaxed);
+        let size_after = self.total_sst_size();
+        // Track bytes involved (approximate: max of before/after)
+        let bytes = size_before.max(size_after);
+        self.compaction_bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.dropped_expired
+            .fetch_add(info.keys_dropped.expired, Ordering::Relaxed);
+        {
+            let mut level_stats = self.level_stats.lock().unwrap();
+            let level = &mut level_stats[info.output_level as usize];
+            level.compaction_count += 1;
+            level.compaction_time += info.duration;
+            level.bytes_read_upper += info.bytes_read_upper;
+            level.bytes_read_level += info.bytes_read_output_level;
+            level.bytes_written += info.bytes_written;
+        }
+        {
+            let mut compaction_reasons = self.compaction_reasons.lock().unwrap();
+            let reason = compaction_reasons.entry(info.reason).or_default();
+            reason.compaction_count += 1;
+            reason.compaction_time += info.duration;
+            reason.bytes_read += info.bytes_read_upper + info.bytes_read_output_level;
+            reason.bytes_written += info.bytes_written;
+        }
+        // Wake writers stalled on the old estimate
+        self.update_pending_compaction_bytes();
+        self.jobs.notify();
+
+        for listener in &self.listeners {
+            listener.on_compaction_completed(&info);
+        }
+        let mut history = self.compaction_history.lock().unwrap();
+        if history.len() >= self.max_compaction_history {
+            history.pop_front();
+        }
+        if self.max_compaction_history > 0 {
+            history.push_back(info);
+        }
+        Ok(true)
+    }
+
+    /// The limiter for compactions the throttle gives `rate`, made anew
+    /// when the rate changes.
+    fn throttle_rate_limiter(&self, rate: u64) -> Arc<RateLimiter> {
+        let mut limiter = self.throttle_rate_limiter.lock().unwrap();
+        match &*limiter {
+            Some(current) if current.bytes_per_second() == rate => Arc::clone(current),
+            _ => Arc::clone(limiter.insert(Arc::new(RateLimiter::new(rate)))),
+        }
+    }
+
+    /// Garbage-collect blob files.
+    ///
+    /// A blob record is live while the newest stored value of its key is
+    /// still the pointer to it. Each blob file whose live ratio (live value
+    /// bytes / value bytes written) is below Options::blob_gc_threshold has
+    /// its live values rewritten through the normal write path, and a flush
+    /// lands them in a new blob file with fresh pointers. The old file is
+    /// dropped from the manifest and deleted once every version that
+    /// existed before the collection has been replaced and released.
+    ///
+    /// Returns the number of blob files collected.
+    pub fn garbage_collect_blobs(&self) -> Result<usize> {
+        let blob_files: Vec<BlobFileMeta> = {
+            let manifest = self.manifest.lock().unwrap();
+            manifest.blob_files().values().cloned().collect()
+        };
+        let block_read_options = self.block_read_options(&ReadOptions {
+            fill_cache: false,
+            ..ReadOptions::default()
+        });
+
+        let mut collected = Vec::new();
+        for blob_file in blob_files {
+            let mut live = Vec::new();
+            let mut live_bytes = 0;
+            let mut reader = BlobFileReader::open(&self.path, blob_file.file_number)?;
+            while let Some((key, index)) = reader.next_record()? {
+                if self.is_live_blob(&key, &index, &block_read_options)? {
+                    live_bytes += index.size;
+                    live.push((key, index));
                 }
-                false => break,
             }
+            if live_bytes as f64 >= blob_file.value_bytes as f64 * self.blob_gc_threshold {
+                continue;
+            }
+
+            for (key, index) in live {
+                self.rewrite_blob_value(&key, &index, &block_read_options)?;
+            }
+            collected.push(blob_file.file_number);
+        }
+        if collected.is_empty() {
+            return Ok(0);
         }
 
+        // Versions from before the rewrites may still point into the
+        // collected files; the flush replaces the current one.
+        for &file_number in &collected {
+            self.version_set.add_obsolete_blob_file(file_number);
+        }
+        {
+            let mut manifest = self.manifest.lock().unwrap();
+            for &file_number in &collected {
+                manifest.record_blob_file_deletion(file_number)?;
+            }
+        }
+        self.flush()?;
+        self.version_set.delete_obsolete_files(&self.path);
+
+        Ok(collected.len())
+    }
+
+    /// Whether the newest stored value of `key` is the pointer `index`.
+    fn is_live_blob(
+        &self,
+        key: &[u8],
+        index: &BlobIndex,
+        block_read_options: &BlockReadOptions,
+    ) -> Result<bool> {
+        Ok(matches!(
+            self.get_stored(key, block_read_options)?,
+            Some(StoredValue::Table(value)) if value.as_ref() == index.encode().as_slice()
+        ))
+    }
+
+    /// Write the live blob value at `index` back under `key`, unless a
+    /// newer write to `key` has replaced it since it was found live.
+    fn rewrite_blob_value(
+        &self,
+        key: &[u8],
+        index: &BlobIndex,
+        block_read_options: &BlockReadOptions,
+    ) -> Result<()> {
+        self.make_room_for_write()?;
+        // put() and delete() hold the WAL lock until their memtable insert,
+        // so no write to `key` can land between the check and the rewrite.
+        let mut wal = self.wal_manager.lock().unwrap();
+        if !self.is_live_blob(key, index, block_read_options)? {
+            return Ok(());
+        }
+        let value = read_blob_value(&self.path, index)?;
+        wal.append(
+            self.next_sequence.load(Ordering::SeqCst),
+            &WALRecord::put(key.to_vec(), value.clone()),
+        )?;
+        let active = self.active_memtable();
+        let mut memtable = active.write();
+        let seq = self.next_sequence.fetch_add(1, Ordering::SeqCst);
+        let before = memtable.size();
+        memtable.add(seq, ValueType::Put, key.to_vec(), value);
+        self.charge_write_buffer(memtable.size() - before);
         Ok(())
     }
 
+    /// Metadata of every live blob file, by file number.
+    pub fn live_blob_files(&self) -> Vec<BlobFileMeta> {
+        let manifest = self.manifest.lock().unwrap();
+        manifest.blob_files().values().cloned().collect()
+    }
+
     /// Get current engine statistics.
     pub fn stats(&self) -> Stats {
-        let memtable_size = {
-            let mt = self.active_memtable.read().unwrap();
-            mt.size()
+        let (memtable_size, memtable) = {
+            let active = self.active_memtable();
+            let mt = active.read();
+            (mt.size(), mt.stats())
         };
 
-        let num_sstables_per_level = {
+        let (num_sstables_per_level, levels) = {
             let current = self.version_set.current();
             let v = current.read().unwrap();
-            v.levels.iter().map(|l| l.len()).collect()
+            let scores = self.compaction_strategy.level_scores(&v.levels);
+            let level_stats = self.level_stats.lock().unwrap();
+            let levels = v
+                .levels
+                .iter()
+                .enumerate()
+                .map(|(i, ssts)| {
+                    let mut level = level_stats.get(i).cloned().unwrap_or_default();
+                    level.num_files = ssts.len();
+                    level.size_bytes = ssts.iter().map(|sst| sst.file_size).sum();
+                    level.score = scores.get(i).copied().unwrap_or(0.0);
+                    if level.bytes_read_upper > 0 {
+                        let upper = level.bytes_read_upper as f64;
+                        level.read_amplification =
+                            (level.bytes_read_upper + level.bytes_read_level) as f64 / upper;
+                        level.write_amplification = level.bytes_written as f64 / upper;
+                    }
+                    level
+                })
+                .collect();
+            (v.levels.iter().map(|l| l.len()).collect(), levels)
         };
 
         let block_cache_hit_rate = {
@@ -472,9 +2428,18 @@ impl DB {
 
         Stats {
             memtable_size,
+            memtable,
             num_sstables_per_level,
-            bloom_filter_hit_rate: 0.0, // bloom checks happen inside SSTable::get()
+            bloom_filter_hit_rate: {
+                let checks = self.bloom_checks.load(Ordering::Relaxed);
+                if checks > 0 {
+                    self.bloom_useful.load(Ordering::Relaxed) as f64 / checks as f64
+                } else {
+                    0.0
+                }
+            },
             block_cache_hit_rate,
+            open_tables: self.table_cache.len(),
             bytes_written: bytes_written_user,
             bytes_read: self.bytes_read.load(Ordering::Relaxed),
             write_amplification: if bytes_written_user > 0 {
@@ -484,9 +2449,41 @@ impl DB {
             },
             compaction_count: self.compaction_count.load(Ordering::Relaxed),
             compaction_bytes: self.compaction_bytes.load(Ordering::Relaxed),
+            dropped_expired: self.dropped_expired.load(Ordering::Relaxed),
+            levels,
+            compaction_reasons: self.compaction_reasons.lock().unwrap().clone(),
+            wal_syncs: self.wal_manager.lock().unwrap().sync_stats(),
         }
     }
 
+    /// A named property of the DB as text, or None for an unknown name.
+    ///
+    /// Properties:
+    /// - `lsm.estimate-pending-compaction-bytes`: bytes compaction is
+    ///   estimated to still have to rewrite, compared against
+    ///   Options::soft/hard_pending_compaction_bytes_limit
+    pub fn get_property(&self, name: &str) -> Option<String> {
+        match name {
+            "lsm.estimate-pending-compaction-bytes" => Some(
+                self.pending_compaction_bytes
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Metadata of every live SSTable: id, level, key range, file size,
+    /// entry count and creation time, as recorded in the manifest.
+    ///
+    /// Ordered by level, then in the order each level was built (oldest
+    /// first).
+    pub fn live_files_metadata(&self) -> Vec<SSTableMeta> {
+        let current = self.version_set.current();
+        let v = current.read().unwrap();
+        v.levels.iter().flatten().cloned().collect()
+    }
+
     /// Sum of all SSTable file sizes in the current version.
     fn total_sst_size(&self) -> u64 {
         let current = self.version_set.current();
@@ -494,23 +2491,10 @@ impl DB {
         v.levels.iter().flatten().map(|m| m.file_size).sum()
     }
 
-    /// Close the database gracefully.
-    ///
-    /// Flushes any remaining memtable data, syncs the WAL.
-    pub fn close(self) -> Result<()> {
-        // Flush if memtable has data
-        {
-            let memtable = self.active_memtable.read().unwrap();
-            if !memtable.is_empty() {
-                drop(memtable);
-                self.flush()?;
-            }
-        }
-
-        // Sync the active WAL
-        let mut wal = self.wal_manager.lock().unwrap();
-        wal.active_writer().sync()?;
-
-        Ok(())
+    /// Ids of the SSTables in the current version.
+    fn live_sst_ids(&self) -> HashSet<u64> {
+        let current = self.version_set.current();
+        let v = current.read().unwrap();
+        v.levels.iter().flatten().map(|m| m.id).collect()
     }
 }
@@ -0,0 +1,45 @@
+/// Extracts the prefix of a key for prefix iteration.
+///
+/// Configured once per database via Options::prefix_extractor. Keys that
+/// share a prefix are adjacent in sort order, so a prefix scan is just a
+/// range scan over [prefix, prefix_successor(prefix)).
+pub trait SliceTransform: Send + Sync {
+    /// Return the prefix of `key`. Only called when in_domain(key) is true.
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8];
+
+    /// Whether `key` has a prefix at all. Default: every key does.
+    fn in_domain(&self, _key: &[u8]) -> bool {
+        true
+    }
+}
+
+/// Fixed-length prefix: the first `n` bytes of the key.
+///
+/// Keys shorter than `n` are outside the domain and have no prefix.
+pub struct FixedPrefix(pub usize);
+
+impl SliceTransform for FixedPrefix {
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+        &key[..self.0]
+    }
+
+    fn in_domain(&self, key: &[u8]) -> bool {
+        key.len() >= self.0
+    }
+}
+
+/// Smallest key greater than every key starting with `prefix`.
+///
+/// Increments the last byte that isn't 0xFF and drops everything after it:
+///   "user:1" → "user:2",  "a\xff" → "b"
+/// Returns None if the prefix is all 0xFF bytes (no upper bound exists).
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
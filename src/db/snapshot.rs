@@ -1,40 +1,65 @@
+use crate::blob::reader::resolve_value;
+use crate::cache::table_cache::{TableCache, open_table};
+use crate::db::iterator::{DBIterator, sstable_sources};
+use crate::db::{ReadOptions, memtable_source};
 use crate::error::Result;
 use crate::iterator::StorageIterator;
-use crate::iterator::merge::MergeIterator;
-use crate::iterator::vec_iter::VecIterator;
-use crate::manifest::version::Version;
-use crate::sstable::reader::SSTable;
+use crate::manifest::version::{PinnedVersion, Version};
+use crate::memtable::MemTable;
+use crate::sstable::paths::TablePaths;
+use crate::sstable::reader::BlockReadOptions;
+use crate::types::ValueType;
 use std::sync::{Arc, RwLock};
 
 /// A frozen view of the database at a point in time.
 ///
-/// Holds a copy of the memtable entries at snapshot creation time plus
-/// references to the current Version (SSTable set) so that ongoing writes
-/// and compaction don't affect reads through this snapshot.
+/// Holds the memtables live at snapshot creation time, read as of its
+/// sequence number, plus references to the current Version (SSTable set)
+/// so that ongoing writes and compaction don't affect reads through this
+/// snapshot.
 pub struct Snapshot {
+    /// Next sequence number when the snapshot was taken: only versions
+    /// written before it are read.
     pub seq: u64,
     pub version: Arc<RwLock<Version>>,
     pub path: std::path::PathBuf,
-    /// Memtable entries captured at snapshot time. Sorted by key.
-    /// Includes tombstones (empty values) so they can shadow older data.
-    pub memtable_entries: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Directories holding the SSTables of `version`.
+    pub table_paths: TablePaths,
+    /// The memtables at snapshot time, oldest first: the immutable ones,
+    /// then the active one. Writes they take later carry later sequence
+    /// numbers, which reads skip.
+    pub(crate) memtables: Vec<Arc<MemTable>>,
+    /// The DB's open tables, so reads go through its block cache.
+    pub(crate) table_cache: Option<Arc<TableCache>>,
 }
 
 impl Snapshot {
     /// Point lookup through the snapshot.
     ///
-    /// Search order: memtable snapshot → L0 (newest-first) → L1+
+    /// Search order: memtables as of the snapshot → L0 (newest-first) → L1+
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        // 1. Check captured memtable entries (binary search, they're sorted)
-        if let Ok(idx) = self
-            .memtable_entries
-            .binary_search_by(|(k, _)| k.as_slice().cmp(key))
-        {
-            let value = &self.memtable_entries[idx].1;
-            if value.is_empty() {
-                return Ok(None); // tombstone
+        self.get_with(
+            key,
+            &BlockReadOptions::default(),
+            self.table_cache.as_deref(),
+        )
+    }
+
+    /// Point lookup with explicit block read options, from tables in
+    /// `table_cache` if given. Used by DB reads with ReadOptions::snapshot set.
+    pub(crate) fn get_with(
+        &self,
+        key: &[u8],
+        read_options: &BlockReadOptions,
+        table_cache: Option<&TableCache>,
+    ) -> Result<Option<Vec<u8>>> {
+        // 1. Check the memtables, newest first, as of the snapshot
+        for memtable in self.memtables.iter().rev() {
+            match memtable.get_version(key, self.seq) {
+                Some((ValueType::Put, value)) => return Ok(Some(value.into())),
+                Some((ValueType::Delete, _)) => return Ok(None),
+                None => {}
             }
-            return Ok(Some(value.clone()));
         }
 
         // 2. Search SSTables via version
@@ -42,28 +67,34 @@ impl Snapshot {
 
         // L0: check all SSTables, newest first
         for meta in version.level(0).iter().rev() {
-            let sst_path = self.path.join(format!("{:06}.sst", meta.id));
-            if let Ok(sst) = SSTable::open(&sst_path)
-                && let Ok(Some(v)) = sst.get(key)
+            if let Ok(sst) = open_table(
+                table_cache,
+                &self.table_paths,
+                meta,
+                read_options.file_access,
+            ) && let Some(v) = sst.get_with_options(key, read_options)?
             {
                 if v.is_empty() {
                     return Ok(None); // tombstone
                 }
-                return Ok(Some(v));
+                return Ok(Some(resolve_value(&self.path, v)?.into()));
             }
         }
 
         // L1+: no overlaps within a level
         for level in 1..version.levels.len() {
             for meta in version.level(level) {
-                let sst_path = self.path.join(format!("{:06}.sst", meta.id));
-                if let Ok(sst) = SSTable::open(&sst_path)
-                    && let Ok(Some(v)) = sst.get(key)
+                if let Ok(sst) = open_table(
+                    table_cache,
+                    &self.table_paths,
+                    meta,
+                    read_options.file_access,
+                ) && let Some(v) = sst.get_with_options(key, read_options)?
                 {
                     if v.is_empty() {
                         return Ok(None);
                     }
-                    return Ok(Some(v));
+                    return Ok(Some(resolve_value(&self.path, v)?.into()));
                 }
             }
         }
@@ -73,130 +104,155 @@ impl Snapshot {
 
     /// Range scan through the snapshot: yields all keys in [start, end).
     ///
-    /// Merges memtable snapshot + all SSTable data using MergeIterator.
+    /// Merges the memtables as of the snapshot + all SSTable data using MergeIterator.
     /// Tombstones are filtered — deleted keys are not yielded.
     pub fn scan(&self, start: &[u8], end: &[u8]) -> Result<Scanner> {
         Scanner::build(
-            &self.memtable_entries,
+            memtable_source(&self.memtables, Some(start), Some(end), self.seq)?,
             &self.version,
             &self.path,
+            &self.table_paths,
+            self.table_cache.as_ref(),
             start,
             end,
         )
     }
+
+    /// DBIterator over the snapshot, honoring the bounds in `read_options`
+    /// (its `snapshot` field is ignored) and reading blocks per
+    /// `block_read_options`, from tables in `table_cache` if given.
+    pub(crate) fn iter_with(
+        &self,
+        read_options: &ReadOptions,
+        block_read_options: &BlockReadOptions,
+        table_cache: Option<&Arc<TableCache>>,
+    ) -> Result<DBIterator> {
+        let lower = read_options.iterate_lower_bound.as_deref();
+        let upper = read_options.iterate_upper_bound.as_deref();
+        let mut iters: Vec<Box<dyn StorageIterator>> = Vec::new();
+
+        iters.push(memtable_source(&self.memtables, lower, upper, self.seq)?);
+
+        {
+            let version = self.version.read().unwrap();
+            iters.extend(sstable_sources(
+                &version,
+                &self.table_paths,
+                lower,
+                upper,
+                block_read_options,
+                table_cache,
+            )?);
+        }
+
+        let mut iter = DBIterator::new(iters, read_options)?;
+        iter.resolve_blobs(self.path.clone(), 1)?;
+        Ok(iter)
+    }
+}
+
+impl std::fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("seq", &self.seq)
+            .field("version", &self.version)
+            .field("path", &self.path)
+            .field("table_paths", &self.table_paths)
+            .field("memtables", &self.memtables.len())
+            .finish_non_exhaustive()
+    }
 }
 
 /// Range scan iterator returned by Snapshot::scan() and DB::scan().
 ///
-/// Wraps a MergeIterator that merges all data sources (memtable + SSTables).
-/// Adds two behaviors on top:
-/// 1. Range bound: stops when key >= end_key
-/// 2. Tombstone filtering: skips entries where value is empty
+/// A DBIterator bounded to [start, end): it merges all data sources
+/// (memtable + SSTables), filters tombstones, and stops at end_key.
+/// SSTables outside the range are never opened.
 pub struct Scanner {
-    merge: MergeIterator,
-    end_key: Vec<u8>,
+    inner: DBIterator,
 }
 
 impl Scanner {
-    /// Build a Scanner from memtable entries + SSTable version.
+    /// Build a Scanner from a memtable source (see memtable_source) +
+    /// SSTable version, reading tables from `table_cache` if given.
     pub(crate) fn build(
-        memtable_entries: &[(Vec<u8>, Vec<u8>)],
+        memtables: Box<dyn StorageIterator>,
         version: &Arc<RwLock<Version>>,
         path: &std::path::Path,
+        table_paths: &TablePaths,
+        table_cache: Option<&Arc<TableCache>>,
         start: &[u8],
         end: &[u8],
     ) -> Result<Self> {
         let mut iters: Vec<Box<dyn StorageIterator>> = Vec::new();
 
-        // Source 0 (highest priority): memtable entries
-        iters.push(Box::new(VecIterator::new(memtable_entries.to_vec())));
+        // Source 0 (highest priority): the memtables, read in place
+        iters.push(memtables);
 
         // SSTable sources: L0 newest-first, then L1+
-        let version = version.read().unwrap();
-
-        // L0: iterate newest-first (higher index = newer in the levels vec)
-        for meta in version.level(0).iter().rev() {
-            let sst_path = path.join(format!("{:06}.sst", meta.id));
-            if let Ok(sst) = SSTable::open(&sst_path) {
-                let entries = read_sst_entries(&sst)?;
-                iters.push(Box::new(VecIterator::new(entries)));
-            }
-        }
-
-        // L1+: order within level doesn't matter for correctness
-        for level in 1..version.levels.len() {
-            for meta in version.level(level) {
-                let sst_path = path.join(format!("{:06}.sst", meta.id));
-                if let Ok(sst) = SSTable::open(&sst_path) {
-                    let entries = read_sst_entries(&sst)?;
-                    iters.push(Box::new(VecIterator::new(entries)));
-                }
-            }
-        }
-
-        drop(version); // release lock before building merge
-
-        let mut merge = MergeIterator::new(iters)?;
-        // Seek to start of range
-        merge.seek(start)?;
+        {
+            let version = version.read().unwrap();
+            iters.extend(sstable_sources(
+                &version,
+                table_paths,
+                Some(start),
+                Some(end),
+                &BlockReadOptions::default(),
+                table_cache,
+            )?);
+        } // release lock before building merge
 
-        let mut scanner = Scanner {
-            merge,
-            end_key: end.to_vec(),
+        let read_options = ReadOptions {
+            iterate_lower_bound: Some(start.to_vec()),
+            iterate_upper_bound: Some(end.to_vec()),
+            ..ReadOptions::default()
         };
 
-        // Skip any initial tombstones
-        scanner.skip_tombstones()?;
-
-        Ok(scanner)
+        let mut inner = DBIterator::new(iters, &read_options)?;
+        inner.resolve_blobs(path.to_path_buf(), 1)?;
+        Ok(Scanner { inner })
     }
 
-    /// Skip forward past any tombstone entries.
-    fn skip_tombstones(&mut self) -> Result<()> {
-        while self.merge.is_valid()
-            && self.merge.key() < self.end_key.as_slice()
-            && self.merge.value().is_empty()
-        {
-            self.merge.next()?;
-        }
-        Ok(())
+    /// Keep `version`'s SSTables alive for the scanner's lifetime.
+    pub(crate) fn pin(&mut self, version: PinnedVersion, sequence: u64) {
+        self.inner.pin(version, sequence);
     }
 }
 
-/// Read all entries from an SSTable into a Vec for use with VecIterator.
-/// This sidesteps the SSTableIterator<'a> lifetime issue.
-fn read_sst_entries(sst: &SSTable) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
-    let mut entries = Vec::new();
-    let mut iter = sst.iter()?;
-    while iter.is_valid() {
-        entries.push((iter.key().to_vec(), iter.value().to_vec()));
-        iter.next()?;
-    }
-    Ok(entries)
-}
-
 impl StorageIterator for Scanner {
     fn key(&self) -> &[u8] {
-        self.merge.key()
+        self.inner.key()
     }
 
     fn value(&self) -> &[u8] {
-        self.merge.value()
+        self.inner.value()
     }
 
     fn is_valid(&self) -> bool {
-        self.merge.is_valid() && self.merge.key() < self.end_key.as_slice()
+        self.inner.is_valid()
     }
 
     fn next(&mut self) -> Result<()> {
-        self.merge.next()?;
-        self.skip_tombstones()?;
-        Ok(())
+        self.inner.next()
     }
 
     fn seek(&mut self, key: &[u8]) -> Result<()> {
-        self.merge.seek(key)?;
-        self.skip_tombstones()?;
-        Ok(())
+        self.inner.seek(key)
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        self.inner.prev()
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.inner.seek_for_prev(key)
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.inner.seek_to_first()
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.inner.seek_to_last()
     }
 }
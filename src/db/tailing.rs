@@ -0,0 +1,113 @@
+use crate::db::iterator::DBIterator;
+use crate::db::{DBInner, ReadOptions};
+use crate::error::Result;
+use crate::iterator::StorageIterator;
+
+/// Forward iterator that keeps observing new writes, returned by
+/// DB::tailing_iter().
+///
+/// A DBIterator is a view of the data at the time it was built. Once it
+/// runs past the end it stays invalid. A TailingIterator instead treats
+/// the end as "nothing new yet": next() past the last key rebuilds the
+/// view from the current memtable and SSTables, and resumes just after
+/// the last key it yielded. Consumers can poll it like a queue:
+///
+///   loop { while it.is_valid() { handle(it.key()); it.next()?; }
+///          sleep(); it.next()?; }
+///
+/// Only keys greater than the current position are picked up. Rebuilding
+/// uses the resume key as a lower bound, so SSTables entirely behind the
+/// cursor are never reopened.
+pub struct TailingIterator<'a> {
+    db: &'a DBInner,
+    read_options: ReadOptions,
+    inner: DBIterator,
+    /// Where a rebuild resumes: (key, exclusive). Exclusive after a key
+    /// has been yielded, inclusive after a seek that found nothing.
+    /// None means from the start of the range.
+    resume: Option<(Vec<u8>, bool)>,
+}
+
+impl<'a> TailingIterator<'a> {
+    pub(crate) fn new(db: &'a DBInner, read_options: &ReadOptions) -> Result<Self> {
+        Ok(TailingIterator {
+            db,
+            read_options: read_options.clone(),
+            inner: db.iter_with_options(read_options)?,
+            resume: None,
+        })
+    }
+
+    /// Rebuild the underlying DBIterator over the current DB state and
+    /// position it at the resume point.
+    fn refresh(&mut self) -> Result<()> {
+        let mut read_options = self.read_options.clone();
+        if let Some((ref key, _)) = self.resume
+            && read_options
+                .iterate_lower_bound
+                .as_ref()
+                .is_none_or(|lower| key > lower)
+        {
+            read_options.iterate_lower_bound = Some(key.clone());
+        }
+
+        self.inner = self.db.iter_with_options(&read_options)?;
+        if let Some((ref key, exclusive)) = self.resume {
+            // Seek (rather than relying on the lower bound) so prefix
+            // mode pins the same prefix again.
+            self.inner.seek(key)?;
+            if exclusive && self.inner.is_valid() && self.inner.key() == key.as_slice() {
+                self.inner.next()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StorageIterator for TailingIterator<'_> {
+    fn key(&self) -> &[u8] {
+        self.inner.key()
+    }
+
+    fn value(&self) -> &[u8] {
+        self.inner.value()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+
+    /// Advance; at (or past) the end, re-check the DB for newer keys.
+    fn next(&mut self) -> Result<()> {
+        if self.inner.is_valid() {
+            self.resume = Some((self.inner.key().to_vec(), true));
+            self.inner.next()?;
+        }
+        if !self.inner.is_valid() {
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.resume = Some((key.to_vec(), false));
+        self.refresh()
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        self.inner.prev()
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.inner.seek_for_prev(key)
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.resume = None;
+        self.refresh()
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.inner.seek_to_last()
+    }
+}
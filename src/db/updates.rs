@@ -0,0 +1,123 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+use crate::error::{Error, Result};
+use crate::wal::reader::WALReader;
+use crate::wal::record::RecordType;
+
+pub use crate::wal::record::WALUpdate;
+
+/// Writes read back from the WALs in sequence order, returned by
+/// DB::get_updates_since(). For change-data-capture: consumers remember
+/// the sequence after the last update they handled and resume from it.
+///
+/// WALs are read one at a time as the iterator reaches them. A WAL that
+/// was archived after the iterator was created is looked up in the
+/// archive. Writes that are no longer logged anywhere (flushed WALs that
+/// were deleted or pruned) end the iteration with Error::NotFound, so a
+/// consumer never skips updates without knowing.
+///
+/// Only what was in the active WAL when it's reached is read; take a
+/// new iterator to see later writes.
+pub struct UpdateIterator {
+    /// WALs still to read, oldest first.
+    wals: VecDeque<PathBuf>,
+    /// Where WALs that move during iteration end up, if anywhere.
+    archive_dir: Option<PathBuf>,
+    /// Updates read from the current WAL, not yet yielded.
+    pending: std::vec::IntoIter<WALUpdate>,
+    /// Sequence number of the next update to read.
+    next_sequence: u64,
+    /// Set once an error has been returned; nothing follows it.
+    done: bool,
+}
+
+impl UpdateIterator {
+    pub(crate) fn new(wals: Vec<PathBuf>, archive_dir: Option<PathBuf>, sequence: u64) -> Self {
+        UpdateIterator {
+            wals: wals.into(),
+            archive_dir,
+            pending: Vec::new().into_iter(),
+            // Sequence numbers start at 1
+            next_sequence: sequence.max(1),
+            done: false,
+        }
+    }
+
+    /// Read the next WAL's updates from `next_sequence` on into
+    /// `pending`.
+    fn read_next_wal(&mut self) -> Result<()> {
+        let Some(path) = self.wals.pop_front() else {
+            return Ok(());
+        };
+        let path = match (&self.archive_dir, path.file_name()) {
+            (Some(dir), Some(name)) if !path.exists() => dir.join(name),
+            _ => path,
+        };
+        let reader = match WALReader::new(&path) {
+            Ok(reader) => reader,
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::NotFound);
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut updates = Vec::new();
+        // WALs written before Sequence records existed can't be placed
+        let mut sequence = None;
+        for record in reader.iter() {
+            let record = record?;
+            if record.record_type == RecordType::Sequence {
+                let first = record.sequence_number().ok_or_else(|| {
+                    Error::Corruption("WAL sequence record with a bad value".into())
+                })?;
+                // The writes in between were either never logged, or in
+                // a WAL that's gone
+                let unlogged = record
+                    .unlogged_from()
+                    .is_some_and(|from| from <= self.next_sequence);
+                if first > self.next_sequence {
+                    if !unlogged {
+                        return Err(Error::NotFound);
+                    }
+                    self.next_sequence = first;
+                }
+                sequence = Some(first);
+                continue;
+            }
+            for record in record.writes()? {
+                let Some(seq) = sequence else { continue };
+                sequence = Some(seq + 1);
+                if seq >= self.next_sequence {
+                    updates.push(WALUpdate {
+                        sequence: seq,
+                        record,
+                    });
+                    self.next_sequence = seq + 1;
+                }
+            }
+        }
+        self.pending = updates.into_iter();
+        Ok(())
+    }
+}
+
+impl Iterator for UpdateIterator {
+    type Item = Result<WALUpdate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(update) = self.pending.next() {
+                return Some(Ok(update));
+            }
+            if self.wals.is_empty() {
+                return None;
+            }
+            if let Err(e) = self.read_next_wal() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Read;
+
+use bytes::{Buf, Bytes};
+
+use crate::blob::reader::BlobValueReader;
+
+/// A value being streamed out of the DB, returned by DB::get_reader().
+///
+/// Values in a blob file are read from it in chunks as the caller reads,
+/// so a multi-megabyte value is never held in memory whole. Other values
+/// are already in memory (a memtable buffer or a decoded block) and are
+/// read from there without copying them first.
+pub struct ValueReader {
+    source: Source,
+    /// Total value length in bytes.
+    len: u64,
+}
+
+enum Source {
+    InMemory(bytes::buf::Reader<Bytes>),
+    Blob(BlobValueReader),
+}
+
+impl ValueReader {
+    pub(crate) fn in_memory(value: Bytes) -> Self {
+        let len = value.len() as u64;
+        ValueReader {
+            source: Source::InMemory(value.reader()),
+            len,
+        }
+    }
+
+    pub(crate) fn blob(reader: BlobValueReader) -> Self {
+        ValueReader {
+            len: reader.remaining(),
+            source: Source::Blob(reader),
+        }
+    }
+
+    /// Total length of the value in bytes, e.g. for a Content-Length header.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Whether the value is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the value is streamed from a blob file rather than memory.
+    pub fn is_blob(&self) -> bool {
+        matches!(self.source, Source::Blob(_))
+    }
+}
+
+impl Read for ValueReader {
+    /// A blob value's checksum is checked when the end is reached: the
+    /// read that would return 0 fails with InvalidData instead if the
+    /// record is corrupt.
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.source {
+            Source::InMemory(reader) => reader.read(buf),
+            Source::Blob(reader) => reader.read(buf),
+        }
+    }
+}
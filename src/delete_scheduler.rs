@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Subdirectory files are moved to while they wait to be deleted.
+pub const TRASH_DIR: &str = "trash";
+
+/// Deletes obsolete files at a bounded rate, so removing the inputs of a
+/// large compaction doesn't unlink gigabytes at once and stall the disk.
+///
+/// A file to delete is first renamed into the `trash` directory next to
+/// it, which is cheap and takes it out of the DB at once. A background
+/// thread then deletes trashed files one at a time, waiting after each
+/// for as long as its size takes at `bytes_per_second`. Files still in
+/// the trash when the scheduler is dropped are left there;
+/// recover_trash() picks them up on the next open.
+///
+/// With a rate of 0 files are deleted directly, without the trash.
+pub struct DeleteScheduler {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    bytes_per_second: u64,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Trashed files, oldest first.
+    queue: VecDeque<PathBuf>,
+    /// Files queued or being deleted.
+    pending: usize,
+    shutdown: bool,
+}
+
+impl DeleteScheduler {
+    /// Delete at most `bytes_per_second`; 0 deletes without limit.
+    pub fn new(bytes_per_second: u64) -> Self {
+        let shared = Arc::new(Shared {
+            bytes_per_second,
+            state: Mutex::new(State::default()),
+            changed: Condvar::new(),
+        });
+        let worker = (bytes_per_second > 0).then(|| {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name("lsm-delete".into())
+                .spawn(move || shared.run())
+                .expect("failed to spawn delete thread")
+        });
+        DeleteScheduler { shared, worker }
+    }
+
+    pub fn bytes_per_second(&self) -> u64 {
+        self.shared.bytes_per_second
+    }
+
+    /// Delete `path`: move it to the trash and queue it, or remove it
+    /// right away without a rate. A file the trash can't take is removed
+    /// right away too.
+    pub fn delete_file(&self, path: &Path) -> io::Result<()> {
+        if self.worker.is_none() {
+            return std::fs::remove_file(path);
+        }
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return std::fs::remove_file(path);
+        };
+        let trash = dir.join(TRASH_DIR);
+        let trashed = trash.join(name);
+        if std::fs::create_dir_all(&trash)
+            .and_then(|()| std::fs::rename(path, &trashed))
+            .is_err()
+        {
+            return std::fs::remove_file(path);
+        }
+        self.shared.push(trashed);
+        Ok(())
+    }
+
+    /// Queue the files a previous run left in `dir`'s trash.
+    pub fn recover_trash(&self, dir: &Path) -> io::Result<()> {
+        let trash = dir.join(TRASH_DIR);
+        let Ok(entries) = std::fs::read_dir(&trash) else {
+            return Ok(());
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+        for file in files {
+            match self.worker {
+                Some(_) => self.shared.push(file),
+                None => std::fs::remove_file(file)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Files in the trash, waiting to be deleted.
+    pub fn num_pending(&self) -> usize {
+        self.shared.state.lock().unwrap().pending
+    }
+
+    /// Block until every trashed file has been deleted.
+    pub fn wait_for_empty_trash(&self) {
+        let mut state = self.shared.state.lock().unwrap();
+        while state.pending > 0 && !state.shutdown {
+            state = self.shared.changed.wait(state).unwrap();
+        }
+    }
+}
+
+impl Drop for DeleteScheduler {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn push(&self, file: PathBuf) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.push_back(file);
+        state.pending += 1;
+        self.changed.notify_all();
+    }
+
+    /// Worker loop: delete the oldest trashed file, then wait out its
+    /// share of the rate before the next.
+    fn run(&self) {
+        loop {
+            let file = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if state.shutdown {
+                        return;
+                    }
+                    if let Some(file) = state.queue.pop_front() {
+                        break file;
+                    }
+                    state = self.changed.wait(state).unwrap();
+                }
+            };
+
+            let size = std::fs::metadata(&file).map_or(0, |meta| meta.len());
+            let _ = std::fs::remove_file(&file);
+            let deadline = Instant::now()
+                + Duration::from_secs_f64(size as f64 / self.bytes_per_second as f64);
+
+            let mut state = self.state.lock().unwrap();
+            state.pending -= 1;
+            self.changed.notify_all();
+            while !state.shutdown {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                state = self.changed.wait_timeout(state, deadline - now).unwrap().0;
+            }
+        }
+    }
+}
@@ -29,6 +29,20 @@ impl fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// io::Error isn't Clone, so a cloned Io error keeps the kind and message
+/// but not the original source. Used to hand out a stored error (e.g. an
+/// iterator's status) more than once.
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        match self {
+            Error::Io(e) => Error::Io(io::Error::new(e.kind(), e.to_string())),
+            Error::Corruption(msg) => Error::Corruption(msg.clone()),
+            Error::NotFound => Error::NotFound,
+            Error::Eof => Error::Eof,
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
         Error::Io(e)
@@ -0,0 +1,288 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::cache::table_cache::{TableCache, open_table};
+use crate::error::Result;
+use crate::iterator::StorageIterator;
+use crate::sstable::footer::SSTableMeta;
+use crate::sstable::iterator::SSTableIterator;
+use crate::sstable::paths::TablePaths;
+use crate::sstable::range_del::RangeTombstones;
+use crate::sstable::reader::BlockReadOptions;
+
+/// Concatenating iterator over one sorted level (L1+).
+///
+/// SSTables within a level don't overlap, so the level as a whole is one
+/// sorted run: file 0's keys, then file 1's, and so on. Instead of putting
+/// every file into the merge heap, a LevelIterator presents the whole level
+/// as a single source and opens one file at a time, only when iteration
+/// reaches it:
+///
+/// ```text
+///   [a..f] [g..m] [n..t] [u..z]     ← SSTables in the level
+///            ▲
+///     only this file is loaded; seek() picks it from the manifest
+///     key ranges without touching the others
+/// ```
+///
+/// This keeps the merge heap and loaded data O(levels) instead of O(files).
+/// The current file is read through an SSTableIterator holding the open
+/// table (within the bounds), one block at a time, with keys and values
+/// served from the loaded block rather than copied.
+///
+/// An L0 table, overlapping the others, is a sorted run of its own: the
+/// DB reads each as a LevelIterator over that one file.
+pub struct LevelIterator {
+    /// SSTables of the level overlapping [lower, upper), sorted by min_key.
+    metas: Vec<SSTableMeta>,
+    /// Directories holding the SSTable files.
+    paths: TablePaths,
+    /// Inclusive lower bound, if any.
+    lower: Option<Vec<u8>>,
+    /// Exclusive upper bound, if any.
+    upper: Option<Vec<u8>>,
+    /// Index into `metas` of the loaded file.
+    file_idx: usize,
+    /// Iterator over the loaded file, or None when positioned past either
+    /// end.
+    current: Option<SSTableIterator<'static>>,
+    /// Checksum / cache behavior for block reads.
+    read_options: BlockReadOptions,
+    /// Open tables shared with the DB, if any.
+    table_cache: Option<Arc<TableCache>>,
+    /// Range tombstones of newer tables; entries they cover are skipped.
+    deleted: RangeTombstones,
+    /// Whether seqno() reports entries' sequence numbers.
+    seqnos: bool,
+}
+
+impl LevelIterator {
+    /// Build an iterator over the SSTables of one non-overlapping level,
+    /// positioned at the first entry within [lower, upper).
+    ///
+    /// Files that can't intersect the bounds are dropped up front and
+    /// never opened.
+    pub fn new(
+        metas: &[SSTableMeta],
+        path: PathBuf,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Result<Self> {
+        Self::new_with_options(
+            metas,
+            TablePaths::single(&path),
+            lower,
+            upper,
+            BlockReadOptions::default(),
+            None,
+            RangeTombstones::default(),
+        )
+    }
+
+    /// Like new(), with the files in `paths`, reading blocks with
+    /// `read_options` from tables in `table_cache` (and through its block
+    /// cache), and skipping entries covered by `deleted`, the range
+    /// tombstones of newer tables.
+    pub fn new_with_options(
+        metas: &[SSTableMeta],
+        paths: TablePaths,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        read_options: BlockReadOptions,
+        table_cache: Option<Arc<TableCache>>,
+        deleted: RangeTombstones,
+    ) -> Result<Self> {
+        let mut metas: Vec<SSTableMeta> = metas
+            .iter()
+            .filter(|meta| {
+                lower.is_none_or(|lower| meta.max_key.as_slice() >= lower)
+                    && upper.is_none_or(|upper| meta.min_key.as_slice() < upper)
+            })
+            .cloned()
+            .collect();
+        metas.sort_by(|a, b| a.min_key.cmp(&b.min_key));
+
+        let mut iter = LevelIterator {
+            metas,
+            paths,
+            lower: lower.map(|k| k.to_vec()),
+            upper: upper.map(|k| k.to_vec()),
+            file_idx: 0,
+            current: None,
+            read_options,
+            table_cache,
+            deleted,
+            seqnos: false,
+        };
+        iter.seek_to_first()?;
+        Ok(iter)
+    }
+
+    /// Report each entry's sequence number through seqno(): the one its
+    /// table stores, or the table's largest_seqno for tables without
+    /// them. Repositions at the first entry.
+    pub fn with_seqnos(mut self) -> Result<Self> {
+        self.seqnos = true;
+        self.seek_to_first()?;
+        Ok(self)
+    }
+
+    /// Number of files currently eligible for iteration.
+    pub fn num_files(&self) -> usize {
+        self.metas.len()
+    }
+
+    /// The files eligible for iteration, sorted by min_key.
+    pub fn metas(&self) -> &[SSTableMeta] {
+        &self.metas
+    }
+
+    /// Load file `idx` (or clear the current file if out of range).
+    /// Open and read errors are returned with the iterator left invalid.
+    fn load_file(&mut self, idx: usize) -> Result<()> {
+        self.file_idx = idx;
+        self.current = None;
+        if let Some(meta) = self.metas.get(idx) {
+            let sst = open_table(
+                self.table_cache.as_deref(),
+                &self.paths,
+                meta,
+                self.read_options.file_access,
+            )?;
+            let iter = sst.shared_iter(
+                self.lower.as_deref(),
+                self.upper.as_deref(),
+                &self.read_options,
+            )?;
+            self.current = Some(if self.seqnos {
+                iter.with_seqnos()?
+            } else {
+                iter
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the loaded file is positioned at an entry a newer table's
+    /// range tombstone deletes.
+    fn at_deleted(&self) -> bool {
+        !self.deleted.is_empty()
+            && self
+                .current
+                .as_ref()
+                .is_some_and(|c| c.is_valid() && self.deleted.covers(c.key()))
+    }
+
+    /// Step forward past deleted entries and, while the loaded file is
+    /// exhausted, on to the next file's first entry.
+    fn skip_forward_exhausted(&mut self) -> Result<()> {
+        loop {
+            while self.at_deleted() {
+                self.current.as_mut().unwrap().next()?;
+            }
+            if self.current.as_ref().is_none_or(|c| c.is_valid()) {
+                return Ok(());
+            }
+            self.load_file(self.file_idx + 1)?;
+        }
+    }
+
+    /// Step back past deleted entries and, while the loaded file is
+    /// exhausted, back to the previous file's last entry.
+    fn skip_backward_exhausted(&mut self) -> Result<()> {
+        loop {
+            while self.at_deleted() {
+                self.current.as_mut().unwrap().prev()?;
+            }
+            if self.current.as_ref().is_none_or(|c| c.is_valid()) {
+                return Ok(());
+            }
+            if self.file_idx == 0 {
+                self.current = None;
+                return Ok(());
+            }
+            self.load_file(self.file_idx - 1)?;
+            if let Some(current) = self.current.as_mut() {
+                current.seek_to_last()?;
+            }
+        }
+    }
+}
+
+impl StorageIterator for LevelIterator {
+    fn key(&self) -> &[u8] {
+        self.current.as_ref().unwrap().key()
+    }
+
+    fn value(&self) -> &[u8] {
+        self.current.as_ref().unwrap().value()
+    }
+
+    fn seqno(&self) -> Option<u64> {
+        if !self.seqnos {
+            return None;
+        }
+        let seqno = self.current.as_ref()?.seqno();
+        seqno.or(Some(self.metas[self.file_idx].largest_seqno))
+    }
+
+    fn is_valid(&self) -> bool {
+        self.current.as_ref().is_some_and(|c| c.is_valid())
+    }
+
+    fn next(&mut self) -> Result<()> {
+        if let Some(current) = self.current.as_mut() {
+            current.next()?;
+        }
+        self.skip_forward_exhausted()
+    }
+
+    /// First file whose max_key >= target holds the answer (or, if the
+    /// target falls in a gap between files, its first key does).
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        let idx = self.metas.partition_point(|m| m.max_key.as_slice() < key);
+        self.load_file(idx)?;
+        if let Some(current) = self.current.as_mut() {
+            current.seek(key)?;
+        }
+        self.skip_forward_exhausted()
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        if let Some(current) = self.current.as_mut() {
+            current.prev()?;
+        }
+        self.skip_backward_exhausted()
+    }
+
+    /// Last file whose min_key <= target holds the answer.
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        let idx = self.metas.partition_point(|m| m.min_key.as_slice() <= key);
+        if idx == 0 {
+            self.current = None;
+            return Ok(());
+        }
+        self.load_file(idx - 1)?;
+        if let Some(current) = self.current.as_mut() {
+            current.seek_for_prev(key)?;
+        }
+        self.skip_backward_exhausted()
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.load_file(0)?;
+        self.skip_forward_exhausted()
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        if self.metas.is_empty() {
+            self.current = None;
+            return Ok(());
+        }
+        self.load_file(self.metas.len() - 1)?;
+        if let Some(current) = self.current.as_mut() {
+            current.seek_to_last()?;
+        }
+        self.skip_backward_exhausted()
+    }
+}
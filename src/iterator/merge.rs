@@ -42,7 +42,7 @@ impl PartialEq for HeapEntry {
 impl Eq for HeapEntry {}
 
 // ---------------------------------------------------------------------------
-// MergeIterator: k-way merge with deduplication, keeps tombstones.
+// MergeIterator: k-way merge with deduplication, optional tombstone filter.
 // ---------------------------------------------------------------------------
 
 /// Merges multiple sorted iterators into a single sorted stream.
@@ -55,8 +55,12 @@ impl Eq for HeapEntry {}
 /// Deduplication: when multiple sources have the same key, only the newest
 /// (lowest iterator index) is yielded. Others are silently skipped.
 ///
-/// Tombstones are NOT filtered — they pass through as normal entries.
-/// The caller (compaction or read path) decides how to handle them.
+/// Tombstones: by default they are NOT filtered — they pass through as
+/// normal entries, and the caller (compaction or read path) decides how to
+/// handle them. Compaction must keep them so they keep shadowing older data
+/// in lower levels. Built with filter_tombstones(), the iterator instead
+/// hides deleted keys in both directions — but only after deduplication,
+/// so a newer tombstone still hides an older live value.
 pub struct MergeIterator {
     /// Sub-iterators, ordered by priority: index 0 = newest source.
     iters: Vec<Box<dyn StorageIterator>>,
@@ -64,6 +68,8 @@ pub struct MergeIterator {
     heap: BinaryHeap<HeapEntry>,
     /// Index of the iterator currently producing key()/value(), or None if exhausted.
     current: Option<usize>,
+    /// Skip entries whose winning version is a tombstone (empty value).
+    filter_tombstones: bool,
 }
 
 impl MergeIterator {
@@ -87,6 +93,7 @@ impl MergeIterator {
             iters,
             heap,
             current: None,
+            filter_tombstones: false,
         };
 
         // Position at the first unique key.
@@ -95,6 +102,91 @@ impl MergeIterator {
         Ok(merge)
     }
 
+    /// Like new(), but deleted keys are hidden: the stream only contains
+    /// keys whose newest version is a live value.
+    pub fn filter_tombstones(iters: Vec<Box<dyn StorageIterator>>) -> Result<Self> {
+        let mut merge = Self::new(iters)?;
+        merge.filter_tombstones = true;
+        merge.skip_tombstones_forward()?;
+        Ok(merge)
+    }
+
+    /// Index of the source the current entry comes from (0 = newest),
+    /// or None if exhausted.
+    pub fn current_source(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// In filtering mode, step forward past tombstones.
+    fn skip_tombstones_forward(&mut self) -> Result<()> {
+        while self.filter_tombstones && self.is_valid() && self.value().is_empty() {
+            self.step_forward()?;
+        }
+        Ok(())
+    }
+
+    /// In filtering mode, step backward past tombstones.
+    fn skip_tombstones_backward(&mut self) -> Result<()> {
+        while self.filter_tombstones && self.is_valid() && self.value().is_empty() {
+            self.step_back()?;
+        }
+        Ok(())
+    }
+
+    /// Advance to the next unique key, tombstone or not.
+    fn step_forward(&mut self) -> Result<()> {
+        if let Some(idx) = self.current {
+            // Advance the current winner past its entry.
+            self.iters[idx].next()?;
+            if self.iters[idx].is_valid() {
+                self.heap.push(HeapEntry {
+                    key: self.iters[idx].key().to_vec(),
+                    index: idx,
+                });
+            }
+
+            // Move to the next unique key.
+            self.advance_to_next_unique()?;
+        }
+        Ok(())
+    }
+
+    /// Move back to the previous unique key, tombstone or not.
+    fn step_back(&mut self) -> Result<()> {
+        if self.current.is_none() {
+            return Ok(());
+        }
+        let current_key = self.key().to_vec();
+
+        // Move every source to its last entry strictly before current_key.
+        for iter in self.iters.iter_mut() {
+            iter.seek_for_prev(&current_key)?;
+            if iter.is_valid() && iter.key() == current_key.as_slice() {
+                iter.prev()?;
+            }
+        }
+
+        self.settle_on_largest()
+    }
+
+    /// Position at the first unique key >= `key`, tombstone or not.
+    fn seek_unfiltered(&mut self, key: &[u8]) -> Result<()> {
+        // Seek every sub-iterator and rebuild the heap from scratch.
+        self.heap.clear();
+        for (i, iter) in self.iters.iter_mut().enumerate() {
+            iter.seek(key)?;
+            if iter.is_valid() {
+                self.heap.push(HeapEntry {
+                    key: iter.key().to_vec(),
+                    index: i,
+                });
+            }
+        }
+
+        self.current = None;
+        self.advance_to_next_unique()
+    }
+
     /// Pop the smallest key from the heap and skip any duplicate keys
     /// from older sources. After this call, `self.current` points to
     /// the iterator holding the winning entry, or is None if exhausted.
@@ -126,6 +218,29 @@ impl MergeIterator {
         }
         Ok(())
     }
+
+    /// After every sub-iterator has been moved backwards, the largest key
+    /// among them is where the merged view should land. Re-seek forward to
+    /// that key so the heap invariant (all sources at >= current) holds again
+    /// and the newest source wins any ties.
+    fn settle_on_largest(&mut self) -> Result<()> {
+        let largest = self
+            .iters
+            .iter()
+            .filter(|iter| iter.is_valid())
+            .map(|iter| iter.key())
+            .max()
+            .map(|key| key.to_vec());
+
+        match largest {
+            Some(key) => self.seek_unfiltered(&key),
+            None => {
+                self.heap.clear();
+                self.current = None;
+                Ok(())
+            }
+        }
+    }
 }
 
 impl StorageIterator for MergeIterator {
@@ -138,32 +253,42 @@ impl StorageIterator for MergeIterator {
         self.iters[self.current.unwrap()].value()
     }
 
+    fn seqno(&self) -> Option<u64> {
+        self.iters[self.current?].seqno()
+    }
+
     fn is_valid(&self) -> bool {
         self.current.is_some()
     }
 
     fn next(&mut self) -> Result<()> {
-        if let Some(idx) = self.current {
-            // Advance the current winner past its entry.
-            self.iters[idx].next()?;
-            if self.iters[idx].is_valid() {
-                self.heap.push(HeapEntry {
-                    key: self.iters[idx].key().to_vec(),
-                    index: idx,
-                });
-            }
+        self.step_forward()?;
+        self.skip_tombstones_forward()
+    }
 
-            // Move to the next unique key.
-            self.advance_to_next_unique()?;
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.seek_unfiltered(key)?;
+        self.skip_tombstones_forward()
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        self.step_back()?;
+        self.skip_tombstones_backward()
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        for iter in self.iters.iter_mut() {
+            iter.seek_for_prev(key)?;
         }
-        Ok(())
+
+        self.settle_on_largest()?;
+        self.skip_tombstones_backward()
     }
 
-    fn seek(&mut self, key: &[u8]) -> Result<()> {
-        // Seek every sub-iterator and rebuild the heap from scratch.
+    fn seek_to_first(&mut self) -> Result<()> {
         self.heap.clear();
         for (i, iter) in self.iters.iter_mut().enumerate() {
-            iter.seek(key)?;
+            iter.seek_to_first()?;
             if iter.is_valid() {
                 self.heap.push(HeapEntry {
                     key: iter.key().to_vec(),
@@ -174,6 +299,15 @@ impl StorageIterator for MergeIterator {
 
         self.current = None;
         self.advance_to_next_unique()?;
-        Ok(())
+        self.skip_tombstones_forward()
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        for iter in self.iters.iter_mut() {
+            iter.seek_to_last()?;
+        }
+
+        self.settle_on_largest()?;
+        self.skip_tombstones_backward()
     }
 }
@@ -1,8 +1,14 @@
+pub mod level;
 pub mod merge;
 pub mod vec_iter;
 
 use crate::error::Result;
 
+/// Value yielded for live entries by keys-only iterators, in place of the
+/// stored value. Tombstones still come out empty, so the usual
+/// "empty value = deleted" check keeps working without reading values.
+pub const KEYS_ONLY_VALUE: &[u8] = b"\x01";
+
 // TODO [M02]: Implement this trait for SkipListIterator
 
 // TODO [M12]: Implement this trait for BlockIterator
@@ -21,6 +27,12 @@ pub trait StorageIterator {
     /// Returns the current value. Only valid when is_valid() is true.
     fn value(&self) -> &[u8];
 
+    /// Sequence number of the write the current entry comes from, for
+    /// sources that know it. Only valid when is_valid() is true.
+    fn seqno(&self) -> Option<u64> {
+        None
+    }
+
     /// Returns true if the iterator is positioned at a valid entry.
     fn is_valid(&self) -> bool;
 
@@ -29,4 +41,20 @@ pub trait StorageIterator {
 
     /// Positions the iterator at the first entry with key >= target.
     fn seek(&mut self, key: &[u8]) -> Result<()>;
+
+    /// Moves back to the previous entry. Only valid when is_valid() is true.
+    /// Becomes invalid when stepping back from the first entry.
+    fn prev(&mut self) -> Result<()>;
+
+    /// Positions the iterator at the last entry with key <= target.
+    ///
+    /// The mirror image of seek(): "find the floor entry". Becomes invalid
+    /// if every key is greater than the target.
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()>;
+
+    /// Positions the iterator at the first entry. Invalid if there are none.
+    fn seek_to_first(&mut self) -> Result<()>;
+
+    /// Positions the iterator at the last entry. Invalid if there are none.
+    fn seek_to_last(&mut self) -> Result<()>;
 }
@@ -9,12 +9,25 @@ use crate::iterator::StorageIterator;
 /// into a Vec<(key, value)> and wrap it in VecIterator.
 pub struct VecIterator {
     entries: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Sequence number of each entry, if known; empty otherwise.
+    seqnos: Vec<u64>,
     pos: usize,
 }
 
 impl VecIterator {
     pub fn new(entries: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
-        Self { entries, pos: 0 }
+        Self::with_seqnos(entries, Vec::new())
+    }
+
+    /// Like new(), with each entry's sequence number reported by seqno().
+    /// `seqnos` is either empty or as long as `entries`.
+    pub fn with_seqnos(entries: Vec<(Vec<u8>, Vec<u8>)>, seqnos: Vec<u64>) -> Self {
+        debug_assert!(seqnos.is_empty() || seqnos.len() == entries.len());
+        Self {
+            entries,
+            seqnos,
+            pos: 0,
+        }
     }
 }
 
@@ -27,6 +40,10 @@ impl StorageIterator for VecIterator {
         &self.entries[self.pos].1
     }
 
+    fn seqno(&self) -> Option<u64> {
+        self.seqnos.get(self.pos).copied()
+    }
+
     fn is_valid(&self) -> bool {
         self.pos < self.entries.len()
     }
@@ -40,4 +57,26 @@ impl StorageIterator for VecIterator {
         self.pos = self.entries.partition_point(|(k, _)| k.as_slice() < key);
         Ok(())
     }
+
+    fn prev(&mut self) -> Result<()> {
+        // Stepping back from index 0 parks the cursor past the end (invalid).
+        self.pos = self.pos.checked_sub(1).unwrap_or(self.entries.len());
+        Ok(())
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        let upper = self.entries.partition_point(|(k, _)| k.as_slice() <= key);
+        self.pos = upper.checked_sub(1).unwrap_or(self.entries.len());
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.pos = self.entries.len().saturating_sub(1);
+        Ok(())
+    }
 }
@@ -9,19 +9,29 @@
 //! This turns random writes into sequential writes — 100-1000x faster
 //! on real hardware.
 
+pub mod blob;
 pub mod bloom;
 pub mod cache;
+pub mod checksum;
 pub mod compaction;
 pub mod db;
+pub mod delete_scheduler;
 pub mod error;
 pub mod iterator;
 pub mod manifest;
 pub mod memtable;
+pub mod rate_limiter;
 pub mod sstable;
+pub mod tools;
 pub mod types;
 pub mod wal;
+pub mod write_buffer_manager;
 
 // Public re-exports for the top-level API
+pub use checksum::ChecksumType;
 pub use compaction::CompactionStyle;
-pub use db::{DB, Options, Stats};
+pub use db::batch::WriteBatch;
+pub use db::{CompactionReasonStats, DB, LevelStats, Options, ReadOptions, Stats, WriteOptions};
 pub use error::{Error, Result};
+pub use sstable::compression::CompressionType;
+pub use sstable::paths::DbPath;
@@ -1,8 +1,11 @@
 pub mod version;
 
+use crate::blob::BlobFileMeta;
+use crate::compaction::CompactionReason;
 use crate::error::{Error, Result};
 use crate::sstable::footer::SSTableMeta;
 use crc32fast::Hasher;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryInto;
 use std::fs::OpenOptions;
 use std::io::{Read, Seek, SeekFrom, Write};
@@ -22,9 +25,26 @@ pub enum ManifestRecord {
     CompactionComplete {
         added: Vec<SSTableMeta>,
         removed: Vec<u64>, // SSTable IDs
+        reason: CompactionReason,
     },
     /// Record the current WAL log number. On recovery, replay WALs with id >= this.
     SetLogNumber(u64),
+    /// A blob file was written (by flush) or adopted at open.
+    BlobFileAdded(BlobFileMeta),
+    /// A blob file was garbage collected.
+    BlobFileDeleted(u64),
+    /// Compactions recorded so far, by reason (written by compact()).
+    CompactionReasons(BTreeMap<CompactionReason, u64>),
+    /// A flush completed: its blob files and SSTables, and the WAL number
+    /// recovery resumes from, applied together or not at all.
+    FlushComplete {
+        blob_files: Vec<BlobFileMeta>,
+        added: Vec<SSTableMeta>,
+        log_number: u64,
+    },
+    /// A WAL was created. It's live, and replayed on recovery, until a
+    /// flush moves the log number past it.
+    WALAdded(u64),
 }
 
 // Helper: append a record as [len(4)][payload][crc(4)]
@@ -40,10 +60,74 @@ fn append_record(file: &mut std::fs::File, payload: &[u8]) -> Result<()> {
     Ok(())
 }
 
+// Record tags. Tags 1, 2 and 4 predate creation_time, 5, 6 and 7 the
+// sequence numbers, and 10, 11 and 12 the path id, and carry metas without
+// them; they're still replayed, but only 13, 15, 16 and 17 are written.
+// Tag 16 holds a whole flush, so replay never sees part of one. Tag 17 is
+// tag 14 with the compaction's reason byte after the tag; tag 18 carries
+// the per-reason compaction counts into a compacted manifest. Tag 19 lists
+// a new WAL; the log number of a later flush (or tag 3) retires it.
+const TAG_NEW_SSTABLE_V1: u8 = 1;
+const TAG_COMPACTION_V1: u8 = 2;
+const TAG_LOG_NUMBER: u8 = 3;
+const TAG_SNAPSHOT_V1: u8 = 4;
+const TAG_NEW_SSTABLE_V2: u8 = 5;
+const TAG_COMPACTION_V2: u8 = 6;
+const TAG_SNAPSHOT_V2: u8 = 7;
+const TAG_BLOB_FILE_ADDED: u8 = 8;
+const TAG_BLOB_FILE_DELETED: u8 = 9;
+const TAG_NEW_SSTABLE_V3: u8 = 10;
+const TAG_COMPACTION_V3: u8 = 11;
+const TAG_SNAPSHOT_V3: u8 = 12;
+const TAG_NEW_SSTABLE: u8 = 13;
+const TAG_COMPACTION: u8 = 14;
+const TAG_SNAPSHOT: u8 = 15;
+const TAG_FLUSH: u8 = 16;
+const TAG_COMPACTION_REASON: u8 = 17;
+const TAG_COMPACTION_REASONS: u8 = 18;
+const TAG_WAL_ADDED: u8 = 19;
+
+/// Layout of the metas in a record with `tag`: 1 without creation_time,
+/// 2 without sequence numbers, 3 without the path id, 4 current.
+fn meta_version(tag: u8) -> u8 {
+    match tag {
+        TAG_NEW_SSTABLE_V1 | TAG_COMPACTION_V1 | TAG_SNAPSHOT_V1 => 1,
+        TAG_NEW_SSTABLE_V2 | TAG_COMPACTION_V2 | TAG_SNAPSHOT_V2 => 2,
+        TAG_NEW_SSTABLE_V3 | TAG_COMPACTION_V3 | TAG_SNAPSHOT_V3 => 3,
+        _ => 4,
+    }
+}
+
+// Blob file records: [file_number(8)][record_count(8)][value_bytes(8)][file_size(8)]
+const BLOB_FILE_META_SIZE: usize = 32;
+
+fn encode_blob_file_meta(m: &BlobFileMeta) -> Vec<u8> {
+    let mut v = Vec::with_capacity(BLOB_FILE_META_SIZE);
+    v.extend_from_slice(&m.file_number.to_le_bytes());
+    v.extend_from_slice(&m.record_count.to_le_bytes());
+    v.extend_from_slice(&m.value_bytes.to_le_bytes());
+    v.extend_from_slice(&m.file_size.to_le_bytes());
+    v
+}
+
+fn decode_blob_file_meta(data: &[u8]) -> Result<BlobFileMeta> {
+    if data.len() < BLOB_FILE_META_SIZE {
+        return Err(Error::Corruption("blob file record too short".into()));
+    }
+    let field = |i: usize| u64::from_le_bytes(data[i * 8..i * 8 + 8].try_into().unwrap());
+    Ok(BlobFileMeta {
+        file_number: field(0),
+        record_count: field(1),
+        value_bytes: field(2),
+        file_size: field(3),
+    })
+}
+
 // Encode/decode SSTableMeta to a compact byte representation.
 fn encode_meta(m: &SSTableMeta) -> Vec<u8> {
-    // layout: [id(8)][level(4)][min_len(4)][min_key][max_len(4)][max_key][file_size(8)][entry_count(8)]
-    let mut v = Vec::with_capacity(64 + m.min_key.len() + m.max_key.len());
+    // layout: [id(8)][level(4)][min_len(4)][min_key][max_len(4)][max_key][file_size(8)][entry_count(8)][creation_time(8)]
+    //         [smallest_seqno(8)][largest_seqno(8)][path_id(4)]
+    let mut v = Vec::with_capacity(92 + m.min_key.len() + m.max_key.len());
     v.extend_from_slice(&m.id.to_le_bytes());
     v.extend_from_slice(&m.level.to_le_bytes());
     v.extend_from_slice(&(m.min_key.len() as u32).to_le_bytes());
@@ -52,15 +136,19 @@ fn encode_meta(m: &SSTableMeta) -> Vec<u8> {
     v.extend_from_slice(&m.max_key);
     v.extend_from_slice(&m.file_size.to_le_bytes());
     v.extend_from_slice(&m.entry_count.to_le_bytes());
+    v.extend_from_slice(&m.creation_time.to_le_bytes());
+    v.extend_from_slice(&m.smallest_seqno.to_le_bytes());
+    v.extend_from_slice(&m.largest_seqno.to_le_bytes());
+    v.extend_from_slice(&m.path_id.to_le_bytes());
     v
 }
 
-fn decode_meta(data: &[u8]) -> Result<SSTableMeta> {
-    let (m, _read) = decode_meta_with_consumed(data)?;
+fn decode_meta(data: &[u8], meta_version: u8) -> Result<SSTableMeta> {
+    let (m, _read) = decode_meta_with_consumed(data, meta_version)?;
     Ok(m)
 }
 
-fn decode_meta_with_consumed(data: &[u8]) -> Result<(SSTableMeta, usize)> {
+fn decode_meta_with_consumed(data: &[u8], meta_version: u8) -> Result<(SSTableMeta, usize)> {
     let mut p = 0usize;
     if p + 8 + 4 > data.len() {
         return Err(Error::Corruption("meta too short".into()));
@@ -96,6 +184,37 @@ fn decode_meta_with_consumed(data: &[u8]) -> Result<(SSTableMeta, usize)> {
     p += 8;
     let entry_count = u64::from_le_bytes(data[p..p + 8].try_into().unwrap());
     p += 8;
+    let creation_time = if meta_version >= 2 {
+        if p + 8 > data.len() {
+            return Err(Error::Corruption("meta creation_time truncated".into()));
+        }
+        let t = u64::from_le_bytes(data[p..p + 8].try_into().unwrap());
+        p += 8;
+        t
+    } else {
+        0
+    };
+    let (smallest_seqno, largest_seqno) = if meta_version >= 3 {
+        if p + 16 > data.len() {
+            return Err(Error::Corruption("meta sequence numbers truncated".into()));
+        }
+        let smallest = u64::from_le_bytes(data[p..p + 8].try_into().unwrap());
+        let largest = u64::from_le_bytes(data[p + 8..p + 16].try_into().unwrap());
+        p += 16;
+        (smallest, largest)
+    } else {
+        (0, 0)
+    };
+    let path_id = if meta_version >= 4 {
+        if p + 4 > data.len() {
+            return Err(Error::Corruption("meta path id truncated".into()));
+        }
+        let path_id = u32::from_le_bytes(data[p..p + 4].try_into().unwrap());
+        p += 4;
+        path_id
+    } else {
+        0
+    };
 
     Ok((
         SSTableMeta {
@@ -105,6 +224,10 @@ fn decode_meta_with_consumed(data: &[u8]) -> Result<(SSTableMeta, usize)> {
             max_key,
             file_size,
             entry_count,
+            creation_time,
+            smallest_seqno,
+            largest_seqno,
+            path_id,
         },
         p,
     ))
@@ -126,7 +249,7 @@ fn encode_snapshot(version: &version::Version, log_number: u64, next_sst_id: u64
     buf
 }
 
-fn decode_snapshot(data: &[u8]) -> Result<(version::Version, u64, u64)> {
+fn decode_snapshot(data: &[u8], meta_version: u8) -> Result<(version::Version, u64, u64)> {
     let mut p = 0usize;
     if p + 8 + 8 + 4 > data.len() {
         return Err(Error::Corruption("snapshot too short".into()));
@@ -147,7 +270,7 @@ fn decode_snapshot(data: &[u8]) -> Result<(version::Version, u64, u64)> {
         p += 4;
         let mut ssts = Vec::with_capacity(num_ssts);
         for _ in 0..num_ssts {
-            let (meta, consumed) = decode_meta_with_consumed(&data[p..])?;
+            let (meta, consumed) = decode_meta_with_consumed(&data[p..], meta_version)?;
             p += consumed;
             ssts.push(meta);
         }
@@ -177,6 +300,13 @@ pub struct Manifest {
     log_number: u64,
     /// Next SSTable ID to use (max seen across all SSTableMeta + 1).
     next_sst_id: u64,
+    /// Live blob files, by file number.
+    blob_files: BTreeMap<u64, BlobFileMeta>,
+    /// Compactions recorded, by reason.
+    compaction_reasons: BTreeMap<CompactionReason, u64>,
+    /// WALs >= log_number, by number. None if the manifest predates WAL
+    /// tracking.
+    live_wals: Option<BTreeSet<u64>>,
 }
 
 impl Manifest {
@@ -202,6 +332,9 @@ impl Manifest {
         let mut parsed = 0usize;
         let mut log_number: u64 = 0;
         let mut max_sst_id: u64 = 0;
+        let mut blob_files = BTreeMap::new();
+        let mut compaction_reasons = BTreeMap::new();
+        let mut live_wals: Option<BTreeSet<u64>> = None;
 
         while offset + 4 <= data.len() {
             let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
@@ -225,10 +358,12 @@ impl Manifest {
             }
 
             // decode payload
-            match payload[0] {
-                1 => {
+            let tag = payload[0];
+            let meta_version = meta_version(tag);
+            match tag {
+                TAG_NEW_SSTABLE_V1 | TAG_NEW_SSTABLE_V2 | TAG_NEW_SSTABLE_V3 | TAG_NEW_SSTABLE => {
                     // NewSSTable
-                    let meta = decode_meta(&payload[1..])?;
+                    let meta = decode_meta(&payload[1..], meta_version)?;
                     if meta.id >= max_sst_id {
                         max_sst_id = meta.id;
                     }
@@ -239,9 +374,21 @@ impl Manifest {
                     }
                     version.levels[lvl].push(meta);
                 }
-                2 => {
+                TAG_COMPACTION_V1
+                | TAG_COMPACTION_V2
+                | TAG_COMPACTION_V3
+                | TAG_COMPACTION
+                | TAG_COMPACTION_REASON => {
                     // CompactionComplete
                     let mut p = 1usize;
+                    let mut reason = CompactionReason::Unknown;
+                    if tag == TAG_COMPACTION_REASON {
+                        let Some(&byte) = payload.get(p) else {
+                            break;
+                        };
+                        reason = CompactionReason::from_byte(byte);
+                        p += 1;
+                    }
                     if p + 4 > payload.len() {
                         break;
                     }
@@ -250,7 +397,7 @@ impl Manifest {
                     p += 4;
                     let mut added = Vec::with_capacity(added_count);
                     for _ in 0..added_count {
-                        let (m, read) = decode_meta_with_consumed(&payload[p..])?;
+                        let (m, read) = decode_meta_with_consumed(&payload[p..], meta_version)?;
                         p += read;
                         added.push(m);
                     }
@@ -276,6 +423,7 @@ impl Manifest {
                             lvl.retain(|m| m.id != *id);
                         }
                     }
+                    *compaction_reasons.entry(reason).or_insert(0) += 1;
                     // apply additions
                     for m in added.into_iter() {
                         if m.id >= max_sst_id {
@@ -288,20 +436,101 @@ impl Manifest {
                         version.levels[lvl].push(m);
                     }
                 }
-                3 => {
+                TAG_LOG_NUMBER => {
                     if payload.len() < 9 {
                         break;
                     }
                     log_number = u64::from_le_bytes(payload[1..9].try_into().unwrap());
                 }
-                4 => {
+                TAG_SNAPSHOT_V1 | TAG_SNAPSHOT_V2 | TAG_SNAPSHOT_V3 | TAG_SNAPSHOT => {
                     // VersionSnapshot — reset state to the snapshot
-                    let (snap_version, snap_log, snap_next) = decode_snapshot(&payload[1..])?;
+                    let (snap_version, snap_log, snap_next) =
+                        decode_snapshot(&payload[1..], meta_version)?;
                     version = snap_version;
                     log_number = snap_log;
                     // next_sst_id is stored as the actual next value,
                     // so max_sst_id = next_sst_id - 1
                     max_sst_id = if snap_next > 0 { snap_next - 1 } else { 0 };
+                    // compact() writes the blob files right after the snapshot
+                    blob_files.clear();
+                }
+                TAG_BLOB_FILE_ADDED => {
+                    let meta = decode_blob_file_meta(&payload[1..])?;
+                    blob_files.insert(meta.file_number, meta);
+                }
+                TAG_COMPACTION_REASONS => {
+                    // [count(4)] then [reason(1)][compactions(8)] each
+                    if payload.len() < 5 {
+                        break;
+                    }
+                    let count = u32::from_le_bytes(payload[1..5].try_into().unwrap()) as usize;
+                    if payload.len() < 5 + count * 9 {
+                        break;
+                    }
+                    for entry in payload[5..5 + count * 9].chunks_exact(9) {
+                        let reason = CompactionReason::from_byte(entry[0]);
+                        let n = u64::from_le_bytes(entry[1..9].try_into().unwrap());
+                        *compaction_reasons.entry(reason).or_insert(0) += n;
+                    }
+                }
+                TAG_WAL_ADDED => {
+                    if payload.len() < 9 {
+                        break;
+                    }
+                    live_wals
+                        .get_or_insert_default()
+                        .insert(u64::from_le_bytes(payload[1..9].try_into().unwrap()));
+                }
+                TAG_BLOB_FILE_DELETED => {
+                    if payload.len() < 9 {
+                        break;
+                    }
+                    blob_files.remove(&u64::from_le_bytes(payload[1..9].try_into().unwrap()));
+                }
+                TAG_FLUSH => {
+                    // FlushComplete: decode all of it before applying any
+                    let mut p = 1usize;
+                    if p + 4 > payload.len() {
+                        break;
+                    }
+                    let blob_count =
+                        u32::from_le_bytes(payload[p..p + 4].try_into().unwrap()) as usize;
+                    p += 4;
+                    let mut new_blob_files = Vec::with_capacity(blob_count);
+                    for _ in 0..blob_count {
+                        new_blob_files.push(decode_blob_file_meta(&payload[p..])?);
+                        p += BLOB_FILE_META_SIZE;
+                    }
+                    if p + 4 > payload.len() {
+                        break;
+                    }
+                    let added_count =
+                        u32::from_le_bytes(payload[p..p + 4].try_into().unwrap()) as usize;
+                    p += 4;
+                    let mut added = Vec::with_capacity(added_count);
+                    for _ in 0..added_count {
+                        let (m, read) = decode_meta_with_consumed(&payload[p..], meta_version)?;
+                        p += read;
+                        added.push(m);
+                    }
+                    if p + 8 > payload.len() {
+                        break;
+                    }
+                    log_number = u64::from_le_bytes(payload[p..p + 8].try_into().unwrap());
+
+                    for meta in new_blob_files {
+                        blob_files.insert(meta.file_number, meta);
+                    }
+                    for m in added {
+                        if m.id >= max_sst_id {
+                            max_sst_id = m.id;
+                        }
+                        let lvl = m.level as usize;
+                        if version.levels.len() <= lvl {
+                            version.levels.resize(lvl + 1, Vec::new());
+                        }
+                        version.levels[lvl].push(m);
+                    }
                 }
                 _ => {
                     // unknown record type — stop
@@ -311,6 +540,9 @@ impl Manifest {
 
             parsed += 1;
         }
+        if let Some(live_wals) = &mut live_wals {
+            live_wals.retain(|&number| number >= log_number);
+        }
 
         // If file was non-empty but we parsed zero valid records, treat as corruption
         if !data.is_empty() && parsed == 0 {
@@ -323,14 +555,17 @@ impl Manifest {
             current_version: version,
             log_number,
             next_sst_id: max_sst_id + 1,
+            blob_files,
+            compaction_reasons,
+            live_wals,
         })
     }
 
     /// Record that a new SSTable was created from a memtable flush.
     pub fn record_flush(&mut self, _new_sst: SSTableMeta) -> Result<()> {
-        // encode payload: [type=1][meta bytes]
+        // encode payload: [type=13][meta bytes]
         let mut payload = Vec::with_capacity(256);
-        payload.push(1u8);
+        payload.push(TAG_NEW_SSTABLE);
         payload.extend_from_slice(&encode_meta(&_new_sst));
         append_record(&mut self.file, &payload)?;
 
@@ -347,15 +582,58 @@ impl Manifest {
         Ok(())
     }
 
-    /// Record that a compaction completed.
+    /// Record a whole flush as one record: the blob files it wrote, its
+    /// SSTables (which may point into them) and the WAL number recovery
+    /// resumes from. A crash leaves either all of it or none.
+    pub fn record_atomic_flush(
+        &mut self,
+        blob_files: Vec<BlobFileMeta>,
+        added: Vec<SSTableMeta>,
+        log_number: u64,
+    ) -> Result<()> {
+        // payload: [type=16][blob_count(4)][blob files...][added_count(4)][added...][log_number(8)]
+        let mut payload = Vec::with_capacity(256);
+        payload.push(TAG_FLUSH);
+        payload.extend_from_slice(&(blob_files.len() as u32).to_le_bytes());
+        for meta in &blob_files {
+            payload.extend_from_slice(&encode_blob_file_meta(meta));
+        }
+        payload.extend_from_slice(&(added.len() as u32).to_le_bytes());
+        for m in &added {
+            payload.extend_from_slice(&encode_meta(m));
+        }
+        payload.extend_from_slice(&log_number.to_le_bytes());
+        append_record(&mut self.file, &payload)?;
+
+        for meta in blob_files {
+            self.blob_files.insert(meta.file_number, meta);
+        }
+        for m in added {
+            let new_next = m.id + 1;
+            if new_next > self.next_sst_id {
+                self.next_sst_id = new_next;
+            }
+            let lvl = m.level as usize;
+            if self.current_version.levels.len() <= lvl {
+                self.current_version.levels.resize(lvl + 1, Vec::new());
+            }
+            self.current_version.levels[lvl].push(m);
+        }
+        self.set_log_number(log_number);
+        Ok(())
+    }
+
+    /// Record that a compaction completed, and why it ran.
     pub fn record_compaction(
         &mut self,
         _added: Vec<SSTableMeta>,
         _removed: Vec<u64>,
+        reason: CompactionReason,
     ) -> Result<()> {
-        // payload: [type=2][added_count(4)][added...][removed_count(4)][removed ids...]
+        // payload: [type=17][reason(1)][added_count(4)][added...][removed_count(4)][removed ids...]
         let mut payload = Vec::with_capacity(256);
-        payload.push(2u8);
+        payload.push(TAG_COMPACTION_REASON);
+        payload.push(reason.to_byte());
         payload.extend_from_slice(&(_added.len() as u32).to_le_bytes());
         for m in _added.iter() {
             payload.extend_from_slice(&encode_meta(m));
@@ -373,6 +651,7 @@ impl Manifest {
                 lvl.retain(|m| m.id != *id);
             }
         }
+        *self.compaction_reasons.entry(reason).or_insert(0) += 1;
         // apply additions
         for m in _added.into_iter() {
             let new_next = m.id + 1;
@@ -393,13 +672,73 @@ impl Manifest {
     /// Called after each flush so recovery knows which WALs to replay.
     pub fn record_log_number(&mut self, log_number: u64) -> Result<()> {
         let mut payload = Vec::with_capacity(9);
-        payload.push(3u8);
+        payload.push(TAG_LOG_NUMBER);
         payload.extend_from_slice(&log_number.to_le_bytes());
         append_record(&mut self.file, &payload)?;
+        self.set_log_number(log_number);
+        Ok(())
+    }
+
+    /// Move the log number to `log_number`, retiring the WALs below it.
+    fn set_log_number(&mut self, log_number: u64) {
         self.log_number = log_number;
+        if let Some(live_wals) = &mut self.live_wals {
+            live_wals.retain(|&number| number >= log_number);
+        }
+    }
+
+    /// Record that WAL `number` was created, before anything is written
+    /// to it.
+    pub fn record_wal_added(&mut self, number: u64) -> Result<()> {
+        let mut payload = Vec::with_capacity(9);
+        payload.push(TAG_WAL_ADDED);
+        payload.extend_from_slice(&number.to_le_bytes());
+        append_record(&mut self.file, &payload)?;
+        if number >= self.log_number {
+            self.live_wals.get_or_insert_default().insert(number);
+        }
+        Ok(())
+    }
+
+    /// WALs holding writes not yet flushed, which recovery replays, by
+    /// number. None if the manifest has no WAL records (written before
+    /// they were tracked); recovery then replays every WAL >= log_number.
+    pub fn live_wals(&self) -> Option<&BTreeSet<u64>> {
+        self.live_wals.as_ref()
+    }
+
+    /// Record a new blob file. Must be called before recording any
+    /// SSTable that points into it.
+    pub fn record_blob_file(&mut self, meta: BlobFileMeta) -> Result<()> {
+        let mut payload = Vec::with_capacity(1 + BLOB_FILE_META_SIZE);
+        payload.push(TAG_BLOB_FILE_ADDED);
+        payload.extend_from_slice(&encode_blob_file_meta(&meta));
+        append_record(&mut self.file, &payload)?;
+        self.blob_files.insert(meta.file_number, meta);
+        Ok(())
+    }
+
+    /// Record that a blob file was garbage collected.
+    pub fn record_blob_file_deletion(&mut self, file_number: u64) -> Result<()> {
+        let mut payload = Vec::with_capacity(9);
+        payload.push(TAG_BLOB_FILE_DELETED);
+        payload.extend_from_slice(&file_number.to_le_bytes());
+        append_record(&mut self.file, &payload)?;
+        self.blob_files.remove(&file_number);
         Ok(())
     }
 
+    /// Live blob files, by file number.
+    pub fn blob_files(&self) -> &BTreeMap<u64, BlobFileMeta> {
+        &self.blob_files
+    }
+
+    /// How many compactions the manifest records for each reason.
+    /// Compactions recorded before reasons were written count as Unknown.
+    pub fn compaction_reasons(&self) -> &BTreeMap<CompactionReason, u64> {
+        &self.compaction_reasons
+    }
+
     /// The WAL number from the last flush. Recovery replays WALs >= this value.
     pub fn log_number(&self) -> u64 {
         self.log_number
@@ -417,7 +756,8 @@ impl Manifest {
 
     /// Compact the manifest: snapshot current version to a new file.
     ///
-    /// 1. Encode the entire current state as a single VersionSnapshot record
+    /// 1. Encode the entire current state as a single VersionSnapshot record,
+    ///    followed by one record per live blob file and live WAL
     /// 2. Write it to a temp file (MANIFEST.compact.tmp)
     /// 3. fsync the temp file
     /// 4. Atomically rename temp → MANIFEST (safe on POSIX)
@@ -434,13 +774,35 @@ impl Manifest {
                 .open(&tmp_path)?;
 
             let mut payload = Vec::with_capacity(256);
-            payload.push(4u8); // tag
+            payload.push(TAG_SNAPSHOT);
             payload.extend_from_slice(&encode_snapshot(
                 &self.current_version,
                 self.log_number,
                 self.next_sst_id,
             ));
             append_record(&mut tmp_file, &payload)?;
+            for meta in self.blob_files.values() {
+                let mut payload = Vec::with_capacity(1 + BLOB_FILE_META_SIZE);
+                payload.push(TAG_BLOB_FILE_ADDED);
+                payload.extend_from_slice(&encode_blob_file_meta(meta));
+                append_record(&mut tmp_file, &payload)?;
+            }
+            for number in self.live_wals.iter().flatten() {
+                let mut payload = Vec::with_capacity(9);
+                payload.push(TAG_WAL_ADDED);
+                payload.extend_from_slice(&number.to_le_bytes());
+                append_record(&mut tmp_file, &payload)?;
+            }
+            if !self.compaction_reasons.is_empty() {
+                let mut payload = Vec::with_capacity(5 + self.compaction_reasons.len() * 9);
+                payload.push(TAG_COMPACTION_REASONS);
+                payload.extend_from_slice(&(self.compaction_reasons.len() as u32).to_le_bytes());
+                for (reason, n) in &self.compaction_reasons {
+                    payload.push(reason.to_byte());
+                    payload.extend_from_slice(&n.to_le_bytes());
+                }
+                append_record(&mut tmp_file, &payload)?;
+            }
             // append_record already calls sync_all
         }
 
@@ -1,7 +1,14 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 
+use crate::blob::blob_file_path;
+use crate::delete_scheduler::DeleteScheduler;
+use crate::error::Result;
 use crate::sstable::footer::SSTableMeta;
+use crate::sstable::paths::TablePaths;
+use crate::wal::writer::{WALManager, sync_parent_dir};
 
 // TODO [M27]: Implement Version
 // TODO [M28]: Use Version in DB recovery
@@ -43,37 +50,260 @@ impl Version {
 ///
 /// Shared across threads via Arc<VersionSet>.
 /// - Readers call current() to get the RwLock, then .read() it
-/// - Compaction calls install() which .write()-locks and swaps the version
+/// - Compaction calls install() which swaps in a new version; readers
+///   still holding the old one keep seeing it
+///
+/// SSTables dropped by install() aren't deleted right away: they're
+/// queued as obsolete and delete_obsolete_files() removes them only once
+/// no live Version (current, or held by an iterator/snapshot) lists them.
+/// Garbage-collected blob files wait the same way, on every Version that
+/// existed when they were collected, since those may still point into them.
 pub struct VersionSet {
-    current: Arc<RwLock<Version>>,
+    current: RwLock<Arc<RwLock<Version>>>,
     next_sst_id: AtomicU64,
+    obsolete: Mutex<ObsoleteFiles>,
+    /// Where SSTables live; None keeps them all in the directory callers
+    /// pass in.
+    table_paths: Option<TablePaths>,
+    /// Paces deletion of obsolete files; None deletes them right away.
+    delete_scheduler: Option<Arc<DeleteScheduler>>,
+}
+
+/// Replaced versions and the SSTables they may still be keeping alive.
+#[derive(Default)]
+struct ObsoleteFiles {
+    /// Versions replaced by install(), dropped once nobody else holds them.
+    versions: Vec<Arc<RwLock<Version>>>,
+    /// IDs and path ids of SSTables no longer in the current version.
+    files: Vec<(u64, u32)>,
+    /// Garbage-collected blob files and the versions that may still
+    /// reference them.
+    blob_files: Vec<(u64, Vec<Weak<RwLock<Version>>>)>,
 }
 
 impl VersionSet {
     pub fn new(num_levels: usize) -> Self {
-        Self {
-            current: Arc::new(RwLock::new(Version::new(num_levels))),
-            next_sst_id: AtomicU64::new(1),
-        }
+        Self::new_from(Version::new(num_levels), 1)
     }
 
     /// Create a VersionSet from recovered state (manifest replay).
     pub fn new_from(version: Version, next_sst_id: u64) -> Self {
         Self {
-            current: Arc::new(RwLock::new(version)),
+            current: RwLock::new(Arc::new(RwLock::new(version))),
             next_sst_id: AtomicU64::new(next_sst_id),
+            obsolete: Mutex::new(ObsoleteFiles::default()),
+            table_paths: None,
+            delete_scheduler: None,
+        }
+    }
+
+    /// Spread SSTables over `paths` (see TablePaths).
+    pub fn with_table_paths(mut self, paths: TablePaths) -> Self {
+        self.table_paths = Some(paths);
+        self
+    }
+
+    /// Delete obsolete files through `scheduler` (see DeleteScheduler).
+    pub fn with_delete_scheduler(mut self, scheduler: Arc<DeleteScheduler>) -> Self {
+        self.delete_scheduler = Some(scheduler);
+        self
+    }
+
+    pub fn table_paths(&self) -> Option<&TablePaths> {
+        self.table_paths.as_ref()
+    }
+
+    /// File of SSTable `id` in path `path_id`, or in `dir` if no table
+    /// paths were set.
+    pub fn table_file(&self, dir: &Path, id: u64, path_id: u32) -> PathBuf {
+        match &self.table_paths {
+            Some(paths) => paths.table_file(id, path_id),
+            None => dir.join(format!("{:06}.sst", id)),
         }
     }
 
+    /// Make `new_version` current. Readers holding the previous version
+    /// keep it (and its SSTables) until they drop it.
     pub fn install(&self, new_version: Version) {
-        *self.current.write().unwrap() = new_version;
+        let new_ids: HashSet<u64> = new_version.levels.iter().flatten().map(|m| m.id).collect();
+        let old = std::mem::replace(
+            &mut *self.current.write().unwrap(),
+            Arc::new(RwLock::new(new_version)),
+        );
+
+        let removed: Vec<(u64, u32)> = old
+            .read()
+            .unwrap()
+            .levels
+            .iter()
+            .flatten()
+            .filter(|m| !new_ids.contains(&m.id))
+            .map(|m| (m.id, m.path_id))
+            .collect();
+        let mut obsolete = self.obsolete.lock().unwrap();
+        obsolete.files.extend(removed);
+        obsolete.versions.push(old);
     }
 
     pub fn current(&self) -> Arc<RwLock<Version>> {
-        Arc::clone(&self.current)
+        Arc::clone(&self.current.read().unwrap())
     }
 
     pub fn next_sst_id(&self) -> u64 {
         self.next_sst_id.fetch_add(1, Ordering::SeqCst)
     }
+
+    /// Reserve `n` consecutive SSTable ids; returns the first.
+    pub fn reserve_sst_ids(&self, n: u64) -> u64 {
+        self.next_sst_id.fetch_add(n, Ordering::SeqCst)
+    }
+
+    /// Number of replaced versions still held by a reader.
+    pub fn num_pinned_versions(&self) -> usize {
+        let mut obsolete = self.obsolete.lock().unwrap();
+        obsolete.versions.retain(|v| Arc::strong_count(v) > 1);
+        obsolete.versions.len()
+    }
+
+    /// Take the obsolete SSTables no live version references any more.
+    /// Each ID is returned once; the caller deletes the files.
+    pub fn take_deletable_files(&self) -> Vec<u64> {
+        self.take_deletable_tables()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// take_deletable_files(), with the path id of each table.
+    fn take_deletable_tables(&self) -> Vec<(u64, u32)> {
+        let mut obsolete = self.obsolete.lock().unwrap();
+        // Only the list itself holds these → no reader can reach them
+        obsolete.versions.retain(|v| Arc::strong_count(v) > 1);
+
+        let mut live: HashSet<u64> = HashSet::new();
+        for version in obsolete.versions.iter().chain([&self.current()]) {
+            live.extend(
+                version
+                    .read()
+                    .unwrap()
+                    .levels
+                    .iter()
+                    .flatten()
+                    .map(|m| m.id),
+            );
+        }
+
+        let (deletable, pending) = obsolete
+            .files
+            .iter()
+            .partition(|(id, _)| !live.contains(id));
+        obsolete.files = pending;
+        deletable
+    }
+
+    /// Queue blob file `file_number` for deletion once every version that
+    /// exists now, the current one included, has been replaced and released.
+    pub fn add_obsolete_blob_file(&self, file_number: u64) {
+        let mut obsolete = self.obsolete.lock().unwrap();
+        obsolete.versions.retain(|v| Arc::strong_count(v) > 1);
+        let holders = obsolete
+            .versions
+            .iter()
+            .chain([&self.current()])
+            .map(Arc::downgrade)
+            .collect();
+        obsolete.blob_files.push((file_number, holders));
+    }
+
+    /// Take the obsolete blob files no version can reach any more.
+    /// Each number is returned once; the caller deletes the files.
+    pub fn take_deletable_blob_files(&self) -> Vec<u64> {
+        let mut obsolete = self.obsolete.lock().unwrap();
+        let current = self.current();
+        // A replaced version held only by the obsolete list is unreachable;
+        // one dropped from the list is gone.
+        let reachable = |v: &Weak<RwLock<Version>>| {
+            std::ptr::eq(v.as_ptr(), Arc::as_ptr(&current)) || v.strong_count() > 1
+        };
+        let (deletable, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut obsolete.blob_files)
+            .into_iter()
+            .partition(|(_, holders)| !holders.iter().any(reachable));
+        obsolete.blob_files = pending;
+        deletable.into_iter().map(|(number, _)| number).collect()
+    }
+
+    /// Delete the SSTable and blob files that take_deletable_files() and
+    /// take_deletable_blob_files() free. Blob files are in `dir`, and so
+    /// are SSTables unless table paths were set.
+    pub fn delete_obsolete_files(&self, dir: &Path) {
+        for (id, path_id) in self.take_deletable_tables() {
+            self.delete_file(&self.table_file(dir, id, path_id));
+        }
+        for number in self.take_deletable_blob_files() {
+            self.delete_file(&blob_file_path(dir, number));
+        }
+    }
+
+    /// Delete an obsolete file, through the delete scheduler if one was
+    /// set. Errors are ignored: at worst the file is left behind.
+    pub fn delete_file(&self, path: &Path) {
+        let _ = match &self.delete_scheduler {
+            Some(scheduler) => scheduler.delete_file(path),
+            None => std::fs::remove_file(path),
+        };
+    }
+
+    /// Delete an obsolete WAL like delete_file(), then fsync its directory
+    /// as WALManager::delete_wal() does, so a crash can't bring it back.
+    pub fn delete_wal(&self, path: &Path) -> Result<()> {
+        match &self.delete_scheduler {
+            Some(scheduler) => {
+                scheduler.delete_file(path)?;
+                sync_parent_dir(path)
+            }
+            None => WALManager::delete_wal(path),
+        }
+    }
+}
+
+/// A reader's hold on one Version.
+///
+/// While it's alive, SSTables in the version survive compaction even if
+/// a newer version no longer lists them. Dropping it releases the hold
+/// and deletes any obsolete files that were only waiting on it.
+pub struct PinnedVersion {
+    version: Option<Arc<RwLock<Version>>>,
+    version_set: Arc<VersionSet>,
+    /// Directory holding the SSTable files.
+    path: PathBuf,
+}
+
+impl PinnedVersion {
+    /// Pin `version` (normally one obtained from `version_set`).
+    pub fn new(version_set: Arc<VersionSet>, version: Arc<RwLock<Version>>, path: PathBuf) -> Self {
+        Self {
+            version: Some(version),
+            version_set,
+            path,
+        }
+    }
+
+    /// Pin the current version of `version_set`.
+    pub fn current(version_set: Arc<VersionSet>, path: PathBuf) -> Self {
+        let version = version_set.current();
+        Self::new(version_set, version, path)
+    }
+
+    /// The pinned version.
+    pub fn version(&self) -> &Arc<RwLock<Version>> {
+        self.version.as_ref().unwrap()
+    }
+}
+
+impl Drop for PinnedVersion {
+    fn drop(&mut self) {
+        // Release our reference first so it no longer counts as live
+        self.version.take();
+        self.version_set.delete_obsolete_files(&self.path);
+    }
 }
@@ -0,0 +1,84 @@
+use bytes::{Bytes, BytesMut};
+
+/// Size of the chunks an Arena carves keys and values out of.
+pub const ARENA_CHUNK_SIZE: usize = 4096;
+
+/// Bump allocator for a skip list's nodes.
+///
+/// Keys and values are copied back to back into shared chunks instead of
+/// each getting its own heap allocation, so neighbouring entries share
+/// cache lines and the allocator is hit once per chunk. Data of a quarter
+/// chunk or more gets a chunk to itself, so one big value doesn't waste
+/// most of a chunk. Each slice handed out is a Bytes over its chunk: a
+/// pinned value keeps that chunk alive, nothing else.
+///
+/// Node towers (forward pointers) come from one shared slab, addressed by
+/// the index of their first link.
+///
+/// Nothing is ever freed: allocated() only grows, and is the exact number
+/// of bytes handed out.
+pub struct Arena {
+    /// Unused rest of the current chunk.
+    chunk: BytesMut,
+    /// Every node's forward pointers, tower after tower.
+    links: Vec<Option<usize>>,
+    allocated: usize,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Arena {
+    pub fn new() -> Self {
+        Arena {
+            chunk: BytesMut::new(),
+            links: Vec::new(),
+            allocated: 0,
+        }
+    }
+
+    /// Copy `data` into the arena.
+    pub fn alloc(&mut self, data: &[u8]) -> Bytes {
+        if data.is_empty() {
+            return Bytes::new();
+        }
+        self.allocated += data.len();
+        if data.len() >= ARENA_CHUNK_SIZE / 4 {
+            return Bytes::copy_from_slice(data);
+        }
+        if self.chunk.capacity() < data.len() {
+            self.chunk = BytesMut::with_capacity(ARENA_CHUNK_SIZE);
+        }
+        self.chunk.extend_from_slice(data);
+        self.chunk.split().freeze()
+    }
+
+    /// Allocate a tower of `height` empty links, returning the index of
+    /// its first. Links of the head tower, allocated before anything else
+    /// with `counted` false, don't count towards allocated().
+    pub fn alloc_tower(&mut self, height: usize, counted: bool) -> usize {
+        let tower = self.links.len();
+        self.links.resize(tower + height, None);
+        if counted {
+            self.allocated += height * std::mem::size_of::<Option<usize>>();
+        }
+        tower
+    }
+
+    /// Link `level` of the tower at `tower`.
+    pub fn link(&self, tower: usize, level: usize) -> Option<usize> {
+        self.links[tower + level]
+    }
+
+    pub fn set_link(&mut self, tower: usize, level: usize, next: Option<usize>) {
+        self.links[tower + level] = next;
+    }
+
+    /// Bytes handed out: keys, values and tower links.
+    pub fn allocated(&self) -> usize {
+        self.allocated
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use crate::bloom::BloomFilter;
+use crate::db::prefix::SliceTransform;
+
+/// Probes per key: a memtable filter is sized by memory, not key count,
+/// so this is fixed, as in RocksDB.
+const NUM_PROBES: u32 = 6;
+
+/// Bloom filter over the keys written to a memtable, so a point lookup
+/// of a key it doesn't hold skips the skip list search.
+///
+/// Holds whole keys, key prefixes (per a SliceTransform), or both. With
+/// only prefixes, a key outside the extractor's domain can't be ruled
+/// out. Deletes are added like puts: a tombstone must still be found.
+///
+/// A filter is built empty and cloned for each new memtable.
+#[derive(Clone)]
+pub struct MemTableFilter {
+    bloom: BloomFilter,
+    prefix_extractor: Option<Arc<dyn SliceTransform>>,
+    whole_key: bool,
+}
+
+impl MemTableFilter {
+    /// An empty filter of `num_bits` bits holding the prefixes
+    /// `prefix_extractor` gives, if any, and whole keys if `whole_key`
+    /// or there's no extractor.
+    pub fn new(
+        num_bits: u32,
+        prefix_extractor: Option<Arc<dyn SliceTransform>>,
+        whole_key: bool,
+    ) -> Self {
+        MemTableFilter {
+            bloom: BloomFilter::with_num_bits(num_bits, NUM_PROBES),
+            whole_key: whole_key || prefix_extractor.is_none(),
+            prefix_extractor,
+        }
+    }
+
+    /// Note that `key` was written.
+    pub fn insert(&mut self, key: &[u8]) {
+        if self.whole_key {
+            self.bloom.insert(key);
+        }
+        if let Some(extractor) = &self.prefix_extractor
+            && extractor.in_domain(key)
+        {
+            self.bloom.insert(extractor.transform(key));
+        }
+    }
+
+    /// false if `key` was definitely never written; true if it may have
+    /// been.
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        if self.whole_key {
+            return self.bloom.may_contain(key);
+        }
+        match &self.prefix_extractor {
+            Some(extractor) if extractor.in_domain(key) => {
+                self.bloom.may_contain(extractor.transform(key))
+            }
+            _ => true,
+        }
+    }
+
+    /// Size of the filter's bit array in bytes.
+    pub fn size_bytes(&self) -> usize {
+        (self.bloom.num_bits() as usize).div_ceil(64) * 8
+    }
+}
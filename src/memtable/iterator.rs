@@ -1,32 +1,542 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+
 use crate::error::Result;
 use crate::iterator::StorageIterator;
+use crate::memtable::MemTable;
+use crate::memtable::rep::MemTableRepIterator;
+use crate::types::ValueType;
+
+/// Entries a SharedMemTableIterator reads each time it takes the lock.
+const SHARED_READ_AHEAD: usize = 64;
+
+/// Iterator over memtable entries in sorted order, tombstones included.
+/// Wraps whichever iterator the memtable's MemTableRep gives and
+/// implements StorageIterator.
+///
+/// Yields one entry per key: its newest version written before the
+/// sequence number the iterator reads at, so writes made after that are
+/// invisible. A Delete comes out as an empty value, as in SSTables.
+///
+/// With bounds (MemTable::range_iter), only keys in [lower, upper) are
+/// yielded: seeks clamp to them and stepping past one leaves the iterator
+/// invalid, so nothing outside the range is ever visited.
+///
+/// Memtable iteration can't fail, so the inherent advance() and seek_to()
+/// skip the Result.
+pub struct MemTableIterator<'a> {
+    inner: Box<dyn MemTableRepIterator + 'a>,
+    /// Versions with this sequence number or higher are skipped.
+    sequence: u64,
+    /// Key of the current entry, while stepping past its older versions.
+    key: Vec<u8>,
+    lower: Option<Vec<u8>>,
+    upper: Option<Vec<u8>>,
+}
+
+impl<'a> MemTableIterator<'a> {
+    /// Wrap `inner`, reading at `sequence` within [lower, upper) (`None`
+    /// leaving that side open), positioned at its first visible entry.
+    pub(crate) fn new(
+        inner: Box<dyn MemTableRepIterator + 'a>,
+        sequence: u64,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Self {
+        let mut iter = MemTableIterator {
+            inner,
+            sequence,
+            key: Vec::new(),
+            lower: lower.map(<[u8]>::to_vec),
+            upper: upper.map(<[u8]>::to_vec),
+        };
+        let _ = iter.seek_to_first();
+        iter
+    }
+
+    /// Returns true if iterator is at a valid position.
+    pub fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+            && self
+                .lower
+                .as_ref()
+                .is_none_or(|lower| self.inner.key() >= lower.as_slice())
+            && self
+                .upper
+                .as_ref()
+                .is_none_or(|upper| self.inner.key() < upper.as_slice())
+    }
+
+    /// Returns the key at current position.
+    /// Panics if iterator is not valid.
+    pub fn key(&self) -> &[u8] {
+        self.inner.key()
+    }
+
+    /// Returns the value at current position.
+    /// Panics if iterator is not valid.
+    pub fn value(&self) -> &[u8] {
+        self.inner.value()
+    }
+
+    /// Sequence number of the version at current position.
+    /// Panics if iterator is not valid.
+    pub fn sequence(&self) -> u64 {
+        self.inner.sequence()
+    }
+
+    /// Type of the version at current position.
+    /// Panics if iterator is not valid.
+    pub fn value_type(&self) -> ValueType {
+        self.inner.value_type()
+    }
+
+    /// Key at current position, sharing the memtable's buffer.
+    /// Panics if iterator is not valid.
+    pub fn pinned_key(&self) -> Bytes {
+        self.inner.pinned_key()
+    }
+
+    /// Value at current position, sharing the memtable's buffer.
+    /// Panics if iterator is not valid.
+    pub fn pinned_value(&self) -> Bytes {
+        self.inner.pinned_value()
+    }
+
+    /// Advances to the next entry.
+    pub fn advance(&mut self) {
+        let _ = self.next();
+    }
+
+    /// Seek to the first key >= target.
+    pub fn seek_to(&mut self, target: &[u8]) {
+        let _ = self.seek(target);
+    }
+
+    /// From the newest version of a key, skip versions too new to see,
+    /// moving on to later keys if none of a key's is visible.
+    fn skip_invisible(&mut self) -> Result<()> {
+        while self.inner.is_valid() && self.inner.sequence() >= self.sequence {
+            self.inner.next()?;
+        }
+        Ok(())
+    }
+
+    /// From the oldest version of a key, find the newest visible version
+    /// of it or, if none is, of the closest earlier key with one.
+    fn settle_backward(&mut self) -> Result<()> {
+        while self.inner.is_valid() {
+            self.key.clear();
+            self.key.extend_from_slice(self.inner.key());
+            self.inner.seek(&self.key)?;
+            while self.inner.is_valid()
+                && self.inner.key() == self.key.as_slice()
+                && self.inner.sequence() >= self.sequence
+            {
+                self.inner.next()?;
+            }
+            if self.inner.is_valid() && self.inner.key() == self.key.as_slice() {
+                return Ok(());
+            }
+            // Nothing visible: on to the key before
+            self.inner.seek(&self.key)?;
+            self.inner.prev()?;
+        }
+        Ok(())
+    }
+}
+
+impl StorageIterator for MemTableIterator<'_> {
+    fn key(&self) -> &[u8] {
+        self.inner.key()
+    }
+
+    fn value(&self) -> &[u8] {
+        self.inner.value()
+    }
+
+    fn seqno(&self) -> Option<u64> {
+        Some(self.sequence())
+    }
+
+    fn is_valid(&self) -> bool {
+        MemTableIterator::is_valid(self)
+    }
+
+    fn next(&mut self) -> Result<()> {
+        if !self.is_valid() {
+            return Ok(());
+        }
+        self.key.clear();
+        self.key.extend_from_slice(self.inner.key());
+        self.inner.next()?;
+        while self.inner.is_valid() && self.inner.key() == self.key.as_slice() {
+            self.inner.next()?;
+        }
+        self.skip_invisible()
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        match &self.lower {
+            Some(lower) if key < lower.as_slice() => self.inner.seek(lower)?,
+            _ => self.inner.seek(key)?,
+        }
+        self.skip_invisible()
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        if !self.is_valid() {
+            return Ok(());
+        }
+        // Back past the key's newer versions, to the key before
+        self.key.clear();
+        self.key.extend_from_slice(self.inner.key());
+        while self.inner.is_valid() && self.inner.key() == self.key.as_slice() {
+            self.inner.prev()?;
+        }
+        self.settle_backward()
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        match &self.upper {
+            Some(upper) if key >= upper.as_slice() => self.seek_to_last(),
+            _ => {
+                self.inner.seek_for_prev(key)?;
+                self.settle_backward()
+            }
+        }
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        match &self.lower {
+            Some(lower) => self.inner.seek(lower)?,
+            None => self.inner.seek_to_first()?,
+        }
+        self.skip_invisible()
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        match &self.upper {
+            // The last key below the bound: before the first at or past it
+            Some(upper) => {
+                self.inner.seek(upper)?;
+                if self.inner.is_valid() {
+                    self.inner.prev()?;
+                } else {
+                    self.inner.seek_to_last()?;
+                }
+            }
+            None => self.inner.seek_to_last()?,
+        }
+        self.settle_backward()
+    }
+}
 
-// TODO [M04]: Implement MemTableIterator wrapping SkipListIterator
+/// Iterator over a memtable shared as an Arc<MemTable>, for readers that
+/// outlive any borrow of it (DBIterator, Scanner). Yields what a
+/// MemTableIterator over it as of the same sequence number and bounds
+/// would, seqno() included.
+///
+/// No lock is held between calls, so writers and flushes never wait on
+/// it: a step that runs out of entries read so far takes the read lock,
+/// finds its place again from the last key and reads up to
+/// SHARED_READ_AHEAD entries on in its direction. Keys and values are
+/// pinned, sharing the memtable's buffers rather than copying them.
+/// Writes made meanwhile carry sequence numbers it doesn't see.
+pub struct SharedMemTableIterator {
+    memtable: Arc<MemTable>,
+    /// Versions with this sequence number or higher are skipped.
+    sequence: u64,
+    lower: Option<Vec<u8>>,
+    upper: Option<Vec<u8>>,
+    /// Entries read ahead, in key order: key, value, sequence number.
+    /// Empty when the iterator is invalid.
+    entries: Vec<(Bytes, Bytes, u64)>,
+    /// Index of the current entry.
+    pos: usize,
+}
 
-/// Iterator over memtable entries in sorted order.
-/// Wraps the skip list iterator and implements StorageIterator.
-pub struct MemTableIterator {
-    // TODO [M04]: Fields — wrap SkipListIterator
+impl SharedMemTableIterator {
+    /// Iterate over `memtable` as of `sequence` within [lower, upper)
+    /// (`None` leaving that side open), positioned at its first visible
+    /// entry.
+    pub(crate) fn new(
+        memtable: Arc<MemTable>,
+        sequence: u64,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Self {
+        let mut iter = SharedMemTableIterator {
+            memtable,
+            sequence,
+            lower: lower.map(<[u8]>::to_vec),
+            upper: upper.map(<[u8]>::to_vec),
+            entries: Vec::new(),
+            pos: 0,
+        };
+        let _ = iter.seek_to_first();
+        iter
+    }
+
+    /// Under the read lock, position a MemTableIterator with `position`
+    /// and read up to SHARED_READ_AHEAD entries from there, on forward or
+    /// backward, as the current ones.
+    fn read_ahead(&mut self, forward: bool, position: impl FnOnce(&mut MemTableIterator<'_>)) {
+        let memtable = self.memtable.read();
+        let mut iter =
+            memtable.range_iter_at(self.lower.as_deref(), self.upper.as_deref(), self.sequence);
+        position(&mut iter);
+        self.entries.clear();
+        while iter.is_valid() && self.entries.len() < SHARED_READ_AHEAD {
+            self.entries
+                .push((iter.pinned_key(), iter.pinned_value(), iter.sequence()));
+            if forward {
+                iter.advance();
+            } else {
+                let _ = iter.prev();
+            }
+        }
+        if forward {
+            self.pos = 0;
+        } else {
+            self.entries.reverse();
+            self.pos = self.entries.len().saturating_sub(1);
+        }
+    }
 }
 
-impl StorageIterator for MemTableIterator {
+impl StorageIterator for SharedMemTableIterator {
     fn key(&self) -> &[u8] {
-        todo!("[M04]: Delegate to skip list iterator")
+        &self.entries[self.pos].0
     }
 
     fn value(&self) -> &[u8] {
-        todo!("[M04]: Delegate to skip list iterator")
+        &self.entries[self.pos].1
+    }
+
+    fn seqno(&self) -> Option<u64> {
+        Some(self.entries[self.pos].2)
     }
 
     fn is_valid(&self) -> bool {
-        todo!("[M04]: Delegate to skip list iterator")
+        !self.entries.is_empty()
     }
 
     fn next(&mut self) -> Result<()> {
-        todo!("[M04]: Advance skip list iterator")
+        if self.pos + 1 < self.entries.len() {
+            self.pos += 1;
+        } else if self.is_valid() {
+            let last = self.entries[self.pos].0.clone();
+            self.read_ahead(true, |iter| {
+                iter.seek_to(&last);
+                if iter.is_valid() && iter.key() == last.as_ref() {
+                    iter.advance();
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.read_ahead(true, |iter| iter.seek_to(key));
+        Ok(())
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        if self.pos > 0 {
+            self.pos -= 1;
+        } else if self.is_valid() {
+            let first = self.entries[0].0.clone();
+            self.read_ahead(false, |iter| {
+                let _ = iter.seek_for_prev(&first);
+                if iter.is_valid() && iter.key() == first.as_ref() {
+                    let _ = iter.prev();
+                }
+            });
+        }
+        Ok(())
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.read_ahead(false, |iter| {
+            let _ = iter.seek_for_prev(key);
+        });
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        // A new MemTableIterator starts at its first entry
+        self.read_ahead(true, |_| {});
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.read_ahead(false, |iter| {
+            let _ = iter.seek_to_last();
+        });
+        Ok(())
+    }
+}
+
+/// Iterator over every version a memtable shared as an Arc<MemTable>
+/// holds, for DB::raw_iter(): shadowed versions and tombstones included,
+/// in InternalKey order (key ascending, then sequence number descending),
+/// as MemTableInner::versions() gives them. Versions with a sequence
+/// number at or past the one it reads at are skipped.
+///
+/// Like SharedMemTableIterator it holds no lock between calls, reading up
+/// to SHARED_READ_AHEAD versions each time it takes the read lock, and
+/// finds its place again from the last (key, sequence number) it read.
+/// key() is the user key, seqno() the version's sequence number and
+/// value_type() its type.
+pub struct SharedVersionIterator {
+    memtable: Arc<MemTable>,
+    /// Versions with this sequence number or higher are skipped.
+    sequence: u64,
+    /// Versions read ahead, in InternalKey order: key, value, sequence
+    /// number, type. Empty when the iterator is invalid.
+    entries: Vec<(Bytes, Bytes, u64, ValueType)>,
+    /// Index of the current version.
+    pos: usize,
+}
+
+impl SharedVersionIterator {
+    /// Iterate over the versions of `memtable` written before `sequence`,
+    /// positioned at the first.
+    pub(crate) fn new(memtable: Arc<MemTable>, sequence: u64) -> Self {
+        let mut iter = SharedVersionIterator {
+            memtable,
+            sequence,
+            entries: Vec::new(),
+            pos: 0,
+        };
+        let _ = iter.seek_to_first();
+        iter
+    }
+
+    /// Type of the version at current position.
+    /// Panics if iterator is not valid.
+    pub fn value_type(&self) -> ValueType {
+        self.entries[self.pos].3
+    }
+
+    /// Under the read lock, position the memtable's versions with
+    /// `position` and read up to SHARED_READ_AHEAD visible ones from
+    /// there, on forward or backward, as the current ones.
+    fn read_ahead(
+        &mut self,
+        forward: bool,
+        position: impl FnOnce(&mut dyn MemTableRepIterator) -> Result<()>,
+    ) -> Result<()> {
+        let memtable = self.memtable.read();
+        let mut iter = memtable.versions();
+        position(iter.as_mut())?;
+        self.entries.clear();
+        while iter.is_valid() && self.entries.len() < SHARED_READ_AHEAD {
+            if iter.sequence() < self.sequence {
+                self.entries.push((
+                    iter.pinned_key(),
+                    iter.pinned_value(),
+                    iter.sequence(),
+                    iter.value_type(),
+                ));
+            }
+            if forward {
+                iter.next()?;
+            } else {
+                iter.prev()?;
+            }
+        }
+        if forward {
+            self.pos = 0;
+        } else {
+            self.entries.reverse();
+            self.pos = self.entries.len().saturating_sub(1);
+        }
+        Ok(())
+    }
+}
+
+/// Move `iter` to version (`key`, `sequence`), or to the first version
+/// after it if that one is gone (superseded meanwhile).
+fn seek_version(iter: &mut dyn MemTableRepIterator, key: &[u8], sequence: u64) -> Result<()> {
+    iter.seek(key)?;
+    while iter.is_valid() && iter.key() == key && iter.sequence() > sequence {
+        iter.next()?;
+    }
+    Ok(())
+}
+
+impl StorageIterator for SharedVersionIterator {
+    fn key(&self) -> &[u8] {
+        &self.entries[self.pos].0
+    }
+
+    fn value(&self) -> &[u8] {
+        &self.entries[self.pos].1
+    }
+
+    fn seqno(&self) -> Option<u64> {
+        Some(self.entries[self.pos].2)
+    }
+
+    fn is_valid(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    fn next(&mut self) -> Result<()> {
+        if self.pos + 1 < self.entries.len() {
+            self.pos += 1;
+            return Ok(());
+        }
+        if !self.is_valid() {
+            return Ok(());
+        }
+        let (last, _, sequence, _) = self.entries[self.pos].clone();
+        self.read_ahead(true, |iter| {
+            seek_version(iter, &last, sequence)?;
+            if iter.is_valid() && iter.key() == last.as_ref() && iter.sequence() == sequence {
+                iter.next()?;
+            }
+            Ok(())
+        })
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.read_ahead(true, |iter| iter.seek(key))
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        if self.pos > 0 {
+            self.pos -= 1;
+            return Ok(());
+        }
+        if !self.is_valid() {
+            return Ok(());
+        }
+        let (first, _, sequence, _) = self.entries[0].clone();
+        self.read_ahead(false, |iter| {
+            // Back from the version, or from the first after it if it's
+            // gone
+            seek_version(iter, &first, sequence)?;
+            if iter.is_valid() {
+                iter.prev()
+            } else {
+                iter.seek_to_last()
+            }
+        })
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.read_ahead(false, |iter| iter.seek_for_prev(key))
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.read_ahead(true, |iter| iter.seek_to_first())
     }
 
-    fn seek(&mut self, _key: &[u8]) -> Result<()> {
-        todo!("[M04]: Seek in skip list")
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.read_ahead(false, |iter| iter.seek_to_last())
     }
 }
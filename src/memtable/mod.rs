@@ -1,40 +1,319 @@
+pub mod arena;
+pub mod filter;
+pub mod iterator;
+pub mod rep;
 pub mod skiplist;
 
-use skiplist::{SkipList, SkipListIterator};
-use std::sync::RwLock;
+use bytes::Bytes;
+use filter::MemTableFilter;
+use iterator::{MemTableIterator, SharedMemTableIterator, SharedVersionIterator};
+use rep::{MemTableRep, MemTableRepIterator, WriteOutcome};
+use skiplist::SkipList;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-// TODO [M04]: Implement MemTable API
-// TODO [M05]: Add concurrent access with Arc<RwLock<MemTable>>
+use crate::iterator::StorageIterator;
+use crate::types::ValueType;
 
-/// In-memory sorted buffer for writes. Wraps a SkipList.
+/// In-memory sorted buffer for writes. Wraps a MemTableRep, a SkipList
+/// unless created with another.
 ///
 /// Every write goes here first. When size exceeds the threshold,
 /// the memtable is frozen (becomes immutable) and flushed to an SSTable.
 ///
-/// Deletes are handled via tombstones — an empty value that means
-/// "this key is deleted." You can't just remove the key because older
-/// versions may exist in SSTables on disk.
+/// Each write adds a version of its key: the key, the write's sequence
+/// number and its ValueType. add() never overwrites a version, so a read
+/// as of a sequence number (a snapshot, an iterator) sees exactly the
+/// writes made before it; the DB writes through it.
+///
+/// Deletes are handled via tombstones — a Delete version that means "this
+/// key is deleted." You can't just remove the key because older versions
+/// may exist in SSTables on disk.
+///
+/// With a MemTableFilter, lookups of keys never written skip the skip
+/// list.
+///
+/// A MemTable is shared as an Arc<MemTable>: it synchronizes itself, with
+/// a lock around its MemTableInner. Writes and reads returning owned data
+/// take it for the one call; reads that borrow from the memtable (get(),
+/// the iterators) go through the guard read() returns.
 pub struct MemTable {
-    data: SkipList,
-    size_limit: usize,
+    inner: RwLock<MemTableInner>,
 }
 
 impl MemTable {
     /// Create a new empty memtable with given size limit.
     pub fn new(size_limit: usize) -> Self {
+        Self::with_filter(size_limit, None)
+    }
+
+    /// Create a new empty memtable whose lookups go through `filter`
+    /// first, if any.
+    pub fn with_filter(size_limit: usize, filter: Option<MemTableFilter>) -> Self {
+        Self::with_rep(size_limit, Box::new(SkipList::new()), filter)
+    }
+
+    /// Create a new memtable storing its entries in `rep`, which must be
+    /// empty, with lookups going through `filter` first, if any.
+    pub fn with_rep(
+        size_limit: usize,
+        rep: Box<dyn MemTableRep>,
+        filter: Option<MemTableFilter>,
+    ) -> Self {
         MemTable {
-            data: SkipList::new(),
-            size_limit,
+            inner: RwLock::new(MemTableInner {
+                data: rep,
+                size_limit,
+                filter,
+                seqno_range: None,
+                stats: MemTableStats::default(),
+                stats_exact: true,
+            }),
         }
     }
 
-    /// Insert or update a key-value pair.
+    /// Lock the memtable for reading, for as long as the guard lives. A
+    /// guard kept through a long scan holds up writes and
+    /// mark_read_only(), and the readers queued behind them.
+    pub fn read(&self) -> RwLockReadGuard<'_, MemTableInner> {
+        self.inner.read().unwrap()
+    }
+
+    /// Lock the memtable for writing, for as long as the guard lives:
+    /// nothing reads it in between several writes.
+    pub fn write(&self) -> RwLockWriteGuard<'_, MemTableInner> {
+        self.inner.write().unwrap()
+    }
+
+    /// See MemTableInner::add.
+    pub fn add(&self, sequence: u64, value_type: ValueType, key: Vec<u8>, value: Vec<u8>) {
+        self.write().add(sequence, value_type, key, value);
+    }
+
+    /// See MemTableInner::supersede.
+    pub fn supersede(&self, sequence: u64, value_type: ValueType, key: Vec<u8>, value: Vec<u8>) {
+        self.write().supersede(sequence, value_type, key, value);
+    }
+
+    /// See MemTableInner::put.
+    pub fn put(&self, key: Vec<u8>, value: Vec<u8>) {
+        self.write().put(key, value);
+    }
+
+    /// See MemTableInner::delete.
+    pub fn delete(&self, key: Vec<u8>) {
+        self.write().delete(key);
+    }
+
+    /// See MemTableInner::record_seqno.
+    pub fn record_seqno(&self, seq: u64) {
+        self.write().record_seqno(seq);
+    }
+
+    /// See MemTableInner::mark_read_only. Waits for reads holding the
+    /// lock to finish, so is left to the flush rather than done at freeze.
+    pub fn mark_read_only(&self) {
+        self.write().mark_read_only();
+    }
+
+    /// See MemTableInner::may_contain.
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.read().may_contain(key)
+    }
+
+    /// See MemTableInner::get_pinned.
+    pub fn get_pinned(&self, key: &[u8]) -> Option<Bytes> {
+        self.read().get_pinned(key)
+    }
+
+    /// See MemTableInner::get_version.
+    pub fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        self.read().get_version(key, sequence)
+    }
+
+    /// See MemTableInner::seqno_range.
+    pub fn seqno_range(&self) -> Option<(u64, u64)> {
+        self.read().seqno_range()
+    }
+
+    /// Like MemTableInner::range_iter_at, but holding on to the memtable
+    /// rather than a read guard: see SharedMemTableIterator.
+    pub fn shared_iter_at(
+        self: &Arc<Self>,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        sequence: u64,
+    ) -> SharedMemTableIterator {
+        SharedMemTableIterator::new(Arc::clone(self), sequence, lower, upper)
+    }
+
+    /// Like MemTableInner::versions, but only those written before
+    /// `sequence`, and holding on to the memtable rather than a read
+    /// guard: see SharedVersionIterator.
+    pub fn shared_versions_at(self: &Arc<Self>, sequence: u64) -> SharedVersionIterator {
+        SharedVersionIterator::new(Arc::clone(self), sequence)
+    }
+
+    /// See MemTableInner::count_in_range.
+    pub fn count_in_range(&self, start: &[u8], end: &[u8]) -> usize {
+        self.read().count_in_range(start, end)
+    }
+
+    /// See MemTableInner::stats.
+    pub fn stats(&self) -> MemTableStats {
+        self.read().stats()
+    }
+
+    /// See MemTableInner::size.
+    pub fn size(&self) -> usize {
+        self.read().size()
+    }
+
+    /// See MemTableInner::garbage_bytes.
+    pub fn garbage_bytes(&self) -> usize {
+        self.read().garbage_bytes()
+    }
+
+    /// See MemTableInner::is_full.
+    pub fn is_full(&self) -> bool {
+        self.read().is_full()
+    }
+
+    /// See MemTableInner::len.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// See MemTableInner::is_empty.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+}
+
+/// A MemTable's contents, reached through the guards of MemTable::read()
+/// and MemTable::write().
+pub struct MemTableInner {
+    data: Box<dyn MemTableRep>,
+    size_limit: usize,
+    filter: Option<MemTableFilter>,
+    /// Smallest and largest sequence number recorded; None until the
+    /// first.
+    seqno_range: Option<(u64, u64)>,
+    /// Kept up to date by every write, from the rep's WriteOutcome.
+    stats: MemTableStats,
+    /// False once the rep couldn't tell what a write replaced, until
+    /// mark_read_only() recounts.
+    stats_exact: bool,
+}
+
+impl MemTableInner {
+    /// Add the write with sequence number `sequence` of `key`: a Put of
+    /// `value`, or a Delete. An empty value reads as a tombstone once
+    /// flushed, so a Put of one is stored as a Delete.
+    pub fn add(&mut self, sequence: u64, value_type: ValueType, key: Vec<u8>, value: Vec<u8>) {
+        self.insert(sequence, value_type, key, value, false);
+        self.record_seqno(sequence);
+    }
+
+    /// Like add(), but in place of the key's newest version, if any (see
+    /// MemTableRep::supersede), so that rewriting a hot key doesn't take a
+    /// new entry each time. Only for writers whose readers never read as
+    /// of an older sequence number: the replaced version is gone, from
+    /// snapshots and versions() alike.
+    pub fn supersede(
+        &mut self,
+        sequence: u64,
+        value_type: ValueType,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) {
+        self.insert(sequence, value_type, key, value, true);
+        self.record_seqno(sequence);
+    }
+
+    /// Insert or update a key-value pair, as sequence number 0: a plain
+    /// map for callers not tracking versions. The DB writes through add().
     pub fn put(&mut self, key: Vec<u8>, value: Vec<u8>) {
-        self.data.insert(key, value);
+        self.insert(0, ValueType::Put, key, value, false);
+    }
+
+    fn insert(
+        &mut self,
+        sequence: u64,
+        value_type: ValueType,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        supersede: bool,
+    ) {
+        if let Some(filter) = &mut self.filter {
+            filter.insert(&key);
+        }
+        let (value_type, value) = match value_type {
+            ValueType::Put if !value.is_empty() => (ValueType::Put, value),
+            _ => (ValueType::Delete, Vec::new()),
+        };
+        // A key already in lies within the range, so checking every
+        // write's is harmless
+        let stats = &mut self.stats;
+        match &mut stats.min_key {
+            Some(min) if min.as_slice() <= key.as_slice() => {}
+            Some(min) => {
+                min.clear();
+                min.extend_from_slice(&key);
+            }
+            None => stats.min_key = Some(key.clone()),
+        }
+        match &mut stats.max_key {
+            Some(max) if max.as_slice() >= key.as_slice() => {}
+            Some(max) => {
+                max.clear();
+                max.extend_from_slice(&key);
+            }
+            None => stats.max_key = Some(key.clone()),
+        }
+
+        let (key_len, value_len) = (key.len() as u64, value.len() as u64);
+        let outcome = if supersede {
+            self.data.supersede(key, sequence, value_type, value)
+        } else {
+            self.data.insert(key, sequence, value_type, value)
+        };
+        match outcome {
+            WriteOutcome::Older => return,
+            WriteOutcome::Replaced {
+                value_type,
+                value_len,
+            } => {
+                stats.raw_value_bytes -= value_len as u64;
+                if value_type == ValueType::Delete {
+                    stats.num_deletes -= 1;
+                }
+            }
+            // Counted as a new key until mark_read_only() recounts
+            WriteOutcome::Unknown | WriteOutcome::NewKey => {
+                self.stats_exact &= outcome == WriteOutcome::NewKey;
+                stats.num_entries += 1;
+                stats.raw_key_bytes += key_len;
+            }
+        }
+        stats.raw_value_bytes += value_len;
+        if value_type == ValueType::Delete {
+            stats.num_deletes += 1;
+        }
+    }
+
+    /// false if `key` was definitely never written here, per the filter;
+    /// always true without one.
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.may_contain(key))
     }
 
     /// Look up a key. Returns None if not found OR if tombstoned.
     pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        if !self.may_contain(key) {
+            return None;
+        }
         match self.data.get(key) {
             Some([]) => None, // tombstone
             Some(v) => Some(v),
@@ -42,14 +321,136 @@ impl MemTable {
         }
     }
 
-    /// Mark a key as deleted by writing a tombstone (empty value).
+    /// Like get(), but returns a pinned handle that shares the stored
+    /// buffer instead of borrowing from the memtable.
+    pub fn get_pinned(&self, key: &[u8]) -> Option<Bytes> {
+        match self.get_version(key, u64::MAX)? {
+            (ValueType::Put, value) => Some(value),
+            (ValueType::Delete, _) => None, // tombstone
+        }
+    }
+
+    /// The newest version of `key` written before sequence number
+    /// `sequence`: its type and value, pinned. Unlike get(), tells a
+    /// tombstone (a Delete) from a key never written.
+    pub fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        if !self.may_contain(key) {
+            return None;
+        }
+        self.data.get_version(key, sequence)
+    }
+
+    /// Mark a key as deleted by writing a tombstone, as sequence number 0
+    /// like put().
     pub fn delete(&mut self, key: Vec<u8>) {
-        self.data.insert(key, Vec::new()); // empty = tombstone
+        self.insert(0, ValueType::Delete, key, Vec::new(), false);
+    }
+
+    /// Note that a write with sequence number `seq` went into this
+    /// memtable, so the SSTables it's flushed to record the range.
+    pub fn record_seqno(&mut self, seq: u64) {
+        self.seqno_range = Some(match self.seqno_range {
+            Some((smallest, largest)) => (smallest.min(seq), largest.max(seq)),
+            None => (seq, seq),
+        });
+    }
+
+    /// Smallest and largest sequence number recorded, or None if none was.
+    pub fn seqno_range(&self) -> Option<(u64, u64)> {
+        self.seqno_range
+    }
+
+    /// Return a sorted iterator over all keys' newest versions
+    /// (including tombstones).
+    pub fn iter(&self) -> MemTableIterator<'_> {
+        self.iter_at(u64::MAX)
+    }
+
+    /// Every stored version, shadowed ones and tombstones included, in
+    /// InternalKey order, positioned at the first. Each carries its own
+    /// sequence number and ValueType.
+    pub fn versions(&self) -> Box<dyn MemTableRepIterator + '_> {
+        let mut iter = self.data.iter();
+        let _ = iter.seek_to_first();
+        iter
+    }
+
+    /// Like iter(), but as of sequence number `sequence`: each key's
+    /// newest version written before it, keys with none skipped.
+    pub fn iter_at(&self, sequence: u64) -> MemTableIterator<'_> {
+        self.range_iter_at(None, None, sequence)
+    }
+
+    /// Like iter(), but over keys in [start, end) only: the iterator
+    /// starts at `start` and turns invalid at `end`, whichever way it
+    /// moves, without visiting keys past either.
+    pub fn range_iter(&self, start: &[u8], end: &[u8]) -> MemTableIterator<'_> {
+        self.range_iter_at(Some(start), Some(end), u64::MAX)
+    }
+
+    /// Like range_iter(), but as of sequence number `sequence` (see
+    /// iter_at()). `None` leaves that side of the range open.
+    pub fn range_iter_at(
+        &self,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        sequence: u64,
+    ) -> MemTableIterator<'_> {
+        MemTableIterator::new(self.data.iter(), sequence, lower, upper)
+    }
+
+    /// Number of live (non-tombstone) entries with keys in [start, end).
+    pub fn count_in_range(&self, start: &[u8], end: &[u8]) -> usize {
+        let mut count = 0;
+        let mut iter = self.range_iter(start, end);
+        while iter.is_valid() {
+            if !iter.value().is_empty() {
+                count += 1;
+            }
+            iter.advance();
+        }
+        count
     }
 
-    /// Return a sorted iterator over all entries (including tombstones).
-    pub fn iter(&self) -> SkipListIterator<'_> {
-        self.data.iter()
+    /// Statistics of what a flush would write: the newest version of each
+    /// key. Kept up to date by writes, so this is only a copy. With a
+    /// VectorRep, rewrites of a key count as more keys until the memtable
+    /// is marked read-only, when it's flushed.
+    pub fn stats(&self) -> MemTableStats {
+        self.stats.clone()
+    }
+
+    /// Count stats() from scratch, walking the memtable.
+    fn count_stats(&self) -> MemTableStats {
+        let mut stats = MemTableStats::default();
+        let mut iter = self.iter();
+        while iter.is_valid() {
+            stats.num_entries += 1;
+            if iter.value_type() == ValueType::Delete {
+                stats.num_deletes += 1;
+            }
+            stats.raw_key_bytes += iter.key().len() as u64;
+            stats.raw_value_bytes += iter.value().len() as u64;
+            if stats.min_key.is_none() {
+                stats.min_key = Some(iter.key().to_vec());
+            }
+            iter.advance();
+        }
+        if stats.num_entries > 0 {
+            iter.seek_to_last().expect("memtable iteration can't fail");
+            stats.max_key = Some(iter.key().to_vec());
+        }
+        stats
+    }
+
+    /// Note that no more writes will come, letting the rep reorganize
+    /// itself for reads (see MemTableRep::mark_read_only).
+    pub fn mark_read_only(&mut self) {
+        self.data.mark_read_only();
+        if !self.stats_exact {
+            self.stats = self.count_stats();
+            self.stats_exact = true;
+        }
     }
 
     /// Current memory usage in bytes.
@@ -57,102 +458,164 @@ impl MemTable {
         self.data.size_bytes()
     }
 
+    /// Bytes of size() taken by values overwritten, superseded or shadowed
+    /// by a newer version of their key, which reads of the newest versions
+    /// don't return. They still count towards the flush threshold and the
+    /// write buffer: a hot key rewritten over and over fills the memtable
+    /// and gets it flushed, rather than growing memory unnoticed.
+    pub fn garbage_bytes(&self) -> usize {
+        self.data.garbage_bytes()
+    }
+
     /// Check if memtable has reached the flush threshold.
     pub fn is_full(&self) -> bool {
         self.data.size_bytes() >= self.size_limit
     }
 
+    /// Number of entries, tombstones and older versions included.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
     /// Check if the memtable has no entries.
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
 }
 
+/// Statistics of a memtable's contents (see MemTable::stats), counting
+/// one entry per key: its newest version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemTableStats {
+    /// Keys, tombstones included.
+    pub num_entries: usize,
+    /// Keys whose newest version is a Delete.
+    pub num_deletes: usize,
+    /// Bytes of the keys, and of their values, before any encoding.
+    pub raw_key_bytes: u64,
+    pub raw_value_bytes: u64,
+    /// Smallest and largest key; None if the memtable is empty.
+    pub min_key: Option<Vec<u8>>,
+    pub max_key: Option<Vec<u8>>,
+}
+
 /// Thread-safe manager for active and immutable memtables.
 ///
-/// Uses RwLock for concurrent read access and exclusive write access.
 /// The active/immutable pattern allows writes to continue during flush:
 ///   - active: receives new writes
 ///   - immutable: being flushed to SSTable (read-only)
+///
+/// The pair is published RCU style: `current` points at a MemTables that
+/// is never changed, only replaced, and its lock is held just long enough
+/// to clone or swap the Arc. Readers clone it and work on their copy, so
+/// freeze() swapping in a new pair never waits for a reader mid-scan, and
+/// a reader keeps the memtables it started with until it's done.
+///
+/// Writers and freeze() are serialized by `write_lock`, so a write never
+/// lands in a memtable after it's frozen.
 pub struct MemTableManager {
-    active: RwLock<MemTable>,
-    immutable: RwLock<Option<MemTable>>,
+    current: RwLock<Arc<MemTables>>,
+    write_lock: Mutex<()>,
     size_limit: usize,
 }
 
+/// The memtables at one point in time.
+struct MemTables {
+    active: Arc<MemTable>,
+    immutable: Option<Arc<MemTable>>,
+}
+
 impl MemTableManager {
     /// Create a new manager with given size limit per memtable.
     pub fn new(size_limit: usize) -> Self {
         MemTableManager {
-            active: RwLock::new(MemTable::new(size_limit)),
-            immutable: RwLock::new(None),
+            current: RwLock::new(Arc::new(MemTables {
+                active: Arc::new(MemTable::new(size_limit)),
+                immutable: None,
+            })),
+            write_lock: Mutex::new(()),
             size_limit,
         }
     }
 
+    /// The memtables as of now.
+    fn current(&self) -> Arc<MemTables> {
+        Arc::clone(&self.current.read().unwrap())
+    }
+
     /// Insert or update a key-value pair.
     pub fn put(&self, key: Vec<u8>, value: Vec<u8>) {
-        let mut active = self.active.write().unwrap();
-        active.put(key, value);
+        let _writer = self.write_lock.lock().unwrap();
+        self.current().active.put(key, value);
     }
 
     /// Look up a key. Checks active first, then immutable.
-    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+    ///
+    /// The value is pinned rather than copied, so it outlives the read
+    /// lock without duplicating large values.
+    pub fn get(&self, key: &[u8]) -> Option<Bytes> {
+        let current = self.current();
         // Check active first (newer data)
-        {
-            let active = self.active.read().unwrap();
-            if let Some(v) = active.get(key) {
-                return Some(v.to_vec());
-            }
+        if let Some(v) = current.active.get_pinned(key) {
+            return Some(v);
         }
-
-        // Check immutable if exists
-        {
-            let immutable = self.immutable.read().unwrap();
-            if let Some(ref imm) = *immutable
-                && let Some(v) = imm.get(key)
-            {
-                return Some(v.to_vec());
-            }
-        }
-
-        None
+        current
+            .immutable
+            .as_ref()
+            .and_then(|imm| imm.get_pinned(key))
     }
 
     /// Mark a key as deleted.
     pub fn delete(&self, key: Vec<u8>) {
-        let mut active = self.active.write().unwrap();
-        active.delete(key);
+        let _writer = self.write_lock.lock().unwrap();
+        self.current().active.delete(key);
+    }
+
+    /// The active memtable, for scanning. It stays readable after a
+    /// freeze, as the immutable one.
+    pub fn active(&self) -> Arc<MemTable> {
+        Arc::clone(&self.current().active)
+    }
+
+    /// The immutable memtable, if any, for scanning or flushing.
+    pub fn immutable(&self) -> Option<Arc<MemTable>> {
+        self.current().immutable.clone()
     }
 
     /// Freeze the active memtable: move it to immutable, create new active.
     /// Call this when active is full and ready to flush.
+    ///
+    /// Only swaps the memtables' Arcs: readers holding the old active one
+    /// keep reading it undisturbed, and nothing waits for them. Marking it
+    /// read-only is left to whoever flushes it (FlushJob does).
     pub fn freeze(&self) {
-        let mut active = self.active.write().unwrap();
-        let mut immutable = self.immutable.write().unwrap();
-
-        // Take the current active, replace with new empty one
-        let old_active = std::mem::replace(&mut *active, MemTable::new(self.size_limit));
-
-        // Move old active to immutable
-        *immutable = Some(old_active);
+        let _writer = self.write_lock.lock().unwrap();
+        let active = Arc::new(MemTable::new(self.size_limit));
+        let mut current = self.current.write().unwrap();
+        *current = Arc::new(MemTables {
+            active,
+            immutable: Some(Arc::clone(&current.active)),
+        });
     }
 
     /// Check if there's an immutable memtable waiting to be flushed.
     pub fn has_immutable(&self) -> bool {
-        let immutable = self.immutable.read().unwrap();
-        immutable.is_some()
+        self.current().immutable.is_some()
     }
 
-    /// Clear the immutable memtable after flush is complete.
+    /// Clear the immutable memtable after flush is complete. Readers that
+    /// already have it keep it until they're done.
     pub fn clear_immutable(&self) {
-        let mut immutable = self.immutable.write().unwrap();
-        *immutable = None;
+        let _writer = self.write_lock.lock().unwrap();
+        let mut current = self.current.write().unwrap();
+        *current = Arc::new(MemTables {
+            active: Arc::clone(&current.active),
+            immutable: None,
+        });
     }
 
     /// Check if active memtable is full.
     pub fn is_full(&self) -> bool {
-        let active = self.active.read().unwrap();
-        active.is_full()
+        self.current().active.is_full()
     }
 }
@@ -0,0 +1,757 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::db::prefix::SliceTransform;
+use crate::error::Result;
+use crate::iterator::StorageIterator;
+use crate::memtable::skiplist::SkipList;
+use crate::types::ValueType;
+
+/// Sorted key → value store behind a MemTable.
+///
+/// Implementations trade insert speed against lookup and scan speed:
+///   - SkipList: O(log n) everything; the default
+///   - BTreeRep: O(log n) everything, fewer cache misses on scans
+///   - HashSkipListRep: a skip list per key prefix, so inserts and
+///     lookups search only their prefix; full scans sort every entry
+///   - VectorRep: O(1) appends, sorted once when the memtable is frozen;
+///     lookups in the active memtable scan every entry
+///
+/// Each entry is one version of a key: its sequence number, ValueType
+/// and value (empty for a Delete). A write adds a version rather than
+/// overwriting the key, so reads as of an older sequence number still
+/// find what was visible then; only a version with the same sequence
+/// number is overwritten. Values are handed out as Bytes so readers can
+/// pin them.
+pub trait MemTableRep: Send + Sync {
+    /// Insert version `sequence` of `key`, overwriting it if present.
+    /// Returns what the write did to the key's newest version.
+    fn insert(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome;
+
+    /// Insert version `sequence` of `key` in place of its newest version
+    /// up to `sequence`, if any, for writers whose readers never look at
+    /// a version once a newer one is in. The replaced value counts
+    /// towards garbage_bytes(). Reps that can't replace in place insert.
+    fn supersede(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        self.insert(key, sequence, value_type, value)
+    }
+
+    /// The value of `key`'s newest version, if present.
+    fn get(&self, key: &[u8]) -> Option<&[u8]>;
+
+    /// The newest version of `key` written before sequence number
+    /// `sequence`: its type and value, sharing the stored buffer.
+    fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)>;
+
+    /// Number of entries, every version counted.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Memory used in bytes. Never decreases.
+    fn size_bytes(&self) -> usize;
+
+    /// Bytes of size_bytes() taken by values overwritten, superseded or
+    /// shadowed by a newer version of their key. Reps that can't tell
+    /// count none.
+    fn garbage_bytes(&self) -> usize {
+        0
+    }
+
+    /// Iterator over every entry, ordered by InternalKey.
+    fn iter(&self) -> Box<dyn MemTableRepIterator + '_>;
+
+    /// Called once the memtable is frozen: no insert follows.
+    fn mark_read_only(&mut self) {}
+}
+
+/// What a write did to its key's newest version, as a rep reports it so
+/// the MemTable keeps its MemTableStats without looking the key up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The write is the key's first version.
+    NewKey,
+    /// The write took over as the key's newest version from one of this
+    /// type and value length (itself, if overwritten).
+    Replaced {
+        value_type: ValueType,
+        value_len: usize,
+    },
+    /// A newer version of the key was already in: its newest is
+    /// unchanged.
+    Older,
+    /// The rep can't tell without a search (VectorRep).
+    Unknown,
+}
+
+/// Creates the MemTableRep of each new memtable
+/// (Options::memtable_factory).
+pub trait MemTableRepFactory: Send + Sync {
+    fn create(&self) -> Box<dyn MemTableRep>;
+}
+
+/// Iterator over the entries of a MemTableRep, every version of every
+/// key, ordered by InternalKey: key ascending, then sequence descending.
+///
+/// key() is the user key. seek() lands on the newest version of the first
+/// key >= target, seek_for_prev() on the oldest version of the last key
+/// <= target; next() and prev() step one version.
+pub trait MemTableRepIterator: StorageIterator {
+    /// Sequence number of the current entry.
+    fn sequence(&self) -> u64;
+
+    /// Type of the current entry.
+    fn value_type(&self) -> ValueType;
+
+    /// Key of the current entry, sharing the stored buffer.
+    fn pinned_key(&self) -> Bytes;
+
+    /// Value of the current entry, sharing the stored buffer.
+    fn pinned_value(&self) -> Bytes;
+}
+
+/// One version of a key, as the reps that copy entries out hold it.
+#[derive(Clone)]
+pub(crate) struct Entry {
+    pub(crate) key: Bytes,
+    pub(crate) sequence: u64,
+    pub(crate) value_type: ValueType,
+    pub(crate) value: Bytes,
+}
+
+impl Entry {
+    /// Sort key: InternalKey order.
+    fn order(&self) -> (&[u8], Reverse<u64>) {
+        (&self.key, Reverse(self.sequence))
+    }
+}
+
+/// Per-entry bookkeeping counted on top of key and value bytes by the
+/// reps that keep both as Bytes.
+const ENTRY_OVERHEAD: usize = std::mem::size_of::<Entry>();
+
+impl MemTableRep for SkipList {
+    fn insert(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        SkipList::add(self, key, sequence, value_type, value)
+    }
+
+    fn supersede(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        SkipList::supersede(self, key, sequence, value_type, value)
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        SkipList::get(self, key)
+    }
+
+    fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        SkipList::get_version(self, key, sequence)
+    }
+
+    fn len(&self) -> usize {
+        SkipList::len(self)
+    }
+
+    fn size_bytes(&self) -> usize {
+        SkipList::size_bytes(self)
+    }
+
+    fn garbage_bytes(&self) -> usize {
+        SkipList::garbage_bytes(self)
+    }
+
+    fn iter(&self) -> Box<dyn MemTableRepIterator + '_> {
+        Box::new(SkipList::iter(self))
+    }
+}
+
+/// Builds SkipList reps: the default.
+pub struct SkipListFactory;
+
+impl MemTableRepFactory for SkipListFactory {
+    fn create(&self) -> Box<dyn MemTableRep> {
+        Box::new(SkipList::new())
+    }
+}
+
+/// Builds SkipList reps whose node heights come from an RNG seeded with
+/// the given seed, the same for every memtable: with it as
+/// Options::memtable_factory, a run's memtables are laid out the same
+/// every time, for reproducible tests and benchmarks.
+pub struct SeededSkipListFactory(pub u64);
+
+impl MemTableRepFactory for SeededSkipListFactory {
+    fn create(&self) -> Box<dyn MemTableRep> {
+        Box::new(SkipList::with_seed(self.0))
+    }
+}
+
+/// MemTableRep over a BTreeMap.
+#[derive(Default)]
+pub struct BTreeRep {
+    map: BTreeMap<VersionKey, (ValueType, Bytes)>,
+    size_bytes: usize,
+    garbage_bytes: usize,
+}
+
+/// BTreeRep key: InternalKey order.
+type VersionKey = (Bytes, Reverse<u64>);
+
+impl BTreeRep {
+    /// What writing `version` does to its key's newest version.
+    fn outcome(&self, version: &VersionKey) -> WriteOutcome {
+        let of_key = |(v, _): &(&VersionKey, _)| v.0 == version.0;
+        if self
+            .map
+            .range(..version.clone())
+            .next_back()
+            .filter(of_key)
+            .is_some()
+        {
+            return WriteOutcome::Older;
+        }
+        match self.map.range(version.clone()..).next().filter(of_key) {
+            Some((_, (value_type, value))) => WriteOutcome::Replaced {
+                value_type: *value_type,
+                value_len: value.len(),
+            },
+            None => WriteOutcome::NewKey,
+        }
+    }
+}
+
+impl MemTableRep for BTreeRep {
+    fn insert(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        self.size_bytes += value.len();
+        let version = (Bytes::from(key), Reverse(sequence));
+        let outcome = self.outcome(&version);
+        match self.map.get_mut(&version) {
+            Some(stored) => {
+                self.garbage_bytes += stored.1.len();
+                *stored = (value_type, Bytes::from(value));
+            }
+            None => {
+                if let WriteOutcome::Replaced { value_len, .. } = outcome {
+                    self.garbage_bytes += value_len;
+                }
+                self.size_bytes += version.0.len() + ENTRY_OVERHEAD;
+                self.map.insert(version, (value_type, Bytes::from(value)));
+            }
+        }
+        outcome
+    }
+
+    fn supersede(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        let replaced = self
+            .map
+            .range((Bytes::from(key.clone()), Reverse(sequence))..)
+            .next()
+            .filter(|(version, _)| version.0 == key.as_slice())
+            .map(|(version, _)| version.clone());
+        let Some(replaced) = replaced else {
+            return self.insert(key, sequence, value_type, value);
+        };
+        // The key's bytes and entry are reused, only the value is new
+        let outcome = self.outcome(&replaced);
+        let (_, old) = self.map.remove(&replaced).unwrap();
+        self.garbage_bytes += old.len();
+        self.size_bytes += value.len();
+        self.map.insert(
+            (replaced.0, Reverse(sequence)),
+            (value_type, Bytes::from(value)),
+        );
+        outcome
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        let (version, (_, value)) = self.map.range(newest(key)..).next()?;
+        (version.0 == key).then_some(value.as_ref())
+    }
+
+    fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        let first = (
+            Bytes::copy_from_slice(key),
+            Reverse(sequence.checked_sub(1)?),
+        );
+        let (version, stored) = self.map.range(first..).next()?;
+        (version.0 == key).then(|| stored.clone())
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    fn garbage_bytes(&self) -> usize {
+        self.garbage_bytes
+    }
+
+    fn iter(&self) -> Box<dyn MemTableRepIterator + '_> {
+        Box::new(BTreeRepIterator {
+            map: &self.map,
+            current: None,
+        })
+    }
+}
+
+/// Where `key`'s newest possible version would sort.
+fn newest(key: &[u8]) -> VersionKey {
+    (Bytes::copy_from_slice(key), Reverse(u64::MAX))
+}
+
+/// Where `key`'s oldest possible version would sort.
+fn oldest(key: &[u8]) -> VersionKey {
+    (Bytes::copy_from_slice(key), Reverse(0))
+}
+
+/// Builds BTreeRep reps.
+pub struct BTreeFactory;
+
+impl MemTableRepFactory for BTreeFactory {
+    fn create(&self) -> Box<dyn MemTableRep> {
+        Box::new(BTreeRep::default())
+    }
+}
+
+/// Cursor over a BTreeRep: each step is a range lookup from the
+/// current entry.
+struct BTreeRepIterator<'a> {
+    map: &'a BTreeMap<VersionKey, (ValueType, Bytes)>,
+    current: Option<(&'a VersionKey, &'a (ValueType, Bytes))>,
+}
+
+impl<'a> BTreeRepIterator<'a> {
+    fn range(
+        &self,
+        lower: Bound<VersionKey>,
+        upper: Bound<VersionKey>,
+    ) -> std::collections::btree_map::Range<'a, VersionKey, (ValueType, Bytes)> {
+        self.map.range((lower, upper))
+    }
+}
+
+impl StorageIterator for BTreeRepIterator<'_> {
+    fn key(&self) -> &[u8] {
+        &self.current.expect("iterator not valid").0.0
+    }
+
+    fn value(&self) -> &[u8] {
+        &self.current.expect("iterator not valid").1.1
+    }
+
+    fn is_valid(&self) -> bool {
+        self.current.is_some()
+    }
+
+    fn next(&mut self) -> Result<()> {
+        if let Some((version, _)) = self.current {
+            self.current = self
+                .range(Bound::Excluded(version.clone()), Bound::Unbounded)
+                .next();
+        }
+        Ok(())
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.current = self
+            .range(Bound::Included(newest(key)), Bound::Unbounded)
+            .next();
+        Ok(())
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        if let Some((version, _)) = self.current {
+            self.current = self
+                .range(Bound::Unbounded, Bound::Excluded(version.clone()))
+                .next_back();
+        }
+        Ok(())
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.current = self
+            .range(Bound::Unbounded, Bound::Included(oldest(key)))
+            .next_back();
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.current = self.map.iter().next();
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.current = self.map.iter().next_back();
+        Ok(())
+    }
+}
+
+impl MemTableRepIterator for BTreeRepIterator<'_> {
+    fn sequence(&self) -> u64 {
+        self.current.expect("iterator not valid").0.1.0
+    }
+
+    fn value_type(&self) -> ValueType {
+        self.current.expect("iterator not valid").1.0
+    }
+
+    fn pinned_key(&self) -> Bytes {
+        self.current.expect("iterator not valid").0.0.clone()
+    }
+
+    fn pinned_value(&self) -> Bytes {
+        self.current.expect("iterator not valid").1.1.clone()
+    }
+}
+
+/// MemTableRep hashing keys by prefix into separate skip lists, like
+/// RocksDB's hash skip list: a write or point lookup searches only the
+/// skip list of its key's prefix. Keys outside the extractor's domain
+/// share one more. Iterating sorts the entries of every prefix, so suits
+/// workloads that scan rarely.
+pub struct HashSkipListRep {
+    prefix_extractor: Arc<dyn SliceTransform>,
+    buckets: HashMap<Option<Vec<u8>>, SkipList>,
+    len: usize,
+    size_bytes: usize,
+    garbage_bytes: usize,
+}
+
+impl HashSkipListRep {
+    pub fn new(prefix_extractor: Arc<dyn SliceTransform>) -> Self {
+        HashSkipListRep {
+            prefix_extractor,
+            buckets: HashMap::new(),
+            len: 0,
+            size_bytes: 0,
+            garbage_bytes: 0,
+        }
+    }
+
+    fn bucket_of(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.prefix_extractor
+            .in_domain(key)
+            .then(|| self.prefix_extractor.transform(key).to_vec())
+    }
+
+    /// Write `key` into its bucket's skip list with `write`, keeping the
+    /// totals up to date.
+    fn write(
+        &mut self,
+        key: Vec<u8>,
+        write: impl FnOnce(&mut SkipList, Vec<u8>) -> WriteOutcome,
+    ) -> WriteOutcome {
+        let bucket = self.bucket_of(&key);
+        let list = self.buckets.entry(bucket).or_default();
+        let (len, size, garbage) = (list.len(), list.size_bytes(), list.garbage_bytes());
+        let outcome = write(list, key);
+        self.len += list.len() - len;
+        self.size_bytes += list.size_bytes() - size;
+        self.garbage_bytes += list.garbage_bytes() - garbage;
+        outcome
+    }
+}
+
+impl MemTableRep for HashSkipListRep {
+    fn insert(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        self.write(key, |list, key| list.add(key, sequence, value_type, value))
+    }
+
+    fn supersede(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        self.write(key, |list, key| {
+            list.supersede(key, sequence, value_type, value)
+        })
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.buckets.get(&self.bucket_of(key))?.get(key)
+    }
+
+    fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        self.buckets
+            .get(&self.bucket_of(key))?
+            .get_version(key, sequence)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    fn garbage_bytes(&self) -> usize {
+        self.garbage_bytes
+    }
+
+    fn iter(&self) -> Box<dyn MemTableRepIterator + '_> {
+        let mut entries = Vec::with_capacity(self.len);
+        for list in self.buckets.values() {
+            let mut iter = list.iter();
+            while iter.is_valid() {
+                entries.push(iter.entry());
+                iter.advance();
+            }
+        }
+        // Buckets hold disjoint keys: sorting merges them
+        entries.sort_by(|a, b| a.order().cmp(&b.order()));
+        Box::new(SortedEntries::new(entries))
+    }
+}
+
+/// Builds HashSkipListRep reps bucketing by `prefix_extractor`.
+pub struct HashSkipListFactory(pub Arc<dyn SliceTransform>);
+
+impl MemTableRepFactory for HashSkipListFactory {
+    fn create(&self) -> Box<dyn MemTableRep> {
+        Box::new(HashSkipListRep::new(Arc::clone(&self.0)))
+    }
+}
+
+/// MemTableRep appending every write to a vector, for bulk loads: an
+/// insert is a push, and the vector is sorted (the last write of each
+/// version kept) once, when the memtable is frozen. Until then a lookup
+/// scans it from the newest write back, an iterator sorts a copy, and
+/// len() counts overwrites too.
+#[derive(Default)]
+pub struct VectorRep {
+    entries: Vec<Entry>,
+    sorted: bool,
+    size_bytes: usize,
+}
+
+impl VectorRep {
+    /// Position of the newest version of `key` written before
+    /// `sequence`; of its latest write if written several times.
+    fn find(&self, key: &[u8], sequence: u64) -> Option<usize> {
+        if self.sorted {
+            let newest = (key, Reverse(sequence.checked_sub(1)?));
+            let i = self.entries.partition_point(|e| e.order() < newest);
+            self.entries
+                .get(i)
+                .is_some_and(|e| e.key.as_ref() == key)
+                .then_some(i)
+        } else {
+            self.entries
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.key.as_ref() == key && e.sequence < sequence)
+                .max_by_key(|(i, e)| (e.sequence, *i))
+                .map(|(i, _)| i)
+        }
+    }
+}
+
+/// `entries` in InternalKey order, keeping only the last of each
+/// version's writes.
+fn sort_latest(mut entries: Vec<Entry>) -> Vec<Entry> {
+    // Stable: a version's writes stay in write order
+    entries.sort_by(|a, b| a.order().cmp(&b.order()));
+    let mut latest: Vec<Entry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match latest.last_mut() {
+            Some(last) if last.order() == entry.order() => *last = entry,
+            _ => latest.push(entry),
+        }
+    }
+    latest
+}
+
+impl MemTableRep for VectorRep {
+    fn insert(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        assert!(!self.sorted, "insert into a read-only VectorRep");
+        self.size_bytes += key.len() + value.len() + ENTRY_OVERHEAD;
+        self.entries.push(Entry {
+            key: Bytes::from(key),
+            sequence,
+            value_type,
+            value: Bytes::from(value),
+        });
+        WriteOutcome::Unknown
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.find(key, u64::MAX)
+            .map(|i| self.entries[i].value.as_ref())
+    }
+
+    fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        self.find(key, sequence).map(|i| {
+            let entry = &self.entries[i];
+            (entry.value_type, entry.value.clone())
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    fn iter(&self) -> Box<dyn MemTableRepIterator + '_> {
+        let entries = if self.sorted {
+            self.entries.clone()
+        } else {
+            sort_latest(self.entries.clone())
+        };
+        Box::new(SortedEntries::new(entries))
+    }
+
+    fn mark_read_only(&mut self) {
+        if !self.sorted {
+            self.entries = sort_latest(std::mem::take(&mut self.entries));
+            self.sorted = true;
+        }
+    }
+}
+
+/// Builds VectorRep reps.
+pub struct VectorFactory;
+
+impl MemTableRepFactory for VectorFactory {
+    fn create(&self) -> Box<dyn MemTableRep> {
+        Box::new(VectorRep::default())
+    }
+}
+
+/// Iterator over entries already in InternalKey order, sharing their
+/// buffers.
+struct SortedEntries {
+    entries: Vec<Entry>,
+    pos: usize,
+}
+
+impl SortedEntries {
+    fn new(entries: Vec<Entry>) -> Self {
+        SortedEntries { entries, pos: 0 }
+    }
+}
+
+impl StorageIterator for SortedEntries {
+    fn key(&self) -> &[u8] {
+        &self.entries[self.pos].key
+    }
+
+    fn value(&self) -> &[u8] {
+        &self.entries[self.pos].value
+    }
+
+    fn is_valid(&self) -> bool {
+        self.pos < self.entries.len()
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn seek(&mut self, key: &[u8]) -> Result<()> {
+        self.pos = self.entries.partition_point(|e| e.key.as_ref() < key);
+        Ok(())
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        // Stepping back from index 0 parks the cursor past the end (invalid)
+        self.pos = self.pos.checked_sub(1).unwrap_or(self.entries.len());
+        Ok(())
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        let upper = self.entries.partition_point(|e| e.key.as_ref() <= key);
+        self.pos = upper.checked_sub(1).unwrap_or(self.entries.len());
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.pos = self.entries.len().saturating_sub(1);
+        Ok(())
+    }
+}
+
+impl MemTableRepIterator for SortedEntries {
+    fn sequence(&self) -> u64 {
+        self.entries[self.pos].sequence
+    }
+
+    fn value_type(&self) -> ValueType {
+        self.entries[self.pos].value_type
+    }
+
+    fn pinned_key(&self) -> Bytes {
+        self.entries[self.pos].key.clone()
+    }
+
+    fn pinned_value(&self) -> Bytes {
+        self.entries[self.pos].value.clone()
+    }
+}
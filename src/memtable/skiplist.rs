@@ -1,8 +1,12 @@
-// TODO [M01]: Implement skip list — insert and get
-// TODO [M02]: Implement skip list iterator
-// TODO [M03]: Track size in bytes
+use bytes::Bytes;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
 use crate::error::Result;
 use crate::iterator::StorageIterator;
+use crate::memtable::arena::Arena;
+use crate::memtable::rep::{Entry, MemTableRepIterator, WriteOutcome};
+use crate::types::ValueType;
 
 /// Maximum height of the skip list. LevelDB uses 12.
 pub const MAX_HEIGHT: usize = 12;
@@ -19,19 +23,32 @@ pub const MAX_HEIGHT: usize = 12;
 /// Level 1:  HEAD ──► 10 ──► 20 ────► 35 ────────► 50 ──► 60 ──► NIL
 /// Level 0:  HEAD ──► 10 ──► 20 ──► 25 ──► 35 ──► 50 ──► 60 ──► 70 ► NIL
 /// ```
-pub struct SkipNode {
-    key: Vec<u8>,
-    value: Vec<u8>,
-    forward: Vec<Option<usize>>, // indices into SkipList.nodes
-                                 //   - key: Vec<u8>
-                                 //   - value: Vec<u8>
-                                 //   - forward: Vec<Option<pointer to next SkipNode at each level>>
-                                 //
-                                 // Design decision: how to represent pointers?
-                                 //   Option A: Box<SkipNode> (owned, simple but hard for back-traversal)
-                                 //   Option B: Raw pointers (unsafe, more flexible)
-                                 //   Option C: Arena allocation with indices (no unsafe, good cache locality)
-                                 //   Start with Option A or C. Avoid raw pointers until you understand why.
+///
+/// Each node is one version of a key: nodes are ordered by InternalKey,
+/// key ascending then sequence descending, so a key's versions sit next
+/// to each other, newest first.
+///
+/// Key, value and tower all live in the list's Arena; forward pointers
+/// are indices into SkipList.nodes. Level 0 is also linked backwards, so
+/// iterators step back in O(1) instead of searching from the head.
+pub(crate) struct SkipNode {
+    key: Bytes,
+    sequence: u64,
+    value_type: ValueType,
+    /// Refcounted so readers can pin a value without copying it.
+    value: Bytes,
+    /// Index in the arena of the node's first forward pointer.
+    tower: usize,
+    /// Node before this one at level 0; None for the first.
+    prev: Option<usize>,
+}
+
+/// Where the last insert through it went: the nodes before it at each
+/// level. Passed to SkipList::add_with_hint() so inserts of ascending keys
+/// don't each search from the head. A new hint points at the head.
+#[derive(Clone, Default)]
+pub struct InsertHint {
+    preds: [usize; MAX_HEIGHT],
 }
 
 /// A probabilistic sorted data structure.
@@ -46,9 +63,16 @@ pub struct SkipNode {
 /// Worst case: O(n) — but astronomically unlikely with random level assignment.
 pub struct SkipList {
     nodes: Vec<SkipNode>,
+    arena: Arena,
     height: usize,
     len: usize,
-    size_bytes: usize,
+    /// Last node at level 0, if any.
+    tail: Option<usize>,
+    /// Bytes of values overwritten or superseded in place.
+    garbage: usize,
+    /// Source of node heights if seeded (with_seed); otherwise the thread's
+    /// RNG.
+    rng: Option<StdRng>,
 }
 
 impl Default for SkipList {
@@ -60,54 +84,153 @@ impl Default for SkipList {
 impl SkipList {
     /// Create a new empty skip list.
     pub fn new() -> Self {
+        Self::with_rng(None)
+    }
+
+    /// Create a new empty skip list whose node heights come from an RNG
+    /// seeded with `seed`: the same inserts then build the same list,
+    /// arena layout included, so tests and benchmarks can be replayed
+    /// exactly.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(Some(StdRng::seed_from_u64(seed)))
+    }
+
+    /// Create a new empty skip list drawing node heights from `rng`, or
+    /// from the thread's RNG if None.
+    pub fn with_rng(rng: Option<StdRng>) -> Self {
+        let mut arena = Arena::new();
         let head = SkipNode {
-            key: Vec::new(),
-            value: Vec::new(),
-            forward: vec![None; MAX_HEIGHT],
+            key: Bytes::new(),
+            sequence: u64::MAX,
+            value_type: ValueType::Put,
+            value: Bytes::new(),
+            tower: arena.alloc_tower(MAX_HEIGHT, false),
+            prev: None,
         };
         let nodes = vec![head];
 
         SkipList {
             nodes,
+            arena,
             height: 1,
             len: 0,
-            size_bytes: 0,
+            tail: None,
+            garbage: 0,
+            rng,
         }
     }
 
-    /// Insert a key-value pair. Overwrites if key already exists.
+    /// Insert a key-value pair as sequence number 0, overwriting the value
+    /// if the key is already present: a plain sorted map, for callers not
+    /// tracking versions.
+    pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.add(key, 0, ValueType::Put, value);
+    }
+
+    /// Insert version `sequence` of `key`. Older and newer versions stay;
+    /// only a version with the same sequence number is overwritten.
     ///
     /// Algorithm:
     ///   1. Find the insertion point at each level (track predecessors)
     ///   2. Generate a random height for the new node (coin flip per level)
     ///   3. Create node with that height
     ///   4. Splice into the list at each level up to the node's height
-    pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+    ///
+    /// Returns what the write did to the key's newest version.
+    pub fn add(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        self.add_with_hint(&mut InsertHint::default(), key, sequence, value_type, value)
+    }
+
+    /// insert() through `hint`, see add_with_hint().
+    pub fn insert_with_hint(&mut self, hint: &mut InsertHint, key: Vec<u8>, value: Vec<u8>) {
+        self.add_with_hint(hint, key, 0, ValueType::Put, value);
+    }
+
+    /// add() starting the search from `hint`, the position of the last
+    /// insert made through it, instead of from the head. For ascending
+    /// inserts each search then only steps over the few nodes in between,
+    /// instead of O(log n) from the head.
+    ///
+    /// Any key may be inserted through a hint: where it isn't past the
+    /// hinted position, the search starts from the head as usual. Nodes
+    /// inserted since without the hint don't invalidate it either. A hint
+    /// belongs to one list.
+    pub fn add_with_hint(
+        &mut self,
+        hint: &mut InsertHint,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
         let mut current = 0; // HEAD index
         let mut update: [usize; MAX_HEIGHT] = [0; MAX_HEIGHT];
 
         // Find insertion point, track predecessors at each level
         for level in (0..self.height).rev() {
-            loop {
-                let next = self.nodes[current].forward[level];
-                if let Some(next_idx) = next {
-                    if self.nodes[next_idx].key.as_slice() < key.as_slice() {
-                        current = next_idx; // move right
-                        continue;
-                    }
-                    // Check for existing key at level 0
-                    if self.nodes[next_idx].key.as_slice() == key.as_slice() {
-                        // Overwrite: add new value size (monotonically increasing)
-                        self.size_bytes += value.len();
-                        self.nodes[next_idx].value = value;
-                        return;
-                    }
-                }
-                break; // can't move right, drop down
+            // Jump ahead to the hinted predecessor if it's still before the
+            // new node and past where the search is
+            let hinted = hint.preds[level];
+            if hinted != 0
+                && hinted < self.nodes.len()
+                && self.before(hinted, &key, sequence)
+                && (current == 0
+                    || self.before(
+                        current,
+                        &self.nodes[hinted].key,
+                        self.nodes[hinted].sequence,
+                    ))
+            {
+                current = hinted;
+            }
+            while let Some(next_idx) = self.next(current, level)
+                && self.before(next_idx, &key, sequence)
+            {
+                current = next_idx; // move right
             }
             update[level] = current; // record predecessor at this level
         }
 
+        // A newer version of the key sits just before; otherwise the one
+        // after, if of the key, is the newest until now
+        let next = self.next(current, 0);
+        let outcome = if current != 0 && self.nodes[current].key.as_ref() == key.as_slice() {
+            WriteOutcome::Older
+        } else {
+            match next {
+                Some(next_idx) if self.nodes[next_idx].key.as_ref() == key.as_slice() => {
+                    self.replaced(next_idx)
+                }
+                _ => WriteOutcome::NewKey,
+            }
+        };
+
+        // Check for the same version at level 0
+        if let Some(next_idx) = next
+            && self.nodes[next_idx].key.as_ref() == key.as_slice()
+            && self.nodes[next_idx].sequence == sequence
+        {
+            // Overwrite: the old value's space isn't reclaimed, so size
+            // only grows
+            self.garbage += self.nodes[next_idx].value.len();
+            self.nodes[next_idx].value = self.arena.alloc(&value);
+            self.nodes[next_idx].value_type = value_type;
+            hint.preds = update;
+            return outcome;
+        }
+
+        // The version the new node shadows is dead to reads of the newest
+        // versions, though it stays for older sequence numbers
+        if let WriteOutcome::Replaced { value_len, .. } = outcome {
+            self.garbage += value_len;
+        }
+
         // Generate random height for new node
         let new_height = self.random_height();
 
@@ -119,11 +242,24 @@ impl SkipList {
             self.height = new_height;
         }
 
-        // Create new node
+        // Another version of the key next door shares its key bytes;
+        // otherwise the key is copied into the arena
+        let key = [Some(current).filter(|&idx| idx != 0), next]
+            .into_iter()
+            .flatten()
+            .map(|idx| &self.nodes[idx].key)
+            .find(|existing| existing.as_ref() == key.as_slice())
+            .cloned()
+            .unwrap_or_else(|| self.arena.alloc(&key));
+
+        // Create new node, its value and tower in the arena
         let new_node = SkipNode {
             key,
-            value,
-            forward: vec![None; new_height],
+            sequence,
+            value_type,
+            value: self.arena.alloc(&value),
+            tower: self.arena.alloc_tower(new_height, true),
+            prev: Some(current).filter(|&idx| idx != 0),
         };
 
         // Add to arena, get its index
@@ -131,23 +267,81 @@ impl SkipList {
         self.nodes.push(new_node);
 
         // Splice into each level
+        let new_tower = self.nodes[new_idx].tower;
         #[allow(clippy::needless_range_loop)]
         for level in 0..new_height {
+            let pred_tower = self.nodes[update[level]].tower;
             // new node points to what predecessor was pointing to
-            self.nodes[new_idx].forward[level] = self.nodes[update[level]].forward[level];
+            let next = self.arena.link(pred_tower, level);
+            self.arena.set_link(new_tower, level, next);
             // predecessor now points to new node
-            self.nodes[update[level]].forward[level] = Some(new_idx);
+            self.arena.set_link(pred_tower, level, Some(new_idx));
         }
-
-        // Track size: key + value + forward pointers overhead
-        self.size_bytes += self.nodes[new_idx].key.len()
-            + self.nodes[new_idx].value.len()
-            + new_height * std::mem::size_of::<Option<usize>>();
+        // And the back link of its successor at level 0
+        match next {
+            Some(next_idx) => self.nodes[next_idx].prev = Some(new_idx),
+            None => self.tail = Some(new_idx),
+        }
+        // The next insert is likely just past the new node
+        hint.preds = update;
+        hint.preds[..new_height].fill(new_idx);
 
         self.len += 1;
+        outcome
+    }
+
+    /// Insert version `sequence` of `key` in place of its newest version
+    /// up to `sequence`, reusing that node, key and tower; add() it if
+    /// there's none. For writers whose readers never look at a version
+    /// once a newer one is in: a hot key's rewrites then take only their
+    /// value bytes, not a node each.
+    ///
+    /// The node keeps its place: no version sits between the two.
+    /// Replaced values count towards garbage_bytes().
+    pub fn supersede(
+        &mut self,
+        key: Vec<u8>,
+        sequence: u64,
+        value_type: ValueType,
+        value: Vec<u8>,
+    ) -> WriteOutcome {
+        let Some(idx) = self.find(&key, sequence.saturating_add(1)) else {
+            return self.add(key, sequence, value_type, value);
+        };
+        let outcome = match self.nodes[idx].prev {
+            Some(prev) if self.nodes[prev].key == self.nodes[idx].key => WriteOutcome::Older,
+            _ => self.replaced(idx),
+        };
+        let value = self.arena.alloc(&value);
+        let node = &mut self.nodes[idx];
+        self.garbage += node.value.len();
+        node.sequence = sequence;
+        node.value_type = value_type;
+        node.value = value;
+        outcome
+    }
+
+    /// WriteOutcome of a write taking over from `node` as its key's
+    /// newest version.
+    fn replaced(&self, node: usize) -> WriteOutcome {
+        WriteOutcome::Replaced {
+            value_type: self.nodes[node].value_type,
+            value_len: self.nodes[node].value.len(),
+        }
     }
 
-    /// Look up a key. Returns the value if found.
+    /// Whether `node` orders before version `sequence` of `key`.
+    fn before(&self, node: usize, key: &[u8], sequence: u64) -> bool {
+        let node = &self.nodes[node];
+        match node.key.as_ref().cmp(key) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Equal => node.sequence > sequence,
+            std::cmp::Ordering::Greater => false,
+        }
+    }
+
+    /// Look up a key. Returns the value of its newest version if found,
+    /// empty for a Delete.
     ///
     /// Algorithm:
     ///   1. Start at head, highest level
@@ -156,13 +350,39 @@ impl SkipList {
     ///   4. Repeat until level 0
     ///   5. Check if the node at level 0 matches
     pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.find(key, u64::MAX)
+            .map(|idx| self.nodes[idx].value.as_ref())
+    }
+
+    /// Look up a key, returning a pinned handle to the value.
+    ///
+    /// The handle shares the node's buffer (refcount bump, no copy) and
+    /// stays valid after the skip list is borrowed mutably or dropped.
+    pub fn get_pinned(&self, key: &[u8]) -> Option<Bytes> {
+        self.find(key, u64::MAX)
+            .map(|idx| self.nodes[idx].value.clone())
+    }
+
+    /// The newest version of `key` written before sequence number
+    /// `sequence`: its type and value, sharing the node's buffer.
+    pub fn get_version(&self, key: &[u8], sequence: u64) -> Option<(ValueType, Bytes)> {
+        self.find(key, sequence).map(|idx| {
+            let node = &self.nodes[idx];
+            (node.value_type, node.value.clone())
+        })
+    }
+
+    /// Index of the node holding the newest version of `key` written
+    /// before `sequence`, if any.
+    fn find(&self, key: &[u8], sequence: u64) -> Option<usize> {
         let mut current = 0; // HEAD index
         let mut level = self.height - 1;
 
         loop {
-            let next = self.nodes[current].forward[level];
+            // Versions at or past `sequence` order before the visible ones
+            let next = self.next(current, level);
             if let Some(next_idx) = next
-                && self.nodes[next_idx].key.as_slice() < key
+                && self.before(next_idx, key, sequence.saturating_sub(1))
             {
                 current = next_idx; // move right
                 continue;
@@ -175,16 +395,17 @@ impl SkipList {
         }
 
         // check the node ahead at level 0
-        if let Some(candidate_idx) = self.nodes[current].forward[0]
-            && self.nodes[candidate_idx].key.as_slice() == key
+        if let Some(candidate_idx) = self.next(current, 0)
+            && self.nodes[candidate_idx].key.as_ref() == key
+            && self.nodes[candidate_idx].sequence < sequence
         {
-            return Some(self.nodes[candidate_idx].value.as_slice());
+            return Some(candidate_idx);
         }
 
         None
     }
 
-    /// Number of entries in the skip list.
+    /// Number of entries in the skip list, every version counted.
     pub fn len(&self) -> usize {
         self.len
     }
@@ -197,9 +418,23 @@ impl SkipList {
         false
     }
 
-    /// Approximate memory usage in bytes.
+    /// Memory used in bytes: the arena's count of the keys, values and
+    /// tower links it has handed out, overwritten values included. Never
+    /// decreases.
     pub fn size_bytes(&self) -> usize {
-        self.size_bytes
+        self.arena.allocated()
+    }
+
+    /// Bytes of size_bytes() taken by values overwritten, superseded or
+    /// shadowed by a newer version of their key: dead to reads of the
+    /// newest versions, but not freed until the list is dropped.
+    pub fn garbage_bytes(&self) -> usize {
+        self.garbage
+    }
+
+    /// Node after `node` at `level`.
+    fn next(&self, node: usize, level: usize) -> Option<usize> {
+        self.arena.link(self.nodes[node].tower, level)
     }
 
     /// Create an iterator over all entries in sorted order.
@@ -207,23 +442,32 @@ impl SkipList {
     pub fn iter(&self) -> SkipListIterator<'_> {
         SkipListIterator {
             list: self,
-            current: self.nodes[0].forward[0],
+            current: self.next(0, 0),
         }
     }
 
     /// Generate a random level for a new node.
     /// Each level has a 1/4 probability (LevelDB uses 1/4, not 1/2).
     /// Higher branching factor = shorter skip list = fewer levels = less memory.
-    fn random_height(&self) -> usize {
+    fn random_height(&mut self) -> usize {
         let mut height = 1;
-        while height < MAX_HEIGHT && rand::random::<f64>() < 0.25 {
+        while height < MAX_HEIGHT && self.coin_flip() {
             height += 1;
         }
         height
     }
+
+    /// True with probability 1/4.
+    fn coin_flip(&mut self) -> bool {
+        match &mut self.rng {
+            Some(rng) => rng.gen_bool(0.25),
+            None => rand::random::<f64>() < 0.25,
+        }
+    }
 }
 
-/// Iterator over skip list entries in sorted order.
+/// Iterator over skip list entries in sorted order, every version of
+/// each key.
 ///
 /// Simply follows level 0 forward pointers — level 0 is a sorted linked list
 /// containing every entry.
@@ -242,32 +486,58 @@ impl<'a> SkipListIterator<'a> {
     /// Panics if iterator is not valid.
     pub fn key(&self) -> &'a [u8] {
         let idx = self.current.expect("iterator not valid");
-        self.list.nodes[idx].key.as_slice()
+        self.list.nodes[idx].key.as_ref()
     }
 
     /// Returns the value at current position.
     /// Panics if iterator is not valid.
     pub fn value(&self) -> &'a [u8] {
         let idx = self.current.expect("iterator not valid");
-        self.list.nodes[idx].value.as_slice()
+        self.list.nodes[idx].value.as_ref()
+    }
+
+    /// Sequence number of the version at current position.
+    /// Panics if iterator is not valid.
+    pub fn sequence(&self) -> u64 {
+        let idx = self.current.expect("iterator not valid");
+        self.list.nodes[idx].sequence
+    }
+
+    /// Type of the version at current position.
+    /// Panics if iterator is not valid.
+    pub fn value_type(&self) -> ValueType {
+        let idx = self.current.expect("iterator not valid");
+        self.list.nodes[idx].value_type
+    }
+
+    /// Entry at current position, sharing the node's buffers.
+    /// Panics if iterator is not valid.
+    pub(crate) fn entry(&self) -> Entry {
+        let node = &self.list.nodes[self.current.expect("iterator not valid")];
+        Entry {
+            key: node.key.clone(),
+            sequence: node.sequence,
+            value_type: node.value_type,
+            value: node.value.clone(),
+        }
     }
 
     /// Advances to the next entry.
     pub fn advance(&mut self) {
         if let Some(idx) = self.current {
-            self.current = self.list.nodes[idx].forward[0];
+            self.current = self.list.next(idx, 0);
         }
     }
 
-    /// Seek to the first key >= target (internal implementation).
-    fn seek_to(&mut self, target: &[u8]) {
+    /// Seek to the first key >= target. Infallible counterpart of seek().
+    pub fn seek_to(&mut self, target: &[u8]) {
         let mut current = 0; // HEAD
         let mut level = self.list.height - 1;
 
         loop {
-            let next = self.list.nodes[current].forward[level];
+            let next = self.list.next(current, level);
             if let Some(next_idx) = next
-                && self.list.nodes[next_idx].key.as_slice() < target
+                && self.list.nodes[next_idx].key.as_ref() < target
             {
                 current = next_idx;
                 continue;
@@ -279,7 +549,41 @@ impl<'a> SkipListIterator<'a> {
         }
 
         // current is predecessor, forward[0] is first key >= target (or None)
-        self.current = self.list.nodes[current].forward[0];
+        self.current = self.list.next(current, 0);
+    }
+
+    /// Steps back to the previous entry: another version of the same key
+    /// or the oldest version of the previous one. O(1), following the
+    /// node's back link.
+    pub fn retreat(&mut self) {
+        if let Some(idx) = self.current {
+            self.current = self.list.nodes[idx].prev;
+        }
+    }
+
+    /// Find the last node with key < target (or <= target when
+    /// `inclusive`): the oldest version of that key.
+    fn find_last_before(&self, target: &[u8], inclusive: bool) -> Option<usize> {
+        let mut current = 0; // HEAD
+        let mut level = self.list.height - 1;
+
+        loop {
+            let next = self.list.next(current, level);
+            if let Some(next_idx) = next {
+                let next_key = self.list.nodes[next_idx].key.as_ref();
+                if next_key < target || (inclusive && next_key == target) {
+                    current = next_idx;
+                    continue;
+                }
+            }
+            if level == 0 {
+                break;
+            }
+            level -= 1;
+        }
+
+        // HEAD means no key qualifies
+        if current == 0 { None } else { Some(current) }
     }
 }
 
@@ -290,12 +594,12 @@ impl<'a> StorageIterator for SkipListIterator<'a> {
 
     fn key(&self) -> &[u8] {
         let idx = self.current.expect("iterator not valid");
-        self.list.nodes[idx].key.as_slice()
+        self.list.nodes[idx].key.as_ref()
     }
 
     fn value(&self) -> &[u8] {
         let idx = self.current.expect("iterator not valid");
-        self.list.nodes[idx].value.as_slice()
+        self.list.nodes[idx].value.as_ref()
     }
 
     fn next(&mut self) -> Result<()> {
@@ -307,4 +611,44 @@ impl<'a> StorageIterator for SkipListIterator<'a> {
         self.seek_to(key);
         Ok(())
     }
+
+    fn prev(&mut self) -> Result<()> {
+        self.retreat();
+        Ok(())
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        self.current = self.find_last_before(key, true);
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.current = self.list.next(0, 0);
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.current = self.list.tail;
+        Ok(())
+    }
+}
+
+impl MemTableRepIterator for SkipListIterator<'_> {
+    fn sequence(&self) -> u64 {
+        SkipListIterator::sequence(self)
+    }
+
+    fn value_type(&self) -> ValueType {
+        SkipListIterator::value_type(self)
+    }
+
+    fn pinned_key(&self) -> Bytes {
+        let idx = self.current.expect("iterator not valid");
+        self.list.nodes[idx].key.clone()
+    }
+
+    fn pinned_value(&self) -> Bytes {
+        let idx = self.current.expect("iterator not valid");
+        self.list.nodes[idx].value.clone()
+    }
 }
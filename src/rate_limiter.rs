@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token-bucket limit on the write rate of background I/O.
+///
+/// Every byte flush and compaction write to SSTables is charged to the
+/// bucket, which refills at `bytes_per_second` up to `burst_bytes`. A
+/// write larger than the tokens left goes into debt, and the writer
+/// sleeps until the bucket has refilled past it. Writers sharing a
+/// limiter are paced together: give DBs on the same disk the same one.
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    bytes_per_second: u64,
+    burst_bytes: u64,
+    /// Bytes that may be written without waiting; negative while in debt.
+    tokens: f64,
+    last_refill: Instant,
+    /// Bytes charged since creation.
+    total_bytes: u64,
+}
+
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.bytes_per_second as f64).min(self.burst_bytes as f64);
+        self.last_refill = now;
+    }
+}
+
+impl RateLimiter {
+    /// Limit writes to `bytes_per_second`, with bursts of up to a tenth
+    /// of a second's worth.
+    pub fn new(bytes_per_second: u64) -> Self {
+        let bytes_per_second = bytes_per_second.max(1);
+        let burst_bytes = (bytes_per_second / 10).max(1);
+        RateLimiter {
+            bucket: Mutex::new(Bucket {
+                bytes_per_second,
+                burst_bytes,
+                tokens: burst_bytes as f64,
+                last_refill: Instant::now(),
+                total_bytes: 0,
+            }),
+        }
+    }
+
+    /// Let up to `burst_bytes` through without waiting after an idle
+    /// period. The bucket starts full.
+    pub fn with_burst(self, burst_bytes: u64) -> Self {
+        {
+            let mut bucket = self.bucket.lock().unwrap();
+            bucket.burst_bytes = burst_bytes;
+            bucket.tokens = burst_bytes as f64;
+        }
+        self
+    }
+
+    /// Change the rate, e.g. to let compaction catch up off-peak.
+    pub fn set_bytes_per_second(&self, bytes_per_second: u64) {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.refill();
+        bucket.bytes_per_second = bytes_per_second.max(1);
+    }
+
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bucket.lock().unwrap().bytes_per_second
+    }
+
+    /// Charge `bytes` about to be written, sleeping first if that
+    /// exceeds the tokens available.
+    pub fn request(&self, bytes: usize) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            bucket.refill();
+            bucket.tokens -= bytes as f64;
+            bucket.total_bytes += bytes as u64;
+            (bucket.tokens < 0.0)
+                .then(|| Duration::from_secs_f64(-bucket.tokens / bucket.bytes_per_second as f64))
+        };
+        if let Some(wait) = wait {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Bytes charged so far.
+    pub fn total_bytes_through(&self) -> u64 {
+        self.bucket.lock().unwrap().total_bytes
+    }
+}
@@ -1,3 +1,7 @@
+use crate::sstable::block::hash_index::{self, HASH_INDEX_FLAG};
+use crate::sstable::block::{MAX_ENTRY_SIZE, put_varint, varint_len};
+use crate::sstable::compression::{CompressionType, compress};
+
 /// Accumulates sorted key-value pairs and serializes them into a block.
 ///
 /// A block is typically 4KB (matching OS page size / SSD block size).
@@ -5,22 +9,33 @@
 ///
 /// On-disk layout of a block:
 /// ```text
-/// ┌────────────────────────────────────────────────────┐
-/// │ Entry 0: [key_len(2B)][val_len(2B)][key][value]    │
-/// │ Entry 1: ...                                       │
-/// │ Entry N: ...                                       │
-/// ├────────────────────────────────────────────────────┤
-/// │ Offset array: [off_0(2B)][off_1(2B)]...[off_N(2B)] │
-/// │ Num entries (2B)                                   │
-/// └────────────────────────────────────────────────────┘
+/// ┌────────────────────────────────────────────────────────┐
+/// │ Entry 0: [key_len(varint)][val_len(varint)][key][value] │
+/// │ Entry 1: ...                                           │
+/// │ Entry N: ...                                           │
+/// ├────────────────────────────────────────────────────────┤
+/// │ Offset array: [off_0(4B)][off_1(4B)]...[off_N(4B)]     │
+/// │ Hash index (optional, see with_hash_index())           │
+/// │ Num entries (4B)                                       │
+/// └────────────────────────────────────────────────────────┘
+///   ↑ all of the above compressed as one payload, then:
+/// [compression type (1B)]
 /// ```
 ///
 /// The offset array at the end enables binary search without parsing
 /// every entry — jump to offsets[mid], read the key, compare.
+///
+/// With a hash index the high bit of the entry count is set.
+///
+/// Tables before format version 4 used 2-byte lengths, offsets and count,
+/// capping entries at 64KB; Block::from_legacy_contents() reads those.
 pub struct BlockBuilder {
     data: Vec<u8>,
-    offsets: Vec<u16>,
+    offsets: Vec<u32>,
     block_size: usize,
+    compression: CompressionType,
+    /// Hash of each entry's key, kept when building a hash index.
+    key_hashes: Option<Vec<u64>>,
 }
 
 impl BlockBuilder {
@@ -30,38 +45,85 @@ impl BlockBuilder {
             data: Vec::new(),
             offsets: Vec::new(),
             block_size,
+            compression: CompressionType::None,
+            key_hashes: None,
         }
     }
 
+    /// Compress the block with `compression` in build().
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Append a hash index mapping key hashes to entries, so Block::get()
+    /// can usually skip the binary search. Costs about 1.3 bytes per key;
+    /// blocks of more than 253 entries are built without one.
+    pub fn with_hash_index(mut self, enabled: bool) -> Self {
+        self.key_hashes = enabled.then(Vec::new);
+        self
+    }
+
     /// Add a key-value pair to the block.
     /// Returns false if the block is full (entry doesn't fit).
     /// First entry is always accepted even if it exceeds block_size.
     /// Entries MUST be added in sorted key order.
+    ///
+    /// Panics if key and value together exceed MAX_ENTRY_SIZE;
+    /// SSTableBuilder::add rejects such entries with an error first.
     pub fn add(&mut self, key: &[u8], value: &[u8]) -> bool {
-        let entry_size = 2 + 2 + key.len() + value.len(); // key_len + val_len + key + value
+        assert!(
+            key.len() + value.len() <= MAX_ENTRY_SIZE,
+            "block entry of {} bytes exceeds MAX_ENTRY_SIZE",
+            key.len() + value.len()
+        );
+        // key_len + val_len + key + value
+        let entry_size =
+            varint_len(key.len() as u64) + varint_len(value.len() as u64) + key.len() + value.len();
 
-        // Check if adding this entry would exceed the target block size.
-        // Always accept the first entry so we never produce an empty block.
-        if !self.offsets.is_empty() && self.estimated_size() + entry_size > self.block_size {
+        // Check if adding this entry would exceed the target block size
+        // (or the u32 offsets). Always accept the first entry so we never
+        // produce an empty block.
+        if !self.offsets.is_empty()
+            && (self.estimated_size() + entry_size > self.block_size
+                || self.data.len() + entry_size > u32::MAX as usize)
+        {
             return false;
         }
 
         // Record offset of this entry
-        self.offsets.push(self.data.len() as u16);
+        self.offsets.push(self.data.len() as u32);
+        if let Some(key_hashes) = &mut self.key_hashes {
+            key_hashes.push(hash_index::hash_key(key));
+        }
 
-        // Serialize: key_len (2B) | val_len (2B) | key | value
-        self.data
-            .extend_from_slice(&(key.len() as u16).to_le_bytes());
-        self.data
-            .extend_from_slice(&(value.len() as u16).to_le_bytes());
+        // Serialize: key_len (varint) | val_len (varint) | key | value
+        put_varint(&mut self.data, key.len() as u64);
+        put_varint(&mut self.data, value.len() as u64);
         self.data.extend_from_slice(key);
         self.data.extend_from_slice(value);
 
         true
     }
 
-    /// Finalize the block: append offset array and entry count.
+    /// Finalize the block: append offset array and entry count, compress,
+    /// and append the compression type byte.
+    ///
+    /// If compression fails or doesn't shrink the block, it's stored
+    /// uncompressed (type None) instead.
     pub fn build(self) -> Vec<u8> {
+        let compression = self.compression;
+        let contents = self.build_contents();
+        let compressed = match compression {
+            CompressionType::None => None,
+            _ => compress(&contents, compression).ok(),
+        };
+        seal_block(contents, compressed, compression.to_byte())
+    }
+
+    /// The uncompressed block contents: entries, offset array, hash
+    /// index if any, count.
+    pub(crate) fn build_contents(self) -> Vec<u8> {
         let mut block = self.data;
 
         // Append offset array
@@ -69,15 +131,29 @@ impl BlockBuilder {
             block.extend_from_slice(&offset.to_le_bytes());
         }
 
+        // Append the hash index, flagged in the entry count
+        let mut num_entries = self.offsets.len() as u32;
+        if let Some(key_hashes) = &self.key_hashes
+            && key_hashes.len() <= hash_index::MAX_ENTRIES
+        {
+            hash_index::encode(&mut block, key_hashes);
+            num_entries |= HASH_INDEX_FLAG;
+        }
+
         // Append num entries
-        block.extend_from_slice(&(self.offsets.len() as u16).to_le_bytes());
+        block.extend_from_slice(&num_entries.to_le_bytes());
 
         block
     }
 
-    /// Current estimated size of the block (data + offsets + count).
+    /// Current estimated (uncompressed) size of the block
+    /// (data + offsets + hash index + count).
     pub fn estimated_size(&self) -> usize {
-        self.data.len() + self.offsets.len() * 2 + 2
+        let hash_index_size = match self.key_hashes {
+            Some(_) => hash_index::encoded_size(self.offsets.len()),
+            None => 0,
+        };
+        self.data.len() + self.offsets.len() * 4 + hash_index_size + 4
     }
 
     /// Whether the block is empty (no entries added).
@@ -85,3 +161,23 @@ impl BlockBuilder {
         self.offsets.is_empty()
     }
 }
+
+/// Finish an on-disk block: keep `compressed` (tagged with `type_byte`)
+/// only if it's actually smaller than `contents`, otherwise store the
+/// contents raw with the None type byte.
+pub(crate) fn seal_block(contents: Vec<u8>, compressed: Option<Vec<u8>>, type_byte: u8) -> Vec<u8> {
+    let mut block = match compressed {
+        Some(compressed) if compressed.len() < contents.len() => {
+            let mut block = compressed;
+            block.push(type_byte);
+            block
+        }
+        _ => {
+            let mut block = contents;
+            block.push(CompressionType::None.to_byte());
+            block
+        }
+    };
+    block.shrink_to_fit();
+    block
+}
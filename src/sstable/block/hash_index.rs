@@ -0,0 +1,94 @@
+// Optional hash index at the end of a data block, mapping each key's hash
+// to its entry so point lookups skip the binary search.
+//
+// On-disk layout, between the offset array and the entry count:
+//
+//   [bucket_0(1B)]...[bucket_M(1B)][num_buckets(2B)]
+//
+// and the entry count has HASH_INDEX_FLAG set. A bucket holds the index
+// of the one entry hashing to it, BUCKET_EMPTY if none does, or
+// BUCKET_COLLISION if several do. Entry indexes must fit in a bucket
+// byte, so blocks with more than MAX_ENTRIES entries get no index.
+
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Flag in a block's entry count marking a hash index.
+pub(crate) const HASH_INDEX_FLAG: u32 = 1 << 31;
+
+/// Most entries a block can have and still get a hash index.
+pub(crate) const MAX_ENTRIES: usize = 253;
+
+/// Bucket no key hashes to: the key isn't in the block.
+const BUCKET_EMPTY: u8 = 255;
+
+/// Bucket several keys hash to: fall back to binary search.
+const BUCKET_COLLISION: u8 = 254;
+
+/// Bytes after the buckets: the bucket count.
+pub(crate) const NUM_BUCKETS_SIZE: usize = 2;
+
+/// What a block's hash index says about a key.
+pub(crate) enum HashLookup {
+    /// The key is not in the block.
+    Absent,
+    /// Only the entry at this index can hold the key.
+    Entry(usize),
+    /// The index can't tell; binary search the block.
+    Collision,
+}
+
+/// Hash of `key` as stored per entry by the builder.
+pub(crate) fn hash_key(key: &[u8]) -> u64 {
+    xxh3_64(key)
+}
+
+/// Bucket count for `num_entries` entries: about 0.75 entries per bucket,
+/// which keeps collisions rare at ~1.3 bytes per key.
+pub(crate) fn num_buckets(num_entries: usize) -> usize {
+    num_entries * 4 / 3 + 1
+}
+
+/// Encoded size of the index for `num_entries` entries, or 0 if the
+/// block is too big to get one.
+pub(crate) fn encoded_size(num_entries: usize) -> usize {
+    if num_entries > MAX_ENTRIES {
+        0
+    } else {
+        num_buckets(num_entries) + NUM_BUCKETS_SIZE
+    }
+}
+
+/// Append the index for entries with hashes `key_hashes` (in entry order)
+/// to `block`. Caller checks the block has at most MAX_ENTRIES entries.
+pub(crate) fn encode(block: &mut Vec<u8>, key_hashes: &[u64]) {
+    let num_buckets = num_buckets(key_hashes.len());
+    let mut buckets = vec![BUCKET_EMPTY; num_buckets];
+    for (index, hash) in key_hashes.iter().enumerate() {
+        let bucket = &mut buckets[(hash % num_buckets as u64) as usize];
+        *bucket = match *bucket {
+            BUCKET_EMPTY => index as u8,
+            _ => BUCKET_COLLISION,
+        };
+    }
+    block.extend_from_slice(&buckets);
+    block.extend_from_slice(&(num_buckets as u16).to_le_bytes());
+}
+
+/// Whether every bucket is empty, a collision, or an entry below
+/// `num_entries`.
+pub(crate) fn is_valid(buckets: &[u8], num_entries: usize) -> bool {
+    !buckets.is_empty()
+        && buckets
+            .iter()
+            .all(|&b| b == BUCKET_EMPTY || b == BUCKET_COLLISION || (b as usize) < num_entries)
+}
+
+/// Look `key` up in `buckets`.
+pub(crate) fn lookup(buckets: &[u8], key: &[u8]) -> HashLookup {
+    let bucket = buckets[(hash_key(key) % buckets.len() as u64) as usize];
+    match bucket {
+        BUCKET_EMPTY => HashLookup::Absent,
+        BUCKET_COLLISION => HashLookup::Collision,
+        index => HashLookup::Entry(index as usize),
+    }
+}
@@ -1,2 +1,47 @@
 pub mod builder;
+pub(crate) mod hash_index;
 pub mod reader;
+
+/// Every data block on disk is followed by a trailer holding the CRC32 of
+/// the block bytes: [block][crc32(4B)]. Index entries record the block
+/// size without the trailer.
+pub const BLOCK_TRAILER_SIZE: usize = 4;
+
+/// Largest key + value a block entry can hold: entries are addressed by
+/// u32 offsets within their block.
+pub const MAX_ENTRY_SIZE: usize = u32::MAX as usize - 2 * MAX_VARINT_LEN;
+
+/// Longest LEB128 encoding of a u64.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Append `value` as a LEB128 varint (7 bits per byte, low bits first).
+pub(crate) fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Decode a LEB128 varint at the start of `data`, returning
+/// (value, bytes_read), or None if it's truncated or too long.
+pub(crate) fn get_varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in data.iter().take(MAX_VARINT_LEN).enumerate() {
+        value |= u64::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Encoded length of `value` as a varint.
+pub(crate) fn varint_len(mut value: u64) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
@@ -1,5 +1,12 @@
-use crate::error::Result;
-use crate::iterator::StorageIterator;
+use bytes::Bytes;
+
+use crate::error::{Error, Result};
+use crate::iterator::{KEYS_ONLY_VALUE, StorageIterator};
+use crate::sstable::block::get_varint;
+use crate::sstable::block::hash_index::{self, HASH_INDEX_FLAG, HashLookup};
+use crate::sstable::compression::{
+    CompressionType, ZSTD_DICT_TYPE_BYTE, decompress, decompress_with_dict,
+};
 
 /// A deserialized block. Holds the raw bytes + parsed offset array.
 ///
@@ -7,59 +14,202 @@ use crate::iterator::StorageIterator;
 /// - Point lookup via binary search over the offset array
 /// - Sequential scan via BlockIterator
 pub struct Block {
-    /// Just the entry bytes (offset array and count are stripped off after decode)
-    data: Vec<u8>,
+    /// Just the entry bytes (offset array and count are stripped off after decode).
+    /// Refcounted so values can be handed out as pinned slices.
+    data: Bytes,
     /// Byte offset of each entry within `data`, parsed from the block tail
-    offsets: Vec<u16>,
+    offsets: Vec<u32>,
+    /// Pre-format-version-4 layout: 2-byte entry lengths.
+    legacy: bool,
+    /// Hash index buckets, if the block was built with one.
+    hash_buckets: Option<Bytes>,
 }
 
 impl Block {
-    /// Decode a block from raw bytes produced by BlockBuilder::build().
+    /// Decode a block from raw bytes produced by BlockBuilder::build():
+    /// strip the compression type byte and decompress if needed.
     pub fn decode(raw: Vec<u8>) -> Result<Self> {
-        // Step 1: read num_entries from last 2 bytes
-        let num_entries = u16::from_le_bytes([raw[raw.len() - 2], raw[raw.len() - 1]]) as usize;
+        Self::from_contents(decompress_block(raw)?)
+    }
+
+    /// Decode an uncompressed block (entries, offset array, count).
+    ///
+    /// Every entry header and key/value span is bounds-checked here, so a
+    /// malformed block is reported as Error::Corruption instead of
+    /// panicking later in key_at()/value_at().
+    pub fn from_contents(raw: Vec<u8>) -> Result<Self> {
+        Self::parse(Bytes::from(raw), false)
+    }
+
+    /// Decode the uncompressed contents of a block from a table written
+    /// before format version 4: 2-byte lengths, offsets and count.
+    pub fn from_legacy_contents(raw: Vec<u8>) -> Result<Self> {
+        Self::parse(Bytes::from(raw), true)
+    }
+
+    /// Like from_contents() (or from_legacy_contents() if `legacy`), over
+    /// contents shared with others, such as a block cache entry: the
+    /// block's keys and values are slices of `raw`, not copies.
+    pub fn from_shared_contents(raw: Bytes, legacy: bool) -> Result<Self> {
+        Self::parse(raw, legacy)
+    }
+
+    fn parse(raw: Bytes, legacy: bool) -> Result<Self> {
+        let corrupt = |what: &str| Error::Corruption(format!("bad block: {what}"));
+        let width = if legacy { 2 } else { 4 };
+        let read_fixed = |pos: usize| -> usize {
+            if legacy {
+                u16::from_le_bytes([raw[pos], raw[pos + 1]]) as usize
+            } else {
+                u32::from_le_bytes(raw[pos..pos + 4].try_into().unwrap()) as usize
+            }
+        };
+
+        // Step 1: read num_entries from the block's tail
+        if raw.len() < width {
+            return Err(corrupt("too short"));
+        }
+        let mut tail = raw.len() - width;
+        let mut num_entries = read_fixed(tail);
+
+        // Step 2: a flagged count means a hash index sits before it
+        let mut hash_range = None;
+        if !legacy && num_entries & HASH_INDEX_FLAG as usize != 0 {
+            num_entries &= !(HASH_INDEX_FLAG as usize);
+            let num_buckets_start = tail
+                .checked_sub(hash_index::NUM_BUCKETS_SIZE)
+                .ok_or_else(|| corrupt("hash index truncated"))?;
+            let num_buckets =
+                u16::from_le_bytes([raw[num_buckets_start], raw[num_buckets_start + 1]]) as usize;
+            let buckets_start = num_buckets_start
+                .checked_sub(num_buckets)
+                .ok_or_else(|| corrupt("hash index truncated"))?;
+            if !hash_index::is_valid(&raw[buckets_start..num_buckets_start], num_entries) {
+                return Err(corrupt("bad hash index"));
+            }
+            hash_range = Some(buckets_start..num_buckets_start);
+            tail = buckets_start;
+        }
 
-        // Step 2: parse offset array (sits right before the 2-byte count)
-        let offsets_start = raw.len() - 2 - (num_entries * 2);
+        // Step 3: parse offset array (sits right before the hash index or count)
+        let offsets_start = num_entries
+            .checked_mul(width)
+            .and_then(|len| tail.checked_sub(len))
+            .ok_or_else(|| corrupt("entry count exceeds block size"))?;
         let mut offsets = Vec::with_capacity(num_entries);
         for i in 0..num_entries {
-            let pos = offsets_start + i * 2;
-            offsets.push(u16::from_le_bytes([raw[pos], raw[pos + 1]]));
+            let offset = read_fixed(offsets_start + i * width);
+
+            // Entry must fit: [key_len][val_len][key][value]
+            let (key_len, val_len, header_len) =
+                entry_header(&raw[..offsets_start], offset, legacy)
+                    .ok_or_else(|| corrupt("entry offset out of range"))?;
+            let end = (offset + header_len)
+                .checked_add(key_len)
+                .and_then(|end| end.checked_add(val_len));
+            if end.is_none_or(|end| end > offsets_start) {
+                return Err(corrupt("entry overruns block"));
+            }
+            offsets.push(offset as u32);
         }
 
-        // Step 3: entry data is everything before the offset array
+        // Step 4: entry data is everything before the offset array
         let mut data = raw;
+        let hash_buckets = hash_range.map(|range| data.slice(range));
         data.truncate(offsets_start);
 
-        Ok(Self { data, offsets })
+        Ok(Self {
+            data,
+            offsets,
+            legacy,
+            hash_buckets,
+        })
+    }
+
+    /// (key_len, val_len, header_len) of the entry at byte `offset`.
+    fn header(&self, offset: usize) -> (usize, usize, usize) {
+        entry_header(&self.data, offset, self.legacy).expect("entry validated on decode")
     }
 
     /// Read the key at a given entry index.
-    /// Entry layout: [key_len(2B)][val_len(2B)][key][value]
+    /// Entry layout: [key_len][val_len][key][value]
     pub fn key_at(&self, index: usize) -> &[u8] {
         let offset = self.offsets[index] as usize;
-        let key_len = u16::from_le_bytes([self.data[offset], self.data[offset + 1]]) as usize;
-        // key starts after key_len(2B) + val_len(2B) = 4 bytes
-        &self.data[offset + 4..offset + 4 + key_len]
+        let (key_len, _, header_len) = self.header(offset);
+        let key_start = offset + header_len;
+        &self.data[key_start..key_start + key_len]
     }
 
     /// Read the value at a given entry index.
     pub fn value_at(&self, index: usize) -> &[u8] {
+        &self.data[self.value_range(index)]
+    }
+
+    /// Whether the value at a given entry index is empty (a tombstone).
+    /// Reads only the entry header, not the value bytes.
+    pub fn value_is_empty(&self, index: usize) -> bool {
+        let (_, val_len, _) = self.header(self.offsets[index] as usize);
+        val_len == 0
+    }
+
+    /// Value at a given entry index as a pinned slice of the block buffer.
+    /// No bytes are copied; the block's memory stays alive while any
+    /// pinned value does.
+    pub fn pinned_value_at(&self, index: usize) -> Bytes {
+        self.data.slice(self.value_range(index))
+    }
+
+    /// Keys-only stand-in for value_at(): empty for tombstones,
+    /// KEYS_ONLY_VALUE otherwise.
+    pub fn keys_only_value_at(&self, index: usize) -> &[u8] {
+        if self.value_is_empty(index) {
+            &[]
+        } else {
+            KEYS_ONLY_VALUE
+        }
+    }
+
+    /// Byte range of the value at a given entry index within `data`.
+    fn value_range(&self, index: usize) -> std::ops::Range<usize> {
         let offset = self.offsets[index] as usize;
-        let key_len = u16::from_le_bytes([self.data[offset], self.data[offset + 1]]) as usize;
-        let val_len = u16::from_le_bytes([self.data[offset + 2], self.data[offset + 3]]) as usize;
-        let val_start = offset + 4 + key_len;
-        &self.data[val_start..val_start + val_len]
+        let (key_len, val_len, header_len) = self.header(offset);
+        let val_start = offset + header_len + key_len;
+        val_start..val_start + val_len
     }
 
     /// Get the offset array.
-    pub fn offsets(&self) -> &[u16] {
+    pub fn offsets(&self) -> &[u32] {
         &self.offsets
     }
 
     /// Point lookup: binary search for a key within the block.
     /// Returns the value if found, None otherwise.
     pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.find(key).map(|index| self.value_at(index))
+    }
+
+    /// Like get(), but returns the value as a pinned slice of the block.
+    pub fn get_pinned(&self, key: &[u8]) -> Option<Bytes> {
+        self.find(key).map(|index| self.pinned_value_at(index))
+    }
+
+    /// Whether the block was built with a hash index.
+    pub fn has_hash_index(&self) -> bool {
+        self.hash_buckets.is_some()
+    }
+
+    /// Index of the entry whose key equals `key`, if any. The hash index,
+    /// when there is one, answers most lookups with at most one key
+    /// comparison; collisions fall back to binary search.
+    fn find(&self, key: &[u8]) -> Option<usize> {
+        if let Some(buckets) = &self.hash_buckets {
+            match hash_index::lookup(buckets, key) {
+                HashLookup::Absent => return None,
+                HashLookup::Entry(index) => return (self.key_at(index) == key).then_some(index),
+                HashLookup::Collision => {}
+            }
+        }
+
         let mut lo = 0usize;
         let mut hi = self.offsets.len();
 
@@ -68,7 +218,7 @@ impl Block {
             let mid_key = self.key_at(mid);
 
             match mid_key.cmp(key) {
-                std::cmp::Ordering::Equal => return Some(self.value_at(mid)),
+                std::cmp::Ordering::Equal => return Some(mid),
                 std::cmp::Ordering::Less => lo = mid + 1,
                 std::cmp::Ordering::Greater => hi = mid,
             }
@@ -82,8 +232,63 @@ impl Block {
         BlockIterator {
             block: self,
             index: 0,
+            keys_only: false,
         }
     }
+
+    /// Like iter(), but value() yields KEYS_ONLY_VALUE (or empty for
+    /// tombstones) instead of the stored value.
+    pub fn keys_only_iter(&self) -> BlockIterator<'_> {
+        BlockIterator {
+            block: self,
+            index: 0,
+            keys_only: true,
+        }
+    }
+}
+
+/// Parse the length header of the entry at `offset` in `entries`:
+/// (key_len, val_len, header_len). Legacy entries have two 2-byte
+/// lengths, current ones two varints. None if the header doesn't fit.
+fn entry_header(entries: &[u8], offset: usize, legacy: bool) -> Option<(usize, usize, usize)> {
+    let header = entries.get(offset..)?;
+    if legacy {
+        let lens = header.get(..4)?;
+        let key_len = u16::from_le_bytes([lens[0], lens[1]]) as usize;
+        let val_len = u16::from_le_bytes([lens[2], lens[3]]) as usize;
+        return Some((key_len, val_len, 4));
+    }
+    let (key_len, key_len_size) = get_varint(header)?;
+    let (val_len, val_len_size) = get_varint(&header[key_len_size..])?;
+    Some((
+        usize::try_from(key_len).ok()?,
+        usize::try_from(val_len).ok()?,
+        key_len_size + val_len_size,
+    ))
+}
+
+/// Turn an on-disk block ([payload][type byte]) into its uncompressed
+/// contents.
+pub fn decompress_block(raw: Vec<u8>) -> Result<Vec<u8>> {
+    decompress_block_with_dict(raw, None)
+}
+
+/// Like decompress_block(), also accepting blocks compressed against the
+/// table's zstd dictionary when `dict` is given.
+pub fn decompress_block_with_dict(mut raw: Vec<u8>, dict: Option<&[u8]>) -> Result<Vec<u8>> {
+    let type_byte = raw
+        .pop()
+        .ok_or_else(|| Error::Corruption("bad block: empty".into()))?;
+    if type_byte == ZSTD_DICT_TYPE_BYTE {
+        let dict = dict.ok_or_else(|| {
+            Error::Corruption("bad block: needs a zstd dictionary the table doesn't have".into())
+        })?;
+        return decompress_with_dict(&raw, dict);
+    }
+    match CompressionType::from_byte(type_byte)? {
+        CompressionType::None => Ok(raw),
+        compression => decompress(&raw, compression),
+    }
 }
 
 /// Sequential iterator over entries in a block.
@@ -91,6 +296,8 @@ pub struct BlockIterator<'a> {
     block: &'a Block,
     /// Current entry index; invalid when index >= block.offsets.len()
     index: usize,
+    /// Skip value bytes; see Block::keys_only_iter().
+    keys_only: bool,
 }
 
 impl<'a> StorageIterator for BlockIterator<'a> {
@@ -99,7 +306,11 @@ impl<'a> StorageIterator for BlockIterator<'a> {
     }
 
     fn value(&self) -> &[u8] {
-        self.block.value_at(self.index)
+        if self.keys_only {
+            self.block.keys_only_value_at(self.index)
+        } else {
+            self.block.value_at(self.index)
+        }
     }
 
     fn is_valid(&self) -> bool {
@@ -132,4 +343,44 @@ impl<'a> StorageIterator for BlockIterator<'a> {
         self.index = lo; // equals offsets.len() if all keys < target
         Ok(())
     }
+
+    fn prev(&mut self) -> Result<()> {
+        // Stepping back from entry 0 parks the cursor past the end (invalid).
+        self.index = self
+            .index
+            .checked_sub(1)
+            .unwrap_or(self.block.offsets.len());
+        Ok(())
+    }
+
+    /// Seek to the last entry with key <= target.
+    /// "upper_bound" binary search, then step one entry back.
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        let mut lo = 0usize;
+        let mut hi = self.block.offsets.len();
+
+        // Find smallest index where key_at(index) > target
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.block.key_at(mid) <= key {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        self.index = lo.checked_sub(1).unwrap_or(self.block.offsets.len());
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.index = 0;
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        // An empty block leaves index at 0 == len, i.e. invalid.
+        self.index = self.block.offsets.len().saturating_sub(1);
+        Ok(())
+    }
 }
@@ -1,11 +1,124 @@
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::bloom::builder::BloomFilterBuilder;
-use crate::error::Result;
-use crate::sstable::block::builder::BlockBuilder;
-use crate::sstable::footer::{Footer, IndexEntry, SSTABLE_MAGIC, SSTableMeta};
+use crate::checksum::ChecksumType;
+use crate::compaction::partitioner::SstPartitioner;
+use crate::error::{Error, Result};
+use crate::rate_limiter::RateLimiter;
+use crate::sstable::block::builder::{BlockBuilder, seal_block};
+use crate::sstable::block::{BLOCK_TRAILER_SIZE, MAX_ENTRY_SIZE};
+use crate::sstable::compression::{
+    CompressionType, ZSTD_DICT_TYPE_BYTE, compress, compress_with_dict, train_dictionary,
+};
+use crate::sstable::direct_io::DirectWriter;
+use crate::sstable::filter::encode_filter_index;
+use crate::sstable::footer::{FORMAT_VERSION, Footer, IndexEntry, SSTABLE_MAGIC, SSTableMeta};
+use crate::sstable::index::{
+    DEFAULT_INDEX_PARTITION_ENTRIES, encode_entries, encode_top_level, find_short_successor,
+    find_shortest_separator,
+};
+use crate::sstable::properties::{
+    TableProperties, TablePropertiesCollector, TablePropertiesCollectorFactory,
+};
+use crate::sstable::range_del::{RangeTombstone, RangeTombstones};
+use crate::sstable::seqno::EntrySeqnos;
+
+/// Settings for building SSTables, shared by memtable flush and compaction.
+#[derive(Clone)]
+pub struct TableOptions {
+    /// Target data block size in bytes. Default: 4KB.
+    pub block_size: usize,
+    /// Data block compression. Default: None.
+    pub compression: CompressionType,
+    /// Compression by output level, overriding `compression`; see
+    /// for_level(). Default: empty (every level uses `compression`).
+    pub compression_per_level: Vec<CompressionType>,
+    /// Zstd dictionary size; see SSTableBuilder::with_zstd_dictionary().
+    /// Default: 0 (off).
+    pub zstd_max_dict_bytes: usize,
+    /// Bloom filter bits per key. Default: 10 (~1% FPR).
+    pub bloom_bits_per_key: usize,
+    /// Build one bloom filter per data block instead of one per table;
+    /// see SSTableBuilder::with_partitioned_filters(). Default: false.
+    pub partition_filters: bool,
+    /// Append a hash index to each data block; see
+    /// SSTableBuilder::with_data_block_hash_index(). Default: false.
+    pub data_block_hash_index: bool,
+    /// Each table gets one collector from every factory. Default: none.
+    pub properties_collectors: Vec<Arc<dyn TablePropertiesCollectorFactory>>,
+    /// Write tables with O_DIRECT, and have compaction read its inputs
+    /// with O_DIRECT, so background I/O doesn't evict the OS page cache.
+    /// Linux only. Default: false.
+    pub use_direct_io: bool,
+    /// Flush and compaction start a new output table once the current
+    /// one reaches this size; see target_file_size(). Default: unlimited.
+    pub target_file_size_base: u64,
+    /// Growth of the target file size per level below L1. Default: 1.
+    pub target_file_size_multiplier: u64,
+    /// Paces every byte written. Default: None (unlimited).
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Have compaction re-read the tables it wrote and check them against
+    /// its inputs before committing them. Default: false.
+    pub paranoid_file_checks: bool,
+    /// Also starts a new output table wherever it says to, below the
+    /// target size. Default: None.
+    pub sst_partitioner: Option<Arc<dyn SstPartitioner>>,
+    /// Checksum of each data block. Default: CRC32.
+    pub checksum: ChecksumType,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        Self {
+            block_size: 4096,
+            compression: CompressionType::None,
+            compression_per_level: Vec::new(),
+            zstd_max_dict_bytes: 0,
+            bloom_bits_per_key: 10,
+            partition_filters: false,
+            data_block_hash_index: false,
+            properties_collectors: Vec::new(),
+            use_direct_io: false,
+            target_file_size_base: u64::MAX,
+            target_file_size_multiplier: 1,
+            rate_limiter: None,
+            paranoid_file_checks: false,
+            sst_partitioner: None,
+            checksum: ChecksumType::Crc32,
+        }
+    }
+}
+
+impl TableOptions {
+    /// Options for a table written to `level`. Level n takes entry n of
+    /// compression_per_level and deeper levels take its last entry.
+    pub fn for_level(&self, level: u32) -> TableOptions {
+        let compression = self
+            .compression_per_level
+            .get(level as usize)
+            .or(self.compression_per_level.last())
+            .copied()
+            .unwrap_or(self.compression);
+        TableOptions {
+            compression,
+            ..self.clone()
+        }
+    }
+
+    /// Target size of tables written to `level`: target_file_size_base
+    /// for L0 and L1, multiplied by target_file_size_multiplier for each
+    /// level below that.
+    pub fn target_file_size(&self, level: u32) -> u64 {
+        let multiplier = self
+            .target_file_size_multiplier
+            .saturating_pow(level.saturating_sub(1));
+        self.target_file_size_base.saturating_mul(multiplier)
+    }
+}
 
 /// Builds an SSTable file from a sorted stream of key-value pairs.
 ///
@@ -24,8 +137,8 @@ pub struct SSTableBuilder {
     index_entries: Vec<IndexEntry>,
     /// Tracks current write position in the file.
     data_offset: u64,
-    /// Buffered file writer.
-    writer: BufWriter<File>,
+    /// File writer: buffered, or O_DIRECT.
+    writer: TableWriter,
     /// Unique SSTable identifier.
     sst_id: u64,
     /// Target block size.
@@ -40,6 +153,47 @@ pub struct SSTableBuilder {
     last_key_in_block: Option<Vec<u8>>,
     /// Bloom filter builder — every key added to the SSTable is also inserted here.
     bloom_builder: BloomFilterBuilder,
+    /// Compression applied to each data block.
+    compression: CompressionType,
+    /// Checksum in each data block's trailer.
+    checksum: ChecksumType,
+    /// Give each data block a hash index for point lookups.
+    data_block_hash_index: bool,
+    /// Max size of the zstd dictionary trained for this table; 0 = none.
+    zstd_max_dict_bytes: usize,
+    /// Uncompressed contents and last key of each full block, held back
+    /// until finish() when a dictionary is being trained.
+    buffered_blocks: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Total size of buffered_blocks' contents.
+    buffered_bytes: u64,
+    /// Trained dictionary, stored in the meta block.
+    compression_dict: Option<Vec<u8>>,
+    /// Index entries per partition; larger indexes are partitioned.
+    index_partition_entries: usize,
+    /// Bits per key for per-block filters.
+    bloom_bits_per_key: usize,
+    /// Write one bloom filter per data block instead of bloom_builder's.
+    partition_filters: bool,
+    /// Keys of the current block, for its filter.
+    block_keys: Vec<Vec<u8>>,
+    /// Serialized filter of each flushed block, in block order.
+    block_filters: Vec<Vec<u8>>,
+    /// Statistics for the properties block, filled in as entries arrive.
+    properties: TableProperties,
+    /// User collectors fed every entry; their output joins the properties.
+    collectors: Vec<Box<dyn TablePropertiesCollector>>,
+    /// Range tombstones, fragmented and written to their block in finish().
+    range_tombstones: Vec<RangeTombstone>,
+    /// Smallest and largest sequence number of the table's writes.
+    smallest_seqno: u64,
+    largest_seqno: u64,
+    /// Sequence numbers of the current block's entries.
+    block_seqnos: Vec<u64>,
+    /// Sequence numbers of each flushed block's entries, in block order.
+    entry_seqnos: Vec<Vec<u64>>,
+    /// Some entry was added without a sequence number; the table gets no
+    /// sequence number block.
+    missing_seqnos: bool,
 }
 
 impl SSTableBuilder {
@@ -58,9 +212,22 @@ impl SSTableBuilder {
         block_size: usize,
         estimated_keys: usize,
     ) -> Result<Self> {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-        Ok(SSTableBuilder {
+        let writer = TableWriter::new(TableFile::Buffered(BufWriter::new(File::create(path)?)));
+        Ok(Self::with_writer(
+            writer,
+            sst_id,
+            block_size,
+            estimated_keys,
+        ))
+    }
+
+    fn with_writer(
+        writer: TableWriter,
+        sst_id: u64,
+        block_size: usize,
+        estimated_keys: usize,
+    ) -> Self {
+        SSTableBuilder {
             block_builder: BlockBuilder::new(block_size),
             index_entries: Vec::new(),
             data_offset: 0,
@@ -72,7 +239,155 @@ impl SSTableBuilder {
             entry_count: 0,
             last_key_in_block: None,
             bloom_builder: BloomFilterBuilder::new(estimated_keys.max(1), Self::DEFAULT_FPR),
-        })
+            compression: CompressionType::None,
+            checksum: ChecksumType::Crc32,
+            data_block_hash_index: false,
+            zstd_max_dict_bytes: 0,
+            buffered_blocks: Vec::new(),
+            buffered_bytes: 0,
+            compression_dict: None,
+            index_partition_entries: DEFAULT_INDEX_PARTITION_ENTRIES,
+            bloom_bits_per_key: 10,
+            partition_filters: false,
+            block_keys: Vec::new(),
+            block_filters: Vec::new(),
+            properties: TableProperties::default(),
+            collectors: Vec::new(),
+            range_tombstones: Vec::new(),
+            smallest_seqno: 0,
+            largest_seqno: 0,
+            block_seqnos: Vec::new(),
+            entry_seqnos: Vec::new(),
+            missing_seqnos: false,
+        }
+    }
+
+    /// Create a builder configured by `options`, with the bloom filter
+    /// sized for `estimated_keys`.
+    pub fn with_options(
+        path: &Path,
+        sst_id: u64,
+        estimated_keys: usize,
+        options: &TableOptions,
+    ) -> Result<Self> {
+        let mut writer = TableWriter::new(if options.use_direct_io {
+            TableFile::Direct(DirectWriter::create(path)?)
+        } else {
+            TableFile::Buffered(BufWriter::new(File::create(path)?))
+        });
+        writer.rate_limiter = options.rate_limiter.clone();
+        let mut builder = Self::with_writer(writer, sst_id, options.block_size, estimated_keys)
+            .with_compression(options.compression)
+            .with_checksum(options.checksum)
+            .with_zstd_dictionary(options.zstd_max_dict_bytes)
+            .with_partitioned_filters(options.partition_filters)
+            .with_data_block_hash_index(options.data_block_hash_index);
+        for factory in &options.properties_collectors {
+            builder = builder.with_properties_collector(factory.create());
+        }
+        builder.bloom_bits_per_key = options.bloom_bits_per_key;
+        builder.bloom_builder = BloomFilterBuilder::with_bits_per_key(
+            estimated_keys.max(1),
+            options.bloom_bits_per_key,
+        );
+        Ok(builder)
+    }
+
+    /// Delete every key in [start, end) from older tables. May be called
+    /// in any order relative to add(); the table's own entries are newer
+    /// than its range tombstones, so writers drop entries a tombstone
+    /// covers rather than adding them. Empty ranges are ignored.
+    pub fn add_range_tombstone(&mut self, start: &[u8], end: &[u8]) {
+        if start < end {
+            self.range_tombstones.push(RangeTombstone::new(start, end));
+        }
+    }
+
+    /// Record the sequence numbers of the writes this table holds, from
+    /// `smallest` to `largest`. Tables left unset record 0 for both.
+    pub fn set_seqno_range(&mut self, smallest: u64, largest: u64) {
+        self.smallest_seqno = smallest;
+        self.largest_seqno = largest;
+    }
+
+    /// Approximate size of the data written so far, counting the block
+    /// being filled and blocks held back for dictionary training at their
+    /// uncompressed size. Flush and compaction compare it against the
+    /// target file size to decide when to start the next table.
+    pub fn estimated_file_size(&self) -> u64 {
+        self.data_offset + self.buffered_bytes + self.block_builder.estimated_size() as u64
+    }
+
+    /// Compress data blocks with `compression`. Call before adding entries.
+    pub fn with_compression(mut self, compression: CompressionType) -> Self {
+        self.compression = compression;
+        self.block_builder = self.new_block_builder();
+        self
+    }
+
+    /// Checksum data blocks with `checksum` instead of CRC32.
+    pub fn with_checksum(mut self, checksum: ChecksumType) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Append a hash index to every data block, so point lookups find
+    /// their entry with one hash and (usually) one key comparison instead
+    /// of a binary search. Costs about 1.3 bytes per key; blocks of more
+    /// than 253 entries go without. Call before adding entries.
+    pub fn with_data_block_hash_index(mut self, enabled: bool) -> Self {
+        self.data_block_hash_index = enabled;
+        self.block_builder = self.new_block_builder();
+        self
+    }
+
+    /// An empty block builder with this table's block options.
+    fn new_block_builder(&self) -> BlockBuilder {
+        BlockBuilder::new(self.block_size)
+            .with_compression(self.compression)
+            .with_hash_index(self.data_block_hash_index)
+    }
+
+    /// Train a zstd dictionary of up to `max_dict_bytes` from this table's
+    /// blocks and compress every block against it. Only applies with
+    /// CompressionType::Zstd; 0 disables it.
+    ///
+    /// Small blocks of similar entries share most of their content with
+    /// each other rather than within themselves, so a per-table dictionary
+    /// recovers the ratio plain per-block zstd can't. The cost is holding
+    /// the uncompressed blocks in memory until finish().
+    pub fn with_zstd_dictionary(mut self, max_dict_bytes: usize) -> Self {
+        self.zstd_max_dict_bytes = max_dict_bytes;
+        self
+    }
+
+    /// Split the index into partitions of `entries` block entries once it
+    /// has more than that many, so readers only load the top level on open.
+    pub fn with_index_partition_entries(mut self, entries: usize) -> Self {
+        self.index_partition_entries = entries.max(1);
+        self
+    }
+
+    /// Write a small bloom filter per data block, plus a filter index,
+    /// in place of the whole-table filter. Readers load only the filters
+    /// of blocks lookups land in, instead of one filter for every key.
+    pub fn with_partitioned_filters(mut self, partition_filters: bool) -> Self {
+        self.partition_filters = partition_filters;
+        self
+    }
+
+    /// Feed every added entry to `collector` and store what it returns
+    /// from finish() in the properties block.
+    pub fn with_properties_collector(
+        mut self,
+        collector: Box<dyn TablePropertiesCollector>,
+    ) -> Self {
+        self.collectors.push(collector);
+        self
+    }
+
+    fn trains_dictionary(&self) -> bool {
+        self.compression == CompressionType::Zstd && self.zstd_max_dict_bytes > 0
     }
 
     /// Add a key-value pair. MUST be called in sorted key order.
@@ -81,7 +396,34 @@ impl SSTableBuilder {
     /// 1. Try adding to the current block
     /// 2. If block is full: flush block to file, record index entry, start new block
     /// 3. Add the entry to the new block
+    ///
+    /// The table only gets a sequence number block if every entry is
+    /// added through add_with_seqno() instead.
     pub fn add(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.add_entry(key, value)?;
+        self.missing_seqnos = true;
+        Ok(())
+    }
+
+    /// Add a key-value pair written by the write with sequence number
+    /// `seqno`. MUST be called in sorted key order, like add().
+    pub fn add_with_seqno(&mut self, key: &[u8], value: &[u8], seqno: u64) -> Result<()> {
+        self.add_entry(key, value)?;
+        self.block_seqnos.push(seqno);
+        Ok(())
+    }
+
+    fn add_entry(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        if key.len() + value.len() > MAX_ENTRY_SIZE {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "entry of {} bytes exceeds the {MAX_ENTRY_SIZE}-byte limit",
+                    key.len() + value.len()
+                ),
+            )));
+        }
+
         // Track min/max keys
         if self.min_key.is_none() {
             self.min_key = Some(key.to_vec());
@@ -89,54 +431,148 @@ impl SSTableBuilder {
         self.max_key = Some(key.to_vec());
         self.entry_count += 1;
 
-        // Add key to bloom filter for later serialization
-        self.bloom_builder.add_key(key);
+        // Properties: built-in stats, then user collectors
+        self.properties.raw_key_size += key.len() as u64;
+        self.properties.raw_value_size += value.len() as u64;
+        if value.is_empty() {
+            self.properties.num_deletions += 1;
+        }
+        for collector in &mut self.collectors {
+            collector.add(key, value);
+        }
 
-        // Try adding to current block
-        if self.block_builder.add(key, value) {
-            self.last_key_in_block = Some(key.to_vec());
-            return Ok(());
+        // Add key to bloom filter for later serialization
+        if !self.partition_filters {
+            self.bloom_builder.add_key(key);
         }
 
-        // Block is full — flush it, then add to a fresh block
-        self.flush_block()?;
+        // Try adding to current block
+        if !self.block_builder.add(key, value) {
+            // Block is full — flush it, then add to a fresh block
+            self.flush_block(Some(key))?;
 
-        // Add to the new block (guaranteed to succeed — first entry always accepted)
-        assert!(self.block_builder.add(key, value));
+            // Add to the new block (guaranteed to succeed — first entry always accepted)
+            assert!(self.block_builder.add(key, value));
+        }
         self.last_key_in_block = Some(key.to_vec());
+        if self.partition_filters {
+            self.block_keys.push(key.to_vec());
+        }
 
         Ok(())
     }
 
     /// Flush the current block to disk and record an index entry.
-    fn flush_block(&mut self) -> Result<()> {
+    /// When training a dictionary the block is buffered instead.
+    ///
+    /// `next_key` is the first key of the following block, if any. The
+    /// index entry gets the shortest key separating the two blocks rather
+    /// than the block's full last key.
+    fn flush_block(&mut self, next_key: Option<&[u8]>) -> Result<()> {
         if self.block_builder.is_empty() {
             return Ok(());
         }
 
         // Take the current block builder, replace with a fresh one
-        let old_builder =
-            std::mem::replace(&mut self.block_builder, BlockBuilder::new(self.block_size));
-        let block_data = old_builder.build();
+        let fresh = self.new_block_builder();
+        let old_builder = std::mem::replace(&mut self.block_builder, fresh);
+        let last_key = self.last_key_in_block.take().unwrap();
+        self.entry_seqnos
+            .push(std::mem::take(&mut self.block_seqnos));
+        let last_key = match next_key {
+            Some(next_key) => find_shortest_separator(&last_key, next_key),
+            None => find_short_successor(&last_key),
+        };
+
+        if self.partition_filters {
+            let mut filter = BloomFilterBuilder::with_bits_per_key(
+                self.block_keys.len(),
+                self.bloom_bits_per_key,
+            );
+            for key in self.block_keys.drain(..) {
+                filter.add_key(&key);
+            }
+            self.block_filters.push(filter.build().serialize());
+        }
+
+        if self.trains_dictionary() {
+            let contents = old_builder.build_contents();
+            self.buffered_bytes += contents.len() as u64;
+            self.buffered_blocks.push((contents, last_key));
+            return Ok(());
+        }
+        self.write_block(&old_builder.build(), last_key)
+    }
+
+    /// Write a finished block and its checksum trailer, and index it.
+    fn write_block(&mut self, block_data: &[u8], last_key: Vec<u8>) -> Result<()> {
         let block_size = block_data.len() as u64;
 
-        // Write block bytes to file
-        self.writer.write_all(&block_data)?;
+        // Write block bytes to file, followed by the checksum trailer
+        self.writer.write_all(block_data)?;
+        self.writer
+            .write_all(&self.checksum.checksum(block_data).to_le_bytes())?;
 
         // Record where this block landed
         self.index_entries.push(IndexEntry {
-            last_key: self.last_key_in_block.take().unwrap(),
+            last_key,
             offset: self.data_offset,
             size: block_size,
         });
 
-        self.data_offset += block_size;
+        self.data_offset += block_size + BLOCK_TRAILER_SIZE as u64;
+        Ok(())
+    }
+
+    /// Train the dictionary from the buffered blocks, then compress and
+    /// write them. Falls back to plain per-block zstd if training fails.
+    fn write_buffered_blocks(&mut self) -> Result<()> {
+        let blocks = std::mem::take(&mut self.buffered_blocks);
+        let samples: Vec<&[u8]> = blocks.iter().map(|(c, _)| c.as_slice()).collect();
+        self.compression_dict = train_dictionary(&samples, self.zstd_max_dict_bytes);
+
+        for (contents, last_key) in blocks {
+            let block = match self.compression_dict {
+                Some(ref dict) => {
+                    let compressed = compress_with_dict(&contents, dict).ok();
+                    seal_block(contents, compressed, ZSTD_DICT_TYPE_BYTE)
+                }
+                None => {
+                    let compressed = compress(&contents, CompressionType::Zstd).ok();
+                    seal_block(contents, compressed, CompressionType::Zstd.to_byte())
+                }
+            };
+            self.write_block(&block, last_key)?;
+        }
         Ok(())
     }
 
+    /// Write the index entries as fixed-size partitions and return the
+    /// encoded top-level index pointing at them.
+    fn write_index_partitions(
+        writer: &mut impl Write,
+        data_offset: &mut u64,
+        entries: &[IndexEntry],
+        entries_per_partition: usize,
+    ) -> Result<Vec<u8>> {
+        let mut top = Vec::new();
+        for chunk in entries.chunks(entries_per_partition) {
+            let data = encode_entries(chunk);
+            top.push(IndexEntry {
+                last_key: chunk.last().unwrap().last_key.clone(),
+                offset: *data_offset,
+                size: data.len() as u64,
+            });
+            writer.write_all(&data)?;
+            *data_offset += data.len() as u64;
+        }
+        Ok(encode_top_level(&top, entries.len(), entries_per_partition))
+    }
+
     /// Encode the SSTable metadata into bytes for the meta block.
     /// Format: [id(8B)][level(4B)][min_key_len(4B)][min_key][max_key_len(4B)][max_key][entry_count(8B)]
-    fn encode_meta_block(&self) -> Vec<u8> {
+    /// followed, if a zstd dictionary was trained, by [dict_len(4B)][dict]
+    fn encode_meta_block(&self, creation_time: u64) -> Vec<u8> {
         let mut buf = Vec::new();
 
         // id (8 bytes)
@@ -158,39 +594,133 @@ impl SSTableBuilder {
         // entry_count (8 bytes)
         buf.extend_from_slice(&self.entry_count.to_le_bytes());
 
+        // smallest_seqno (8 bytes) + largest_seqno (8 bytes)
+        buf.extend_from_slice(&self.smallest_seqno.to_le_bytes());
+        buf.extend_from_slice(&self.largest_seqno.to_le_bytes());
+
+        // creation_time (8 bytes)
+        buf.extend_from_slice(&creation_time.to_le_bytes());
+
+        // Optional zstd dictionary (4 bytes len + dict)
+        if let Some(ref dict) = self.compression_dict {
+            buf.extend_from_slice(&(dict.len() as u32).to_le_bytes());
+            buf.extend_from_slice(dict);
+        }
+
         buf
     }
 
-    /// Finalize the SSTable: flush last block, write meta block, index block, footer, fsync.
+    /// Finalize the SSTable: flush last block, write meta block, bloom
+    /// block, index block, properties block, range deletion block and
+    /// sequence number block (if any), footer, fsync.
     pub fn finish(mut self) -> Result<SSTableMeta> {
-        // 1. Flush the last data block
-        self.flush_block()?;
+        // 1. Flush the last data block (and any held back for the dictionary)
+        self.flush_block(None)?;
+        if !self.buffered_blocks.is_empty() {
+            self.write_buffered_blocks()?;
+        }
+
+        // The table's key range grows to cover its range tombstones, so
+        // reads of keys they delete still consult this table
+        let range_tombstones = RangeTombstones::new(std::mem::take(&mut self.range_tombstones));
+        if let (Some(first), Some(last)) = (
+            range_tombstones.fragments().first(),
+            range_tombstones.fragments().last(),
+        ) {
+            if self.min_key.as_ref().is_none_or(|min| first.start < *min) {
+                self.min_key = Some(first.start.clone());
+            }
+            if self.max_key.as_ref().is_none_or(|max| last.end > *max) {
+                self.max_key = Some(last.end.clone());
+            }
+        }
 
         // 2. Write meta block with SSTable metadata
+        let creation_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
         let meta_block_offset = self.data_offset;
-        let meta_data = self.encode_meta_block();
+        let meta_data = self.encode_meta_block(creation_time);
         let meta_block_size = meta_data.len() as u64;
         self.writer.write_all(&meta_data)?;
         self.data_offset += meta_block_size;
 
-        // 3. Write bloom filter block
+        // 3. Write bloom filter block. Per-block filters go first, and the
+        // bloom block becomes the filter index over them
+        let filters_offset = self.data_offset;
+        let bloom_data = if self.partition_filters {
+            let mut handles = Vec::with_capacity(self.block_filters.len());
+            for filter in &self.block_filters {
+                handles.push((self.data_offset, filter.len() as u64));
+                self.writer.write_all(filter)?;
+                self.data_offset += filter.len() as u64;
+            }
+            encode_filter_index(&handles)
+        } else {
+            self.bloom_builder.build().serialize()
+        };
         let bloom_block_offset = self.data_offset;
-        let bloom = self.bloom_builder.build();
-        let bloom_data = bloom.serialize();
         let bloom_block_size = bloom_data.len() as u64;
         self.writer.write_all(&bloom_data)?;
         self.data_offset += bloom_block_size;
 
-        // 4. Write index block: serialize all index entries sequentially
+        // 4. Write index block: serialize all index entries sequentially.
+        // Past the partition size, write the entries as partitions first
+        // and make the index block a top-level index over them.
+        let index_parts_offset = self.data_offset;
+        let index_data = if self.index_entries.len() > self.index_partition_entries {
+            Self::write_index_partitions(
+                &mut self.writer,
+                &mut self.data_offset,
+                &self.index_entries,
+                self.index_partition_entries,
+            )?
+        } else {
+            encode_entries(&self.index_entries)
+        };
         let index_block_offset = self.data_offset;
-        let mut index_data = Vec::new();
-        for entry in &self.index_entries {
-            index_data.extend_from_slice(&entry.encode());
-        }
         let index_block_size = index_data.len() as u64;
         self.writer.write_all(&index_data)?;
+        self.data_offset += index_block_size;
+
+        // 5. Write properties block
+        let properties_block_offset = self.data_offset;
+        self.properties.num_entries = self.entry_count;
+        self.properties.num_data_blocks = self.index_entries.len() as u64;
+        self.properties.data_size = meta_block_offset;
+        self.properties.filter_size = bloom_block_offset + bloom_block_size - filters_offset;
+        self.properties.index_size = index_block_offset + index_block_size - index_parts_offset;
+        for collector in &mut self.collectors {
+            self.properties
+                .user_collected_properties
+                .extend(collector.finish());
+        }
+        let properties_data = self.properties.encode();
+        let properties_block_size = properties_data.len() as u64;
+        self.writer.write_all(&properties_data)?;
+        self.data_offset += properties_block_size;
+
+        // 6. Write range deletion block, if there are range tombstones
+        let range_del_block_offset = self.data_offset;
+        let mut range_del_block_size = 0;
+        if !range_tombstones.is_empty() {
+            let range_del_data = range_tombstones.encode();
+            range_del_block_size = range_del_data.len() as u64;
+            self.writer.write_all(&range_del_data)?;
+            self.data_offset += range_del_block_size;
+        }
+
+        // 7. Write sequence number block, if every entry came with one
+        let seqno_block_offset = self.data_offset;
+        let mut seqno_block_size = 0;
+        if !self.missing_seqnos && self.entry_count > 0 {
+            let seqno_data = EntrySeqnos::new(std::mem::take(&mut self.entry_seqnos)).encode();
+            seqno_block_size = seqno_data.len() as u64;
+            self.writer.write_all(&seqno_data)?;
+            self.data_offset += seqno_block_size;
+        }
 
-        // 5. Write footer
+        // 8. Write footer
         let footer = Footer {
             index_block_offset,
             index_block_size,
@@ -198,19 +728,22 @@ impl SSTableBuilder {
             meta_block_size,
             bloom_block_offset,
             bloom_block_size,
+            properties_block_offset,
+            properties_block_size,
+            range_del_block_offset,
+            range_del_block_size,
+            checksum: self.checksum,
+            seqno_block_offset,
+            seqno_block_size,
+            format_version: FORMAT_VERSION,
             magic: SSTABLE_MAGIC,
         };
         self.writer.write_all(&footer.encode())?;
 
-        // 6. Flush buffer + fsync to guarantee durability
-        self.writer.flush()?;
-        self.writer.get_ref().sync_all()?;
+        // 9. Flush buffer + fsync to guarantee durability
+        self.writer.finish()?;
 
-        let file_size = meta_block_offset
-            + meta_block_size
-            + bloom_block_size
-            + index_block_size
-            + Footer::SIZE as u64;
+        let file_size = self.data_offset + Footer::SIZE as u64;
 
         Ok(SSTableMeta {
             id: self.sst_id,
@@ -219,10 +752,69 @@ impl SSTableBuilder {
             max_key: self.max_key.unwrap_or_default(),
             file_size,
             entry_count: self.entry_count,
+            creation_time,
+            smallest_seqno: self.smallest_seqno,
+            largest_seqno: self.largest_seqno,
+            path_id: 0,
         })
     }
 }
 
+/// Destination of an SSTableBuilder's bytes.
+struct TableWriter {
+    file: TableFile,
+    /// Charged every byte written, pacing the writer.
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+/// The file a TableWriter writes to.
+enum TableFile {
+    /// Buffered writes through the OS page cache.
+    Buffered(BufWriter<File>),
+    /// O_DIRECT writes that bypass the page cache.
+    Direct(DirectWriter),
+}
+
+impl TableWriter {
+    fn new(file: TableFile) -> Self {
+        TableWriter {
+            file,
+            rate_limiter: None,
+        }
+    }
+
+    /// Write out everything buffered and fsync.
+    fn finish(self) -> std::io::Result<()> {
+        match self.file {
+            TableFile::Buffered(mut writer) => {
+                writer.flush()?;
+                writer.get_ref().sync_all()
+            }
+            TableFile::Direct(writer) => writer.finish(),
+        }
+    }
+}
+
+impl Write for TableWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let written = match self.file {
+            TableFile::Buffered(ref mut writer) => writer.write(data)?,
+            TableFile::Direct(ref mut writer) => writer.write(data)?,
+        };
+        if let Some(ref rate_limiter) = self.rate_limiter {
+            rate_limiter.request(written);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.file {
+            TableFile::Buffered(ref mut writer) => writer.flush(),
+            TableFile::Direct(ref mut writer) => writer.flush(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,4 +904,46 @@ mod tests {
         // File should be larger than a single block
         assert!(meta.file_size > 64);
     }
+
+    #[test]
+    fn entry_seqnos_follow_entries_across_blocks() {
+        use crate::iterator::StorageIterator;
+        use crate::sstable::reader::SSTable;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.sst");
+        let mut builder = SSTableBuilder::new(&path, 1, 64).unwrap();
+        for i in 0..20u64 {
+            let key = format!("key_{:05}", i);
+            builder
+                .add_with_seqno(key.as_bytes(), b"value", 100 + i * 3)
+                .unwrap();
+        }
+        builder.finish().unwrap();
+
+        let sst = SSTable::open(&path).unwrap();
+        assert!(sst.num_blocks() > 1);
+        let mut iter = sst.iter().unwrap().with_seqnos().unwrap();
+        let mut seqnos = Vec::new();
+        while iter.is_valid() {
+            seqnos.push(iter.seqno().unwrap());
+            iter.next().unwrap();
+        }
+        assert_eq!(seqnos, (0..20).map(|i| 100 + i * 3).collect::<Vec<_>>());
+
+        iter.seek(b"key_00013").unwrap();
+        assert_eq!(iter.seqno(), Some(139));
+        iter.prev().unwrap();
+        assert_eq!(iter.seqno(), Some(136));
+
+        // Without one for every entry, no sequence number block is written
+        let mut builder = SSTableBuilder::new(&path, 2, 64).unwrap();
+        builder.add_with_seqno(b"a", b"1", 1).unwrap();
+        builder.add(b"b", b"2").unwrap();
+        builder.finish().unwrap();
+        let sst = SSTable::open(&path).unwrap();
+        assert_eq!(sst.footer().seqno_block_size, 0);
+        assert!(sst.entry_seqnos().unwrap().is_none());
+        assert_eq!(sst.iter().unwrap().with_seqnos().unwrap().seqno(), None);
+    }
 }
@@ -0,0 +1,152 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+/// Alignment O_DIRECT needs for file offsets, transfer sizes and buffer
+/// addresses. 4KB covers both 512-byte and 4KB logical sector devices.
+pub const DIRECT_IO_ALIGNMENT: usize = 4096;
+
+/// Bytes DirectWriter buffers before each write: 1MB.
+const WRITE_BUFFER_SIZE: usize = 256 * DIRECT_IO_ALIGNMENT;
+
+/// Open `path` with O_DIRECT, so its reads and writes bypass the OS page
+/// cache. Only supported on Linux.
+fn open_direct(path: &Path, options: &mut OpenOptions) -> io::Result<File> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_DIRECT).open(path)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (path, options);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "direct I/O is only supported on Linux",
+        ))
+    }
+}
+
+/// Open an SSTable for O_DIRECT reads through read_direct().
+pub fn open_for_reads(path: &Path) -> io::Result<File> {
+    open_direct(path, OpenOptions::new().read(true))
+}
+
+/// Heap buffer whose usable region starts on an alignment boundary.
+struct AlignedBuf {
+    storage: Vec<u8>,
+    start: usize,
+    len: usize,
+}
+
+impl AlignedBuf {
+    fn new(len: usize) -> Self {
+        let storage = vec![0u8; len + DIRECT_IO_ALIGNMENT];
+        let start = storage.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+        Self {
+            storage,
+            start,
+            len,
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.storage[self.start..self.start + self.len]
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.storage[self.start..self.start + self.len]
+    }
+}
+
+/// Read `size` bytes at `offset` from a file opened with O_DIRECT.
+///
+/// The read is widened to aligned boundaries into an aligned buffer, and
+/// the requested range is copied out of it.
+pub fn read_direct(file: &File, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+    let align = DIRECT_IO_ALIGNMENT as u64;
+    let start = offset / align * align;
+    let end = (offset + size).div_ceil(align) * align;
+    let mut buf = AlignedBuf::new((end - start) as usize);
+
+    let mut filled = 0;
+    while filled < buf.len {
+        let n = file.read_at(&mut buf.as_mut_slice()[filled..], start + filled as u64)?;
+        filled += n;
+        // A short, unaligned read means end of file; reading on from an
+        // unaligned offset would fail
+        if n == 0 || filled % DIRECT_IO_ALIGNMENT != 0 {
+            break;
+        }
+    }
+
+    let skip = (offset - start) as usize;
+    if filled < skip + size as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "read past end of file",
+        ));
+    }
+    Ok(buf.as_slice()[skip..skip + size as usize].to_vec())
+}
+
+/// Sequential O_DIRECT writer for SSTableBuilder.
+///
+/// Bytes collect in an aligned buffer that is written out whole each time
+/// it fills. finish() pads the tail to the alignment, writes it, trims the
+/// padding off the file and fsyncs.
+pub struct DirectWriter {
+    file: File,
+    buf: AlignedBuf,
+    /// Bytes in `buf` not yet written.
+    buffered: usize,
+    /// Bytes written to the file so far.
+    written: u64,
+}
+
+impl DirectWriter {
+    /// Create (or truncate) `path` for O_DIRECT writes.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = open_direct(
+            path,
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )?;
+        Ok(Self {
+            file,
+            buf: AlignedBuf::new(WRITE_BUFFER_SIZE),
+            buffered: 0,
+            written: 0,
+        })
+    }
+
+    /// Write the buffered tail, cut the file to its real length and fsync.
+    pub fn finish(mut self) -> io::Result<()> {
+        let tail = self.buffered;
+        let padded = tail.next_multiple_of(DIRECT_IO_ALIGNMENT);
+        self.buf.as_mut_slice()[tail..padded].fill(0);
+        self.file.write_all(&self.buf.as_slice()[..padded])?;
+        self.file.set_len(self.written + tail as u64)?;
+        self.file.sync_all()
+    }
+}
+
+impl Write for DirectWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(WRITE_BUFFER_SIZE - self.buffered);
+        self.buf.as_mut_slice()[self.buffered..self.buffered + n].copy_from_slice(&data[..n]);
+        self.buffered += n;
+        if self.buffered == WRITE_BUFFER_SIZE {
+            self.file.write_all(self.buf.as_slice())?;
+            self.written += WRITE_BUFFER_SIZE as u64;
+            self.buffered = 0;
+        }
+        Ok(n)
+    }
+
+    /// Only whole aligned buffers can be written, so the partial tail
+    /// stays buffered until finish().
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
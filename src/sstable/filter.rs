@@ -0,0 +1,148 @@
+use std::sync::OnceLock;
+
+use crate::bloom::BloomFilter;
+use crate::error::{Error, Result};
+
+/// Trailer magic marking a partitioned (per-block) filter index.
+pub const PARTITIONED_FILTER_MAGIC: u64 = 0x4C534D5F50464C54; // "LSM_PFLT"
+
+/// Size of one filter handle: [offset(8B)][size(8B)]
+const HANDLE_SIZE: usize = 16;
+
+/// Size of the partitioned filter trailer: [num_filters(8B)][magic(8B)]
+const PARTITIONED_TRAILER_SIZE: usize = 8 + 8;
+
+/// Encode the filter index of a partitioned filter: the (offset, size) of
+/// each data block's filter, in block order, then the trailer.
+pub fn encode_filter_index(handles: &[(u64, u64)]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(handles.len() * HANDLE_SIZE + PARTITIONED_TRAILER_SIZE);
+    for &(offset, size) in handles {
+        buf.extend_from_slice(&offset.to_le_bytes());
+        buf.extend_from_slice(&size.to_le_bytes());
+    }
+    buf.extend_from_slice(&(handles.len() as u64).to_le_bytes());
+    buf.extend_from_slice(&PARTITIONED_FILTER_MAGIC.to_le_bytes());
+    buf
+}
+
+/// An SSTable's bloom filter.
+///
+/// Either one filter over the whole table, loaded on open, or one small
+/// filter per data block behind a filter index. Per-block filters are
+/// read the first time a lookup lands in their block, so huge tables
+/// don't keep a monolithic filter resident.
+pub struct Filter {
+    kind: FilterKind,
+}
+
+enum FilterKind {
+    Full(BloomFilter),
+    Partitioned {
+        /// (offset, size) of each block's filter.
+        handles: Vec<(u64, u64)>,
+        filters: Vec<OnceLock<BloomFilter>>,
+    },
+}
+
+impl Filter {
+    /// Parse a bloom block: a whole-table filter or a filter index.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let partitioned = data.len() >= PARTITIONED_TRAILER_SIZE
+            && data[data.len() - 8..] == PARTITIONED_FILTER_MAGIC.to_le_bytes();
+        if !partitioned {
+            return Ok(Self {
+                kind: FilterKind::Full(BloomFilter::deserialize(data)?),
+            });
+        }
+
+        let body = &data[..data.len() - PARTITIONED_TRAILER_SIZE];
+        let count_bytes = &data[body.len()..body.len() + 8];
+        let count = u64::from_le_bytes(count_bytes.try_into().unwrap()) as usize;
+        if body.len() != count * HANDLE_SIZE {
+            return Err(Error::Corruption(
+                "filter index size doesn't match its filter count".into(),
+            ));
+        }
+        let handles = body
+            .chunks_exact(HANDLE_SIZE)
+            .map(|h| {
+                (
+                    u64::from_le_bytes(h[0..8].try_into().unwrap()),
+                    u64::from_le_bytes(h[8..16].try_into().unwrap()),
+                )
+            })
+            .collect();
+        let filters = (0..count).map(|_| OnceLock::new()).collect();
+        Ok(Self {
+            kind: FilterKind::Partitioned { handles, filters },
+        })
+    }
+
+    /// Whether filters are per data block.
+    pub fn is_partitioned(&self) -> bool {
+        matches!(self.kind, FilterKind::Partitioned { .. })
+    }
+
+    /// The whole-table filter, or None for per-block filters.
+    pub fn full_filter(&self) -> Option<&BloomFilter> {
+        match &self.kind {
+            FilterKind::Full(bloom) => Some(bloom),
+            FilterKind::Partitioned { .. } => None,
+        }
+    }
+
+    /// Number of per-block filters (0 for a whole-table filter).
+    pub fn num_partitions(&self) -> usize {
+        match &self.kind {
+            FilterKind::Full(_) => 0,
+            FilterKind::Partitioned { handles, .. } => handles.len(),
+        }
+    }
+
+    /// Number of per-block filters currently in memory (0 for a
+    /// whole-table filter).
+    pub fn loaded_partitions(&self) -> usize {
+        match &self.kind {
+            FilterKind::Full(_) => 0,
+            FilterKind::Partitioned { filters, .. } => {
+                filters.iter().filter(|f| f.get().is_some()).count()
+            }
+        }
+    }
+
+    /// Whole-table check. Always true for per-block filters, which can
+    /// only answer once the candidate block is known.
+    pub fn table_may_contain(&self, key: &[u8]) -> bool {
+        match &self.kind {
+            FilterKind::Full(bloom) => bloom.may_contain(key),
+            FilterKind::Partitioned { .. } => true,
+        }
+    }
+
+    /// Check `key` against the filter of data block `block_idx`, the only
+    /// block that could hold it. Always true for a whole-table filter.
+    /// `read` fetches (offset, size) when the block's filter isn't loaded.
+    pub fn block_may_contain(
+        &self,
+        block_idx: usize,
+        key: &[u8],
+        read: &dyn Fn(u64, u64) -> Result<Vec<u8>>,
+    ) -> Result<bool> {
+        let FilterKind::Partitioned { handles, filters } = &self.kind else {
+            return Ok(true);
+        };
+        let Some(&(offset, size)) = handles.get(block_idx) else {
+            return Err(Error::Corruption(format!(
+                "no filter for block {block_idx}"
+            )));
+        };
+        let filter = match filters[block_idx].get() {
+            Some(filter) => filter,
+            None => {
+                let filter = BloomFilter::deserialize(&read(offset, size)?)?;
+                filters[block_idx].get_or_init(|| filter)
+            }
+        };
+        Ok(filter.may_contain(key))
+    }
+}
@@ -1,5 +1,13 @@
-/// Magic number to identify SSTable files.
-pub const SSTABLE_MAGIC: u64 = 0x4C534D5F53535400; // "LSM_SST\0"
+use crate::checksum::ChecksumType;
+
+/// Magic number to identify SSTable files with a versioned footer.
+pub const SSTABLE_MAGIC: u64 = 0x4C534D5F53535401; // "LSM_SST\x01"
+
+/// Magic number of the original, unversioned footer (format_version 1).
+pub const LEGACY_SSTABLE_MAGIC: u64 = 0x4C534D5F53535400; // "LSM_SST\0"
+
+/// Format version written by SSTableBuilder.
+pub const FORMAT_VERSION: u32 = 10;
 
 /// Metadata about an SSTable file, stored in the manifest.
 #[derive(Debug, Clone)]
@@ -16,13 +24,26 @@ pub struct SSTableMeta {
     pub file_size: u64,
     /// Number of entries (including tombstones).
     pub entry_count: u64,
+    /// When the SSTable was written, in seconds since the Unix epoch.
+    /// 0 for tables written before it was recorded.
+    pub creation_time: u64,
+    /// Smallest sequence number of the writes in the SSTable. 0 for
+    /// tables written before sequence numbers were recorded.
+    pub smallest_seqno: u64,
+    /// Largest sequence number of the writes in the SSTable; 0 if unknown.
+    pub largest_seqno: u64,
+    /// Which of the DB's paths (Options::db_paths) holds the file; 0 is
+    /// the first. Recorded in the manifest, not in the file itself.
+    pub path_id: u32,
 }
 
 /// An entry in the SSTable's index block.
-/// Maps a block's last key to its location in the file.
+/// Maps a block's separator key to its location in the file.
 #[derive(Debug, Clone)]
 pub struct IndexEntry {
-    /// Last (largest) key in the block.
+    /// Upper bound on the block's keys: >= its last key and < the next
+    /// block's first. Builders store the shortest such separator; tables
+    /// written before that store the last key itself.
     pub last_key: Vec<u8>,
     /// Byte offset of the block in the file.
     pub offset: u64,
@@ -32,10 +53,10 @@ pub struct IndexEntry {
 
 impl IndexEntry {
     /// Encode this index entry to bytes.
-    /// Format: [key_len(2B)][key][offset(8B)][size(8B)]
+    /// Format: [key_len(4B)][key][offset(8B)][size(8B)]
     pub fn encode(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(2 + self.last_key.len() + 16);
-        buf.extend_from_slice(&(self.last_key.len() as u16).to_le_bytes());
+        let mut buf = Vec::with_capacity(4 + self.last_key.len() + 16);
+        buf.extend_from_slice(&(self.last_key.len() as u32).to_le_bytes());
         buf.extend_from_slice(&self.last_key);
         buf.extend_from_slice(&self.offset.to_le_bytes());
         buf.extend_from_slice(&self.size.to_le_bytes());
@@ -44,21 +65,38 @@ impl IndexEntry {
 
     /// Decode an index entry from bytes, returning (entry, bytes_consumed).
     pub fn decode(data: &[u8]) -> crate::error::Result<(Self, usize)> {
-        if data.len() < 2 {
+        Self::decode_with_key_len_size(data, 4)
+    }
+
+    /// Decode an index entry written before format version 4, whose key
+    /// length is 2 bytes: [key_len(2B)][key][offset(8B)][size(8B)]
+    pub fn decode_legacy(data: &[u8]) -> crate::error::Result<(Self, usize)> {
+        Self::decode_with_key_len_size(data, 2)
+    }
+
+    fn decode_with_key_len_size(
+        data: &[u8],
+        len_size: usize,
+    ) -> crate::error::Result<(Self, usize)> {
+        if data.len() < len_size {
             return Err(crate::error::Error::Corruption(
                 "index entry too short".into(),
             ));
         }
-        let key_len = u16::from_le_bytes([data[0], data[1]]) as usize;
-        let total = 2 + key_len + 16;
+        let key_len = match len_size {
+            2 => u16::from_le_bytes([data[0], data[1]]) as usize,
+            _ => u32::from_le_bytes(data[0..4].try_into().unwrap()) as usize,
+        };
+        let total = len_size + key_len + 16;
         if data.len() < total {
             return Err(crate::error::Error::Corruption(
                 "index entry truncated".into(),
             ));
         }
-        let last_key = data[2..2 + key_len].to_vec();
-        let offset = u64::from_le_bytes(data[2 + key_len..10 + key_len].try_into().unwrap());
-        let size = u64::from_le_bytes(data[10 + key_len..18 + key_len].try_into().unwrap());
+        let key_end = len_size + key_len;
+        let last_key = data[len_size..key_end].to_vec();
+        let offset = u64::from_le_bytes(data[key_end..key_end + 8].try_into().unwrap());
+        let size = u64::from_le_bytes(data[key_end + 8..key_end + 16].try_into().unwrap());
         Ok((
             IndexEntry {
                 last_key,
@@ -81,9 +119,37 @@ impl IndexEntry {
 /// │ Meta block size (8B)                 │
 /// │ Bloom block offset (8B)              │
 /// │ Bloom block size (8B)                │
+/// │ Properties block offset (8B)         │
+/// │ Properties block size (8B)           │
+/// │ Range deletion block offset (8B)     │
+/// │ Range deletion block size (8B)       │
+/// │ Checksum type (8B)                   │
+/// │ Sequence number block offset (8B)    │
+/// │ Sequence number block size (8B)      │
+/// │ Format version (4B)                  │
+/// │ Footer CRC32 (4B)                    │
 /// │ Magic number (8B)                    │
 /// └──────────────────────────────────────┘
 /// ```
+///
+/// Format version 1 files have no version or reserved fields and end in
+/// LEGACY_SSTABLE_MAGIC. Readers find the layout from the magic, then
+/// dispatch on the version, so layout changes bump FORMAT_VERSION instead
+/// of breaking existing files:
+/// - 2: data blocks carry a compression type byte and CRC32 trailer
+/// - 3: adds the properties block
+/// - 4: block entry lengths become varints and block offsets, entry
+///   counts and index key lengths 4 bytes, lifting the 64KB key/value cap
+/// - 5: the field after the version, zero before, holds a CRC32 of the
+///   footer bytes preceding it
+/// - 6: adds the optional range deletion block
+/// - 7: the meta block records the smallest and largest sequence number
+/// - 8: the meta block records the creation time
+/// - 9: adds the checksum type of the data block trailers (CRC32 before)
+/// - 10: adds the optional sequence number block, each entry's sequence
+///   number
+///
+/// The version always sits 16 bytes from the end of the file.
 #[derive(Debug, Clone)]
 pub struct Footer {
     pub index_block_offset: u64,
@@ -92,14 +158,44 @@ pub struct Footer {
     pub meta_block_size: u64,
     pub bloom_block_offset: u64,
     pub bloom_block_size: u64,
+    /// Zero (no properties block) before format version 3.
+    pub properties_block_offset: u64,
+    pub properties_block_size: u64,
+    /// Zero (no range tombstones) before format version 6, and in tables
+    /// without any.
+    pub range_del_block_offset: u64,
+    pub range_del_block_size: u64,
+    /// Algorithm of the data block checksums. CRC32 before format
+    /// version 9. The footer's own checksum is always CRC32.
+    pub checksum: ChecksumType,
+    /// Zero (no per-entry sequence numbers) before format version 10, and
+    /// in tables with entries added without one.
+    pub seqno_block_offset: u64,
+    pub seqno_block_size: u64,
+    pub format_version: u32,
     pub magic: u64,
 }
 
 impl Footer {
-    /// Size of the footer in bytes (fixed).
-    pub const SIZE: usize = 8 * 7; // 56 bytes
+    /// Size of the current footer in bytes.
+    pub const SIZE: usize = 8 * 13 + 4 + 4 + 8; // 120 bytes
+
+    /// Size of a format version 9 footer in bytes.
+    pub const V9_SIZE: usize = 8 * 11 + 4 + 4 + 8; // 104 bytes
+
+    /// Size of a format version 6 to 8 footer in bytes.
+    pub const V8_SIZE: usize = 8 * 10 + 4 + 4 + 8; // 96 bytes
 
-    /// Encode footer to bytes.
+    /// Size of a format version 3 to 5 footer in bytes.
+    pub const V5_SIZE: usize = 8 * 8 + 4 + 4 + 8; // 80 bytes
+
+    /// Size of a format version 2 footer in bytes.
+    pub const V2_SIZE: usize = 8 * 6 + 4 + 4 + 8; // 64 bytes
+
+    /// Size of a format version 1 footer in bytes.
+    pub const LEGACY_SIZE: usize = 8 * 7; // 56 bytes
+
+    /// Encode footer to bytes, in the layout of its format_version.
     pub fn encode(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(Self::SIZE);
         buf.extend_from_slice(&self.index_block_offset.to_le_bytes());
@@ -108,40 +204,168 @@ impl Footer {
         buf.extend_from_slice(&self.meta_block_size.to_le_bytes());
         buf.extend_from_slice(&self.bloom_block_offset.to_le_bytes());
         buf.extend_from_slice(&self.bloom_block_size.to_le_bytes());
+        if self.format_version >= 3 {
+            buf.extend_from_slice(&self.properties_block_offset.to_le_bytes());
+            buf.extend_from_slice(&self.properties_block_size.to_le_bytes());
+        }
+        if self.format_version >= 6 {
+            buf.extend_from_slice(&self.range_del_block_offset.to_le_bytes());
+            buf.extend_from_slice(&self.range_del_block_size.to_le_bytes());
+        }
+        if self.format_version >= 9 {
+            buf.extend_from_slice(&u64::from(self.checksum.to_byte()).to_le_bytes());
+        }
+        if self.format_version >= 10 {
+            buf.extend_from_slice(&self.seqno_block_offset.to_le_bytes());
+            buf.extend_from_slice(&self.seqno_block_size.to_le_bytes());
+        }
+        if self.format_version >= 2 {
+            buf.extend_from_slice(&self.format_version.to_le_bytes());
+            let crc = if self.format_version >= 5 {
+                crc32fast::hash(&buf)
+            } else {
+                0
+            };
+            buf.extend_from_slice(&crc.to_le_bytes());
+        }
         buf.extend_from_slice(&self.magic.to_le_bytes());
         buf
     }
 
-    /// Decode footer from bytes.
+    /// Decode the footer from the tail of `data` (at least the last
+    /// LEGACY_SIZE or SIZE bytes of the file, depending on the format).
     pub fn decode(data: &[u8]) -> crate::error::Result<Self> {
-        if data.len() < Self::SIZE {
-            return Err(crate::error::Error::Corruption("footer too short".into()));
-        }
-        let index_block_offset = u64::from_le_bytes(data[0..8].try_into().unwrap());
-        let index_block_size = u64::from_le_bytes(data[8..16].try_into().unwrap());
-        let meta_block_offset = u64::from_le_bytes(data[16..24].try_into().unwrap());
-        let meta_block_size = u64::from_le_bytes(data[24..32].try_into().unwrap());
-        let bloom_block_offset = u64::from_le_bytes(data[32..40].try_into().unwrap());
-        let bloom_block_size = u64::from_le_bytes(data[40..48].try_into().unwrap());
-        let magic = u64::from_le_bytes(data[48..56].try_into().unwrap());
-
-        if magic != SSTABLE_MAGIC {
-            return Err(crate::error::Error::Corruption(format!(
-                "bad magic: expected {:#x}, got {:#x}",
-                SSTABLE_MAGIC, magic
-            )));
+        use crate::error::Error;
+
+        if data.len() < 8 {
+            return Err(Error::Corruption("footer too short".into()));
         }
+        let magic = u64::from_le_bytes(data[data.len() - 8..].try_into().unwrap());
+
+        let (footer, format_version) = match magic {
+            LEGACY_SSTABLE_MAGIC => {
+                if data.len() < Self::LEGACY_SIZE {
+                    return Err(Error::Corruption("footer too short".into()));
+                }
+                (&data[data.len() - Self::LEGACY_SIZE..], 1)
+            }
+            SSTABLE_MAGIC => {
+                if data.len() < 16 {
+                    return Err(Error::Corruption("footer too short".into()));
+                }
+                let version_at = data.len() - 16;
+                let format_version =
+                    u32::from_le_bytes(data[version_at..version_at + 4].try_into().unwrap());
+                let size = match format_version {
+                    2 => Self::V2_SIZE,
+                    3..=5 => Self::V5_SIZE,
+                    6..=8 => Self::V8_SIZE,
+                    9 => Self::V9_SIZE,
+                    10 => Self::SIZE,
+                    v => {
+                        return Err(Error::Corruption(format!(
+                            "unsupported SSTable format_version {v} (newest supported is {FORMAT_VERSION})"
+                        )));
+                    }
+                };
+                if data.len() < size {
+                    return Err(Error::Corruption("footer too short".into()));
+                }
+                let footer = &data[data.len() - size..];
+                if format_version >= 5 {
+                    let stored =
+                        u32::from_le_bytes(footer[size - 12..size - 8].try_into().unwrap());
+                    if crc32fast::hash(&footer[..size - 12]) != stored {
+                        return Err(Error::Corruption("footer checksum mismatch".into()));
+                    }
+                }
+                (footer, format_version)
+            }
+            _ => {
+                return Err(Error::Corruption(format!(
+                    "bad magic: expected {:#x}, got {:#x}",
+                    SSTABLE_MAGIC, magic
+                )));
+            }
+        };
 
+        let field = |i: usize| u64::from_le_bytes(footer[i * 8..i * 8 + 8].try_into().unwrap());
+        let checksum = if format_version >= 9 {
+            u8::try_from(field(10))
+                .ok()
+                .and_then(ChecksumType::from_byte)
+                .ok_or_else(|| Error::Corruption(format!("unknown checksum type {}", field(10))))?
+        } else {
+            ChecksumType::Crc32
+        };
         Ok(Footer {
-            index_block_offset,
-            index_block_size,
-            meta_block_offset,
-            meta_block_size,
-            bloom_block_offset,
-            bloom_block_size,
+            index_block_offset: field(0),
+            index_block_size: field(1),
+            meta_block_offset: field(2),
+            meta_block_size: field(3),
+            bloom_block_offset: field(4),
+            bloom_block_size: field(5),
+            properties_block_offset: if format_version >= 3 { field(6) } else { 0 },
+            properties_block_size: if format_version >= 3 { field(7) } else { 0 },
+            range_del_block_offset: if format_version >= 6 { field(8) } else { 0 },
+            range_del_block_size: if format_version >= 6 { field(9) } else { 0 },
+            checksum,
+            seqno_block_offset: if format_version >= 10 { field(11) } else { 0 },
+            seqno_block_size: if format_version >= 10 { field(12) } else { 0 },
+            format_version,
             magic,
         })
     }
+
+    /// Find the last intact footer in `data` (a whole file), for files
+    /// whose tail is torn or garbled: scans back from the end for a
+    /// checksummed footer whose blocks all lie before it. Returns it with
+    /// the length of the file it ends.
+    ///
+    /// Footers before format version 5 have no checksum, so can't be told
+    /// from stray bytes and are never returned.
+    pub fn find_last_valid(data: &[u8]) -> Option<(u64, Self)> {
+        Self::find_last_valid_in(data, 0, 0)
+    }
+
+    /// find_last_valid() over part of a file: `data` is read from
+    /// `offset`, and only footers ending more than `skip` bytes into it
+    /// are considered, so the `skip` bytes before can hold the start of
+    /// one. Returns the footer with the file offset it ends at.
+    pub fn find_last_valid_in(data: &[u8], offset: u64, skip: usize) -> Option<(u64, Self)> {
+        let magic = SSTABLE_MAGIC.to_le_bytes();
+        (Self::V5_SIZE.max(skip + 1)..=data.len())
+            .rev()
+            .find_map(|end| {
+                if data[end - 8..end] != magic {
+                    return None;
+                }
+                let footer = Self::decode(&data[end.saturating_sub(Self::SIZE)..end]).ok()?;
+                let start = offset + (end - footer.encoded_size()) as u64;
+                let within =
+                    |offset: u64, size: u64| offset.checked_add(size).is_some_and(|e| e <= start);
+                (footer.format_version >= 5
+                    && within(footer.index_block_offset, footer.index_block_size)
+                    && within(footer.meta_block_offset, footer.meta_block_size)
+                    && within(footer.bloom_block_offset, footer.bloom_block_size)
+                    && within(footer.properties_block_offset, footer.properties_block_size)
+                    && within(footer.range_del_block_offset, footer.range_del_block_size)
+                    && within(footer.seqno_block_offset, footer.seqno_block_size))
+                .then_some((offset + end as u64, footer))
+            })
+    }
+
+    /// Size of this footer on disk, per its format version.
+    pub fn encoded_size(&self) -> usize {
+        match self.format_version {
+            1 => Self::LEGACY_SIZE,
+            2 => Self::V2_SIZE,
+            3..=5 => Self::V5_SIZE,
+            6..=8 => Self::V8_SIZE,
+            9 => Self::V9_SIZE,
+            _ => Self::SIZE,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -157,6 +381,14 @@ mod tests {
             meta_block_size: 0,
             bloom_block_offset: 2048,
             bloom_block_size: 256,
+            properties_block_offset: 4000,
+            properties_block_size: 96,
+            range_del_block_offset: 4096,
+            range_del_block_size: 40,
+            checksum: ChecksumType::Crc32c,
+            seqno_block_offset: 4136,
+            seqno_block_size: 12,
+            format_version: FORMAT_VERSION,
             magic: SSTABLE_MAGIC,
         };
         let encoded = footer.encode();
@@ -168,6 +400,14 @@ mod tests {
         assert_eq!(decoded.meta_block_size, 0);
         assert_eq!(decoded.bloom_block_offset, 2048);
         assert_eq!(decoded.bloom_block_size, 256);
+        assert_eq!(decoded.properties_block_offset, 4000);
+        assert_eq!(decoded.properties_block_size, 96);
+        assert_eq!(decoded.range_del_block_offset, 4096);
+        assert_eq!(decoded.range_del_block_size, 40);
+        assert_eq!(decoded.checksum, ChecksumType::Crc32c);
+        assert_eq!(decoded.seqno_block_offset, 4136);
+        assert_eq!(decoded.seqno_block_size, 12);
+        assert_eq!(decoded.format_version, FORMAT_VERSION);
         assert_eq!(decoded.magic, SSTABLE_MAGIC);
     }
 
@@ -180,11 +420,19 @@ mod tests {
             meta_block_size: 0,
             bloom_block_offset: 0,
             bloom_block_size: 0,
+            properties_block_offset: 0,
+            properties_block_size: 0,
+            range_del_block_offset: 0,
+            range_del_block_size: 0,
+            checksum: ChecksumType::Crc32,
+            seqno_block_offset: 0,
+            seqno_block_size: 0,
+            format_version: FORMAT_VERSION,
             magic: SSTABLE_MAGIC,
         }
         .encode();
         // Corrupt the magic
-        encoded[48] = 0xFF;
+        encoded[Footer::SIZE - 1] = 0xFF;
         assert!(Footer::decode(&encoded).is_err());
     }
 
@@ -0,0 +1,255 @@
+use std::sync::OnceLock;
+
+use crate::error::{Error, Result};
+use crate::sstable::footer::IndexEntry;
+
+/// Trailer magic marking a partitioned (two-level) index block.
+pub const PARTITIONED_INDEX_MAGIC: u64 = 0x4C534D5F50494458; // "LSM_PIDX"
+
+/// Block entries per index partition; tables with more data blocks than
+/// this get a partitioned index.
+pub const DEFAULT_INDEX_PARTITION_ENTRIES: usize = 1024;
+
+/// Size of the partitioned index trailer:
+/// [num_blocks(8B)][entries_per_partition(4B)][magic(8B)]
+const PARTITIONED_TRAILER_SIZE: usize = 8 + 4 + 8;
+
+/// Shortest key `k` with `start <= k < limit`, used as the index key of a
+/// block ending in `start` when the next block begins with `limit`.
+///
+/// Keys are ordered bytewise. The first byte where the two keys differ is
+/// bumped and everything after it dropped; when that would reach `limit`,
+/// the bump moves to a later byte of `start` instead. Falls back to
+/// `start` itself when nothing shorter separates them.
+pub fn find_shortest_separator(start: &[u8], limit: &[u8]) -> Vec<u8> {
+    let shared = start.iter().zip(limit).take_while(|(a, b)| a == b).count();
+    if shared >= start.len().min(limit.len()) {
+        // One key is a prefix of the other
+        return start.to_vec();
+    }
+
+    let diff = start[shared];
+    if diff < 0xFF && diff + 1 < limit[shared] {
+        let mut separator = start[..=shared].to_vec();
+        separator[shared] += 1;
+        return separator;
+    }
+
+    // start[..=shared] already sorts below limit, so any key extending it
+    // does too: bump the first later byte that can be bumped
+    for i in shared + 1..start.len() {
+        if start[i] < 0xFF {
+            let mut separator = start[..=i].to_vec();
+            separator[i] += 1;
+            return separator;
+        }
+    }
+    start.to_vec()
+}
+
+/// Shortest key `k >= key`, used as the index key of a table's last block.
+pub fn find_short_successor(key: &[u8]) -> Vec<u8> {
+    match key.iter().position(|&b| b < 0xFF) {
+        Some(i) => {
+            let mut successor = key[..=i].to_vec();
+            successor[i] += 1;
+            successor
+        }
+        None => key.to_vec(),
+    }
+}
+
+/// Serialize index entries back to back: the flat index block format, and
+/// the format of each partition of a partitioned index.
+pub fn encode_entries(entries: &[IndexEntry]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for entry in entries {
+        buf.extend_from_slice(&entry.encode());
+    }
+    buf
+}
+
+/// Encode the top-level block of a partitioned index: one entry per
+/// partition (its last key and location), then the trailer.
+pub fn encode_top_level(
+    partitions: &[IndexEntry],
+    num_blocks: usize,
+    entries_per_partition: usize,
+) -> Vec<u8> {
+    let mut buf = encode_entries(partitions);
+    buf.extend_from_slice(&(num_blocks as u64).to_le_bytes());
+    buf.extend_from_slice(&(entries_per_partition as u32).to_le_bytes());
+    buf.extend_from_slice(&PARTITIONED_INDEX_MAGIC.to_le_bytes());
+    buf
+}
+
+/// Decode consecutive index entries; `legacy` for the 2-byte key lengths
+/// of tables before format version 4.
+fn decode_entries(mut data: &[u8], legacy: bool) -> Result<Vec<IndexEntry>> {
+    let mut entries = Vec::new();
+    while !data.is_empty() {
+        let (entry, consumed) = if legacy {
+            IndexEntry::decode_legacy(data)?
+        } else {
+            IndexEntry::decode(data)?
+        };
+        entries.push(entry);
+        data = &data[consumed..];
+    }
+    Ok(entries)
+}
+
+/// An SSTable's block index, addressed by global block number.
+///
+/// Small tables keep a flat index fully in memory. Large ones are written
+/// as fixed-size partitions plus a top-level index over them; only the
+/// top level is read on open, and each partition is loaded the first time
+/// a lookup lands in it.
+pub struct Index {
+    kind: IndexKind,
+    /// Entries use the pre-format-version-4 layout.
+    legacy: bool,
+}
+
+enum IndexKind {
+    Flat(Vec<IndexEntry>),
+    Partitioned {
+        /// One entry per partition: its last key, offset and size.
+        top: Vec<IndexEntry>,
+        num_blocks: usize,
+        entries_per_partition: usize,
+        partitions: Vec<OnceLock<Vec<IndexEntry>>>,
+    },
+}
+
+impl Index {
+    /// Parse an index block, flat or the top level of a partitioned one,
+    /// from a table of the given format version.
+    pub fn decode(data: &[u8], format_version: u32) -> Result<Self> {
+        let legacy = format_version < 4;
+        let partitioned = data.len() >= PARTITIONED_TRAILER_SIZE
+            && data[data.len() - 8..] == PARTITIONED_INDEX_MAGIC.to_le_bytes();
+        if !partitioned {
+            return Ok(Self {
+                kind: IndexKind::Flat(decode_entries(data, legacy)?),
+                legacy,
+            });
+        }
+
+        let trailer = &data[data.len() - PARTITIONED_TRAILER_SIZE..];
+        let num_blocks = u64::from_le_bytes(trailer[0..8].try_into().unwrap()) as usize;
+        let entries_per_partition = u32::from_le_bytes(trailer[8..12].try_into().unwrap()) as usize;
+        let top = decode_entries(&data[..data.len() - PARTITIONED_TRAILER_SIZE], legacy)?;
+        if entries_per_partition == 0 || num_blocks.div_ceil(entries_per_partition) != top.len() {
+            return Err(Error::Corruption(
+                "partitioned index trailer doesn't match its partitions".into(),
+            ));
+        }
+        let partitions = (0..top.len()).map(|_| OnceLock::new()).collect();
+        Ok(Self {
+            kind: IndexKind::Partitioned {
+                top,
+                num_blocks,
+                entries_per_partition,
+                partitions,
+            },
+            legacy,
+        })
+    }
+
+    /// Number of data blocks.
+    pub fn len(&self) -> usize {
+        match &self.kind {
+            IndexKind::Flat(entries) => entries.len(),
+            IndexKind::Partitioned { num_blocks, .. } => *num_blocks,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether this is a two-level index.
+    pub fn is_partitioned(&self) -> bool {
+        matches!(self.kind, IndexKind::Partitioned { .. })
+    }
+
+    /// Number of index partitions currently in memory (0 for flat indexes).
+    pub fn loaded_partitions(&self) -> usize {
+        match &self.kind {
+            IndexKind::Flat(_) => 0,
+            IndexKind::Partitioned { partitions, .. } => {
+                partitions.iter().filter(|p| p.get().is_some()).count()
+            }
+        }
+    }
+
+    /// Entry for data block `block_idx` (< len()). `read` fetches
+    /// (offset, size) from the file when a partition has to be loaded.
+    pub fn entry(
+        &self,
+        block_idx: usize,
+        read: &dyn Fn(u64, u64) -> Result<Vec<u8>>,
+    ) -> Result<&IndexEntry> {
+        match &self.kind {
+            IndexKind::Flat(entries) => Ok(&entries[block_idx]),
+            IndexKind::Partitioned {
+                entries_per_partition,
+                ..
+            } => {
+                let partition = self.partition(block_idx / entries_per_partition, read)?;
+                partition
+                    .get(block_idx % entries_per_partition)
+                    .ok_or_else(|| Error::Corruption("index partition is short".into()))
+            }
+        }
+    }
+
+    /// First block whose index key >= `key`, or len() if every block ends
+    /// before it. Loads at most one partition.
+    pub fn find_block(
+        &self,
+        key: &[u8],
+        read: &dyn Fn(u64, u64) -> Result<Vec<u8>>,
+    ) -> Result<usize> {
+        match &self.kind {
+            IndexKind::Flat(entries) => {
+                Ok(entries.partition_point(|e| e.last_key.as_slice() < key))
+            }
+            IndexKind::Partitioned {
+                top,
+                num_blocks,
+                entries_per_partition,
+                ..
+            } => {
+                let p = top.partition_point(|e| e.last_key.as_slice() < key);
+                if p >= top.len() {
+                    return Ok(*num_blocks);
+                }
+                let within = self
+                    .partition(p, read)?
+                    .partition_point(|e| e.last_key.as_slice() < key);
+                Ok(p * entries_per_partition + within)
+            }
+        }
+    }
+
+    fn partition(
+        &self,
+        p: usize,
+        read: &dyn Fn(u64, u64) -> Result<Vec<u8>>,
+    ) -> Result<&[IndexEntry]> {
+        let IndexKind::Partitioned {
+            top, partitions, ..
+        } = &self.kind
+        else {
+            unreachable!("partition() on a flat index");
+        };
+        if let Some(entries) = partitions[p].get() {
+            return Ok(entries);
+        }
+        let data = read(top[p].offset, top[p].size)?;
+        let entries = decode_entries(&data, self.legacy)?;
+        Ok(partitions[p].get_or_init(|| entries))
+    }
+}
@@ -1,41 +1,97 @@
-use std::cell::RefMut;
-use std::io::{Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::sync::Arc;
 
 use crate::error::Result;
 use crate::iterator::StorageIterator;
 use crate::sstable::block::reader::Block;
-use crate::sstable::reader::SSTable;
+use crate::sstable::reader::{BlockReadOptions, ReadaheadBuffer, SSTable};
+
+/// Consecutive next-block loads after which a scan counts as sequential
+/// and starts reading ahead.
+const SEQUENTIAL_LOADS_BEFORE_READAHEAD: usize = 2;
+
+/// The table an SSTableIterator reads blocks from.
+enum TableRef<'a> {
+    Borrowed(&'a SSTable),
+    /// Shared with the table cache, so the iterator owns its table.
+    Shared(Arc<SSTable>),
+}
+
+impl Deref for TableRef<'_> {
+    type Target = SSTable;
+
+    fn deref(&self) -> &SSTable {
+        match self {
+            TableRef::Borrowed(sstable) => sstable,
+            TableRef::Shared(sstable) => sstable,
+        }
+    }
+}
 
 /// Sequential iterator over all entries in an SSTable.
 ///
 /// Reads blocks one at a time, iterates within each block,
-/// then moves to the next block via the index.
+/// then moves to the next block via the index. Keys and values are
+/// borrowed from the loaded block, which shares the block cache's buffer
+/// when it came from there: nothing is copied out per entry.
+///
+/// Borrows its SSTable, or owns a share of it (SSTable::shared_iter), so
+/// merges can hold it as a Box<dyn StorageIterator>.
 pub struct SSTableIterator<'a> {
-    /// Reference to parent SSTable for reading blocks.
-    sstable: &'a SSTable,
+    /// Parent SSTable for reading blocks.
+    sstable: TableRef<'a>,
     /// Current block index in the index vector.
     current_block_idx: usize,
     /// Current block data, loaded from disk.
     current_block: Option<Block>,
     /// Current position within the block (entry index).
     current_entry_idx: usize,
+    /// Start key for range iteration (optional).
+    start_key: Option<Vec<u8>>,
     /// End key for range iteration (optional).
     end_key: Option<Vec<u8>>,
+    /// Checksum / cache behavior for every block this iterator loads.
+    read_options: BlockReadOptions,
+    /// Blocks loaded in a row, each the one after the last.
+    sequential_loads: usize,
+    /// Bytes read ahead once the scan is sequential.
+    readahead: ReadaheadBuffer,
+    /// Report the table's per-entry sequence numbers, once with_seqnos()
+    /// has loaded them.
+    with_seqnos: bool,
+}
+
+impl SSTableIterator<'static> {
+    /// Like new_bounded(), over a table shared as an Arc<SSTable> (as the
+    /// table cache hands them out), which the iterator keeps alive.
+    pub fn shared(
+        sstable: Arc<SSTable>,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        read_options: BlockReadOptions,
+    ) -> Result<Self> {
+        Self::with_table(TableRef::Shared(sstable), lower, upper, read_options)
+    }
 }
 
 impl<'a> SSTableIterator<'a> {
     /// Create a new iterator starting at the first block.
     pub fn new(sstable: &'a SSTable) -> Result<Self> {
         let mut iter = Self {
-            sstable,
+            sstable: TableRef::Borrowed(sstable),
             current_block_idx: 0,
             current_block: None,
             current_entry_idx: 0,
+            start_key: None,
             end_key: None,
+            read_options: BlockReadOptions::default(),
+            sequential_loads: 0,
+            readahead: ReadaheadBuffer::default(),
+            with_seqnos: false,
         };
 
         // Load the first block if there is one
-        if !sstable.index().is_empty() {
+        if sstable.num_blocks() > 0 {
             iter.load_block(0)?;
         }
 
@@ -44,42 +100,77 @@ impl<'a> SSTableIterator<'a> {
 
     /// Create a new iterator for the range [start, end).
     pub fn new_range(sstable: &'a SSTable, start: &[u8], end: &[u8]) -> Result<Self> {
+        Self::new_bounded(sstable, Some(start), Some(end), BlockReadOptions::default())
+    }
+
+    /// Create a new iterator limited to [lower, upper); either bound may be open.
+    /// Positions at the first entry inside the bounds.
+    pub fn new_bounded(
+        sstable: &'a SSTable,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        read_options: BlockReadOptions,
+    ) -> Result<Self> {
+        Self::with_table(TableRef::Borrowed(sstable), lower, upper, read_options)
+    }
+
+    /// Like new_bounded(), positioned inside [lower, upper) of the table
+    /// `sstable` refers to.
+    fn with_table(
+        sstable: TableRef<'a>,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        read_options: BlockReadOptions,
+    ) -> Result<Self> {
         let mut iter = Self {
             sstable,
             current_block_idx: 0,
             current_block: None,
             current_entry_idx: 0,
-            end_key: Some(end.to_vec()),
+            start_key: lower.map(|k| k.to_vec()),
+            end_key: upper.map(|k| k.to_vec()),
+            read_options,
+            sequential_loads: 0,
+            readahead: ReadaheadBuffer::default(),
+            with_seqnos: false,
         };
 
-        // Seek to start key
-        iter.seek(start)?;
+        iter.seek_to_first()?;
 
         Ok(iter)
     }
 
+    /// Report each entry's sequence number through seqno(), reading the
+    /// table's sequence number block. Tables without one still report None.
+    pub fn with_seqnos(mut self) -> Result<Self> {
+        self.with_seqnos = self.sstable.entry_seqnos()?.is_some();
+        Ok(self)
+    }
+
     /// Load a specific block by index.
     fn load_block(&mut self, block_idx: usize) -> Result<()> {
-        if block_idx >= self.sstable.index().len() {
+        if block_idx >= self.sstable.num_blocks() {
             // No more blocks
             self.current_block = None;
-            self.current_block_idx = self.sstable.index().len();
+            self.current_block_idx = self.sstable.num_blocks();
             self.current_entry_idx = 0;
             return Ok(());
         }
 
-        let entry = &self.sstable.index()[block_idx];
-
-        // Read block from disk
-        let mut block_data = vec![0u8; entry.size as usize];
-        {
-            let mut file: RefMut<'_, std::fs::File> = self.sstable.file().borrow_mut();
-            file.seek(SeekFrom::Start(entry.offset))?;
-            file.read_exact(&mut block_data)?;
+        if self.current_block.is_some() && block_idx == self.current_block_idx + 1 {
+            self.sequential_loads += 1;
+        } else {
+            self.sequential_loads = 0;
         }
+        let readahead = (self.sequential_loads >= SEQUENTIAL_LOADS_BEFORE_READAHEAD)
+            .then_some(&mut self.readahead);
 
-        // Decode block
-        self.current_block = Some(Block::decode(block_data)?);
+        // Read (cache, readahead or disk) and decode the block
+        self.current_block = Some(self.sstable.read_block_with_readahead(
+            block_idx,
+            &self.read_options,
+            readahead,
+        )?);
         self.current_block_idx = block_idx;
         self.current_entry_idx = 0;
 
@@ -91,6 +182,73 @@ impl<'a> SSTableIterator<'a> {
         self.load_block(self.current_block_idx + 1)
     }
 
+    /// Step back to the last entry of the previous block, or become
+    /// invalid if the current block is the first one.
+    fn prev_block(&mut self) -> Result<()> {
+        if self.current_block_idx == 0 {
+            self.invalidate();
+            return Ok(());
+        }
+        self.load_block(self.current_block_idx - 1)?;
+        self.position_at_last_entry();
+        Ok(())
+    }
+
+    /// Move the cursor to the last entry of the loaded block.
+    fn position_at_last_entry(&mut self) {
+        if let Some(ref block) = self.current_block {
+            // Blocks are never empty, so len - 1 is a real entry.
+            self.current_entry_idx = block.offsets().len().saturating_sub(1);
+        }
+    }
+
+    /// Drop the current block so is_valid() returns false.
+    fn invalidate(&mut self) {
+        self.current_block = None;
+        self.current_block_idx = self.sstable.num_blocks();
+        self.current_entry_idx = 0;
+    }
+
+    /// Position at the last entry with key <= target, ignoring the end key.
+    fn seek_for_prev_unbounded(&mut self, key: &[u8]) -> Result<()> {
+        let num_blocks = self.sstable.num_blocks();
+        if num_blocks == 0 {
+            self.invalidate();
+            return Ok(());
+        }
+
+        // First block whose last_key >= target; if none, the floor is the
+        // very last entry of the table.
+        let block_idx = self.sstable.find_block(key)?;
+        if block_idx >= num_blocks {
+            self.load_block(num_blocks - 1)?;
+            self.position_at_last_entry();
+            return Ok(());
+        }
+
+        self.load_block(block_idx)?;
+
+        // "upper_bound" within the block: smallest index with key > target
+        let mut lo = 0usize;
+        let mut hi = self.current_block.as_ref().map_or(0, |b| b.offsets().len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.key_at(mid) <= key {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        if lo == 0 {
+            // Every key in this block is > target; floor lives in the previous block.
+            self.prev_block()
+        } else {
+            self.current_entry_idx = lo - 1;
+            Ok(())
+        }
+    }
+
     /// Check if current position is past the end key.
     fn is_past_end(&self) -> bool {
         if let Some(ref end) = self.end_key
@@ -102,6 +260,17 @@ impl<'a> SSTableIterator<'a> {
         false
     }
 
+    /// Check if current position is before the start key (only reachable via prev).
+    fn is_before_start(&self) -> bool {
+        if let Some(ref start) = self.start_key
+            && let Some(ref block) = self.current_block
+            && self.current_entry_idx < block.offsets().len()
+        {
+            return self.key() < start.as_slice();
+        }
+        false
+    }
+
     /// Get key at current position.
     fn key_at(&self, idx: usize) -> &[u8] {
         if let Some(ref block) = self.current_block {
@@ -114,6 +283,9 @@ impl<'a> SSTableIterator<'a> {
     /// Get value at current position.
     fn value_at(&self, idx: usize) -> &[u8] {
         if let Some(ref block) = self.current_block {
+            if self.read_options.keys_only {
+                return block.keys_only_value_at(idx);
+            }
             block.value_at(idx)
         } else {
             &[]
@@ -130,8 +302,19 @@ impl<'a> StorageIterator for SSTableIterator<'a> {
         self.value_at(self.current_entry_idx)
     }
 
+    fn seqno(&self) -> Option<u64> {
+        if !self.with_seqnos {
+            return None;
+        }
+        // Already loaded, and checked, by with_seqnos()
+        self.sstable
+            .entry_seqnos()
+            .ok()??
+            .get(self.current_block_idx, self.current_entry_idx)
+    }
+
     fn is_valid(&self) -> bool {
-        if self.is_past_end() {
+        if self.is_past_end() || self.is_before_start() {
             return false;
         }
         if let Some(ref block) = self.current_block {
@@ -154,24 +337,22 @@ impl<'a> StorageIterator for SSTableIterator<'a> {
     }
 
     fn seek(&mut self, key: &[u8]) -> Result<()> {
-        // Binary search index to find the right block
-        let block_idx = match self
-            .sstable
-            .index()
-            .binary_search_by(|entry| entry.last_key.as_slice().cmp(key))
+        // Never position before the start key
+        if let Some(ref start) = self.start_key
+            && key < start.as_slice()
         {
-            Ok(idx) => idx, // key == last_key, this block contains it
-            Err(idx) => {
-                // key < last_key, this block might contain it
-                if idx >= self.sstable.index().len() {
-                    // key > all keys in SSTable
-                    self.current_block = None;
-                    self.current_block_idx = self.sstable.index().len();
-                    return Ok(());
-                }
-                idx
-            }
-        };
+            let start = start.clone();
+            return self.seek(&start);
+        }
+
+        // Binary search index to find the right block
+        let block_idx = self.sstable.find_block(key)?;
+        if block_idx >= self.sstable.num_blocks() {
+            // key > all keys in SSTable
+            self.current_block = None;
+            self.current_block_idx = self.sstable.num_blocks();
+            return Ok(());
+        }
 
         // Load that block
         self.load_block(block_idx)?;
@@ -193,8 +374,62 @@ impl<'a> StorageIterator for SSTableIterator<'a> {
             }
 
             self.current_entry_idx = lo;
+
+            // The index key is a separator that can sort after every key in
+            // the block, so the target may fall past its end: the first key
+            // >= target is then the next block's first
+            if lo == offsets.len() {
+                return self.next_block();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn prev(&mut self) -> Result<()> {
+        if self.current_block.is_none() {
+            return Ok(());
+        }
+        if self.current_entry_idx == 0 {
+            return self.prev_block();
         }
+        self.current_entry_idx -= 1;
+        Ok(())
+    }
 
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        // For range iterators the floor must also be < end_key.
+        match self.end_key.clone() {
+            Some(end) if key >= end.as_slice() => {
+                self.seek_for_prev_unbounded(&end)?;
+                if self.current_block.is_some() && self.key() == end.as_slice() {
+                    self.prev()?;
+                }
+            }
+            _ => self.seek_for_prev_unbounded(key)?,
+        }
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        if let Some(start) = self.start_key.clone() {
+            return self.seek(&start);
+        }
+        self.load_block(0)
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        if let Some(end) = self.end_key.clone() {
+            // seek_for_prev already keeps the floor strictly below end_key
+            return self.seek_for_prev(&end);
+        }
+        let num_blocks = self.sstable.num_blocks();
+        if num_blocks == 0 {
+            self.invalidate();
+            return Ok(());
+        }
+        self.load_block(num_blocks - 1)?;
+        self.position_at_last_entry();
         Ok(())
     }
 }
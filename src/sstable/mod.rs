@@ -1,5 +1,14 @@
 pub mod block;
 pub mod builder;
+pub mod compression;
+pub mod direct_io;
+pub mod filter;
 pub mod footer;
+pub mod index;
 pub mod iterator;
+pub mod paths;
+pub mod properties;
+pub mod range_del;
 pub mod reader;
+pub mod seqno;
+pub mod verify;
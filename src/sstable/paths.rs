@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::sstable::footer::SSTableMeta;
+
+/// A directory SSTables may be written to, and how many bytes of them
+/// it should hold.
+#[derive(Debug, Clone)]
+pub struct DbPath {
+    pub path: PathBuf,
+    /// Once the live SSTables in `path` add up to this, or its disk has
+    /// no room for the next one, new ones go to the next path. The last
+    /// path takes whatever doesn't fit.
+    pub target_size: u64,
+}
+
+impl DbPath {
+    pub fn new(path: impl Into<PathBuf>, target_size: u64) -> Self {
+        DbPath {
+            path: path.into(),
+            target_size,
+        }
+    }
+}
+
+/// The directories a DB's SSTables live in. A table's file is
+/// `{dir}/{id:06}.sst`, with `dir` the path at its SSTableMeta::path_id.
+///
+/// With a cold tier, it's the last path, and holds every table from its
+/// level down; the other paths hold the levels above.
+#[derive(Debug, Clone)]
+pub struct TablePaths {
+    paths: Arc<[DbPath]>,
+    /// First level kept in the cold tier, if there is one.
+    cold_level: Option<u32>,
+}
+
+impl TablePaths {
+    /// Tables spread over `paths`, in order. Must not be empty.
+    pub fn new(paths: Vec<DbPath>) -> Self {
+        assert!(!paths.is_empty(), "TablePaths needs at least one path");
+        TablePaths {
+            paths: paths.into(),
+            cold_level: None,
+        }
+    }
+
+    /// Add `dir` as the cold tier, holding the tables of `level` and
+    /// every level below it.
+    pub fn with_cold_tier(self, dir: &Path, level: u32) -> Self {
+        let mut paths = self.paths.to_vec();
+        paths.push(DbPath::new(dir, u64::MAX));
+        TablePaths {
+            paths: paths.into(),
+            cold_level: Some(level),
+        }
+    }
+
+    /// Every table in `dir`.
+    pub fn single(dir: &Path) -> Self {
+        Self::new(vec![DbPath::new(dir, u64::MAX)])
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// The directory of `path_id`; ids past the end map to the last path.
+    pub fn dir(&self, path_id: u32) -> &Path {
+        let last = self.paths.len() - 1;
+        &self.paths[(path_id as usize).min(last)].path
+    }
+
+    /// File of SSTable `id` in path `path_id`.
+    pub fn table_file(&self, id: u64, path_id: u32) -> PathBuf {
+        self.dir(path_id).join(format!("{:06}.sst", id))
+    }
+
+    /// Where a new table of `level`, expected to take `table_size` bytes,
+    /// goes: the cold tier if the level belongs there, otherwise the first
+    /// other path whose live tables in `tables` are still below its target
+    /// size and whose filesystem has `table_size` bytes free. If none
+    /// has both, the last of them with the space free, or the last.
+    pub fn choose<'a>(
+        &self,
+        level: u32,
+        tables: impl IntoIterator<Item = &'a SSTableMeta>,
+        table_size: u64,
+    ) -> u32 {
+        self.choose_with(level, tables, table_size, available_space)
+    }
+
+    /// Like choose(), with the bytes free under a directory from
+    /// `available`; None, if they can't be told, counts as enough.
+    pub fn choose_with<'a>(
+        &self,
+        level: u32,
+        tables: impl IntoIterator<Item = &'a SSTableMeta>,
+        table_size: u64,
+        available: impl Fn(&Path) -> Option<u64>,
+    ) -> u32 {
+        let last = self.paths.len() - 1;
+        let hot_paths = match self.cold_level {
+            Some(cold_level) if level >= cold_level => return last as u32,
+            Some(_) => &self.paths[..last],
+            None => &self.paths[..],
+        };
+        let mut usage = vec![0u64; hot_paths.len()];
+        for meta in tables {
+            if let Some(bytes) = usage.get_mut(meta.path_id as usize) {
+                *bytes += meta.file_size;
+            }
+        }
+        let fits: Vec<bool> = hot_paths
+            .iter()
+            .map(|path| available(&path.path).is_none_or(|free| free >= table_size))
+            .collect();
+        hot_paths
+            .iter()
+            .zip(&usage)
+            .zip(&fits)
+            .position(|((path, used), fits)| *used < path.target_size && *fits)
+            .or_else(|| fits.iter().rposition(|fits| *fits))
+            .unwrap_or(hot_paths.len() - 1) as u32
+    }
+}
+
+/// Bytes free for new files on the filesystem holding `dir`, or None if
+/// that can't be read (e.g. `dir` doesn't exist yet).
+pub fn available_space(dir: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+        // SAFETY: `path` is NUL-terminated and `stat` is written by the
+        // call before it's read
+        let stat = unsafe {
+            let mut stat: libc::statvfs = std::mem::zeroed();
+            if libc::statvfs(path.as_ptr(), &mut stat) != 0 {
+                return None;
+            }
+            stat
+        };
+        // The field types differ between platforms
+        #[allow(clippy::unnecessary_cast)]
+        Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+        None
+    }
+}
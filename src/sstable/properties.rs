@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use crate::error::{Error, Result};
+
+/// Gathers custom statistics about an SSTable while it's built.
+///
+/// add() sees every entry in key order (tombstones have empty values);
+/// finish() returns the properties to store, which readers get back from
+/// SSTable::properties() in user_collected_properties.
+pub trait TablePropertiesCollector: Send {
+    /// Called for every entry added to the table.
+    fn add(&mut self, key: &[u8], value: &[u8]);
+
+    /// Called once, when the table is finished.
+    fn finish(&mut self) -> Vec<(String, Vec<u8>)>;
+}
+
+/// Creates one collector per SSTable built by flush or compaction.
+/// Configured through Options::table_properties_collectors.
+pub trait TablePropertiesCollectorFactory: Send + Sync {
+    fn create(&self) -> Box<dyn TablePropertiesCollector>;
+}
+
+/// Statistics stored in an SSTable's properties block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableProperties {
+    /// Entries in the table, tombstones included.
+    pub num_entries: u64,
+    /// Tombstones in the table.
+    pub num_deletions: u64,
+    /// Sum of key lengths, before compression.
+    pub raw_key_size: u64,
+    /// Sum of value lengths, before compression.
+    pub raw_value_size: u64,
+    /// Number of data blocks.
+    pub num_data_blocks: u64,
+    /// Bytes of data blocks on disk (after compression, with trailers).
+    pub data_size: u64,
+    /// Bytes of the index, all partitions included.
+    pub index_size: u64,
+    /// Bytes of the bloom filter(s), filter index included.
+    pub filter_size: u64,
+    /// Properties returned by TablePropertiesCollectors.
+    pub user_collected_properties: BTreeMap<String, Vec<u8>>,
+}
+
+impl TableProperties {
+    /// Encode the properties block.
+    /// Format: 8 × u64 stats, then [count(4B)] and per user property
+    /// [name_len(4B)][name][value_len(4B)][value]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for stat in [
+            self.num_entries,
+            self.num_deletions,
+            self.raw_key_size,
+            self.raw_value_size,
+            self.num_data_blocks,
+            self.data_size,
+            self.index_size,
+            self.filter_size,
+        ] {
+            buf.extend_from_slice(&stat.to_le_bytes());
+        }
+        buf.extend_from_slice(&(self.user_collected_properties.len() as u32).to_le_bytes());
+        for (name, value) in &self.user_collected_properties {
+            buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            buf.extend_from_slice(name.as_bytes());
+            buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            buf.extend_from_slice(value);
+        }
+        buf
+    }
+
+    /// Decode a properties block written by encode().
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader { data, offset: 0 };
+        let mut stats = [0u64; 8];
+        for stat in &mut stats {
+            *stat = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+        }
+
+        let count = reader.u32()?;
+        let mut user_collected_properties = BTreeMap::new();
+        for _ in 0..count {
+            let name_len = reader.u32()? as usize;
+            let name = String::from_utf8(reader.take(name_len)?.to_vec())
+                .map_err(|_| Error::Corruption("property name is not UTF-8".into()))?;
+            let value_len = reader.u32()? as usize;
+            user_collected_properties.insert(name, reader.take(value_len)?.to_vec());
+        }
+
+        let [
+            num_entries,
+            num_deletions,
+            raw_key_size,
+            raw_value_size,
+            num_data_blocks,
+            data_size,
+            index_size,
+            filter_size,
+        ] = stats;
+        Ok(Self {
+            num_entries,
+            num_deletions,
+            raw_key_size,
+            raw_value_size,
+            num_data_blocks,
+            data_size,
+            index_size,
+            filter_size,
+            user_collected_properties,
+        })
+    }
+}
+
+/// Bounds-checked cursor over the properties block.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.data.len() < self.offset + n {
+            return Err(Error::Corruption("properties block truncated".into()));
+        }
+        let bytes = &self.data[self.offset..self.offset + n];
+        self.offset += n;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
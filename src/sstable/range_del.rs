@@ -0,0 +1,161 @@
+use crate::error::{Error, Result};
+
+/// Deletion of every key in [start, end).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeTombstone {
+    /// First deleted key (inclusive).
+    pub start: Vec<u8>,
+    /// End of the deleted range (exclusive).
+    pub end: Vec<u8>,
+}
+
+impl RangeTombstone {
+    pub fn new(start: &[u8], end: &[u8]) -> Self {
+        RangeTombstone {
+            start: start.to_vec(),
+            end: end.to_vec(),
+        }
+    }
+}
+
+/// An SSTable's range tombstones, stored fragmented: sorted by start,
+/// with overlapping and touching ranges merged, so a key is covered by at
+/// most one fragment and lookups are a binary search.
+///
+/// A table's range tombstones delete keys in older tables only. Point
+/// entries in the same table are newer than its range tombstones; writers
+/// drop entries a tombstone covers before the tombstone is written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeTombstones {
+    fragments: Vec<RangeTombstone>,
+}
+
+impl RangeTombstones {
+    /// Fragment `tombstones`, given in any order. Empty ranges are dropped.
+    pub fn new(mut tombstones: Vec<RangeTombstone>) -> Self {
+        tombstones.retain(|t| t.start < t.end);
+        tombstones.sort_by(|a, b| a.start.cmp(&b.start));
+        let mut fragments: Vec<RangeTombstone> = Vec::with_capacity(tombstones.len());
+        for tombstone in tombstones {
+            match fragments.last_mut() {
+                Some(last) if tombstone.start <= last.end => {
+                    if tombstone.end > last.end {
+                        last.end = tombstone.end;
+                    }
+                }
+                _ => fragments.push(tombstone),
+            }
+        }
+        RangeTombstones { fragments }
+    }
+
+    /// Whether some fragment covers `key`.
+    pub fn covers(&self, key: &[u8]) -> bool {
+        // First fragment starting after key; the one before it is the
+        // only candidate
+        let after = self
+            .fragments
+            .partition_point(|t| t.start.as_slice() <= key);
+        after > 0 && key < self.fragments[after - 1].end.as_slice()
+    }
+
+    /// Add the ranges of `other`, keeping the set fragmented.
+    pub fn extend(&mut self, other: &RangeTombstones) {
+        if other.is_empty() {
+            return;
+        }
+        let mut all = std::mem::take(&mut self.fragments);
+        all.extend(other.fragments.iter().cloned());
+        *self = Self::new(all);
+    }
+
+    /// The parts of the fragments within [lower, upper); None is unbounded.
+    pub fn clipped(&self, lower: Option<&[u8]>, upper: Option<&[u8]>) -> Vec<RangeTombstone> {
+        self.fragments
+            .iter()
+            .filter_map(|t| {
+                let start = match lower {
+                    Some(lower) if lower > t.start.as_slice() => lower,
+                    _ => t.start.as_slice(),
+                };
+                let end = match upper {
+                    Some(upper) if upper < t.end.as_slice() => upper,
+                    _ => t.end.as_slice(),
+                };
+                (start < end).then(|| RangeTombstone::new(start, end))
+            })
+            .collect()
+    }
+
+    /// The fragments, in key order.
+    pub fn fragments(&self) -> &[RangeTombstone] {
+        &self.fragments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// Encode the range deletion block.
+    /// Format: [count(4B)] and per fragment
+    /// [start_len(4B)][start][end_len(4B)][end], then a CRC32 (4B) of the
+    /// bytes before it
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(self.fragments.len() as u32).to_le_bytes());
+        for t in &self.fragments {
+            for key in [&t.start, &t.end] {
+                buf.extend_from_slice(&(key.len() as u32).to_le_bytes());
+                buf.extend_from_slice(key);
+            }
+        }
+        let crc = crc32fast::hash(&buf);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Decode a range deletion block written by encode().
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 {
+            return Err(Error::Corruption("range deletion block too short".into()));
+        }
+        let (body, crc) = data.split_at(data.len() - 4);
+        if crc32fast::hash(body) != u32::from_le_bytes(crc.try_into().unwrap()) {
+            return Err(Error::Corruption(
+                "range deletion block checksum mismatch".into(),
+            ));
+        }
+
+        let mut offset = 0;
+        let mut take = |n: usize| -> Result<&[u8]> {
+            if body.len() < offset + n {
+                return Err(Error::Corruption("range deletion block truncated".into()));
+            }
+            offset += n;
+            Ok(&body[offset - n..offset])
+        };
+        let count = u32::from_le_bytes(take(4)?.try_into().unwrap());
+        let mut fragments = Vec::new();
+        for _ in 0..count {
+            let start_len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let start = take(start_len)?.to_vec();
+            let end_len = u32::from_le_bytes(take(4)?.try_into().unwrap()) as usize;
+            let end = take(end_len)?.to_vec();
+            if fragments
+                .last()
+                .is_some_and(|last: &RangeTombstone| last.end >= start)
+                || start >= end
+            {
+                return Err(Error::Corruption(
+                    "range deletion fragments out of order".into(),
+                ));
+            }
+            fragments.push(RangeTombstone { start, end });
+        }
+        Ok(RangeTombstones { fragments })
+    }
+}
@@ -1,16 +1,101 @@
-use std::cell::RefCell;
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::FileExt;
 use std::path::{Path, PathBuf};
-
-use crate::bloom::BloomFilter;
-use crate::error::Result;
-use crate::sstable::block::reader::Block;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use bytes::Bytes;
+use memmap2::Mmap;
+
+use crate::cache::BlockCache;
+use crate::error::{Error, Result};
+use crate::sstable::block::BLOCK_TRAILER_SIZE;
+use crate::sstable::block::reader::{Block, decompress_block_with_dict};
+use crate::sstable::direct_io;
+use crate::sstable::filter::Filter;
 use crate::sstable::footer::{Footer, IndexEntry, SSTableMeta};
+use crate::sstable::index::Index;
 use crate::sstable::iterator::SSTableIterator;
+use crate::sstable::properties::TableProperties;
+use crate::sstable::range_del::RangeTombstones;
+use crate::sstable::seqno::EntrySeqnos;
+use crate::sstable::verify::{IntegrityIssue, IntegrityReport};
 
 // TODO [M15]: Implement range iteration
 
+/// Per-read options for data block reads, derived from the DB-level
+/// ReadOptions and threaded through SSTable lookups and iterators.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockReadOptions {
+    /// Check each block's CRC32 trailer before decoding. Default: true.
+    pub verify_checksums: bool,
+    /// Insert blocks read from disk into the block cache. Default: true.
+    /// Turn off for large one-off scans so they don't evict hot blocks.
+    pub fill_cache: bool,
+    /// Iterators yield KEYS_ONLY_VALUE (empty for tombstones) instead of
+    /// the stored values. Point lookups ignore it. Default: false.
+    pub keys_only: bool,
+    /// Once an iterator is reading blocks sequentially, fetch this many
+    /// bytes per file read instead of one block at a time. 0 disables
+    /// readahead. Default: 0.
+    pub readahead_size: usize,
+    /// How tables are read (Options::use_mmap_reads, use_direct_reads).
+    /// Only consulted where the DB opens tables; an already open table
+    /// keeps its access path. Default: Buffered.
+    pub file_access: FileAccess,
+}
+
+impl Default for BlockReadOptions {
+    fn default() -> Self {
+        Self {
+            verify_checksums: true,
+            fill_cache: true,
+            keys_only: false,
+            readahead_size: 0,
+            file_access: FileAccess::Buffered,
+        }
+    }
+}
+
+/// How an opened SSTable reads its file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FileAccess {
+    /// Positioned reads through the OS page cache.
+    #[default]
+    Buffered,
+    /// Slices of a read-only memory map of the file.
+    Mmap,
+    /// O_DIRECT reads into aligned buffers, bypassing the page cache.
+    Direct,
+}
+
+/// Data block bytes fetched ahead of a sequential scan, starting at file
+/// offset `offset`. Owned by the iterator doing the scan.
+#[derive(Default)]
+pub(crate) struct ReadaheadBuffer {
+    offset: u64,
+    data: Vec<u8>,
+}
+
+impl ReadaheadBuffer {
+    /// The `size` bytes at `offset`, if they were read ahead.
+    fn get(&self, offset: u64, size: u64) -> Option<&[u8]> {
+        let start = offset.checked_sub(self.offset)? as usize;
+        self.data.get(start..start + size as usize)
+    }
+}
+
+/// Block contents held by the block cache, shared with the Blocks
+/// decoded from them.
+struct CachedBlock(Arc<Vec<u8>>);
+
+impl AsRef<[u8]> for CachedBlock {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 /// An opened SSTable file. Supports point lookups and range scans.
 ///
 /// On open:
@@ -20,21 +105,31 @@ use crate::sstable::iterator::SSTableIterator;
 /// 4. Ready for queries (data blocks read on demand)
 pub struct SSTable {
     /// Path to the SSTable file (for debugging/error messages).
-    #[allow(dead_code)]
     path: PathBuf,
-    /// Open file handle for reading data blocks.
-    /// Wrapped in RefCell to allow interior mutability for seeking/reading.
-    file: RefCell<File>,
-    /// Index entries parsed from the index block.
-    /// Each entry maps a block's last key to its file location.
-    index: Vec<IndexEntry>,
+    /// The file, read with seek+read or through a memory map.
+    file: TableFile,
+    /// Block index: maps each block's last key to its file location.
+    /// Partitioned indexes load their partitions on demand.
+    index: Index,
     /// Metadata about this SSTable (min/max keys, entry count, etc.).
     meta: SSTableMeta,
-    /// Bloom filter loaded from disk — checked before any block reads.
-    bloom: BloomFilter,
+    /// Bloom filter (whole-table, or per block and loaded on demand) —
+    /// checked before any block reads.
+    filter: Filter,
     /// Footer with offsets to index and meta blocks.
-    #[allow(dead_code)]
     footer: Footer,
+    /// Shared block cache consulted before reading data blocks, if any.
+    block_cache: Option<Arc<Mutex<BlockCache>>>,
+    /// Zstd dictionary from the meta block, if the table was built with one.
+    compression_dict: Option<Vec<u8>>,
+    /// Properties block, for tables written since format version 3.
+    properties: Option<TableProperties>,
+    /// Range deletion block; empty for tables without one.
+    range_tombstones: RangeTombstones,
+    /// Sequence number block, read on first use by entry_seqnos().
+    entry_seqnos: OnceLock<EntrySeqnos>,
+    /// File reads issued for data blocks (readahead counts once).
+    data_block_reads: AtomicU64,
 }
 
 impl SSTable {
@@ -43,77 +138,181 @@ impl SSTable {
     /// Reads the footer from the end of the file, then uses footer
     /// offsets to read and parse the index block into memory.
     pub fn open(path: &Path) -> Result<Self> {
-        // Open file for reading
-        let mut file = File::open(path)?;
+        Self::open_file(path, TableFile::Buffered(File::open(path)?))
+    }
+
+    /// Open an SSTable through a read-only memory map of the file.
+    ///
+    /// Blocks are served as slices of the map: no seek or read syscalls
+    /// and no file handle borrowed per read.
+    pub fn open_mmap(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: SSTables are immutable once written and only ever
+        // deleted (never truncated or rewritten in place), so the mapped
+        // bytes don't change underneath us.
+        let map = unsafe { Mmap::map(&file)? };
+        Self::open_file(path, TableFile::Mapped(map))
+    }
+
+    /// Open an SSTable for O_DIRECT reads, which bypass the OS page cache.
+    /// Linux only.
+    pub fn open_direct(path: &Path) -> Result<Self> {
+        Self::open_file(path, TableFile::Direct(direct_io::open_for_reads(path)?))
+    }
+
+    /// Open an SSTable with the given file access path.
+    pub fn open_with(path: &Path, access: FileAccess) -> Result<Self> {
+        match access {
+            FileAccess::Buffered => Self::open(path),
+            FileAccess::Mmap => Self::open_mmap(path),
+            FileAccess::Direct => Self::open_direct(path),
+        }
+    }
 
+    fn open_file(path: &Path, file: TableFile) -> Result<Self> {
         // Get file size to locate footer
-        let file_size = file.metadata()?.len();
-        if file_size < Footer::SIZE as u64 {
+        let file_size = file.len()?;
+        if file_size < Footer::LEGACY_SIZE as u64 {
             return Err(crate::error::Error::Corruption(
                 "file too short to contain footer".into(),
             ));
         }
 
-        // Read footer (last 40 bytes)
-        let footer_offset = file_size - Footer::SIZE as u64;
-        file.seek(SeekFrom::Start(footer_offset))?;
-        let mut footer_buf = vec![0u8; Footer::SIZE];
-        file.read_exact(&mut footer_buf)?;
-        let footer = Footer::decode(&footer_buf)?;
-
-        // Read index block
-        file.seek(SeekFrom::Start(footer.index_block_offset))?;
-        let mut index_buf = vec![0u8; footer.index_block_size as usize];
-        file.read_exact(&mut index_buf)?;
+        // Read enough of the tail for any footer version; decode() picks
+        // the layout from the magic number
+        let footer_len = file_size.min(Footer::SIZE as u64);
+        let (footer, file_size) =
+            match Footer::decode(&file.bytes_at(file_size - footer_len, footer_len)?) {
+                Ok(footer) => (footer, file_size),
+                // A torn or garbled tail (e.g. junk after a crash): fall
+                // back to the last intact footer before it
+                Err(Error::Corruption(msg)) => {
+                    Self::find_last_footer(&file, file_size)?.ok_or(Error::Corruption(msg))?
+                }
+                Err(e) => return Err(e),
+            };
 
-        // Parse index entries
-        let mut index = Vec::new();
-        let mut offset = 0usize;
-        while offset < index_buf.len() {
-            let (entry, consumed) = IndexEntry::decode(&index_buf[offset..])?;
-            index.push(entry);
-            offset += consumed;
-        }
+        // Read index block and parse its entries (only the top level if
+        // partitioned)
+        let index_buf = file.bytes_at(footer.index_block_offset, footer.index_block_size)?;
+        let index = Index::decode(&index_buf, footer.format_version)?;
 
         // Read bloom filter block
-        file.seek(SeekFrom::Start(footer.bloom_block_offset))?;
-        let mut bloom_buf = vec![0u8; footer.bloom_block_size as usize];
-        file.read_exact(&mut bloom_buf)?;
-        let bloom = BloomFilter::deserialize(&bloom_buf)?;
+        let bloom_buf = file.bytes_at(footer.bloom_block_offset, footer.bloom_block_size)?;
+        let filter = Filter::decode(&bloom_buf)?;
 
         // Read meta block and parse SSTableMeta
         // Format: [id(8B)][level(4B)][min_key_len(4B)][min_key][max_key_len(4B)][max_key][entry_count(8B)]
-        file.seek(SeekFrom::Start(footer.meta_block_offset))?;
-        let mut meta_buf = vec![0u8; footer.meta_block_size as usize];
-        file.read_exact(&mut meta_buf)?;
+        // then, from format version 7, [smallest_seqno(8B)][largest_seqno(8B)],
+        // from format version 8, [creation_time(8B)],
+        // plus an optional [dict_len(4B)][dict]
+        let meta_buf = file.bytes_at(footer.meta_block_offset, footer.meta_block_size)?;
 
-        let meta = if meta_buf.is_empty() {
+        let (meta, compression_dict) = if meta_buf.is_empty() {
             // Empty meta block - this shouldn't happen for valid SSTables
             // but we'll create a minimal one
-            SSTableMeta {
+            let meta = SSTableMeta {
                 id: 0,
                 level: 0,
                 min_key: vec![],
                 max_key: vec![],
                 file_size,
                 entry_count: 0,
-            }
+                creation_time: 0,
+                smallest_seqno: 0,
+                largest_seqno: 0,
+                path_id: 0,
+            };
+            (meta, None)
+        } else {
+            Self::parse_meta(&meta_buf, file_size, footer.format_version)?
+        };
+
+        // Read properties block (absent before format version 3)
+        let properties = if footer.properties_block_size > 0 {
+            let properties_buf =
+                file.bytes_at(footer.properties_block_offset, footer.properties_block_size)?;
+            Some(TableProperties::decode(&properties_buf)?)
         } else {
-            Self::parse_meta(&meta_buf, file_size)?
+            None
+        };
+
+        // Read range deletion block (absent before format version 6)
+        let range_tombstones = if footer.range_del_block_size > 0 {
+            let range_del_buf =
+                file.bytes_at(footer.range_del_block_offset, footer.range_del_block_size)?;
+            RangeTombstones::decode(&range_del_buf)?
+        } else {
+            RangeTombstones::default()
         };
 
         Ok(Self {
             path: path.to_path_buf(),
-            file: RefCell::new(file),
+            file,
             index,
             meta,
-            bloom,
+            filter,
             footer,
+            block_cache: None,
+            compression_dict,
+            properties,
+            range_tombstones,
+            entry_seqnos: OnceLock::new(),
+            data_block_reads: AtomicU64::new(0),
         })
     }
 
+    /// Find the last intact footer of a table with a torn tail, returning
+    /// it with the file size it ends. Scans back from the end a chunk at
+    /// a time, so a damaged large table isn't read into memory whole.
+    fn find_last_footer(file: &TableFile, file_size: u64) -> Result<Option<(Footer, u64)>> {
+        const CHUNK: u64 = 64 * 1024;
+        let mut end = file_size;
+        while end > 0 {
+            // Footers ending in (low, end]; the bytes before low are read
+            // too, for one that starts there
+            let low = end.saturating_sub(CHUNK);
+            let read_from = low.saturating_sub(Footer::SIZE as u64);
+            let data = file.bytes_at(read_from, end - read_from)?;
+            if let Some((found_end, footer)) =
+                Footer::find_last_valid_in(&data, read_from, (low - read_from) as usize)
+            {
+                return Ok(Some((footer, found_end)));
+            }
+            end = low;
+        }
+        Ok(None)
+    }
+
+    /// Open an SSTable with `access`, attaching `cache` if one is given.
+    pub(crate) fn open_cached(
+        path: &Path,
+        cache: Option<&Arc<Mutex<BlockCache>>>,
+        access: FileAccess,
+    ) -> Result<Self> {
+        let sst = Self::open_with(path, access)?;
+        Ok(match cache {
+            Some(cache) => sst.with_block_cache(Arc::clone(cache)),
+            None => sst,
+        })
+    }
+
+    /// Attach a shared block cache: data block reads check it first and,
+    /// unless fill_cache is off, insert blocks they had to read from disk.
+    pub fn with_block_cache(mut self, cache: Arc<Mutex<BlockCache>>) -> Self {
+        self.block_cache = Some(cache);
+        self
+    }
+
     /// Parse SSTableMeta from bytes.
-    fn parse_meta(data: &[u8], file_size: u64) -> Result<SSTableMeta> {
+    /// Parse the meta block into SSTableMeta plus the optional trailing
+    /// zstd dictionary (absent in tables built without one). Sequence
+    /// numbers are 0 before format version 7, creation time before 8.
+    fn parse_meta(
+        data: &[u8],
+        file_size: u64,
+        format_version: u32,
+    ) -> Result<(SSTableMeta, Option<Vec<u8>>)> {
         use crate::error::Error;
 
         let mut offset = 0usize;
@@ -169,15 +368,89 @@ impl SSTable {
             ));
         }
         let entry_count = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        offset += 8;
+
+        // smallest_seqno (8 bytes) + largest_seqno (8 bytes)
+        let (smallest_seqno, largest_seqno) = if format_version >= 7 {
+            if data.len() < offset + 16 {
+                return Err(Error::Corruption(
+                    "meta block too short for sequence numbers".into(),
+                ));
+            }
+            let smallest = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            let largest = u64::from_le_bytes(data[offset + 8..offset + 16].try_into().unwrap());
+            offset += 16;
+            (smallest, largest)
+        } else {
+            (0, 0)
+        };
 
-        Ok(SSTableMeta {
+        // creation_time (8 bytes)
+        let creation_time = if format_version >= 8 {
+            if data.len() < offset + 8 {
+                return Err(Error::Corruption(
+                    "meta block too short for creation_time".into(),
+                ));
+            }
+            let t = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            offset += 8;
+            t
+        } else {
+            0
+        };
+
+        // Optional dict_len (4 bytes) + dict
+        let compression_dict = if offset == data.len() {
+            None
+        } else {
+            if data.len() < offset + 4 {
+                return Err(Error::Corruption(
+                    "meta block too short for dict_len".into(),
+                ));
+            }
+            let dict_len =
+                u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+            offset += 4;
+            if data.len() < offset + dict_len {
+                return Err(Error::Corruption("meta block too short for dict".into()));
+            }
+            Some(data[offset..offset + dict_len].to_vec())
+        };
+
+        let meta = SSTableMeta {
             id,
             level,
             min_key,
             max_key,
             file_size,
             entry_count,
-        })
+            creation_time,
+            smallest_seqno,
+            largest_seqno,
+            path_id: 0,
+        };
+        Ok((meta, compression_dict))
+    }
+
+    /// Whether `key` may be in this table: inside [min_key, max_key] and
+    /// not ruled out by the bloom filter, or covered by a range tombstone.
+    /// Only per-block filters (and index partitions to find the block) may
+    /// need a disk read.
+    pub fn may_contain(&self, key: &[u8]) -> Result<bool> {
+        if self.range_tombstones.covers(key) {
+            return Ok(true);
+        }
+        if key < self.meta.min_key.as_slice()
+            || key > self.meta.max_key.as_slice()
+            || !self.filter.table_may_contain(key)
+        {
+            return Ok(false);
+        }
+        if !self.filter.is_partitioned() {
+            return Ok(true);
+        }
+        let block_idx = self.find_block(key)?;
+        Ok(block_idx < self.index.len() && self.block_may_contain(block_idx, key)?)
     }
 
     /// Point lookup: check if key exists and return its value.
@@ -187,48 +460,364 @@ impl SSTable {
     /// 2. Binary search index → find the right data block
     /// 3. Read that block from disk
     /// 4. Binary search within the block
-    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    ///
+    /// A key with no entry here but covered by one of the table's range
+    /// tombstones returns an empty value (a tombstone), so callers stop
+    /// before reaching older tables.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Bytes>> {
+        self.get_with_options(key, &BlockReadOptions::default())
+    }
+
+    /// Point lookup with explicit block read options.
+    pub fn get_with_options(
+        &self,
+        key: &[u8],
+        read_options: &BlockReadOptions,
+    ) -> Result<Option<Bytes>> {
+        let value = self.get_point(key, read_options)?;
+        if value.is_none() && self.range_tombstones.covers(key) {
+            return Ok(Some(Bytes::new()));
+        }
+        Ok(value)
+    }
+
+    /// The entry stored for `key` itself, ignoring range tombstones.
+    fn get_point(&self, key: &[u8], read_options: &BlockReadOptions) -> Result<Option<Bytes>> {
         // Step 1: Range check using cached metadata
         if key < self.meta.min_key.as_slice() || key > self.meta.max_key.as_slice() {
             return Ok(None);
         }
 
         // Step 2: Bloom filter check — if it says "no", key is definitely not here
-        if !self.bloom.may_contain(key) {
+        if !self.filter.table_may_contain(key) {
             return Ok(None);
         }
 
         // Step 3: Binary search the index to find the right block
         // Index is sorted by last_key, so we find the first block where
         // last_key >= key (lower_bound)
-        let block_idx = match self
-            .index
-            .binary_search_by(|entry| entry.last_key.as_slice().cmp(key))
+        let block_idx = self.find_block(key)?;
+        if block_idx >= self.index.len() {
+            return Ok(None);
+        }
+
+        // Per-block filters can only answer now that the block is known
+        if !self.block_may_contain(block_idx, key)? {
+            return Ok(None);
+        }
+
+        // Step 4: Read the block (cache or disk) and binary search within it.
+        // The value is a pinned slice of the decoded block — no copy
+        let block = self.read_block(block_idx, read_options)?;
+        Ok(block.get_pinned(key))
+    }
+
+    /// Approximate number of entries with keys in [start, end), without
+    /// scanning the table.
+    ///
+    /// Tables entirely inside the range answer from entry_count. Otherwise
+    /// the index narrows the range to a run of blocks: the two boundary
+    /// blocks are read and counted exactly, and every block in between is
+    /// assumed to hold the table's average entries per block.
+    pub fn estimate_keys_in_range(&self, start: &[u8], end: &[u8]) -> Result<u64> {
+        if start >= end
+            || end <= self.meta.min_key.as_slice()
+            || start > self.meta.max_key.as_slice()
+            || self.index.is_empty()
         {
-            Ok(idx) => idx, // key == last_key, this block contains it
-            Err(idx) => {
-                // key < last_key, this block might contain it
-                // But idx could be out of bounds (key > all last_keys)
-                if idx >= self.index.len() {
-                    return Ok(None);
+            return Ok(0);
+        }
+        if start <= self.meta.min_key.as_slice() && end > self.meta.max_key.as_slice() {
+            return Ok(self.meta.entry_count);
+        }
+
+        // Blocks that can hold keys in [start, end): the first whose
+        // last_key >= start through the first whose last_key >= end
+        let last = self.index.len() - 1;
+        let first_block = self.find_block(start)?.min(last);
+        let last_block = self.find_block(end)?.min(last);
+
+        // Sample the boundary blocks without polluting the cache
+        let sample = BlockReadOptions {
+            fill_cache: false,
+            ..BlockReadOptions::default()
+        };
+        let count_in_block = |idx: usize| -> Result<u64> {
+            let block = self.read_block(idx, &sample)?;
+            let n = (0..block.offsets().len())
+                .filter(|&i| {
+                    let key = block.key_at(i);
+                    key >= start && key < end
+                })
+                .count();
+            Ok(n as u64)
+        };
+
+        let mut estimate = count_in_block(first_block)?;
+        if last_block > first_block {
+            estimate += count_in_block(last_block)?;
+            let middle_blocks = (last_block - first_block - 1) as u64;
+            estimate += middle_blocks * self.meta.entry_count / self.index.len() as u64;
+        }
+        Ok(estimate)
+    }
+
+    /// Read every data block straight from the file, bypassing the block
+    /// cache, and check the table against itself: block checksums, strictly
+    /// increasing keys, each key within its block's index range, and the
+    /// meta block's entry count and min/max keys (plus the properties
+    /// block's entry count) against what was read. The count and key
+    /// cross-checks are skipped if any block couldn't be read.
+    ///
+    /// Problems with the data are collected in the report; only failures
+    /// to load the index itself are returned as errors.
+    pub fn verify_integrity(&self) -> Result<IntegrityReport> {
+        let mut report = IntegrityReport::default();
+        let mut first_key: Option<Vec<u8>> = None;
+        let mut prev_key: Option<Vec<u8>> = None;
+        let mut prev_separator: Option<Vec<u8>> = None;
+        let mut all_readable = true;
+
+        for block_idx in 0..self.index.len() {
+            let entry = self.index_entry(block_idx)?.clone();
+            report.blocks_checked += 1;
+            match self.verify_block(block_idx, &entry) {
+                Ok(block) => {
+                    for i in 0..block.offsets().len() {
+                        let key = block.key_at(i);
+                        if prev_key.as_deref().is_some_and(|prev| key <= prev) {
+                            report.issues.push(IntegrityIssue::KeyOutOfOrder {
+                                block_idx,
+                                key: key.to_vec(),
+                            });
+                        }
+                        if key > entry.last_key.as_slice()
+                            || prev_separator.as_deref().is_some_and(|sep| key <= sep)
+                        {
+                            report.issues.push(IntegrityIssue::KeyOutsideIndexRange {
+                                block_idx,
+                                key: key.to_vec(),
+                            });
+                        }
+                        first_key.get_or_insert_with(|| key.to_vec());
+                        prev_key = Some(key.to_vec());
+                        report.entries_checked += 1;
+                    }
+                }
+                Err(issue) => {
+                    all_readable = false;
+                    report.issues.push(issue);
                 }
-                idx
+            }
+            prev_separator = Some(entry.last_key);
+        }
+
+        if !all_readable {
+            return Ok(report);
+        }
+        if report.entries_checked != self.meta.entry_count {
+            report.issues.push(IntegrityIssue::EntryCountMismatch {
+                meta: self.meta.entry_count,
+                actual: report.entries_checked,
+            });
+        }
+        if let (Some(first), Some(last)) = (first_key, prev_key) {
+            if first != self.meta.min_key {
+                report.issues.push(IntegrityIssue::MinKeyMismatch {
+                    meta: self.meta.min_key.clone(),
+                    actual: first,
+                });
+            }
+            if last != self.meta.max_key {
+                report.issues.push(IntegrityIssue::MaxKeyMismatch {
+                    meta: self.meta.max_key.clone(),
+                    actual: last,
+                });
+            }
+        }
+        if let Some(ref properties) = self.properties
+            && properties.num_entries != report.entries_checked
+        {
+            report
+                .issues
+                .push(IntegrityIssue::PropertiesEntryCountMismatch {
+                    properties: properties.num_entries,
+                    actual: report.entries_checked,
+                });
+        }
+        Ok(report)
+    }
+
+    /// Read, checksum and decode one data block for verify_integrity(),
+    /// without touching the block cache.
+    fn verify_block(
+        &self,
+        block_idx: usize,
+        entry: &IndexEntry,
+    ) -> std::result::Result<Block, IntegrityIssue> {
+        let unreadable = |e: Error| IntegrityIssue::UnreadableBlock {
+            block_idx,
+            offset: entry.offset,
+            reason: e.to_string(),
+        };
+
+        // Format version 1 blocks have no checksum to verify
+        if self.footer.format_version < 2 {
+            let contents = self.read_at(entry.offset, entry.size).map_err(unreadable)?;
+            return self.decode_contents(contents).map_err(unreadable);
+        }
+
+        let size = entry.size as usize;
+        let mut raw = self
+            .read_at(entry.offset, (size + BLOCK_TRAILER_SIZE) as u64)
+            .map_err(unreadable)?;
+        let stored_crc = u32::from_le_bytes(raw[size..].try_into().unwrap());
+        if self.footer.checksum.checksum(&raw[..size]) != stored_crc {
+            return Err(IntegrityIssue::ChecksumMismatch {
+                block_idx,
+                offset: entry.offset,
+            });
+        }
+        raw.truncate(size);
+        let contents = decompress_block_with_dict(raw, self.compression_dict.as_deref())
+            .map_err(unreadable)?;
+        self.decode_contents(contents).map_err(unreadable)
+    }
+
+    /// Read and decode data block `block_idx`.
+    ///
+    /// Checks the block cache first (keyed by (sst_id, block_offset)). On a
+    /// miss the block and its CRC32 trailer are read from disk, the
+    /// checksum is verified if requested, the block is decompressed, and
+    /// the uncompressed contents are cached if fill_cache is set.
+    pub(crate) fn read_block(
+        &self,
+        block_idx: usize,
+        read_options: &BlockReadOptions,
+    ) -> Result<Block> {
+        self.read_block_with_readahead(block_idx, read_options, None)
+    }
+
+    /// Like read_block(), serving the block from `readahead` when it holds
+    /// it and otherwise refilling it with read_options.readahead_size bytes
+    /// from the block on. For sequential scans.
+    pub(crate) fn read_block_with_readahead(
+        &self,
+        block_idx: usize,
+        read_options: &BlockReadOptions,
+        readahead: Option<&mut ReadaheadBuffer>,
+    ) -> Result<Block> {
+        let entry = self.index_entry(block_idx)?;
+
+        if let Some(ref cache) = self.block_cache
+            && let Some(cached) = cache.lock().unwrap().get(self.meta.id, entry.offset)
+        {
+            return self.decode_shared(Bytes::from_owner(CachedBlock(cached)));
+        }
+
+        // Format version 1 blocks are bare contents: no type byte, no CRC
+        if self.footer.format_version < 2 {
+            let contents = self
+                .data_block_bytes(entry.offset, entry.size, read_options, readahead)?
+                .into_owned();
+            return self.cache_and_decode(entry.offset, contents, read_options);
+        }
+
+        let size = entry.size as usize;
+        let raw = self.data_block_bytes(
+            entry.offset,
+            (size + BLOCK_TRAILER_SIZE) as u64,
+            read_options,
+            readahead,
+        )?;
+        let stored_crc = u32::from_le_bytes(raw[size..].try_into().unwrap());
+
+        if read_options.verify_checksums
+            && self.footer.checksum.checksum(&raw[..size]) != stored_crc
+        {
+            return Err(Error::Corruption(format!(
+                "block checksum mismatch in {} at offset {}",
+                self.path.display(),
+                entry.offset
+            )));
+        }
+
+        // The cache holds uncompressed contents, so hits skip decompression
+        let mut buf = raw.into_owned();
+        buf.truncate(size);
+        let contents = decompress_block_with_dict(buf, self.compression_dict.as_deref())?;
+        self.cache_and_decode(entry.offset, contents, read_options)
+    }
+
+    /// The `size` bytes of a data block at `offset`, through `readahead`
+    /// if given. Refills read ahead up to the end of the data blocks.
+    /// Mapped tables ignore readahead: their bytes are already in memory.
+    fn data_block_bytes<'b>(
+        &'b self,
+        offset: u64,
+        size: u64,
+        read_options: &BlockReadOptions,
+        readahead: Option<&'b mut ReadaheadBuffer>,
+    ) -> Result<Cow<'b, [u8]>> {
+        let readahead = match readahead {
+            Some(buf) if read_options.readahead_size > 0 && !self.is_mapped() => buf,
+            _ => {
+                self.data_block_reads.fetch_add(1, Ordering::Relaxed);
+                return self.file.bytes_at(offset, size);
             }
         };
+        if readahead.get(offset, size).is_none() {
+            let data_end = self.footer.meta_block_offset;
+            let len = (read_options.readahead_size as u64)
+                .min(data_end.saturating_sub(offset))
+                .max(size);
+            readahead.data = self.file.bytes_at(offset, len)?.into_owned();
+            readahead.offset = offset;
+            self.data_block_reads.fetch_add(1, Ordering::Relaxed);
+        }
+        let bytes = readahead.get(offset, size).unwrap();
+        Ok(Cow::Borrowed(bytes))
+    }
 
-        let entry = &self.index[block_idx];
+    /// Number of file reads issued for data blocks since open. Block
+    /// cache hits don't read; a readahead refill counts as one read.
+    pub fn data_block_reads(&self) -> u64 {
+        self.data_block_reads.load(Ordering::Relaxed)
+    }
 
-        // Step 3: Read the block from disk
-        let mut block_data = vec![0u8; entry.size as usize];
+    fn is_mapped(&self) -> bool {
+        matches!(self.file, TableFile::Mapped(_))
+    }
+
+    /// Insert uncompressed block contents into the cache (if fill_cache is
+    /// set) and decode them.
+    fn cache_and_decode(
+        &self,
+        offset: u64,
+        contents: Vec<u8>,
+        read_options: &BlockReadOptions,
+    ) -> Result<Block> {
+        if read_options.fill_cache
+            && let Some(ref cache) = self.block_cache
         {
-            let mut file = self.file.borrow_mut();
-            file.seek(SeekFrom::Start(entry.offset))?;
-            file.read_exact(&mut block_data)?;
+            cache
+                .lock()
+                .unwrap()
+                .insert(self.meta.id, offset, contents.clone());
         }
 
-        // Step 4: Decode block and binary search within it
-        let block = Block::decode(block_data)?;
-        Ok(block.get(key).map(|v| v.to_vec()))
+        self.decode_contents(contents)
+    }
+
+    /// Decode uncompressed block contents in this table's block layout.
+    fn decode_contents(&self, contents: Vec<u8>) -> Result<Block> {
+        self.decode_shared(Bytes::from(contents))
+    }
+
+    /// decode_contents() over shared contents, which the block slices
+    /// rather than copies.
+    fn decode_shared(&self, contents: Bytes) -> Result<Block> {
+        Block::from_shared_contents(contents, self.footer.format_version < 4)
     }
 
     /// Create an iterator over all entries in the SSTable.
@@ -241,18 +830,178 @@ impl SSTable {
         SSTableIterator::new_range(self, start, end)
     }
 
+    /// Create an iterator over entries in [lower, upper); `None` leaves that side open.
+    pub fn bounded_iter(
+        &self,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+    ) -> Result<SSTableIterator<'_>> {
+        self.bounded_iter_with_options(lower, upper, &BlockReadOptions::default())
+    }
+
+    /// Like bounded_iter(), with explicit block read options.
+    pub fn bounded_iter_with_options(
+        &self,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        read_options: &BlockReadOptions,
+    ) -> Result<SSTableIterator<'_>> {
+        SSTableIterator::new_bounded(self, lower, upper, *read_options)
+    }
+
+    /// Like bounded_iter_with_options(), but the iterator holds on to
+    /// the table instead of borrowing it (see SSTableIterator::shared).
+    pub fn shared_iter(
+        self: &Arc<Self>,
+        lower: Option<&[u8]>,
+        upper: Option<&[u8]>,
+        read_options: &BlockReadOptions,
+    ) -> Result<SSTableIterator<'static>> {
+        SSTableIterator::shared(Arc::clone(self), lower, upper, *read_options)
+    }
+
     /// Get metadata about this SSTable.
     pub fn meta(&self) -> &SSTableMeta {
         &self.meta
     }
 
-    /// Get the index entries.
-    pub(crate) fn index(&self) -> &[IndexEntry] {
+    /// The zstd dictionary this table's blocks were compressed with, if any.
+    pub fn compression_dict(&self) -> Option<&[u8]> {
+        self.compression_dict.as_deref()
+    }
+
+    /// The table's range tombstones; empty for tables without any.
+    pub fn range_tombstones(&self) -> &RangeTombstones {
+        &self.range_tombstones
+    }
+
+    /// The sequence number of each entry, for tables with a sequence
+    /// number block (from format version 10, when the writer knew every
+    /// entry's); None otherwise. Read from disk on first use.
+    pub fn entry_seqnos(&self) -> Result<Option<&EntrySeqnos>> {
+        if self.footer.seqno_block_size == 0 {
+            return Ok(None);
+        }
+        if let Some(seqnos) = self.entry_seqnos.get() {
+            return Ok(Some(seqnos));
+        }
+        let seqno_buf = self
+            .file
+            .bytes_at(self.footer.seqno_block_offset, self.footer.seqno_block_size)?;
+        let seqnos = EntrySeqnos::decode(&seqno_buf)?;
+        if seqnos.num_blocks() != self.num_blocks() {
+            return Err(Error::Corruption(format!(
+                "sequence number block covers {} data blocks, table has {}",
+                seqnos.num_blocks(),
+                self.num_blocks()
+            )));
+        }
+        Ok(Some(self.entry_seqnos.get_or_init(|| seqnos)))
+    }
+
+    /// Table properties: built-in stats and user-collected properties.
+    /// None for tables written before format version 3.
+    pub fn properties(&self) -> Option<&TableProperties> {
+        self.properties.as_ref()
+    }
+
+    /// Number of data blocks.
+    pub fn num_blocks(&self) -> usize {
+        self.index.len()
+    }
+
+    /// The block index (flat or partitioned).
+    pub fn index(&self) -> &Index {
         &self.index
     }
 
-    /// Get the file handle.
-    pub(crate) fn file(&self) -> &RefCell<File> {
-        &self.file
+    /// Index entry for data block `block_idx`, loading its index
+    /// partition if needed.
+    pub(crate) fn index_entry(&self, block_idx: usize) -> Result<&IndexEntry> {
+        self.index
+            .entry(block_idx, &|offset, size| self.read_at(offset, size))
+    }
+
+    /// The footer: block locations and format version.
+    pub fn footer(&self) -> &Footer {
+        &self.footer
+    }
+
+    /// On-disk format version, from the footer.
+    pub fn format_version(&self) -> u32 {
+        self.footer.format_version
+    }
+
+    /// The bloom filter (whole-table or per block).
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    /// Check the per-block filter of `block_idx`, loading it if needed.
+    fn block_may_contain(&self, block_idx: usize, key: &[u8]) -> Result<bool> {
+        self.filter
+            .block_may_contain(block_idx, key, &|offset, size| self.read_at(offset, size))
+    }
+
+    /// First block whose last_key >= `key` (num_blocks() if none).
+    pub(crate) fn find_block(&self, key: &[u8]) -> Result<usize> {
+        self.index
+            .find_block(key, &|offset, size| self.read_at(offset, size))
+    }
+
+    /// Read `size` bytes at `offset`.
+    fn read_at(&self, offset: u64, size: u64) -> Result<Vec<u8>> {
+        Ok(self.file.bytes_at(offset, size)?.into_owned())
+    }
+
+    /// How this table reads its file.
+    pub fn file_access(&self) -> FileAccess {
+        match self.file {
+            TableFile::Buffered(_) => FileAccess::Buffered,
+            TableFile::Mapped(_) => FileAccess::Mmap,
+            TableFile::Direct(_) => FileAccess::Direct,
+        }
+    }
+}
+
+/// How an open SSTable reaches its bytes.
+enum TableFile {
+    /// Positioned reads (pread) on a file handle. No shared file offset,
+    /// so concurrent readers of one open table don't interfere.
+    Buffered(File),
+    /// Slices of a read-only memory map of the whole file.
+    Mapped(Mmap),
+    /// Positioned O_DIRECT reads.
+    Direct(File),
+}
+
+impl TableFile {
+    fn len(&self) -> Result<u64> {
+        match self {
+            TableFile::Buffered(file) => Ok(file.metadata()?.len()),
+            TableFile::Mapped(map) => Ok(map.len() as u64),
+            TableFile::Direct(file) => Ok(file.metadata()?.len()),
+        }
+    }
+
+    /// `size` bytes at `offset`: borrowed from the map, or read from the file.
+    fn bytes_at(&self, offset: u64, size: u64) -> Result<Cow<'_, [u8]>> {
+        match self {
+            TableFile::Buffered(file) => {
+                let mut buf = vec![0u8; size as usize];
+                file.read_exact_at(&mut buf, offset)?;
+                Ok(Cow::Owned(buf))
+            }
+            TableFile::Direct(file) => Ok(Cow::Owned(direct_io::read_direct(file, offset, size)?)),
+            TableFile::Mapped(map) => offset
+                .checked_add(size)
+                .filter(|&end| end <= map.len() as u64)
+                .map(|end| Cow::Borrowed(&map[offset as usize..end as usize]))
+                .ok_or_else(|| {
+                    Error::Corruption(format!(
+                        "read of {size} bytes at offset {offset} runs past end of file"
+                    ))
+                }),
+        }
     }
 }
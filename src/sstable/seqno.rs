@@ -0,0 +1,79 @@
+use crate::error::{Error, Result};
+use crate::sstable::block::{get_varint, put_varint};
+
+/// The sequence number of every entry in an SSTable, by data block: the
+/// write each entry's version came from.
+///
+/// Stored in the table's sequence number block, from format version 10,
+/// when every entry was added with one (SSTableBuilder::add_with_seqno).
+/// Tables without the block only know their seqno range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntrySeqnos {
+    /// One list per data block, in block order, with one sequence number
+    /// per entry.
+    blocks: Vec<Vec<u64>>,
+}
+
+impl EntrySeqnos {
+    /// Sequence numbers of each data block's entries, in block order.
+    pub fn new(blocks: Vec<Vec<u64>>) -> Self {
+        EntrySeqnos { blocks }
+    }
+
+    /// Sequence number of entry `entry` of data block `block`, or None
+    /// past the end of either.
+    pub fn get(&self, block: usize, entry: usize) -> Option<u64> {
+        self.blocks.get(block)?.get(entry).copied()
+    }
+
+    /// Number of data blocks covered.
+    pub fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Encode as the sequence number block.
+    /// Format: [block_count(varint)] then per block
+    /// [entry_count(varint)][seqno(varint)]..., then [crc32(4B)]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        put_varint(&mut buf, self.blocks.len() as u64);
+        for block in &self.blocks {
+            put_varint(&mut buf, block.len() as u64);
+            for &seqno in block {
+                put_varint(&mut buf, seqno);
+            }
+        }
+        let crc = crc32fast::hash(&buf);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Decode a sequence number block written by encode().
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < 5 {
+            return Err(Error::Corruption("sequence number block too short".into()));
+        }
+        let (body, crc) = data.split_at(data.len() - 4);
+        if crc32fast::hash(body) != u32::from_le_bytes(crc.try_into().unwrap()) {
+            return Err(Error::Corruption(
+                "sequence number block checksum mismatch".into(),
+            ));
+        }
+
+        let mut offset = 0;
+        let mut next = || -> Result<u64> {
+            let (value, len) = get_varint(&body[offset..])
+                .ok_or_else(|| Error::Corruption("sequence number block truncated".into()))?;
+            offset += len;
+            Ok(value)
+        };
+        let num_blocks = next()?;
+        let mut blocks = Vec::new();
+        for _ in 0..num_blocks {
+            let num_entries = next()?;
+            let block = (0..num_entries).map(|_| next()).collect::<Result<_>>()?;
+            blocks.push(block);
+        }
+        Ok(EntrySeqnos { blocks })
+    }
+}
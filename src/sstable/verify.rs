@@ -0,0 +1,43 @@
+/// Result of SSTable::verify_integrity(): what was checked and every
+/// problem found. An empty `issues` means the table is sound.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Data blocks read.
+    pub blocks_checked: usize,
+    /// Entries decoded across all readable blocks.
+    pub entries_checked: u64,
+    /// Problems, in file order.
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// No problems found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A single problem found by SSTable::verify_integrity().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    /// A data block's bytes don't match its CRC32 trailer.
+    ChecksumMismatch { block_idx: usize, offset: u64 },
+    /// A data block failed to decompress or decode.
+    UnreadableBlock {
+        block_idx: usize,
+        offset: u64,
+        reason: String,
+    },
+    /// A key is not strictly greater than the one before it.
+    KeyOutOfOrder { block_idx: usize, key: Vec<u8> },
+    /// A key falls outside the range the index assigns to its block.
+    KeyOutsideIndexRange { block_idx: usize, key: Vec<u8> },
+    /// The meta block's entry count disagrees with the entries read.
+    EntryCountMismatch { meta: u64, actual: u64 },
+    /// The meta block's min key isn't the first key read.
+    MinKeyMismatch { meta: Vec<u8>, actual: Vec<u8> },
+    /// The meta block's max key isn't the last key read.
+    MaxKeyMismatch { meta: Vec<u8>, actual: Vec<u8> },
+    /// The properties block's entry count disagrees with the entries read.
+    PropertiesEntryCountMismatch { properties: u64, actual: u64 },
+}
@@ -0,0 +1,2 @@
+pub mod sst_dump;
+pub mod sst_import;
@@ -0,0 +1,327 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::iterator::StorageIterator;
+use crate::sstable::reader::SSTable;
+
+/// Output format of dump().
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// One `[section]` header per part of the file, then indented fields.
+    #[default]
+    Text,
+    /// A single JSON object with one member per section.
+    Json,
+}
+
+/// What dump() prints and how.
+#[derive(Debug, Clone, Default)]
+pub struct DumpOptions {
+    pub format: DumpFormat,
+    /// Also print every key-value pair, in key order.
+    pub entries: bool,
+}
+
+/// Open the SSTable at `path` and dump() it.
+pub fn dump_file(path: &Path, options: &DumpOptions, out: &mut dyn Write) -> Result<()> {
+    let sst = SSTable::open(path)?;
+    dump(&sst, options, out)
+}
+
+/// Print an SSTable's footer, meta block, properties, bloom filter stats
+/// and index, and optionally its entries.
+///
+/// Keys and values are printed as strings with anything outside printable
+/// ASCII (and backslashes and quotes) escaped as `\xNN`.
+pub fn dump(sst: &SSTable, options: &DumpOptions, out: &mut dyn Write) -> Result<()> {
+    let mut sections = vec![
+        footer_section(sst),
+        meta_section(sst),
+        properties_section(sst),
+        user_properties_section(sst),
+        filter_section(sst),
+        index_section(sst)?,
+    ];
+    if options.entries {
+        sections.push(entries_section(sst)?);
+    }
+
+    match options.format {
+        DumpFormat::Text => write_text(&sections, out)?,
+        DumpFormat::Json => write_json(&sections, out)?,
+    }
+    Ok(())
+}
+
+/// A value in the dump: printed bare in text, typed in JSON.
+enum Field {
+    Num(u64),
+    Float(f64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+}
+
+type Fields = Vec<(&'static str, Field)>;
+
+enum Body {
+    Fields(Fields),
+    /// One line (text) or object (JSON) per index entry or key-value pair.
+    Records(Vec<Fields>),
+    /// The section doesn't exist in this file.
+    Missing(&'static str),
+}
+
+struct Section {
+    name: &'static str,
+    body: Body,
+}
+
+fn footer_section(sst: &SSTable) -> Section {
+    let footer = sst.footer();
+    Section {
+        name: "footer",
+        body: Body::Fields(vec![
+            ("format_version", Field::Num(footer.format_version as u64)),
+            ("index_block_offset", Field::Num(footer.index_block_offset)),
+            ("index_block_size", Field::Num(footer.index_block_size)),
+            ("meta_block_offset", Field::Num(footer.meta_block_offset)),
+            ("meta_block_size", Field::Num(footer.meta_block_size)),
+            ("bloom_block_offset", Field::Num(footer.bloom_block_offset)),
+            ("bloom_block_size", Field::Num(footer.bloom_block_size)),
+            (
+                "properties_block_offset",
+                Field::Num(footer.properties_block_offset),
+            ),
+            (
+                "properties_block_size",
+                Field::Num(footer.properties_block_size),
+            ),
+            (
+                "range_del_block_offset",
+                Field::Num(footer.range_del_block_offset),
+            ),
+            (
+                "range_del_block_size",
+                Field::Num(footer.range_del_block_size),
+            ),
+            ("checksum", Field::Num(footer.checksum.to_byte() as u64)),
+            ("seqno_block_offset", Field::Num(footer.seqno_block_offset)),
+            ("seqno_block_size", Field::Num(footer.seqno_block_size)),
+            ("magic", Field::Num(footer.magic)),
+        ]),
+    }
+}
+
+fn meta_section(sst: &SSTable) -> Section {
+    let meta = sst.meta();
+    Section {
+        name: "meta",
+        body: Body::Fields(vec![
+            ("id", Field::Num(meta.id)),
+            ("file_size", Field::Num(meta.file_size)),
+            ("entry_count", Field::Num(meta.entry_count)),
+            ("smallest_seqno", Field::Num(meta.smallest_seqno)),
+            ("largest_seqno", Field::Num(meta.largest_seqno)),
+            ("creation_time", Field::Num(meta.creation_time)),
+            ("min_key", Field::Bytes(meta.min_key.clone())),
+            ("max_key", Field::Bytes(meta.max_key.clone())),
+            (
+                "compression_dict_size",
+                Field::Num(sst.compression_dict().map_or(0, |d| d.len()) as u64),
+            ),
+        ]),
+    }
+}
+
+fn properties_section(sst: &SSTable) -> Section {
+    let Some(props) = sst.properties() else {
+        return Section {
+            name: "properties",
+            body: Body::Missing("no properties block before format version 3"),
+        };
+    };
+    let fields = vec![
+        ("num_entries", Field::Num(props.num_entries)),
+        ("num_deletions", Field::Num(props.num_deletions)),
+        ("raw_key_size", Field::Num(props.raw_key_size)),
+        ("raw_value_size", Field::Num(props.raw_value_size)),
+        ("num_data_blocks", Field::Num(props.num_data_blocks)),
+        ("data_size", Field::Num(props.data_size)),
+        ("index_size", Field::Num(props.index_size)),
+        ("filter_size", Field::Num(props.filter_size)),
+    ];
+    Section {
+        name: "properties",
+        body: Body::Fields(fields),
+    }
+}
+
+/// Properties from TablePropertiesCollectors, one record per property.
+fn user_properties_section(sst: &SSTable) -> Section {
+    let Some(props) = sst.properties() else {
+        return Section {
+            name: "user_properties",
+            body: Body::Missing("no properties block before format version 3"),
+        };
+    };
+    let records = props
+        .user_collected_properties
+        .iter()
+        .map(|(name, value)| {
+            vec![
+                ("name", Field::Bytes(name.as_bytes().to_vec())),
+                ("value", Field::Bytes(value.clone())),
+            ]
+        })
+        .collect();
+    Section {
+        name: "user_properties",
+        body: Body::Records(records),
+    }
+}
+
+fn filter_section(sst: &SSTable) -> Section {
+    let filter = sst.filter();
+    let mut fields = vec![
+        ("partitioned", Field::Bool(filter.is_partitioned())),
+        ("block_size", Field::Num(sst.footer().bloom_block_size)),
+    ];
+    match filter.full_filter() {
+        Some(bloom) => {
+            let entries = sst.meta().entry_count.max(1);
+            fields.push(("num_bits", Field::Num(bloom.num_bits() as u64)));
+            fields.push(("num_hashes", Field::Num(bloom.num_hashes() as u64)));
+            fields.push((
+                "bits_per_key",
+                Field::Float(bloom.num_bits() as f64 / entries as f64),
+            ));
+        }
+        None => fields.push(("num_filters", Field::Num(filter.num_partitions() as u64))),
+    }
+    Section {
+        name: "filter",
+        body: Body::Fields(fields),
+    }
+}
+
+fn index_section(sst: &SSTable) -> Result<Section> {
+    let mut records = Vec::with_capacity(sst.num_blocks());
+    for block_idx in 0..sst.num_blocks() {
+        let entry = sst.index_entry(block_idx)?;
+        records.push(vec![
+            ("block", Field::Num(block_idx as u64)),
+            ("offset", Field::Num(entry.offset)),
+            ("size", Field::Num(entry.size)),
+            ("key", Field::Bytes(entry.last_key.clone())),
+        ]);
+    }
+    Ok(Section {
+        name: "index",
+        body: Body::Records(records),
+    })
+}
+
+fn entries_section(sst: &SSTable) -> Result<Section> {
+    let mut records = Vec::new();
+    let mut iter = sst.iter()?;
+    while iter.is_valid() {
+        records.push(vec![
+            ("key", Field::Bytes(iter.key().to_vec())),
+            ("value", Field::Bytes(iter.value().to_vec())),
+        ]);
+        iter.next()?;
+    }
+    Ok(Section {
+        name: "entries",
+        body: Body::Records(records),
+    })
+}
+
+/// Printable ASCII as is, everything else (and `\` and `"`) as `\xNN`.
+fn escape_bytes(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len());
+    for &b in bytes {
+        if b.is_ascii_graphic() && b != b'\\' && b != b'"' || b == b' ' {
+            s.push(b as char);
+        } else {
+            s.push_str(&format!("\\x{b:02x}"));
+        }
+    }
+    s
+}
+
+fn text_value(field: &Field) -> String {
+    match field {
+        Field::Num(n) => n.to_string(),
+        Field::Float(f) => format!("{f:.2}"),
+        Field::Bool(b) => b.to_string(),
+        Field::Bytes(bytes) => format!("\"{}\"", escape_bytes(bytes)),
+    }
+}
+
+fn json_value(field: &Field) -> String {
+    match field {
+        Field::Num(n) => n.to_string(),
+        Field::Float(f) => format!("{f:.2}"),
+        Field::Bool(b) => b.to_string(),
+        Field::Bytes(bytes) => {
+            // escape_bytes() leaves no quotes, only its own backslashes
+            format!("\"{}\"", escape_bytes(bytes).replace('\\', "\\\\"))
+        }
+    }
+}
+
+fn write_text(sections: &[Section], out: &mut dyn Write) -> std::io::Result<()> {
+    for section in sections {
+        match &section.body {
+            Body::Fields(fields) => {
+                writeln!(out, "[{}]", section.name)?;
+                for (name, field) in fields {
+                    writeln!(out, "  {name}: {}", text_value(field))?;
+                }
+            }
+            Body::Records(records) => {
+                writeln!(out, "[{}] {} records", section.name, records.len())?;
+                for record in records {
+                    let line: Vec<String> = record
+                        .iter()
+                        .map(|(name, field)| format!("{name}={}", text_value(field)))
+                        .collect();
+                    writeln!(out, "  {}", line.join(" "))?;
+                }
+            }
+            Body::Missing(reason) => {
+                writeln!(out, "[{}]", section.name)?;
+                writeln!(out, "  ({reason})")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn json_object(fields: &Fields) -> String {
+    let members: Vec<String> = fields
+        .iter()
+        .map(|(name, field)| format!("\"{name}\":{}", json_value(field)))
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
+
+fn write_json(sections: &[Section], out: &mut dyn Write) -> std::io::Result<()> {
+    let members: Vec<String> = sections
+        .iter()
+        .map(|section| {
+            let value = match &section.body {
+                Body::Fields(fields) => json_object(fields),
+                Body::Records(records) => {
+                    let items: Vec<String> = records.iter().map(json_object).collect();
+                    format!("[{}]", items.join(","))
+                }
+                Body::Missing(_) => "null".to_string(),
+            };
+            format!("\"{}\":{value}", section.name)
+        })
+        .collect();
+    writeln!(out, "{{{}}}", members.join(","))
+}
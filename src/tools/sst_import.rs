@@ -0,0 +1,476 @@
+// Converter from LevelDB and RocksDB table files to this engine's SSTable
+// format, so existing datasets can be migrated without replaying every
+// key through the write path.
+//
+// Supported sources:
+// - LevelDB tables: blocks uncompressed, snappy or zstd
+// - RocksDB block-based tables with the legacy footer or format_version
+//   1-5: blocks uncompressed, snappy, lz4 or zstd (lz4 and zstd from
+//   format_version 2), binary-search or hash index (not partitioned)
+//
+// Both store internal keys, [user_key][seq << 8 | type (8B LE)], sorted
+// by user key, then newest first. Only the newest version of each user
+// key is kept; deletions become tombstones. The source must use the
+// bytewise comparator, and merge operands and range deletions are
+// rejected since this engine has neither. As with put(key, b""), empty
+// values come out as tombstones.
+//
+// Block checksums are verified when they're CRC32C (LevelDB, and RocksDB's
+// default); other RocksDB checksum types are not checked.
+
+use std::path::Path;
+
+use crate::blob::is_blob_index;
+use crate::checksum::ChecksumType;
+use crate::error::{Error, Result};
+use crate::sstable::block::get_varint;
+use crate::sstable::builder::{SSTableBuilder, TableOptions};
+use crate::sstable::footer::SSTableMeta;
+
+/// Footer magic of LevelDB tables and of RocksDB's legacy footer.
+const LEGACY_MAGIC: u64 = 0xdb4775248b80fb57;
+
+/// Footer magic of RocksDB block-based tables with format_version >= 1.
+const ROCKSDB_MAGIC: u64 = 0x88e241b785f4cff7;
+
+/// [metaindex handle][index handle][padding to 40B][magic(8B)]
+const LEGACY_FOOTER_SIZE: usize = 48;
+
+/// [checksum type(1B)][metaindex handle][index handle][padding to 41B]
+/// [format_version(4B)][magic(8B)]
+const ROCKSDB_FOOTER_SIZE: usize = 53;
+
+/// Newest RocksDB format_version understood; 6 changed the footer.
+const MAX_ROCKSDB_FORMAT_VERSION: u32 = 5;
+
+/// Every block is followed by [compression type(1B)][checksum(4B)].
+const BLOCK_TRAILER_SIZE: u64 = 5;
+
+/// Flag in a RocksDB data block's restart count marking a hash index.
+const DATA_BLOCK_HASH_INDEX_FLAG: u32 = 1 << 31;
+
+const BYTEWISE_COMPARATOR: &[u8] = b"leveldb.BytewiseComparator";
+
+// Value types in an internal key's trailer.
+const TYPE_DELETION: u8 = 0x0;
+const TYPE_VALUE: u8 = 0x1;
+const TYPE_MERGE: u8 = 0x2;
+const TYPE_SINGLE_DELETION: u8 = 0x7;
+
+// Block compression types.
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_SNAPPY: u8 = 1;
+const LEVELDB_COMPRESSION_ZSTD: u8 = 2;
+const ROCKSDB_COMPRESSION_LZ4: u8 = 4;
+const ROCKSDB_COMPRESSION_LZ4HC: u8 = 5;
+const ROCKSDB_COMPRESSION_ZSTD: u8 = 7;
+
+/// RocksDB checksum type for CRC32C, which LevelDB always uses.
+const CHECKSUM_CRC32C: u8 = 1;
+
+// RocksDB table properties consulted.
+const PROPERTIES_BLOCK: &[u8] = b"rocksdb.properties";
+const PROP_COMPARATOR: &[u8] = b"rocksdb.comparator";
+const PROP_INDEX_TYPE: &[u8] = b"rocksdb.block.based.table.index.type";
+const PROP_INDEX_DELTA_ENCODED: &[u8] = b"rocksdb.index.value.is.delta.encoded";
+const PROP_NUM_RANGE_DELETIONS: &[u8] = b"rocksdb.num.range-deletions";
+
+/// Engine and format version of a converted table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    LevelDb,
+    /// format_version 0 is RocksDB's legacy (LevelDB-style) footer.
+    RocksDb {
+        format_version: u32,
+    },
+}
+
+/// What convert() wrote.
+#[derive(Debug, Clone)]
+pub struct ImportSummary {
+    pub format: SourceFormat,
+    /// The SSTable written, to hand to the manifest on ingestion.
+    pub meta: SSTableMeta,
+    /// Entries in the source table, every version counted.
+    pub entries_read: u64,
+    /// Older versions dropped in favor of a newer one of the same key.
+    pub shadowed: u64,
+}
+
+/// Convert the LevelDB or RocksDB table at `src` into an SSTable with id
+/// `sst_id` at `dest`.
+pub fn convert_file(
+    src: &Path,
+    dest: &Path,
+    sst_id: u64,
+    options: &TableOptions,
+) -> Result<ImportSummary> {
+    convert(&std::fs::read(src)?, dest, sst_id, options)
+}
+
+/// Convert a LevelDB or RocksDB table held in `data` into an SSTable with
+/// id `sst_id` at `dest`, built with `options` as for level 0. On error,
+/// nothing is left at `dest`.
+pub fn convert(
+    data: &[u8],
+    dest: &Path,
+    sst_id: u64,
+    options: &TableOptions,
+) -> Result<ImportSummary> {
+    let table = ForeignTable::open(data)?;
+    let result = table.write_sstable(dest, sst_id, options);
+    if result.is_err() {
+        let _ = std::fs::remove_file(dest);
+    }
+    result
+}
+
+fn unsupported(what: impl std::fmt::Display) -> Error {
+    Error::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("unsupported source table: {what}"),
+    ))
+}
+
+/// Decode a varint at `*pos`, advancing past it.
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64> {
+    let (value, len) = get_varint(data.get(*pos..).unwrap_or_default())
+        .ok_or_else(|| Error::Corruption("bad varint in source table".into()))?;
+    *pos += len;
+    Ok(value)
+}
+
+/// Location of a block: [offset varint][size varint], size excluding the
+/// trailer.
+#[derive(Debug, Clone, Copy)]
+struct BlockHandle {
+    offset: u64,
+    size: u64,
+}
+
+impl BlockHandle {
+    fn decode(data: &[u8], pos: &mut usize) -> Result<Self> {
+        Ok(BlockHandle {
+            offset: read_varint(data, pos)?,
+            size: read_varint(data, pos)?,
+        })
+    }
+}
+
+struct ForeignTable<'a> {
+    data: &'a [u8],
+    format: SourceFormat,
+    checksum_type: u8,
+    index: BlockHandle,
+    /// Index entries after a restart point store only the size change
+    /// from the previous handle (RocksDB format_version >= 4).
+    index_delta_encoded: bool,
+}
+
+impl<'a> ForeignTable<'a> {
+    /// Parse the footer and, for RocksDB, check the table properties.
+    fn open(data: &'a [u8]) -> Result<Self> {
+        if data.len() < 8 {
+            return Err(Error::Corruption("source table too short".into()));
+        }
+        let magic = u64::from_le_bytes(data[data.len() - 8..].try_into().unwrap());
+        let (footer, checksum_type, format) = match magic {
+            LEGACY_MAGIC if data.len() >= LEGACY_FOOTER_SIZE => (
+                &data[data.len() - LEGACY_FOOTER_SIZE..],
+                CHECKSUM_CRC32C,
+                SourceFormat::LevelDb,
+            ),
+            ROCKSDB_MAGIC if data.len() >= ROCKSDB_FOOTER_SIZE => {
+                let footer = &data[data.len() - ROCKSDB_FOOTER_SIZE..];
+                let format_version = u32::from_le_bytes(footer[41..45].try_into().unwrap());
+                if format_version == 0 || format_version > MAX_ROCKSDB_FORMAT_VERSION {
+                    return Err(unsupported(format!(
+                        "RocksDB format_version {format_version}"
+                    )));
+                }
+                (
+                    &footer[1..],
+                    footer[0],
+                    SourceFormat::RocksDb { format_version },
+                )
+            }
+            _ => {
+                return Err(Error::Corruption(format!(
+                    "not a LevelDB or RocksDB table (magic {magic:#x})"
+                )));
+            }
+        };
+        let mut pos = 0;
+        let metaindex = BlockHandle::decode(footer, &mut pos)?;
+        let index = BlockHandle::decode(footer, &mut pos)?;
+
+        let mut table = ForeignTable {
+            data,
+            format,
+            checksum_type,
+            index,
+            index_delta_encoded: false,
+        };
+        let properties = table
+            .read_entries(metaindex)?
+            .into_iter()
+            .find(|(name, _)| name == PROPERTIES_BLOCK);
+        if let Some((_, handle)) = properties {
+            if table.format == SourceFormat::LevelDb {
+                table.format = SourceFormat::RocksDb { format_version: 0 };
+            }
+            let handle = BlockHandle::decode(&handle, &mut 0)?;
+            table.check_properties(handle)?;
+        }
+        Ok(table)
+    }
+
+    /// Reject tables this engine can't represent, and note how the index
+    /// is encoded.
+    fn check_properties(&mut self, handle: BlockHandle) -> Result<()> {
+        for (name, value) in self.read_entries(handle)? {
+            match name.as_slice() {
+                PROP_COMPARATOR if value != BYTEWISE_COMPARATOR => {
+                    return Err(unsupported(format!(
+                        "comparator {}",
+                        String::from_utf8_lossy(&value)
+                    )));
+                }
+                PROP_INDEX_TYPE => {
+                    // 0 = binary search, 1 = hash search: same index block
+                    let index_type = value
+                        .get(..4)
+                        .map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+                    if !matches!(index_type, Some(0 | 1)) {
+                        return Err(unsupported(format!("index type {index_type:?}")));
+                    }
+                }
+                PROP_INDEX_DELTA_ENCODED => {
+                    self.index_delta_encoded = read_varint(&value, &mut 0)? != 0;
+                }
+                PROP_NUM_RANGE_DELETIONS if read_varint(&value, &mut 0)? != 0 => {
+                    return Err(unsupported("range deletions"));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Read, verify and decompress the block at `handle`.
+    fn read_block(&self, handle: BlockHandle) -> Result<Vec<u8>> {
+        let end = handle
+            .offset
+            .checked_add(handle.size)
+            .filter(|end| end + BLOCK_TRAILER_SIZE <= self.data.len() as u64)
+            .ok_or_else(|| Error::Corruption("block handle out of range".into()))?
+            as usize;
+        let contents = &self.data[handle.offset as usize..end];
+        let compression = self.data[end];
+
+        if self.checksum_type == CHECKSUM_CRC32C {
+            let stored = u32::from_le_bytes(self.data[end + 1..end + 5].try_into().unwrap());
+            let actual = ChecksumType::Crc32c.checksum(&self.data[handle.offset as usize..=end]);
+            if unmask_crc(stored) != actual {
+                return Err(Error::Corruption(format!(
+                    "block checksum mismatch at offset {}",
+                    handle.offset
+                )));
+            }
+        }
+
+        let corrupt = |codec: &str, e: &dyn std::fmt::Display| {
+            Error::Corruption(format!("{codec} decompression failed: {e}"))
+        };
+        let format_version = match self.format {
+            SourceFormat::LevelDb => None,
+            SourceFormat::RocksDb { format_version } => Some(format_version),
+        };
+        match (compression, format_version) {
+            (COMPRESSION_NONE, _) => Ok(contents.to_vec()),
+            (COMPRESSION_SNAPPY, _) => snap::raw::Decoder::new()
+                .decompress_vec(contents)
+                .map_err(|e| corrupt("snappy", &e)),
+            (LEVELDB_COMPRESSION_ZSTD, None) => {
+                zstd::stream::decode_all(contents).map_err(|e| corrupt("zstd", &e))
+            }
+            // Since format_version 2: [uncompressed size varint][payload]
+            (ROCKSDB_COMPRESSION_LZ4 | ROCKSDB_COMPRESSION_LZ4HC, Some(2..)) => {
+                let mut pos = 0;
+                let size = read_varint(contents, &mut pos)? as usize;
+                lz4_flex::block::decompress(&contents[pos..], size).map_err(|e| corrupt("lz4", &e))
+            }
+            (ROCKSDB_COMPRESSION_ZSTD, Some(2..)) => {
+                let mut pos = 0;
+                let size = read_varint(contents, &mut pos)? as usize;
+                zstd::bulk::decompress(&contents[pos..], size).map_err(|e| corrupt("zstd", &e))
+            }
+            (other, _) => Err(unsupported(format!("compression type {other}"))),
+        }
+    }
+
+    /// Every entry of the block at `handle`.
+    fn read_entries(&self, handle: BlockHandle) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        decode_block(&self.read_block(handle)?)
+    }
+
+    /// Handles of every data block, in key order.
+    fn data_blocks(&self) -> Result<Vec<BlockHandle>> {
+        if self.index_delta_encoded {
+            return decode_delta_index(&self.read_block(self.index)?);
+        }
+        self.read_entries(self.index)?
+            .iter()
+            .map(|(_, value)| BlockHandle::decode(value, &mut 0))
+            .collect()
+    }
+
+    fn write_sstable(
+        &self,
+        dest: &Path,
+        sst_id: u64,
+        options: &TableOptions,
+    ) -> Result<ImportSummary> {
+        let blocks = self.data_blocks()?;
+
+        // First pass counts entries to size the bloom filter
+        let mut entries_read = 0;
+        for &handle in &blocks {
+            entries_read += self.read_entries(handle)?.len() as u64;
+        }
+
+        let mut builder = SSTableBuilder::with_options(
+            dest,
+            sst_id,
+            entries_read as usize,
+            &options.for_level(0),
+        )?;
+        let mut last_key: Option<Vec<u8>> = None;
+        let mut shadowed = 0;
+        for &handle in &blocks {
+            for (internal_key, value) in self.read_entries(handle)? {
+                let Some(split) = internal_key.len().checked_sub(8) else {
+                    return Err(Error::Corruption(
+                        "internal key shorter than 8 bytes".into(),
+                    ));
+                };
+                let (user_key, trailer) = internal_key.split_at(split);
+                match last_key.as_deref() {
+                    // Older version of the key just written
+                    Some(last) if last == user_key => {
+                        shadowed += 1;
+                        continue;
+                    }
+                    Some(last) if last > user_key => {
+                        return Err(Error::Corruption("source keys out of order".into()));
+                    }
+                    _ => {}
+                }
+
+                let value = match trailer[0] {
+                    TYPE_VALUE if is_blob_index(&value) => {
+                        return Err(unsupported("value that looks like a blob pointer"));
+                    }
+                    TYPE_VALUE => value,
+                    TYPE_DELETION | TYPE_SINGLE_DELETION => Vec::new(),
+                    TYPE_MERGE => return Err(unsupported("merge operands")),
+                    other => return Err(unsupported(format!("value type {other:#x}"))),
+                };
+                builder.add(user_key, &value)?;
+                last_key = Some(user_key.to_vec());
+            }
+        }
+
+        Ok(ImportSummary {
+            format: self.format,
+            meta: builder.finish()?,
+            entries_read,
+            shadowed,
+        })
+    }
+}
+
+/// Find the entries and restart offsets of a block:
+/// [entries][restart offsets(4B each)][num_restarts(4B)], with a RocksDB
+/// data block hash index ([buckets][num_buckets(2B)]) before the count
+/// when its flag is set.
+fn block_layout(block: &[u8]) -> Result<(usize, Vec<usize>)> {
+    let corrupt = || Error::Corruption("bad block layout in source table".into());
+    let mut end = block.len().checked_sub(4).ok_or_else(corrupt)?;
+    let packed = u32::from_le_bytes(block[end..].try_into().unwrap());
+    let num_restarts = (packed & !DATA_BLOCK_HASH_INDEX_FLAG) as usize;
+    if packed & DATA_BLOCK_HASH_INDEX_FLAG != 0 {
+        end = end.checked_sub(2).ok_or_else(corrupt)?;
+        let num_buckets = u16::from_le_bytes(block[end..end + 2].try_into().unwrap());
+        end = end.checked_sub(num_buckets as usize).ok_or_else(corrupt)?;
+    }
+    let restarts_start = end.checked_sub(num_restarts * 4).ok_or_else(corrupt)?;
+    let restarts = block[restarts_start..end]
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+        .collect();
+    Ok((restarts_start, restarts))
+}
+
+/// Decode a block's prefix-compressed entries:
+/// [shared varint][non_shared varint][value_len varint][key delta][value]
+fn decode_block(block: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let (end, _) = block_layout(block)?;
+    let entries = &block[..end];
+    let mut out = Vec::new();
+    let mut key: Vec<u8> = Vec::new();
+    let mut pos = 0;
+    while pos < end {
+        let shared = read_varint(entries, &mut pos)? as usize;
+        let non_shared = read_varint(entries, &mut pos)? as usize;
+        let value_len = read_varint(entries, &mut pos)? as usize;
+        if shared > key.len() || non_shared + value_len > end - pos {
+            return Err(Error::Corruption("bad block entry in source table".into()));
+        }
+        key.truncate(shared);
+        key.extend_from_slice(&entries[pos..pos + non_shared]);
+        pos += non_shared;
+        out.push((key.clone(), entries[pos..pos + value_len].to_vec()));
+        pos += value_len;
+    }
+    Ok(out)
+}
+
+/// Decode the block handles of a RocksDB index block with delta-encoded
+/// values: entries have no value length, and the value is a full handle
+/// at restart points and otherwise the signed size change from the
+/// previous handle, whose block this one directly follows.
+fn decode_delta_index(block: &[u8]) -> Result<Vec<BlockHandle>> {
+    let (end, restarts) = block_layout(block)?;
+    let entries = &block[..end];
+    let mut handles: Vec<BlockHandle> = Vec::new();
+    let mut pos = 0;
+    while pos < end {
+        let at_restart = restarts.binary_search(&pos).is_ok();
+        let _shared = read_varint(entries, &mut pos)?;
+        let non_shared = read_varint(entries, &mut pos)? as usize;
+        if non_shared > end - pos {
+            return Err(Error::Corruption("bad index entry in source table".into()));
+        }
+        pos += non_shared;
+        let handle = match handles.last() {
+            Some(prev) if !at_restart => {
+                let zigzag = read_varint(entries, &mut pos)?;
+                let delta = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+                BlockHandle {
+                    offset: prev.offset + prev.size + BLOCK_TRAILER_SIZE,
+                    size: prev.size.wrapping_add_signed(delta),
+                }
+            }
+            _ => BlockHandle::decode(entries, &mut pos)?,
+        };
+        handles.push(handle);
+    }
+    Ok(handles)
+}
+
+/// Undo the rotate-and-add LevelDB applies to stored CRCs.
+fn unmask_crc(masked: u32) -> u32 {
+    let rot = masked.wrapping_sub(0xa282_ead8);
+    rot.rotate_left(15)
+}
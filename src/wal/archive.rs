@@ -0,0 +1,100 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::error::Result;
+
+/// Subdirectory of the DB flushed WALs are archived to.
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// Flushed WALs kept after their memtable is in SSTables, for
+/// point-in-time recovery or shipping the log elsewhere, until they're
+/// older than a TTL or the archive outgrows a size limit.
+///
+/// WALs are moved in with their names, so their order and numbers are
+/// kept. expired() says which to prune; the caller deletes them.
+#[derive(Debug, Clone)]
+pub struct WALArchive {
+    dir: PathBuf,
+    /// Archived WALs last modified longer ago are pruned; zero keeps them.
+    ttl: Duration,
+    /// Oldest WALs are pruned while the archive is bigger; 0 for no limit.
+    size_limit: u64,
+}
+
+impl WALArchive {
+    /// An archive in `db_dir`'s archive directory.
+    pub fn new(db_dir: &Path, ttl: Duration, size_limit: u64) -> Self {
+        WALArchive {
+            dir: db_dir.join(ARCHIVE_DIR),
+            ttl,
+            size_limit,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Move the flushed WAL at `wal` into the archive. Returns its new
+    /// path. A WAL on another filesystem (see Options::wal_dirs) is
+    /// copied over, then removed.
+    pub fn archive(&self, wal: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let archived = self.dir.join(wal.file_name().unwrap_or_default());
+        match std::fs::rename(wal, &archived) {
+            Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+                std::fs::copy(wal, &archived)?;
+                std::fs::File::open(&archived)?.sync_all()?;
+                std::fs::remove_file(wal)?;
+            }
+            result => result?,
+        }
+        Ok(archived)
+    }
+
+    /// The archived WALs by number, oldest first.
+    pub fn wal_files(&self) -> Result<Vec<(u64, PathBuf)>> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            if let Some(number) = name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".wal"))
+                .and_then(|stem| stem.parse::<u64>().ok())
+            {
+                files.push((number, entry.path()));
+            }
+        }
+        files.sort_unstable();
+        Ok(files)
+    }
+
+    /// Archived WALs to prune at `now`, oldest first: those older than
+    /// the TTL, then the oldest of the rest until what's left fits the
+    /// size limit.
+    pub fn expired(&self, now: SystemTime) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for (_, path) in self.wal_files()? {
+            let meta = std::fs::metadata(&path)?;
+            files.push((path, meta.len(), meta.modified()?));
+        }
+
+        let mut expired = Vec::new();
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+        for (path, size, modified) in files {
+            let too_old =
+                !self.ttl.is_zero() && now.duration_since(modified).unwrap_or_default() > self.ttl;
+            let too_big = self.size_limit > 0 && total > self.size_limit;
+            if !too_old && !too_big {
+                continue;
+            }
+            total -= size;
+            expired.push(path);
+        }
+        Ok(expired)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::checksum::ChecksumType;
+use crate::wal::record::record_checksum;
+
+/// Size of a block in a block-format WAL (see WALOptions::block_format).
+///
+/// The file is cut into blocks at multiples of BLOCK_SIZE, the first one
+/// starting with the file header. Records are written as fragments that
+/// never cross a block boundary:
+/// ```text
+/// ┌──────────────┬─────────┬─────────┬───────────────┐
+/// │ Checksum (4B)│ Len (2B)│ Type(1B)│ Payload (Len) │
+/// └──────────────┴─────────┴─────────┴───────────────┘
+/// ```
+/// A record that fits in what's left of the block is one Full fragment;
+/// a longer one is split into First, Middle.. and Last. Fewer than
+/// FRAGMENT_HEADER_SIZE bytes left at the end of a block are zeroed.
+///
+/// A torn write can only damage the blocks it touched: the reader skips
+/// a block whose fragment fails its checksum and picks up at the next
+/// record starting in a later block.
+pub const BLOCK_SIZE: usize = 32 * 1024;
+pub(crate) const FRAGMENT_HEADER_SIZE: usize = 7;
+
+/// Which part of a record a fragment holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FragmentType {
+    Full = 1,
+    First = 2,
+    Middle = 3,
+    Last = 4,
+}
+
+impl FragmentType {
+    fn from_u8(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(FragmentType::Full),
+            2 => Some(FragmentType::First),
+            3 => Some(FragmentType::Middle),
+            4 => Some(FragmentType::Last),
+            _ => None,
+        }
+    }
+}
+
+/// The block the next fragment written at file offset `offset` lands in:
+/// the one after, if too little of this one is left for a fragment.
+pub(crate) fn next_fragment_block(offset: u64) -> u64 {
+    let block = offset / BLOCK_SIZE as u64;
+    let left = BLOCK_SIZE - (offset % BLOCK_SIZE as u64) as usize;
+    if left < FRAGMENT_HEADER_SIZE {
+        block + 1
+    } else {
+        block
+    }
+}
+
+/// Frame encoded record `payload` as fragments written at file offset
+/// `offset`, padding block trailers as needed. Fragment checksums are
+/// seeded with `log_number` in a recyclable WAL, like record checksums.
+pub(crate) fn frame_record(
+    payload: &[u8],
+    offset: u64,
+    checksum: ChecksumType,
+    log_number: Option<u64>,
+) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(payload.len() + 2 * FRAGMENT_HEADER_SIZE);
+    let mut block_offset = (offset % BLOCK_SIZE as u64) as usize;
+    let mut rest = payload;
+    let mut first = true;
+    loop {
+        let left = BLOCK_SIZE - block_offset;
+        if left < FRAGMENT_HEADER_SIZE {
+            buf.resize(buf.len() + left, 0);
+            block_offset = 0;
+            continue;
+        }
+        let len = rest.len().min(left - FRAGMENT_HEADER_SIZE);
+        let last = len == rest.len();
+        let fragment_type = match (first, last) {
+            (true, true) => FragmentType::Full,
+            (true, false) => FragmentType::First,
+            (false, false) => FragmentType::Middle,
+            (false, true) => FragmentType::Last,
+        };
+
+        let mut body = Vec::with_capacity(1 + len);
+        body.push(fragment_type as u8);
+        body.extend_from_slice(&rest[..len]);
+        let crc = record_checksum(checksum, log_number, &body);
+        buf.extend_from_slice(&crc.to_le_bytes());
+        buf.extend_from_slice(&(len as u16).to_le_bytes());
+        buf.extend_from_slice(&body);
+
+        block_offset += FRAGMENT_HEADER_SIZE + len;
+        rest = &rest[len..];
+        first = false;
+        if last {
+            return buf;
+        }
+    }
+}
+
+/// Parse the fragment at the start of `data`, which ends at most at the
+/// end of its block. None if it's truncated or fails its checksum.
+pub(crate) fn parse_fragment(
+    data: &[u8],
+    checksum: ChecksumType,
+    log_number: Option<u64>,
+) -> Option<(FragmentType, &[u8])> {
+    if data.len() < FRAGMENT_HEADER_SIZE {
+        return None;
+    }
+    let stored = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    let len = u16::from_le_bytes(data[4..6].try_into().unwrap()) as usize;
+    let body = data.get(6..FRAGMENT_HEADER_SIZE + len)?;
+    if record_checksum(checksum, log_number, body) != stored {
+        return None;
+    }
+    Some((FragmentType::from_u8(body[0])?, &body[1..]))
+}
@@ -0,0 +1,127 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// What made a WAL fsync happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncTrigger {
+    /// SyncPolicy::EveryWrite, after an append.
+    EveryWrite,
+    /// SyncPolicy::EveryNWrites, once N appends have built up.
+    Batch,
+    /// SyncPolicy::EveryNMillis, from the background syncer.
+    Timer,
+    /// Asked for: WALWriter::sync(), DB::flush_wal(true),
+    /// WriteOptions::sync, a WAL switch or close.
+    Manual,
+}
+
+/// Number of histogram buckets: the last one takes everything from
+/// 2^(BUCKETS - 1) microseconds (about 36 minutes) up.
+const BUCKETS: usize = 32;
+
+/// fsync latencies in power-of-two buckets: bucket 0 counts syncs under
+/// 2us, bucket i > 0 those of [2^i, 2^(i+1)) microseconds.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            buckets: [0; BUCKETS],
+            count: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().max(1);
+        let bucket = (micros.ilog2() as usize).min(BUCKETS - 1);
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    /// Syncs recorded.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean latency; zero before any sync.
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.total.as_nanos() / self.count as u128) as u64)
+    }
+
+    /// Slowest sync.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Upper bound of the bucket holding the `p`th percentile (0-100),
+    /// capped at max(); zero before any sync.
+    pub fn percentile(&self, p: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((p.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil() as u64;
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank.max(1) {
+                return Duration::from_micros(1 << (i + 1)).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Count of each bucket, fastest first.
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+}
+
+/// Counts and latencies of WAL fsyncs, in Stats::wal_syncs.
+#[derive(Debug, Clone, Default)]
+pub struct WALSyncStats {
+    pub every_write_syncs: u64,
+    pub batch_syncs: u64,
+    pub timer_syncs: u64,
+    pub manual_syncs: u64,
+    /// Latency of every sync, whatever its trigger.
+    pub latency: LatencyHistogram,
+}
+
+/// WALSyncStats shared by a WALManager's writers and its background
+/// syncer, carried over as WALs rotate.
+#[derive(Debug, Default)]
+pub(crate) struct SyncMetrics {
+    stats: Mutex<WALSyncStats>,
+}
+
+impl SyncMetrics {
+    pub(crate) fn record(&self, trigger: SyncTrigger, latency: Duration) {
+        let mut stats = self.stats.lock().unwrap();
+        match trigger {
+            SyncTrigger::EveryWrite => stats.every_write_syncs += 1,
+            SyncTrigger::Batch => stats.batch_syncs += 1,
+            SyncTrigger::Timer => stats.timer_syncs += 1,
+            SyncTrigger::Manual => stats.manual_syncs += 1,
+        }
+        stats.latency.record(latency);
+    }
+
+    pub(crate) fn snapshot(&self) -> WALSyncStats {
+        self.stats.lock().unwrap().clone()
+    }
+}
@@ -1,8 +1,15 @@
+pub mod archive;
+pub mod block;
+pub mod metrics;
 pub mod reader;
 pub mod record;
+mod syncer;
 pub mod writer;
 
-pub use record::{RecordType, WALRecord};
+pub use metrics::{LatencyHistogram, SyncTrigger, WALSyncStats};
+pub use record::{RecordType, WALRecord, WALUpdate};
+
+use crate::checksum::ChecksumType;
 
 // TODO [M10]: Implement configurable sync policies
 
@@ -20,6 +27,56 @@ pub enum SyncPolicy {
     EveryWrite,
     /// fsync every N records. Batched durability.
     EveryNWrites(usize),
-    /// fsync on timer. Bounded data loss window.
+    /// fsync on timer. Bounded data loss window. The timer is a thread
+    /// run by WALManager; a bare WALWriter only syncs when asked.
     EveryNMillis(u64),
 }
+
+/// How a WALManager writes, syncs and reuses its WAL files.
+#[derive(Debug, Clone, Copy)]
+pub struct WALOptions {
+    /// When each WAL is fsync'd. Default: EveryWrite.
+    pub sync_policy: SyncPolicy,
+    /// Bytes of disk space reserved for each WAL when it's opened
+    /// (fallocate on Linux, without changing the file's size), so appends
+    /// don't have to grow its allocation and sync that metadata. 0
+    /// reserves nothing. Default: 0.
+    pub preallocate_size: u64,
+    /// Flushed WALs kept to be overwritten by later ones instead of being
+    /// deleted, saving a file create and delete per rotation. WALs are
+    /// then written in the recyclable format (see reader::WALReader).
+    /// Default: 0.
+    pub recycle_log_file_num: usize,
+    /// Size past which WALManager::append() switches to a new WAL, so a
+    /// memtable that's slow to fill doesn't leave one huge WAL. 0 never
+    /// switches. Default: 0.
+    pub max_wal_size: u64,
+    /// Checksum over each record. Anything but CRC32 adds a header naming
+    /// it to each WAL (see record::CHECKSUM_MAGIC). Default: Crc32.
+    pub checksum: ChecksumType,
+    /// Frame records in 32KB blocks (see block::BLOCK_SIZE), so a torn
+    /// write damages only the blocks it touched and recovery skips them
+    /// instead of stopping at the first bad record. Adds a header to
+    /// each WAL, and 7 bytes per record per block it spans. Default: false.
+    pub block_format: bool,
+    /// Leave appended records in the writer's buffer until flush() or the
+    /// next sync, instead of handing each to the OS as it's written:
+    /// fewer write calls, but a process crash loses what's still
+    /// buffered. Ignored under SyncPolicy::EveryNMillis, whose background
+    /// syncer needs records in the file. Default: false.
+    pub manual_flush: bool,
+}
+
+impl Default for WALOptions {
+    fn default() -> Self {
+        WALOptions {
+            sync_policy: SyncPolicy::EveryWrite,
+            preallocate_size: 0,
+            recycle_log_file_num: 0,
+            max_wal_size: 0,
+            checksum: ChecksumType::Crc32,
+            block_format: false,
+            manual_flush: false,
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use std::fs;
 use std::path::Path;
 
+use crate::checksum::ChecksumType;
 use crate::error::Result;
-use crate::wal::record::WALRecord;
+use crate::wal::block::{BLOCK_SIZE, FRAGMENT_HEADER_SIZE, FragmentType, parse_fragment};
+use crate::wal::record::{
+    CHECKSUM_HEADER_SIZE, CHECKSUM_MAGIC, RECYCLABLE_HEADER_SIZE, RECYCLABLE_MAGIC, WALRecord,
+    parse_checksum_header,
+};
 
 /// Reads WAL records from a file for crash recovery.
 ///
@@ -12,22 +17,64 @@ use crate::wal::record::WALRecord;
 /// 2. Replay each record into a fresh memtable
 /// 3. If CRC fails on a record, stop — it was a partial write from a crash.
 ///    All preceding records are valid.
+///
+/// A recyclable WAL (see WALOptions::recycle_log_file_num) starts with
+/// its log number, which its records' CRCs cover: what's left of the
+/// file's previous use fails the CRC and ends it the same way. A WAL
+/// checksummed with something other than CRC32 names it in its header.
+///
+/// A WAL framed in blocks (see block::BLOCK_SIZE) doesn't end at a bad
+/// record: the rest of its block is skipped and reading resumes with the
+/// next record starting in a later block, so a torn write loses only the
+/// records in the blocks it touched.
 pub struct WALReader {
     data: Vec<u8>,
+    /// Bytes of header before the first record.
+    header_size: usize,
+    checksum: ChecksumType,
+    /// Log number from the recyclable header, if the file has one.
+    log_number: Option<u64>,
+    /// Whether records are framed in blocks.
+    blocks: bool,
 }
 
 impl WALReader {
     /// Open a WAL file for reading.
     pub fn new(path: &Path) -> Result<Self> {
         let data = fs::read(path)?;
-        Ok(WALReader { data })
+        let (header_size, checksum, log_number, blocks) =
+            if data.len() >= CHECKSUM_HEADER_SIZE && data.starts_with(CHECKSUM_MAGIC) {
+                let (checksum, log_number, blocks) = parse_checksum_header(&data)?;
+                (CHECKSUM_HEADER_SIZE, checksum, log_number, blocks)
+            } else if data.len() >= RECYCLABLE_HEADER_SIZE && data.starts_with(RECYCLABLE_MAGIC) {
+                let log_number =
+                    u64::from_le_bytes(data[8..RECYCLABLE_HEADER_SIZE].try_into().unwrap());
+                (
+                    RECYCLABLE_HEADER_SIZE,
+                    ChecksumType::Crc32,
+                    Some(log_number),
+                    false,
+                )
+            } else {
+                (0, ChecksumType::Crc32, None, false)
+            };
+        Ok(WALReader {
+            data,
+            header_size,
+            checksum,
+            log_number,
+            blocks,
+        })
     }
 
     /// Create an iterator over all valid records in the WAL.
     pub fn iter(&self) -> WALIterator<'_> {
         WALIterator {
             data: &self.data,
-            offset: 0,
+            offset: self.header_size,
+            checksum: self.checksum,
+            log_number: self.log_number,
+            blocks: self.blocks,
         }
     }
 }
@@ -41,19 +88,74 @@ impl WALReader {
 pub struct WALIterator<'a> {
     data: &'a [u8],
     offset: usize,
+    checksum: ChecksumType,
+    log_number: Option<u64>,
+    blocks: bool,
+}
+
+impl WALIterator<'_> {
+    /// next() in a WAL framed in blocks: reassemble the next record from
+    /// its fragments, skipping the rest of any block with a bad one.
+    fn next_in_blocks(&mut self) -> Option<WALRecord> {
+        // Fragments of the record being reassembled, from its First on
+        let mut partial: Option<Vec<u8>> = None;
+        loop {
+            if self.offset >= self.data.len() {
+                return None;
+            }
+            let block_end = (self.offset / BLOCK_SIZE + 1) * BLOCK_SIZE;
+            let block = &self.data[self.offset..block_end.min(self.data.len())];
+            let Some((fragment_type, payload)) =
+                parse_fragment(block, self.checksum, self.log_number)
+            else {
+                // A torn write or a zeroed trailer: on to the next block,
+                // dropping any record it cut short
+                self.offset = block_end;
+                partial = None;
+                continue;
+            };
+            self.offset += FRAGMENT_HEADER_SIZE + payload.len();
+
+            let payload = match (fragment_type, partial.take()) {
+                (FragmentType::Full, _) => payload.to_vec(),
+                (FragmentType::First, _) => {
+                    partial = Some(payload.to_vec());
+                    continue;
+                }
+                (FragmentType::Middle, Some(mut record)) => {
+                    record.extend_from_slice(payload);
+                    partial = Some(record);
+                    continue;
+                }
+                (FragmentType::Last, Some(mut record)) => {
+                    record.extend_from_slice(payload);
+                    record
+                }
+                // The rest of a record whose start was skipped
+                (FragmentType::Middle | FragmentType::Last, None) => continue,
+            };
+            match WALRecord::decode_for_log(&payload, self.checksum, self.log_number) {
+                Ok(record) => return Some(record),
+                Err(_) => continue,
+            }
+        }
+    }
 }
 
 impl<'a> Iterator for WALIterator<'a> {
     type Item = Result<WALRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.blocks {
+            return self.next_in_blocks().map(Ok);
+        }
         if self.offset >= self.data.len() {
             return None;
         }
 
         let remaining = &self.data[self.offset..];
 
-        match WALRecord::decode(remaining) {
+        match WALRecord::decode_for_log(remaining, self.checksum, self.log_number) {
             Ok(record) => {
                 self.offset += record.encoded_size();
                 Some(Ok(record))
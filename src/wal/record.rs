@@ -3,13 +3,36 @@
 //   - Deserialization: bytes → WALRecord
 //   - CRC computation and verification
 
+use crate::checksum::{ChecksumHasher, ChecksumType};
 use crate::error::{Error, Result};
+use crate::types::ValueType;
 
 /// Record type stored in the WAL.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RecordType {
     Put = 0x01,
     Delete = 0x02,
+    /// Not a write: the sequence number of the next record in the file,
+    /// as the value (u64 LE). The DB starts every WAL with one; each
+    /// write after it takes the next number. After writes that skipped
+    /// the WAL, another follows with the first skipped number appended
+    /// (u64 LE).
+    Sequence = 0x03,
+    /// Not a write: a batch prepared for two-phase commit, held until a
+    /// Commit or Rollback with the same transaction id (the key). The
+    /// value is the batch's Put and Delete records, each
+    /// `[type(1)][key_len(4)][key][value_len(4)][value]`.
+    Prepare = 0x04,
+    /// A prepared batch committed: its writes, encoded as in Prepare,
+    /// take the next sequence numbers in order.
+    Commit = 0x05,
+    /// Not a write: the prepared batch with this transaction id is
+    /// discarded.
+    Rollback = 0x06,
+    /// A write batch: its Puts and Deletes, encoded as in Prepare, take
+    /// the next sequence numbers in order. Logged as one record, so a
+    /// crash keeps all of them or none. The key is empty.
+    Batch = 0x07,
 }
 
 impl RecordType {
@@ -17,6 +40,11 @@ impl RecordType {
         match byte {
             0x01 => Ok(RecordType::Put),
             0x02 => Ok(RecordType::Delete),
+            0x03 => Ok(RecordType::Sequence),
+            0x04 => Ok(RecordType::Prepare),
+            0x05 => Ok(RecordType::Commit),
+            0x06 => Ok(RecordType::Rollback),
+            0x07 => Ok(RecordType::Batch),
             _ => Err(Error::Corruption(format!("invalid record type: {}", byte))),
         }
     }
@@ -41,6 +69,126 @@ pub struct WALRecord {
     pub value: Vec<u8>,
 }
 
+/// One logged write and its sequence number.
+#[derive(Debug, Clone)]
+pub struct WALUpdate {
+    pub sequence: u64,
+    pub record: WALRecord,
+}
+
+/// First bytes of a recyclable WAL, followed by its log number (8B).
+/// Files without them hold plain records from the start.
+pub(crate) const RECYCLABLE_MAGIC: &[u8; 8] = b"LSMWALR1";
+pub(crate) const RECYCLABLE_HEADER_SIZE: usize = 16;
+
+/// The header of a recyclable WAL numbered `log_number`.
+pub(crate) fn recyclable_header(log_number: u64) -> [u8; RECYCLABLE_HEADER_SIZE] {
+    let mut header = [0u8; RECYCLABLE_HEADER_SIZE];
+    header[..8].copy_from_slice(RECYCLABLE_MAGIC);
+    header[8..].copy_from_slice(&log_number.to_le_bytes());
+    header
+}
+
+/// First bytes of a WAL whose records use a checksum other than CRC32,
+/// or are framed in blocks, followed by
+/// `[checksum(1)][flags(1)][reserved(6)][log number(8)]`. Flag bit 0
+/// marks the file recyclable, with records tied to the log number as in
+/// a LSMWALR1 file; bit 1 marks records framed in blocks (see
+/// block::BLOCK_SIZE). Other WALs keep the formats above, so older
+/// builds can still read them.
+pub(crate) const CHECKSUM_MAGIC: &[u8; 8] = b"LSMWALC1";
+pub(crate) const CHECKSUM_HEADER_SIZE: usize = 24;
+const FLAG_RECYCLABLE: u8 = 0x01;
+const FLAG_BLOCKS: u8 = 0x02;
+
+/// The header of a WAL numbered `log_number` whose records use `checksum`,
+/// framed in blocks if `blocks`.
+pub(crate) fn checksum_header(
+    checksum: ChecksumType,
+    log_number: u64,
+    recyclable: bool,
+    blocks: bool,
+) -> [u8; CHECKSUM_HEADER_SIZE] {
+    let mut header = [0u8; CHECKSUM_HEADER_SIZE];
+    header[..8].copy_from_slice(CHECKSUM_MAGIC);
+    header[8] = checksum.to_byte();
+    if recyclable {
+        header[9] |= FLAG_RECYCLABLE;
+    }
+    if blocks {
+        header[9] |= FLAG_BLOCKS;
+    }
+    header[16..].copy_from_slice(&log_number.to_le_bytes());
+    header
+}
+
+/// Parse a LSMWALC1 header: the checksum, the log number if the file is
+/// recyclable, and whether records are framed in blocks.
+pub(crate) fn parse_checksum_header(header: &[u8]) -> Result<(ChecksumType, Option<u64>, bool)> {
+    let checksum = ChecksumType::from_byte(header[8])
+        .ok_or_else(|| Error::Corruption(format!("unknown WAL checksum type: {}", header[8])))?;
+    let log_number = (header[9] & FLAG_RECYCLABLE != 0)
+        .then(|| u64::from_le_bytes(header[16..CHECKSUM_HEADER_SIZE].try_into().unwrap()));
+    Ok((checksum, log_number, header[9] & FLAG_BLOCKS != 0))
+}
+
+/// Checksum of a record body, seeded with the log number in recyclable WALs.
+pub(crate) fn record_checksum(checksum: ChecksumType, log_number: Option<u64>, body: &[u8]) -> u32 {
+    let mut hasher = ChecksumHasher::new(checksum);
+    if let Some(log_number) = log_number {
+        hasher.update(&log_number.to_le_bytes());
+    }
+    hasher.update(body);
+    hasher.finalize()
+}
+
+/// Encode the writes of a Prepare or Commit record.
+fn encode_batch(writes: &[WALRecord]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for write in writes {
+        buf.push(write.record_type as u8);
+        buf.extend_from_slice(&(write.key.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&write.key);
+        buf.extend_from_slice(&(write.value.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&write.value);
+    }
+    buf
+}
+
+fn decode_batch(mut data: &[u8]) -> Result<Vec<WALRecord>> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if data.len() < len {
+            return Err(Error::Corruption("batch entry truncated".into()));
+        }
+        let (head, rest) = data.split_at(len);
+        *data = rest;
+        Ok(head)
+    }
+    fn take_len(data: &mut &[u8]) -> Result<usize> {
+        Ok(u32::from_le_bytes(take(data, 4)?.try_into().unwrap()) as usize)
+    }
+
+    let mut writes = Vec::new();
+    while !data.is_empty() {
+        let record_type = RecordType::from_u8(take(&mut data, 1)?[0])?;
+        if !matches!(record_type, RecordType::Put | RecordType::Delete) {
+            return Err(Error::Corruption(format!(
+                "{record_type:?} record in a batch"
+            )));
+        }
+        let key_len = take_len(&mut data)?;
+        let key = take(&mut data, key_len)?.to_vec();
+        let value_len = take_len(&mut data)?;
+        let value = take(&mut data, value_len)?.to_vec();
+        writes.push(WALRecord {
+            record_type,
+            key,
+            value,
+        });
+    }
+    Ok(writes)
+}
+
 // Header sizes
 const CRC_SIZE: usize = 4;
 const LEN_SIZE: usize = 4;
@@ -67,8 +215,126 @@ impl WALRecord {
         }
     }
 
+    /// Create a Sequence record: the next record is write `sequence`.
+    pub fn sequence(sequence: u64) -> Self {
+        WALRecord {
+            record_type: RecordType::Sequence,
+            key: Vec::new(),
+            value: sequence.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Create a Sequence record for logging resumed at `sequence`, after
+    /// writes from `unlogged_from` on skipped the WAL.
+    pub fn sequence_after_unlogged(sequence: u64, unlogged_from: u64) -> Self {
+        let mut record = Self::sequence(sequence);
+        record.value.extend_from_slice(&unlogged_from.to_le_bytes());
+        record
+    }
+
+    /// The sequence number a Sequence record carries.
+    pub fn sequence_number(&self) -> Option<u64> {
+        match self.record_type {
+            RecordType::Sequence => Some(u64::from_le_bytes(self.value.get(..8)?.try_into().ok()?)),
+            _ => None,
+        }
+    }
+
+    /// The first of the writes a Sequence record says skipped the WAL,
+    /// if any did.
+    pub fn unlogged_from(&self) -> Option<u64> {
+        match self.record_type {
+            RecordType::Sequence => {
+                Some(u64::from_le_bytes(self.value.get(8..16)?.try_into().ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// Create a Prepare record holding `writes` (Puts and Deletes) for
+    /// transaction `xid`.
+    pub fn prepare(xid: Vec<u8>, writes: &[WALRecord]) -> Self {
+        WALRecord {
+            record_type: RecordType::Prepare,
+            key: xid,
+            value: encode_batch(writes),
+        }
+    }
+
+    /// Create a Commit record applying `writes`, prepared as `xid`.
+    pub fn commit(xid: Vec<u8>, writes: &[WALRecord]) -> Self {
+        WALRecord {
+            record_type: RecordType::Commit,
+            key: xid,
+            value: encode_batch(writes),
+        }
+    }
+
+    /// Create a Batch record applying `writes` (Puts and Deletes) in
+    /// order, atomically.
+    pub fn write_batch(writes: &[WALRecord]) -> Self {
+        WALRecord {
+            record_type: RecordType::Batch,
+            key: Vec::new(),
+            value: encode_batch(writes),
+        }
+    }
+
+    /// Create a Rollback record for transaction `xid`.
+    pub fn rollback(xid: Vec<u8>) -> Self {
+        WALRecord {
+            record_type: RecordType::Rollback,
+            key: xid,
+            value: Vec::new(),
+        }
+    }
+
+    /// The writes a Prepare, Commit or Batch record holds.
+    pub fn batch(&self) -> Result<Vec<WALRecord>> {
+        if !matches!(
+            self.record_type,
+            RecordType::Prepare | RecordType::Commit | RecordType::Batch
+        ) {
+            return Err(Error::Corruption(format!(
+                "{:?} record holds no batch",
+                self.record_type
+            )));
+        }
+        decode_batch(&self.value)
+    }
+
+    /// The writes logging this record makes: itself for a Put or Delete,
+    /// its batch for a Commit or Batch, none for records that apply nothing.
+    pub fn writes(&self) -> Result<Vec<WALRecord>> {
+        match self.record_type {
+            RecordType::Put | RecordType::Delete => Ok(vec![self.clone()]),
+            RecordType::Commit | RecordType::Batch => self.batch(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// The memtable ValueType of a write: Delete for a Delete record, Put
+    /// otherwise.
+    pub fn value_type(&self) -> ValueType {
+        match self.record_type {
+            RecordType::Delete => ValueType::Delete,
+            _ => ValueType::Put,
+        }
+    }
+
     /// Serialize this record to bytes (including CRC header).
     pub fn encode(&self) -> Vec<u8> {
+        self.encode_for_log(ChecksumType::Crc32, None)
+    }
+
+    /// Serialize with `checksum` in place of the CRC. In a recyclable WAL
+    /// it also covers `log_number` (see RECYCLABLE_HEADER_SIZE), so records
+    /// left over from the file's previous use fail it.
+    pub(crate) fn encode_for_log(
+        &self,
+        checksum: ChecksumType,
+        log_number: Option<u64>,
+    ) -> Vec<u8> {
         let payload_len = TYPE_SIZE + KEY_LEN_SIZE + self.key.len() + self.value.len();
         let total_len = CRC_SIZE + LEN_SIZE + payload_len;
 
@@ -93,7 +359,7 @@ impl WALRecord {
         buf.extend_from_slice(&self.value);
 
         // Compute CRC over everything after CRC field
-        let crc = crc32fast::hash(&buf[CRC_SIZE..]);
+        let crc = record_checksum(checksum, log_number, &buf[CRC_SIZE..]);
         buf[0..CRC_SIZE].copy_from_slice(&crc.to_le_bytes());
 
         buf
@@ -101,6 +367,15 @@ impl WALRecord {
 
     /// Deserialize a record from bytes. Returns error if CRC doesn't match.
     pub fn decode(data: &[u8]) -> Result<Self> {
+        Self::decode_for_log(data, ChecksumType::Crc32, None)
+    }
+
+    /// Deserialize a record written by encode_for_log(`checksum`, `log_number`).
+    pub(crate) fn decode_for_log(
+        data: &[u8],
+        checksum: ChecksumType,
+        log_number: Option<u64>,
+    ) -> Result<Self> {
         // Need at least header
         if data.len() < HEADER_SIZE {
             return Err(Error::Corruption("record too short".into()));
@@ -119,7 +394,7 @@ impl WALRecord {
         }
 
         // Verify CRC (covers everything after CRC field)
-        let computed_crc = crc32fast::hash(&data[CRC_SIZE..total_len]);
+        let computed_crc = record_checksum(checksum, log_number, &data[CRC_SIZE..total_len]);
         if stored_crc != computed_crc {
             return Err(Error::Corruption("CRC mismatch".into()));
         }
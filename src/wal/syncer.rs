@@ -0,0 +1,109 @@
+use std::fs::File;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::wal::metrics::SyncTrigger;
+use crate::wal::writer::SyncState;
+
+/// Thread that fsyncs the active WAL every `interval`, for
+/// SyncPolicy::EveryNMillis. Ticks with no new writes skip the fsync.
+///
+/// A failed fsync is stored in the writer's SyncState, failing its next
+/// append: the writes since the last good sync may not be on disk.
+pub(crate) struct BackgroundSyncer {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    interval: Duration,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    /// Second handle on the active WAL file, and its writer's state.
+    target: (File, Arc<SyncState>),
+    shutdown: bool,
+}
+
+impl BackgroundSyncer {
+    pub(crate) fn start(interval: Duration, target: (File, Arc<SyncState>)) -> Self {
+        let shared = Arc::new(Shared {
+            interval,
+            state: Mutex::new(State {
+                target,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        });
+        let worker = {
+            let shared = Arc::clone(&shared);
+            std::thread::Builder::new()
+                .name("lsm-wal-sync".into())
+                .spawn(move || shared.run())
+                .expect("failed to spawn WAL sync thread")
+        };
+        BackgroundSyncer {
+            shared,
+            worker: Some(worker),
+        }
+    }
+
+    /// Sync `target` from now on, after the WAL rotated.
+    pub(crate) fn set_target(&self, target: (File, Arc<SyncState>)) {
+        self.shared.state.lock().unwrap().target = target;
+    }
+}
+
+impl Drop for BackgroundSyncer {
+    /// Stop the thread, which syncs once more on the way out.
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let deadline = Instant::now() + self.interval;
+            while !state.shutdown {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+                state = self.changed.wait_timeout(state, deadline - now).unwrap().0;
+            }
+
+            let (file, sync_state) = &state.target;
+            let writes = sync_state.writes_since_sync.load(Ordering::SeqCst);
+            if writes > 0 {
+                // Held across the fsync so a rotation can't swap the file
+                // out from under it; appends don't take this lock.
+                match sync_state.sync_file(file, SyncTrigger::Timer) {
+                    Ok(()) => {
+                        // The writer may have synced and reset it meanwhile
+                        let _ = sync_state.writes_since_sync.fetch_update(
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                            |n| Some(n.saturating_sub(writes)),
+                        );
+                    }
+                    Err(e) => {
+                        sync_state.error.lock().unwrap().get_or_insert(e.into());
+                    }
+                }
+            }
+            if state.shutdown {
+                return;
+            }
+        }
+    }
+}
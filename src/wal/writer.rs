@@ -1,10 +1,18 @@
+use std::collections::VecDeque;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::error::Result;
-use crate::wal::SyncPolicy;
-use crate::wal::record::WALRecord;
+use crate::checksum::ChecksumType;
+use crate::error::{Error, Result};
+use crate::wal::block::{frame_record, next_fragment_block};
+use crate::wal::metrics::{SyncMetrics, SyncTrigger, WALSyncStats};
+use crate::wal::record::{WALRecord, WALUpdate, checksum_header, recyclable_header};
+use crate::wal::syncer::BackgroundSyncer;
+use crate::wal::{SyncPolicy, WALOptions};
 
 // TODO [M07]: Implement WAL writer with fsync
 // TODO [M09]: Implement WAL rotation on memtable flush
@@ -22,71 +30,331 @@ pub struct WALWriter {
     writer: BufWriter<File>,
     offset: u64,
     sync_policy: SyncPolicy,
-    writes_since_sync: usize,
+    sync_state: Arc<SyncState>,
+    /// Log number records are tied to, in a recyclable WAL.
+    log_number: Option<u64>,
+    checksum: ChecksumType,
+    /// Whether records are framed in blocks (WALOptions::block_format).
+    blocks: bool,
+    /// Appends leave records buffered (WALOptions::manual_flush).
+    manual_flush: bool,
+}
+
+/// What a writer shares with the background syncer that syncs its file.
+#[derive(Default)]
+pub(crate) struct SyncState {
+    /// Records appended since the last fsync.
+    pub(crate) writes_since_sync: AtomicUsize,
+    /// Set when a background fsync fails; every later append fails with it.
+    pub(crate) error: Mutex<Option<Error>>,
+    /// Where every fsync of the file is counted and timed.
+    pub(crate) metrics: Arc<SyncMetrics>,
+    /// Where the updates each fsync makes durable are delivered.
+    pub(crate) shipper: Arc<Shipper>,
+    /// Updates appended since the last fsync, for `shipper`.
+    pub(crate) unsynced: Mutex<Vec<WALUpdate>>,
+}
+
+impl SyncState {
+    /// fsync `file`, counted as caused by `trigger`, then hand the updates
+    /// it made durable to the shipper's hook.
+    pub(crate) fn sync_file(&self, file: &File, trigger: SyncTrigger) -> std::io::Result<()> {
+        // Held until delivered, so the hook sees updates in log order
+        // whichever of the writer and the background syncer syncs
+        let _delivering = self.shipper.delivering.lock().unwrap();
+        // Taken before the fsync: only what was written by now is covered.
+        // If it fails, they're dropped; the writes may not be on disk
+        let updates = std::mem::take(&mut *self.unsynced.lock().unwrap());
+        let start = Instant::now();
+        file.sync_all()?;
+        self.metrics.record(trigger, start.elapsed());
+        if !updates.is_empty()
+            && let Some(hook) = self.shipper.hook()
+        {
+            hook(&updates);
+        }
+        Ok(())
+    }
+}
+
+/// Called with the updates each fsync of a WALManager's WALs made durable,
+/// in log order.
+pub type WALSyncedHook = Arc<dyn Fn(&[WALUpdate]) + Send + Sync>;
+
+/// The synced hook a WALManager's writers share, across rotations.
+#[derive(Default)]
+pub(crate) struct Shipper {
+    hook: RwLock<Option<WALSyncedHook>>,
+    /// Held from taking a writer's unsynced updates to delivering them.
+    delivering: Mutex<()>,
+}
+
+impl Shipper {
+    fn hook(&self) -> Option<WALSyncedHook> {
+        self.hook.read().unwrap().clone()
+    }
+
+    fn is_set(&self) -> bool {
+        self.hook.read().unwrap().is_some()
+    }
 }
 
 impl WALWriter {
-    /// Create a new WAL writer at the given path.
+    /// Create a new WAL writer at the given path. An existing file is
+    /// appended to, with offset() starting at its current size.
     pub fn new(path: &Path, sync_policy: SyncPolicy) -> Result<Self> {
         let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let offset = file.metadata()?.len();
 
         Ok(WALWriter {
             writer: BufWriter::new(file),
-            offset: 0,
+            offset,
             sync_policy,
-            writes_since_sync: 0,
+            sync_state: Arc::new(SyncState::default()),
+            log_number: None,
+            checksum: ChecksumType::Crc32,
+            blocks: false,
+            manual_flush: false,
         })
     }
 
+    /// Open WAL `log_number` at `path` for a WALManager, writing from the
+    /// start of the file: a recycled WAL is overwritten in place. Writes
+    /// the recyclable header if `options` recycles WALs, and the checksum
+    /// header if its checksum isn't CRC32 or it frames records in blocks.
+    /// Syncs are counted in `metrics` and ship updates to `shipper`.
+    fn open_for_manager(
+        path: &Path,
+        log_number: u64,
+        options: &WALOptions,
+        metrics: &Arc<SyncMetrics>,
+        shipper: &Arc<Shipper>,
+    ) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(path)?;
+        preallocate(&file, options.preallocate_size);
+        let mut writer = WALWriter {
+            writer: BufWriter::new(file),
+            offset: 0,
+            sync_policy: options.sync_policy,
+            sync_state: Arc::new(SyncState {
+                metrics: Arc::clone(metrics),
+                shipper: Arc::clone(shipper),
+                ..SyncState::default()
+            }),
+            log_number: None,
+            checksum: options.checksum,
+            blocks: options.block_format,
+            manual_flush: options.manual_flush,
+        };
+        let recyclable = options.recycle_log_file_num > 0;
+        let header = if options.checksum != ChecksumType::Crc32 || options.block_format {
+            checksum_header(
+                options.checksum,
+                log_number,
+                recyclable,
+                options.block_format,
+            )
+            .to_vec()
+        } else if recyclable {
+            recyclable_header(log_number).to_vec()
+        } else {
+            Vec::new()
+        };
+        if !header.is_empty() {
+            writer.writer.write_all(&header)?;
+            writer.sync()?;
+            writer.offset = header.len() as u64;
+        }
+        if recyclable {
+            writer.log_number = Some(log_number);
+        }
+        Ok(writer)
+    }
+
     /// Append a record to the WAL.
-    /// Depending on SyncPolicy, may fsync after this write.
+    /// Depending on SyncPolicy, may fsync after this write. With
+    /// WALOptions::manual_flush, the record stays buffered until flush()
+    /// or the next sync.
     pub fn append(&mut self, record: &WALRecord) -> Result<()> {
-        let encoded = record.encode();
+        self.append_shipping(record, Vec::new())
+    }
+
+    /// Append `record`, whose writes are `updates`: they're delivered to
+    /// the synced hook once an fsync covers the record.
+    fn append_shipping(&mut self, record: &WALRecord, updates: Vec<WALUpdate>) -> Result<()> {
+        if let Some(e) = &*self.sync_state.error.lock().unwrap() {
+            return Err(e.clone());
+        }
+        let mut encoded = record.encode_for_log(self.checksum, self.log_number);
+        if self.blocks {
+            encoded = frame_record(&encoded, self.offset, self.checksum, self.log_number);
+        }
 
         self.writer.write_all(&encoded)?;
-        self.writer.flush()?;
+        // The background syncer of EveryNMillis syncs through its own
+        // handle on the file, so needs the record there
+        if !self.manual_flush || matches!(self.sync_policy, SyncPolicy::EveryNMillis(_)) {
+            self.writer.flush()?;
+        }
         self.offset += encoded.len() as u64;
-        self.writes_since_sync += 1;
+        if !updates.is_empty() {
+            self.sync_state.unsynced.lock().unwrap().extend(updates);
+        }
+        let writes_since_sync = self
+            .sync_state
+            .writes_since_sync
+            .fetch_add(1, Ordering::SeqCst)
+            + 1;
 
         // Sync based on policy
         match self.sync_policy {
-            SyncPolicy::EveryWrite => {
-                self.writer.get_ref().sync_all()?;
-                self.writes_since_sync = 0;
-            }
+            SyncPolicy::EveryWrite => self.sync_for(SyncTrigger::EveryWrite)?,
             SyncPolicy::EveryNWrites(n) => {
-                if self.writes_since_sync >= n {
-                    self.writer.get_ref().sync_all()?;
-                    self.writes_since_sync = 0;
+                if writes_since_sync >= n {
+                    self.sync_for(SyncTrigger::Batch)?;
                 }
             }
             SyncPolicy::EveryNMillis(_) => {
-                // Timer-based sync handled externally
+                // Synced by the WALManager's background syncer
             }
         }
 
         Ok(())
     }
 
+    /// Hand buffered writes to the OS, without fsync.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
     /// Force fsync to disk. Ensures all buffered writes are durable.
     pub fn sync(&mut self) -> Result<()> {
+        self.sync_for(SyncTrigger::Manual)
+    }
+
+    /// fsync, counted as caused by `trigger`.
+    fn sync_for(&mut self, trigger: SyncTrigger) -> Result<()> {
         self.writer.flush()?;
-        self.writer.get_ref().sync_all()?;
-        self.writes_since_sync = 0;
+        self.sync_state.sync_file(self.writer.get_ref(), trigger)?;
+        self.sync_state.writes_since_sync.store(0, Ordering::SeqCst);
         Ok(())
     }
 
+    /// Sync by `sync_policy` from the next append on. Writes not yet
+    /// synced are synced now, so a tighter policy covers them too.
+    pub fn set_sync_policy(&mut self, sync_policy: SyncPolicy) -> Result<()> {
+        if self.writes_since_sync() > 0 {
+            self.sync()?;
+        }
+        self.sync_policy = sync_policy;
+        Ok(())
+    }
+
+    /// Counts and latencies of this writer's fsyncs (with a WALManager's
+    /// writer, of every WAL the manager has written).
+    pub fn sync_stats(&self) -> WALSyncStats {
+        self.sync_state.metrics.snapshot()
+    }
+
     /// Current file offset (bytes written so far).
     pub fn offset(&self) -> u64 {
         self.offset
     }
 
+    /// In a WAL framed in blocks, the block the next record starts in.
+    pub(crate) fn next_block(&self) -> Option<u64> {
+        self.blocks.then(|| next_fragment_block(self.offset))
+    }
+
+    /// Cut the file back to `offset` bytes and fsync it, e.g. to drop a
+    /// torn record left by a crash before appending after the last good
+    /// one. Fails with InvalidInput past the current offset.
+    pub fn truncate_to(&mut self, offset: u64) -> Result<()> {
+        if offset > self.offset {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "can't truncate WAL to {offset}, past its end at {}",
+                    self.offset
+                ),
+            )));
+        }
+        self.writer.flush()?;
+        let file = self.writer.get_ref();
+        file.set_len(offset)?;
+        file.sync_all()?;
+        self.offset = offset;
+        Ok(())
+    }
+
     /// Number of writes since the last fsync. Useful for testing sync policies.
     pub fn writes_since_sync(&self) -> usize {
-        self.writes_since_sync
+        self.sync_state.writes_since_sync.load(Ordering::SeqCst)
+    }
+
+    /// A second handle on the file and the state the background syncer
+    /// needs to sync it.
+    fn sync_target(&self) -> Result<(File, Arc<SyncState>)> {
+        Ok((
+            self.writer.get_ref().try_clone()?,
+            Arc::clone(&self.sync_state),
+        ))
+    }
+}
+
+/// fsync directory `dir`, making the creation, rename or removal of a
+/// file in it durable: without it a WAL synced after being created may
+/// still be gone after a crash. Directories can't be opened for this on
+/// Windows, where it's skipped.
+fn sync_dir(dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// sync_dir() on the directory holding `path`, once the file was
+/// created, renamed away or removed.
+pub(crate) fn sync_parent_dir(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(dir) if dir.as_os_str().is_empty() => sync_dir(Path::new(".")),
+        Some(dir) => sync_dir(dir),
+        None => Ok(()),
     }
 }
 
+/// Reserve `size` bytes of disk for `file` without changing its size.
+/// Best effort: a filesystem that can't just leaves the file to grow.
+fn preallocate(file: &File, size: u64) {
+    if size == 0 {
+        return;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+        // SAFETY: the descriptor is owned by `file`, open for the call
+        unsafe {
+            libc::fallocate(
+                file.as_raw_fd(),
+                libc::FALLOC_FL_KEEP_SIZE,
+                0,
+                size as libc::off_t,
+            );
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = file;
+}
+
+/// Called by a WALManager with the number of each WAL it switches to.
+pub type WALCreatedHook = Box<dyn FnMut(u64) -> Result<()> + Send>;
+
 /// Manages WAL file rotation.
 ///
 /// When a memtable is flushed to SSTable:
@@ -96,12 +364,60 @@ impl WALWriter {
 ///
 /// CRITICAL INVARIANT: Old WAL is only deleted AFTER its SSTable is
 /// fully written and fsync'd. Violating this loses data.
+///
+/// With SyncPolicy::EveryNMillis the manager also runs a background
+/// thread that fsyncs the active WAL at that interval, so a crash loses
+/// at most about that much of the latest writes. The thread is stopped,
+/// after a last sync, when the manager is dropped.
+///
+/// With WALOptions::recycle_log_file_num set, flushed WALs handed to
+/// recycle_wal() are kept and reused by later rotations, renamed and
+/// overwritten rather than deleted and created anew.
+///
+/// With WALOptions::max_wal_size set, append() also switches WALs once
+/// the active one is that big. The WALs switched away from hold writes
+/// of the same memtable as the active one; take_size_rotated() hands
+/// them over to be deleted with it.
+///
+/// A hook set with set_wal_created_hook() hears of every WAL the manager
+/// switches to before anything is written to it, so the DB can list it
+/// in the manifest.
+///
+/// A hook set with set_wal_synced_hook() is handed each write once an
+/// fsync has made it durable, e.g. for a replication agent to ship the
+/// log to followers as it's written rather than polling the files.
+///
+/// Created with several directories (with_dirs()), the manager puts each
+/// new WAL in the next one round-robin, so the fsyncs of successive WALs
+/// land on different devices.
 pub struct WALManager {
-    dir: std::path::PathBuf,
+    /// Directories WALs are created in, in turn.
+    dirs: Vec<PathBuf>,
+    /// Index in `dirs` of the active WAL's directory.
+    dir_index: usize,
     active_writer: WALWriter,
     active_path: std::path::PathBuf,
     next_wal_id: u64,
-    sync_policy: SyncPolicy,
+    options: WALOptions,
+    syncer: Option<BackgroundSyncer>,
+    /// Flushed WALs waiting to be reused, oldest first.
+    recycled: VecDeque<PathBuf>,
+    /// WALs append() switched away from (or adopt_wals() was given),
+    /// oldest first, until take_size_rotated().
+    size_rotated: Vec<PathBuf>,
+    /// Called with each new WAL's number once it's created.
+    wal_created: Option<WALCreatedHook>,
+    /// Whether the active WAL's next write is numbered by a Sequence
+    /// record already.
+    sequence_logged: bool,
+    /// First write since the last append() that skipped the WAL.
+    unlogged_from: Option<u64>,
+    /// In a WAL framed in blocks, the block of the last Sequence record.
+    sequence_block: Option<u64>,
+    /// fsyncs of every WAL this manager has written.
+    metrics: Arc<SyncMetrics>,
+    /// Synced hook shared by every WAL this manager writes.
+    shipper: Arc<Shipper>,
 }
 
 impl WALManager {
@@ -110,47 +426,275 @@ impl WALManager {
     /// Scans for existing WAL files to determine the next ID,
     /// then creates a new active WAL file.
     pub fn new(dir: &Path, sync_policy: SyncPolicy) -> Result<Self> {
-        std::fs::create_dir_all(dir)?;
+        Self::with_options(
+            dir,
+            WALOptions {
+                sync_policy,
+                ..WALOptions::default()
+            },
+        )
+    }
+
+    /// Create a WAL manager for the given directory, with `options`.
+    pub fn with_options(dir: &Path, options: WALOptions) -> Result<Self> {
+        Self::with_dirs(&[dir.to_path_buf()], &[], options)
+    }
+
+    /// Create a WAL manager striping WALs over `dirs` (at least one),
+    /// starting with the first. WAL numbers continue after the highest
+    /// in `dirs` and `old_dirs`, which may hold WALs of earlier runs
+    /// that wrote elsewhere.
+    pub fn with_dirs(dirs: &[PathBuf], old_dirs: &[PathBuf], options: WALOptions) -> Result<Self> {
+        let Some(dir) = dirs.first() else {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "no WAL directory",
+            )));
+        };
+        for dir in dirs {
+            std::fs::create_dir_all(dir)?;
+        }
 
         // Find the highest existing WAL ID so we don't collide
-        let max_id = Self::find_max_wal_id(dir);
+        let max_id = dirs
+            .iter()
+            .chain(old_dirs)
+            .map(|dir| Self::find_max_wal_id(dir))
+            .max()
+            .unwrap_or(0);
         let next_id = max_id + 1;
 
         let active_path = dir.join(format!("{:06}.wal", next_id));
-        let active_writer = WALWriter::new(&active_path, sync_policy)?;
+        let metrics = Arc::new(SyncMetrics::default());
+        let shipper = Arc::new(Shipper::default());
+        let active_writer =
+            WALWriter::open_for_manager(&active_path, next_id, &options, &metrics, &shipper)?;
+        sync_dir(dir)?;
+        let syncer = match options.sync_policy {
+            SyncPolicy::EveryNMillis(millis) => Some(BackgroundSyncer::start(
+                Duration::from_millis(millis.max(1)),
+                active_writer.sync_target()?,
+            )),
+            _ => None,
+        };
 
         Ok(WALManager {
-            dir: dir.to_path_buf(),
+            dirs: dirs.to_vec(),
+            dir_index: 0,
             active_writer,
             active_path,
             next_wal_id: next_id + 1,
-            sync_policy,
+            options,
+            syncer,
+            recycled: VecDeque::new(),
+            size_rotated: Vec::new(),
+            wal_created: None,
+            sequence_logged: false,
+            unlogged_from: None,
+            sequence_block: None,
+            metrics,
+            shipper,
         })
     }
 
+    /// Append write number `sequence` to the active WAL, then switch to a
+    /// new WAL if the active one has reached WALOptions::max_wal_size.
+    ///
+    /// Each write must take the number after the last one appended: a
+    /// WAL records only the number of its first write, in a Sequence
+    /// record ahead of it. A WAL framed in blocks also gets one ahead of
+    /// the first record starting in each block, so reading resumed past
+    /// a damaged block still knows the numbers.
+    pub fn append(&mut self, sequence: u64, record: &WALRecord) -> Result<()> {
+        let block = self.active_writer.next_block();
+        if block != self.sequence_block {
+            self.sequence_logged = false;
+        }
+        if !self.sequence_logged {
+            let marker = match self.unlogged_from {
+                Some(from) => WALRecord::sequence_after_unlogged(sequence, from),
+                None => WALRecord::sequence(sequence),
+            };
+            self.active_writer.append(&marker)?;
+            self.sequence_logged = true;
+            self.sequence_block = block;
+            self.unlogged_from = None;
+        }
+        // Only built when someone's listening
+        let updates = if self.shipper.is_set() {
+            record
+                .writes()?
+                .into_iter()
+                .zip(sequence..)
+                .map(|(record, sequence)| WALUpdate { sequence, record })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        self.active_writer.append_shipping(record, updates)?;
+        if self.options.max_wal_size > 0 && self.active_writer.offset() >= self.options.max_wal_size
+        {
+            let old_path = self.switch_wal()?;
+            self.size_rotated.push(old_path);
+        }
+        Ok(())
+    }
+
+    /// Note that write `sequence` skipped the WAL. The next append()
+    /// numbers its write afresh and records that the ones in between
+    /// weren't logged, rather than lost.
+    pub fn skip(&mut self, sequence: u64) {
+        self.sequence_logged = false;
+        self.unlogged_from.get_or_insert(sequence);
+    }
+
+    /// Hand the active WAL's buffered writes to the OS, and fsync it if
+    /// `sync`.
+    pub fn flush(&mut self, sync: bool) -> Result<()> {
+        if sync {
+            self.active_writer.sync()
+        } else {
+            self.active_writer.flush()
+        }
+    }
+
+    /// Switch every WAL from now on, the active one included, to
+    /// `sync_policy` (see WALWriter::set_sync_policy), starting, stopping
+    /// or re-timing the background syncer to match.
+    pub fn set_sync_policy(&mut self, sync_policy: SyncPolicy) -> Result<()> {
+        self.active_writer.set_sync_policy(sync_policy)?;
+        self.options.sync_policy = sync_policy;
+        // The old timer, if any, is stopped first; it has nothing left
+        // to sync
+        self.syncer = None;
+        if let SyncPolicy::EveryNMillis(millis) = sync_policy {
+            self.syncer = Some(BackgroundSyncer::start(
+                Duration::from_millis(millis.max(1)),
+                self.active_writer.sync_target()?,
+            ));
+        }
+        Ok(())
+    }
+
+    /// The policy WALs are synced by.
+    pub fn sync_policy(&self) -> SyncPolicy {
+        self.options.sync_policy
+    }
+
+    /// WALs append() switched away from by size since the last call,
+    /// oldest first. They're older than the active WAL; delete them along
+    /// with the one the next rotate() returns.
+    pub fn take_size_rotated(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.size_rotated)
+    }
+
+    /// Hand over older WALs holding writes of the same memtable as the
+    /// active one (those replayed into it at open), oldest first, so
+    /// take_size_rotated() returns them to be deleted with it.
+    pub fn adopt_wals(&mut self, wals: Vec<PathBuf>) {
+        self.size_rotated.splice(0..0, wals);
+    }
+
+    /// Call `hook` with the number of every WAL switched to from now on,
+    /// after it's created and before it's written to. An error fails the
+    /// switch, leaving the old WAL active.
+    pub fn set_wal_created_hook(&mut self, hook: WALCreatedHook) {
+        self.wal_created = Some(hook);
+    }
+
+    /// Call `hook` with the writes appended from now on, in order, once
+    /// an fsync has made them durable: after an append with
+    /// SyncPolicy::EveryWrite, with the batch at each sync otherwise.
+    /// Commits deliver their batch's writes; records that apply nothing
+    /// aren't delivered. The hook runs on the syncing thread, often with
+    /// the WAL locked, so it must not write to the DB. None removes it.
+    pub fn set_wal_synced_hook(&mut self, hook: Option<WALSyncedHook>) {
+        *self.shipper.hook.write().unwrap() = hook;
+    }
+
     /// Rotate: sync current WAL, create a new one.
     /// Returns the path of the old WAL (caller deletes after SSTable flush).
     pub fn rotate(&mut self) -> Result<std::path::PathBuf> {
+        self.switch_wal()
+    }
+
+    /// Sync the active WAL and start the next one. Returns the old
+    /// active WAL's path.
+    fn switch_wal(&mut self) -> Result<PathBuf> {
         // Sync the current WAL before freezing it
         self.active_writer.sync()?;
 
         let old_path = self.active_path.clone();
 
-        // Create new WAL file
-        let new_path = self.dir.join(format!("{:06}.wal", self.next_wal_id));
-        let new_writer = WALWriter::new(&new_path, self.sync_policy)?;
+        // Create new WAL file in the next directory, or reuse a recycled
+        // one there: its new header is written before the rename, so
+        // under either name it reads as the new log, without the old
+        // records
+        let dir_index = (self.dir_index + 1) % self.dirs.len();
+        let dir = &self.dirs[dir_index];
+        let new_path = dir.join(format!("{:06}.wal", self.next_wal_id));
+        let recycled = self
+            .recycled
+            .iter()
+            .position(|path| path.parent() == Some(dir.as_path()))
+            .and_then(|i| self.recycled.remove(i));
+        let new_writer = match recycled {
+            Some(recycled) => {
+                let writer = WALWriter::open_for_manager(
+                    &recycled,
+                    self.next_wal_id,
+                    &self.options,
+                    &self.metrics,
+                    &self.shipper,
+                )?;
+                std::fs::rename(&recycled, &new_path)?;
+                writer
+            }
+            None => WALWriter::open_for_manager(
+                &new_path,
+                self.next_wal_id,
+                &self.options,
+                &self.metrics,
+                &self.shipper,
+            )?,
+        };
+        sync_dir(dir)?;
+        if let Some(hook) = &mut self.wal_created {
+            hook(self.next_wal_id)?;
+        }
+        if let Some(syncer) = &self.syncer {
+            syncer.set_target(new_writer.sync_target()?);
+        }
 
         self.active_writer = new_writer;
         self.active_path = new_path;
+        self.dir_index = dir_index;
         self.next_wal_id += 1;
+        self.sequence_logged = false;
 
         Ok(old_path)
     }
 
+    /// Keep a flushed WAL to be reused by a later rotation. Returns false,
+    /// leaving it to the caller to delete, if recycling is off or enough
+    /// WALs are already kept.
+    pub fn recycle_wal(&mut self, path: &Path) -> bool {
+        if self.recycled.len() >= self.options.recycle_log_file_num {
+            return false;
+        }
+        self.recycled.push_back(path.to_path_buf());
+        true
+    }
+
+    /// Flushed WALs kept for reuse, oldest first.
+    pub fn recycled_wals(&self) -> impl Iterator<Item = &Path> {
+        self.recycled.iter().map(PathBuf::as_path)
+    }
+
     /// Delete an old WAL file (safe only after SSTable is fsync'd).
     pub fn delete_wal(path: &Path) -> Result<()> {
         std::fs::remove_file(path)?;
-        Ok(())
+        sync_parent_dir(path)
     }
 
     /// Access the active WAL writer for appending records.
@@ -158,11 +702,22 @@ impl WALManager {
         &mut self.active_writer
     }
 
+    /// Directories WALs are created in, in turn.
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
     /// Path of the current active WAL file.
     pub fn active_path(&self) -> &Path {
         &self.active_path
     }
 
+    /// Counts and latencies of the fsyncs of every WAL this manager has
+    /// written, by what triggered them.
+    pub fn sync_stats(&self) -> WALSyncStats {
+        self.metrics.snapshot()
+    }
+
     /// ID of the current active WAL file.
     pub fn active_wal_id(&self) -> u64 {
         self.next_wal_id - 1
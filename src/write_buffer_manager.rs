@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Weak};
+
+/// A DB whose memtables a WriteBufferManager budgets.
+pub(crate) trait FlushTarget: Send + Sync {
+    /// Memory of the active memtable, in bytes.
+    fn active_memtable_size(&self) -> usize;
+
+    /// Freeze the active memtable and schedule its flush.
+    fn request_flush(&self);
+}
+
+/// Cap on the memory of all memtables of the DBs sharing it
+/// (Options::write_buffer_manager).
+///
+/// Each DB charges the manager for what its memtables take as writes
+/// land, and is credited once they're flushed. Past the budget, the next
+/// write to any of the DBs has the largest active memtable among them
+/// frozen and flushed, wherever it is, rather than waiting for some
+/// memtable to reach its own size limit. Like RocksDB, that happens once
+/// active memtables take 7/8 of the budget, or all memtables the whole
+/// budget and active ones half of it: memory already waiting to be
+/// flushed isn't freed any sooner by freezing more.
+pub struct WriteBufferManager {
+    buffer_size: usize,
+    /// Bytes of every memtable charged, active or waiting for flush.
+    memory_usage: AtomicUsize,
+    /// Bytes of active memtables charged.
+    mutable_usage: AtomicUsize,
+    /// DBs sharing the manager; dropped ones are pruned as found.
+    targets: Mutex<Vec<Weak<dyn FlushTarget>>>,
+}
+
+impl WriteBufferManager {
+    /// Budget `buffer_size` bytes for the memtables of every DB opened
+    /// with this manager.
+    pub fn new(buffer_size: usize) -> Self {
+        WriteBufferManager {
+            buffer_size: buffer_size.max(1),
+            memory_usage: AtomicUsize::new(0),
+            mutable_usage: AtomicUsize::new(0),
+            targets: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The budget in bytes.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Bytes taken by the memtables of the DBs sharing the manager.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage.load(Ordering::Relaxed)
+    }
+
+    /// Bytes taken by their active memtables.
+    pub fn mutable_memtable_memory_usage(&self) -> usize {
+        self.mutable_usage.load(Ordering::Relaxed)
+    }
+
+    /// Whether memory is over budget, so a memtable should be flushed.
+    pub fn should_flush(&self) -> bool {
+        let mutable = self.mutable_memtable_memory_usage();
+        mutable > self.buffer_size / 8 * 7
+            || (self.memory_usage() >= self.buffer_size && mutable >= self.buffer_size / 2)
+    }
+
+    pub(crate) fn register(&self, target: Weak<dyn FlushTarget>) {
+        self.targets.lock().unwrap().push(target);
+    }
+
+    /// Charge `bytes` written to an active memtable.
+    pub(crate) fn reserve(&self, bytes: usize) {
+        self.memory_usage.fetch_add(bytes, Ordering::Relaxed);
+        self.mutable_usage.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Note that an active memtable of `bytes` was frozen.
+    pub(crate) fn schedule_free(&self, bytes: usize) {
+        self.mutable_usage.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    /// Credit `bytes` of frozen memtables flushed or dropped.
+    pub(crate) fn free(&self, bytes: usize) {
+        self.memory_usage.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    /// Have the largest active memtable of the DBs sharing the manager
+    /// frozen and flushed.
+    pub(crate) fn flush_largest(&self) {
+        let largest = {
+            let mut targets = self.targets.lock().unwrap();
+            targets.retain(|target| target.strong_count() > 0);
+            targets
+                .iter()
+                .filter_map(Weak::upgrade)
+                .map(|target| (target.active_memtable_size(), target))
+                .filter(|(size, _)| *size > 0)
+                .max_by_key(|(size, _)| *size)
+        };
+        // Called without the lock: the flush takes the target's own
+        if let Some((_, target)) = largest {
+            target.request_flush();
+        }
+    }
+}
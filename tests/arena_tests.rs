@@ -0,0 +1,79 @@
+// Arena tests
+//
+// Skip list keys, values and towers are carved out of a bump Arena, and
+// SkipList::size_bytes() is the arena's exact count of what it handed out.
+
+use lsm_engine::memtable::arena::{ARENA_CHUNK_SIZE, Arena};
+use lsm_engine::memtable::skiplist::SkipList;
+
+const LINK: usize = std::mem::size_of::<Option<usize>>();
+
+// =============================================================================
+// Test 1: Small allocations are packed into one chunk
+// =============================================================================
+#[test]
+fn small_allocations_share_chunk() {
+    let mut arena = Arena::new();
+    let a = arena.alloc(b"hello");
+    let b = arena.alloc(b"world!");
+    assert_eq!(&a[..], b"hello");
+    assert_eq!(&b[..], b"world!");
+    // Back to back in the same buffer
+    assert_eq!(a.as_ptr() as usize + a.len(), b.as_ptr() as usize);
+    assert_eq!(arena.allocated(), 11);
+    assert!(arena.alloc(b"").is_empty());
+    assert_eq!(arena.allocated(), 11);
+}
+
+// =============================================================================
+// Test 2: Large allocations get their own buffer; a full chunk moves on
+// =============================================================================
+#[test]
+fn large_and_overflowing_allocations() {
+    let mut arena = Arena::new();
+    let small = arena.alloc(b"x");
+    let big = arena.alloc(&vec![7u8; ARENA_CHUNK_SIZE]);
+    let next = arena.alloc(b"y");
+    assert_eq!(big.len(), ARENA_CHUNK_SIZE);
+    assert_eq!(small.as_ptr() as usize + 1, next.as_ptr() as usize);
+
+    let piece = vec![1u8; ARENA_CHUNK_SIZE / 4 - 1];
+    let mut last = next;
+    for _ in 0..8 {
+        let alloc = arena.alloc(&piece);
+        assert_eq!(&alloc[..], &piece[..]);
+        last = alloc;
+    }
+    assert_eq!(last.len(), piece.len());
+    assert_eq!(arena.allocated(), 2 + ARENA_CHUNK_SIZE + 8 * piece.len());
+}
+
+// =============================================================================
+// Test 3: size_bytes() counts keys, values and towers exactly
+// =============================================================================
+#[test]
+fn size_bytes_is_exact() {
+    let mut sl = SkipList::new();
+    sl.insert(b"key".to_vec(), b"value".to_vec());
+    let towers = sl.size_bytes() - 8;
+    assert!(towers >= LINK && towers.is_multiple_of(LINK));
+
+    // An overwrite adds exactly the new value
+    sl.insert(b"key".to_vec(), b"v2".to_vec());
+    assert_eq!(sl.size_bytes(), 8 + towers + 2);
+    assert_eq!(sl.get(b"key"), Some(&b"v2"[..]));
+}
+
+// =============================================================================
+// Test 4: Pinned values outlive the skip list that allocated them
+// =============================================================================
+#[test]
+fn pinned_values_outlive_list() {
+    let mut sl = SkipList::new();
+    for i in 0..1000u32 {
+        sl.insert(i.to_be_bytes().to_vec(), format!("value{i}").into_bytes());
+    }
+    let pinned = sl.get_pinned(&500u32.to_be_bytes()).unwrap();
+    drop(sl);
+    assert_eq!(&pinned[..], b"value500");
+}
@@ -0,0 +1,159 @@
+// Atomic flush tests
+//
+// With Options::max_write_buffer_number above 2, full memtables queue up
+// while a flush runs. The next flush writes all of them and commits the
+// SSTables, any blob file and the new log number in a single MANIFEST
+// record, so recovery never sees part of the set.
+
+use std::fs::OpenOptions;
+use std::sync::Arc;
+
+use lsm_engine::blob::BlobFileMeta;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::manifest::Manifest;
+use lsm_engine::rate_limiter::RateLimiter;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn value(i: u32, round: u32) -> Vec<u8> {
+    format!("value_{i}_{round}_{}", "x".repeat(80)).into_bytes()
+}
+
+fn make_sst(id: u64, min_key: &[u8], max_key: &[u8]) -> SSTableMeta {
+    SSTableMeta {
+        id,
+        level: 0,
+        min_key: min_key.to_vec(),
+        max_key: max_key.to_vec(),
+        file_size: 100,
+        entry_count: 10,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
+    }
+}
+
+/// Memtables of 4KB whose flushes are slowed down, so writes freeze
+/// several while one is being flushed.
+fn options(max_write_buffer_number: usize) -> Options {
+    Options {
+        memtable_size: 4096,
+        max_write_buffer_number,
+        disable_auto_compactions: true,
+        rate_limiter: Some(Arc::new(RateLimiter::new(32 * 1024).with_burst(0))),
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: One flush writes every queued memtable, newest value winning
+// =============================================================================
+#[test]
+fn queued_memtables_flushed_together() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(4)).unwrap();
+    // Each round overwrites the same 40 keys, about one memtable's worth
+    for round in 0..12 {
+        for i in 0..40 {
+            db.put(&key(i), &value(i, round)).unwrap();
+        }
+    }
+    db.flush().unwrap();
+
+    // A flush of several memtables merges their overwrites into one
+    // table; flushed one by one they'd give more than a table per round
+    let files = db.live_files_metadata();
+    assert!(files.len() < 12, "{} files", files.len());
+    assert!(files.iter().all(|f| f.entry_count <= 40));
+    for i in 0..40 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i, 11)));
+    }
+    drop(db);
+
+    let db = DB::open(dir.path(), options(4)).unwrap();
+    for i in 0..40 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i, 11)));
+    }
+}
+
+// =============================================================================
+// Test 2: Reads see every queued memtable while they wait
+// =============================================================================
+#[test]
+fn queued_memtables_readable() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(4)).unwrap();
+    for i in 0..200 {
+        db.put(&key(i), &value(i, 0)).unwrap();
+    }
+    for i in 0..200 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i, 0)), "key {i}");
+    }
+    let mut iter = db.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 200);
+    drop(iter);
+    drop(db);
+
+    // Whatever was still queued at close comes back from the WALs
+    let db = DB::open(dir.path(), options(4)).unwrap();
+    for i in 0..200 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i, 0)), "key {i}");
+    }
+}
+
+// =============================================================================
+// Test 3: The MANIFEST replays a flush whole or not at all
+// =============================================================================
+#[test]
+fn manifest_flush_record_all_or_nothing() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("MANIFEST");
+    let blob = BlobFileMeta {
+        file_number: 3,
+        record_count: 5,
+        value_bytes: 500,
+        file_size: 600,
+    };
+    {
+        let mut manifest = Manifest::open(&path).unwrap();
+        manifest.record_flush(make_sst(1, b"a", b"c")).unwrap();
+        manifest.record_log_number(4).unwrap();
+        manifest
+            .record_atomic_flush(
+                vec![blob.clone()],
+                vec![make_sst(2, b"d", b"f"), make_sst(5, b"g", b"i")],
+                9,
+            )
+            .unwrap();
+    }
+
+    let manifest = Manifest::open(&path).unwrap();
+    assert_eq!(manifest.current_version().level(0).len(), 3);
+    assert_eq!(manifest.log_number(), 9);
+    assert_eq!(manifest.next_sst_id(), 6);
+    assert_eq!(manifest.blob_files().get(&3), Some(&blob));
+    drop(manifest);
+
+    // Lose the tail of the flush record: none of it applies
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    let len = file.metadata().unwrap().len();
+    file.set_len(len - 6).unwrap();
+    drop(file);
+
+    let manifest = Manifest::open(&path).unwrap();
+    let level0 = manifest.current_version().level(0);
+    assert_eq!(level0.iter().map(|m| m.id).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(manifest.log_number(), 4);
+    assert!(manifest.blob_files().is_empty());
+}
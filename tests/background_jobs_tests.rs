@@ -0,0 +1,207 @@
+// Background job tests
+//
+// A full memtable is frozen and flushed by a pool of
+// Options::max_background_jobs worker threads, and every flush queues a
+// compaction round. Flushes run before compactions. Reads see every
+// acknowledged write while jobs move data from memtables to SSTables,
+// and closing the DB with jobs queued loses nothing.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn value(i: u32) -> Vec<u8> {
+    format!("value_{i}_").repeat(5).into_bytes()
+}
+
+fn small_memtable() -> Options {
+    Options {
+        memtable_size: 4 * 1024,
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: Writes past the memtable size are flushed without flush()
+// =============================================================================
+#[test]
+fn full_memtable_flushes_in_background() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            disable_auto_compactions: true,
+            ..small_memtable()
+        },
+    )
+    .unwrap();
+    for i in 0..2000 {
+        db.put(&key(i), &value(i)).unwrap();
+    }
+    db.wait_for_background_jobs().unwrap();
+
+    let l0 = db
+        .live_files_metadata()
+        .iter()
+        .filter(|meta| meta.level == 0)
+        .count();
+    assert!(l0 > 10, "expected many L0 tables, got {l0}");
+    for i in 0..2000 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i)), "key {i}");
+    }
+}
+
+// =============================================================================
+// Test 2: Flushes queue compactions that keep L0 under its trigger
+// =============================================================================
+#[test]
+fn flushes_trigger_background_compaction() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_style: CompactionStyle::Leveled,
+            level0_file_num_compaction_trigger: 2,
+            max_background_jobs: 4,
+            ..small_memtable()
+        },
+    )
+    .unwrap();
+    for i in 0..2000 {
+        db.put(&key(i % 500), &value(i)).unwrap();
+    }
+    db.flush().unwrap();
+    db.wait_for_background_jobs().unwrap();
+
+    let files = db.live_files_metadata();
+    assert!(files.iter().filter(|meta| meta.level == 0).count() < 2);
+    assert!(files.iter().any(|meta| meta.level > 0), "{files:?}");
+    assert!(db.stats().compaction_count > 0);
+    for i in 1500..2000 {
+        assert_eq!(db.get(&key(i % 500)).unwrap(), Some(value(i)));
+    }
+}
+
+// =============================================================================
+// Test 3: Reads see every acknowledged write while jobs run
+// =============================================================================
+#[test]
+fn reads_see_writes_during_background_work() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            level0_file_num_compaction_trigger: 2,
+            ..small_memtable()
+        },
+    )
+    .unwrap();
+    let written = AtomicU32::new(0);
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..3000 {
+                db.put(&key(i), &value(i)).unwrap();
+                written.store(i + 1, Ordering::SeqCst);
+            }
+        });
+        while written.load(Ordering::SeqCst) < 3000 {
+            let n = written.load(Ordering::SeqCst);
+            if n == 0 {
+                continue;
+            }
+            assert_eq!(db.get(&key(n - 1)).unwrap(), Some(value(n - 1)));
+            assert_eq!(db.get(&key(n / 2)).unwrap(), Some(value(n / 2)));
+
+            let mut iter = db.iter().unwrap();
+            let mut count = 0;
+            while iter.is_valid() {
+                count += 1;
+                iter.next().unwrap();
+            }
+            assert!(count >= n, "scan saw {count} of {n} keys");
+        }
+    });
+    db.wait_for_background_jobs().unwrap();
+}
+
+// =============================================================================
+// Test 4: Closing with jobs queued loses nothing
+// =============================================================================
+#[test]
+fn close_with_queued_jobs_then_reopen() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(
+            dir.path(),
+            Options {
+                max_background_jobs: 1,
+                ..small_memtable()
+            },
+        )
+        .unwrap();
+        for i in 0..2000 {
+            db.put(&key(i), &value(i)).unwrap();
+        }
+        db.close().unwrap();
+    }
+    {
+        // Dropped without close(): unflushed writes come back from the WAL
+        let db = DB::open(dir.path(), small_memtable()).unwrap();
+        for i in 2000..2500 {
+            db.put(&key(i), &value(i)).unwrap();
+        }
+    }
+
+    let db = DB::open(dir.path(), small_memtable()).unwrap();
+    for i in 0..2500 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i)), "key {i}");
+    }
+}
+
+// =============================================================================
+// Test 5: The automatic flush runs end to end
+// =============================================================================
+// A put that finds the memtable full freezes it and rotates the WAL; the
+// background flush writes an SSTable, records it in the manifest, deletes
+// the old WAL and retires the frozen memtable.
+#[test]
+fn automatic_flush_pipeline() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        disable_auto_compactions: true,
+        ..small_memtable()
+    };
+    let wal_files = || {
+        std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension() == Some("wal".as_ref()))
+            .count()
+    };
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    let mut i = 0;
+    while db.live_files_metadata().is_empty() {
+        db.put(&key(i), &value(i)).unwrap();
+        i += 1;
+        db.wait_for_background_jobs().unwrap();
+    }
+    // Only the WAL of the new active memtable is left
+    assert_eq!(wal_files(), 1);
+    assert!(db.stats().memtable_size < 4 * 1024);
+    let tables = db.live_files_metadata();
+    drop(db);
+
+    // The manifest lists the flushed table: reopening finds it
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.live_files_metadata().len(), tables.len());
+    for j in 0..i {
+        assert_eq!(db.get(&key(j)).unwrap(), Some(value(j)), "key {j}");
+    }
+}
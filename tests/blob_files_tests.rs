@@ -0,0 +1,198 @@
+// Blob file (key-value separation) tests
+//
+// With Options::enable_blob_files, flush moves values of at least
+// min_blob_size bytes into append-only blob files and SSTables store a
+// BlobIndex instead. Compaction copies only the pointers, and every read
+// path resolves them back to the value.
+
+use std::fs::OpenOptions;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use lsm_engine::blob::writer::BlobFileWriter;
+use lsm_engine::blob::{BLOB_INDEX_MAGIC, BlobIndex, blob_file_path, reader::read_blob_value};
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{CompactionStyle, DB, Error, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:04}", i).into_bytes()
+}
+
+/// 100KB for even keys, 10 bytes for odd ones.
+fn value(i: u32) -> Vec<u8> {
+    let len = if i.is_multiple_of(2) { 100 * 1024 } else { 10 };
+    (0..len).map(|j| (i as usize * 31 + j) as u8).collect()
+}
+
+fn blob_options() -> Options {
+    Options {
+        enable_blob_files: true,
+        min_blob_size: 1024,
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    }
+}
+
+fn files_with_extension(dir: &Path, ext: &str) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == ext))
+        .collect();
+    files.sort();
+    files
+}
+
+// =============================================================================
+// Test 1: Blob records round-trip and are checksummed
+// =============================================================================
+#[test]
+fn blob_file_round_trip() {
+    let dir = tempdir().unwrap();
+    let mut writer = BlobFileWriter::create(dir.path(), 3).unwrap();
+    let first = writer.add(b"a", b"first value").unwrap();
+    let second = writer.add(b"b", &value(0)).unwrap();
+    assert_eq!(writer.num_records(), 2);
+    writer.finish().unwrap();
+
+    assert_eq!(first.file_number, 3);
+    assert_eq!(BlobIndex::decode(&second.encode()).unwrap(), second);
+    assert!(BlobIndex::decode(b"short").is_err());
+    assert_eq!(read_blob_value(dir.path(), &first).unwrap(), b"first value");
+    assert_eq!(read_blob_value(dir.path(), &second).unwrap(), value(0));
+
+    // Flip a byte inside the second value
+    let file = OpenOptions::new()
+        .write(true)
+        .open(blob_file_path(dir.path(), 3))
+        .unwrap();
+    file.write_all_at(b"\x00", second.offset + 100).unwrap();
+    let err = read_blob_value(dir.path(), &second).unwrap_err();
+    assert!(matches!(err, Error::Corruption(_)), "{err:?}");
+    assert_eq!(read_blob_value(dir.path(), &first).unwrap(), b"first value");
+}
+
+// =============================================================================
+// Test 2: Large values go to blob files and every read path resolves them
+// =============================================================================
+#[test]
+fn large_values_are_separated() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    for i in 0..20 {
+        db.put(&key(i), &value(i)).unwrap();
+    }
+    db.flush().unwrap();
+
+    let blobs = files_with_extension(dir.path(), "blob");
+    assert_eq!(blobs.len(), 1);
+    let ssts = files_with_extension(dir.path(), "sst");
+    assert!(std::fs::metadata(&ssts[0]).unwrap().len() < 10 * 1024);
+
+    for i in 0..20 {
+        assert_eq!(db.get(&key(i)).unwrap().unwrap(), value(i), "key {i}");
+    }
+
+    let mut iter = db.iter().unwrap();
+    let mut i = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.value(), value(i).as_slice());
+        iter.next().unwrap();
+        i += 1;
+    }
+    assert_eq!(i, 20);
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.value(), value(19).as_slice());
+    iter.prev().unwrap();
+    assert_eq!(iter.value(), value(18).as_slice());
+
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get(&key(4)).unwrap().unwrap(), value(4));
+    let scanner = db.scan(&key(2), &key(3)).unwrap();
+    assert_eq!(scanner.value(), value(2).as_slice());
+    let raw: Vec<_> = db.raw_iter().unwrap().collect();
+    assert_eq!(raw[6].1, value(6));
+
+    // A newer memtable value shadows the separated one
+    db.put(&key(0), b"small").unwrap();
+    assert_eq!(db.get(&key(0)).unwrap().unwrap(), b"small");
+    let iter = db.iter().unwrap();
+    assert_eq!(iter.value(), b"small");
+}
+
+// =============================================================================
+// Test 3: Compaction copies pointers, not values
+// =============================================================================
+#[test]
+fn compaction_keeps_values_in_place() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    for round in 0..3 {
+        for i in round * 10..(round + 1) * 10 {
+            db.put(&key(i), &value(i)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    assert_eq!(files_with_extension(dir.path(), "blob").len(), 3);
+    let written_before = db.stats().write_amplification;
+
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.stats().num_sstables_per_level[1], 1);
+    let ssts = files_with_extension(dir.path(), "sst");
+    assert_eq!(ssts.len(), 1);
+    assert!(std::fs::metadata(&ssts[0]).unwrap().len() < 10 * 1024);
+    assert_eq!(db.stats().write_amplification, written_before);
+    for i in 0..30 {
+        assert_eq!(db.get(&key(i)).unwrap().unwrap(), value(i));
+    }
+}
+
+// =============================================================================
+// Test 4: Blob files outlive a reopen and aren't overwritten after it
+// =============================================================================
+#[test]
+fn reopen_resolves_and_numbers_new_files() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), blob_options()).unwrap();
+        db.put(&key(0), &value(0)).unwrap();
+        db.flush().unwrap();
+    }
+
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    assert_eq!(db.get(&key(0)).unwrap().unwrap(), value(0));
+    db.put(&key(2), &value(2)).unwrap();
+    db.flush().unwrap();
+    assert_eq!(files_with_extension(dir.path(), "blob").len(), 2);
+    assert_eq!(db.get(&key(0)).unwrap().unwrap(), value(0));
+    assert_eq!(db.get(&key(2)).unwrap().unwrap(), value(2));
+
+    // Turning separation off leaves existing pointers readable
+    drop(db);
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert_eq!(db.get(&key(2)).unwrap().unwrap(), value(2));
+}
+
+// =============================================================================
+// Test 5: Values that look like blob pointers are stored as values
+// =============================================================================
+#[test]
+fn pointer_lookalikes_round_trip() {
+    let dir = tempdir().unwrap();
+    // Separation off: only the lookalike is moved to a blob file
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    let mut lookalike = BLOB_INDEX_MAGIC.to_vec();
+    lookalike.extend_from_slice(&[7u8; 24]);
+    db.put(b"lookalike", &lookalike).unwrap();
+    db.put(b"plain", &value(0)).unwrap();
+    db.flush().unwrap();
+
+    assert_eq!(files_with_extension(dir.path(), "blob").len(), 1);
+    assert_eq!(db.get(b"lookalike").unwrap().unwrap(), lookalike);
+    assert_eq!(db.get(b"plain").unwrap().unwrap(), value(0));
+    let iter = db.iter().unwrap();
+    assert_eq!(iter.key(), b"lookalike");
+    assert_eq!(iter.value(), lookalike.as_slice());
+}
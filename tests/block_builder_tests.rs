@@ -11,8 +11,8 @@ fn build_empty_block() {
     let builder = BlockBuilder::new(4096);
     assert!(builder.is_empty());
     let block = builder.build();
-    // Empty block: just the num_entries (2 bytes) = 0
-    assert_eq!(block.len(), 2);
+    // Empty block: just the num_entries (4 bytes) = 0, + compression type (1 byte)
+    assert_eq!(block.len(), 5);
 }
 
 // =============================================================================
@@ -25,8 +25,9 @@ fn add_one_entry_and_build() {
     assert!(!builder.is_empty());
 
     let block = builder.build();
-    // Should contain: entry (2+2+4+6=14 bytes) + offset (2 bytes) + count (2 bytes) = 18
-    assert_eq!(block.len(), 18);
+    // Should contain: entry (1+1+4+6=12 bytes, varint lengths) + offset (4 bytes)
+    // + count (4 bytes) + compression type (1 byte) = 21
+    assert_eq!(block.len(), 21);
 }
 
 // =============================================================================
@@ -40,9 +41,9 @@ fn add_sorted_entries() {
     assert!(builder.add(b"ccc", b"val_c"));
 
     let block = builder.build();
-    // 3 entries + 3 offsets (6 bytes) + count (2 bytes)
-    // Each entry: 2 + 2 + 3 + 5 = 12 bytes → 36 + 6 + 2 = 44
-    assert_eq!(block.len(), 44);
+    // 3 entries + 3 offsets (12 bytes) + count (4 bytes)
+    // Each entry: 1 + 1 + 3 + 5 = 10 bytes → 30 + 12 + 4 = 46, + type byte = 47
+    assert_eq!(block.len(), 47);
 }
 
 // =============================================================================
@@ -52,7 +53,7 @@ fn add_sorted_entries() {
 fn block_full_returns_false() {
     // Tiny block size: only fits a small entry
     let mut builder = BlockBuilder::new(32);
-    // First entry should fit (2+2+1+1 = 6 bytes data + 2 offset + 2 count = 10)
+    // First entry should fit (1+1+1+1 = 4 bytes data + 4 offset + 4 count = 12)
     assert!(builder.add(b"a", b"b"));
 
     // Second entry would push past 32 bytes
@@ -5,6 +5,11 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 
 use lsm_engine::cache::BlockCache;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
 
 // =============================================================================
 // Test 1: Cache miss returns None
@@ -262,3 +267,67 @@ fn concurrent_reads_no_data_races() {
         assert_eq!(*block, vec![i as u8; 100], "block data should be intact");
     }
 }
+
+// =============================================================================
+// Test 11: SSTable gets and scans read each block from disk only once
+// =============================================================================
+#[test]
+fn sstable_reads_go_through_cache() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 256).unwrap();
+    for i in 0..500u32 {
+        builder
+            .add(format!("key_{:04}", i).as_bytes(), b"value")
+            .unwrap();
+    }
+    builder.finish().unwrap();
+
+    let cache = Arc::new(Mutex::new(BlockCache::new(1024 * 1024)));
+    let sst = SSTable::open(&path)
+        .unwrap()
+        .with_block_cache(Arc::clone(&cache));
+
+    // Hot key: the first get reads its block, the rest hit the cache
+    for _ in 0..10 {
+        assert_eq!(sst.get(b"key_0250").unwrap().unwrap(), b"value".as_slice());
+    }
+    assert_eq!(sst.data_block_reads(), 1);
+
+    // A full scan reads every other block once; a second scan reads none
+    for _ in 0..2 {
+        let mut iter = sst.iter().unwrap();
+        while iter.is_valid() {
+            iter.next().unwrap();
+        }
+    }
+    assert_eq!(sst.data_block_reads(), sst.num_blocks() as u64);
+    assert!(cache.lock().unwrap().hit_rate() > 0.5);
+}
+
+// =============================================================================
+// Test 12: Snapshot reads and DB scans go through the DB's block cache
+// =============================================================================
+#[test]
+fn snapshot_and_scan_reads_hit_cache() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for i in 0..100u32 {
+        db.put(format!("key_{:04}", i).as_bytes(), b"value")
+            .unwrap();
+    }
+    db.flush().unwrap();
+
+    let snapshot = db.snapshot();
+    for _ in 0..10 {
+        assert_eq!(snapshot.get(b"key_0050").unwrap().unwrap(), b"value");
+        let mut scanner = snapshot.scan(b"key_0010", b"key_0020").unwrap();
+        while scanner.is_valid() {
+            scanner.next().unwrap();
+        }
+        let scanner = db.scan(b"key_0030", b"key_0040").unwrap();
+        assert!(scanner.is_valid());
+    }
+    // Only the first reads of the table's blocks miss
+    assert!(db.stats().block_cache_hit_rate > 0.8);
+}
@@ -1,8 +1,11 @@
 // M18: Bloom + SSTable Integration tests
 // Verify bloom filter is built during SSTable creation and checked during reads.
 
-use lsm_engine::sstable::builder::SSTableBuilder;
+use bytes::Bytes;
+use lsm_engine::sstable::builder::{SSTableBuilder, TableOptions};
+use lsm_engine::sstable::footer::Footer;
 use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
 use tempfile::tempdir;
 
 // =============================================================================
@@ -130,9 +133,18 @@ fn bloom_survives_reopen() {
 
     // Reopen — bloom must be deserialized correctly from disk
     let sstable = SSTable::open(&path).unwrap();
-    assert_eq!(sstable.get(b"alpha").unwrap(), Some(b"one".to_vec()));
-    assert_eq!(sstable.get(b"beta").unwrap(), Some(b"two".to_vec()));
-    assert_eq!(sstable.get(b"gamma").unwrap(), Some(b"three".to_vec()));
+    assert_eq!(
+        sstable.get(b"alpha").unwrap(),
+        Some(Bytes::from_static(b"one"))
+    );
+    assert_eq!(
+        sstable.get(b"beta").unwrap(),
+        Some(Bytes::from_static(b"two"))
+    );
+    assert_eq!(
+        sstable.get(b"gamma").unwrap(),
+        Some(Bytes::from_static(b"three"))
+    );
     assert_eq!(sstable.get(b"delta").unwrap(), None);
 }
 
@@ -141,7 +153,6 @@ fn bloom_survives_reopen() {
 // =============================================================================
 #[test]
 fn footer_has_bloom_block_info() {
-    use lsm_engine::sstable::footer::Footer;
     use std::io::{Read, Seek, SeekFrom};
 
     let dir = tempdir().unwrap();
@@ -172,3 +183,96 @@ fn footer_has_bloom_block_info() {
     assert!(footer.bloom_block_offset >= footer.meta_block_offset + footer.meta_block_size);
     assert!(footer.bloom_block_offset + footer.bloom_block_size <= footer.index_block_offset);
 }
+
+fn bloom_block_size(path: &std::path::Path) -> u64 {
+    let data = std::fs::read(path).unwrap();
+    Footer::decode(&data[data.len() - Footer::SIZE..])
+        .unwrap()
+        .bloom_block_size
+}
+
+// =============================================================================
+// Test 6: Filters sized from the real key count keep a low FPR on big tables
+// =============================================================================
+#[test]
+fn bloom_sized_for_large_table() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.sst");
+
+    let mut builder =
+        SSTableBuilder::with_options(&path, 1, 20_000, &TableOptions::default()).unwrap();
+    for i in 0..20_000u32 {
+        builder
+            .add(format!("key_{:06}", i).as_bytes(), b"v")
+            .unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sstable = SSTable::open(&path).unwrap();
+    let false_positives = (0..10_000u32)
+        .filter(|i| {
+            sstable
+                .may_contain(format!("key_{:06}x", i).as_bytes())
+                .unwrap()
+        })
+        .count();
+    assert!(
+        false_positives < 300,
+        "{false_positives} false positives out of 10000"
+    );
+}
+
+// =============================================================================
+// Test 7: bloom_bits_per_key scales the filter
+// =============================================================================
+#[test]
+fn bloom_bits_per_key_controls_size() {
+    let dir = tempdir().unwrap();
+    let mut sizes = Vec::new();
+    for bits in [10, 20] {
+        let path = dir.path().join(format!("{bits}.sst"));
+        let opts = TableOptions {
+            bloom_bits_per_key: bits,
+            ..TableOptions::default()
+        };
+        let mut builder = SSTableBuilder::with_options(&path, 1, 5000, &opts).unwrap();
+        for i in 0..5000u32 {
+            builder
+                .add(format!("key_{:06}", i).as_bytes(), b"v")
+                .unwrap();
+        }
+        builder.finish().unwrap();
+        sizes.push(bloom_block_size(&path));
+    }
+    let ratio = sizes[1] as f64 / sizes[0] as f64;
+    assert!((1.8..=2.2).contains(&ratio), "sizes {sizes:?}");
+}
+
+// =============================================================================
+// Test 8: DB::get misses are answered by bloom filters and show in stats
+// =============================================================================
+#[test]
+fn db_get_misses_use_bloom() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for i in 0..5000u32 {
+        db.put(format!("key_{:06}", i * 2).as_bytes(), b"v")
+            .unwrap();
+    }
+    db.flush().unwrap();
+
+    // Odd keys fall inside the table's key range but were never written
+    for i in 0..1000u32 {
+        assert!(
+            db.get(format!("key_{:06}", i * 2 + 1).as_bytes())
+                .unwrap()
+                .is_none()
+        );
+    }
+    let hit_rate = db.stats().bloom_filter_hit_rate;
+    assert!(hit_rate > 0.95, "bloom hit rate {hit_rate}");
+
+    // Keys outside every table's range never reach a filter
+    assert!(db.get(b"zzz").unwrap().is_none());
+    assert_eq!(db.stats().bloom_filter_hit_rate, hit_rate);
+}
@@ -0,0 +1,163 @@
+// Compaction cancellation tests
+//
+// A running compaction checks a CancellationToken between entries.
+// DB::pause_background_work() and DB::close() cancel it instead of waiting
+// for it to finish: the tables it wrote are deleted, its inputs stay live,
+// and compaction picks them up again on resume or reopen.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use lsm_engine::compaction::filter::{CompactionFilter, FilterDecision};
+use lsm_engine::error::Error;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// Sleeps 1ms per entry while `slow` is set, so a compaction of a few
+/// thousand entries takes seconds.
+struct SlowFilter {
+    calls: AtomicUsize,
+    slow: AtomicBool,
+}
+
+impl CompactionFilter for SlowFilter {
+    fn filter(&self, _level: u32, _key: &[u8], _value: &[u8]) -> FilterDecision {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.slow.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        FilterDecision::Keep
+    }
+}
+
+fn slow_filter() -> Arc<SlowFilter> {
+    Arc::new(SlowFilter {
+        calls: AtomicUsize::new(0),
+        slow: AtomicBool::new(true),
+    })
+}
+
+fn options(filter: &Arc<SlowFilter>) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        level0_file_num_compaction_trigger: 2,
+        target_file_size_base: 1024,
+        max_subcompactions: 2,
+        compaction_filter: Some(Arc::clone(filter) as Arc<dyn CompactionFilter>),
+        ..Options::default()
+    }
+}
+
+/// Flush 1000 keys twice, queueing a compaction, and wait
+/// until it's well under way.
+fn start_compaction(db: &DB, filter: &SlowFilter) {
+    for file in 0..2 {
+        for i in file * 1000..(file + 1) * 1000 {
+            db.put(&key(i), format!("value_{i}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    while filter.calls.load(Ordering::SeqCst) < 200 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn assert_contents(db: &DB) {
+    for i in (0..2000).step_by(7) {
+        assert_eq!(
+            db.get(&key(i)).unwrap(),
+            Some(format!("value_{i}").into_bytes())
+        );
+    }
+}
+
+/// The SSTable files in `dir` are exactly the live ones.
+fn assert_no_stray_tables(db: &DB, dir: &Path) {
+    let on_disk: HashSet<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".sst"))
+        .collect();
+    let live: HashSet<String> = db
+        .live_files_metadata()
+        .iter()
+        .map(|meta| format!("{:06}.sst", meta.id))
+        .collect();
+    assert_eq!(on_disk, live);
+}
+
+// =============================================================================
+// Test 1: Pausing cancels the running compaction; continuing redoes it
+// =============================================================================
+#[test]
+fn pause_cancels_and_continue_resumes() {
+    let dir = tempdir().unwrap();
+    let filter = slow_filter();
+    let db = DB::open(dir.path(), options(&filter)).unwrap();
+    start_compaction(&db, &filter);
+
+    let start = Instant::now();
+    db.pause_background_work();
+    assert!(start.elapsed() < Duration::from_millis(500));
+    let files = db.live_files_metadata();
+    assert!(files.iter().all(|f| f.level == 0));
+    assert_no_stray_tables(&db, dir.path());
+    assert_contents(&db);
+
+    // Nothing compacts while paused; writes and flushes go on
+    match db.compact_range(None, None) {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Interrupted),
+        other => panic!("expected an interrupted compaction, got {other:?}"),
+    }
+    db.put(b"paused", b"value").unwrap();
+    db.flush().unwrap();
+    db.wait_for_background_jobs().unwrap();
+    assert!(db.live_files_metadata().len() > files.len());
+
+    filter.slow.store(false, Ordering::SeqCst);
+    db.continue_background_work();
+    db.wait_for_background_jobs().unwrap();
+    assert!(db.live_files_metadata().iter().all(|f| f.level > 0));
+    assert_no_stray_tables(&db, dir.path());
+    assert_contents(&db);
+    assert_eq!(db.get(b"paused").unwrap(), Some(b"value".to_vec()));
+}
+
+// =============================================================================
+// Test 2: Closing doesn't wait for the compaction; reopen finds the inputs
+// =============================================================================
+#[test]
+fn close_cancels_running_compaction() {
+    let dir = tempdir().unwrap();
+    let filter = slow_filter();
+    let db = DB::open(dir.path(), options(&filter)).unwrap();
+    start_compaction(&db, &filter);
+
+    let start = Instant::now();
+    db.close().unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500));
+
+    filter.slow.store(false, Ordering::SeqCst);
+    let db = DB::open(dir.path(), options(&filter)).unwrap();
+    assert_contents(&db);
+    db.wait_for_background_jobs().unwrap();
+    assert!(db.live_files_metadata().iter().all(|f| f.level > 0));
+    assert_no_stray_tables(&db, dir.path());
+    assert_contents(&db);
+
+    // Dropping the handle cancels too
+    filter.slow.store(true, Ordering::SeqCst);
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(&filter)).unwrap();
+    start_compaction(&db, &filter);
+    let start = Instant::now();
+    drop(db);
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
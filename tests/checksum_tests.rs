@@ -0,0 +1,144 @@
+// Checksum type tests
+//
+// Options::checksum picks the checksum over WAL records and SSTable data
+// blocks: CRC32 (the default, and what older files use), CRC32C or
+// xxHash64. Each file names the one it was written with, so a DB reads
+// its old files after the option changes.
+
+use lsm_engine::checksum::ChecksumHasher;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::wal::reader::WALReader;
+use lsm_engine::wal::writer::WALManager;
+use lsm_engine::wal::{SyncPolicy, WALOptions, WALRecord};
+use lsm_engine::{ChecksumType, CompactionStyle, DB, Error, Options};
+use tempfile::tempdir;
+
+const ALL: [ChecksumType; 3] = [
+    ChecksumType::Crc32,
+    ChecksumType::Crc32c,
+    ChecksumType::XxHash64,
+];
+
+fn options(checksum: ChecksumType) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        checksum,
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: Known values, and streaming matches one-shot
+// =============================================================================
+#[test]
+fn checksum_values() {
+    assert_eq!(ChecksumType::Crc32.checksum(b"123456789"), 0xCBF43926);
+    assert_eq!(ChecksumType::Crc32c.checksum(b"123456789"), 0xE3069283);
+    assert_eq!(ChecksumType::XxHash64.checksum(b""), 0x51D8E999);
+
+    let data: Vec<u8> = (0..10_000u32).map(|i| (i * 31) as u8).collect();
+    for checksum in ALL {
+        let mut hasher = ChecksumHasher::new(checksum);
+        for chunk in data.chunks(777) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), checksum.checksum(&data));
+        assert_eq!(ChecksumType::from_byte(checksum.to_byte()), Some(checksum));
+    }
+}
+
+// =============================================================================
+// Test 2: SSTables read back and detect corruption with every checksum
+// =============================================================================
+#[test]
+fn sstable_checksums() {
+    for checksum in ALL {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("000001.sst");
+        let mut builder = SSTableBuilder::new(&path, 1, 4096)
+            .unwrap()
+            .with_checksum(checksum);
+        for i in 0..500u32 {
+            builder
+                .add(format!("key_{i:04}").as_bytes(), b"value")
+                .unwrap();
+        }
+        builder.finish().unwrap();
+
+        let sst = SSTable::open(&path).unwrap();
+        assert_eq!(sst.footer().checksum, checksum);
+        assert_eq!(
+            sst.get(b"key_0123").unwrap().as_deref(),
+            Some(&b"value"[..])
+        );
+
+        let mut data = std::fs::read(&path).unwrap();
+        data[10] ^= 0xFF; // inside the first data block
+        std::fs::write(&path, data).unwrap();
+        let sst = SSTable::open(&path).unwrap();
+        assert!(matches!(sst.get(b"key_0000"), Err(Error::Corruption(_))));
+    }
+}
+
+// =============================================================================
+// Test 3: WALs written with each checksum replay, and stop at a torn record
+// =============================================================================
+#[test]
+fn wal_checksums() {
+    for checksum in ALL {
+        for recycle_log_file_num in [0, 1] {
+            let dir = tempdir().unwrap();
+            let options = WALOptions {
+                sync_policy: SyncPolicy::EveryWrite,
+                recycle_log_file_num,
+                checksum,
+                ..WALOptions::default()
+            };
+            let mut manager = WALManager::with_options(dir.path(), options).unwrap();
+            for i in 0..10u64 {
+                manager
+                    .append(i + 1, &WALRecord::put(vec![i as u8], b"v".to_vec()))
+                    .unwrap();
+            }
+            let path = manager.active_path().to_path_buf();
+            drop(manager);
+
+            let read = |path| WALReader::new(path).unwrap().iter().count();
+            // The Sequence record, then the ten puts
+            assert_eq!(read(&path), 11);
+
+            let mut data = std::fs::read(&path).unwrap();
+            let last = data.len() - 1;
+            data[last] ^= 0xFF;
+            std::fs::write(&path, data).unwrap();
+            assert_eq!(read(&path), 10);
+        }
+    }
+}
+
+// =============================================================================
+// Test 4: A DB reads files written under a different checksum
+// =============================================================================
+#[test]
+fn reopen_with_other_checksum() {
+    let dir = tempdir().unwrap();
+    for (round, checksum) in ALL.into_iter().enumerate() {
+        let db = DB::open(dir.path(), options(checksum)).unwrap();
+        db.put(format!("flushed_{round}").as_bytes(), b"v").unwrap();
+        db.flush().unwrap();
+        // Left in the WAL for the next open to replay
+        db.put(format!("logged_{round}").as_bytes(), b"v").unwrap();
+    }
+
+    let db = DB::open(dir.path(), options(ChecksumType::Crc32)).unwrap();
+    for round in 0..ALL.len() {
+        for prefix in ["flushed", "logged"] {
+            assert_eq!(
+                db.get(format!("{prefix}_{round}").as_bytes()).unwrap(),
+                Some(b"v".to_vec())
+            );
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Cold tier tests (Options::cold_tier_path)
+//
+// Tables of Options::cold_tier_level and the levels below it live in the
+// cold tier directory; the levels above stay in the DB directory or
+// db_paths. Flushes land on the fast tier and compaction writes each
+// output to the tier of its level.
+
+use std::path::Path;
+
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::sstable::paths::TablePaths;
+use lsm_engine::{CompactionStyle, DB, DbPath, Options};
+use tempfile::{TempDir, tempdir};
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn options(cold: &TempDir, style: CompactionStyle) -> Options {
+    Options {
+        compaction_style: style,
+        level0_file_num_compaction_trigger: 2,
+        disable_auto_compactions: true,
+        max_levels: 3,
+        cold_tier_path: Some(cold.path().to_path_buf()),
+        cold_tier_level: 1,
+        ..Options::default()
+    }
+}
+
+/// Flush `files` SSTables of 100 keys each.
+fn write_files(db: &DB, files: u32) {
+    for file in 0..files {
+        for i in file * 100..(file + 1) * 100 {
+            db.put(&key(i), format!("value_{i}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+fn sst_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().ends_with(".sst")
+        })
+        .count()
+}
+
+// =============================================================================
+// Test 1: Flushes stay on the fast tier, compaction moves data to the cold one
+// =============================================================================
+#[test]
+fn compaction_writes_to_cold_tier() {
+    for style in [CompactionStyle::Leveled, CompactionStyle::SizeTiered] {
+        let (dir, cold) = (tempdir().unwrap(), tempdir().unwrap());
+        let db = DB::open(dir.path(), options(&cold, style)).unwrap();
+        write_files(&db, 3);
+        assert_eq!(sst_count(dir.path()), 3);
+        assert_eq!(sst_count(cold.path()), 0);
+
+        db.compact_range(None, None).unwrap();
+        let files = db.live_files_metadata();
+        assert!(files.iter().all(|f| f.level >= 1 && f.path_id == 1));
+        assert_eq!(sst_count(dir.path()), 0);
+        assert_eq!(sst_count(cold.path()), files.len());
+        for i in 0..300 {
+            assert_eq!(
+                db.get(&key(i)).unwrap(),
+                Some(format!("value_{i}").into_bytes())
+            );
+        }
+
+        // New writes go to the fast tier again
+        write_files(&db, 1);
+        assert_eq!(sst_count(dir.path()), 1);
+    }
+}
+
+// =============================================================================
+// Test 2: Cold tables are found after reopen; the tier must be configured
+// =============================================================================
+#[test]
+fn cold_tier_survives_reopen() {
+    let (dir, cold) = (tempdir().unwrap(), tempdir().unwrap());
+    {
+        let db = DB::open(dir.path(), options(&cold, CompactionStyle::Leveled)).unwrap();
+        write_files(&db, 2);
+        db.compact_range(None, None).unwrap();
+        write_files(&db, 1);
+    }
+
+    let db = DB::open(dir.path(), options(&cold, CompactionStyle::Leveled)).unwrap();
+    assert_eq!(db.get(&key(50)).unwrap(), Some(b"value_50".to_vec()));
+    assert_eq!(db.get(&key(150)).unwrap(), Some(b"value_150".to_vec()));
+    drop(db);
+
+    // Without the cold tier the cold tables' path id is out of range
+    let no_cold = Options {
+        max_levels: 3,
+        ..Options::default()
+    };
+    assert!(DB::open(dir.path(), no_cold).is_err());
+}
+
+// =============================================================================
+// Test 3: choose() sends cold levels to the cold tier, the rest to db_paths
+// =============================================================================
+#[test]
+fn choose_by_level() {
+    let paths = TablePaths::new(vec![DbPath::new("/fast", 100), DbPath::new("/more", 100)])
+        .with_cold_tier(Path::new("/cold"), 2);
+    let table = |path_id: u32, file_size: u64| SSTableMeta {
+        id: 1,
+        level: 0,
+        min_key: Vec::new(),
+        max_key: Vec::new(),
+        file_size,
+        entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id,
+    };
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths.choose(0, &[], 0), 0);
+    assert_eq!(paths.choose(1, &[table(0, 100)], 0), 1);
+    // Full hot paths overflow into the last of them, not the cold tier
+    assert_eq!(paths.choose(1, &[table(0, 100), table(1, 100)], 0), 1);
+    assert_eq!(paths.choose(2, &[], 0), 2);
+    assert_eq!(paths.choose(5, &[table(2, 1 << 40)], 0), 2);
+    assert_eq!(
+        paths.table_file(3, 2),
+        Path::new("/cold").join("000003.sst")
+    );
+}
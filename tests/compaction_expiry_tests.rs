@@ -0,0 +1,130 @@
+// Compaction expiry tests
+//
+// Options::expiry_policy tells compaction when each key's newest value
+// expires. Entries expired by the time a compaction starts are dropped
+// (written as a tombstone while a deeper level may hold an older
+// version), counted in Stats::dropped_expired. Unexpired entries still
+// reach the compaction filter.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use lsm_engine::compaction::expiry::ExpiryPolicy;
+use lsm_engine::compaction::filter::{CompactionFilter, FilterDecision};
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+/// Values start with their expiry time: 8 bytes of big-endian Unix
+/// seconds, 0 for never.
+struct DeadlinePrefix;
+
+impl ExpiryPolicy for DeadlinePrefix {
+    fn expires_at(&self, _key: &[u8], value: &[u8]) -> Option<SystemTime> {
+        let secs = u64::from_be_bytes(value.get(..8)?.try_into().unwrap());
+        (secs > 0).then(|| UNIX_EPOCH + Duration::from_secs(secs))
+    }
+}
+
+/// Keeps every key, counting the calls.
+#[derive(Default)]
+struct CountCalls(AtomicUsize);
+
+impl CompactionFilter for CountCalls {
+    fn filter(&self, _level: u32, _key: &[u8], _value: &[u8]) -> FilterDecision {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        FilterDecision::Keep
+    }
+}
+
+fn value(expires: Option<SystemTime>, data: &[u8]) -> Vec<u8> {
+    let secs = expires.map_or(0, |at| at.duration_since(UNIX_EPOCH).unwrap().as_secs());
+    let mut value = secs.to_be_bytes().to_vec();
+    value.extend_from_slice(data);
+    value
+}
+
+fn past() -> Option<SystemTime> {
+    Some(SystemTime::now() - Duration::from_secs(60))
+}
+
+fn future() -> Option<SystemTime> {
+    Some(SystemTime::now() + Duration::from_secs(3600))
+}
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        expiry_policy: Some(Arc::new(DeadlinePrefix)),
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: Expired entries are dropped and counted
+// =============================================================================
+#[test]
+fn compaction_drops_expired_entries() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"expired", &value(past(), b"a")).unwrap();
+    db.put(b"fresh", &value(future(), b"b")).unwrap();
+    db.put(b"forever", &value(None, b"c")).unwrap();
+    db.flush().unwrap();
+    // Still readable until compaction runs
+    assert!(db.get(b"expired").unwrap().is_some());
+
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.get(b"expired").unwrap(), None);
+    assert!(db.get(b"fresh").unwrap().is_some());
+    assert!(db.get(b"forever").unwrap().is_some());
+
+    assert_eq!(db.stats().dropped_expired, 1);
+    // Bottommost: no tombstone is left for the expired key
+    let files = db.live_files_metadata();
+    assert_eq!(files.iter().map(|f| f.entry_count).sum::<u64>(), 2);
+}
+
+// =============================================================================
+// Test 2: Only the newest value counts
+// =============================================================================
+#[test]
+fn expiry_applies_to_newest_value() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"renewed", &value(past(), b"old")).unwrap();
+    db.put(b"lapsed", &value(future(), b"old")).unwrap();
+    db.flush().unwrap();
+    db.put(b"renewed", &value(future(), b"new")).unwrap();
+    db.put(b"lapsed", &value(past(), b"new")).unwrap();
+    db.flush().unwrap();
+
+    db.compact_range(None, None).unwrap();
+    assert!(db.get(b"renewed").unwrap().unwrap().ends_with(b"new"));
+    assert_eq!(db.get(b"lapsed").unwrap(), None);
+    assert_eq!(db.stats().dropped_expired, 1);
+}
+
+// =============================================================================
+// Test 3: Expired entries skip the compaction filter
+// =============================================================================
+#[test]
+fn expired_entries_are_not_filtered() {
+    let dir = tempdir().unwrap();
+    let filter = Arc::new(CountCalls::default());
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_filter: Some(filter.clone()),
+            ..options()
+        },
+    )
+    .unwrap();
+    db.put(b"a", &value(past(), b"a")).unwrap();
+    db.put(b"b", &value(future(), b"b")).unwrap();
+    db.flush().unwrap();
+
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.stats().dropped_expired, 1);
+    assert_eq!(filter.0.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,204 @@
+// Compaction job info tests
+//
+// Every compaction reports a CompactionJobInfo: its input and output
+// files, bytes and entries read and written, and the entries it dropped
+// by reason. The DB passes it to Options::listeners and keeps the last
+// Options::max_compaction_history of them for DB::compaction_history().
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use lsm_engine::compaction::filter::{CompactionFilter, FilterDecision};
+use lsm_engine::compaction::scheduler::{CompactionJobOptions, run_compaction};
+use lsm_engine::compaction::size_tiered::SizeTieredStrategy;
+use lsm_engine::compaction::{CompactionJobInfo, DroppedKeys};
+use lsm_engine::db::listener::EventListener;
+use lsm_engine::manifest::version::VersionSet;
+use lsm_engine::sstable::builder::{SSTableBuilder, TableOptions};
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+#[derive(Default)]
+struct Recorder {
+    jobs: Mutex<Vec<CompactionJobInfo>>,
+}
+
+impl EventListener for Recorder {
+    fn on_compaction_completed(&self, info: &CompactionJobInfo) {
+        self.jobs.lock().unwrap().push(info.clone());
+    }
+}
+
+/// Removes keys ending in 7.
+struct DropSevens;
+
+impl CompactionFilter for DropSevens {
+    fn filter(&self, _level: u32, key: &[u8], _value: &[u8]) -> FilterDecision {
+        if key.ends_with(b"7") {
+            FilterDecision::Remove
+        } else {
+            FilterDecision::Keep
+        }
+    }
+}
+
+fn ids(files: &[SSTableMeta]) -> Vec<u64> {
+    let mut ids: Vec<u64> = files.iter().map(|f| f.id).collect();
+    ids.sort();
+    ids
+}
+
+// =============================================================================
+// Test 1: Listeners get the files, bytes and dropped entries of a compaction
+// =============================================================================
+#[test]
+fn listener_sees_job_info() {
+    let dir = tempdir().unwrap();
+    let recorder = Arc::new(Recorder::default());
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_style: CompactionStyle::SizeTiered,
+            disable_auto_compactions: true,
+            compaction_filter: Some(Arc::new(DropSevens)),
+            listeners: vec![Arc::clone(&recorder) as Arc<dyn EventListener>],
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    // 0..100, then 50..150 over it, then tombstones for 0..10
+    for range in [0..100, 50..150] {
+        for i in range {
+            db.put(&key(i), b"value").unwrap();
+        }
+        db.flush().unwrap();
+    }
+    for i in 0..10 {
+        db.delete(&key(i)).unwrap();
+    }
+    db.flush().unwrap();
+    let inputs = db.live_files_metadata();
+
+    db.compact_range(None, None).unwrap();
+    let jobs = recorder.jobs.lock().unwrap().clone();
+    assert_eq!(jobs.len(), 1);
+    let job = &jobs[0];
+    assert_eq!(job.output_level, 1);
+    assert_eq!(ids(&job.input_files), ids(&inputs));
+    assert_eq!(ids(&job.output_files), ids(&db.live_files_metadata()));
+    assert_eq!(
+        job.bytes_read_upper,
+        inputs.iter().map(|f| f.file_size).sum::<u64>()
+    );
+    assert_eq!(
+        job.bytes_written,
+        job.output_files.iter().map(|f| f.file_size).sum::<u64>()
+    );
+    assert_eq!(job.input_records, 210);
+    // Keys 10..150 survive, less the 14 ending in 7
+    assert_eq!(job.output_records, 140 - 14);
+    assert_eq!(
+        job.keys_dropped,
+        DroppedKeys {
+            overwritten: 60,
+            range_deleted: 0,
+            obsolete_tombstones: 10,
+            filtered: 14,
+            expired: 0,
+        }
+    );
+
+    let history = db.compaction_history();
+    assert_eq!(history.len(), 1);
+    assert_eq!(ids(&history[0].output_files), ids(&job.output_files));
+}
+
+// =============================================================================
+// Test 2: History keeps the most recent jobs, and starts empty on open
+// =============================================================================
+#[test]
+fn history_keeps_last_jobs() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        max_compaction_history: 2,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), options()).unwrap();
+    // Each round's compaction adds its output to L1
+    let mut outputs = Vec::new();
+    for round in 0..3u32 {
+        for i in 0..50 {
+            db.put(&key(round * 50 + i), b"value").unwrap();
+        }
+        db.flush().unwrap();
+        let before = ids(&db.live_files_metadata());
+        db.compact_range(None, None).unwrap();
+        let mut added = ids(&db.live_files_metadata());
+        added.retain(|id| !before.contains(id));
+        outputs.push(added);
+    }
+
+    let history = db.compaction_history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(ids(&history[0].output_files), outputs[1]);
+    assert_eq!(ids(&history[1].output_files), outputs[2]);
+    assert!(history.iter().all(|job| job.input_records == 50));
+    drop(db);
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert!(db.compaction_history().is_empty());
+}
+
+// =============================================================================
+// Test 3: Entries under a newer range tombstone count as range-deleted
+// =============================================================================
+#[test]
+fn range_deleted_counted() {
+    let dir = tempdir().unwrap();
+    let write_table =
+        |path: &Path, id: u64, keys: std::ops::Range<u32>, range: Option<(u32, u32)>| {
+            let mut builder =
+                SSTableBuilder::new(&path.join(format!("{:06}.sst", id)), id, 4096).unwrap();
+            if let Some((start, end)) = range {
+                builder.add_range_tombstone(&key(start), &key(end));
+            }
+            for i in keys {
+                builder.add(&key(i), b"value").unwrap();
+            }
+            builder.finish().unwrap()
+        };
+    let vs = VersionSet::new(3);
+    {
+        let current = vs.current();
+        let mut v = current.write().unwrap();
+        v.levels[0].push(write_table(dir.path(), 1, 0..50, None));
+        v.levels[0].push(write_table(dir.path(), 2, 40..60, Some((10, 20))));
+    }
+
+    let info = run_compaction(
+        &vs,
+        &SizeTieredStrategy::new(1),
+        dir.path(),
+        &TableOptions::default(),
+        &CompactionJobOptions::default(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(info.input_records, 70);
+    assert_eq!(info.output_records, 50);
+    assert_eq!(
+        info.keys_dropped,
+        DroppedKeys {
+            overwritten: 10,
+            range_deleted: 10,
+            ..DroppedKeys::default()
+        }
+    );
+}
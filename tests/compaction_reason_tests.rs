@@ -0,0 +1,171 @@
+// Compaction reason tests
+//
+// Every compaction carries a CompactionReason: why the strategy picked it,
+// or ManualCompaction for compact_range(). The reason is written to the
+// MANIFEST with the compaction's result, reported in CompactionJobInfo and
+// counted per reason in Stats::compaction_reasons.
+
+use lsm_engine::compaction::universal::UniversalStrategy;
+use lsm_engine::compaction::{CompactionReason, CompactionStrategy};
+use lsm_engine::manifest::Manifest;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn make_sst(id: u64, level: u32, size: u64) -> SSTableMeta {
+    SSTableMeta {
+        id,
+        level,
+        min_key: b"a".to_vec(),
+        max_key: b"z".to_vec(),
+        file_size: size,
+        entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
+    }
+}
+
+fn write_files(db: &DB, files: u32) {
+    for file in 0..files {
+        for i in 0..50u32 {
+            db.put(format!("key_{:05}", i).as_bytes(), &file.to_le_bytes())
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+// =============================================================================
+// Test 1: Automatic and manual compactions report their reasons
+// =============================================================================
+#[test]
+fn auto_and_manual_reasons() {
+    let dir = tempdir().unwrap();
+    let options = Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        level0_file_num_compaction_trigger: 4,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), options).unwrap();
+
+    // Four L0 files reach the size-tiered trigger
+    write_files(&db, 4);
+    db.wait_for_background_jobs().unwrap();
+    write_files(&db, 1);
+    db.compact_range(None, None).unwrap();
+
+    let history = db.compaction_history();
+    let reasons: Vec<_> = history.iter().map(|info| info.reason).collect();
+    assert_eq!(
+        reasons,
+        [
+            CompactionReason::Level0FilesNum,
+            CompactionReason::ManualCompaction
+        ]
+    );
+
+    let stats = db.stats().compaction_reasons;
+    assert_eq!(stats.len(), 2);
+    for info in &history {
+        let reason = &stats[&info.reason];
+        assert_eq!(reason.compaction_count, 1);
+        assert_eq!(reason.bytes_written, info.bytes_written);
+        assert!(reason.bytes_read > 0);
+    }
+}
+
+// =============================================================================
+// Test 2: The MANIFEST keeps the counts through reopen and compact()
+// =============================================================================
+#[test]
+fn manifest_counts_reasons() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("MANIFEST");
+    {
+        let mut manifest = Manifest::open(&path).unwrap();
+        for id in 1..=3 {
+            manifest.record_flush(make_sst(id, 0, 100)).unwrap();
+        }
+        manifest
+            .record_compaction(
+                vec![make_sst(4, 1, 300)],
+                vec![1, 2],
+                CompactionReason::Level0FilesNum,
+            )
+            .unwrap();
+        manifest
+            .record_compaction(
+                vec![make_sst(5, 1, 400)],
+                vec![3, 4],
+                CompactionReason::ManualCompaction,
+            )
+            .unwrap();
+    }
+
+    let mut manifest = Manifest::open(&path).unwrap();
+    let expected = [
+        (CompactionReason::Level0FilesNum, 1),
+        (CompactionReason::ManualCompaction, 1),
+    ];
+    assert!(
+        manifest
+            .compaction_reasons()
+            .clone()
+            .into_iter()
+            .eq(expected)
+    );
+
+    manifest.compact().unwrap();
+    manifest
+        .record_compaction(
+            vec![make_sst(6, 2, 400)],
+            vec![5],
+            CompactionReason::ManualCompaction,
+        )
+        .unwrap();
+    drop(manifest);
+    let manifest = Manifest::open(&path).unwrap();
+    assert_eq!(
+        manifest.compaction_reasons()[&CompactionReason::Level0FilesNum],
+        1
+    );
+    assert_eq!(
+        manifest.compaction_reasons()[&CompactionReason::ManualCompaction],
+        2
+    );
+    assert_eq!(manifest.current_version().levels[2].len(), 1);
+}
+
+// =============================================================================
+// Test 3: Universal compaction names the condition that picked it
+// =============================================================================
+#[test]
+fn universal_reasons() {
+    // Two runs, the newer as big as the oldest: space amplification
+    let strategy = UniversalStrategy::new(2, 7).with_max_size_amplification_percent(50);
+    let levels = vec![vec![make_sst(2, 0, 1000)], vec![make_sst(1, 1, 1000)]];
+    let task = strategy.pick_compaction(&levels).unwrap();
+    assert_eq!(task.reason, CompactionReason::UniversalSizeAmplification);
+
+    // Similar small runs over a large oldest one: size ratio
+    let levels = vec![
+        vec![make_sst(4, 0, 10), make_sst(3, 0, 10), make_sst(2, 0, 10)],
+        vec![],
+        vec![make_sst(1, 2, 100_000)],
+    ];
+    let task = strategy.pick_compaction(&levels).unwrap();
+    assert_eq!(task.reason, CompactionReason::UniversalSizeRatio);
+}
+
+// =============================================================================
+// Test 4: Reason bytes round-trip; unknown bytes read as Unknown
+// =============================================================================
+#[test]
+fn reason_bytes() {
+    for byte in 0..=8 {
+        assert_eq!(CompactionReason::from_byte(byte).to_byte(), byte);
+    }
+    assert_eq!(CompactionReason::from_byte(200), CompactionReason::Unknown);
+}
@@ -18,6 +18,10 @@ fn make_sst(id: u64, level: u32, min_key: &[u8], max_key: &[u8]) -> SSTableMeta
         max_key: max_key.to_vec(),
         file_size: 0,
         entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
     }
 }
 
@@ -370,6 +374,12 @@ fn compaction_merges_l0_into_l1() {
     let new_path = db_path.join(format!("{:06}.sst", l1_meta.id));
     assert!(new_path.exists());
     let sst = SSTable::open(&new_path).unwrap();
-    assert_eq!(sst.get(b"key_00000").unwrap(), Some(b"val_00000".to_vec()));
-    assert_eq!(sst.get(b"key_00039").unwrap(), Some(b"val_00039".to_vec()));
+    assert_eq!(
+        sst.get(b"key_00000").unwrap().as_deref(),
+        Some(b"val_00000".as_slice())
+    );
+    assert_eq!(
+        sst.get(b"key_00039").unwrap().as_deref(),
+        Some(b"val_00039".as_slice())
+    );
 }
@@ -0,0 +1,191 @@
+// Compaction service tests
+//
+// With Options::compaction_service set, the DB hands each compaction to
+// the service as an encoded CompactionServiceJob. The executor runs it
+// with run_compaction_job(), writing the outputs to the job's output
+// directory, and the DB moves them into place and records them. If the
+// service fails or returns outputs that don't check out, the compaction
+// runs locally.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use lsm_engine::compaction::DroppedKeys;
+use lsm_engine::compaction::remote::{
+    CompactionService, CompactionServiceJob, CompactionServiceResult, REMOTE_OUTPUT_DIR,
+    run_compaction_job,
+};
+use lsm_engine::sstable::builder::TableOptions;
+use lsm_engine::{CompactionStyle, DB, Error, Options, Result};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn options(service: Arc<dyn CompactionService>) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        compaction_service: Some(service),
+        ..Options::default()
+    }
+}
+
+/// Three overlapping L0 tables: 0..100, then 50..150 and 100..200 over them.
+fn write_files(db: &DB) {
+    for range in [0..100, 50..150, 100..200] {
+        for i in range.clone() {
+            db.put(&key(i), format!("value_{}", range.start).as_bytes())
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+/// The value write_files() leaves for key `i`: from the last range with it.
+fn expected(i: u32) -> Vec<u8> {
+    let start = match i {
+        0..50 => 0,
+        50..100 => 50,
+        _ => 100,
+    };
+    format!("value_{start}").into_bytes()
+}
+
+/// Runs jobs in-process, as an executor on another machine would.
+#[derive(Default)]
+struct InProcess {
+    jobs: Mutex<Vec<CompactionServiceJob>>,
+}
+
+impl CompactionService for InProcess {
+    fn run(&self, job: &[u8]) -> Result<Vec<u8>> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .push(CompactionServiceJob::decode(job)?);
+        run_compaction_job(job, &TableOptions::default(), None, None)
+    }
+}
+
+/// Fails every job, or answers with ids it was never given.
+struct Broken {
+    calls: AtomicUsize,
+    bogus_result: bool,
+}
+
+impl CompactionService for Broken {
+    fn run(&self, _job: &[u8]) -> Result<Vec<u8>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if !self.bogus_result {
+            return Err(Error::Io(std::io::Error::other("executor unreachable")));
+        }
+        Ok(CompactionServiceResult {
+            output_ids: vec![1_000_000],
+            input_records: 0,
+            output_records: 0,
+            keys_dropped: DroppedKeys::default(),
+        }
+        .encode())
+    }
+}
+
+// =============================================================================
+// Test 1: The service runs the compaction; the DB installs its outputs
+// =============================================================================
+#[test]
+fn service_runs_compaction() {
+    let dir = tempdir().unwrap();
+    let service = Arc::new(InProcess::default());
+    let db = DB::open(dir.path(), options(service.clone())).unwrap();
+    write_files(&db);
+    db.compact_range(None, None).unwrap();
+
+    let jobs = service.jobs.lock().unwrap().clone();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].inputs.len(), 3);
+    assert_eq!(jobs[0].output_level, 1);
+
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 1);
+    assert_eq!((files[0].level, files[0].entry_count), (1, 200));
+    assert!(files[0].id >= jobs[0].first_output_id);
+    let history = db.compaction_history();
+    assert_eq!(history[0].output_records, 200);
+    assert_eq!(history[0].keys_dropped.overwritten, 100);
+
+    // The scratch directory is gone, the outputs survive reopen
+    assert!(!jobs[0].output_dir.exists());
+    drop(db);
+    let db = DB::open(dir.path(), options(service)).unwrap();
+    for i in 0..200 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(expected(i)), "key {i}");
+    }
+}
+
+// =============================================================================
+// Test 2: A failing service or a bogus result falls back to local compaction
+// =============================================================================
+#[test]
+fn broken_service_compacts_locally() {
+    for bogus_result in [false, true] {
+        let dir = tempdir().unwrap();
+        let service = Arc::new(Broken {
+            calls: AtomicUsize::new(0),
+            bogus_result,
+        });
+        let db = DB::open(dir.path(), options(service.clone())).unwrap();
+        write_files(&db);
+        db.compact_range(None, None).unwrap();
+
+        assert_eq!(service.calls.load(Ordering::SeqCst), 1);
+        let files = db.live_files_metadata();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].entry_count, 200);
+        for i in 0..200 {
+            assert_eq!(db.get(&key(i)).unwrap(), Some(expected(i)), "key {i}");
+        }
+        let remote_dir = dir.path().join(REMOTE_OUTPUT_DIR);
+        assert!(!remote_dir.exists() || remote_dir.read_dir().unwrap().next().is_none());
+    }
+}
+
+// =============================================================================
+// Test 3: Jobs and results survive encoding; truncated ones are rejected
+// =============================================================================
+#[test]
+fn job_encoding_round_trip() {
+    let job = CompactionServiceJob {
+        inputs: vec![
+            (PathBuf::from("/db/000004.sst"), 4, 0),
+            (PathBuf::from("/db/000002.sst"), 2, 1),
+        ],
+        blob_dir: PathBuf::from("/db"),
+        output_level: 1,
+        is_bottommost: false,
+        deeper_ranges: vec![(b"a".to_vec(), b"m".to_vec())],
+        max_subcompactions: 2,
+        output_dir: PathBuf::from("/db/remote_compaction/000010"),
+        first_output_id: 10,
+        max_output_files: 8,
+    };
+    let encoded = job.encode();
+    assert_eq!(CompactionServiceJob::decode(&encoded).unwrap(), job);
+    assert!(CompactionServiceJob::decode(&encoded[..encoded.len() - 1]).is_err());
+
+    let result = CompactionServiceResult {
+        output_ids: vec![10, 11],
+        input_records: 300,
+        output_records: 200,
+        keys_dropped: DroppedKeys {
+            overwritten: 100,
+            ..DroppedKeys::default()
+        },
+    };
+    assert_eq!(
+        CompactionServiceResult::decode(&result.encode()).unwrap(),
+        result
+    );
+}
@@ -0,0 +1,156 @@
+// Compaction throttle tests
+//
+// Options::compaction_throttle is asked for limits before every
+// compaction: how many subcompactions it may use and the rate it may
+// write at, overriding Options::max_subcompactions and
+// Options::rate_limiter. TimeWindowThrottle picks the limits by time of
+// day. Timing checks leave generous slack.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use lsm_engine::compaction::throttle::{CompactionLimits, CompactionThrottle, TimeWindowThrottle};
+use lsm_engine::rate_limiter::RateLimiter;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+const HOUR: u64 = 60 * 60;
+
+fn hours(h: u64) -> Duration {
+    Duration::from_secs(h * HOUR)
+}
+
+/// `h` hours into the first day of the epoch, UTC.
+fn at(h: u64) -> SystemTime {
+    UNIX_EPOCH + hours(h)
+}
+
+/// Limits set by the test, counting the compactions that asked.
+#[derive(Default)]
+struct Switch {
+    limits: Mutex<CompactionLimits>,
+    calls: AtomicUsize,
+}
+
+impl CompactionThrottle for Switch {
+    fn limits(&self, _now: SystemTime) -> CompactionLimits {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        *self.limits.lock().unwrap()
+    }
+}
+
+fn options(throttle: Arc<Switch>) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        compaction_throttle: Some(throttle),
+        ..Options::default()
+    }
+}
+
+/// Three L0 tables of 300 keys with 100-byte values, ~100KB in all.
+fn write_files(path: &Path) {
+    let db = DB::open(path, Options::default()).unwrap();
+    for file in 0..3u32 {
+        for i in 0..300u32 {
+            let key = format!("key_{:05}", file * 300 + i);
+            db.put(key.as_bytes(), &[b'v'; 100]).unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+// =============================================================================
+// Test 1: Windows apply by time of day, wrapping midnight and UTC offset
+// =============================================================================
+#[test]
+fn time_windows() {
+    let night = CompactionLimits {
+        max_subcompactions: Some(4),
+        rate_bytes_per_sec: Some(0),
+    };
+    let peak = CompactionLimits {
+        max_subcompactions: Some(1),
+        rate_bytes_per_sec: Some(1024 * 1024),
+    };
+    let throttle = TimeWindowThrottle::new()
+        .with_window(hours(22), hours(2), peak)
+        .with_window(hours(1), hours(6), night);
+
+    // The peak runs over midnight and wins where the windows overlap
+    assert_eq!(throttle.limits(at(23)), peak);
+    assert_eq!(throttle.limits(at(24 + 1)), peak);
+    assert_eq!(throttle.limits(at(3)), night);
+    // Windows end before their end time
+    assert_eq!(throttle.limits(at(6)), CompactionLimits::default());
+    assert_eq!(throttle.limits(at(12)), CompactionLimits::default());
+
+    // 23:00 UTC is 03:00 at UTC+4
+    let shifted = throttle.with_utc_offset(4 * HOUR as i64);
+    assert_eq!(shifted.limits(at(23)), night);
+}
+
+// =============================================================================
+// Test 2: The throttle's subcompactions replace the DB's
+// =============================================================================
+#[test]
+fn throttle_sets_subcompactions() {
+    let dir = tempdir().unwrap();
+    write_files(dir.path());
+    let throttle = Arc::new(Switch::default());
+    *throttle.limits.lock().unwrap() = CompactionLimits {
+        max_subcompactions: Some(3),
+        rate_bytes_per_sec: None,
+    };
+    let db = DB::open(dir.path(), options(throttle.clone())).unwrap();
+    db.compact_range(None, None).unwrap();
+
+    assert!(throttle.calls.load(Ordering::SeqCst) >= 1);
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 3);
+    assert_eq!(files.iter().map(|f| f.entry_count).sum::<u64>(), 900);
+}
+
+// =============================================================================
+// Test 3: The throttle's rate lowers or lifts Options::rate_limiter
+// =============================================================================
+#[test]
+fn throttle_sets_rate() {
+    // Lifted: at 1KB/s the ~100KB output would take over a minute
+    let dir = tempdir().unwrap();
+    write_files(dir.path());
+    let throttle = Arc::new(Switch::default());
+    *throttle.limits.lock().unwrap() = CompactionLimits {
+        max_subcompactions: None,
+        rate_bytes_per_sec: Some(0),
+    };
+    let slow = Arc::new(RateLimiter::new(1024));
+    let db = DB::open(
+        dir.path(),
+        Options {
+            rate_limiter: Some(slow.clone()),
+            ..options(throttle)
+        },
+    )
+    .unwrap();
+    let start = Instant::now();
+    db.compact_range(None, None).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(slow.total_bytes_through(), 0);
+    drop(db);
+
+    // Lowered: ~100KB at 200KB/s
+    let dir = tempdir().unwrap();
+    write_files(dir.path());
+    let throttle = Arc::new(Switch::default());
+    *throttle.limits.lock().unwrap() = CompactionLimits {
+        max_subcompactions: None,
+        rate_bytes_per_sec: Some(200 * 1024),
+    };
+    let db = DB::open(dir.path(), options(throttle)).unwrap();
+    let start = Instant::now();
+    db.compact_range(None, None).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
@@ -0,0 +1,93 @@
+// Per-level compression tests
+//
+// Options::compression_per_level picks the codec by the level a table is
+// written to: flushes build L0 tables, compactions their output level.
+// Levels past the end of the list use its last entry.
+
+use std::path::Path;
+
+use lsm_engine::sstable::builder::TableOptions;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{CompactionStyle, CompressionType, DB, Options};
+use tempfile::tempdir;
+
+fn value(i: u32) -> Vec<u8> {
+    format!("{{\"id\":{i},\"padding\":\"{}\"}}", "x".repeat(200)).into_bytes()
+}
+
+/// Whether the table's data blocks came out smaller than the raw entries.
+fn is_compressed(path: &Path) -> bool {
+    let sst = SSTable::open(path).unwrap();
+    let props = sst.properties().unwrap();
+    props.data_size < (props.raw_key_size + props.raw_value_size) / 2
+}
+
+fn sst_files(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "sst"))
+        .collect();
+    files.sort();
+    files
+}
+
+// =============================================================================
+// Test 1: Level lookup, falling back to the last entry and to `compression`
+// =============================================================================
+#[test]
+fn compression_for_level() {
+    let options = TableOptions {
+        compression: CompressionType::Lz4,
+        compression_per_level: vec![CompressionType::None, CompressionType::Snappy],
+        ..TableOptions::default()
+    };
+    assert_eq!(options.for_level(0).compression, CompressionType::None);
+    assert_eq!(options.for_level(1).compression, CompressionType::Snappy);
+    assert_eq!(options.for_level(6).compression, CompressionType::Snappy);
+
+    let options = TableOptions {
+        compression: CompressionType::Lz4,
+        ..TableOptions::default()
+    };
+    assert_eq!(options.for_level(3).compression, CompressionType::Lz4);
+}
+
+// =============================================================================
+// Test 2: Flushes stay uncompressed, compaction output is compressed
+// =============================================================================
+#[test]
+fn flush_and_compaction_levels() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        compression: CompressionType::Lz4,
+        compression_per_level: vec![CompressionType::None, CompressionType::Zstd],
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for round in 0..2 {
+        for i in round * 300..(round + 1) * 300 {
+            db.put(format!("key_{i:04}").as_bytes(), &value(i)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    let flushed = sst_files(dir.path());
+    assert_eq!(flushed.len(), 2);
+    assert!(flushed.iter().all(|path| !is_compressed(path)));
+
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.stats().num_sstables_per_level[1], 1);
+    let compacted = sst_files(dir.path());
+    assert_eq!(compacted.len(), 1);
+    assert!(is_compressed(&compacted[0]));
+
+    for i in [0, 299, 300, 599] {
+        assert_eq!(
+            db.get(format!("key_{i:04}").as_bytes()).unwrap().unwrap(),
+            value(i)
+        );
+    }
+}
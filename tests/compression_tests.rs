@@ -0,0 +1,166 @@
+// Block compression tests
+//
+// Blocks carry a trailing compression-type byte; readers decompress
+// transparently whatever codec (if any) the block was written with.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::sstable::compression::{compress, decompress};
+use lsm_engine::{CompactionStyle, CompressionType, DB, Error, Options};
+use tempfile::tempdir;
+
+const CODECS: [CompressionType; 3] = [
+    CompressionType::Snappy,
+    CompressionType::Lz4,
+    CompressionType::Zstd,
+];
+
+fn compressible_value(i: u32) -> Vec<u8> {
+    format!(
+        "{{\"id\":{i},\"status\":\"active\",\"padding\":\"{}\"}}",
+        "x".repeat(200)
+    )
+    .into_bytes()
+}
+
+fn sst_bytes(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "sst"))
+        .map(|e| e.metadata().unwrap().len())
+        .sum()
+}
+
+fn fill_db(dir: &Path, compression: CompressionType) -> DB {
+    let opts = Options {
+        compression,
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir, opts).unwrap();
+    for i in 0..500u32 {
+        db.put(format!("key_{:04}", i).as_bytes(), &compressible_value(i))
+            .unwrap();
+    }
+    db.flush().unwrap();
+    db
+}
+
+// =============================================================================
+// Test 1: Every codec round-trips
+// =============================================================================
+#[test]
+fn codecs_round_trip() {
+    let data = compressible_value(7);
+    for codec in CODECS {
+        let compressed = compress(&data, codec).unwrap();
+        assert!(compressed.len() < data.len(), "{codec:?} didn't shrink");
+        assert_eq!(decompress(&compressed, codec).unwrap(), data);
+    }
+}
+
+// =============================================================================
+// Test 2: Compressed block decodes to the same entries
+// =============================================================================
+#[test]
+fn compressed_block_round_trip() {
+    for codec in CODECS {
+        let mut plain = BlockBuilder::new(4096);
+        let mut builder = BlockBuilder::new(4096).with_compression(codec);
+        for i in 0..10u32 {
+            let key = format!("key_{:02}", i);
+            plain.add(key.as_bytes(), &compressible_value(i));
+            builder.add(key.as_bytes(), &compressible_value(i));
+        }
+        let raw = builder.build();
+        assert_eq!(*raw.last().unwrap(), codec.to_byte());
+        assert!(raw.len() < plain.build().len());
+
+        let block = Block::decode(raw).unwrap();
+        let mut iter = block.iter();
+        iter.seek(b"key_05").unwrap();
+        assert_eq!(iter.value(), compressible_value(5).as_slice());
+    }
+}
+
+// =============================================================================
+// Test 3: Blocks that don't shrink are stored uncompressed
+// =============================================================================
+#[test]
+fn incompressible_block_stored_raw() {
+    let mut builder = BlockBuilder::new(4096).with_compression(CompressionType::Zstd);
+    builder.add(b"k", b"v");
+    let raw = builder.build();
+    assert_eq!(*raw.last().unwrap(), CompressionType::None.to_byte());
+    assert_eq!(Block::decode(raw).unwrap().get(b"k"), Some(&b"v"[..]));
+}
+
+// =============================================================================
+// Test 4: Options::compression shrinks SSTables and reads stay correct
+// =============================================================================
+#[test]
+fn db_compression_reduces_disk_use() {
+    let plain_dir = tempdir().unwrap();
+    drop(fill_db(plain_dir.path(), CompressionType::None));
+    let plain = sst_bytes(plain_dir.path());
+
+    for codec in CODECS {
+        let dir = tempdir().unwrap();
+        let db = fill_db(dir.path(), codec);
+        let compressed = sst_bytes(dir.path());
+        assert!(compressed * 3 < plain, "{codec:?}: {compressed} vs {plain}");
+
+        assert_eq!(
+            db.get(b"key_0123").unwrap().unwrap(),
+            compressible_value(123)
+        );
+        let mut iter = db.iter().unwrap();
+        let mut count = 0;
+        while iter.is_valid() {
+            count += 1;
+            iter.next().unwrap();
+        }
+        assert_eq!(count, 500);
+    }
+}
+
+// =============================================================================
+// Test 5: Tables written with different codecs merge through compaction
+// =============================================================================
+#[test]
+fn mixed_codecs_compact_together() {
+    let dir = tempdir().unwrap();
+    drop(fill_db(dir.path(), CompressionType::None));
+
+    let opts = Options {
+        compression: CompressionType::Lz4,
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    db.put(b"key_9999", b"new").unwrap();
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+
+    assert_eq!(db.get(b"key_9999").unwrap().unwrap(), b"new");
+    assert_eq!(
+        db.get(b"key_0499").unwrap().unwrap(),
+        compressible_value(499)
+    );
+}
+
+// =============================================================================
+// Test 6: Unknown compression type is corruption
+// =============================================================================
+#[test]
+fn unknown_type_byte_is_corruption() {
+    let mut builder = BlockBuilder::new(4096);
+    builder.add(b"k", b"v");
+    let mut raw = builder.build();
+    *raw.last_mut().unwrap() = 0x7F;
+    assert!(matches!(Block::decode(raw), Err(Error::Corruption(_))));
+}
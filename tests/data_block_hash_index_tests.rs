@@ -0,0 +1,130 @@
+// Data block hash index tests
+//
+// With a hash index, each data block maps key hashes to entries so
+// Block::get() skips the binary search. Lookups must give the same
+// answers with and without it, including misses and bucket collisions.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::sstable::builder::{SSTableBuilder, TableOptions};
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn build_block(num_entries: u32, hash_index: bool) -> Block {
+    let mut builder = BlockBuilder::new(64 * 1024).with_hash_index(hash_index);
+    for i in 0..num_entries {
+        assert!(builder.add(&key(i * 2), format!("v{i}").as_bytes()));
+    }
+    Block::decode(builder.build()).unwrap()
+}
+
+// =============================================================================
+// Test 1: Hits and misses match a block without the index
+// =============================================================================
+#[test]
+fn lookups_match_binary_search() {
+    // 200 entries in ~267 buckets: some buckets collide
+    let plain = build_block(200, false);
+    let hashed = build_block(200, true);
+    assert!(!plain.has_hash_index());
+    assert!(hashed.has_hash_index());
+
+    for i in 0..400 {
+        assert_eq!(hashed.get(&key(i)), plain.get(&key(i)), "key {i}");
+    }
+    assert_eq!(hashed.get(&key(10)).unwrap(), b"v5");
+    assert!(hashed.get(&key(11)).is_none());
+    assert!(hashed.get(b"").is_none());
+    assert!(hashed.get(b"zzz").is_none());
+
+    // Iteration is unaffected by the extra bytes
+    let mut iter = hashed.iter();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 200);
+}
+
+// =============================================================================
+// Test 2: Blocks with more entries than a bucket can address go without
+// =============================================================================
+#[test]
+fn large_blocks_skip_the_index() {
+    let block = build_block(253, true);
+    assert!(block.has_hash_index());
+    let block = build_block(254, true);
+    assert!(!block.has_hash_index());
+    assert_eq!(block.get(&key(506)).unwrap(), b"v253");
+}
+
+// =============================================================================
+// Test 3: Corrupt buckets are rejected on decode
+// =============================================================================
+#[test]
+fn corrupt_bucket_rejected() {
+    let mut builder = BlockBuilder::new(4096).with_hash_index(true);
+    for i in 0..4 {
+        builder.add(&key(i), b"v");
+    }
+    let mut raw = builder.build();
+    // [..buckets][num_buckets(2)][count(4)][type(1)]: point the first
+    // bucket at an entry that doesn't exist
+    let num_buckets = u16::from_le_bytes([raw[raw.len() - 7], raw[raw.len() - 6]]) as usize;
+    let first_bucket = raw.len() - 7 - num_buckets;
+    raw[first_bucket] = 9;
+    assert!(Block::decode(raw).is_err());
+}
+
+// =============================================================================
+// Test 4: SSTables and the DB read through the index
+// =============================================================================
+#[test]
+fn sstable_and_db_point_lookups() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let options = TableOptions {
+        data_block_hash_index: true,
+        ..TableOptions::default()
+    };
+    let mut builder = SSTableBuilder::with_options(&path, 1, 1000, &options).unwrap();
+    for i in 0..1000 {
+        builder
+            .add(&key(i * 2), format!("v{i}").as_bytes())
+            .unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    assert!(sst.num_blocks() > 1);
+    assert!(sst.verify_integrity().unwrap().is_ok());
+    for i in 0..1000 {
+        assert_eq!(
+            sst.get(&key(i * 2)).unwrap().unwrap(),
+            format!("v{i}").into_bytes()
+        );
+        assert!(sst.get(&key(i * 2 + 1)).unwrap().is_none());
+    }
+
+    let db_dir = tempdir().unwrap();
+    let opts = Options {
+        data_block_hash_index: true,
+        ..Options::default()
+    };
+    let db = DB::open(db_dir.path(), opts).unwrap();
+    for i in 0..500 {
+        db.put(&key(i), b"value").unwrap();
+    }
+    db.delete(&key(7)).unwrap();
+    db.flush().unwrap();
+    assert_eq!(db.get(&key(3)).unwrap().unwrap(), b"value");
+    assert!(db.get(&key(7)).unwrap().is_none());
+    assert!(db.get(&key(500)).unwrap().is_none());
+}
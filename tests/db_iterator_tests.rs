@@ -0,0 +1,127 @@
+// DBIterator tests
+//
+// DB::iter() scans the whole database: active memtable + every SSTable,
+// newest version wins, tombstones hidden.
+
+use lsm_engine::db::iterator::DBIterator;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn open_test_db() -> (tempfile::TempDir, DB) {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        memtable_size: 64 * 1024,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    (dir, db)
+}
+
+fn collect_all(iter: &mut DBIterator) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut result = Vec::new();
+    while iter.is_valid() {
+        result.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    result
+}
+
+// =============================================================================
+// Test 1: Empty database → iterator starts invalid
+// =============================================================================
+#[test]
+fn empty_db_iterator_is_invalid() {
+    let (_dir, db) = open_test_db();
+    let iter = db.iter().unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 2: Keys spread over memtable and several SSTables come out merged
+// =============================================================================
+#[test]
+fn iter_merges_memtable_and_sstables() {
+    let (_dir, db) = open_test_db();
+
+    db.put(b"a", b"1").unwrap();
+    db.put(b"d", b"4").unwrap();
+    db.flush().unwrap();
+    db.put(b"b", b"2").unwrap();
+    db.flush().unwrap();
+    db.put(b"c", b"3").unwrap(); // stays in memtable
+
+    let mut iter = db.iter().unwrap();
+    let keys: Vec<Vec<u8>> = collect_all(&mut iter).into_iter().map(|(k, _)| k).collect();
+    assert_eq!(
+        keys,
+        vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()]
+    );
+}
+
+// =============================================================================
+// Test 3: Overwrites across layers → only the newest value is visible
+// =============================================================================
+#[test]
+fn iter_hides_old_versions() {
+    let (_dir, db) = open_test_db();
+
+    db.put(b"key", b"v1").unwrap();
+    db.flush().unwrap();
+    db.put(b"key", b"v2").unwrap();
+    db.flush().unwrap();
+    db.put(b"key", b"v3").unwrap();
+
+    let mut iter = db.iter().unwrap();
+    assert_eq!(
+        collect_all(&mut iter),
+        vec![(b"key".to_vec(), b"v3".to_vec())]
+    );
+}
+
+// =============================================================================
+// Test 4: Deleted keys are hidden, even when the value lives in an SSTable
+// =============================================================================
+#[test]
+fn iter_hides_tombstones() {
+    let (_dir, db) = open_test_db();
+
+    db.put(b"a", b"1").unwrap();
+    db.put(b"b", b"2").unwrap();
+    db.put(b"c", b"3").unwrap();
+    db.flush().unwrap();
+    db.delete(b"a").unwrap();
+    db.delete(b"c").unwrap();
+
+    let mut iter = db.iter().unwrap();
+    assert_eq!(collect_all(&mut iter), vec![(b"b".to_vec(), b"2".to_vec())]);
+
+    // Reverse direction hides them too
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"b");
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 5: Data compacted into L1 is still visible
+// =============================================================================
+#[test]
+fn iter_sees_compacted_data() {
+    let (_dir, db) = open_test_db();
+
+    for i in 0..100u32 {
+        db.put(format!("key_{:03}", i).as_bytes(), b"value")
+            .unwrap();
+        if i % 25 == 24 {
+            db.flush().unwrap();
+        }
+    }
+    db.compact_range(None, None).unwrap();
+
+    let mut iter = db.iter().unwrap();
+    assert_eq!(collect_all(&mut iter).len(), 100);
+
+    iter.seek(b"key_050").unwrap();
+    assert_eq!(iter.key(), b"key_050");
+}
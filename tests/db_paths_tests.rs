@@ -0,0 +1,231 @@
+// Multiple data directories (Options::db_paths)
+//
+// SSTables are spread over the configured paths: each new table goes to
+// the first path whose live tables are below its target size, the last
+// path taking the rest. The manifest records each table's path, so reads,
+// reopen and obsolete file deletion find it there.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::sstable::paths::{TablePaths, available_space};
+use lsm_engine::{CompactionStyle, DB, DbPath, Options};
+use tempfile::{TempDir, tempdir};
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn options(fast: &TempDir, slow: &TempDir, fast_target: u64) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        db_paths: vec![
+            DbPath::new(fast.path(), fast_target),
+            DbPath::new(slow.path(), u64::MAX),
+        ],
+        ..Options::default()
+    }
+}
+
+/// Flush `files` SSTables of 100 keys each.
+fn write_files(db: &DB, files: u32) {
+    for file in 0..files {
+        for i in file * 100..(file + 1) * 100 {
+            db.put(&key(i), format!("value_{i}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+fn sst_files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".sst"))
+        .collect();
+    names.sort();
+    names
+}
+
+fn count(db: &DB) -> usize {
+    let mut iter = db.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    count
+}
+
+fn file_name(meta: &SSTableMeta) -> String {
+    format!("{:06}.sst", meta.id)
+}
+
+// =============================================================================
+// Test 1: A path takes tables until it reaches its target size
+// =============================================================================
+#[test]
+fn tables_fill_paths_in_order() {
+    let (dir, fast, slow) = (tempdir().unwrap(), tempdir().unwrap(), tempdir().unwrap());
+    let db = DB::open(dir.path(), options(&fast, &slow, 2000)).unwrap();
+    write_files(&db, 4);
+
+    let files = db.live_files_metadata();
+    let in_fast: Vec<&SSTableMeta> = files.iter().filter(|f| f.path_id == 0).collect();
+    assert!(!in_fast.is_empty() && in_fast.len() < files.len());
+    // Only the last table in the fast path may take it past the target
+    let fast_bytes: u64 = in_fast.iter().map(|f| f.file_size).sum();
+    assert!(fast_bytes - in_fast.last().unwrap().file_size < 2000);
+    for meta in &files {
+        let dir = if meta.path_id == 0 { &fast } else { &slow };
+        assert!(dir.path().join(file_name(meta)).exists(), "{meta:?}");
+    }
+    assert!(sst_files(dir.path()).is_empty());
+
+    for i in 0..400 {
+        assert_eq!(
+            db.get(&key(i)).unwrap(),
+            Some(format!("value_{i}").into_bytes())
+        );
+    }
+    assert_eq!(count(&db), 400);
+}
+
+// =============================================================================
+// Test 2: Paths survive reopen; too few configured paths is an error
+// =============================================================================
+#[test]
+fn paths_recorded_in_manifest() {
+    let (dir, fast, slow) = (tempdir().unwrap(), tempdir().unwrap(), tempdir().unwrap());
+    let before = {
+        let db = DB::open(dir.path(), options(&fast, &slow, 1)).unwrap();
+        write_files(&db, 3);
+        db.live_files_metadata()
+    };
+    assert_eq!(
+        before.iter().map(|f| f.path_id).collect::<Vec<_>>(),
+        vec![0, 1, 1]
+    );
+
+    let db = DB::open(dir.path(), options(&fast, &slow, 1)).unwrap();
+    let after = db.live_files_metadata();
+    assert_eq!(
+        after.iter().map(|f| (f.id, f.path_id)).collect::<Vec<_>>(),
+        before.iter().map(|f| (f.id, f.path_id)).collect::<Vec<_>>()
+    );
+    assert_eq!(db.get(&key(250)).unwrap(), Some(b"value_250".to_vec()));
+    drop(db);
+
+    let only_fast = Options {
+        db_paths: vec![DbPath::new(fast.path(), u64::MAX)],
+        ..Options::default()
+    };
+    assert!(DB::open(dir.path(), only_fast).is_err());
+}
+
+// =============================================================================
+// Test 3: Compaction deletes inputs from their paths and picks its output's
+// =============================================================================
+#[test]
+fn compaction_across_paths() {
+    let (dir, fast, slow) = (tempdir().unwrap(), tempdir().unwrap(), tempdir().unwrap());
+    let db = DB::open(dir.path(), options(&fast, &slow, 1)).unwrap();
+    write_files(&db, 3);
+    assert_eq!(sst_files(fast.path()).len(), 1);
+    assert_eq!(sst_files(slow.path()).len(), 2);
+
+    // The fast path is full while the inputs are live, so the output
+    // goes to the slow one
+    db.compact_range(None, None).unwrap();
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path_id, 1);
+    assert!(sst_files(fast.path()).is_empty());
+    assert_eq!(sst_files(slow.path()), vec![file_name(&files[0])]);
+    assert_eq!(count(&db), 300);
+
+    // Now empty, the fast path takes the next flush
+    write_files(&db, 1);
+    assert_eq!(sst_files(fast.path()).len(), 1);
+}
+
+// =============================================================================
+// Test 4: choose() skips paths at their target
+// =============================================================================
+#[test]
+fn choose_first_path_with_room() {
+    let paths = TablePaths::new(vec![
+        DbPath::new("/a", 100),
+        DbPath::new("/b", 100),
+        DbPath::new("/c", 100),
+    ]);
+    let table = |path_id: u32, file_size: u64| SSTableMeta {
+        id: 1,
+        level: 0,
+        min_key: Vec::new(),
+        max_key: Vec::new(),
+        file_size,
+        entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id,
+    };
+    assert_eq!(paths.choose(0, &[], 0), 0);
+    assert_eq!(paths.choose(0, &[table(0, 99)], 0), 0);
+    assert_eq!(paths.choose(0, &[table(0, 60), table(0, 40)], 0), 1);
+    // Every path full: the last one takes the overflow
+    assert_eq!(
+        paths.choose(0, &[table(0, 100), table(1, 100), table(2, 500)], 0),
+        2
+    );
+    assert_eq!(paths.table_file(7, 1), Path::new("/b").join("000007.sst"));
+}
+
+// =============================================================================
+// Test 5: choose() skips paths without the table's size free on disk
+// =============================================================================
+#[test]
+fn choose_skips_full_disks() {
+    let paths = TablePaths::new(vec![
+        DbPath::new("/a", 1000),
+        DbPath::new("/b", 1000),
+        DbPath::new("/c", 1000),
+    ]);
+    let free = |a: u64, b: u64, c: u64| {
+        move |dir: &Path| match dir.to_str() {
+            Some("/a") => Some(a),
+            Some("/b") => Some(b),
+            _ => Some(c),
+        }
+    };
+    assert_eq!(paths.choose_with(0, &[], 50, free(100, 100, 100)), 0);
+    // Below its target, but the disk can't take the table
+    assert_eq!(paths.choose_with(0, &[], 50, free(10, 100, 100)), 1);
+    assert_eq!(paths.choose_with(0, &[], 50, free(10, 10, 100)), 2);
+    // Every path at its target: the last one with the space free
+    let table = |path_id: u32| SSTableMeta {
+        id: 1,
+        level: 0,
+        min_key: Vec::new(),
+        max_key: Vec::new(),
+        file_size: 1000,
+        entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id,
+    };
+    let full = [table(0), table(1), table(2)];
+    assert_eq!(paths.choose_with(0, &full, 50, free(100, 100, 10)), 1);
+    // An unreadable free space counts as enough
+    assert_eq!(paths.choose_with(0, &[], 50, |_| None), 0);
+
+    // The real filesystem: a temp dir has room for a small table
+    let dir = tempdir().unwrap();
+    let paths = TablePaths::new(vec![DbPath::new(dir.path(), 1000), DbPath::new("/b", 1000)]);
+    assert!(available_space(dir.path()).unwrap() > 0);
+    assert_eq!(paths.choose(0, &[], 1), 0);
+}
@@ -0,0 +1,132 @@
+// Delete scheduler tests
+//
+// With Options::delete_rate_bytes_per_sec set, obsolete files are moved
+// to a `trash` directory beside them and deleted in the background at
+// that rate. Files left in the trash are picked up on the next open.
+// Timing checks leave generous slack.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use lsm_engine::delete_scheduler::{DeleteScheduler, TRASH_DIR};
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn options(delete_rate_bytes_per_sec: u64) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        delete_rate_bytes_per_sec,
+        ..Options::default()
+    }
+}
+
+fn files_with(dir: &Path, extension: &str) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter(|entry| {
+                    entry
+                        .as_ref()
+                        .unwrap()
+                        .file_name()
+                        .to_string_lossy()
+                        .ends_with(extension)
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+// =============================================================================
+// Test 1: Trashed files are deleted one by one at the rate
+// =============================================================================
+#[test]
+fn deletes_paced_through_trash() {
+    let dir = tempdir().unwrap();
+    let scheduler = DeleteScheduler::new(1024 * 1024);
+    for i in 0..3 {
+        std::fs::write(dir.path().join(format!("{i}.sst")), vec![0u8; 100 * 1024]).unwrap();
+    }
+
+    let start = Instant::now();
+    for i in 0..3 {
+        scheduler
+            .delete_file(&dir.path().join(format!("{i}.sst")))
+            .unwrap();
+    }
+    // Out of the DB's directory at once
+    assert_eq!(files_with(dir.path(), ".sst"), 0);
+    assert!(scheduler.num_pending() > 0);
+
+    // The second and third wait 100KB at 1MB/s each
+    scheduler.wait_for_empty_trash();
+    assert!(start.elapsed() >= Duration::from_millis(180));
+    assert_eq!(scheduler.num_pending(), 0);
+    assert_eq!(files_with(&dir.path().join(TRASH_DIR), ".sst"), 0);
+}
+
+// =============================================================================
+// Test 2: Without a rate, files are deleted right away
+// =============================================================================
+#[test]
+fn no_rate_deletes_directly() {
+    let dir = tempdir().unwrap();
+    let scheduler = DeleteScheduler::new(0);
+    let file = dir.path().join("000001.sst");
+    std::fs::write(&file, b"data").unwrap();
+
+    scheduler.delete_file(&file).unwrap();
+    assert!(!file.exists());
+    assert!(!dir.path().join(TRASH_DIR).exists());
+    assert_eq!(scheduler.num_pending(), 0);
+}
+
+// =============================================================================
+// Test 3: Compaction inputs and flushed WALs go through the trash
+// =============================================================================
+#[test]
+fn obsolete_db_files_trashed() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(64 * 1024)).unwrap();
+    for file in 0..3u32 {
+        for i in 0..100u32 {
+            let key = format!("key_{:05}", file * 100 + i);
+            db.put(key.as_bytes(), &[b'v'; 100]).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    db.compact_range(None, None).unwrap();
+    assert_eq!(files_with(dir.path(), ".sst"), 1);
+
+    db.wait_for_empty_trash();
+    let trash = dir.path().join(TRASH_DIR);
+    assert!(trash.exists());
+    assert_eq!(std::fs::read_dir(&trash).unwrap().count(), 0);
+    assert_eq!(
+        db.get(b"key_00150").unwrap().as_deref(),
+        Some(&[b'v'; 100][..])
+    );
+}
+
+// =============================================================================
+// Test 4: Files left in the trash are deleted after reopen
+// =============================================================================
+#[test]
+fn leftover_trash_recovered() {
+    let dir = tempdir().unwrap();
+    let trash = dir.path().join(TRASH_DIR);
+    std::fs::create_dir_all(&trash).unwrap();
+    std::fs::write(trash.join("000007.sst"), vec![0u8; 1024]).unwrap();
+    std::fs::write(trash.join("000008.log"), vec![0u8; 1024]).unwrap();
+
+    let db = DB::open(dir.path(), options(1024 * 1024)).unwrap();
+    db.wait_for_empty_trash();
+    assert_eq!(std::fs::read_dir(&trash).unwrap().count(), 0);
+    drop(db);
+
+    // Without a rate, they're deleted during open
+    std::fs::write(trash.join("000009.sst"), vec![0u8; 1024]).unwrap();
+    let _db = DB::open(dir.path(), options(0)).unwrap();
+    assert_eq!(std::fs::read_dir(&trash).unwrap().count(), 0);
+}
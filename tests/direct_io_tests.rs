@@ -0,0 +1,132 @@
+// Direct I/O tests
+//
+// With O_DIRECT, SSTable writes go through an aligned buffer and a final
+// padded write that's trimmed back off; reads widen each request to
+// aligned boundaries. Neither may change a byte of what's stored or read.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::{SSTableBuilder, TableOptions};
+use lsm_engine::sstable::reader::{FileAccess, SSTable};
+use lsm_engine::{CompactionStyle, DB, Error, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn value(i: u32) -> Vec<u8> {
+    format!("value_{i}_").repeat(10).into_bytes()
+}
+
+/// ~3MB, so the direct writer fills its buffer several times.
+fn build_table(path: &Path, use_direct_io: bool) {
+    let options = TableOptions {
+        use_direct_io,
+        ..TableOptions::default()
+    };
+    let mut builder = SSTableBuilder::with_options(path, 1, 30_000, &options).unwrap();
+    for i in 0..30_000 {
+        builder.add(&key(i), &value(i)).unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+// =============================================================================
+// Test 1: Direct writes produce the same file as buffered writes
+// =============================================================================
+#[test]
+fn direct_writes_match_buffered() {
+    let dir = tempdir().unwrap();
+    let buffered = dir.path().join("000001.sst");
+    let direct = dir.path().join("000002.sst");
+    let creation_time = |path: &Path| SSTable::open(path).unwrap().meta().creation_time;
+    // Tables are stamped with their creation second: build again if the
+    // pair straddled a second boundary
+    loop {
+        build_table(&buffered, false);
+        build_table(&direct, true);
+        if creation_time(&buffered) == creation_time(&direct) {
+            break;
+        }
+    }
+
+    let expected = std::fs::read(&buffered).unwrap();
+    assert!(expected.len() > 2 * 1024 * 1024);
+    assert_eq!(std::fs::read(&direct).unwrap(), expected);
+}
+
+// =============================================================================
+// Test 2: Direct reads serve gets and scans at unaligned offsets
+// =============================================================================
+#[test]
+fn direct_reads() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, true);
+
+    let sst = SSTable::open_direct(&path).unwrap();
+    assert_eq!(sst.file_access(), FileAccess::Direct);
+    assert_eq!(sst.meta().entry_count, 30_000);
+    for i in [0, 1, 12_345, 29_999] {
+        assert_eq!(sst.get(&key(i)).unwrap().unwrap(), value(i).as_slice());
+    }
+    assert!(sst.get(b"key_12345x").unwrap().is_none());
+
+    let mut iter = sst.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.key(), key(count).as_slice());
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 30_000);
+}
+
+// =============================================================================
+// Test 3: DB with direct reads, flushes and compaction
+// =============================================================================
+#[test]
+fn db_direct_io_options() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        use_direct_reads: true,
+        use_direct_io_for_flush_and_compaction: true,
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for round in 0..4 {
+        for i in round * 200..(round + 1) * 200 {
+            db.put(&key(i), &value(i)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    db.compact_range(None, None).unwrap();
+
+    for i in [0, 199, 555, 799] {
+        assert_eq!(db.get(&key(i)).unwrap().unwrap(), value(i));
+    }
+    let mut iter = db.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 800);
+}
+
+// =============================================================================
+// Test 4: mmap and direct reads are mutually exclusive
+// =============================================================================
+#[test]
+fn mmap_and_direct_reads_conflict() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        use_mmap_reads: true,
+        use_direct_reads: true,
+        ..Options::default()
+    };
+    assert!(matches!(DB::open(dir.path(), opts), Err(Error::Io(_))));
+}
@@ -0,0 +1,143 @@
+// Disabled-WAL writes and manual WAL flush tests
+//
+// WriteOptions::disable_wal writes go to the memtable only: they survive a
+// flush or close, but not a crash before it. DB::flush_wal(sync) writes out
+// (and optionally fsyncs) the WAL on demand, whatever the SyncPolicy; with
+// Options::manual_wal_flush, logged writes wait in the process for it.
+
+use lsm_engine::wal::writer::WALManager;
+use lsm_engine::wal::{SyncPolicy, WALOptions, WALRecord};
+use lsm_engine::{CompactionStyle, DB, Options, WriteOptions};
+use tempfile::tempdir;
+
+const UNLOGGED: WriteOptions = WriteOptions {
+    disable_wal: true,
+    sync: false,
+};
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: Unlogged writes are lost by a crash, logged ones around them aren't
+// =============================================================================
+#[test]
+fn unlogged_writes_lost_on_crash() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.put(b"a", b"logged").unwrap();
+        db.put_with_options(b"b", b"unlogged", &UNLOGGED).unwrap();
+        db.delete_with_options(b"a", &UNLOGGED).unwrap();
+        db.put(b"c", b"logged").unwrap();
+        assert_eq!(db.get(b"b").unwrap(), Some(b"unlogged".to_vec()));
+        assert_eq!(db.get(b"a").unwrap(), None);
+        // Dropped without close(): nothing is flushed
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.get(b"a").unwrap(), Some(b"logged".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+    assert_eq!(db.get(b"c").unwrap(), Some(b"logged".to_vec()));
+}
+
+// =============================================================================
+// Test 2: A flush makes unlogged writes durable
+// =============================================================================
+#[test]
+fn flush_persists_unlogged_writes() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..100u32 {
+            db.put_with_options(format!("key_{i:03}").as_bytes(), b"v", &UNLOGGED)
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.get(b"key_042").unwrap(), Some(b"v".to_vec()));
+}
+
+// =============================================================================
+// Test 3: get_updates_since() passes over unlogged writes without an error
+// =============================================================================
+#[test]
+fn updates_skip_unlogged_writes() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"k1", b"v").unwrap();
+    db.put(b"k2", b"v").unwrap();
+    db.put_with_options(b"k3", b"v", &UNLOGGED).unwrap();
+    db.put_with_options(b"k4", b"v", &UNLOGGED).unwrap();
+    db.put(b"k5", b"v").unwrap();
+
+    let sequences = |since| -> Vec<u64> {
+        db.get_updates_since(since)
+            .unwrap()
+            .map(|u| u.unwrap().sequence)
+            .collect()
+    };
+    assert_eq!(sequences(1), vec![1, 2, 5]);
+    assert_eq!(sequences(3), vec![5]);
+}
+
+// =============================================================================
+// Test 4: Flushing the WAL by hand, with and without fsync
+// =============================================================================
+#[test]
+fn manual_wal_flush() {
+    let on_disk = |manager: &WALManager| std::fs::metadata(manager.active_path()).unwrap().len();
+    let append = |manager: &mut WALManager| {
+        for i in 0..10u64 {
+            manager
+                .append(i + 1, &WALRecord::put(vec![i as u8], b"v".to_vec()))
+                .unwrap();
+        }
+    };
+
+    // By default each record reaches the OS as it's appended, synced or not
+    let dir = tempdir().unwrap();
+    let mut manager = WALManager::new(dir.path(), SyncPolicy::EveryNWrites(1000)).unwrap();
+    append(&mut manager);
+    assert_eq!(on_disk(&manager), manager.active_writer().offset());
+
+    // With manual_flush they stay buffered in the process until flushed
+    let dir = tempdir().unwrap();
+    let wal_options = WALOptions {
+        sync_policy: SyncPolicy::EveryNWrites(1000),
+        manual_flush: true,
+        ..WALOptions::default()
+    };
+    let mut manager = WALManager::with_options(dir.path(), wal_options).unwrap();
+    append(&mut manager);
+    assert_eq!(on_disk(&manager), 0);
+    manager.flush(false).unwrap();
+    assert_eq!(on_disk(&manager), manager.active_writer().offset());
+    // The Sequence record counts as a write
+    assert_eq!(manager.active_writer().writes_since_sync(), 11);
+    manager.flush(true).unwrap();
+    assert_eq!(manager.active_writer().writes_since_sync(), 0);
+
+    let db_dir = tempdir().unwrap();
+    let db = DB::open(
+        db_dir.path(),
+        Options {
+            sync_policy: SyncPolicy::EveryNWrites(1000),
+            manual_wal_flush: true,
+            ..options()
+        },
+    )
+    .unwrap();
+    db.put(b"key", b"value").unwrap();
+    db.flush_wal(true).unwrap();
+    drop(db);
+    let db = DB::open(db_dir.path(), options()).unwrap();
+    assert_eq!(db.get(b"key").unwrap(), Some(b"value".to_vec()));
+}
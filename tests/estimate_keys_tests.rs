@@ -0,0 +1,96 @@
+// Key-count estimation tests
+//
+// estimate_num_keys_in_range() combines memtable counts with SSTable
+// index/block sampling, so it should be close to the true count without
+// scanning every block.
+
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+// =============================================================================
+// Test 1: Empty DB and empty ranges estimate zero
+// =============================================================================
+#[test]
+fn estimate_empty() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert_eq!(db.estimate_num_keys_in_range(b"a", b"z").unwrap(), 0);
+
+    db.put(b"m", b"v").unwrap();
+    assert_eq!(db.estimate_num_keys_in_range(b"x", b"z").unwrap(), 0);
+    assert_eq!(db.estimate_num_keys_in_range(b"z", b"a").unwrap(), 0);
+}
+
+// =============================================================================
+// Test 2: Memtable-only data is counted exactly, minus tombstones
+// =============================================================================
+#[test]
+fn estimate_memtable_exact() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for i in 0..100 {
+        db.put(&key(i), b"v").unwrap();
+    }
+    db.delete(&key(10)).unwrap();
+    assert_eq!(
+        db.estimate_num_keys_in_range(&key(0), &key(20)).unwrap(),
+        19
+    );
+}
+
+// =============================================================================
+// Test 3: SSTable estimate is exact for whole tables and close for slices
+// =============================================================================
+#[test]
+fn estimate_sstable_partial_range() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 256).unwrap();
+    for i in 0..2000 {
+        builder.add(&key(i), b"value").unwrap();
+    }
+    builder.finish().unwrap();
+    let sst = SSTable::open(&path).unwrap();
+
+    assert_eq!(sst.estimate_keys_in_range(b"a", b"z").unwrap(), 2000);
+    assert_eq!(sst.estimate_keys_in_range(b"a", b"b").unwrap(), 0);
+
+    // Boundaries inside a single block are counted exactly
+    assert_eq!(sst.estimate_keys_in_range(&key(3), &key(7)).unwrap(), 4);
+
+    let estimate = sst.estimate_keys_in_range(&key(300), &key(1500)).unwrap();
+    assert!((1100..=1300).contains(&estimate), "estimate {estimate}");
+}
+
+// =============================================================================
+// Test 4: DB estimate spans memtable and several SSTables
+// =============================================================================
+#[test]
+fn estimate_across_sources() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        block_size: 256,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..3000 {
+        db.put(&key(i), b"value").unwrap();
+        if i % 1000 == 999 {
+            db.flush().unwrap();
+        }
+    }
+    for i in 3000..3200 {
+        db.put(&key(i), b"value").unwrap();
+    }
+
+    let estimate = db
+        .estimate_num_keys_in_range(&key(500), &key(3100))
+        .unwrap();
+    assert!((2400..=2800).contains(&estimate), "estimate {estimate}");
+}
@@ -0,0 +1,184 @@
+// Flush job tests
+//
+// FlushJob writes immutable memtables to L0 SSTables, tombstones
+// included, and returns their metadata (plus any blob file) for the
+// caller to record; DB flushes run one before editing the manifest.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+
+use lsm_engine::blob::BlobIndex;
+use lsm_engine::blob::reader::read_blob_value;
+use lsm_engine::db::flush::FlushJob;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::manifest::version::VersionSet;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::sstable::builder::TableOptions;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::types::ValueType;
+use tempfile::tempdir;
+
+fn read_table(dir: &Path, meta: &SSTableMeta) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let sst = SSTable::open(&dir.join(format!("{:06}.sst", meta.id))).unwrap();
+    let mut entries = Vec::new();
+    let mut iter = sst.iter().unwrap();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    entries
+}
+
+fn entry(key: &[u8], value: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    (key.to_vec(), value.to_vec())
+}
+
+// =============================================================================
+// Test 1: One memtable becomes one L0 table, tombstones kept
+// =============================================================================
+#[test]
+fn flush_one_memtable() {
+    let dir = tempdir().unwrap();
+    let vs = VersionSet::new(7);
+    let mt = MemTable::new(1024 * 1024);
+    mt.add(5, ValueType::Put, b"b".to_vec(), b"b5".to_vec());
+    mt.add(6, ValueType::Delete, b"c".to_vec(), Vec::new());
+    mt.add(7, ValueType::Put, b"a".to_vec(), b"a7".to_vec());
+    mt.add(8, ValueType::Put, b"b".to_vec(), b"b8".to_vec());
+    let memtables = [Arc::new(mt)];
+
+    let output = FlushJob::new(dir.path(), &vs, &TableOptions::default(), &memtables)
+        .run()
+        .unwrap();
+    assert!(output.blob_file.is_none());
+    assert_eq!(output.tables.len(), 1);
+    let meta = &output.tables[0];
+    assert_eq!(meta.level, 0);
+    assert_eq!(
+        (meta.min_key.as_slice(), meta.max_key.as_slice()),
+        (&b"a"[..], &b"c"[..])
+    );
+    assert_eq!(meta.entry_count, 3);
+    assert_eq!((meta.smallest_seqno, meta.largest_seqno), (5, 8));
+    assert_eq!(
+        read_table(dir.path(), meta),
+        vec![entry(b"a", b"a7"), entry(b"b", b"b8"), entry(b"c", b"")]
+    );
+
+    // Nothing is installed: that's the caller's job
+    assert!(vs.current().read().unwrap().levels[0].is_empty());
+}
+
+// =============================================================================
+// Test 2: Several memtables are merged, the newest winning
+// =============================================================================
+#[test]
+fn flush_merges_memtables() {
+    let dir = tempdir().unwrap();
+    let vs = VersionSet::new(7);
+    let older = MemTable::new(1024 * 1024);
+    older.add(1, ValueType::Put, b"a".to_vec(), b"old".to_vec());
+    older.add(2, ValueType::Put, b"b".to_vec(), b"old".to_vec());
+    let newer = MemTable::new(1024 * 1024);
+    newer.add(3, ValueType::Delete, b"a".to_vec(), Vec::new());
+    newer.add(4, ValueType::Put, b"c".to_vec(), b"new".to_vec());
+    let memtables = [Arc::new(older), Arc::new(newer)];
+
+    let output = FlushJob::new(dir.path(), &vs, &TableOptions::default(), &memtables)
+        .run()
+        .unwrap();
+    let meta = &output.tables[0];
+    assert_eq!((meta.smallest_seqno, meta.largest_seqno), (1, 4));
+    assert_eq!(
+        read_table(dir.path(), meta),
+        vec![entry(b"a", b""), entry(b"b", b"old"), entry(b"c", b"new")]
+    );
+
+    // Empty memtables write nothing
+    let empty = [Arc::new(MemTable::new(1024))];
+    let output = FlushJob::new(dir.path(), &vs, &TableOptions::default(), &empty)
+        .run()
+        .unwrap();
+    assert!(output.tables.is_empty());
+}
+
+// =============================================================================
+// Test 3: Large values go to a blob file
+// =============================================================================
+#[test]
+fn flush_separates_large_values() {
+    let dir = tempdir().unwrap();
+    let vs = VersionSet::new(7);
+    let mt = MemTable::new(1024 * 1024);
+    mt.put(b"big".to_vec(), vec![b'x'; 1000]);
+    mt.put(b"small".to_vec(), b"v".to_vec());
+    let memtables = [Arc::new(mt)];
+
+    let next_blob_file_number = AtomicU64::new(9);
+    let output = FlushJob::new(dir.path(), &vs, &TableOptions::default(), &memtables)
+        .with_blob_files(Some(100), &next_blob_file_number)
+        .run()
+        .unwrap();
+    let blob_file = output.blob_file.unwrap();
+    assert_eq!(blob_file.file_number, 9);
+    assert_eq!(blob_file.record_count, 1);
+
+    let entries = read_table(dir.path(), &output.tables[0]);
+    assert_eq!(entries[1], entry(b"small", b"v"));
+    let index = BlobIndex::decode(&entries[0].1).unwrap();
+    assert_eq!(
+        read_blob_value(dir.path(), &index).unwrap(),
+        vec![b'x'; 1000]
+    );
+}
+
+/// Each entry of a flushed table with the sequence number it stores.
+fn read_table_seqnos(dir: &Path, meta: &SSTableMeta) -> Vec<(Vec<u8>, Option<u64>)> {
+    let sst = SSTable::open(&dir.join(format!("{:06}.sst", meta.id))).unwrap();
+    let mut entries = Vec::new();
+    let mut iter = sst.iter().unwrap().with_seqnos().unwrap();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.seqno()));
+        iter.next().unwrap();
+    }
+    entries
+}
+
+// =============================================================================
+// Test 4: Flushed tables store each entry's sequence number
+// =============================================================================
+#[test]
+fn flush_keeps_entry_seqnos() {
+    let dir = tempdir().unwrap();
+    let vs = VersionSet::new(7);
+
+    // One memtable: each key's newest version, with its own sequence
+    let mt = MemTable::new(1024 * 1024);
+    mt.add(3, ValueType::Put, b"a".to_vec(), b"a3".to_vec());
+    mt.add(4, ValueType::Put, b"b".to_vec(), b"b4".to_vec());
+    mt.add(6, ValueType::Delete, b"a".to_vec(), Vec::new());
+    let output = FlushJob::new(dir.path(), &vs, &TableOptions::default(), &[Arc::new(mt)])
+        .run()
+        .unwrap();
+    assert_eq!(
+        read_table_seqnos(dir.path(), &output.tables[0]),
+        vec![(b"a".to_vec(), Some(6)), (b"b".to_vec(), Some(4))]
+    );
+
+    // Several: the winning memtable's sequence comes along with its value
+    let older = MemTable::new(1024 * 1024);
+    older.add(10, ValueType::Put, b"a".to_vec(), b"old".to_vec());
+    older.add(11, ValueType::Put, b"b".to_vec(), b"old".to_vec());
+    let newer = MemTable::new(1024 * 1024);
+    newer.add(12, ValueType::Put, b"a".to_vec(), b"new".to_vec());
+    let memtables = [Arc::new(older), Arc::new(newer)];
+    let output = FlushJob::new(dir.path(), &vs, &TableOptions::default(), &memtables)
+        .run()
+        .unwrap();
+    assert_eq!(
+        read_table_seqnos(dir.path(), &output.tables[0]),
+        vec![(b"a".to_vec(), Some(12)), (b"b".to_vec(), Some(11))]
+    );
+}
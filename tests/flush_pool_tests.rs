@@ -0,0 +1,110 @@
+// Flush thread pool tests
+//
+// Options::max_background_flushes workers run only flushes, so a full
+// memtable is flushed even while a long compaction holds every
+// Options::max_background_jobs worker. Without them, the flush waits for
+// a worker and writes stall behind the compaction.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use lsm_engine::compaction::filter::{CompactionFilter, FilterDecision};
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// Blocks every compaction until released.
+#[derive(Default)]
+struct Gate {
+    open: Mutex<bool>,
+    opened: Condvar,
+}
+
+impl Gate {
+    fn release(&self) {
+        *self.open.lock().unwrap() = true;
+        self.opened.notify_all();
+    }
+}
+
+impl CompactionFilter for Gate {
+    fn filter(&self, _level: u32, _key: &[u8], _value: &[u8]) -> FilterDecision {
+        let mut open = self.open.lock().unwrap();
+        while !*open {
+            open = self.opened.wait(open).unwrap();
+        }
+        FilterDecision::Keep
+    }
+}
+
+/// One compaction worker, stuck in a compaction until `gate` opens.
+fn open_with_stuck_compaction(dir: &std::path::Path, gate: &Arc<Gate>, flushers: usize) -> DB {
+    let db = DB::open(
+        dir,
+        Options {
+            memtable_size: 4 * 1024,
+            compaction_style: CompactionStyle::SizeTiered,
+            level0_file_num_compaction_trigger: 2,
+            compaction_filter: Some(Arc::clone(gate) as Arc<dyn CompactionFilter>),
+            max_background_jobs: 1,
+            max_background_flushes: flushers,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    for i in 0..2 {
+        db.put(&key(i), b"value").unwrap();
+        db.flush().unwrap();
+    }
+    std::thread::sleep(Duration::from_millis(50));
+    db
+}
+
+/// Write enough to fill several memtables.
+fn fill_memtables(db: &DB) {
+    for i in 100..1100 {
+        db.put(&key(i), &[b'v'; 100]).unwrap();
+    }
+}
+
+// =============================================================================
+// Test 1: Flush workers keep writes going while compaction is stuck
+// =============================================================================
+#[test]
+fn flushes_run_beside_long_compaction() {
+    let dir = tempdir().unwrap();
+    let gate = Arc::new(Gate::default());
+    let db = open_with_stuck_compaction(dir.path(), &gate, 1);
+
+    std::thread::scope(|s| {
+        let writer = s.spawn(|| fill_memtables(&db));
+        std::thread::sleep(Duration::from_millis(500));
+        let done = writer.is_finished();
+        gate.release();
+        assert!(done, "writes stalled behind the compaction");
+    });
+    db.wait_for_background_jobs().unwrap();
+    assert_eq!(db.get(&key(1099)).unwrap(), Some(vec![b'v'; 100]));
+}
+
+// =============================================================================
+// Test 2: Without flush workers the flush waits for the compaction
+// =============================================================================
+#[test]
+fn flushes_share_workers_when_disabled() {
+    let dir = tempdir().unwrap();
+    let gate = Arc::new(Gate::default());
+    let db = open_with_stuck_compaction(dir.path(), &gate, 0);
+
+    std::thread::scope(|s| {
+        let writer = s.spawn(|| fill_memtables(&db));
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!writer.is_finished());
+        gate.release();
+    });
+    db.wait_for_background_jobs().unwrap();
+    assert_eq!(db.get(&key(1099)).unwrap(), Some(vec![b'v'; 100]));
+}
@@ -0,0 +1,322 @@
+// SSTable footer format_version tests
+//
+// The footer records the table's format version; readers pick the
+// footer layout from the magic number and then dispatch on the version,
+// so files from older layouts stay readable. Since version 5 the footer
+// carries its own CRC, and a table whose tail is torn opens from the last
+// intact footer.
+
+use std::io::Write;
+use std::path::Path;
+
+use lsm_engine::bloom::BloomFilter;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::footer::{FORMAT_VERSION, Footer, LEGACY_SSTABLE_MAGIC, SSTABLE_MAGIC};
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{ChecksumType, Error};
+use tempfile::tempdir;
+
+/// Block contents in the pre-version-4 layout: [key_len(2B)][val_len(2B)]
+/// [key][value] entries, then 2-byte offsets and count.
+fn legacy_block(entries: &[(&[u8], &[u8])]) -> Vec<u8> {
+    let mut block = Vec::new();
+    let mut offsets = Vec::new();
+    for (key, value) in entries {
+        offsets.push(block.len() as u16);
+        block.extend_from_slice(&(key.len() as u16).to_le_bytes());
+        block.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        block.extend_from_slice(key);
+        block.extend_from_slice(value);
+    }
+    for offset in &offsets {
+        block.extend_from_slice(&offset.to_le_bytes());
+    }
+    block.extend_from_slice(&(offsets.len() as u16).to_le_bytes());
+    block
+}
+
+/// Index entry in the pre-version-4 layout: [key_len(2B)][key][offset][size]
+fn legacy_index_entry(last_key: &[u8], offset: u64, size: u64) -> Vec<u8> {
+    let mut entry = (last_key.len() as u16).to_le_bytes().to_vec();
+    entry.extend_from_slice(last_key);
+    entry.extend_from_slice(&offset.to_le_bytes());
+    entry.extend_from_slice(&size.to_le_bytes());
+    entry
+}
+
+/// Write a format version 1 table by hand: bare block contents (no type
+/// byte, no checksum trailer), meta, bloom, index, 56-byte footer.
+fn write_v1_table(path: &Path, keys: &[&[u8]]) {
+    let mut file = std::fs::File::create(path).unwrap();
+    let mut offset = 0u64;
+    let mut index = Vec::new();
+    let mut bloom = BloomFilter::new(keys.len(), 0.01);
+
+    // Two keys per block
+    for chunk in keys.chunks(2) {
+        let entries: Vec<(&[u8], &[u8])> = chunk.iter().map(|key| (*key, &b"old"[..])).collect();
+        for key in chunk {
+            bloom.insert(key);
+        }
+        let contents = legacy_block(&entries);
+        file.write_all(&contents).unwrap();
+        index.extend_from_slice(&legacy_index_entry(
+            chunk.last().unwrap(),
+            offset,
+            contents.len() as u64,
+        ));
+        offset += contents.len() as u64;
+    }
+
+    let mut meta = Vec::new();
+    meta.extend_from_slice(&7u64.to_le_bytes());
+    meta.extend_from_slice(&0u32.to_le_bytes());
+    for key in [keys[0], keys[keys.len() - 1]] {
+        meta.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        meta.extend_from_slice(key);
+    }
+    meta.extend_from_slice(&(keys.len() as u64).to_le_bytes());
+    let bloom = bloom.serialize();
+
+    let meta_block_offset = offset;
+    let bloom_block_offset = meta_block_offset + meta.len() as u64;
+    let index_block_offset = bloom_block_offset + bloom.len() as u64;
+    let footer = Footer {
+        index_block_offset,
+        index_block_size: index.len() as u64,
+        meta_block_offset,
+        meta_block_size: meta.len() as u64,
+        bloom_block_offset,
+        bloom_block_size: bloom.len() as u64,
+        properties_block_offset: 0,
+        properties_block_size: 0,
+        range_del_block_offset: 0,
+        range_del_block_size: 0,
+        checksum: ChecksumType::Crc32,
+        seqno_block_offset: 0,
+        seqno_block_size: 0,
+        format_version: 1,
+        magic: LEGACY_SSTABLE_MAGIC,
+    };
+    for part in [meta, bloom, index, footer.encode()] {
+        file.write_all(&part).unwrap();
+    }
+}
+
+// =============================================================================
+// Test 1: New tables are written with the current format version
+// =============================================================================
+#[test]
+fn new_tables_use_current_version() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 4096).unwrap();
+    builder.add(b"a", b"1").unwrap();
+    builder.finish().unwrap();
+
+    let data = std::fs::read(&path).unwrap();
+    let footer = Footer::decode(&data[data.len() - Footer::SIZE..]).unwrap();
+    assert_eq!(footer.format_version, FORMAT_VERSION);
+    assert_eq!(footer.magic, SSTABLE_MAGIC);
+    assert_eq!(
+        SSTable::open(&path).unwrap().format_version(),
+        FORMAT_VERSION
+    );
+}
+
+// =============================================================================
+// Test 2: Legacy footers encode to the old size and decode as version 1
+// =============================================================================
+#[test]
+fn legacy_footer_round_trip() {
+    let footer = Footer {
+        index_block_offset: 100,
+        index_block_size: 20,
+        meta_block_offset: 40,
+        meta_block_size: 30,
+        bloom_block_offset: 70,
+        bloom_block_size: 30,
+        properties_block_offset: 0,
+        properties_block_size: 0,
+        range_del_block_offset: 0,
+        range_del_block_size: 0,
+        checksum: ChecksumType::Crc32,
+        seqno_block_offset: 0,
+        seqno_block_size: 0,
+        format_version: 1,
+        magic: LEGACY_SSTABLE_MAGIC,
+    };
+    let encoded = footer.encode();
+    assert_eq!(encoded.len(), Footer::LEGACY_SIZE);
+
+    // Decoding from a longer tail (as readers do) finds the same footer
+    let mut tail = vec![0xAB; 8];
+    tail.extend_from_slice(&encoded);
+    let decoded = Footer::decode(&tail).unwrap();
+    assert_eq!(decoded.format_version, 1);
+    assert_eq!(decoded.index_block_offset, 100);
+    assert_eq!(decoded.bloom_block_size, 30);
+}
+
+// =============================================================================
+// Test 3: Version 1 tables (bare blocks) are still readable
+// =============================================================================
+#[test]
+fn v1_table_readable() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000007.sst");
+    let keys: Vec<&[u8]> = vec![b"a", b"b", b"c", b"d", b"e"];
+    write_v1_table(&path, &keys);
+
+    let sst = SSTable::open(&path).unwrap();
+    assert_eq!(sst.format_version(), 1);
+    assert_eq!(sst.num_blocks(), 3);
+    assert!(sst.properties().is_none());
+    assert_eq!(sst.get(b"d").unwrap().unwrap(), &b"old"[..]);
+    assert!(sst.get(b"bb").unwrap().is_none());
+
+    let mut iter = sst.iter().unwrap();
+    let mut seen = Vec::new();
+    while iter.is_valid() {
+        seen.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    assert_eq!(seen, keys.iter().map(|k| k.to_vec()).collect::<Vec<_>>());
+}
+
+// =============================================================================
+// Test 4: Versions newer than the reader are rejected clearly
+// =============================================================================
+#[test]
+fn future_version_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 4096).unwrap();
+    builder.add(b"a", b"1").unwrap();
+    builder.finish().unwrap();
+
+    let mut data = std::fs::read(&path).unwrap();
+    let version_at = data.len() - 16;
+    data[version_at..version_at + 4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    std::fs::write(&path, data).unwrap();
+
+    match SSTable::open(&path) {
+        Err(Error::Corruption(msg)) => assert!(msg.contains("format_version"), "{msg}"),
+        Err(e) => panic!("unexpected error {e}"),
+        Ok(_) => panic!("future format_version was accepted"),
+    }
+}
+
+/// Build a three-key table at `path` and return its bytes.
+fn build_abc_table(path: &Path) -> Vec<u8> {
+    let mut builder = SSTableBuilder::new(path, 1, 4096).unwrap();
+    for key in [b"a", b"b", b"c"] {
+        builder.add(key, b"value").unwrap();
+    }
+    builder.finish().unwrap();
+    std::fs::read(path).unwrap()
+}
+
+// =============================================================================
+// Test 5: Corrupt footer fields fail the footer checksum
+// =============================================================================
+#[test]
+fn footer_checksum_detects_corruption() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut data = build_abc_table(&path);
+
+    // Flip a bit in the index block size
+    let index_size_at = data.len() - Footer::SIZE + 8;
+    data[index_size_at] ^= 0x01;
+    match Footer::decode(&data[data.len() - Footer::SIZE..]) {
+        Err(Error::Corruption(msg)) => assert!(msg.contains("checksum"), "{msg}"),
+        other => panic!("expected a checksum error, got {other:?}"),
+    }
+
+    // No earlier footer to fall back to
+    std::fs::write(&path, &data).unwrap();
+    assert!(matches!(SSTable::open(&path), Err(Error::Corruption(_))));
+}
+
+// =============================================================================
+// Test 6: A garbled tail falls back to the last intact footer
+// =============================================================================
+#[test]
+fn torn_tail_recovers_previous_footer() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let data = build_abc_table(&path);
+    let table_len = data.len() as u64;
+
+    // Junk after the footer, ending in half of another footer
+    let mut torn = data.clone();
+    torn.extend_from_slice(&[0u8; 23]);
+    torn.extend_from_slice(&data[data.len() - 40..]);
+    let (end, footer) = Footer::find_last_valid(&torn).unwrap();
+    assert_eq!(end, table_len);
+    assert_eq!(footer.format_version, FORMAT_VERSION);
+
+    std::fs::write(&path, &torn).unwrap();
+    let sst = SSTable::open(&path).unwrap();
+    assert_eq!(sst.meta().file_size, table_len);
+    assert_eq!(sst.get(b"b").unwrap().unwrap(), &b"value"[..]);
+    let mut iter = sst.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 3);
+}
+
+// =============================================================================
+// Test 7: Recovery scans long tails in chunks, footers across their edges
+// =============================================================================
+#[test]
+fn long_torn_tail_recovers_previous_footer() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let data = build_abc_table(&path);
+    let table_len = data.len() as u64;
+
+    // The reader scans back 64KB at a time: junk around that long puts
+    // the footer across a chunk boundary, and longer takes several chunks
+    let chunk = 64 * 1024;
+    let junk_lens = (chunk - Footer::SIZE - 2..chunk + 2).chain([3 * chunk + 17]);
+    for junk_len in junk_lens {
+        let mut torn = data.clone();
+        torn.resize(data.len() + junk_len, 0xAB);
+        std::fs::write(&path, &torn).unwrap();
+        let sst = SSTable::open(&path).unwrap();
+        assert_eq!(sst.meta().file_size, table_len, "junk {junk_len}");
+        assert_eq!(sst.get(b"c").unwrap().unwrap(), &b"value"[..]);
+    }
+}
+
+// =============================================================================
+// Test 8: Version 9 footers (no sequence number block) are still readable
+// =============================================================================
+#[test]
+fn v9_table_readable() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let data = build_abc_table(&path);
+
+    // Same blocks, behind the shorter version 9 footer
+    let (blocks, tail) = data.split_at(data.len() - Footer::SIZE);
+    let mut footer = Footer::decode(tail).unwrap();
+    footer.format_version = 9;
+    let encoded = footer.encode();
+    assert_eq!(encoded.len(), Footer::V9_SIZE);
+    let mut v9 = blocks.to_vec();
+    v9.extend_from_slice(&encoded);
+    std::fs::write(&path, &v9).unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    assert_eq!(sst.format_version(), 9);
+    assert_eq!(sst.footer().seqno_block_size, 0);
+    assert!(sst.entry_seqnos().unwrap().is_none());
+    assert_eq!(sst.get(b"b").unwrap().unwrap(), &b"value"[..]);
+}
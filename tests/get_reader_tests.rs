@@ -0,0 +1,128 @@
+// Streaming read tests
+//
+// DB::get_reader() returns a ValueReader instead of the value: blob file
+// values are read from disk as the reader is consumed, other values are
+// read from the memtable or block they already live in.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::FileExt;
+
+use lsm_engine::blob::blob_file_path;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+/// 3MB value, large enough to go to a blob file.
+fn large_value() -> Vec<u8> {
+    (0..3 * 1024 * 1024).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+fn blob_options() -> Options {
+    Options {
+        enable_blob_files: true,
+        min_blob_size: 1024,
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    }
+}
+
+/// Read `reader` to the end in 64KB chunks.
+fn read_in_chunks(reader: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            return Ok(out);
+        }
+        out.extend_from_slice(&chunk[..n]);
+    }
+}
+
+// =============================================================================
+// Test 1: Blob values stream from the blob file
+// =============================================================================
+#[test]
+fn get_reader_streams_blob_value() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    db.put(b"big", &large_value()).unwrap();
+
+    // Still in the memtable
+    let mut reader = db.get_reader(b"big").unwrap().unwrap();
+    assert!(!reader.is_blob());
+    assert_eq!(reader.len(), large_value().len() as u64);
+    assert_eq!(read_in_chunks(&mut reader).unwrap(), large_value());
+
+    db.flush().unwrap();
+    let mut reader = db.get_reader(b"big").unwrap().unwrap();
+    assert!(reader.is_blob());
+    assert_eq!(reader.len(), large_value().len() as u64);
+    assert_eq!(read_in_chunks(&mut reader).unwrap(), large_value());
+}
+
+// =============================================================================
+// Test 2: Inline, missing and deleted values
+// =============================================================================
+#[test]
+fn get_reader_inline_and_missing_values() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    db.put(b"small", b"inline value").unwrap();
+    db.put(b"gone", b"soon deleted").unwrap();
+    db.flush().unwrap();
+    db.delete(b"gone").unwrap();
+    db.flush().unwrap();
+
+    let mut reader = db.get_reader(b"small").unwrap().unwrap();
+    assert!(!reader.is_blob());
+    let mut value = Vec::new();
+    reader.read_to_end(&mut value).unwrap();
+    assert_eq!(value, b"inline value");
+
+    assert!(db.get_reader(b"gone").unwrap().is_none());
+    assert!(db.get_reader(b"never").unwrap().is_none());
+}
+
+// =============================================================================
+// Test 3: Corrupt blob records fail at the end of the stream
+// =============================================================================
+#[test]
+fn get_reader_detects_corruption() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    db.put(b"big", &large_value()).unwrap();
+    db.flush().unwrap();
+
+    // Flip a byte in the middle of the value
+    let file = OpenOptions::new()
+        .write(true)
+        .open(blob_file_path(dir.path(), 1))
+        .unwrap();
+    file.write_all_at(&[0xAA], 1024 * 1024).unwrap();
+
+    let mut reader = db.get_reader(b"big").unwrap().unwrap();
+    let err = read_in_chunks(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+// =============================================================================
+// Test 4: An open reader outlives garbage collection of its blob file
+// =============================================================================
+#[test]
+fn get_reader_survives_blob_gc() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), blob_options()).unwrap();
+    db.put(b"big", &large_value()).unwrap();
+    db.flush().unwrap();
+
+    let mut reader = db.get_reader(b"big").unwrap().unwrap();
+    db.put(b"big", b"replaced").unwrap();
+    db.flush().unwrap();
+    assert_eq!(db.garbage_collect_blobs().unwrap(), 1);
+    db.put(b"other", b"v").unwrap();
+    db.flush().unwrap();
+    assert!(!blob_file_path(dir.path(), 1).exists());
+
+    assert_eq!(read_in_chunks(&mut reader).unwrap(), large_value());
+}
@@ -0,0 +1,145 @@
+// Index separator tests
+//
+// Index entries store the shortest key that still separates a block from
+// the next one instead of the block's full last key. Lookups that land
+// between a block's last key and its separator must still find the right
+// entry, and long keys must no longer bloat the index.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::index::{find_short_successor, find_shortest_separator};
+use lsm_engine::sstable::reader::SSTable;
+use tempfile::tempdir;
+
+/// Even-numbered keys with a long shared suffix.
+fn long_key(i: u32) -> Vec<u8> {
+    format!("{:05}_{}", i * 2, "x".repeat(200)).into_bytes()
+}
+
+fn build_table(path: &Path, count: u32) {
+    let mut builder = SSTableBuilder::new(path, 1, 1024).unwrap();
+    for i in 0..count {
+        builder
+            .add(&long_key(i), format!("v{i}").as_bytes())
+            .unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+// =============================================================================
+// Test 1: Separator and successor edge cases
+// =============================================================================
+#[test]
+fn separator_cases() {
+    let cases: [(&[u8], &[u8], &[u8]); 7] = [
+        (b"abc", b"abe", b"abd"),
+        (b"apple_pie", b"banana", b"aq"),
+        (b"apple", b"cherry", b"b"),
+        (b"key_00099", b"key_00100", b"key_000:"),
+        (b"ab", b"abc", b"ab"),
+        (b"ab\xff\xff", b"ac", b"ab\xff\xff"),
+        (b"ab\xffz", b"ac", b"ab\xff{"),
+    ];
+    for (start, limit, expected) in cases {
+        let separator = find_shortest_separator(start, limit);
+        assert_eq!(separator, expected, "{start:?} {limit:?}");
+        assert!(start <= separator.as_slice() && separator.as_slice() < limit);
+    }
+
+    assert_eq!(find_short_successor(b"key_99"), b"l");
+    assert_eq!(find_short_successor(b"\xff\xffa"), b"\xff\xffb");
+    assert_eq!(find_short_successor(b"\xff\xff"), b"\xff\xff");
+    assert_eq!(find_short_successor(b""), b"");
+}
+
+// =============================================================================
+// Test 2: Long keys no longer inflate the index
+// =============================================================================
+#[test]
+fn long_keys_shrink_index() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 2000);
+
+    let sst = SSTable::open(&path).unwrap();
+    let num_blocks = sst.num_blocks() as u64;
+    assert!(num_blocks > 100);
+
+    // Full last keys would take over 200 bytes per entry
+    let index_size = sst.properties().unwrap().index_size;
+    assert!(
+        index_size < num_blocks * 40,
+        "{index_size} bytes for {num_blocks} blocks"
+    );
+}
+
+// =============================================================================
+// Test 3: Gets and seeks for keys between a block's end and its separator
+// =============================================================================
+#[test]
+fn lookups_between_blocks() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 2000);
+    let sst = SSTable::open(&path).unwrap();
+
+    for i in (0..2000).step_by(37) {
+        assert_eq!(
+            sst.get(&long_key(i)).unwrap().unwrap(),
+            format!("v{i}").as_bytes()
+        );
+
+        // Just past key i: sorts after it and before key i + 1
+        let mut gap = long_key(i);
+        gap.push(0);
+        assert!(sst.get(&gap).unwrap().is_none());
+
+        let mut iter = sst.iter().unwrap();
+        iter.seek(&gap).unwrap();
+        if i + 1 < 2000 {
+            assert_eq!(iter.key(), long_key(i + 1).as_slice());
+        } else {
+            assert!(!iter.is_valid());
+        }
+
+        iter.seek_for_prev(&gap).unwrap();
+        assert_eq!(iter.key(), long_key(i).as_slice());
+    }
+
+    // Past the last key but below the last block's separator
+    let mut iter = sst.iter().unwrap();
+    iter.seek(b"03999").unwrap();
+    assert!(!iter.is_valid());
+    iter.seek_for_prev(b"03999").unwrap();
+    assert_eq!(iter.key(), long_key(1999).as_slice());
+}
+
+// =============================================================================
+// Test 4: Partitioned indexes use separators at both levels
+// =============================================================================
+#[test]
+fn partitioned_index_separators() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 20_000);
+    let sst = SSTable::open(&path).unwrap();
+    assert!(sst.index().is_partitioned());
+
+    for i in [0, 4321, 10_000, 19_999] {
+        assert_eq!(
+            sst.get(&long_key(i)).unwrap().unwrap(),
+            format!("v{i}").as_bytes()
+        );
+    }
+
+    let mut iter = sst.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.key(), long_key(count).as_slice());
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 20_000);
+}
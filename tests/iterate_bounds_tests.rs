@@ -0,0 +1,180 @@
+// Iterate bounds tests
+//
+// ReadOptions::iterate_lower_bound / iterate_upper_bound restrict DB and
+// SSTable iterators to [lower, upper).
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn bounds(lower: Option<&[u8]>, upper: Option<&[u8]>) -> ReadOptions {
+    ReadOptions {
+        iterate_lower_bound: lower.map(|k| k.to_vec()),
+        iterate_upper_bound: upper.map(|k| k.to_vec()),
+        ..ReadOptions::default()
+    }
+}
+
+fn collect_keys(iter: &mut dyn StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+/// DB with keys a..=j; a..e flushed to an SSTable, f..j in the memtable.
+fn populated_db() -> (tempfile::TempDir, DB) {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for c in b'a'..=b'e' {
+        db.put(&[c], &[c]).unwrap();
+    }
+    db.flush().unwrap();
+    for c in b'f'..=b'j' {
+        db.put(&[c], &[c]).unwrap();
+    }
+    (dir, db)
+}
+
+// =============================================================================
+// Test 1: Both bounds → yields exactly [lower, upper)
+// =============================================================================
+#[test]
+fn db_iter_respects_both_bounds() {
+    let (_dir, db) = populated_db();
+    let mut iter = db
+        .iter_with_options(&bounds(Some(b"c"), Some(b"h")))
+        .unwrap();
+    assert_eq!(
+        collect_keys(&mut iter),
+        vec![
+            b"c".to_vec(),
+            b"d".to_vec(),
+            b"e".to_vec(),
+            b"f".to_vec(),
+            b"g".to_vec()
+        ]
+    );
+}
+
+// =============================================================================
+// Test 2: Only one bound set → the other side stays open
+// =============================================================================
+#[test]
+fn db_iter_single_bound() {
+    let (_dir, db) = populated_db();
+
+    let mut iter = db.iter_with_options(&bounds(Some(b"i"), None)).unwrap();
+    assert_eq!(collect_keys(&mut iter), vec![b"i".to_vec(), b"j".to_vec()]);
+
+    let mut iter = db.iter_with_options(&bounds(None, Some(b"b"))).unwrap();
+    assert_eq!(collect_keys(&mut iter), vec![b"a".to_vec()]);
+}
+
+// =============================================================================
+// Test 3: Seeks are clamped into the range
+// =============================================================================
+#[test]
+fn db_iter_seeks_clamped_to_bounds() {
+    let (_dir, db) = populated_db();
+    let mut iter = db
+        .iter_with_options(&bounds(Some(b"c"), Some(b"h")))
+        .unwrap();
+
+    iter.seek(b"a").unwrap();
+    assert_eq!(iter.key(), b"c");
+
+    iter.seek(b"h").unwrap();
+    assert!(!iter.is_valid());
+
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"g");
+
+    iter.seek_for_prev(b"z").unwrap();
+    assert_eq!(iter.key(), b"g");
+
+    // Walking back stops at the lower bound
+    iter.seek(b"d").unwrap();
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), b"c");
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 4: Bounds that miss every key → invalid immediately
+// =============================================================================
+#[test]
+fn db_iter_empty_range() {
+    let (_dir, db) = populated_db();
+    let iter = db
+        .iter_with_options(&bounds(Some(b"x"), Some(b"z")))
+        .unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 5: Non-overlapping SSTable is skipped — corrupting its file is harmless
+// =============================================================================
+#[test]
+fn db_iter_skips_non_overlapping_sstables() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    db.put(b"a1", b"v").unwrap();
+    db.put(b"a2", b"v").unwrap();
+    db.flush().unwrap();
+    db.put(b"m1", b"v").unwrap();
+    db.put(b"m2", b"v").unwrap();
+    db.flush().unwrap();
+
+    // Corrupt the SSTable holding a* — if it were opened, reading would fail.
+    let first_sst = dir.path().join(format!("{:06}.sst", 1));
+    std::fs::write(
+        &first_sst,
+        b"not an sstable at all, just garbage bytes....................",
+    )
+    .unwrap();
+
+    let mut iter = db
+        .iter_with_options(&bounds(Some(b"m"), Some(b"n")))
+        .unwrap();
+    assert_eq!(
+        collect_keys(&mut iter),
+        vec![b"m1".to_vec(), b"m2".to_vec()]
+    );
+}
+
+// =============================================================================
+// Test 6: SSTable bounded iterator — clamps seeks and stops at upper
+// =============================================================================
+#[test]
+fn sstable_bounded_iter() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.sst");
+
+    let mut builder = SSTableBuilder::new(&path, 1, 64).unwrap();
+    for i in 0..100u32 {
+        builder.add(format!("k{:03}", i).as_bytes(), b"v").unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let mut iter = sst.bounded_iter(Some(b"k010"), Some(b"k015")).unwrap();
+    assert_eq!(collect_keys(&mut iter).len(), 5);
+
+    iter.seek(b"k000").unwrap();
+    assert_eq!(iter.key(), b"k010");
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+
+    let mut open_lower = sst.bounded_iter(None, Some(b"k003")).unwrap();
+    assert_eq!(
+        collect_keys(&mut open_lower),
+        vec![b"k000".to_vec(), b"k001".to_vec(), b"k002".to_vec()]
+    );
+}
@@ -0,0 +1,125 @@
+// DBIterator::refresh() tests
+//
+// refresh() swaps in the latest memtable and SSTable set while keeping the
+// iterator's position.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{DB, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn open_db() -> (tempfile::TempDir, DB) {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    (dir, db)
+}
+
+fn remaining_keys(iter: &mut dyn StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: Writes made after creation appear after refresh()
+// =============================================================================
+#[test]
+fn refresh_picks_up_new_writes() {
+    let (_dir, db) = open_db();
+    db.put(b"a", b"1").unwrap();
+    db.put(b"c", b"3").unwrap();
+
+    let mut iter = db.iter().unwrap();
+    assert_eq!(iter.key(), b"a");
+
+    db.put(b"b", b"2").unwrap();
+    db.put(b"c", b"33").unwrap();
+
+    iter.refresh().unwrap();
+    assert_eq!(iter.key(), b"a");
+    iter.next().unwrap();
+    assert_eq!(iter.key(), b"b");
+    iter.next().unwrap();
+    assert_eq!(iter.value(), b"33");
+}
+
+// =============================================================================
+// Test 2: Current key deleted → refresh lands on the next live key
+// =============================================================================
+#[test]
+fn refresh_skips_deleted_current_key() {
+    let (_dir, db) = open_db();
+    for k in [b"a", b"b", b"c"] {
+        db.put(k, b"v").unwrap();
+    }
+
+    let mut iter = db.iter().unwrap();
+    iter.seek(b"b").unwrap();
+    db.delete(b"b").unwrap();
+
+    iter.refresh().unwrap();
+    assert_eq!(iter.key(), b"c");
+}
+
+// =============================================================================
+// Test 3: Data flushed and compacted after creation is still reachable
+// =============================================================================
+#[test]
+fn refresh_follows_flush_and_compaction() {
+    let (_dir, db) = open_db();
+    for i in 0..10u32 {
+        db.put(format!("k{}", i).as_bytes(), b"v").unwrap();
+    }
+
+    let mut iter = db.iter().unwrap();
+    iter.seek(b"k5").unwrap();
+
+    db.flush().unwrap();
+    db.put(b"k55", b"v").unwrap();
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+
+    iter.refresh().unwrap();
+    assert_eq!(
+        remaining_keys(&mut iter),
+        vec![
+            b"k5".to_vec(),
+            b"k55".to_vec(),
+            b"k6".to_vec(),
+            b"k7".to_vec(),
+            b"k8".to_vec(),
+            b"k9".to_vec()
+        ]
+    );
+}
+
+// =============================================================================
+// Test 4: Exhausted iterator stays exhausted; bounds are preserved
+// =============================================================================
+#[test]
+fn refresh_exhausted_and_bounded() {
+    let (_dir, db) = open_db();
+    db.put(b"b", b"v").unwrap();
+
+    let mut iter = db
+        .iter_with_options(&ReadOptions {
+            iterate_upper_bound: Some(b"m".to_vec()),
+            ..ReadOptions::default()
+        })
+        .unwrap();
+    iter.next().unwrap();
+    assert!(!iter.is_valid());
+
+    db.put(b"c", b"v").unwrap();
+    db.put(b"x", b"v").unwrap();
+    iter.refresh().unwrap();
+    assert!(!iter.is_valid());
+
+    iter.seek_to_first().unwrap();
+    assert_eq!(
+        remaining_keys(&mut iter),
+        vec![b"b".to_vec(), b"c".to_vec()]
+    );
+}
@@ -0,0 +1,121 @@
+// DBIterator::status() tests
+//
+// Read errors hit mid-scan invalidate the iterator and stay retrievable
+// through status() instead of being swallowed.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::manifest::version::Version;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::{DB, Error, Options};
+use tempfile::tempdir;
+
+/// Install two non-overlapping L1 SSTables (a00..a09, b00..b09) directly
+/// into the DB's version, returning their file paths.
+fn db_with_l1_files(dir: &Path) -> (DB, Vec<std::path::PathBuf>) {
+    let db = DB::open(dir, Options::default()).unwrap();
+    let mut version = Version::new(7);
+    let mut paths = Vec::new();
+    for prefix in ["a", "b"] {
+        let id = db.version_set.next_sst_id();
+        let path = dir.join(format!("{:06}.sst", id));
+        let mut builder = SSTableBuilder::new(&path, id, 4096).unwrap();
+        for i in 0..10 {
+            builder
+                .add(format!("{prefix}{:02}", i).as_bytes(), b"v")
+                .unwrap();
+        }
+        let mut meta = builder.finish().unwrap();
+        meta.level = 1;
+        version.levels[1].push(meta);
+        paths.push(path);
+    }
+    db.version_set.install(version);
+    (db, paths)
+}
+
+// =============================================================================
+// Test 1: A healthy scan ends with an Ok status
+// =============================================================================
+#[test]
+fn status_ok_after_full_scan() {
+    let dir = tempdir().unwrap();
+    let (db, _) = db_with_l1_files(dir.path());
+
+    let mut iter = db.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 20);
+    assert!(iter.status().is_ok());
+}
+
+// =============================================================================
+// Test 2: Corruption in a lazily opened file surfaces mid-scan
+// =============================================================================
+#[test]
+fn corruption_mid_scan_sets_status() {
+    let dir = tempdir().unwrap();
+    let (db, paths) = db_with_l1_files(dir.path());
+
+    let mut iter = db.iter().unwrap();
+
+    // Corrupt a value byte of the second file after the scan has started
+    let mut data = std::fs::read(&paths[1]).unwrap();
+    data[4 + 3] ^= 0xFF; // [key_len][val_len]["b00"] then the value byte
+    std::fs::write(&paths[1], data).unwrap();
+
+    let mut count = 0;
+    let mut error = None;
+    while iter.is_valid() {
+        count += 1;
+        if let Err(e) = iter.next() {
+            error = Some(e);
+            break;
+        }
+    }
+    assert_eq!(count, 10);
+    assert!(matches!(error, Some(Error::Corruption(_))));
+    assert!(!iter.is_valid());
+    assert!(matches!(iter.status(), Err(Error::Corruption(_))));
+
+    // Further steps keep reporting the error
+    assert!(iter.next().is_err());
+    assert!(iter.prev().is_err());
+}
+
+// =============================================================================
+// Test 3: A missing SSTable is reported, not skipped
+// =============================================================================
+#[test]
+fn missing_file_is_an_error() {
+    let dir = tempdir().unwrap();
+    let (db, paths) = db_with_l1_files(dir.path());
+
+    let mut iter = db.iter().unwrap();
+    std::fs::remove_file(&paths[1]).unwrap();
+
+    assert!(matches!(iter.seek(b"b05"), Err(Error::Io(_))));
+    assert!(matches!(iter.status(), Err(Error::Io(_))));
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 4: A seek clears the status and retries
+// =============================================================================
+#[test]
+fn seek_clears_status() {
+    let dir = tempdir().unwrap();
+    let (db, paths) = db_with_l1_files(dir.path());
+
+    let mut iter = db.iter().unwrap();
+    std::fs::remove_file(&paths[1]).unwrap();
+    assert!(iter.seek(b"b00").is_err());
+
+    iter.seek(b"a05").unwrap();
+    assert!(iter.status().is_ok());
+    assert_eq!(iter.key(), b"a05");
+}
@@ -0,0 +1,143 @@
+// Keys-only iteration tests
+//
+// In keys-only mode values are never copied out of blocks or memtables:
+// block and SSTable iterators yield KEYS_ONLY_VALUE (empty for
+// tombstones), and DBIterator yields an empty value for every key.
+
+use lsm_engine::iterator::{KEYS_ONLY_VALUE, StorageIterator};
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::{BlockReadOptions, SSTable};
+use lsm_engine::{DB, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn keys_only() -> ReadOptions {
+    ReadOptions {
+        keys_only: true,
+        ..ReadOptions::default()
+    }
+}
+
+// =============================================================================
+// Test 1: Block keys-only iterator hides values but keeps tombstones empty
+// =============================================================================
+#[test]
+fn block_keys_only_iter() {
+    let mut builder = BlockBuilder::new(4096);
+    builder.add(b"a", b"value_a");
+    builder.add(b"b", b"");
+    builder.add(b"c", b"value_c");
+    let block = Block::decode(builder.build()).unwrap();
+
+    let mut iter = block.keys_only_iter();
+    let mut entries = Vec::new();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    assert_eq!(
+        entries,
+        vec![
+            (b"a".to_vec(), KEYS_ONLY_VALUE.to_vec()),
+            (b"b".to_vec(), Vec::new()),
+            (b"c".to_vec(), KEYS_ONLY_VALUE.to_vec()),
+        ]
+    );
+}
+
+// =============================================================================
+// Test 2: SSTable iterator in keys-only mode, across blocks
+// =============================================================================
+#[test]
+fn sstable_keys_only_iter() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 64).unwrap();
+    for i in 0..20u32 {
+        builder
+            .add(format!("k{:02}", i).as_bytes(), b"some longer value")
+            .unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let opts = BlockReadOptions {
+        keys_only: true,
+        ..BlockReadOptions::default()
+    };
+    let mut iter = sst.bounded_iter_with_options(None, None, &opts).unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.value(), KEYS_ONLY_VALUE);
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 20);
+
+    // Point lookups still return the real value
+    assert_eq!(
+        sst.get_with_options(b"k05", &opts).unwrap().unwrap(),
+        &b"some longer value"[..]
+    );
+}
+
+// =============================================================================
+// Test 3: DB keys-only scan skips deleted keys across memtable and SSTables
+// =============================================================================
+#[test]
+fn db_keys_only_scan() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for k in [b"a", b"b", b"c", b"d"] {
+        db.put(k, b"value").unwrap();
+    }
+    db.delete(b"b").unwrap();
+    db.flush().unwrap();
+    db.delete(b"c").unwrap();
+    db.put(b"e", b"value").unwrap();
+
+    let mut iter = db.iter_with_options(&keys_only()).unwrap();
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        assert!(iter.value().is_empty());
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    assert_eq!(keys, vec![b"a".to_vec(), b"d".to_vec(), b"e".to_vec()]);
+
+    // Reverse works the same way
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"e");
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), b"d");
+}
+
+// =============================================================================
+// Test 4: Keys-only scan at a snapshot
+// =============================================================================
+#[test]
+fn snapshot_keys_only_scan() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"a", b"1").unwrap();
+    db.put(b"b", b"").unwrap();
+    db.delete(b"b").unwrap();
+    db.put(b"c", b"3").unwrap();
+
+    let snapshot = std::sync::Arc::new(db.snapshot());
+    db.delete(b"a").unwrap();
+
+    let mut iter = db
+        .iter_with_options(&ReadOptions {
+            snapshot: Some(snapshot),
+            ..keys_only()
+        })
+        .unwrap();
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    assert_eq!(keys, vec![b"a".to_vec(), b"c".to_vec()]);
+}
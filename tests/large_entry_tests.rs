@@ -0,0 +1,100 @@
+// Large key/value tests
+//
+// Block entries store varint lengths and blocks use 4-byte offsets, so
+// keys and values of 64KB and beyond round-trip instead of having their
+// lengths truncated.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn value_of(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+        .collect()
+}
+
+// =============================================================================
+// Test 1: Entries at varint length boundaries round-trip through a block
+// =============================================================================
+#[test]
+fn block_varint_boundaries() {
+    for (i, len) in [0, 1, 127, 128, 16_383, 16_384, 65_535, 65_536, 300_000]
+        .into_iter()
+        .enumerate()
+    {
+        let key = format!("key_{i:02}").into_bytes();
+        let value = value_of(len, i as u8);
+        let mut builder = BlockBuilder::new(4096);
+        assert!(builder.add(&key, &value));
+        let block = Block::decode(builder.build()).unwrap();
+        assert_eq!(block.get(&key).unwrap(), value.as_slice());
+        assert_eq!(block.value_is_empty(0), len == 0);
+    }
+
+    // An oversized first entry fills its block
+    let mut builder = BlockBuilder::new(4096);
+    assert!(builder.add(b"a", &value_of(70_000, 1)));
+    assert!(!builder.add(b"b", b"full"));
+}
+
+// =============================================================================
+// Test 2: SSTables hold values and keys past 64KB
+// =============================================================================
+#[test]
+fn sstable_large_entries() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let big_key = [b'k'; 70_000];
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = vec![
+        (b"a".to_vec(), value_of(10, 0)),
+        (b"b".to_vec(), value_of(65_536, 1)),
+        (b"c".to_vec(), value_of(100_000, 2)),
+        (big_key.to_vec(), value_of(1 << 20, 3)),
+        (b"l".to_vec(), value_of(10, 4)),
+    ];
+
+    let mut builder = SSTableBuilder::new(&path, 1, 4096).unwrap();
+    for (key, value) in &entries {
+        builder.add(key, value).unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    for (key, value) in &entries {
+        assert_eq!(sst.get(key).unwrap().unwrap(), value.as_slice());
+    }
+
+    let mut iter = sst.iter().unwrap();
+    for (key, value) in &entries {
+        assert!(iter.is_valid());
+        assert_eq!(iter.key(), key.as_slice());
+        assert_eq!(iter.value(), value.as_slice());
+        iter.next().unwrap();
+    }
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 3: Large values through the DB: WAL, flush and reopen
+// =============================================================================
+#[test]
+fn db_large_values() {
+    let dir = tempdir().unwrap();
+    let big = value_of(200_000, 7);
+    {
+        let db = DB::open(dir.path(), Options::default()).unwrap();
+        db.put(b"flushed", &big).unwrap();
+        db.flush().unwrap();
+        db.put(b"in_wal", &big).unwrap();
+        assert_eq!(db.get(b"flushed").unwrap().unwrap(), big);
+    }
+
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert_eq!(db.get(b"flushed").unwrap().unwrap(), big);
+    assert_eq!(db.get(b"in_wal").unwrap().unwrap(), big);
+}
@@ -0,0 +1,155 @@
+// LevelIterator tests
+//
+// A sorted level's SSTables are presented as one concatenated source and
+// opened lazily, one file at a time.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::iterator::level::LevelIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+/// Write SSTable `id` containing keys "k{start}".."k{end}" (zero-padded).
+fn write_sst(dir: &Path, id: u64, start: u32, end: u32) -> SSTableMeta {
+    let path = dir.join(format!("{:06}.sst", id));
+    let mut builder = SSTableBuilder::new(&path, id, 4096).unwrap();
+    for i in start..end {
+        builder.add(format!("k{:03}", i).as_bytes(), b"v").unwrap();
+    }
+    builder.finish().unwrap()
+}
+
+/// A level of three files: k000..k010, k010..k020, k020..k030.
+fn three_file_level(dir: &Path) -> Vec<SSTableMeta> {
+    vec![
+        write_sst(dir, 1, 0, 10),
+        write_sst(dir, 2, 10, 20),
+        write_sst(dir, 3, 20, 30),
+    ]
+}
+
+fn collect_keys(iter: &mut dyn StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("k{:03}", i).into_bytes()
+}
+
+// =============================================================================
+// Test 1: Full scan concatenates every file in order
+// =============================================================================
+#[test]
+fn level_iter_full_scan() {
+    let dir = tempdir().unwrap();
+    let metas = three_file_level(dir.path());
+
+    let mut iter = LevelIterator::new(&metas, dir.path().to_path_buf(), None, None).unwrap();
+    let expected: Vec<Vec<u8>> = (0..30).map(key).collect();
+    assert_eq!(collect_keys(&mut iter), expected);
+}
+
+// =============================================================================
+// Test 2: Seek jumps straight to the right file
+// =============================================================================
+#[test]
+fn level_iter_seek_across_files() {
+    let dir = tempdir().unwrap();
+    let metas = three_file_level(dir.path());
+
+    // Delete the last file — seeks into earlier files never open it.
+    std::fs::remove_file(dir.path().join(format!("{:06}.sst", 3))).unwrap();
+
+    let mut iter = LevelIterator::new(&metas, dir.path().to_path_buf(), None, None).unwrap();
+    iter.seek(b"k015").unwrap();
+    assert_eq!(iter.key(), b"k015");
+
+    // Crossing a file boundary forward
+    iter.seek(b"k009").unwrap();
+    iter.next().unwrap();
+    assert_eq!(iter.key(), b"k010");
+
+    // Reaching the missing file surfaces the error
+    assert!(iter.seek(b"k025").is_err());
+    assert!(!iter.is_valid());
+
+    iter.seek(b"k999").unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 3: Reverse iteration crosses file boundaries
+// =============================================================================
+#[test]
+fn level_iter_reverse() {
+    let dir = tempdir().unwrap();
+    let metas = three_file_level(dir.path());
+
+    let mut iter = LevelIterator::new(&metas, dir.path().to_path_buf(), None, None).unwrap();
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"k029");
+
+    iter.seek_for_prev(b"k020").unwrap();
+    assert_eq!(iter.key(), b"k020");
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), b"k019");
+
+    iter.seek_to_first().unwrap();
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 4: Bounds drop non-overlapping files and clip the rest
+// =============================================================================
+#[test]
+fn level_iter_bounds() {
+    let dir = tempdir().unwrap();
+    let metas = three_file_level(dir.path());
+
+    let mut iter = LevelIterator::new(
+        &metas,
+        dir.path().to_path_buf(),
+        Some(b"k012"),
+        Some(b"k015"),
+    )
+    .unwrap();
+    assert_eq!(iter.num_files(), 1);
+    assert_eq!(collect_keys(&mut iter), vec![key(12), key(13), key(14)]);
+}
+
+// =============================================================================
+// Test 5: DB iteration over compacted (L1) data goes through the level
+// =============================================================================
+#[test]
+fn db_iter_over_compacted_level() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        block_size: 256,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+
+    for i in 0..200u32 {
+        db.put(&key(i), b"value").unwrap();
+        if i % 50 == 49 {
+            db.flush().unwrap();
+        }
+    }
+    db.compact_range(None, None).unwrap();
+    db.delete(&key(100)).unwrap();
+
+    let mut iter = db.iter().unwrap();
+    assert_eq!(collect_keys(&mut iter).len(), 199);
+
+    iter.seek_for_prev(&key(100)).unwrap();
+    assert_eq!(iter.key(), key(99).as_slice());
+}
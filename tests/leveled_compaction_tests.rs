@@ -1,6 +1,8 @@
 use lsm_engine::compaction::CompactionStrategy;
 use lsm_engine::compaction::leveled::LeveledStrategy;
 use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
 
 // ---------------------------------------------------------------------------
 // Helper: build an SSTableMeta with the fields leveled compaction cares about.
@@ -15,6 +17,10 @@ fn make_sst(id: u64, level: u32, min_key: &[u8], max_key: &[u8], file_size: u64)
         max_key: max_key.to_vec(),
         file_size,
         entry_count: 100,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
     }
 }
 
@@ -349,14 +355,14 @@ fn only_some_l2_sstables_overlap() {
 }
 
 // ---------------------------------------------------------------------------
-// L0 is not managed by leveled strategy (L0 is handled by size-tiered)
+// L0 is compacted by file count, not size
 // ---------------------------------------------------------------------------
 
 #[test]
-fn l0_sstables_ignored_by_leveled_strategy() {
+fn l0_below_file_trigger_no_compaction() {
     let strategy = test_strategy();
 
-    // Even if L0 has tons of SSTables, leveled doesn't trigger on L0
+    // However large, L0 isn't compacted below the file count trigger (4)
     let levels = make_levels(vec![
         vec![
             make_sst(1, 0, b"a", b"z", 9999),
@@ -370,6 +376,118 @@ fn l0_sstables_ignored_by_leveled_strategy() {
     assert!(strategy.pick_compaction(&levels).is_none());
 }
 
+#[test]
+fn l0_at_file_trigger_compacts_into_l1() {
+    let strategy = test_strategy().with_level0_trigger(2);
+
+    let levels = make_levels(vec![
+        vec![
+            make_sst(1, 0, b"a", b"c", 100),
+            make_sst(2, 0, b"b", b"f", 100),
+        ],
+        vec![
+            make_sst(10, 1, b"a", b"d", 100),
+            make_sst(11, 1, b"e", b"h", 100),
+            make_sst(12, 1, b"m", b"p", 100),
+        ],
+        vec![],
+    ]);
+
+    let task = strategy.pick_compaction(&levels).expect("L0 at trigger");
+    assert_eq!(task.output_level, 1);
+    let mut ids: Vec<u64> = task.inputs.iter().map(|s| s.id).collect();
+    ids.sort();
+    // Every L0 file, and the L1 files overlapping [a, f]
+    assert_eq!(ids, vec![1, 2, 10, 11]);
+}
+
+// ---------------------------------------------------------------------------
+// The file overlapping the most next-level bytes is picked
+// ---------------------------------------------------------------------------
+
+#[test]
+fn picks_file_with_most_next_level_overlap() {
+    let strategy = test_strategy();
+
+    let levels = make_levels(vec![
+        vec![],
+        vec![
+            make_sst(1, 1, b"a", b"f", 600),
+            make_sst(2, 1, b"g", b"m", 600),
+            make_sst(3, 1, b"n", b"z", 600),
+        ], // L1 = 1800 > 1000
+        vec![
+            make_sst(10, 2, b"a", b"c", 1000),
+            make_sst(11, 2, b"h", b"i", 3000),
+            make_sst(12, 2, b"j", b"k", 3000),
+            make_sst(13, 2, b"x", b"z", 2000),
+        ],
+    ]);
+
+    let task = strategy.pick_compaction(&levels).expect("L1 over budget");
+    let mut ids: Vec<u64> = task.inputs.iter().map(|s| s.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec![2, 11, 12]);
+    assert_eq!(strategy.level_target(1), 1000);
+    assert_eq!(strategy.level_target(3), 100_000);
+}
+
+// ---------------------------------------------------------------------------
+// Through the DB: results go through the manifest and survive a reopen
+// ---------------------------------------------------------------------------
+
+#[test]
+fn db_leveled_compaction_survives_reopen() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        level0_file_num_compaction_trigger: 3,
+        ..Options::default()
+    };
+    let key = |i: u32| format!("key_{:04}", i).into_bytes();
+    let live_ids = |db: &DB| {
+        let mut ids: Vec<(u32, u64)> = db
+            .live_files_metadata()
+            .iter()
+            .map(|m| (m.level, m.id))
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    let before = {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for batch in 0..3u32 {
+            for i in 0..100 {
+                db.put(&key(i), format!("v{batch}").as_bytes()).unwrap();
+            }
+            db.flush().unwrap();
+        }
+        db.compact_range(None, None).unwrap();
+        let ids = live_ids(&db);
+        assert!(!ids.is_empty());
+        assert!(ids.iter().all(|(level, _)| *level == 1), "{ids:?}");
+        ids
+    };
+
+    // The manifest lists the outputs, not the deleted inputs
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(live_ids(&db), before);
+    for i in 0..100 {
+        assert_eq!(db.get(&key(i)).unwrap().as_deref(), Some(&b"v2"[..]));
+    }
+    let on_disk = std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter(|e| {
+            e.as_ref()
+                .unwrap()
+                .path()
+                .extension()
+                .is_some_and(|x| x == "sst")
+        })
+        .count();
+    assert_eq!(on_disk, before.len());
+}
+
 // ---------------------------------------------------------------------------
 // Exactly at budget boundary
 // ---------------------------------------------------------------------------
@@ -0,0 +1,159 @@
+// Live files metadata tests
+//
+// DB::live_files_metadata() lists every SSTable in the current version
+// with its level, key range, size, entry count and creation time, all
+// taken from the manifest so they survive a reopen. The creation time is
+// also stamped in the SSTable's meta block.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lsm_engine::manifest::Manifest;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+// =============================================================================
+// Test 1: Flushed tables are listed with their key range and counts
+// =============================================================================
+#[test]
+fn lists_flushed_tables() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert!(db.live_files_metadata().is_empty());
+
+    let before = now_secs();
+    for (start, end) in [(0, 50), (25, 100)] {
+        for i in start..end {
+            db.put(format!("key_{i:03}").as_bytes(), b"value").unwrap();
+        }
+        db.flush().unwrap();
+    }
+    let after = now_secs();
+
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 2);
+    assert!(files.iter().all(|f| f.level == 0));
+    assert!(files[0].id < files[1].id);
+    assert_eq!(files[0].min_key, b"key_000");
+    assert_eq!(files[0].max_key, b"key_049");
+    assert_eq!(files[0].entry_count, 50);
+    assert_eq!(files[1].min_key, b"key_025");
+    assert_eq!(files[1].entry_count, 75);
+    for file in &files {
+        assert!(file.file_size > 0);
+        assert!((before..=after).contains(&file.creation_time));
+    }
+}
+
+// =============================================================================
+// Test 2: Metadata survives a reopen
+// =============================================================================
+#[test]
+fn survives_reopen() {
+    let dir = tempdir().unwrap();
+    let before = {
+        let db = DB::open(dir.path(), Options::default()).unwrap();
+        db.put(b"a", b"1").unwrap();
+        db.put(b"z", b"2").unwrap();
+        db.flush().unwrap();
+        db.live_files_metadata()
+    };
+
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    let after = db.live_files_metadata();
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].id, before[0].id);
+    assert_eq!(after[0].min_key, b"a");
+    assert_eq!(after[0].max_key, b"z");
+    assert_eq!(after[0].file_size, before[0].file_size);
+    assert_eq!(after[0].creation_time, before[0].creation_time);
+    assert!(after[0].creation_time > 0);
+}
+
+// =============================================================================
+// Test 3: Compaction output replaces its inputs at the output level
+// =============================================================================
+#[test]
+fn reflects_compaction() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for round in 0..3 {
+        for i in 0..20 {
+            db.put(format!("key_{:03}", round * 20 + i).as_bytes(), b"v")
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+    let inputs: Vec<u64> = db.live_files_metadata().iter().map(|f| f.id).collect();
+
+    db.compact_range(None, None).unwrap();
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].level, 1);
+    assert!(!inputs.contains(&files[0].id));
+    assert_eq!(files[0].min_key, b"key_000");
+    assert_eq!(files[0].max_key, b"key_059");
+    assert_eq!(files[0].entry_count, 60);
+}
+
+// =============================================================================
+// Test 4: Creation time round-trips through the manifest
+// =============================================================================
+#[test]
+fn manifest_keeps_creation_time() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("MANIFEST");
+    let meta = SSTableMeta {
+        id: 3,
+        level: 1,
+        min_key: b"a".to_vec(),
+        max_key: b"m".to_vec(),
+        file_size: 4096,
+        entry_count: 10,
+        creation_time: 1_700_000_000,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
+    };
+    {
+        let mut manifest = Manifest::open(&path).unwrap();
+        manifest.record_flush(meta.clone()).unwrap();
+        manifest.compact().unwrap();
+    }
+
+    let manifest = Manifest::open(&path).unwrap();
+    let level1 = manifest.current_version().level(1);
+    assert_eq!(level1.len(), 1);
+    assert_eq!(level1[0].creation_time, meta.creation_time);
+    assert_eq!(level1[0].entry_count, meta.entry_count);
+}
+
+// =============================================================================
+// Test 5: The SSTable's meta block carries the same creation time
+// =============================================================================
+#[test]
+fn sstable_records_creation_time() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    let before = now_secs();
+    db.put(b"key", b"value").unwrap();
+    db.flush().unwrap();
+    let after = now_secs();
+
+    let files = db.live_files_metadata();
+    let sst = SSTable::open(&dir.path().join(format!("{:06}.sst", files[0].id))).unwrap();
+    assert_eq!(sst.meta().creation_time, files[0].creation_time);
+    assert!((before..=after).contains(&sst.meta().creation_time));
+}
@@ -0,0 +1,129 @@
+// Live WAL tracking tests
+//
+// Every WAL is listed in the manifest before it's written to, and a
+// flush's log number (the oldest WAL an unflushed memtable still needs)
+// retires the ones below it. Recovery replays exactly the listed WALs:
+// unlisted ones are deleted, and a listed one that's missing is an error.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use lsm_engine::manifest::Manifest;
+use lsm_engine::wal::writer::WALWriter;
+use lsm_engine::wal::{SyncPolicy, WALRecord};
+use lsm_engine::{CompactionStyle, DB, Error, Options};
+use tempfile::tempdir;
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+fn wal_files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".wal"))
+        .collect();
+    names.sort();
+    names
+}
+
+// =============================================================================
+// Test 1: The manifest keeps the WALs at or past the log number, across
+// reopen and compact()
+// =============================================================================
+#[test]
+fn manifest_tracks_live_wals() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("MANIFEST");
+    let mut manifest = Manifest::open(&path).unwrap();
+    assert!(manifest.live_wals().is_none());
+
+    for number in 1..=3 {
+        manifest.record_wal_added(number).unwrap();
+    }
+    manifest.record_log_number(2).unwrap();
+    let expected = BTreeSet::from([2, 3]);
+    assert_eq!(manifest.live_wals(), Some(&expected));
+    drop(manifest);
+
+    let mut manifest = Manifest::open(&path).unwrap();
+    assert_eq!(manifest.live_wals(), Some(&expected));
+    manifest.compact().unwrap();
+    drop(manifest);
+    assert_eq!(Manifest::open(&path).unwrap().live_wals(), Some(&expected));
+}
+
+// =============================================================================
+// Test 2: A WAL the manifest doesn't list is deleted, not replayed
+// =============================================================================
+#[test]
+fn unlisted_wal_not_replayed() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.put(b"real", b"v").unwrap();
+    }
+    let stray = dir.path().join("000099.wal");
+    let mut writer = WALWriter::new(&stray, SyncPolicy::EveryWrite).unwrap();
+    writer
+        .append(&WALRecord::put(b"ghost".to_vec(), b"v".to_vec()))
+        .unwrap();
+    drop(writer);
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.wait_for_empty_trash();
+    assert_eq!(db.get(b"real").unwrap(), Some(b"v".to_vec()));
+    assert_eq!(db.get(b"ghost").unwrap(), None);
+    assert!(!stray.exists());
+}
+
+// =============================================================================
+// Test 3: WALs replayed at open are deleted once their memtable is flushed
+// =============================================================================
+#[test]
+fn replayed_wals_deleted_by_flush() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.put(b"key", b"v1").unwrap();
+    }
+    let replayed = wal_files(dir.path());
+    assert_eq!(replayed.len(), 1);
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"key", b"v2").unwrap();
+    db.flush().unwrap();
+    db.wait_for_empty_trash();
+    let left = wal_files(dir.path());
+    assert_eq!(left.len(), 1);
+    assert!(!left.contains(&replayed[0]));
+    drop(db);
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.get(b"key").unwrap(), Some(b"v2".to_vec()));
+}
+
+// =============================================================================
+// Test 4: A listed WAL that's missing fails the open
+// =============================================================================
+#[test]
+fn missing_listed_wal_is_corruption() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.put(b"key", b"v").unwrap();
+    }
+    for name in wal_files(dir.path()) {
+        std::fs::remove_file(dir.path().join(name)).unwrap();
+    }
+
+    assert!(matches!(
+        DB::open(dir.path(), options()),
+        Err(Error::Corruption(_))
+    ));
+}
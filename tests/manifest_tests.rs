@@ -7,6 +7,7 @@ use std::fs;
 
 use tempfile::tempdir;
 
+use lsm_engine::compaction::CompactionReason;
 use lsm_engine::manifest::Manifest;
 use lsm_engine::sstable::footer::SSTableMeta;
 
@@ -18,6 +19,10 @@ fn make_sst(id: u64, level: u32, min_key: &[u8], max_key: &[u8]) -> SSTableMeta
         max_key: max_key.to_vec(),
         file_size: 0,
         entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
     }
 }
 
@@ -50,7 +55,11 @@ fn manifest_record_compaction_adjusts_version() {
 
     let merged = make_sst(3, 0, b"a", b"k");
     manifest
-        .record_compaction(vec![merged.clone()], vec![meta_a.id, meta_b.id])
+        .record_compaction(
+            vec![merged.clone()],
+            vec![meta_a.id, meta_b.id],
+            CompactionReason::ManualCompaction,
+        )
         .expect("record compaction");
 
     let level0 = manifest.current_version().level(0);
@@ -128,6 +137,7 @@ fn manifest_compact_produces_smaller_file() {
             .record_compaction(
                 vec![make_sst(new_id, 1, min_key.as_bytes(), max_key.as_bytes())],
                 vec![old_a, old_b],
+                CompactionReason::LevelMaxBytes,
             )
             .expect("record compaction");
     }
@@ -170,7 +180,11 @@ fn manifest_compact_reopen_reconstructs_version() {
 
         // Compact two L0 SSTables into L1
         manifest
-            .record_compaction(vec![make_sst(4, 1, b"a", b"h")], vec![1, 2])
+            .record_compaction(
+                vec![make_sst(4, 1, b"a", b"h")],
+                vec![1, 2],
+                CompactionReason::Level0FilesNum,
+            )
             .expect("compaction");
 
         manifest.record_log_number(7).expect("log number");
@@ -386,12 +400,20 @@ fn manifest_compact_multi_level_version() {
 
         // Compact SSTs 1,2 from L0 → L1
         manifest
-            .record_compaction(vec![make_sst(4, 1, b"a", b"h")], vec![1, 2])
+            .record_compaction(
+                vec![make_sst(4, 1, b"a", b"h")],
+                vec![1, 2],
+                CompactionReason::Level0FilesNum,
+            )
             .expect("compaction to L1");
 
         // Compact SST 4 from L1 → L2
         manifest
-            .record_compaction(vec![make_sst(5, 2, b"a", b"h")], vec![4])
+            .record_compaction(
+                vec![make_sst(5, 2, b"a", b"h")],
+                vec![4],
+                CompactionReason::LevelMaxBytes,
+            )
             .expect("compaction to L2");
 
         manifest.compact().expect("compact");
@@ -0,0 +1,113 @@
+// Memtable filter tests
+//
+// With Options::memtable_prefix_bloom_size_ratio set, each memtable keeps
+// a bloom filter of the keys (or key prefixes) written to it, and point
+// lookups of keys it never saw skip the skip list.
+
+use std::sync::Arc;
+
+use lsm_engine::db::prefix::FixedPrefix;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::memtable::filter::MemTableFilter;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+// =============================================================================
+// Test 1: A whole-key filter rules out most keys never written
+// =============================================================================
+#[test]
+fn whole_key_filter_rules_out_misses() {
+    let mut filter = MemTableFilter::new(16 * 1024 * 8, None, false);
+    for i in 0..1000 {
+        filter.insert(&key(i));
+    }
+    assert!((0..1000).all(|i| filter.may_contain(&key(i))));
+    let false_positives = (1000..11000)
+        .filter(|&i| filter.may_contain(&key(i)))
+        .count();
+    assert!(false_positives < 100, "{false_positives} false positives");
+    assert_eq!(filter.size_bytes(), 16 * 1024);
+}
+
+// =============================================================================
+// Test 2: A prefix filter rules out unwritten prefixes only
+// =============================================================================
+#[test]
+fn prefix_filter() {
+    let mut filter = MemTableFilter::new(8 * 1024, Some(Arc::new(FixedPrefix(4))), false);
+    filter.insert(b"user:1");
+    filter.insert(b"ab");
+
+    // Any key of a written prefix may be there
+    assert!(filter.may_contain(b"user:999"));
+    assert!(!filter.may_contain(b"item:1"));
+    // Keys outside the extractor's domain can't be ruled out
+    assert!(filter.may_contain(b"xy"));
+
+    let mut filter = MemTableFilter::new(8 * 1024, Some(Arc::new(FixedPrefix(4))), true);
+    filter.insert(b"user:1");
+    assert!(filter.may_contain(b"user:1"));
+    assert!(!filter.may_contain(b"user:999"));
+}
+
+// =============================================================================
+// Test 3: A filtered memtable still finds its values and tombstones
+// =============================================================================
+#[test]
+fn filtered_memtable_lookups() {
+    let filter = MemTableFilter::new(8 * 1024, None, false);
+    let mt = MemTable::with_filter(1024 * 1024, Some(filter));
+    mt.put(b"a".to_vec(), b"1".to_vec());
+    mt.delete(b"b".to_vec());
+
+    assert_eq!(mt.read().get(b"a"), Some(&b"1"[..]));
+    assert!(mt.may_contain(b"b"));
+    assert_eq!(mt.read().get(b"b"), None);
+    assert!(!mt.may_contain(b"missing"));
+    assert_eq!(mt.get_pinned(b"missing"), None);
+    assert!(MemTable::new(1024).may_contain(b"anything"));
+}
+
+// =============================================================================
+// Test 4: Reads through filtered memtables match unfiltered ones
+// =============================================================================
+#[test]
+fn db_reads_with_memtable_filter() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        memtable_size: 16 * 1024,
+        memtable_prefix_bloom_size_ratio: 0.1,
+        prefix_extractor: Some(Arc::new(FixedPrefix(4))),
+        memtable_whole_key_filtering: true,
+        disable_auto_compactions: true,
+        ..Options::default()
+    };
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..500 {
+            db.put(&key(i), format!("v{i}").as_bytes()).unwrap();
+        }
+        for i in (0..500).step_by(5) {
+            db.put(&key(i), b"new").unwrap();
+        }
+        for i in 0..500 {
+            let expected = if i % 5 == 0 {
+                b"new".to_vec()
+            } else {
+                format!("v{i}").into_bytes()
+            };
+            assert_eq!(db.get(&key(i)).unwrap(), Some(expected));
+        }
+        assert_eq!(db.get(&key(9999)).unwrap(), None);
+    }
+
+    // Replayed into a filtered memtable on reopen
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.get(&key(495)).unwrap(), Some(b"new".to_vec()));
+    assert_eq!(db.get(&key(499)).unwrap(), Some(b"v499".to_vec()));
+    assert_eq!(db.get(b"nope").unwrap(), None);
+}
@@ -0,0 +1,142 @@
+// Memtable garbage tests
+//
+// supersede() writes a key's new version in the place of its newest one,
+// so rewriting a hot key costs its value bytes only. The DB writes with
+// add() instead, keeping every version for snapshots and raw iterators.
+// Either way, values no read of the newest versions returns are counted
+// as garbage, and still count towards the flush threshold.
+
+use std::sync::Arc;
+
+use lsm_engine::db::prefix::FixedPrefix;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::memtable::rep::{
+    BTreeFactory, HashSkipListFactory, MemTableRepFactory, SkipListFactory,
+};
+use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::types::ValueType;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+// =============================================================================
+// Test 1: Superseding reuses the node, only the value is new
+// =============================================================================
+#[test]
+fn supersede_reuses_node() {
+    let mut sl = SkipList::new();
+    sl.supersede(b"hot".to_vec(), 1, ValueType::Put, vec![b'a'; 10]);
+    let size = sl.size_bytes();
+    assert_eq!(sl.garbage_bytes(), 0);
+
+    sl.supersede(b"hot".to_vec(), 2, ValueType::Put, vec![b'b'; 20]);
+    sl.supersede(b"hot".to_vec(), 3, ValueType::Delete, Vec::new());
+    assert_eq!(sl.len(), 1);
+    assert_eq!(sl.size_bytes(), size + 20);
+    assert_eq!(sl.garbage_bytes(), 30);
+    assert_eq!(
+        sl.get_version(b"hot", u64::MAX).unwrap().0,
+        ValueType::Delete
+    );
+
+    // Versions newer than the write stay where they are
+    sl.add(b"hot".to_vec(), 9, ValueType::Put, b"v9".to_vec());
+    sl.supersede(b"hot".to_vec(), 5, ValueType::Put, b"v5".to_vec());
+    assert_eq!(sl.len(), 2);
+    assert_eq!(sl.get_version(b"hot", 9).unwrap().1, &b"v5"[..]);
+    assert_eq!(sl.get(b"hot"), Some(&b"v9"[..]));
+}
+
+// =============================================================================
+// Test 2: Reps that can reuse entries do, and count the garbage
+// =============================================================================
+#[test]
+fn reps_supersede_in_place() {
+    let factories: Vec<(&str, Arc<dyn MemTableRepFactory>)> = vec![
+        ("skiplist", Arc::new(SkipListFactory)),
+        ("btree", Arc::new(BTreeFactory)),
+        (
+            "hash_skiplist",
+            Arc::new(HashSkipListFactory(Arc::new(FixedPrefix(2)))),
+        ),
+    ];
+    for (name, factory) in factories {
+        let mt = MemTable::with_rep(1024 * 1024, factory.create(), None);
+        for seq in 1..=100u64 {
+            let value = format!("value_{seq:03}").into_bytes();
+            mt.supersede(seq, ValueType::Put, b"hot".to_vec(), value);
+        }
+        mt.supersede(101, ValueType::Put, b"cold".to_vec(), b"x".to_vec());
+
+        assert_eq!(mt.len(), 2, "{name}");
+        assert_eq!(mt.read().get(b"hot"), Some(&b"value_100"[..]), "{name}");
+        assert_eq!(mt.garbage_bytes(), 99 * 9, "{name}");
+        assert!(mt.size() > 100 * 9, "{name}");
+        assert_eq!(mt.seqno_range(), Some((1, 101)), "{name}");
+    }
+}
+
+// =============================================================================
+// Test 3: add() keeps shadowed versions, counting their values as garbage
+// =============================================================================
+#[test]
+fn reps_count_shadowed_versions_as_garbage() {
+    let factories: Vec<(&str, Arc<dyn MemTableRepFactory>)> = vec![
+        ("skiplist", Arc::new(SkipListFactory)),
+        ("btree", Arc::new(BTreeFactory)),
+        (
+            "hash_skiplist",
+            Arc::new(HashSkipListFactory(Arc::new(FixedPrefix(2)))),
+        ),
+    ];
+    for (name, factory) in factories {
+        let mt = MemTable::with_rep(1024 * 1024, factory.create(), None);
+        for seq in 1..=100u64 {
+            let value = format!("value_{seq:03}").into_bytes();
+            mt.add(seq, ValueType::Put, b"hot".to_vec(), value);
+        }
+        // Older than the newest version: shadows nothing
+        mt.add(0, ValueType::Put, b"hot".to_vec(), b"old".to_vec());
+
+        assert_eq!(mt.len(), 101, "{name}");
+        assert_eq!(mt.read().get(b"hot"), Some(&b"value_100"[..]), "{name}");
+        assert_eq!(mt.garbage_bytes(), 99 * 9, "{name}");
+        assert_eq!(
+            mt.get_version(b"hot", 2).unwrap().1,
+            &b"value_001"[..],
+            "{name}"
+        );
+    }
+}
+
+// =============================================================================
+// Test 4: A hot key's rewrites fill the memtable, and get flushed
+// =============================================================================
+#[test]
+fn hot_key_rewrites_in_db() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            memtable_size: 64 * 1024,
+            disable_auto_compactions: true,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+
+    // Every rewrite keeps a version, charged to the memtable's size
+    for i in 0..300u32 {
+        db.put(b"hot", &[i as u8; 100]).unwrap();
+    }
+    let size = db.stats().memtable_size;
+    assert!(size >= 300 * 100, "{size}");
+
+    // Shadowed versions count towards the threshold: the memtable fills
+    // and flushes
+    for i in 0..1000u32 {
+        db.put(b"hot", &[i as u8; 100]).unwrap();
+    }
+    db.wait_for_background_jobs().unwrap();
+    assert!(!db.live_files_metadata().is_empty());
+    assert_eq!(db.get(b"hot").unwrap(), Some(vec![(999 % 256) as u8; 100]));
+}
@@ -0,0 +1,126 @@
+// Memtable range iterator tests
+//
+// MemTable::range_iter(start, end) yields only keys in [start, end),
+// both ways, and seeks clamp to the range.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::types::ValueType;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn memtable() -> MemTable {
+    let mt = MemTable::new(1024 * 1024);
+    for key in [&b"a"[..], b"c", b"e", b"g", b"i"] {
+        mt.put(key.to_vec(), key.to_vec());
+    }
+    mt
+}
+
+fn collect(mut iter: impl StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: Forward iteration stops at the upper bound
+// =============================================================================
+#[test]
+fn forward_within_bounds() {
+    let mt = memtable();
+    assert_eq!(
+        collect(mt.read().range_iter(b"b", b"g")),
+        vec![b"c".to_vec(), b"e".to_vec()]
+    );
+    assert_eq!(
+        collect(mt.read().range_iter(b"c", b"d")),
+        vec![b"c".to_vec()]
+    );
+    assert!(collect(mt.read().range_iter(b"j", b"z")).is_empty());
+    assert!(collect(mt.read().range_iter(b"e", b"e")).is_empty());
+    assert_eq!(mt.count_in_range(b"a", b"f"), 3);
+}
+
+// =============================================================================
+// Test 2: Seeks clamp to the bounds, both ways
+// =============================================================================
+#[test]
+fn seeks_clamp_to_bounds() {
+    let mt = memtable();
+    let mt = mt.read();
+    let mut iter = mt.range_iter(b"c", b"h");
+
+    iter.seek(b"a").unwrap();
+    assert_eq!(iter.key(), b"c");
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"g");
+    iter.next().unwrap();
+    assert!(!iter.is_valid());
+
+    iter.seek_for_prev(b"z").unwrap();
+    assert_eq!(iter.key(), b"g");
+    iter.seek_for_prev(b"f").unwrap();
+    assert_eq!(iter.key(), b"e");
+    iter.seek(b"h").unwrap();
+    assert!(!iter.is_valid());
+
+    // An upper bound past every key
+    let mut iter = mt.range_iter(b"f", b"z");
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"i");
+}
+
+// =============================================================================
+// Test 3: Bounds combine with reading as of a sequence number
+// =============================================================================
+#[test]
+fn bounds_and_sequence() {
+    let mt = MemTable::new(1024 * 1024);
+    mt.add(1, ValueType::Put, b"b".to_vec(), b"1".to_vec());
+    mt.add(2, ValueType::Put, b"c".to_vec(), b"2".to_vec());
+    mt.add(3, ValueType::Put, b"d".to_vec(), b"3".to_vec());
+
+    let mt = mt.read();
+    let mut iter = mt.range_iter_at(Some(b"b"), Some(b"d"), 2);
+    assert_eq!(iter.key(), b"b");
+    iter.advance();
+    assert!(!iter.is_valid());
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"b");
+}
+
+// =============================================================================
+// Test 4: DB scans read only their range from the memtable
+// =============================================================================
+#[test]
+fn db_scan_of_memtable_range() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for i in 0..100u32 {
+        db.put(format!("k{:03}", i).as_bytes(), b"v").unwrap();
+    }
+    db.delete(b"k042").unwrap();
+
+    let mut scanner = db.scan(b"k040", b"k045").unwrap();
+    let mut keys = Vec::new();
+    while scanner.is_valid() {
+        keys.push(scanner.key().to_vec());
+        scanner.next().unwrap();
+    }
+    assert_eq!(
+        keys,
+        vec![
+            b"k040".to_vec(),
+            b"k041".to_vec(),
+            b"k043".to_vec(),
+            b"k044".to_vec()
+        ]
+    );
+}
@@ -0,0 +1,166 @@
+// Memtable rep tests
+//
+// A MemTable stores its entries in whichever MemTableRep
+// Options::memtable_factory builds: a skip list, a BTreeMap, skip lists
+// hashed by prefix, or a vector sorted at freeze. All of them must look
+// the same from outside.
+
+use std::sync::Arc;
+
+use lsm_engine::db::prefix::FixedPrefix;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::memtable::rep::{
+    BTreeFactory, HashSkipListFactory, MemTableRepFactory, SkipListFactory, VectorFactory,
+};
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn factories() -> Vec<(&'static str, Arc<dyn MemTableRepFactory>)> {
+    vec![
+        ("skiplist", Arc::new(SkipListFactory)),
+        ("btree", Arc::new(BTreeFactory)),
+        (
+            "hash_skiplist",
+            Arc::new(HashSkipListFactory(Arc::new(FixedPrefix(2)))),
+        ),
+        ("vector", Arc::new(VectorFactory)),
+    ]
+}
+
+fn keys(mt: &MemTable) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    let mt = mt.read();
+    let mut iter = mt.iter();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.advance();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: Every rep gets, overwrites and deletes alike
+// =============================================================================
+#[test]
+fn reps_point_operations() {
+    for (name, factory) in factories() {
+        let mt = MemTable::with_rep(1024 * 1024, factory.create(), None);
+        mt.put(b"b1".to_vec(), b"old".to_vec());
+        mt.put(b"a1".to_vec(), b"x".to_vec());
+        mt.put(b"b1".to_vec(), b"new".to_vec());
+        mt.delete(b"a1".to_vec());
+        mt.put(b"z".to_vec(), b"short key".to_vec());
+
+        assert_eq!(mt.read().get(b"b1"), Some(&b"new"[..]), "{name}");
+        assert_eq!(mt.read().get(b"a1"), None, "{name}");
+        assert_eq!(mt.get_pinned(b"z").as_deref(), Some(&b"short key"[..]));
+        assert_eq!(mt.read().get(b"c1"), None, "{name}");
+        assert_eq!(
+            keys(&mt),
+            vec![b"a1".to_vec(), b"b1".to_vec(), b"z".to_vec()],
+            "{name}"
+        );
+        assert!(mt.size() >= 2 + 3 + 2 + 1 + 9, "{name}");
+
+        mt.mark_read_only();
+        assert_eq!(mt.read().get(b"b1"), Some(&b"new"[..]), "{name}");
+        assert_eq!(mt.len(), 3, "{name}");
+        assert_eq!(
+            keys(&mt),
+            vec![b"a1".to_vec(), b"b1".to_vec(), b"z".to_vec()],
+            "{name}"
+        );
+    }
+}
+
+// =============================================================================
+// Test 2: Every rep's iterator seeks both ways
+// =============================================================================
+#[test]
+fn reps_iterator_seeks() {
+    for (name, factory) in factories() {
+        let mt = MemTable::with_rep(1024 * 1024, factory.create(), None);
+        for key in [&b"k3"[..], b"k1", b"m5", b"k7", b"a9"] {
+            mt.put(key.to_vec(), key.to_vec());
+        }
+
+        let mt = mt.read();
+        let mut iter = mt.iter();
+        iter.seek(b"k2").unwrap();
+        assert_eq!(iter.key(), b"k3", "{name}");
+        iter.prev().unwrap();
+        assert_eq!(iter.key(), b"k1", "{name}");
+        iter.seek_for_prev(b"k6").unwrap();
+        assert_eq!(iter.key(), b"k3", "{name}");
+        iter.next().unwrap();
+        assert_eq!(iter.key(), b"k7", "{name}");
+        iter.seek_to_last().unwrap();
+        assert_eq!(iter.key(), b"m5", "{name}");
+        iter.next().unwrap();
+        assert!(!iter.is_valid(), "{name}");
+        iter.seek_for_prev(b"a0").unwrap();
+        assert!(!iter.is_valid(), "{name}");
+        iter.seek_to_first().unwrap();
+        assert_eq!(iter.key(), b"a9", "{name}");
+    }
+}
+
+// =============================================================================
+// Test 3: The vector rep counts every write until frozen
+// =============================================================================
+#[test]
+fn vector_rep_sorted_at_freeze() {
+    let mt = MemTable::with_rep(1024 * 1024, VectorFactory.create(), None);
+    for round in 0..3u8 {
+        for key in [b"c", b"a", b"b"] {
+            mt.put(key.to_vec(), vec![round]);
+        }
+    }
+    assert_eq!(mt.len(), 9);
+    assert_eq!(mt.read().get(b"a"), Some(&[2u8][..]));
+
+    mt.mark_read_only();
+    assert_eq!(mt.len(), 3);
+    assert_eq!(keys(&mt), vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+    assert_eq!(mt.read().get(b"c"), Some(&[2u8][..]));
+}
+
+// =============================================================================
+// Test 4: A DB reads and recovers the same whatever its memtable rep
+// =============================================================================
+#[test]
+fn db_with_each_rep() {
+    for (name, factory) in factories() {
+        let dir = tempdir().unwrap();
+        let options = || Options {
+            memtable_size: 8 * 1024,
+            memtable_factory: Arc::clone(&factory),
+            disable_auto_compactions: true,
+            ..Options::default()
+        };
+        {
+            let db = DB::open(dir.path(), options()).unwrap();
+            for i in 0..300u32 {
+                db.put(format!("k{:04}", i).as_bytes(), &[b'v'; 50])
+                    .unwrap();
+            }
+            for i in (0..300u32).step_by(3) {
+                db.put(format!("k{:04}", i).as_bytes(), b"new").unwrap();
+            }
+            assert_eq!(db.get(b"k0297").unwrap(), Some(b"new".to_vec()), "{name}");
+            let mut scanner = db.scan(b"k0010", b"k0013").unwrap();
+            let mut scanned = 0;
+            while scanner.is_valid() {
+                scanned += 1;
+                scanner.next().unwrap();
+            }
+            assert_eq!(scanned, 3, "{name}");
+        }
+
+        let db = DB::open(dir.path(), options()).unwrap();
+        assert_eq!(db.get(b"k0297").unwrap(), Some(b"new".to_vec()), "{name}");
+        assert_eq!(db.get(b"k0298").unwrap(), Some(vec![b'v'; 50]), "{name}");
+        assert_eq!(db.get(b"k0300").unwrap(), None, "{name}");
+    }
+}
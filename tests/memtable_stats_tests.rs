@@ -0,0 +1,170 @@
+// Memtable statistics tests
+//
+// MemTable::stats() describes what a flush would write: one entry per
+// key, tombstones counted apart, raw key and value bytes, and the key
+// range. Writes keep them up to date, so reading them is cheap:
+// DB::stats() reports them for the active memtable, and flushes size the
+// SSTable bloom filter from the exact entry count.
+
+use std::sync::Arc;
+
+use lsm_engine::bloom::builder::BloomFilterBuilder;
+use lsm_engine::db::prefix::FixedPrefix;
+use lsm_engine::memtable::rep::{
+    BTreeFactory, HashSkipListFactory, MemTableRepFactory, SkipListFactory, VectorFactory,
+};
+use lsm_engine::memtable::{MemTable, MemTableStats};
+use lsm_engine::rate_limiter::RateLimiter;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::types::ValueType;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+// =============================================================================
+// Test 1: Stats count each key's newest version
+// =============================================================================
+#[test]
+fn stats_of_newest_versions() {
+    let mt = MemTable::new(1024 * 1024);
+    assert_eq!(mt.stats(), MemTableStats::default());
+
+    mt.add(1, ValueType::Put, b"b".to_vec(), b"old value".to_vec());
+    mt.add(2, ValueType::Put, b"b".to_vec(), b"new".to_vec());
+    mt.add(3, ValueType::Put, b"a".to_vec(), b"12345".to_vec());
+    mt.add(4, ValueType::Delete, b"c".to_vec(), Vec::new());
+    mt.add(5, ValueType::Put, b"d".to_vec(), b"x".to_vec());
+    mt.add(6, ValueType::Delete, b"d".to_vec(), Vec::new());
+
+    assert_eq!(mt.len(), 6);
+    assert_eq!(
+        mt.stats(),
+        MemTableStats {
+            num_entries: 4,
+            num_deletes: 2,
+            raw_key_bytes: 4,
+            raw_value_bytes: 8,
+            min_key: Some(b"a".to_vec()),
+            max_key: Some(b"d".to_vec()),
+        }
+    );
+}
+
+// =============================================================================
+// Test 2: DB::stats() reports the active memtable
+// =============================================================================
+#[test]
+fn db_stats_report_memtable() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for i in 0..100 {
+        db.put(&key(i), b"value").unwrap();
+    }
+    db.put(&key(0), b"v").unwrap();
+    db.delete(&key(99)).unwrap();
+
+    let stats = db.stats().memtable;
+    assert_eq!(stats.num_entries, 100);
+    assert_eq!(stats.num_deletes, 1);
+    assert_eq!(stats.raw_key_bytes, 100 * 9);
+    assert_eq!(stats.raw_value_bytes, 98 * 5 + 1);
+    assert_eq!(stats.min_key, Some(key(0)));
+    assert_eq!(stats.max_key, Some(key(99)));
+
+    db.flush().unwrap();
+    assert_eq!(db.stats().memtable, MemTableStats::default());
+}
+
+// =============================================================================
+// Test 3: Flushes size the bloom filter for the keys they write
+// =============================================================================
+#[test]
+fn flush_sizes_bloom_filter_exactly() {
+    let dir = tempdir().unwrap();
+    // Slowed flushes, so the rewrites of the same keys queue up in
+    // several memtables flushed together
+    let options = Options {
+        memtable_size: 8192,
+        max_write_buffer_number: 4,
+        disable_auto_compactions: true,
+        rate_limiter: Some(Arc::new(RateLimiter::new(32 * 1024).with_burst(0))),
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), options).unwrap();
+    for round in 0..12 {
+        for i in 0..40 {
+            db.put(&key(i), format!("{round}{}", "x".repeat(80)).as_bytes())
+                .unwrap();
+        }
+    }
+    db.flush().unwrap();
+
+    let files = db.live_files_metadata();
+    assert!(files.len() < 12, "{} files", files.len());
+    for meta in files {
+        let sst = SSTable::open(&dir.path().join(format!("{:06}.sst", meta.id))).unwrap();
+        let expected = BloomFilterBuilder::with_bits_per_key(meta.entry_count as usize, 10)
+            .build()
+            .serialize()
+            .len();
+        assert_eq!(sst.properties().unwrap().filter_size, expected as u64);
+    }
+}
+
+/// Stats counted from scratch, by walking the memtable.
+fn recount(mt: &MemTable) -> MemTableStats {
+    let mut stats = MemTableStats::default();
+    let mt = mt.read();
+    let mut iter = mt.iter();
+    while iter.is_valid() {
+        stats.num_entries += 1;
+        if iter.value_type() == ValueType::Delete {
+            stats.num_deletes += 1;
+        }
+        stats.raw_key_bytes += iter.key().len() as u64;
+        stats.raw_value_bytes += iter.value().len() as u64;
+        stats.min_key.get_or_insert_with(|| iter.key().to_vec());
+        stats.max_key = Some(iter.key().to_vec());
+        iter.advance();
+    }
+    stats
+}
+
+// =============================================================================
+// Test 4: Writes keep the stats up to date on every rep
+// =============================================================================
+#[test]
+fn stats_kept_up_to_date_by_writes() {
+    let factories: Vec<(&str, Arc<dyn MemTableRepFactory>)> = vec![
+        ("skiplist", Arc::new(SkipListFactory)),
+        ("btree", Arc::new(BTreeFactory)),
+        (
+            "hash_skiplist",
+            Arc::new(HashSkipListFactory(Arc::new(FixedPrefix(5)))),
+        ),
+        ("vector", Arc::new(VectorFactory)),
+    ];
+    for (name, factory) in factories {
+        let mt = MemTable::with_rep(1024 * 1024, factory.create(), None);
+        for i in 0..500u64 {
+            let k = key((i * 37 % 100) as u32);
+            let value = vec![b'v'; (i % 13) as usize];
+            match i % 4 {
+                0 => mt.supersede(i + 100, ValueType::Put, k, value),
+                1 => mt.supersede(i + 100, ValueType::Delete, k, Vec::new()),
+                // Older than what's in, and the same version again
+                2 => mt.add(i % 50, ValueType::Put, k, value),
+                _ => mt.add(i + 100, ValueType::Put, k, value),
+            }
+        }
+        if name != "vector" {
+            assert_eq!(mt.stats(), recount(&mt), "{name}");
+        }
+        // Exact for every rep once frozen
+        mt.mark_read_only();
+        assert_eq!(mt.stats(), recount(&mt), "{name}");
+    }
+}
@@ -9,10 +9,10 @@ use lsm_engine::memtable::MemTable;
 // =============================================================================
 #[test]
 fn put_then_get_returns_value() {
-    let mut mt = MemTable::new(1024 * 1024); // 1MB limit
+    let mt = MemTable::new(1024 * 1024); // 1MB limit
     mt.put(b"key".to_vec(), b"value".to_vec());
 
-    assert_eq!(mt.get(b"key"), Some(b"value".as_slice()));
+    assert_eq!(mt.read().get(b"key"), Some(b"value".as_slice()));
 }
 
 // =============================================================================
@@ -21,7 +21,7 @@ fn put_then_get_returns_value() {
 #[test]
 fn get_nonexistent_returns_none() {
     let mt = MemTable::new(1024 * 1024);
-    assert_eq!(mt.get(b"missing"), None);
+    assert_eq!(mt.read().get(b"missing"), None);
 }
 
 // =============================================================================
@@ -29,12 +29,12 @@ fn get_nonexistent_returns_none() {
 // =============================================================================
 #[test]
 fn delete_then_get_returns_none() {
-    let mut mt = MemTable::new(1024 * 1024);
+    let mt = MemTable::new(1024 * 1024);
     mt.put(b"key".to_vec(), b"value".to_vec());
     mt.delete(b"key".to_vec());
 
     // After delete, get should return None
-    assert_eq!(mt.get(b"key"), None);
+    assert_eq!(mt.read().get(b"key"), None);
 }
 
 // =============================================================================
@@ -42,12 +42,12 @@ fn delete_then_get_returns_none() {
 // =============================================================================
 #[test]
 fn put_delete_put_returns_new_value() {
-    let mut mt = MemTable::new(1024 * 1024);
+    let mt = MemTable::new(1024 * 1024);
     mt.put(b"key".to_vec(), b"first".to_vec());
     mt.delete(b"key".to_vec());
     mt.put(b"key".to_vec(), b"second".to_vec());
 
-    assert_eq!(mt.get(b"key"), Some(b"second".as_slice()));
+    assert_eq!(mt.read().get(b"key"), Some(b"second".as_slice()));
 }
 
 // =============================================================================
@@ -55,12 +55,12 @@ fn put_delete_put_returns_new_value() {
 // =============================================================================
 #[test]
 fn delete_nonexistent_key_succeeds() {
-    let mut mt = MemTable::new(1024 * 1024);
+    let mt = MemTable::new(1024 * 1024);
     // Should not panic — tombstone is written even for non-existent key
     mt.delete(b"never_existed".to_vec());
 
     // And get should return None
-    assert_eq!(mt.get(b"never_existed"), None);
+    assert_eq!(mt.read().get(b"never_existed"), None);
 }
 
 // =============================================================================
@@ -77,7 +77,7 @@ fn is_full_false_when_under_limit() {
 // =============================================================================
 #[test]
 fn is_full_true_when_over_limit() {
-    let mut mt = MemTable::new(100); // tiny 100 byte limit
+    let mt = MemTable::new(100); // tiny 100 byte limit
 
     // Insert enough data to exceed limit
     mt.put(b"key1".to_vec(), b"a]value that is pretty long".to_vec());
@@ -94,12 +94,13 @@ fn is_full_true_when_over_limit() {
 // so they propagate to disk and block old values.
 #[test]
 fn iterator_includes_tombstones() {
-    let mut mt = MemTable::new(1024 * 1024);
+    let mt = MemTable::new(1024 * 1024);
     mt.put(b"a".to_vec(), b"value_a".to_vec());
     mt.put(b"b".to_vec(), b"value_b".to_vec());
     mt.delete(b"b".to_vec()); // tombstone for b
     mt.put(b"c".to_vec(), b"value_c".to_vec());
 
+    let mt = mt.read();
     let mut iter = mt.iter();
     let mut keys = Vec::new();
 
@@ -120,7 +121,7 @@ fn iterator_includes_tombstones() {
 // =============================================================================
 #[test]
 fn size_tracks_memory_usage() {
-    let mut mt = MemTable::new(1024 * 1024);
+    let mt = MemTable::new(1024 * 1024);
     assert_eq!(mt.size(), 0);
 
     mt.put(b"key".to_vec(), b"value".to_vec());
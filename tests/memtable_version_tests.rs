@@ -0,0 +1,319 @@
+// Memtable version tests
+//
+// Each memtable entry is one version of a key: (key, sequence, ValueType).
+// Writes add versions instead of overwriting, and reads as of a sequence
+// number see each key's newest version written before it, tombstones
+// included.
+
+use std::sync::Arc;
+
+use lsm_engine::db::prefix::FixedPrefix;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::memtable::rep::{
+    BTreeFactory, HashSkipListFactory, MemTableRepFactory, SkipListFactory, VectorFactory,
+};
+use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::types::ValueType;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn factories() -> Vec<(&'static str, Arc<dyn MemTableRepFactory>)> {
+    vec![
+        ("skiplist", Arc::new(SkipListFactory)),
+        ("btree", Arc::new(BTreeFactory)),
+        (
+            "hash_skiplist",
+            Arc::new(HashSkipListFactory(Arc::new(FixedPrefix(1)))),
+        ),
+        ("vector", Arc::new(VectorFactory)),
+    ]
+}
+
+/// a@1=a1, b@2=b2, a@3=a3, b@4 deleted, c@5=c5
+fn versioned(factory: &dyn MemTableRepFactory) -> MemTable {
+    let mt = MemTable::with_rep(1024 * 1024, factory.create(), None);
+    mt.add(1, ValueType::Put, b"a".to_vec(), b"a1".to_vec());
+    mt.add(2, ValueType::Put, b"b".to_vec(), b"b2".to_vec());
+    mt.add(3, ValueType::Put, b"a".to_vec(), b"a3".to_vec());
+    mt.add(4, ValueType::Delete, b"b".to_vec(), Vec::new());
+    mt.add(5, ValueType::Put, b"c".to_vec(), b"c5".to_vec());
+    mt
+}
+
+fn entries_at(mt: &MemTable, sequence: u64) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut entries = Vec::new();
+    let mt = mt.read();
+    let mut iter = mt.iter_at(sequence);
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.advance();
+    }
+    entries
+}
+
+fn entry(key: &[u8], value: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    (key.to_vec(), value.to_vec())
+}
+
+// =============================================================================
+// Test 1: The skip list keeps every version, newest first
+// =============================================================================
+#[test]
+fn skiplist_keeps_versions() {
+    let mut sl = SkipList::new();
+    sl.add(b"k".to_vec(), 7, ValueType::Put, b"v7".to_vec());
+    sl.add(b"k".to_vec(), 3, ValueType::Put, b"v3".to_vec());
+    sl.add(b"k".to_vec(), 9, ValueType::Delete, Vec::new());
+    sl.add(b"j".to_vec(), 5, ValueType::Put, b"j5".to_vec());
+    assert_eq!(sl.len(), 4);
+
+    let mut versions = Vec::new();
+    let mut iter = sl.iter();
+    while iter.is_valid() {
+        versions.push((iter.key().to_vec(), iter.sequence(), iter.value_type()));
+        iter.advance();
+    }
+    assert_eq!(
+        versions,
+        vec![
+            (b"j".to_vec(), 5, ValueType::Put),
+            (b"k".to_vec(), 9, ValueType::Delete),
+            (b"k".to_vec(), 7, ValueType::Put),
+            (b"k".to_vec(), 3, ValueType::Put),
+        ]
+    );
+
+    assert_eq!(sl.get_version(b"k", 3), None);
+    assert_eq!(
+        sl.get_version(b"k", 4),
+        Some((ValueType::Put, b"v3".to_vec().into()))
+    );
+    assert_eq!(
+        sl.get_version(b"k", 9),
+        Some((ValueType::Put, b"v7".to_vec().into()))
+    );
+    assert_eq!(sl.get_version(b"k", u64::MAX).unwrap().0, ValueType::Delete);
+
+    // Only the same version is overwritten
+    sl.add(b"k".to_vec(), 7, ValueType::Put, b"v7'".to_vec());
+    assert_eq!(sl.len(), 4);
+    assert_eq!(sl.get_version(b"k", 8).unwrap().1, &b"v7'"[..]);
+}
+
+// =============================================================================
+// Test 2: Every rep reads versions as of a sequence number
+// =============================================================================
+#[test]
+fn reps_read_as_of_sequence() {
+    for (name, factory) in factories() {
+        let mt = versioned(factory.as_ref());
+        assert_eq!(mt.len(), 5, "{name}");
+
+        assert_eq!(mt.get_version(b"a", 1), None, "{name}");
+        assert_eq!(
+            mt.get_version(b"a", 3),
+            Some((ValueType::Put, b"a1".to_vec().into())),
+            "{name}"
+        );
+        assert_eq!(
+            mt.get_version(b"b", u64::MAX),
+            Some((ValueType::Delete, Default::default())),
+            "{name}"
+        );
+        assert_eq!(mt.read().get(b"a"), Some(&b"a3"[..]), "{name}");
+        assert_eq!(mt.read().get(b"b"), None, "{name}");
+
+        assert_eq!(entries_at(&mt, 1), vec![], "{name}");
+        assert_eq!(
+            entries_at(&mt, 3),
+            vec![entry(b"a", b"a1"), entry(b"b", b"b2")],
+            "{name}"
+        );
+        assert_eq!(
+            entries_at(&mt, u64::MAX),
+            vec![entry(b"a", b"a3"), entry(b"b", b""), entry(b"c", b"c5")],
+            "{name}"
+        );
+    }
+}
+
+// =============================================================================
+// Test 3: Reading as of a sequence number works backwards too
+// =============================================================================
+#[test]
+fn backward_iteration_as_of_sequence() {
+    for (name, factory) in factories() {
+        let mt = versioned(factory.as_ref());
+
+        let mt = mt.read();
+        let mut iter = mt.iter_at(4);
+        iter.seek_to_last().unwrap();
+        assert_eq!(
+            (iter.key(), iter.value()),
+            (&b"b"[..], &b"b2"[..]),
+            "{name}"
+        );
+        iter.prev().unwrap();
+        assert_eq!(
+            (iter.key(), iter.value()),
+            (&b"a"[..], &b"a3"[..]),
+            "{name}"
+        );
+        iter.prev().unwrap();
+        assert!(!iter.is_valid(), "{name}");
+
+        // c isn't visible at 5: seek_for_prev lands on b
+        let mut iter = mt.iter_at(5);
+        iter.seek_for_prev(b"z").unwrap();
+        assert_eq!((iter.key(), iter.value()), (&b"b"[..], &b""[..]), "{name}");
+        iter.next().unwrap();
+        assert!(!iter.is_valid(), "{name}");
+
+        let mut iter = mt.iter_at(2);
+        iter.seek(b"b").unwrap();
+        assert!(!iter.is_valid(), "{name}");
+        iter.seek_to_last().unwrap();
+        assert_eq!(
+            (iter.key(), iter.value()),
+            (&b"a"[..], &b"a1"[..]),
+            "{name}"
+        );
+    }
+}
+
+// =============================================================================
+// Test 4: A tombstone in the memtable hides a flushed value
+// =============================================================================
+#[test]
+fn memtable_tombstone_hides_flushed_value() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"k", b"flushed").unwrap();
+    db.flush().unwrap();
+
+    db.delete(b"k").unwrap();
+    assert_eq!(db.get(b"k").unwrap(), None);
+    assert!(db.get_reader(b"k").unwrap().is_none());
+
+    db.put(b"k", b"back").unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"back".to_vec()));
+}
+
+// =============================================================================
+// Test 5: A snapshot sees the versions before it after new writes
+// =============================================================================
+#[test]
+fn snapshot_reads_older_versions() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"a", b"1").unwrap();
+    db.put(b"b", b"1").unwrap();
+    let snapshot = db.snapshot();
+
+    db.put(b"a", b"2").unwrap();
+    db.delete(b"b").unwrap();
+    db.put(b"c", b"2").unwrap();
+
+    assert_eq!(snapshot.get(b"a").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(snapshot.get(b"b").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(snapshot.get(b"c").unwrap(), None);
+    assert_eq!(db.get(b"a").unwrap(), Some(b"2".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+}
+
+// =============================================================================
+// Test 6: versions() lists every version with its sequence and type
+// =============================================================================
+#[test]
+fn versions_lists_every_version() {
+    for (name, factory) in factories() {
+        let mt = versioned(factory.as_ref());
+        let mut versions = Vec::new();
+        let mt = mt.read();
+        let mut iter = mt.versions();
+        while iter.is_valid() {
+            versions.push((iter.key().to_vec(), iter.sequence(), iter.value_type()));
+            iter.next().unwrap();
+        }
+        assert_eq!(
+            versions,
+            vec![
+                (b"a".to_vec(), 3, ValueType::Put),
+                (b"a".to_vec(), 1, ValueType::Put),
+                (b"b".to_vec(), 4, ValueType::Delete),
+                (b"b".to_vec(), 2, ValueType::Put),
+                (b"c".to_vec(), 5, ValueType::Put),
+            ],
+            "{name}"
+        );
+    }
+}
+
+// =============================================================================
+// Test 7: raw_iter() reports memtable versions with their own sequence
+// =============================================================================
+#[test]
+fn raw_iter_reports_memtable_sequences() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"a", b"1").unwrap();
+    db.flush().unwrap();
+    db.put(b"c", b"2").unwrap();
+    db.put(b"b", b"2").unwrap();
+    db.delete(b"a").unwrap();
+
+    let raw: Vec<_> = db
+        .raw_iter()
+        .unwrap()
+        .map(|(k, _)| (k.user_key, k.sequence, k.value_type))
+        .collect();
+    let keys: Vec<_> = raw.iter().map(|(key, _, t)| (key.clone(), *t)).collect();
+    assert_eq!(
+        keys,
+        vec![
+            (b"a".to_vec(), ValueType::Delete),
+            (b"a".to_vec(), ValueType::Put),
+            (b"b".to_vec(), ValueType::Put),
+            (b"c".to_vec(), ValueType::Put),
+        ]
+    );
+
+    // Each memtable write has its own sequence number, in write order,
+    // all above the flushed table's
+    let (a_del, a_put, b, c) = (raw[0].1, raw[1].1, raw[2].1, raw[3].1);
+    assert_eq!(a_put, db.live_files_metadata()[0].largest_seqno);
+    assert!(a_put < c && c < b && b < a_del);
+}
+
+// =============================================================================
+// Test 8: Overwrites inside one memtable keep every version for raw_iter()
+// =============================================================================
+#[test]
+fn raw_iter_keeps_overwritten_memtable_versions() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"k", b"v1").unwrap();
+    db.put(b"k", b"v2").unwrap();
+    db.delete(b"k").unwrap();
+
+    let raw: Vec<_> = db
+        .raw_iter()
+        .unwrap()
+        .map(|(k, v)| (k.sequence, k.value_type, v))
+        .collect();
+    assert_eq!(raw.len(), 3);
+    let versions: Vec<_> = raw.iter().map(|(_, t, v)| (*t, v.clone())).collect();
+    assert_eq!(
+        versions,
+        vec![
+            (ValueType::Delete, Vec::new()),
+            (ValueType::Put, b"v2".to_vec()),
+            (ValueType::Put, b"v1".to_vec()),
+        ]
+    );
+    assert!(raw[0].0 > raw[1].0 && raw[1].0 > raw[2].0);
+
+    // The newest version still wins for reads
+    assert_eq!(db.get(b"k").unwrap(), None);
+}
@@ -45,6 +45,27 @@ impl StorageIterator for VecIterator {
         self.pos = self.entries.partition_point(|(k, _)| k.as_slice() < key);
         Ok(())
     }
+
+    fn prev(&mut self) -> Result<()> {
+        self.pos = self.pos.checked_sub(1).unwrap_or(self.entries.len());
+        Ok(())
+    }
+
+    fn seek_for_prev(&mut self, key: &[u8]) -> Result<()> {
+        let upper = self.entries.partition_point(|(k, _)| k.as_slice() <= key);
+        self.pos = upper.checked_sub(1).unwrap_or(self.entries.len());
+        Ok(())
+    }
+
+    fn seek_to_first(&mut self) -> Result<()> {
+        self.pos = 0;
+        Ok(())
+    }
+
+    fn seek_to_last(&mut self) -> Result<()> {
+        self.pos = self.entries.len().saturating_sub(1);
+        Ok(())
+    }
 }
 
 /// Collect all (key, value) pairs from a MergeIterator into a Vec.
@@ -312,3 +333,33 @@ fn merge_no_iterators() {
     assert!(!merge.is_valid());
     assert!(collect_all(&mut merge).is_empty());
 }
+
+#[test]
+fn merge_filter_tombstones_hides_deleted_keys() {
+    // Newer tombstone for "b" must hide the older live value, not expose it.
+    let newer = VecIterator::new(vec![(b"b", b""), (b"d", b"")]);
+    let older = VecIterator::new(vec![(b"a", b"1"), (b"b", b"2"), (b"c", b"3"), (b"d", b"4")]);
+
+    let iters: Vec<Box<dyn StorageIterator>> = vec![Box::new(newer), Box::new(older)];
+    let mut merge = MergeIterator::filter_tombstones(iters).unwrap();
+
+    assert_eq!(
+        collect_all(&mut merge),
+        vec![
+            (b"a".to_vec(), b"1".to_vec()),
+            (b"c".to_vec(), b"3".to_vec())
+        ]
+    );
+
+    // Seeking onto a deleted key lands on the next live one
+    merge.seek(b"b").unwrap();
+    assert_eq!(merge.key(), b"c");
+
+    // Reverse direction skips tombstones too
+    merge.seek_to_last().unwrap();
+    assert_eq!(merge.key(), b"c");
+    merge.prev().unwrap();
+    assert_eq!(merge.key(), b"a");
+    merge.prev().unwrap();
+    assert!(!merge.is_valid());
+}
@@ -0,0 +1,114 @@
+// Memory-mapped SSTable read tests
+//
+// With Options::use_mmap_reads, SSTables are mapped and blocks are served
+// as slices of the map instead of seek+read calls on a file handle. Reads
+// must behave exactly as they do through the file.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::{FileAccess, SSTable};
+use lsm_engine::{DB, Error, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn build_table(path: &Path) {
+    let mut builder = SSTableBuilder::new(path, 1, 256).unwrap();
+    for i in 0..1000 {
+        builder.add(&key(i), format!("v{i}").as_bytes()).unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+// =============================================================================
+// Test 1: A mapped table answers gets and scans like a buffered one
+// =============================================================================
+#[test]
+fn mmap_matches_buffered_reads() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+
+    let buffered = SSTable::open(&path).unwrap();
+    let mapped = SSTable::open_mmap(&path).unwrap();
+    assert_eq!(buffered.file_access(), FileAccess::Buffered);
+    assert_eq!(mapped.file_access(), FileAccess::Mmap);
+    assert_eq!(mapped.meta().entry_count, 1000);
+    assert_eq!(mapped.num_blocks(), buffered.num_blocks());
+
+    for i in [0, 1, 500, 999] {
+        assert_eq!(mapped.get(&key(i)).unwrap(), buffered.get(&key(i)).unwrap());
+    }
+    assert!(mapped.get(b"key_00500x").unwrap().is_none());
+
+    let mut iter = mapped.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.key(), key(count).as_slice());
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 1000);
+}
+
+// =============================================================================
+// Test 2: Checksums are verified on mapped blocks too
+// =============================================================================
+#[test]
+fn mmap_detects_corruption() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+
+    let mut data = std::fs::read(&path).unwrap();
+    data[10] ^= 0xFF; // inside the first data block
+    std::fs::write(&path, data).unwrap();
+
+    let sst = SSTable::open_mmap(&path).unwrap();
+    assert!(matches!(sst.get(&key(0)), Err(Error::Corruption(_))));
+}
+
+// =============================================================================
+// Test 3: Options::use_mmap_reads through get, iterators and snapshots
+// =============================================================================
+#[test]
+fn db_use_mmap_reads_option() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        use_mmap_reads: true,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..500 {
+        db.put(&key(i), format!("v{i}").as_bytes()).unwrap();
+    }
+    db.flush().unwrap();
+    let snapshot = db.snapshot();
+    db.put(&key(7), b"new").unwrap();
+
+    assert_eq!(db.get(&key(7)).unwrap().unwrap(), b"new");
+    assert_eq!(db.get(&key(321)).unwrap().unwrap(), b"v321");
+
+    let read_options = ReadOptions {
+        snapshot: Some(Arc::new(snapshot)),
+        ..ReadOptions::default()
+    };
+    assert_eq!(
+        db.get_with_options(&key(7), &read_options)
+            .unwrap()
+            .unwrap(),
+        b"v7"
+    );
+    let mut iter = db.iter_with_options(&read_options).unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 500);
+}
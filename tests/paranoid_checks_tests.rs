@@ -0,0 +1,150 @@
+// Paranoid file checks tests
+//
+// With Options::paranoid_file_checks, a compaction reads back every table
+// it wrote before recording it in the MANIFEST. Outputs that fail the
+// check are deleted and the inputs stay live. The corruption here comes
+// from a compaction filter that damages an output the compaction has
+// already finished writing.
+
+use std::collections::HashSet;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use lsm_engine::compaction::filter::{CompactionFilter, FilterDecision};
+use lsm_engine::error::Error;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn sst_files(dir: &Path) -> HashSet<PathBuf> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sst"))
+        .collect()
+}
+
+/// When it sees `trigger`, flips a byte in the first data block of every
+/// SSTable in `dir` that isn't in `keep`, once.
+struct Corrupter {
+    dir: PathBuf,
+    keep: HashSet<PathBuf>,
+    trigger: Vec<u8>,
+    armed: AtomicBool,
+}
+
+impl CompactionFilter for Corrupter {
+    fn filter(&self, _level: u32, key: &[u8], _value: &[u8]) -> FilterDecision {
+        if key == self.trigger && self.armed.swap(false, Ordering::SeqCst) {
+            for path in sst_files(&self.dir).difference(&self.keep) {
+                let mut file = std::fs::OpenOptions::new().write(true).open(path).unwrap();
+                file.seek(SeekFrom::Start(16)).unwrap();
+                file.write_all(b"\xff\xff\xff\xff").unwrap();
+            }
+        }
+        FilterDecision::Keep
+    }
+}
+
+fn options(filter: Option<Arc<dyn CompactionFilter>>) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        target_file_size_base: 1024,
+        paranoid_file_checks: true,
+        compaction_filter: filter,
+        ..Options::default()
+    }
+}
+
+/// Flush `files` SSTables of 100 keys each.
+fn write_files(db: &DB, files: u32) {
+    for file in 0..files {
+        for i in file * 100..(file + 1) * 100 {
+            db.put(&key(i), format!("value_{i}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+fn assert_contents(db: &DB) {
+    for i in 0..300 {
+        assert_eq!(
+            db.get(&key(i)).unwrap(),
+            Some(format!("value_{i}").into_bytes())
+        );
+    }
+}
+
+// =============================================================================
+// Test 1: Sound outputs pass the check and are installed
+// =============================================================================
+#[test]
+fn sound_outputs_installed() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            max_subcompactions: 2,
+            ..options(None)
+        },
+    )
+    .unwrap();
+    write_files(&db, 3);
+    db.put(&key(500), b"deleted").unwrap();
+    db.delete(&key(500)).unwrap();
+    db.flush().unwrap();
+
+    db.compact_range(None, None).unwrap();
+    let files = db.live_files_metadata();
+    assert!(files.len() > 1 && files.iter().all(|f| f.level > 0));
+    assert_eq!(sst_files(dir.path()).len(), files.len());
+    assert_contents(&db);
+    assert_eq!(db.get(&key(500)).unwrap(), None);
+}
+
+// =============================================================================
+// Test 2: A corrupt output aborts the compaction and keeps the inputs
+// =============================================================================
+#[test]
+fn corrupt_output_keeps_inputs() {
+    let dir = tempdir().unwrap();
+    let before = {
+        let db = DB::open(dir.path(), options(None)).unwrap();
+        write_files(&db, 3);
+        db.live_files_metadata()
+    };
+    let inputs = sst_files(dir.path());
+
+    // The last key is merged after every output but the last is finished
+    let corrupter = Arc::new(Corrupter {
+        dir: dir.path().to_path_buf(),
+        keep: inputs.clone(),
+        trigger: key(299),
+        armed: AtomicBool::new(true),
+    });
+    let db = DB::open(dir.path(), options(Some(corrupter.clone()))).unwrap();
+    match db.compact_range(None, None) {
+        Err(Error::Corruption(_)) => {}
+        other => panic!("expected corruption, got {other:?}"),
+    }
+    assert!(!corrupter.armed.load(Ordering::SeqCst));
+    let after: Vec<u64> = db.live_files_metadata().iter().map(|f| f.id).collect();
+    assert_eq!(after, before.iter().map(|f| f.id).collect::<Vec<_>>());
+    assert_eq!(sst_files(dir.path()), inputs);
+    assert_contents(&db);
+
+    // Nothing interferes the second time around
+    db.compact_range(None, None).unwrap();
+    assert!(db.live_files_metadata().iter().all(|f| f.level > 0));
+    assert_contents(&db);
+    drop(db);
+
+    let db = DB::open(dir.path(), options(None)).unwrap();
+    assert_contents(&db);
+}
@@ -0,0 +1,112 @@
+// Partitioned (per-block) bloom filter tests
+//
+// With partitioned filters each data block gets its own small bloom
+// filter behind a filter index; a lookup loads only the filter of the one
+// block that could hold its key.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// Even keys only, so odd keys fall inside blocks without being present.
+fn build_table(path: &Path) {
+    let mut builder = SSTableBuilder::new(path, 1, 256)
+        .unwrap()
+        .with_partitioned_filters(true);
+    for i in 0..2000 {
+        builder.add(&key(i * 2), b"value").unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+// =============================================================================
+// Test 1: Opening loads no filters; a lookup loads only its block's filter
+// =============================================================================
+#[test]
+fn block_filters_load_lazily() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+
+    let sst = SSTable::open(&path).unwrap();
+    assert!(sst.filter().is_partitioned());
+    assert_eq!(sst.filter().loaded_partitions(), 0);
+
+    assert_eq!(sst.get(&key(1000)).unwrap().unwrap(), &b"value"[..]);
+    assert_eq!(sst.filter().loaded_partitions(), 1);
+    assert!(sst.get(&key(1001)).unwrap().is_none());
+    assert_eq!(sst.filter().loaded_partitions(), 1);
+}
+
+// =============================================================================
+// Test 2: No false negatives, and absent keys are mostly filtered
+// =============================================================================
+#[test]
+fn block_filters_are_accurate() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+    let sst = SSTable::open(&path).unwrap();
+
+    for i in 0..2000 {
+        assert!(sst.may_contain(&key(i * 2)).unwrap());
+    }
+    let false_positives = (0..2000)
+        .filter(|i| sst.may_contain(&key(i * 2 + 1)).unwrap())
+        .count();
+    assert!(false_positives < 100, "{false_positives} false positives");
+}
+
+// =============================================================================
+// Test 3: Iteration doesn't depend on filters
+// =============================================================================
+#[test]
+fn iteration_with_block_filters() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+    let sst = SSTable::open(&path).unwrap();
+
+    let mut iter = sst.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 2000);
+    assert_eq!(sst.filter().loaded_partitions(), 0);
+}
+
+// =============================================================================
+// Test 4: Options::partition_filters through flush and reopen
+// =============================================================================
+#[test]
+fn db_partition_filters_option() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        partition_filters: true,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..1000 {
+        db.put(&key(i * 2), b"value").unwrap();
+    }
+    db.flush().unwrap();
+
+    for i in 0..500 {
+        assert!(db.get(&key(i * 2 + 1)).unwrap().is_none());
+    }
+    assert!(db.stats().bloom_filter_hit_rate > 0.9);
+
+    drop(db);
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert_eq!(db.get(&key(998)).unwrap().unwrap(), b"value");
+}
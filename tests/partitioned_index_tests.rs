@@ -0,0 +1,117 @@
+// Partitioned index tests
+//
+// Tables with more data blocks than the partition size write their index
+// as partitions plus a top-level index. Opening reads only the top level;
+// partitions are loaded as lookups reach them.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// 64-byte blocks hold three entries each: 1000 entries → 334 blocks,
+/// i.e. 21 partitions of (up to) 16 entries.
+fn build_table(path: &Path, partition_entries: usize) {
+    let mut builder = SSTableBuilder::new(path, 1, 64)
+        .unwrap()
+        .with_index_partition_entries(partition_entries);
+    for i in 0..1000 {
+        builder.add(&key(i), format!("v{i}").as_bytes()).unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+// =============================================================================
+// Test 1: Small indexes stay flat
+// =============================================================================
+#[test]
+fn small_index_is_flat() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 100_000);
+
+    let sst = SSTable::open(&path).unwrap();
+    assert!(!sst.index().is_partitioned());
+    assert_eq!(sst.get(&key(500)).unwrap().unwrap(), &b"v500"[..]);
+}
+
+// =============================================================================
+// Test 2: Open loads no partitions; a point lookup loads exactly one
+// =============================================================================
+#[test]
+fn partitions_load_lazily() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 16);
+
+    let sst = SSTable::open(&path).unwrap();
+    assert!(sst.index().is_partitioned());
+    assert_eq!(sst.num_blocks(), 334);
+    assert_eq!(sst.index().loaded_partitions(), 0);
+
+    assert_eq!(sst.get(&key(777)).unwrap().unwrap(), &b"v777"[..]);
+    assert_eq!(sst.index().loaded_partitions(), 1);
+
+    // Absent keys past the end don't load anything more
+    assert!(sst.get(b"zzz").unwrap().is_none());
+    assert_eq!(sst.index().loaded_partitions(), 1);
+}
+
+// =============================================================================
+// Test 3: Iteration and seeks work across partition boundaries
+// =============================================================================
+#[test]
+fn iterate_across_partitions() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 16);
+    let sst = SSTable::open(&path).unwrap();
+
+    let mut iter = sst.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.key(), key(count).as_slice());
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 1000);
+
+    // Block 16 (keys 48..=50) starts the second partition
+    iter.seek(&key(48)).unwrap();
+    assert_eq!(iter.key(), key(48).as_slice());
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), key(47).as_slice());
+
+    iter.seek_for_prev(b"key_00999z").unwrap();
+    assert_eq!(iter.key(), key(999).as_slice());
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), key(999).as_slice());
+}
+
+// =============================================================================
+// Test 4: Range scans and estimates over a partitioned index
+// =============================================================================
+#[test]
+fn range_queries_on_partitioned_index() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path, 16);
+    let sst = SSTable::open(&path).unwrap();
+
+    let mut iter = sst.range_iter(&key(100), &key(110)).unwrap();
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    assert_eq!(keys, (100..110).map(key).collect::<Vec<_>>());
+
+    let estimate = sst.estimate_keys_in_range(&key(200), &key(600)).unwrap();
+    assert!((380..=420).contains(&estimate), "estimate {estimate}");
+}
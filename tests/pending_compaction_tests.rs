@@ -0,0 +1,213 @@
+// Pending compaction bytes tests
+//
+// The compaction strategy estimates how many bytes compaction still has to
+// rewrite. The DB keeps the estimate current as versions change, reports
+// it as the "lsm.estimate-pending-compaction-bytes" property, and holds
+// writes back past Options::soft/hard_pending_compaction_bytes_limit.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use lsm_engine::compaction::CompactionStrategy;
+use lsm_engine::compaction::filter::{CompactionFilter, FilterDecision};
+use lsm_engine::compaction::leveled::LeveledStrategy;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+const PENDING: &str = "lsm.estimate-pending-compaction-bytes";
+
+fn make_sst(id: u64, level: u32, min_key: &[u8], max_key: &[u8], file_size: u64) -> SSTableMeta {
+    SSTableMeta {
+        id,
+        level,
+        min_key: min_key.to_vec(),
+        max_key: max_key.to_vec(),
+        file_size,
+        entry_count: 100,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
+    }
+}
+
+fn pending(db: &DB) -> u64 {
+    db.get_property(PENDING).unwrap().parse().unwrap()
+}
+
+/// Write `files` L0 SSTables of 50 keys each.
+fn write_files(db: &DB, files: u32) {
+    for file in 0..files {
+        for i in 0..50u32 {
+            db.put(format!("key_{:05}", file * 50 + i).as_bytes(), b"value")
+                .unwrap();
+        }
+        db.flush().unwrap();
+    }
+}
+
+fn l0_bytes(db: &DB) -> u64 {
+    db.live_files_metadata()
+        .iter()
+        .filter(|f| f.level == 0)
+        .map(|f| f.file_size)
+        .sum()
+}
+
+/// Blocks every compaction until released.
+#[derive(Default)]
+struct Gate {
+    open: Mutex<bool>,
+    opened: Condvar,
+}
+
+impl Gate {
+    fn release(&self) {
+        *self.open.lock().unwrap() = true;
+        self.opened.notify_all();
+    }
+}
+
+impl CompactionFilter for Gate {
+    fn filter(&self, _level: u32, _key: &[u8], _value: &[u8]) -> FilterDecision {
+        let mut open = self.open.lock().unwrap();
+        while !*open {
+            open = self.opened.wait(open).unwrap();
+        }
+        FilterDecision::Keep
+    }
+}
+
+// =============================================================================
+// Test 1: Leveled estimate cascades the excess of each level down
+// =============================================================================
+#[test]
+fn leveled_estimate_cascades() {
+    // L1: 1000B, L2: 10_000B, L3 is the last level
+    let strategy = LeveledStrategy::new(1000, 10, 4);
+    let mut levels = vec![
+        vec![],
+        vec![make_sst(1, 1, b"a", b"m", 900)],
+        vec![make_sst(2, 2, b"a", b"z", 9900)],
+        vec![make_sst(3, 3, b"a", b"z", 1_000_000)],
+    ];
+    assert_eq!(strategy.estimated_pending_bytes(&levels), 0);
+
+    // Four L0 files are due: all of L0 and L1 are merged, pushing L1
+    // 300B over its target and L2 200B past its own
+    for id in 10..14 {
+        levels[0].push(make_sst(id, 0, b"a", b"z", 100));
+    }
+    assert_eq!(
+        strategy.estimated_pending_bytes(&levels),
+        (400 + 900) + 300 * 11 + 200 * 11
+    );
+}
+
+// =============================================================================
+// Test 2: The property follows flushes and compactions
+// =============================================================================
+#[test]
+fn property_tracks_flush_and_compaction() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_style: CompactionStyle::SizeTiered,
+            level0_file_num_compaction_trigger: 3,
+            disable_auto_compactions: true,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(db.get_property("lsm.no-such-property"), None);
+    assert_eq!(pending(&db), 0);
+
+    write_files(&db, 2);
+    assert_eq!(pending(&db), 0);
+    write_files(&db, 1);
+    assert_eq!(pending(&db), l0_bytes(&db));
+
+    db.compact_range(None, None).unwrap();
+    assert_eq!(pending(&db), 0);
+}
+
+// =============================================================================
+// Test 3: Past the hard limit, writes wait for compaction to catch up
+// =============================================================================
+#[test]
+fn hard_limit_stops_writes() {
+    let dir = tempdir().unwrap();
+    let gate = Arc::new(Gate::default());
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_style: CompactionStyle::SizeTiered,
+            level0_file_num_compaction_trigger: 2,
+            compaction_filter: Some(Arc::clone(&gate) as Arc<dyn CompactionFilter>),
+            hard_pending_compaction_bytes_limit: 1,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    write_files(&db, 2);
+    assert!(pending(&db) > 0);
+
+    std::thread::scope(|s| {
+        let writer = s.spawn(|| db.put(b"late", b"value"));
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(!writer.is_finished());
+
+        gate.release();
+        writer.join().unwrap().unwrap();
+    });
+    assert_eq!(pending(&db), 0);
+    assert_eq!(db.get(b"late").unwrap().as_deref(), Some(&b"value"[..]));
+}
+
+// =============================================================================
+// Test 4: Past the soft limit writes are delayed; no limit applies without
+// auto compactions
+// =============================================================================
+#[test]
+fn soft_limit_delays_writes() {
+    let dir = tempdir().unwrap();
+    let gate = Arc::new(Gate::default());
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_style: CompactionStyle::SizeTiered,
+            level0_file_num_compaction_trigger: 2,
+            compaction_filter: Some(Arc::clone(&gate) as Arc<dyn CompactionFilter>),
+            soft_pending_compaction_bytes_limit: 1,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    write_files(&db, 2);
+
+    let start = Instant::now();
+    for i in 0..50u32 {
+        db.put(format!("slow_{i}").as_bytes(), b"value").unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    gate.release();
+    drop(db);
+
+    // The same debt with auto compactions off holds nothing back
+    let db = DB::open(
+        dir.path(),
+        Options {
+            compaction_style: CompactionStyle::SizeTiered,
+            level0_file_num_compaction_trigger: 2,
+            disable_auto_compactions: true,
+            hard_pending_compaction_bytes_limit: 1,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    write_files(&db, 2);
+    assert!(pending(&db) > 0);
+    db.put(b"fast", b"value").unwrap();
+}
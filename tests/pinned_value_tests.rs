@@ -0,0 +1,127 @@
+// Pinned value tests
+//
+// Point lookups hand out values as refcounted slices of the memtable node or
+// decoded block instead of copying them, and iterators read them in place.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+// =============================================================================
+// Test 1: Block pinned value points into the block buffer
+// =============================================================================
+#[test]
+fn block_pinned_value_shares_buffer() {
+    let mut builder = BlockBuilder::new(4096);
+    builder.add(b"k1", b"first");
+    builder.add(b"k2", b"second");
+    let block = Block::decode(builder.build()).unwrap();
+
+    let pinned = block.get_pinned(b"k2").unwrap();
+    assert_eq!(pinned.as_ref(), b"second");
+    // Same memory as the borrowed view — no copy was made
+    assert_eq!(pinned.as_ptr(), block.get(b"k2").unwrap().as_ptr());
+
+    assert!(block.get_pinned(b"k3").is_none());
+}
+
+// =============================================================================
+// Test 2: Pinned value outlives the block it came from
+// =============================================================================
+#[test]
+fn pinned_value_outlives_block() {
+    let mut builder = BlockBuilder::new(4096);
+    builder.add(b"key", b"value");
+    let pinned = {
+        let block = Block::decode(builder.build()).unwrap();
+        block.get_pinned(b"key").unwrap()
+    };
+    assert_eq!(pinned.as_ref(), b"value");
+}
+
+// =============================================================================
+// Test 3: Skip list pinned value survives an overwrite of the key
+// =============================================================================
+#[test]
+fn skiplist_pinned_value_survives_overwrite() {
+    let mut sl = SkipList::new();
+    sl.insert(b"key".to_vec(), b"old".to_vec());
+    let pinned = sl.get_pinned(b"key").unwrap();
+
+    sl.insert(b"key".to_vec(), b"new".to_vec());
+    assert_eq!(pinned.as_ref(), b"old");
+    assert_eq!(sl.get(b"key"), Some(b"new".as_slice()));
+}
+
+// =============================================================================
+// Test 4: MemTable pinned lookup hides tombstones
+// =============================================================================
+#[test]
+fn memtable_pinned_hides_tombstones() {
+    let mt = MemTable::new(1024);
+    mt.put(b"a".to_vec(), b"1".to_vec());
+    mt.delete(b"b".to_vec());
+
+    assert_eq!(mt.get_pinned(b"a").as_deref(), Some(b"1".as_slice()));
+    assert!(mt.get_pinned(b"b").is_none());
+}
+
+// =============================================================================
+// Test 5: DB::get_pinned — memtable, SSTable, and deleted keys
+// =============================================================================
+#[test]
+fn db_get_pinned_all_sources() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    let large = vec![b'x'; 16 * 1024];
+    db.put(b"on_disk", &large).unwrap();
+    db.put(b"deleted", b"v").unwrap();
+    db.delete(b"deleted").unwrap();
+    db.flush().unwrap();
+    db.put(b"in_memory", b"m").unwrap();
+
+    assert_eq!(
+        db.get_pinned(b"on_disk").unwrap().as_deref(),
+        Some(large.as_slice())
+    );
+    assert_eq!(
+        db.get_pinned(b"in_memory").unwrap().as_deref(),
+        Some(b"m".as_slice())
+    );
+    assert!(db.get_pinned(b"deleted").unwrap().is_none());
+    assert!(db.get_pinned(b"missing").unwrap().is_none());
+}
+
+// =============================================================================
+// Test 6: Iterators read values in place, from the memtable and cached blocks
+// =============================================================================
+#[test]
+fn iterator_values_share_buffers() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    let large = vec![b'x'; 16 * 1024];
+    db.put(b"on_disk", &large).unwrap();
+    db.flush().unwrap();
+    db.put(b"in_memory", &large).unwrap();
+
+    // The memtable's own buffer
+    let pinned = db.get_pinned(b"in_memory").unwrap().unwrap();
+    let mut iter = db.iter().unwrap();
+    iter.seek(b"in_memory").unwrap();
+    assert_eq!(iter.value().as_ptr(), pinned.as_ptr());
+
+    // The first read fills the block cache; later ones share its buffer
+    db.get_pinned(b"on_disk").unwrap();
+    let pinned = db.get_pinned(b"on_disk").unwrap().unwrap();
+    for mut iter in [db.iter().unwrap(), db.iter().unwrap()] {
+        iter.seek(b"on_disk").unwrap();
+        assert_eq!(iter.value(), large.as_slice());
+        assert_eq!(iter.value().as_ptr(), pinned.as_ptr());
+    }
+}
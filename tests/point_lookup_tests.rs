@@ -1,6 +1,7 @@
 // M24: Point Lookup Integration Tests
 // Tests for the full read path: memtable → immutable memtable → L0 → L1 → ...
 
+use bytes::Bytes;
 use lsm_engine::memtable::MemTable;
 use lsm_engine::sstable::builder::SSTableBuilder;
 use lsm_engine::sstable::reader::SSTable;
@@ -11,11 +12,14 @@ use tempfile::tempdir;
 // =============================================================================
 #[test]
 fn key_in_active_memtable_found_directly() {
-    let mut memtable = MemTable::new(1024 * 1024);
+    let memtable = MemTable::new(1024 * 1024);
     memtable.put(b"alice".to_vec(), b"value_alice".to_vec());
 
     // Simulate: just check memtable, don't go to SSTables
-    assert_eq!(memtable.get(b"alice"), Some(b"value_alice".as_slice()));
+    assert_eq!(
+        memtable.read().get(b"alice"),
+        Some(b"value_alice".as_slice())
+    );
 }
 
 // =============================================================================
@@ -23,12 +27,12 @@ fn key_in_active_memtable_found_directly() {
 // =============================================================================
 #[test]
 fn key_in_immutable_memtable_found() {
-    let mut memtable = MemTable::new(1024 * 1024);
+    let memtable = MemTable::new(1024 * 1024);
     memtable.put(b"bob".to_vec(), b"value_bob".to_vec());
 
     // In reality, immutable memtable would be wrapped in Arc<MemTable>
     // Here we just verify memtable.get works
-    assert_eq!(memtable.get(b"bob"), Some(b"value_bob".as_slice()));
+    assert_eq!(memtable.read().get(b"bob"), Some(b"value_bob".as_slice()));
 }
 
 // =============================================================================
@@ -49,7 +53,7 @@ fn key_in_l0_sstable_found() {
     let sstable = SSTable::open(&path).unwrap();
     assert_eq!(
         sstable.get(b"charlie").unwrap(),
-        Some(b"value_charlie".to_vec())
+        Some(Bytes::from_static(b"value_charlie"))
     );
 }
 
@@ -73,7 +77,10 @@ fn key_in_l1_sstable_found() {
 
     // Open and read
     let sstable = SSTable::open(&path).unwrap();
-    assert_eq!(sstable.get(b"eve").unwrap(), Some(b"value_eve".to_vec()));
+    assert_eq!(
+        sstable.get(b"eve").unwrap(),
+        Some(Bytes::from_static(b"value_eve"))
+    );
 }
 
 // =============================================================================
@@ -92,17 +99,20 @@ fn key_updated_returns_newest_from_memtable() {
     let l1_table = SSTable::open(&l1_path).unwrap();
 
     // Active memtable with new value
-    let mut memtable = MemTable::new(1024 * 1024);
+    let memtable = MemTable::new(1024 * 1024);
     memtable.put(b"george".to_vec(), b"new_value".to_vec());
 
     // Read order: memtable first
     // memtable has the key, so we return that and don't check L1
-    assert_eq!(memtable.get(b"george"), Some(b"new_value".as_slice()));
+    assert_eq!(
+        memtable.read().get(b"george"),
+        Some(b"new_value".as_slice())
+    );
 
     // Verify L1 has old value (for understanding)
     assert_eq!(
         l1_table.get(b"george").unwrap(),
-        Some(b"old_value".to_vec())
+        Some(Bytes::from_static(b"old_value"))
     );
 }
 
@@ -124,18 +134,18 @@ fn key_deleted_tombstone_hides_old_value() {
     let l1_table = SSTable::open(&l1_path).unwrap();
 
     // Active memtable with tombstone (delete)
-    let mut memtable = MemTable::new(1024 * 1024);
+    let memtable = MemTable::new(1024 * 1024);
     memtable.put(b"helen".to_vec(), b"value_helen".to_vec());
     memtable.delete(b"helen".to_vec());
 
     // Read order: check memtable first
     // memtable has tombstone for the key, return None (don't check L1)
-    assert_eq!(memtable.get(b"helen"), None);
+    assert_eq!(memtable.read().get(b"helen"), None);
 
     // Verify L1 has the value (but we wouldn't read it due to tombstone)
     assert_eq!(
         l1_table.get(b"helen").unwrap(),
-        Some(b"value_helen".to_vec())
+        Some(Bytes::from_static(b"value_helen"))
     );
 }
 
@@ -147,7 +157,7 @@ fn key_not_found_anywhere_returns_none() {
     let memtable = MemTable::new(1024 * 1024);
 
     // Key not in memtable
-    assert_eq!(memtable.get(b"nonexistent"), None);
+    assert_eq!(memtable.read().get(b"nonexistent"), None);
 
     // In a full DB, this would:
     // 1. Check active memtable → miss
@@ -163,7 +173,7 @@ fn key_not_found_anywhere_returns_none() {
 // =============================================================================
 #[test]
 fn multiple_keys_in_memtable_correct_state() {
-    let mut memtable = MemTable::new(1024 * 1024);
+    let memtable = MemTable::new(1024 * 1024);
 
     // Add some keys
     memtable.put(b"key1".to_vec(), b"val1".to_vec());
@@ -177,9 +187,9 @@ fn multiple_keys_in_memtable_correct_state() {
     memtable.put(b"key3".to_vec(), b"val3_new".to_vec());
 
     // Verify state
-    assert_eq!(memtable.get(b"key1"), Some(b"val1".as_slice()));
-    assert_eq!(memtable.get(b"key2"), None);
-    assert_eq!(memtable.get(b"key3"), Some(b"val3_new".as_slice()));
+    assert_eq!(memtable.read().get(b"key1"), Some(b"val1".as_slice()));
+    assert_eq!(memtable.read().get(b"key2"), None);
+    assert_eq!(memtable.read().get(b"key3"), Some(b"val3_new".as_slice()));
 }
 
 // =============================================================================
@@ -205,8 +215,14 @@ fn l0_with_multiple_sstables_all_checked() {
     let sst2 = SSTable::open(&l0_2_path).unwrap();
 
     // In L0, both tables must be checked because they can overlap
-    assert_eq!(sst1.get(b"alice").unwrap(), Some(b"alice_val".to_vec()));
-    assert_eq!(sst2.get(b"bob").unwrap(), Some(b"bob_val".to_vec()));
+    assert_eq!(
+        sst1.get(b"alice").unwrap(),
+        Some(Bytes::from_static(b"alice_val"))
+    );
+    assert_eq!(
+        sst2.get(b"bob").unwrap(),
+        Some(Bytes::from_static(b"bob_val"))
+    );
 
     // Neither table has the other's key
     assert_eq!(sst1.get(b"bob").unwrap(), None);
@@ -229,15 +245,18 @@ fn read_order_memtable_before_l0() {
     let sst = SSTable::open(&l0_path).unwrap();
 
     // Active memtable with updated value for same key
-    let mut memtable = MemTable::new(1024 * 1024);
+    let memtable = MemTable::new(1024 * 1024);
     memtable.put(b"shared_key".to_vec(), b"memtable_value".to_vec());
 
     // Reading memtable first gives us the newest value
     assert_eq!(
-        memtable.get(b"shared_key"),
+        memtable.read().get(b"shared_key"),
         Some(b"memtable_value".as_slice())
     );
 
     // L0 has stale data (wouldn't be read due to early termination)
-    assert_eq!(sst.get(b"shared_key").unwrap(), Some(b"l0_value".to_vec()));
+    assert_eq!(
+        sst.get(b"shared_key").unwrap(),
+        Some(Bytes::from_static(b"l0_value"))
+    );
 }
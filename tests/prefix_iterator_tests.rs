@@ -0,0 +1,151 @@
+// Prefix iteration tests
+//
+// DB::prefix_iter() scans all keys under a prefix; with a prefix extractor
+// and ReadOptions::prefix_same_as_start, seeks stay within the target's
+// prefix.
+
+use std::sync::Arc;
+
+use lsm_engine::db::iterator::DBIterator;
+use lsm_engine::db::prefix::{FixedPrefix, SliceTransform, prefix_successor};
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{DB, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn collect_keys(iter: &mut DBIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+/// Three users with two fields each; user:1 flushed, the rest in memtable.
+fn users_db(opts: Options) -> (tempfile::TempDir, DB) {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), opts).unwrap();
+    db.put(b"user:1:age", b"30").unwrap();
+    db.put(b"user:1:name", b"ann").unwrap();
+    db.flush().unwrap();
+    db.put(b"user:2:age", b"41").unwrap();
+    db.put(b"user:2:name", b"bob").unwrap();
+    db.put(b"user:3:age", b"25").unwrap();
+    db.put(b"user:3:name", b"cat").unwrap();
+    (dir, db)
+}
+
+// =============================================================================
+// Test 1: prefix_successor — increments the last non-0xFF byte
+// =============================================================================
+#[test]
+fn prefix_successor_cases() {
+    assert_eq!(prefix_successor(b"user:1"), Some(b"user:2".to_vec()));
+    assert_eq!(prefix_successor(b"a\xff\xff"), Some(b"b".to_vec()));
+    assert_eq!(prefix_successor(b"\xff\xff"), None);
+    assert_eq!(prefix_successor(b""), None);
+}
+
+// =============================================================================
+// Test 2: FixedPrefix — first n bytes, shorter keys out of domain
+// =============================================================================
+#[test]
+fn fixed_prefix_transform() {
+    let t = FixedPrefix(7);
+    assert!(t.in_domain(b"user:1:name"));
+    assert_eq!(t.transform(b"user:1:name"), b"user:1:");
+    assert!(!t.in_domain(b"user"));
+}
+
+// =============================================================================
+// Test 3: prefix_iter yields exactly the keys under the prefix
+// =============================================================================
+#[test]
+fn prefix_iter_yields_only_matching_keys() {
+    let (_dir, db) = users_db(Options::default());
+
+    let mut iter = db.prefix_iter(b"user:2:").unwrap();
+    assert_eq!(
+        collect_keys(&mut iter),
+        vec![b"user:2:age".to_vec(), b"user:2:name".to_vec()]
+    );
+
+    // Prefix in an SSTable
+    let mut iter = db.prefix_iter(b"user:1:").unwrap();
+    assert_eq!(collect_keys(&mut iter).len(), 2);
+
+    let iter = db.prefix_iter(b"user:9:").unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 4: prefix_iter never opens SSTables that can't hold the prefix
+// =============================================================================
+#[test]
+fn prefix_iter_skips_non_matching_sstables() {
+    let (dir, db) = users_db(Options::default());
+    db.flush().unwrap();
+
+    // Corrupt the SSTable holding user:1:* — opening it would fail
+    std::fs::write(
+        dir.path().join(format!("{:06}.sst", 1)),
+        b"not an sstable at all, just garbage bytes....................",
+    )
+    .unwrap();
+
+    let mut iter = db.prefix_iter(b"user:3:").unwrap();
+    assert_eq!(
+        collect_keys(&mut iter),
+        vec![b"user:3:age".to_vec(), b"user:3:name".to_vec()]
+    );
+}
+
+// =============================================================================
+// Test 5: prefix_same_as_start — each seek stops at the end of its prefix
+// =============================================================================
+#[test]
+fn prefix_same_as_start_stops_at_prefix_end() {
+    let opts = Options {
+        prefix_extractor: Some(Arc::new(FixedPrefix(7))),
+        ..Options::default()
+    };
+    let (_dir, db) = users_db(opts);
+    db.delete(b"user:2:name").unwrap();
+
+    let mut iter = db
+        .iter_with_options(&ReadOptions {
+            prefix_same_as_start: true,
+            ..ReadOptions::default()
+        })
+        .unwrap();
+
+    iter.seek(b"user:1:").unwrap();
+    assert_eq!(
+        collect_keys(&mut iter),
+        vec![b"user:1:age".to_vec(), b"user:1:name".to_vec()]
+    );
+
+    // Deleted tail key doesn't leak the next prefix
+    iter.seek(b"user:2:").unwrap();
+    assert_eq!(collect_keys(&mut iter), vec![b"user:2:age".to_vec()]);
+
+    // seek_to_first drops the prefix restriction
+    iter.seek_to_first().unwrap();
+    assert_eq!(collect_keys(&mut iter).len(), 5);
+}
+
+// =============================================================================
+// Test 6: Without prefix_same_as_start, seeks are not confined
+// =============================================================================
+#[test]
+fn extractor_without_prefix_mode_is_unrestricted() {
+    let opts = Options {
+        prefix_extractor: Some(Arc::new(FixedPrefix(7))),
+        ..Options::default()
+    };
+    let (_dir, db) = users_db(opts);
+
+    let mut iter = db.iter().unwrap();
+    iter.seek(b"user:2:").unwrap();
+    assert_eq!(collect_keys(&mut iter).len(), 4);
+}
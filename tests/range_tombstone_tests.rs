@@ -0,0 +1,253 @@
+// Range tombstone block tests
+//
+// SSTables can carry a range deletion block: fragmented [start, end)
+// ranges deleting keys in older tables. SSTable::get returns a tombstone
+// for covered keys the table has no entry of its own for, DB iterators
+// skip covered entries of older tables, and compaction carries the ranges
+// into its output unless it's bottommost.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use lsm_engine::Error;
+use lsm_engine::compaction::scheduler::{CompactionJobOptions, run_compaction};
+use lsm_engine::compaction::size_tiered::SizeTieredStrategy;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::manifest::Manifest;
+use lsm_engine::manifest::version::VersionSet;
+use lsm_engine::sstable::builder::{SSTableBuilder, TableOptions};
+use lsm_engine::sstable::footer::{Footer, SSTableMeta};
+use lsm_engine::sstable::range_del::{RangeTombstone, RangeTombstones};
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("k{:03}", i).into_bytes()
+}
+
+/// Table `id` at `level` holding `keys` with `value`, and `ranges`
+/// (as key numbers) as range tombstones.
+fn write_table(
+    dir: &Path,
+    id: u64,
+    level: u32,
+    keys: &[u32],
+    value: &[u8],
+    ranges: &[(u32, u32)],
+) -> SSTableMeta {
+    let path = dir.join(format!("{:06}.sst", id));
+    let mut builder = SSTableBuilder::new(&path, id, 4096).unwrap();
+    for (start, end) in ranges {
+        builder.add_range_tombstone(&key(*start), &key(*end));
+    }
+    for i in keys {
+        builder.add(&key(*i), value).unwrap();
+    }
+    let mut meta = builder.finish().unwrap();
+    meta.level = level;
+    meta
+}
+
+fn collect_keys(iter: &mut impl StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: The block round-trips fragmented, and get() consults it
+// =============================================================================
+#[test]
+fn range_tombstone_block_round_trip() {
+    let dir = tempdir().unwrap();
+    // Unordered and overlapping: fragments to [10, 30) and [40, 50)
+    let meta = write_table(
+        dir.path(),
+        1,
+        0,
+        &[15, 60],
+        b"v",
+        &[(20, 30), (40, 50), (10, 25), (5, 5)],
+    );
+    assert_eq!(meta.min_key, key(10));
+    assert_eq!(meta.max_key, key(60));
+
+    let sst = SSTable::open(&dir.path().join("000001.sst")).unwrap();
+    assert!(sst.footer().range_del_block_size > 0);
+    assert_eq!(
+        sst.range_tombstones().fragments(),
+        &[
+            RangeTombstone::new(&key(10), &key(30)),
+            RangeTombstone::new(&key(40), &key(50)),
+        ]
+    );
+    assert_eq!(sst.meta().min_key, key(10));
+
+    // The table's own entry wins over its range tombstone
+    assert_eq!(sst.get(&key(15)).unwrap().unwrap(), &b"v"[..]);
+    assert_eq!(sst.get(&key(20)).unwrap().unwrap(), &b""[..]);
+    assert_eq!(sst.get(&key(49)).unwrap().unwrap(), &b""[..]);
+    assert!(sst.may_contain(&key(45)).unwrap());
+    assert!(sst.get(&key(30)).unwrap().is_none());
+    assert!(sst.get(&key(55)).unwrap().is_none());
+    assert_eq!(
+        collect_keys(&mut sst.iter().unwrap()),
+        vec![key(15), key(60)]
+    );
+
+    // Tables without range tombstones have no block
+    let plain = write_table(dir.path(), 2, 0, &[1], b"v", &[]);
+    let sst = SSTable::open(&dir.path().join("000002.sst")).unwrap();
+    assert_eq!(sst.footer().range_del_block_size, 0);
+    assert!(sst.range_tombstones().is_empty());
+    assert_eq!(plain.min_key, key(1));
+
+    let set = RangeTombstones::new(vec![
+        RangeTombstone::new(b"a", b"c"),
+        RangeTombstone::new(b"c", b"e"),
+    ]);
+    assert_eq!(set.len(), 1);
+    assert_eq!(
+        set.clipped(Some(b"b"), Some(b"d")),
+        vec![RangeTombstone::new(b"b", b"d")]
+    );
+}
+
+// =============================================================================
+// Test 2: A corrupt range deletion block fails to open
+// =============================================================================
+#[test]
+fn corrupt_range_tombstone_block_detected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    write_table(dir.path(), 1, 0, &[1, 2], b"v", &[(3, 9)]);
+
+    let mut data = std::fs::read(&path).unwrap();
+    let footer = Footer::decode(&data[data.len() - Footer::SIZE..]).unwrap();
+    data[footer.range_del_block_offset as usize + 6] ^= 0xFF;
+    std::fs::write(&path, &data).unwrap();
+
+    match SSTable::open(&path) {
+        Err(Error::Corruption(msg)) => assert!(msg.contains("range deletion"), "{msg}"),
+        Err(e) => panic!("unexpected error {e}"),
+        Ok(_) => panic!("corrupt range deletion block was accepted"),
+    }
+}
+
+// =============================================================================
+// Test 3: DB reads hide keys a newer table's range tombstone covers
+// =============================================================================
+#[test]
+fn db_reads_honor_range_tombstones() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..20 {
+            db.put(&key(i), b"old").unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    // Newer L0 table: delete [5, 15), but rewrite 8 after the deletion
+    {
+        let mut manifest = Manifest::open(&dir.path().join("MANIFEST")).unwrap();
+        let id = manifest.next_sst_id();
+        let meta = write_table(dir.path(), id, 0, &[8], b"new", &[(5, 15)]);
+        manifest.record_flush(meta).unwrap();
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    let expected: Vec<Vec<u8>> = (0..20)
+        .filter(|i| !(5..15).contains(i) || *i == 8)
+        .map(key)
+        .collect();
+    for i in 0..20 {
+        let value = db.get(&key(i)).unwrap();
+        match i {
+            8 => assert_eq!(value.as_deref(), Some(&b"new"[..])),
+            5..15 => assert_eq!(value, None, "key {i}"),
+            _ => assert_eq!(value.as_deref(), Some(&b"old"[..]), "key {i}"),
+        }
+    }
+    assert_eq!(collect_keys(&mut db.iter().unwrap()), expected);
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get(&key(10)).unwrap(), None);
+
+    // Bottommost compaction applies the ranges and drops them
+    db.compact_range(None, None).unwrap();
+    assert_eq!(collect_keys(&mut db.iter().unwrap()), expected);
+    assert_eq!(db.get(&key(8)).unwrap().as_deref(), Some(&b"new"[..]));
+    assert_eq!(db.raw_iter().unwrap().count(), expected.len());
+}
+
+// =============================================================================
+// Test 4: Non-bottommost compaction splits ranges across its outputs
+// =============================================================================
+#[test]
+fn compaction_keeps_range_tombstones_above_older_data() {
+    let dir = tempdir().unwrap();
+    let vs = Arc::new(VersionSet::new(3));
+    {
+        let current = vs.current();
+        let mut v = current.write().unwrap();
+        let all: Vec<u32> = (0..100).collect();
+        v.levels[2].push(write_table(dir.path(), 501, 2, &all, b"old", &[]));
+        let ends: Vec<u32> = (0..10).chain(90..100).collect();
+        v.levels[0].push(write_table(
+            dir.path(),
+            502,
+            0,
+            &ends,
+            &[7u8; 200],
+            &[(10, 90)],
+        ));
+    }
+
+    let table_options = TableOptions {
+        target_file_size_base: 1024,
+        ..TableOptions::default()
+    };
+    let strategy = SizeTieredStrategy::new(1);
+    assert!(
+        run_compaction(
+            &vs,
+            &strategy,
+            dir.path(),
+            &table_options,
+            &CompactionJobOptions::default(),
+        )
+        .unwrap()
+        .is_some()
+    );
+
+    let current = vs.current();
+    let v = current.read().unwrap();
+    let l1 = v.level(1);
+    assert!(l1.len() > 1, "expected the output to be cut");
+
+    let mut fragments = Vec::new();
+    for meta in l1 {
+        let sst = SSTable::open(&dir.path().join(format!("{:06}.sst", meta.id))).unwrap();
+        for t in sst.range_tombstones().fragments() {
+            assert!(meta.min_key <= t.start && t.end <= meta.max_key);
+            fragments.push(t.clone());
+        }
+        for i in 10..90 {
+            if let Some(value) = sst.get(&key(i)).unwrap() {
+                assert!(value.is_empty(), "key {i} resurfaced");
+            }
+        }
+    }
+    assert_eq!(
+        RangeTombstones::new(fragments).fragments(),
+        &[RangeTombstone::new(&key(10), &key(90))]
+    );
+}
@@ -0,0 +1,92 @@
+// Rate limiter tests
+//
+// RateLimiter is a token bucket: writes up to the burst size go through
+// at once, anything beyond waits for the bucket to refill at the
+// configured rate. Flush and compaction charge every SSTable byte to
+// Options::rate_limiter. Timing checks leave generous slack.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use lsm_engine::rate_limiter::RateLimiter;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+const MB: u64 = 1024 * 1024;
+
+// =============================================================================
+// Test 1: The burst goes through, the rest is paced
+// =============================================================================
+#[test]
+fn burst_then_paced() {
+    let limiter = RateLimiter::new(MB).with_burst(100 * 1024);
+    assert_eq!(limiter.bytes_per_second(), MB);
+
+    let start = Instant::now();
+    limiter.request(100 * 1024);
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    // 200KB of debt at 1MB/s
+    limiter.request(200 * 1024);
+    assert!(start.elapsed() >= Duration::from_millis(180));
+    assert_eq!(limiter.total_bytes_through(), 300 * 1024);
+}
+
+// =============================================================================
+// Test 2: Threads sharing a limiter are paced together
+// =============================================================================
+#[test]
+fn shared_between_threads() {
+    let limiter = RateLimiter::new(MB).with_burst(0);
+    let start = Instant::now();
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..5 {
+                    limiter.request(10 * 1024);
+                }
+            });
+        }
+    });
+    // 200KB at 1MB/s, whichever thread wrote it
+    assert!(start.elapsed() >= Duration::from_millis(180));
+
+    // A faster rate applies from the next request
+    limiter.set_bytes_per_second(100 * MB);
+    let start = Instant::now();
+    limiter.request(100 * 1024);
+    assert!(start.elapsed() < Duration::from_millis(100));
+}
+
+// =============================================================================
+// Test 3: Flush and compaction writes are charged to the DB's limiter
+// =============================================================================
+#[test]
+fn db_writes_go_through_limiter() {
+    let dir = tempdir().unwrap();
+    let limiter = Arc::new(RateLimiter::new(2 * MB).with_burst(0));
+    let db = DB::open(
+        dir.path(),
+        Options {
+            rate_limiter: Some(Arc::clone(&limiter)),
+            compaction_style: CompactionStyle::SizeTiered,
+            ..Options::default()
+        },
+    )
+    .unwrap();
+    for i in 0..2000u32 {
+        db.put(format!("key_{i:05}").as_bytes(), &[b'v'; 200])
+            .unwrap();
+    }
+
+    let start = Instant::now();
+    db.flush().unwrap();
+    let flushed: u64 = db.live_files_metadata().iter().map(|f| f.file_size).sum();
+    assert!(flushed > 400 * 1024);
+    assert_eq!(limiter.total_bytes_through(), flushed);
+    // 400KB+ at 2MB/s
+    assert!(start.elapsed() >= Duration::from_millis(180));
+
+    db.compact_range(None, None).unwrap();
+    assert!(limiter.total_bytes_through() > flushed);
+}
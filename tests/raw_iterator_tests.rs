@@ -0,0 +1,242 @@
+// Raw iterator tests
+//
+// DB::raw_iter() exposes every stored version — tombstones and shadowed
+// values included — ordered by InternalKey, each with the sequence
+// number it was written with. Versions are streamed from every source,
+// not read up front.
+
+use lsm_engine::types::ValueType;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+/// Put `key` and return the sequence number the write got.
+fn put(db: &DB, key: &[u8], value: &[u8]) -> u64 {
+    let seq = db.snapshot().seq;
+    db.put(key, value).unwrap();
+    seq
+}
+
+/// Delete `key` and return the sequence number the write got.
+fn delete(db: &DB, key: &[u8]) -> u64 {
+    let seq = db.snapshot().seq;
+    db.delete(key).unwrap();
+    seq
+}
+
+/// Every version as (user key, sequence, value).
+fn raw_versions(db: &DB) -> Vec<(Vec<u8>, u64, Vec<u8>)> {
+    db.raw_iter()
+        .unwrap()
+        .map(|(k, v)| (k.user_key, k.sequence, v))
+        .collect()
+}
+
+// =============================================================================
+// Test 1: Empty database yields nothing
+// =============================================================================
+#[test]
+fn raw_iter_empty_db() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert_eq!(db.raw_iter().unwrap().count(), 0);
+}
+
+// =============================================================================
+// Test 2: Overwrites in one memtable are all listed, newest first
+// =============================================================================
+#[test]
+fn raw_iter_lists_memtable_overwrites_newest_first() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    let s1 = put(&db, b"key", b"v1");
+    let s2 = put(&db, b"key", b"v2");
+    let s3 = put(&db, b"key", b"v3");
+    let other = put(&db, b"other", b"x");
+
+    assert_eq!(
+        raw_versions(&db),
+        vec![
+            (b"key".to_vec(), s3, b"v3".to_vec()),
+            (b"key".to_vec(), s2, b"v2".to_vec()),
+            (b"key".to_vec(), s1, b"v1".to_vec()),
+            (b"other".to_vec(), other, b"x".to_vec()),
+        ]
+    );
+}
+
+// =============================================================================
+// Test 3: Tombstones come out as ValueType::Delete
+// =============================================================================
+#[test]
+fn raw_iter_exposes_tombstones() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    db.put(b"a", b"1").unwrap();
+    db.put(b"b", b"2").unwrap();
+    db.flush().unwrap();
+    db.delete(b"a").unwrap();
+
+    let entries: Vec<_> = db
+        .raw_iter()
+        .unwrap()
+        .map(|(k, v)| (k.user_key, k.value_type, v))
+        .collect();
+    assert_eq!(
+        entries,
+        vec![
+            (b"a".to_vec(), ValueType::Delete, Vec::new()),
+            (b"a".to_vec(), ValueType::Put, b"1".to_vec()),
+            (b"b".to_vec(), ValueType::Put, b"2".to_vec()),
+        ]
+    );
+}
+
+// =============================================================================
+// Test 4: Entries are sorted by user key across all sources
+// =============================================================================
+#[test]
+fn raw_iter_sorted_by_user_key() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    db.put(b"c", b"v").unwrap();
+    db.put(b"a", b"v").unwrap();
+    db.flush().unwrap();
+    db.put(b"b", b"v").unwrap();
+    db.put(b"d", b"v").unwrap();
+
+    let keys: Vec<Vec<u8>> = db.raw_iter().unwrap().map(|(k, _)| k.user_key).collect();
+    assert_eq!(
+        keys,
+        vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec(), b"d".to_vec()]
+    );
+}
+
+// =============================================================================
+// Test 5: SSTable entries carry the sequence number they were written with
+// =============================================================================
+#[test]
+fn raw_iter_reports_sstable_entry_sequences() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    // The first table's entries are written well before its last one
+    let a1 = put(&db, b"a", b"a1");
+    let b1 = put(&db, b"b", b"b1");
+    let a2 = put(&db, b"a", b"a2");
+    let c1 = put(&db, b"c", b"c1");
+    db.flush().unwrap();
+    let b2 = put(&db, b"b", b"b2");
+    let d = delete(&db, b"c");
+    db.flush().unwrap();
+    let a3 = put(&db, b"a", b"a3");
+
+    let mut metas = db.live_files_metadata();
+    metas.sort_by_key(|meta| meta.largest_seqno);
+    assert_eq!(metas.len(), 2);
+    assert_eq!(metas[0].largest_seqno, c1);
+    assert_eq!(metas[1].largest_seqno, d);
+
+    // The flush kept only a2 of key a's versions in its memtable
+    assert!(a1 < a2);
+    assert_eq!(
+        raw_versions(&db),
+        vec![
+            (b"a".to_vec(), a3, b"a3".to_vec()),
+            (b"a".to_vec(), a2, b"a2".to_vec()),
+            (b"b".to_vec(), b2, b"b2".to_vec()),
+            (b"b".to_vec(), b1, b"b1".to_vec()),
+            (b"c".to_vec(), d, Vec::new()),
+            (b"c".to_vec(), c1, b"c1".to_vec()),
+        ]
+    );
+}
+
+// =============================================================================
+// Test 6: Sequence numbers survive reopening and compaction
+// =============================================================================
+#[test]
+fn raw_iter_sequences_survive_reopen_and_compaction() {
+    let dir = tempdir().unwrap();
+    // Size-tiered, so compact_range() merges both tables into one
+    let options = || Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let (k1, k2, k3) = {
+        let db = DB::open(dir.path(), options()).unwrap();
+        let k1 = put(&db, b"k1", b"v");
+        put(&db, b"k2", b"old");
+        db.flush().unwrap();
+        let k3 = put(&db, b"k3", b"v");
+        let k2 = put(&db, b"k2", b"new");
+        db.flush().unwrap();
+        (k1, k2, k3)
+    };
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.live_files_metadata().len(), 1);
+
+    // k2's older version is gone; the rest keep their own sequences
+    assert_eq!(
+        raw_versions(&db),
+        vec![
+            (b"k1".to_vec(), k1, b"v".to_vec()),
+            (b"k2".to_vec(), k2, b"new".to_vec()),
+            (b"k3".to_vec(), k3, b"v".to_vec()),
+        ]
+    );
+}
+
+// =============================================================================
+// Test 7: An open raw iterator streams on through writes, flushes and
+// compaction, across many sources
+// =============================================================================
+#[test]
+fn raw_iter_streams_through_flush_and_compaction() {
+    let dir = tempdir().unwrap();
+    let options = Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), options).unwrap();
+
+    // Three L0 tables and a memtable, each overwriting some of the keys
+    for round in 0..4 {
+        for i in (round..300).step_by(round + 1) {
+            let key = format!("key{:04}", i);
+            db.put(key.as_bytes(), format!("v{}", round).as_bytes())
+                .unwrap();
+        }
+        if round < 3 {
+            db.flush().unwrap();
+        }
+    }
+
+    let expected = raw_versions(&db);
+    assert!(
+        expected
+            .windows(2)
+            .all(|w| (&w[0].0, std::cmp::Reverse(w[0].1)) < (&w[1].0, std::cmp::Reverse(w[1].1)))
+    );
+
+    let mut iter = db.raw_iter().unwrap();
+    let mut seen: Vec<_> = iter
+        .by_ref()
+        .take(10)
+        .map(|(k, v)| (k.user_key, k.sequence, v))
+        .collect();
+
+    // Neither shows up in, nor ends, the open iteration
+    db.put(b"key0005", b"later").unwrap();
+    db.flush().unwrap();
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.live_files_metadata().len(), 1);
+
+    seen.extend(iter.by_ref().map(|(k, v)| (k.user_key, k.sequence, v)));
+    iter.status().unwrap();
+    assert_eq!(seen, expected);
+}
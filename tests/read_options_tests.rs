@@ -0,0 +1,163 @@
+// ReadOptions tests
+//
+// fill_cache, verify_checksums and snapshot are honored by get and iter,
+// down to the SSTable block reads.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{DB, Error, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn open_db(path: &Path) -> DB {
+    DB::open(path, Options::default()).unwrap()
+}
+
+/// Flip one byte inside the first occurrence of `needle` in every SSTable.
+fn corrupt_sstables(dir: &Path, needle: &[u8]) {
+    for entry in std::fs::read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "sst") {
+            let mut data = std::fs::read(&path).unwrap();
+            if let Some(pos) = data.windows(needle.len()).position(|w| w == needle) {
+                data[pos] ^= 0x01;
+                std::fs::write(&path, data).unwrap();
+            }
+        }
+    }
+}
+
+// =============================================================================
+// Test 1: Defaults fill the cache, verify checksums and read the latest state
+// =============================================================================
+#[test]
+fn read_options_defaults() {
+    let opts = ReadOptions::default();
+    assert!(opts.fill_cache);
+    assert!(opts.verify_checksums);
+    assert!(opts.snapshot.is_none());
+}
+
+// =============================================================================
+// Test 2: Corrupted block is detected only when verify_checksums is on
+// =============================================================================
+#[test]
+fn verify_checksums_detects_corruption() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    db.put(b"key", b"original_value").unwrap();
+    db.flush().unwrap();
+    corrupt_sstables(dir.path(), b"original_value");
+
+    let result = db.get_with_options(b"key", &ReadOptions::default());
+    assert!(matches!(result, Err(Error::Corruption(_))));
+    assert!(matches!(db.iter(), Err(Error::Corruption(_))));
+
+    let unchecked = ReadOptions {
+        verify_checksums: false,
+        ..ReadOptions::default()
+    };
+    let value = db.get_with_options(b"key", &unchecked).unwrap().unwrap();
+    assert_eq!(value, b"nriginal_value");
+}
+
+// =============================================================================
+// Test 3: fill_cache = false leaves the block cache untouched
+// =============================================================================
+#[test]
+fn fill_cache_controls_block_cache() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    db.put(b"key", b"value").unwrap();
+    db.flush().unwrap();
+
+    let no_fill = ReadOptions {
+        fill_cache: false,
+        ..ReadOptions::default()
+    };
+    for _ in 0..2 {
+        assert_eq!(
+            db.get_with_options(b"key", &no_fill).unwrap().unwrap(),
+            b"value"
+        );
+    }
+    assert_eq!(db.stats().block_cache_hit_rate, 0.0);
+
+    // First read fills the cache, the second one hits it
+    for _ in 0..2 {
+        assert_eq!(db.get(b"key").unwrap().unwrap(), b"value");
+    }
+    assert!(db.stats().block_cache_hit_rate > 0.0);
+}
+
+// =============================================================================
+// Test 4: get at a snapshot ignores later writes
+// =============================================================================
+#[test]
+fn get_at_snapshot() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    db.put(b"a", b"1").unwrap();
+    db.put(b"b", b"1").unwrap();
+    db.flush().unwrap();
+
+    let snapshot = Arc::new(db.snapshot());
+    db.put(b"a", b"2").unwrap();
+    db.delete(b"b").unwrap();
+
+    let at_snapshot = ReadOptions {
+        snapshot: Some(snapshot),
+        ..ReadOptions::default()
+    };
+    assert_eq!(
+        db.get_with_options(b"a", &at_snapshot).unwrap().unwrap(),
+        b"1"
+    );
+    assert_eq!(
+        db.get_with_options(b"b", &at_snapshot).unwrap().unwrap(),
+        b"1"
+    );
+    assert_eq!(db.get(b"a").unwrap().unwrap(), b"2");
+}
+
+// =============================================================================
+// Test 5: Iterating at a snapshot sees the snapshot's keys within bounds
+// =============================================================================
+#[test]
+fn iter_at_snapshot() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    for k in [b"a", b"b", b"c", b"d"] {
+        db.put(k, b"old").unwrap();
+    }
+    db.flush().unwrap();
+    db.put(b"e", b"old").unwrap();
+
+    let snapshot = Arc::new(db.snapshot());
+    db.delete(b"b").unwrap();
+    db.put(b"c", b"new").unwrap();
+    db.put(b"bb", b"new").unwrap();
+
+    let mut iter = db
+        .iter_with_options(&ReadOptions {
+            iterate_lower_bound: Some(b"b".to_vec()),
+            snapshot: Some(snapshot),
+            ..ReadOptions::default()
+        })
+        .unwrap();
+    let mut entries = Vec::new();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    assert_eq!(
+        entries,
+        vec![
+            (b"b".to_vec(), b"old".to_vec()),
+            (b"c".to_vec(), b"old".to_vec()),
+            (b"d".to_vec(), b"old".to_vec()),
+            (b"e".to_vec(), b"old".to_vec()),
+        ]
+    );
+}
@@ -0,0 +1,137 @@
+// Readahead tests
+//
+// Once an SSTableIterator has loaded a few consecutive blocks, it reads
+// ReadOptions::readahead_size bytes per file read and serves the following
+// blocks from that buffer instead of issuing one small read per block.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::{BlockReadOptions, SSTable};
+use lsm_engine::{DB, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// 256-byte blocks: a few hundred of them.
+fn build_table(path: &Path) {
+    let mut builder = SSTableBuilder::new(path, 1, 256).unwrap();
+    for i in 0..5000 {
+        builder.add(&key(i), format!("v{i}").as_bytes()).unwrap();
+    }
+    builder.finish().unwrap();
+}
+
+fn scan(sst: &SSTable, readahead_size: usize) -> Vec<Vec<u8>> {
+    let opts = BlockReadOptions {
+        readahead_size,
+        ..BlockReadOptions::default()
+    };
+    let mut iter = sst.bounded_iter_with_options(None, None, &opts).unwrap();
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: Sequential scans issue far fewer reads with readahead
+// =============================================================================
+#[test]
+fn readahead_reduces_reads() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+
+    let sst = SSTable::open(&path).unwrap();
+    assert_eq!(scan(&sst, 0).len(), 5000);
+    let num_blocks = sst.num_blocks() as u64;
+    assert_eq!(sst.data_block_reads(), num_blocks);
+
+    let sst = SSTable::open(&path).unwrap();
+    assert_eq!(scan(&sst, 64 * 1024).len(), 5000);
+    assert!(
+        sst.data_block_reads() < num_blocks / 10,
+        "{} reads for {num_blocks} blocks",
+        sst.data_block_reads()
+    );
+}
+
+// =============================================================================
+// Test 2: Results don't depend on the readahead size
+// =============================================================================
+#[test]
+fn readahead_sizes_agree() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+    let sst = SSTable::open(&path).unwrap();
+
+    let expected: Vec<Vec<u8>> = (0..5000).map(key).collect();
+    for readahead_size in [0, 1, 300, 4096, 1 << 20] {
+        assert_eq!(scan(&sst, readahead_size), expected, "{readahead_size}");
+    }
+}
+
+// =============================================================================
+// Test 3: Seeks and reverse steps mixed with readahead
+// =============================================================================
+#[test]
+fn readahead_with_seeks() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    build_table(&path);
+    let sst = SSTable::open(&path).unwrap();
+
+    let opts = BlockReadOptions {
+        readahead_size: 8 * 1024,
+        ..BlockReadOptions::default()
+    };
+    let mut iter = sst.bounded_iter_with_options(None, None, &opts).unwrap();
+    for start in [4000, 100, 2500] {
+        iter.seek(&key(start)).unwrap();
+        for i in start..start + 300 {
+            assert_eq!(iter.key(), key(i).as_slice());
+            iter.next().unwrap();
+        }
+        for i in (start + 250..start + 300).rev() {
+            iter.prev().unwrap();
+            assert_eq!(iter.key(), key(i).as_slice());
+        }
+    }
+}
+
+// =============================================================================
+// Test 4: ReadOptions::readahead_size through DB iterators
+// =============================================================================
+#[test]
+fn db_readahead_option() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        block_size: 256,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..3000 {
+        db.put(&key(i), b"value").unwrap();
+    }
+    db.flush().unwrap();
+
+    let read_options = ReadOptions {
+        readahead_size: 32 * 1024,
+        ..ReadOptions::default()
+    };
+    let mut iter = db.iter_with_options(&read_options).unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.key(), key(count).as_slice());
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 3000);
+}
@@ -0,0 +1,209 @@
+// seek_for_prev / prev tests
+//
+// Every StorageIterator can be positioned at the floor entry (last key <= target)
+// and stepped backwards. Covers each implementor plus the merged DB scan view.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::iterator::merge::MergeIterator;
+use lsm_engine::iterator::vec_iter::VecIterator;
+use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn vec_iter(entries: &[(&[u8], &[u8])]) -> VecIterator {
+    VecIterator::new(
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect(),
+    )
+}
+
+// =============================================================================
+// Test 1: VecIterator — exact hit, between keys, before first, after last
+// =============================================================================
+#[test]
+fn vec_iterator_seek_for_prev() {
+    let mut iter = vec_iter(&[(b"b", b"1"), (b"d", b"2"), (b"f", b"3")]);
+
+    iter.seek_for_prev(b"d").unwrap();
+    assert_eq!(iter.key(), b"d");
+
+    iter.seek_for_prev(b"e").unwrap();
+    assert_eq!(iter.key(), b"d");
+
+    iter.seek_for_prev(b"z").unwrap();
+    assert_eq!(iter.key(), b"f");
+
+    iter.seek_for_prev(b"a").unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 2: Skip list — floor lookup and backward walk
+// =============================================================================
+#[test]
+fn skiplist_seek_for_prev_and_prev() {
+    let mut sl = SkipList::new();
+    for i in (0..100u32).step_by(10) {
+        sl.insert(format!("k{:03}", i).into_bytes(), b"v".to_vec());
+    }
+
+    let mut iter = sl.iter();
+    iter.seek_for_prev(b"k055").unwrap();
+    assert_eq!(iter.key(), b"k050");
+
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(String::from_utf8(iter.key().to_vec()).unwrap());
+        iter.prev().unwrap();
+    }
+    assert_eq!(keys, vec!["k050", "k040", "k030", "k020", "k010", "k000"]);
+
+    iter.seek_for_prev(b"a").unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 3: Block iterator — floor within a single block
+// =============================================================================
+#[test]
+fn block_iterator_seek_for_prev() {
+    let mut builder = BlockBuilder::new(4096);
+    builder.add(b"apple", b"1");
+    builder.add(b"banana", b"2");
+    builder.add(b"cherry", b"3");
+    let block = Block::decode(builder.build()).unwrap();
+
+    let mut iter = block.iter();
+    iter.seek_for_prev(b"blueberry").unwrap();
+    assert_eq!(iter.key(), b"banana");
+
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), b"apple");
+
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+
+    iter.seek_for_prev(b"aardvark").unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 4: SSTable iterator — floor crosses block boundaries
+// =============================================================================
+#[test]
+fn sstable_iterator_seek_for_prev_across_blocks() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.sst");
+
+    // Tiny blocks force many block boundaries
+    let mut builder = SSTableBuilder::new(&path, 1, 64).unwrap();
+    for i in (0..200u32).step_by(2) {
+        let key = format!("key_{:05}", i);
+        builder.add(key.as_bytes(), b"value").unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let mut iter = sst.iter().unwrap();
+
+    // Odd targets fall between two even keys
+    for i in (1..199u32).step_by(2) {
+        let target = format!("key_{:05}", i);
+        iter.seek_for_prev(target.as_bytes()).unwrap();
+        assert_eq!(iter.key(), format!("key_{:05}", i - 1).as_bytes());
+    }
+
+    iter.seek_for_prev(b"zzz").unwrap();
+    assert_eq!(iter.key(), b"key_00198");
+
+    // Walk the whole table backwards
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.prev().unwrap();
+    }
+    assert_eq!(count, 100);
+
+    iter.seek_for_prev(b"a").unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 5: SSTable range iterator — floor stays below the end key
+// =============================================================================
+#[test]
+fn sstable_range_iterator_seek_for_prev_respects_end() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.sst");
+
+    let mut builder = SSTableBuilder::new(&path, 1, 4096).unwrap();
+    for c in b'a'..=b'z' {
+        builder.add(&[c], b"v").unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let mut iter = sst.range_iter(b"c", b"h").unwrap();
+    iter.seek_for_prev(b"x").unwrap();
+    assert!(iter.is_valid());
+    assert_eq!(iter.key(), b"g");
+}
+
+// =============================================================================
+// Test 6: MergeIterator — newest source wins at the floor key
+// =============================================================================
+#[test]
+fn merge_iterator_seek_for_prev_keeps_newest() {
+    let newer = vec_iter(&[(b"b", b"new"), (b"e", b"new")]);
+    let older = vec_iter(&[(b"b", b"old"), (b"c", b"old"), (b"f", b"old")]);
+
+    let iters: Vec<Box<dyn StorageIterator>> = vec![Box::new(newer), Box::new(older)];
+    let mut merge = MergeIterator::new(iters).unwrap();
+
+    merge.seek_for_prev(b"d").unwrap();
+    assert_eq!(merge.key(), b"c");
+
+    merge.prev().unwrap();
+    assert_eq!(merge.key(), b"b");
+    assert_eq!(merge.value(), b"new");
+
+    // Moving forward again after a backward step works normally
+    merge.next().unwrap();
+    assert_eq!(merge.key(), b"c");
+    merge.next().unwrap();
+    assert_eq!(merge.key(), b"e");
+
+    merge.seek_for_prev(b"a").unwrap();
+    assert!(!merge.is_valid());
+}
+
+// =============================================================================
+// Test 7: DB scan — floor skips deleted keys
+// =============================================================================
+#[test]
+fn db_scan_seek_for_prev_skips_tombstones() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    db.put(b"t01", b"first").unwrap();
+    db.put(b"t02", b"second").unwrap();
+    db.flush().unwrap();
+    db.put(b"t03", b"third").unwrap();
+    db.delete(b"t03").unwrap();
+    db.delete(b"t02").unwrap();
+
+    let mut scanner = db.scan(b"t00", b"t99").unwrap();
+    scanner.seek_for_prev(b"t05").unwrap();
+    assert!(scanner.is_valid());
+    assert_eq!(scanner.key(), b"t01");
+    assert_eq!(scanner.value(), b"first");
+
+    scanner.prev().unwrap();
+    assert!(!scanner.is_valid());
+}
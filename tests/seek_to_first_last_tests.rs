@@ -0,0 +1,198 @@
+// seek_to_first / seek_to_last tests
+//
+// Full forward and reverse scans position the cursor without sentinel keys.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::iterator::merge::MergeIterator;
+use lsm_engine::iterator::vec_iter::VecIterator;
+use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::sstable::block::builder::BlockBuilder;
+use lsm_engine::sstable::block::reader::Block;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+/// Walk from the current position backwards, collecting keys.
+fn collect_reverse(iter: &mut dyn StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.prev().unwrap();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: Empty sources are invalid after either seek
+// =============================================================================
+#[test]
+fn empty_iterators_are_invalid() {
+    let mut vec = VecIterator::new(Vec::new());
+    vec.seek_to_first().unwrap();
+    assert!(!vec.is_valid());
+    vec.seek_to_last().unwrap();
+    assert!(!vec.is_valid());
+
+    let sl = SkipList::new();
+    let mut iter = sl.iter();
+    iter.seek_to_first().unwrap();
+    assert!(!iter.is_valid());
+    iter.seek_to_last().unwrap();
+    assert!(!iter.is_valid());
+}
+
+// =============================================================================
+// Test 2: Skip list — first/last and a full reverse scan
+// =============================================================================
+#[test]
+fn skiplist_seek_to_first_and_last() {
+    let mut sl = SkipList::new();
+    for c in [b'd', b'a', b'c', b'b'] {
+        sl.insert(vec![c], b"v".to_vec());
+    }
+
+    let mut iter = sl.iter();
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"d");
+    assert_eq!(
+        collect_reverse(&mut iter),
+        vec![b"d".to_vec(), b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]
+    );
+
+    iter.seek_to_first().unwrap();
+    assert_eq!(iter.key(), b"a");
+}
+
+// =============================================================================
+// Test 3: Block iterator — first/last entry
+// =============================================================================
+#[test]
+fn block_seek_to_first_and_last() {
+    let mut builder = BlockBuilder::new(4096);
+    builder.add(b"k1", b"v1");
+    builder.add(b"k2", b"v2");
+    builder.add(b"k3", b"v3");
+    let block = Block::decode(builder.build()).unwrap();
+
+    let mut iter = block.iter();
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"k3");
+    iter.seek_to_first().unwrap();
+    assert_eq!(iter.key(), b"k1");
+}
+
+// =============================================================================
+// Test 4: SSTable — reverse scan over many blocks returns every key
+// =============================================================================
+#[test]
+fn sstable_reverse_scan_from_last() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.sst");
+
+    let mut builder = SSTableBuilder::new(&path, 1, 64).unwrap();
+    for i in 0..50u32 {
+        builder
+            .add(format!("key_{:03}", i).as_bytes(), b"value")
+            .unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let mut iter = sst.iter().unwrap();
+    iter.seek_to_last().unwrap();
+    let keys = collect_reverse(&mut iter);
+
+    let expected: Vec<Vec<u8>> = (0..50u32)
+        .rev()
+        .map(|i| format!("key_{:03}", i).into_bytes())
+        .collect();
+    assert_eq!(keys, expected);
+
+    iter.seek_to_first().unwrap();
+    assert_eq!(iter.key(), b"key_000");
+}
+
+// =============================================================================
+// Test 5: SSTable range iterator — first/last clamp to [start, end)
+// =============================================================================
+#[test]
+fn sstable_range_seek_to_first_and_last() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("test.sst");
+
+    let mut builder = SSTableBuilder::new(&path, 1, 4096).unwrap();
+    for c in b'a'..=b'z' {
+        builder.add(&[c], b"v").unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let mut iter = sst.range_iter(b"f", b"k").unwrap();
+    iter.seek_to_last().unwrap();
+    assert_eq!(iter.key(), b"j");
+    iter.seek_to_first().unwrap();
+    assert_eq!(iter.key(), b"f");
+}
+
+// =============================================================================
+// Test 6: MergeIterator — last key across sources, reverse dedup
+// =============================================================================
+#[test]
+fn merge_seek_to_last_reverse_dedup() {
+    let newer = VecIterator::new(vec![
+        (b"b".to_vec(), b"new".to_vec()),
+        (b"c".to_vec(), b"new".to_vec()),
+    ]);
+    let older = VecIterator::new(vec![
+        (b"a".to_vec(), b"old".to_vec()),
+        (b"c".to_vec(), b"old".to_vec()),
+        (b"d".to_vec(), b"old".to_vec()),
+    ]);
+    let iters: Vec<Box<dyn StorageIterator>> = vec![Box::new(newer), Box::new(older)];
+    let mut merge = MergeIterator::new(iters).unwrap();
+
+    merge.seek_to_last().unwrap();
+    assert_eq!(merge.key(), b"d");
+    merge.prev().unwrap();
+    assert_eq!(merge.key(), b"c");
+    assert_eq!(merge.value(), b"new");
+
+    merge.seek_to_last().unwrap();
+    assert_eq!(
+        collect_reverse(&mut merge),
+        vec![b"d".to_vec(), b"c".to_vec(), b"b".to_vec(), b"a".to_vec()]
+    );
+
+    merge.seek_to_first().unwrap();
+    assert_eq!(merge.key(), b"a");
+}
+
+// =============================================================================
+// Test 7: DB scan — reverse scan of the range skips deleted keys
+// =============================================================================
+#[test]
+fn db_scan_reverse_from_last() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    for c in b'a'..=b'f' {
+        db.put(&[c], &[c]).unwrap();
+    }
+    db.flush().unwrap();
+    db.delete(b"d").unwrap();
+
+    let mut scanner = db.scan(b"b", b"f").unwrap();
+    scanner.seek_to_last().unwrap();
+    assert_eq!(scanner.key(), b"e");
+
+    let mut keys = Vec::new();
+    while scanner.is_valid() && scanner.key() >= b"b".as_slice() {
+        keys.push(scanner.key().to_vec());
+        scanner.prev().unwrap();
+    }
+    assert_eq!(keys, vec![b"e".to_vec(), b"c".to_vec(), b"b".to_vec()]);
+
+    scanner.seek_to_first().unwrap();
+    assert_eq!(scanner.key(), b"b");
+}
@@ -0,0 +1,115 @@
+// SSTable sequence number range tests
+//
+// Every SSTable records the smallest and largest sequence number of the
+// writes it holds, in its meta block and in the manifest. Flush takes the
+// range from the memtable, compaction spans its inputs, and reopening
+// numbers new writes past everything already flushed.
+
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    }
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:04}", i).into_bytes()
+}
+
+fn files(db: &DB) -> Vec<SSTableMeta> {
+    let mut files = db.live_files_metadata();
+    files.sort_by_key(|meta| meta.smallest_seqno);
+    files
+}
+
+// =============================================================================
+// Test 1: Flushed tables carry their writes' range, in file and manifest
+// =============================================================================
+#[test]
+fn flush_records_sequence_range() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..10 {
+            db.put(&key(i), b"v").unwrap();
+        }
+        db.flush().unwrap();
+        for i in 0..5 {
+            db.delete(&key(i)).unwrap();
+        }
+        db.flush().unwrap();
+
+        let files = files(&db);
+        assert_eq!(files.len(), 2);
+        assert_eq!((files[0].smallest_seqno, files[0].largest_seqno), (1, 10));
+        assert_eq!((files[1].smallest_seqno, files[1].largest_seqno), (11, 15));
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    let files = files(&db);
+    assert_eq!((files[1].smallest_seqno, files[1].largest_seqno), (11, 15));
+    let sst = SSTable::open(&dir.path().join(format!("{:06}.sst", files[1].id))).unwrap();
+    assert_eq!(sst.meta().smallest_seqno, 11);
+    assert_eq!(sst.meta().largest_seqno, 15);
+}
+
+// =============================================================================
+// Test 2: Compaction output spans the sequence numbers of its inputs
+// =============================================================================
+#[test]
+fn compaction_output_spans_inputs() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    for batch in 0..3 {
+        for i in 0..20 {
+            db.put(&key(i), format!("v{batch}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    assert_eq!(files(&db).len(), 3);
+
+    db.compact_range(None, None).unwrap();
+    let files = files(&db);
+    assert_eq!(files.len(), 1);
+    assert_eq!((files[0].smallest_seqno, files[0].largest_seqno), (1, 60));
+    assert_eq!(db.get(&key(3)).unwrap().as_deref(), Some(&b"v2"[..]));
+}
+
+// =============================================================================
+// Test 3: Sequence numbers keep increasing across reopens
+// =============================================================================
+#[test]
+fn sequence_numbers_monotonic_across_reopen() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..8 {
+            db.put(&key(i), b"v").unwrap();
+        }
+        db.flush().unwrap();
+        // Left in the WAL, replayed on reopen
+        db.put(&key(100), b"v").unwrap();
+        db.put(&key(101), b"v").unwrap();
+    }
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        assert_eq!(db.get(&key(101)).unwrap().as_deref(), Some(&b"v"[..]));
+        db.put(&key(102), b"v").unwrap();
+        db.flush().unwrap();
+
+        let files = files(&db);
+        assert_eq!((files[0].smallest_seqno, files[0].largest_seqno), (1, 8));
+        assert_eq!((files[1].smallest_seqno, files[1].largest_seqno), (9, 11));
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(&key(103), b"v").unwrap();
+    db.flush().unwrap();
+    let files = files(&db);
+    assert_eq!((files[2].smallest_seqno, files[2].largest_seqno), (12, 12));
+}
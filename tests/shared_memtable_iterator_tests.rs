@@ -0,0 +1,235 @@
+// Shared memtable iterator tests
+//
+// MemTable::shared_iter_at() iterates over a memtable held as an Arc,
+// yielding what range_iter_at() would, without holding its lock between
+// calls: writes, freezes and flushes go ahead while it's open.
+// MemTable::shared_versions_at() does the same for every version.
+
+use std::sync::Arc;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::memtable::MemTable;
+use lsm_engine::memtable::rep::{MemTableRepFactory, VectorFactory};
+use lsm_engine::types::ValueType;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+/// More entries than a few read-aheads' worth.
+const SEVERAL_READ_AHEADS: usize = 200;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key{:04}", i).into_bytes()
+}
+
+/// Memtable with keys 0..300, every third one overwritten and every
+/// fifth deleted afterwards. Returns it with the sequence number the
+/// first writes end at.
+fn memtable() -> (Arc<MemTable>, u64) {
+    let mt = Arc::new(MemTable::new(1024 * 1024));
+    let mut seq = 1;
+    for i in 0..300 {
+        mt.add(seq, ValueType::Put, key(i), b"old".to_vec());
+        seq += 1;
+    }
+    let first = seq;
+    for i in (0..300).step_by(3) {
+        mt.add(seq, ValueType::Put, key(i), b"new".to_vec());
+        seq += 1;
+    }
+    for i in (0..300).step_by(5) {
+        mt.add(seq, ValueType::Delete, key(i), Vec::new());
+        seq += 1;
+    }
+    (mt, first)
+}
+
+fn forward(iter: &mut dyn StorageIterator) -> Vec<(Vec<u8>, Vec<u8>, Option<u64>)> {
+    let mut entries = Vec::new();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec(), iter.seqno()));
+        iter.next().unwrap();
+    }
+    entries
+}
+
+fn backward(iter: &mut dyn StorageIterator) -> Vec<(Vec<u8>, Vec<u8>, Option<u64>)> {
+    let mut entries = Vec::new();
+    iter.seek_to_last().unwrap();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec(), iter.seqno()));
+        iter.prev().unwrap();
+    }
+    entries.reverse();
+    entries
+}
+
+// =============================================================================
+// Test 1: Same entries as range_iter_at(), both ways, across read-aheads
+// =============================================================================
+#[test]
+fn matches_range_iter_at() {
+    let (mt, first) = memtable();
+    let bounds = [
+        (None, None),
+        (Some(key(17)), None),
+        (None, Some(key(250))),
+        (Some(key(3)), Some(key(299))),
+    ];
+    for sequence in [first, u64::MAX] {
+        for (lower, upper) in &bounds {
+            let (lower, upper) = (lower.as_deref(), upper.as_deref());
+            let expected = forward(&mut mt.read().range_iter_at(lower, upper, sequence));
+            assert!(expected.len() > 100);
+
+            let mut shared = mt.shared_iter_at(lower, upper, sequence);
+            assert_eq!(forward(&mut shared), expected);
+            assert_eq!(backward(&mut shared), expected);
+        }
+    }
+}
+
+// =============================================================================
+// Test 2: Seeks and direction changes land where range_iter_at()'s do
+// =============================================================================
+#[test]
+fn seeks_and_direction_changes() {
+    let (mt, first) = memtable();
+    let guard = mt.read();
+    let mut expected = guard.range_iter_at(Some(&key(10)), Some(&key(200)), first);
+    let mut shared = mt.shared_iter_at(Some(&key(10)), Some(&key(200)), first);
+
+    for target in [key(0), key(64), key(150), key(199), key(250)] {
+        expected.seek(&target).unwrap();
+        shared.seek(&target).unwrap();
+        // Back across a read-ahead boundary, then forward again
+        for _ in 0..70 {
+            assert_eq!(shared.is_valid(), expected.is_valid());
+            if !expected.is_valid() {
+                break;
+            }
+            assert_eq!(shared.key(), expected.key());
+            expected.prev().unwrap();
+            shared.prev().unwrap();
+        }
+        expected.seek_for_prev(&target).unwrap();
+        shared.seek_for_prev(&target).unwrap();
+        for _ in 0..70 {
+            assert_eq!(shared.is_valid(), expected.is_valid());
+            if !expected.is_valid() {
+                break;
+            }
+            assert_eq!(shared.key(), expected.key());
+            assert_eq!(shared.value(), expected.value());
+            expected.next().unwrap();
+            shared.next().unwrap();
+        }
+    }
+}
+
+// =============================================================================
+// Test 3: Writes and marking read-only don't wait for an open iterator
+// =============================================================================
+#[test]
+fn writes_go_ahead_while_open() {
+    // A vector rep reorders its entries when marked read-only
+    let mt = Arc::new(MemTable::with_rep(
+        1024 * 1024,
+        VectorFactory.create(),
+        None,
+    ));
+    for i in (0..200).rev() {
+        mt.add(u64::from(i) + 1, ValueType::Put, key(i), b"v".to_vec());
+    }
+    let mut iter = mt.shared_iter_at(None, None, 201);
+    for _ in 0..10 {
+        iter.next().unwrap();
+    }
+    assert_eq!(iter.key(), key(10));
+
+    // Would deadlock if the iterator held the lock
+    mt.add(201, ValueType::Put, key(50), b"later".to_vec());
+    mt.add(202, ValueType::Put, b"key0100a".to_vec(), b"later".to_vec());
+    mt.mark_read_only();
+
+    let rest = forward(&mut iter);
+    assert_eq!(rest.len(), 190);
+    assert!(rest.iter().all(|(_, value, _)| value == b"v"));
+    assert_eq!(rest[0].0, key(10));
+}
+
+// =============================================================================
+// Test 4: A DB iterator stays open through writes and a flush
+// =============================================================================
+#[test]
+fn db_iterator_open_through_flush() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    for i in 0..200 {
+        db.put(&key(i), b"v1").unwrap();
+    }
+
+    let mut iter = db.iter().unwrap();
+    iter.next().unwrap();
+    for i in 0..200 {
+        db.put(&key(i), b"v2").unwrap();
+    }
+    db.flush().unwrap();
+    db.put(&key(500), b"v3").unwrap();
+
+    let rest = forward(&mut iter);
+    assert_eq!(rest.len(), 199);
+    assert!(rest.iter().all(|(_, value, _)| value == b"v1"));
+
+    // Refreshed, it reads the flushed table and the new memtable
+    iter.refresh().unwrap();
+    iter.seek_to_first().unwrap();
+    let all = forward(&mut iter);
+    assert_eq!(all.len(), 201);
+    assert!(all[..200].iter().all(|(_, value, _)| value == b"v2"));
+}
+
+// =============================================================================
+// Test 5: shared_versions_at() yields versions() below its sequence number
+// =============================================================================
+#[test]
+fn shared_versions_match_versions() {
+    let (mt, first) = memtable();
+    for sequence in [first, u64::MAX] {
+        let mut expected = Vec::new();
+        {
+            let guard = mt.read();
+            let mut versions = guard.versions();
+            while versions.is_valid() {
+                if versions.sequence() < sequence {
+                    expected.push((
+                        versions.key().to_vec(),
+                        versions.value().to_vec(),
+                        Some(versions.sequence()),
+                    ));
+                }
+                versions.next().unwrap();
+            }
+        }
+        assert!(expected.len() > SEVERAL_READ_AHEADS);
+
+        let mut shared = mt.shared_versions_at(sequence);
+        assert_eq!(forward(&mut shared), expected);
+        assert_eq!(backward(&mut shared), expected);
+
+        // A key's versions straddle read-aheads: back from a seek, then on
+        shared.seek(&key(150)).unwrap();
+        let at = expected.iter().position(|e| e.0 >= key(150)).unwrap();
+        for i in (0..=at).rev().take(70) {
+            assert_eq!(shared.key(), expected[i].0);
+            assert_eq!(shared.seqno(), expected[i].2);
+            shared.prev().unwrap();
+        }
+        shared.seek_for_prev(&key(150)).unwrap();
+        let at = expected.iter().rposition(|e| e.0 <= key(150)).unwrap();
+        for entry in expected[at..].iter().take(70) {
+            assert_eq!(shared.key(), entry.0);
+            assert_eq!(shared.seqno(), entry.2);
+            shared.next().unwrap();
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// Skip list insert hint tests
+//
+// SkipList::add_with_hint() starts its search from where the last insert
+// through the hint went, so ascending inserts skip the search from the
+// head. Whatever the order of the keys, the list must come out the same
+// as with plain inserts.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::memtable::skiplist::{InsertHint, SkipList};
+use lsm_engine::types::ValueType;
+
+type Versions = Vec<(Vec<u8>, u64)>;
+
+/// (key, sequence) of every node, front to back and back to front.
+fn versions(sl: &SkipList) -> (Versions, Versions) {
+    let mut forward = Vec::new();
+    let mut iter = sl.iter();
+    while iter.is_valid() {
+        forward.push((iter.key().to_vec(), iter.sequence()));
+        iter.advance();
+    }
+    let mut backward = Vec::new();
+    let mut iter = sl.iter();
+    iter.seek_to_last().unwrap();
+    while iter.is_valid() {
+        backward.push((iter.key().to_vec(), iter.sequence()));
+        iter.retreat();
+    }
+    backward.reverse();
+    (forward, backward)
+}
+
+// =============================================================================
+// Test 1: Ascending inserts through a hint
+// =============================================================================
+#[test]
+fn ascending_inserts() {
+    let mut hinted = SkipList::new();
+    let mut plain = SkipList::new();
+    let mut hint = InsertHint::default();
+    for i in 0..5000u32 {
+        let key = format!("key_{:06}", i).into_bytes();
+        let value = format!("val_{}", i).into_bytes();
+        hinted.insert_with_hint(&mut hint, key.clone(), value.clone());
+        plain.insert(key, value);
+    }
+
+    assert_eq!(hinted.len(), 5000);
+    for i in (0..5000u32).step_by(97) {
+        let key = format!("key_{:06}", i).into_bytes();
+        assert_eq!(hinted.get(&key), Some(format!("val_{}", i).as_bytes()));
+    }
+    let (forward, backward) = versions(&hinted);
+    assert_eq!(forward, backward);
+    assert_eq!(forward, versions(&plain).0);
+}
+
+// =============================================================================
+// Test 2: Keys behind the hint, versions and unhinted inserts in between
+// =============================================================================
+#[test]
+fn any_order_through_hint() {
+    let mut sl = SkipList::new();
+    let mut hint = InsertHint::default();
+    let mut expected = Vec::new();
+    for i in 0..2000u64 {
+        // Runs of ascending keys, each run starting further back
+        let key = format!("k{:04}", (i * 7919) % 1000).into_bytes();
+        sl.add_with_hint(&mut hint, key.clone(), i, ValueType::Put, b"v".to_vec());
+        expected.push((key, i));
+        if i % 10 == 0 {
+            let key = format!("k{:04}x", i % 1000).into_bytes();
+            sl.add(key.clone(), i, ValueType::Put, b"x".to_vec());
+            expected.push((key, i));
+        }
+    }
+    // The same version again overwrites in place
+    sl.add_with_hint(
+        &mut hint,
+        b"k0000".to_vec(),
+        0,
+        ValueType::Delete,
+        Vec::new(),
+    );
+
+    // Keys ascending, a key's versions newest first
+    expected.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    assert_eq!(sl.len(), expected.len());
+    let (forward, backward) = versions(&sl);
+    assert_eq!(forward, expected);
+    assert_eq!(backward, expected);
+    assert_eq!(sl.get_version(b"k0000", 1).unwrap().0, ValueType::Delete);
+}
@@ -174,3 +174,56 @@ fn iterator_seek_to_beginning() {
     assert!(iter.is_valid());
     assert_eq!(iter.key(), b"b");
 }
+
+// =============================================================================
+// Test 9: Walk backward from the end
+// =============================================================================
+// seek_to_last() jumps to the tail and retreat() follows back links: a
+// full reverse walk sees every entry, largest first, whatever the insert
+// order.
+#[test]
+fn iterator_walks_backward() {
+    let mut sl = SkipList::new();
+    let mut keys: Vec<Vec<u8>> = (0..500u32)
+        .map(|i| format!("key_{:04}", (i * 7919) % 500).into_bytes())
+        .collect();
+    for key in &keys {
+        sl.insert(key.clone(), b"v".to_vec());
+    }
+    keys.sort();
+
+    let mut iter = sl.iter();
+    iter.seek_to_last().unwrap();
+    let mut seen = Vec::new();
+    while iter.is_valid() {
+        seen.push(iter.key().to_vec());
+        iter.retreat();
+    }
+    seen.reverse();
+    assert_eq!(seen, keys);
+}
+
+// =============================================================================
+// Test 10: Back and forth around an insert
+// =============================================================================
+// Nodes spliced in later are linked both ways: stepping back from a key
+// lands on the one inserted right before it.
+#[test]
+fn iterator_back_links_follow_inserts() {
+    let mut sl = SkipList::new();
+    for key in [b"a", b"e", b"c", b"b", b"d"] {
+        sl.insert(key.to_vec(), key.to_vec());
+    }
+
+    let mut iter = sl.iter();
+    iter.seek_to(b"d");
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), b"c");
+    iter.prev().unwrap();
+    assert_eq!(iter.key(), b"b");
+    iter.advance();
+    assert_eq!(iter.key(), b"c");
+    iter.seek_to(b"a");
+    iter.prev().unwrap();
+    assert!(!iter.is_valid());
+}
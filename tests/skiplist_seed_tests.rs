@@ -0,0 +1,59 @@
+// Skip list seed tests
+//
+// A skip list created with_seed() draws its node heights from an RNG
+// seeded with it, so the same inserts build the same list every run.
+// SeededSkipListFactory does the same for a DB's memtables.
+
+use std::sync::Arc;
+
+use lsm_engine::memtable::rep::SeededSkipListFactory;
+use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+/// Tower links are counted in size_bytes(), so lists of the same entries
+/// only have the same size if their nodes got the same heights.
+fn size_after_inserts(mut sl: SkipList) -> usize {
+    for i in 0..2000u32 {
+        sl.insert(format!("key_{:05}", i).into_bytes(), b"v".to_vec());
+    }
+    sl.size_bytes()
+}
+
+// =============================================================================
+// Test 1: The same seed gives the same heights
+// =============================================================================
+#[test]
+fn same_seed_same_list() {
+    let size = size_after_inserts(SkipList::with_seed(42));
+    assert_eq!(size_after_inserts(SkipList::with_seed(42)), size);
+    assert_ne!(size_after_inserts(SkipList::with_seed(43)), size);
+
+    // Seeded or not, the list works the same
+    let mut sl = SkipList::with_seed(7);
+    sl.insert(b"b".to_vec(), b"2".to_vec());
+    sl.insert(b"a".to_vec(), b"1".to_vec());
+    assert_eq!(sl.get(b"a"), Some(&b"1"[..]));
+    assert_eq!(sl.len(), 2);
+}
+
+// =============================================================================
+// Test 2: Seeded memtables through Options
+// =============================================================================
+#[test]
+fn seeded_memtables_in_db() {
+    let memtable_size = || {
+        let dir = tempdir().unwrap();
+        let options = Options {
+            memtable_factory: Arc::new(SeededSkipListFactory(1234)),
+            ..Options::default()
+        };
+        let db = DB::open(dir.path(), options).unwrap();
+        for i in 0..2000u32 {
+            db.put(format!("key_{:05}", i).as_bytes(), b"v").unwrap();
+        }
+        assert_eq!(db.get(b"key_01000").unwrap(), Some(b"v".to_vec()));
+        db.stats().memtable_size
+    };
+    assert_eq!(memtable_size(), memtable_size());
+}
@@ -1,7 +1,9 @@
 // M03: Skip List Size Tracking tests
 // Tests for approximate memory usage tracking.
 
+use lsm_engine::memtable::MemTable;
 use lsm_engine::memtable::skiplist::SkipList;
+use lsm_engine::types::ValueType;
 
 // =============================================================================
 // Test 1: Empty skip list size
@@ -88,3 +90,25 @@ fn multiple_inserts_accumulate_size() {
     // Size should be at least the sum of all key+value bytes
     assert!(sl.size_bytes() >= total_data_size);
 }
+
+// =============================================================================
+// Test 6: Size counts per-node overhead
+// =============================================================================
+// Each new key adds its bytes and at least one forward pointer; an
+// overwrite of a plain key adds only the new value, and another version
+// of a key shares its key bytes.
+#[test]
+fn size_counts_node_overhead() {
+    let link = std::mem::size_of::<Option<usize>>();
+    let mt = MemTable::new(1024 * 1024);
+    mt.put(b"a".to_vec(), b"1".to_vec());
+    assert!(mt.size() >= 2 + link);
+
+    let before = mt.size();
+    mt.put(b"a".to_vec(), b"22".to_vec());
+    assert_eq!(mt.size(), before + 2);
+
+    let before = mt.size();
+    mt.add(5, ValueType::Put, b"a".to_vec(), b"333".to_vec());
+    assert!(mt.size() >= before + 3 + link);
+}
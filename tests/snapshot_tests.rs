@@ -133,3 +133,51 @@ fn snapshot_on_empty_db() {
     let scanner = snap.scan(b"a", b"z").unwrap();
     assert!(!scanner.is_valid());
 }
+
+#[test]
+fn snapshot_reads_memtables_as_of_its_sequence() {
+    let (_dir, db) = open_temp_db();
+
+    db.put(b"a", b"a1").unwrap();
+    db.put(b"b", b"b1").unwrap();
+    let snap = db.snapshot();
+
+    // Overwrites, a delete and a new key, all in the same memtable
+    db.put(b"a", b"a2").unwrap();
+    db.delete(b"b").unwrap();
+    db.put(b"c", b"c1").unwrap();
+
+    assert_eq!(snap.get(b"a").unwrap(), Some(b"a1".to_vec()));
+    assert_eq!(snap.get(b"b").unwrap(), Some(b"b1".to_vec()));
+    assert_eq!(snap.get(b"c").unwrap(), None);
+
+    let mut scanner = snap.scan(b"a", b"z").unwrap();
+    let mut seen = Vec::new();
+    while scanner.is_valid() {
+        seen.push((scanner.key().to_vec(), scanner.value().to_vec()));
+        scanner.next().unwrap();
+    }
+    assert_eq!(
+        seen,
+        vec![
+            (b"a".to_vec(), b"a1".to_vec()),
+            (b"b".to_vec(), b"b1".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn snapshot_keeps_reading_memtables_after_flush() {
+    let (_dir, db) = open_temp_db();
+
+    db.put(b"k", b"old").unwrap();
+    let snap = db.snapshot();
+    db.put(b"k", b"new").unwrap();
+
+    // The memtable holding both versions goes to an SSTable, which keeps
+    // only the newest; the snapshot still has the memtable
+    db.flush().unwrap();
+
+    assert_eq!(snap.get(b"k").unwrap(), Some(b"old".to_vec()));
+    assert_eq!(db.get(b"k").unwrap(), Some(b"new".to_vec()));
+}
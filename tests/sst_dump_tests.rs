@@ -0,0 +1,130 @@
+// sst_dump tests
+//
+// The dump prints every part of an SSTable (footer, meta, properties,
+// filter, index) and, on request, its entries, as text or JSON. Binary
+// keys and values are escaped so the output stays printable.
+
+use std::path::Path;
+use std::process::Command;
+
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::tools::sst_dump::{DumpFormat, DumpOptions, dump, dump_file};
+use tempfile::tempdir;
+
+fn build_table(path: &Path) {
+    let mut builder = SSTableBuilder::new(path, 7, 64).unwrap();
+    builder.add(b"apple", b"red").unwrap();
+    builder.add(b"bin\x00\xff", b"say \"hi\"\\").unwrap();
+    builder.add(b"cherry", b"").unwrap();
+    builder.finish().unwrap();
+}
+
+fn dump_to_string(path: &Path, options: &DumpOptions) -> String {
+    let mut out = Vec::new();
+    dump_file(path, options, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+// =============================================================================
+// Test 1: Text dump lists every section, entries only on request
+// =============================================================================
+#[test]
+fn text_dump() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000007.sst");
+    build_table(&path);
+
+    let text = dump_to_string(&path, &DumpOptions::default());
+    for header in ["[footer]", "[meta]", "[properties]", "[filter]", "[index] "] {
+        assert!(text.contains(header), "missing {header} in\n{text}");
+    }
+    assert!(text.contains("  format_version: 10\n"));
+    assert!(text.contains("  id: 7\n"));
+    assert!(text.contains("  num_deletions: 1\n"));
+    assert!(text.contains("  min_key: \"apple\"\n"));
+    assert!(!text.contains("[entries]"));
+
+    let options = DumpOptions {
+        entries: true,
+        ..DumpOptions::default()
+    };
+    let text = dump_to_string(&path, &options);
+    assert!(text.contains("[entries] 3 records\n"));
+    assert!(text.contains("  key=\"apple\" value=\"red\"\n"));
+    assert!(text.contains("  key=\"bin\\x00\\xff\" value=\"say \\x22hi\\x22\\x5c\"\n"));
+}
+
+// =============================================================================
+// Test 2: JSON dump escapes keys and values
+// =============================================================================
+#[test]
+fn json_dump() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000007.sst");
+    build_table(&path);
+
+    let options = DumpOptions {
+        format: DumpFormat::Json,
+        entries: true,
+    };
+    let json = dump_to_string(&path, &options);
+    assert!(json.starts_with("{\"footer\":{\"format_version\":10,"));
+    assert!(json.ends_with("}\n"));
+    assert!(json.contains("\"user_properties\":[]"));
+    assert!(json.contains("\"filter\":{\"partitioned\":false,"));
+    assert!(
+        json.contains("{\"key\":\"bin\\\\x00\\\\xff\",\"value\":\"say \\\\x22hi\\\\x22\\\\x5c\"}")
+    );
+    assert!(json.contains("{\"key\":\"cherry\",\"value\":\"\"}"));
+    assert_eq!(json.matches('{').count(), json.matches('}').count());
+}
+
+// =============================================================================
+// Test 3: The index section has one record per data block
+// =============================================================================
+#[test]
+fn index_records_match_blocks() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 128).unwrap();
+    for i in 0..200 {
+        builder
+            .add(format!("key_{i:04}").as_bytes(), b"value")
+            .unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let mut out = Vec::new();
+    dump(&sst, &DumpOptions::default(), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let header = format!("[index] {} records\n", sst.num_blocks());
+    assert!(text.contains(&header), "{text}");
+    assert_eq!(text.matches("  block=").count(), sst.num_blocks());
+}
+
+// =============================================================================
+// Test 4: The sst_dump binary
+// =============================================================================
+#[test]
+fn sst_dump_binary() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000007.sst");
+    build_table(&path);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_sst_dump"))
+        .arg("--entries")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("key=\"cherry\""));
+
+    let missing = Command::new(env!("CARGO_BIN_EXE_sst_dump"))
+        .arg(dir.path().join("missing.sst"))
+        .output()
+        .unwrap();
+    assert!(!missing.status.success());
+}
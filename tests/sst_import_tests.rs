@@ -0,0 +1,304 @@
+// LevelDB/RocksDB table import tests
+//
+// tools::sst_import converts foreign table files into this engine's
+// SSTable format. The source tables here are built by hand, byte for
+// byte in the LevelDB / RocksDB block-based layout.
+
+use lsm_engine::Error;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::TableOptions;
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::tools::sst_import::{SourceFormat, convert};
+use tempfile::tempdir;
+
+const LEVELDB_MAGIC: u64 = 0xdb4775248b80fb57;
+const ROCKSDB_MAGIC: u64 = 0x88e241b785f4cff7;
+
+const TYPE_DELETION: u8 = 0;
+const TYPE_VALUE: u8 = 1;
+const TYPE_MERGE: u8 = 2;
+
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0x82F63B78
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn masked_crc32c(data: &[u8]) -> u32 {
+    crc32c(data).rotate_right(15).wrapping_add(0xa282ead8)
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn handle(offset: u64, size: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    put_varint(&mut buf, offset);
+    put_varint(&mut buf, size);
+    buf
+}
+
+fn internal_key(user_key: &[u8], seq: u64, value_type: u8) -> Vec<u8> {
+    let mut key = user_key.to_vec();
+    key.extend_from_slice(&(seq << 8 | value_type as u64).to_le_bytes());
+    key
+}
+
+/// Prefix-compressed block with a restart point every `restart_interval`
+/// entries. With `delta_values`, entries carry no value length (RocksDB's
+/// delta-encoded index).
+fn block(entries: &[(Vec<u8>, Vec<u8>)], restart_interval: usize, delta_values: bool) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut restarts = Vec::new();
+    let mut last_key: &[u8] = &[];
+    for (i, (key, value)) in entries.iter().enumerate() {
+        let shared = if i % restart_interval == 0 {
+            restarts.push(buf.len() as u32);
+            0
+        } else {
+            key.iter().zip(last_key).take_while(|(a, b)| a == b).count()
+        };
+        put_varint(&mut buf, shared as u64);
+        put_varint(&mut buf, (key.len() - shared) as u64);
+        if !delta_values {
+            put_varint(&mut buf, value.len() as u64);
+        }
+        buf.extend_from_slice(&key[shared..]);
+        buf.extend_from_slice(value);
+        last_key = key;
+    }
+    for restart in &restarts {
+        buf.extend_from_slice(&restart.to_le_bytes());
+    }
+    buf.extend_from_slice(&(restarts.len() as u32).to_le_bytes());
+    buf
+}
+
+/// Append `contents` with its [type][masked crc32c] trailer; returns the
+/// block's (offset, size).
+fn append_block(file: &mut Vec<u8>, contents: &[u8], compression: u8) -> (u64, u64) {
+    let offset = file.len() as u64;
+    file.extend_from_slice(contents);
+    file.push(compression);
+    let crc = masked_crc32c(&file[offset as usize..]);
+    file.extend_from_slice(&crc.to_le_bytes());
+    (offset, contents.len() as u64)
+}
+
+/// Two data blocks holding a, b (an older version, then a newer one), c
+/// (deleted), d, e; index keys are each block's last internal key.
+fn leveldb_table() -> Vec<u8> {
+    let blocks = [
+        vec![
+            (internal_key(b"apple", 9, TYPE_VALUE), b"red".to_vec()),
+            (internal_key(b"banana", 12, TYPE_VALUE), b"ripe".to_vec()),
+            (internal_key(b"banana", 4, TYPE_VALUE), b"green".to_vec()),
+        ],
+        vec![
+            (internal_key(b"cherry", 15, TYPE_DELETION), Vec::new()),
+            (internal_key(b"cherry", 3, TYPE_VALUE), b"dark".to_vec()),
+            (internal_key(b"date", 7, TYPE_VALUE), b"sweet".to_vec()),
+            (internal_key(b"durian", 8, TYPE_VALUE), b"smelly".to_vec()),
+        ],
+    ];
+    let mut file = Vec::new();
+    let mut index = Vec::new();
+    for entries in &blocks {
+        let (offset, size) = append_block(&mut file, &block(entries, 2, false), 0);
+        index.push((entries.last().unwrap().0.clone(), handle(offset, size)));
+    }
+    let (meta_offset, meta_size) = append_block(&mut file, &block(&[], 1, false), 0);
+    let (index_offset, index_size) = append_block(&mut file, &block(&index, 1, false), 0);
+
+    let mut footer = handle(meta_offset, meta_size);
+    footer.extend_from_slice(&handle(index_offset, index_size));
+    footer.resize(40, 0);
+    footer.extend_from_slice(&LEVELDB_MAGIC.to_le_bytes());
+    file.extend_from_slice(&footer);
+    file
+}
+
+/// RocksDB format_version 5 table: three snappy-compressed data blocks,
+/// a delta-encoded index and a properties block with `comparator`.
+fn rocksdb_table(comparator: &[u8], extra_entry: Option<(Vec<u8>, Vec<u8>)>) -> Vec<u8> {
+    let mut file = Vec::new();
+    let mut index = Vec::new();
+    let mut previous_size = None;
+    for b in 0..3u32 {
+        let mut entries: Vec<_> = (0..5u32)
+            .map(|i| {
+                let key = format!("key_{:02}", b * 5 + i).into_bytes();
+                (
+                    internal_key(&key, 100, TYPE_VALUE),
+                    format!("value_{}", b * 5 + i).into_bytes(),
+                )
+            })
+            .collect();
+        if b == 2
+            && let Some(extra) = extra_entry.clone()
+        {
+            entries.push(extra);
+        }
+        let contents = snap::raw::Encoder::new()
+            .compress_vec(&block(&entries, 16, false))
+            .unwrap();
+        let (offset, size) = append_block(&mut file, &contents, 1);
+        // First index entry is a restart point (full handle); the rest hold
+        // the zigzag-encoded size change
+        let value = match previous_size {
+            None => handle(offset, size),
+            Some(previous) => {
+                let delta = size as i64 - previous as i64;
+                let mut buf = Vec::new();
+                put_varint(&mut buf, ((delta << 1) ^ (delta >> 63)) as u64);
+                buf
+            }
+        };
+        previous_size = Some(size);
+        index.push((entries.last().unwrap().0.clone(), value));
+    }
+
+    let mut delta_encoded = Vec::new();
+    put_varint(&mut delta_encoded, 1);
+    let properties = vec![
+        (
+            b"rocksdb.block.based.table.index.type".to_vec(),
+            0u32.to_le_bytes().to_vec(),
+        ),
+        (b"rocksdb.comparator".to_vec(), comparator.to_vec()),
+        (
+            b"rocksdb.index.value.is.delta.encoded".to_vec(),
+            delta_encoded,
+        ),
+    ];
+    let (props_offset, props_size) = append_block(&mut file, &block(&properties, 1, false), 0);
+    let metaindex = vec![(
+        b"rocksdb.properties".to_vec(),
+        handle(props_offset, props_size),
+    )];
+    let (meta_offset, meta_size) = append_block(&mut file, &block(&metaindex, 1, false), 0);
+    let (index_offset, index_size) = append_block(&mut file, &block(&index, 16, true), 0);
+
+    let mut footer = vec![1u8]; // CRC32C
+    footer.extend_from_slice(&handle(meta_offset, meta_size));
+    footer.extend_from_slice(&handle(index_offset, index_size));
+    footer.resize(41, 0);
+    footer.extend_from_slice(&5u32.to_le_bytes());
+    footer.extend_from_slice(&ROCKSDB_MAGIC.to_le_bytes());
+    file.extend_from_slice(&footer);
+    file
+}
+
+fn entries(sst: &SSTable) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut iter = sst.iter().unwrap();
+    let mut out = Vec::new();
+    while iter.is_valid() {
+        out.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    out
+}
+
+// =============================================================================
+// Test 1: LevelDB tables keep the newest version of each key
+// =============================================================================
+#[test]
+fn import_leveldb_table() {
+    assert_eq!(crc32c(b"123456789"), 0xE3069283);
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("000042.sst");
+
+    let summary = convert(&leveldb_table(), &dest, 42, &TableOptions::default()).unwrap();
+    assert_eq!(summary.format, SourceFormat::LevelDb);
+    assert_eq!(summary.entries_read, 7);
+    assert_eq!(summary.shadowed, 2);
+    assert_eq!(summary.meta.id, 42);
+    assert_eq!(summary.meta.entry_count, 5);
+
+    let sst = SSTable::open(&dest).unwrap();
+    let expected: Vec<(Vec<u8>, Vec<u8>)> = [
+        ("apple", "red"),
+        ("banana", "ripe"),
+        ("cherry", ""),
+        ("date", "sweet"),
+        ("durian", "smelly"),
+    ]
+    .iter()
+    .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+    .collect();
+    assert_eq!(entries(&sst), expected);
+    assert_eq!(sst.get(b"banana").unwrap().unwrap(), &b"ripe"[..]);
+}
+
+// =============================================================================
+// Test 2: RocksDB tables with compressed blocks and a delta-encoded index
+// =============================================================================
+#[test]
+fn import_rocksdb_table() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("000001.sst");
+    let source = rocksdb_table(b"leveldb.BytewiseComparator", None);
+
+    let summary = convert(&source, &dest, 1, &TableOptions::default()).unwrap();
+    assert_eq!(summary.format, SourceFormat::RocksDb { format_version: 5 });
+    assert_eq!(summary.entries_read, 15);
+    assert_eq!(summary.shadowed, 0);
+
+    let sst = SSTable::open(&dest).unwrap();
+    let imported = entries(&sst);
+    assert_eq!(imported.len(), 15);
+    for (i, (key, value)) in imported.iter().enumerate() {
+        assert_eq!(key, format!("key_{:02}", i).as_bytes());
+        assert_eq!(value, format!("value_{i}").as_bytes());
+    }
+}
+
+// =============================================================================
+// Test 3: Unsupported and corrupt sources are rejected, leaving no output
+// =============================================================================
+#[test]
+fn import_rejects_unsupported_and_corrupt_tables() {
+    let dir = tempdir().unwrap();
+    let dest = dir.path().join("000001.sst");
+    let options = TableOptions::default();
+    let is_unsupported =
+        |e: &Error| matches!(e, Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput);
+
+    let reverse = rocksdb_table(b"rocksdb.ReverseBytewiseComparator", None);
+    assert!(is_unsupported(
+        &convert(&reverse, &dest, 1, &options).unwrap_err()
+    ));
+
+    let merge = (internal_key(b"key_99", 100, TYPE_MERGE), b"+1".to_vec());
+    let with_merge = rocksdb_table(b"leveldb.BytewiseComparator", Some(merge));
+    assert!(is_unsupported(
+        &convert(&with_merge, &dest, 1, &options).unwrap_err()
+    ));
+    assert!(!dest.exists());
+
+    let mut corrupt = leveldb_table();
+    corrupt[3] ^= 0xFF;
+    assert!(matches!(
+        convert(&corrupt, &dest, 1, &options),
+        Err(Error::Corruption(_))
+    ));
+    assert!(matches!(
+        convert(b"definitely not a table", &dest, 1, &options),
+        Err(Error::Corruption(_))
+    ));
+    assert!(!dest.exists());
+}
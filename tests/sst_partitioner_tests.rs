@@ -0,0 +1,123 @@
+// SST partitioner tests
+//
+// Options::sst_partitioner picks keys flush and compaction cut their
+// output at, below the target file size, so tables line up with the
+// application's shards. FixedPrefixPartitioner cuts wherever the first
+// bytes of the key change.
+
+use std::sync::{Arc, Mutex};
+
+use lsm_engine::compaction::partitioner::{FixedPrefixPartitioner, SstPartitioner};
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn options(sst_partitioner: Option<Arc<dyn SstPartitioner>>) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        sst_partitioner,
+        ..Options::default()
+    }
+}
+
+/// Write 30 keys for each of `tenants`, spread over three flushes.
+fn write_tenants(db: &DB, tenants: &[&str]) {
+    for round in 0..3 {
+        for tenant in tenants {
+            for i in round * 10..(round + 1) * 10 {
+                db.put(format!("{tenant}/{i:03}").as_bytes(), b"value")
+                    .unwrap();
+            }
+        }
+        db.flush().unwrap();
+    }
+}
+
+/// Cuts before the first key starting with `at`, recording the levels
+/// asked.
+struct CutBefore {
+    at: u8,
+    levels: Mutex<Vec<u32>>,
+}
+
+impl SstPartitioner for CutBefore {
+    fn should_partition(&self, level: u32, prev_key: &[u8], key: &[u8]) -> bool {
+        self.levels.lock().unwrap().push(level);
+        prev_key[0] != self.at && key[0] == self.at
+    }
+}
+
+// =============================================================================
+// Test 1: Compaction output is cut between prefixes
+// =============================================================================
+#[test]
+fn compaction_cuts_at_prefixes() {
+    let dir = tempdir().unwrap();
+    let partitioner = Arc::new(FixedPrefixPartitioner::new(2));
+    let db = DB::open(dir.path(), options(Some(partitioner))).unwrap();
+    write_tenants(&db, &["t1", "t2", "t3"]);
+    db.compact_range(None, None).unwrap();
+
+    let mut files = db.live_files_metadata();
+    files.sort_by(|a, b| a.min_key.cmp(&b.min_key));
+    assert_eq!(files.len(), 3);
+    for (file, tenant) in files.iter().zip(["t1", "t2", "t3"]) {
+        assert_eq!(file.level, 1);
+        assert!(file.min_key.starts_with(tenant.as_bytes()), "{file:?}");
+        assert!(file.max_key.starts_with(tenant.as_bytes()), "{file:?}");
+        assert_eq!(file.entry_count, 30);
+    }
+    assert_eq!(db.get(b"t2/015").unwrap(), Some(b"value".to_vec()));
+}
+
+// =============================================================================
+// Test 2: Without a partitioner the output stays in one table
+// =============================================================================
+#[test]
+fn no_partitioner_one_table() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(None)).unwrap();
+    write_tenants(&db, &["t1", "t2", "t3"]);
+    db.compact_range(None, None).unwrap();
+
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].entry_count, 90);
+}
+
+// =============================================================================
+// Test 3: Custom partitioner sees the output level; flushes are cut too
+// =============================================================================
+#[test]
+fn custom_partitioner_levels() {
+    let dir = tempdir().unwrap();
+    let partitioner = Arc::new(CutBefore {
+        at: b'b',
+        levels: Mutex::new(Vec::new()),
+    });
+    let db = DB::open(dir.path(), options(Some(partitioner.clone()))).unwrap();
+    write_tenants(&db, &["a", "b"]);
+
+    // Each flush is cut before the first "b" key
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 6);
+    assert!(partitioner.levels.lock().unwrap().iter().all(|&l| l == 0));
+
+    partitioner.levels.lock().unwrap().clear();
+    db.compact_range(None, None).unwrap();
+    let files = db.live_files_metadata();
+    assert_eq!(files.len(), 2);
+    assert!(partitioner.levels.lock().unwrap().iter().all(|&l| l == 1));
+}
+
+// =============================================================================
+// Test 4: FixedPrefixPartitioner compares at most `len` bytes
+// =============================================================================
+#[test]
+fn fixed_prefix_boundaries() {
+    let partitioner = FixedPrefixPartitioner::new(3);
+    assert!(!partitioner.should_partition(0, b"abc1", b"abc2"));
+    assert!(partitioner.should_partition(0, b"abc9", b"abd0"));
+    assert!(partitioner.should_partition(0, b"ab", b"abc"));
+    assert!(!partitioner.should_partition(0, b"ab", b"ab"));
+}
@@ -1,6 +1,7 @@
 // M14: SSTable Reader tests
 // Tests for opening SSTables and point lookups.
 
+use bytes::Bytes;
 use lsm_engine::sstable::builder::SSTableBuilder;
 use lsm_engine::sstable::reader::SSTable;
 use std::fs;
@@ -31,7 +32,7 @@ fn read_1000_entries_back() {
         let result = sstable.get(key.as_bytes()).unwrap();
         assert_eq!(
             result,
-            Some(expected_val.into_bytes()),
+            Some(Bytes::from(expected_val)),
             "Failed for key {}",
             key
         );
@@ -192,15 +193,15 @@ fn multiple_blocks_index_search() {
     // Spot check: first, middle, last entries
     assert_eq!(
         sstable.get(b"key_00000").unwrap(),
-        Some(b"value_00000".to_vec())
+        Some(Bytes::from_static(b"value_00000"))
     );
     assert_eq!(
         sstable.get(b"key_00050").unwrap(),
-        Some(b"value_00050".to_vec())
+        Some(Bytes::from_static(b"value_00050"))
     );
     assert_eq!(
         sstable.get(b"key_00099").unwrap(),
-        Some(b"value_00099".to_vec())
+        Some(Bytes::from_static(b"value_00099"))
     );
 }
 
@@ -217,5 +218,8 @@ fn empty_value_roundtrip() {
     builder.finish().unwrap();
 
     let sstable = SSTable::open(&path).unwrap();
-    assert_eq!(sstable.get(b"key_with_empty_value").unwrap(), Some(vec![]));
+    assert_eq!(
+        sstable.get(b"key_with_empty_value").unwrap(),
+        Some(Bytes::new())
+    );
 }
@@ -1,8 +1,11 @@
 // M34: Stats / Observability Tests
 //
-// Verifies that DB::stats() returns meaningful metrics after various operations.
+// Verifies that DB::stats() returns meaningful metrics after various operations,
+// overall and per level.
 
-use lsm_engine::{DB, Options};
+use std::time::Duration;
+
+use lsm_engine::{CompactionStyle, DB, Options};
 use tempfile::tempdir;
 
 fn open_test_db() -> (tempfile::TempDir, DB) {
@@ -150,3 +153,92 @@ fn stats_after_compaction_count_positive() {
         "compaction_bytes should be > 0 after compact_range"
     );
 }
+
+// =============================================================================
+// Test 8: Per-level files, bytes and flush/compaction I/O
+// =============================================================================
+#[test]
+fn stats_per_level_io() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    assert_eq!(db.stats().levels.len(), 7);
+
+    for round in 0..2u32 {
+        for i in 0..100u32 {
+            let key = format!("key_{:05}", i).into_bytes();
+            db.put(&key, format!("value_{round}").as_bytes()).unwrap();
+        }
+        db.flush().unwrap();
+    }
+
+    let stats = db.stats();
+    let l0 = &stats.levels[0];
+    let l0_size: u64 = db.live_files_metadata().iter().map(|f| f.file_size).sum();
+    assert_eq!(l0.num_files, 2);
+    assert_eq!(l0.size_bytes, l0_size);
+    assert_eq!(l0.bytes_written, l0_size);
+    assert!(l0.bytes_read_upper > 0);
+    assert!(l0.write_amplification > 0.0);
+    assert_eq!(l0.compaction_count, 0);
+    // Two of the four files that trigger a compaction
+    assert_eq!(l0.score, 0.5);
+
+    db.compact_range(None, None).unwrap();
+    let stats = db.stats();
+    assert_eq!(stats.levels[0].num_files, 0);
+    let l1 = &stats.levels[1];
+    assert_eq!(l1.num_files, 1);
+    assert_eq!(l1.compaction_count, 1);
+    assert!(l1.compaction_time > Duration::ZERO);
+    assert_eq!(l1.bytes_read_upper, l0_size);
+    assert_eq!(l1.bytes_read_level, 0);
+    assert_eq!(l1.bytes_written, l1.size_bytes);
+    assert_eq!(l1.read_amplification, 1.0);
+    // The overwritten half of the keys is gone
+    assert!(l1.write_amplification < 1.0);
+}
+
+// =============================================================================
+// Test 9: Leveled scores compare each level to its target
+// =============================================================================
+#[test]
+fn stats_leveled_scores() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        compaction_style: CompactionStyle::Leveled,
+        disable_auto_compactions: true,
+        max_bytes_for_level_base: 1024,
+        level0_file_num_compaction_trigger: 2,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for round in 0..2u32 {
+        for i in round * 250..(round + 1) * 250 {
+            let key = format!("key_{:05}", i).into_bytes();
+            db.put(&key, b"value_data").unwrap();
+        }
+        db.flush().unwrap();
+        assert_eq!(db.stats().levels[0].score, (round + 1) as f64 / 2.0);
+    }
+
+    // Compacting pushes the data down past L1's 1KB target until every
+    // level is within its own
+    db.compact_range(None, None).unwrap();
+    let stats = db.stats();
+    assert_eq!(stats.levels[0].num_files, 0);
+    assert!(stats.levels.iter().any(|level| level.score > 0.1));
+    for (level, stats) in stats.levels.iter().enumerate().skip(1) {
+        let expected = if level == 6 {
+            0.0
+        } else {
+            stats.size_bytes as f64 / (1024.0 * 10f64.powi(level as i32 - 1))
+        };
+        assert_eq!(stats.score, expected, "L{level}");
+        assert!(stats.score <= 1.0, "L{level}");
+    }
+}
@@ -0,0 +1,113 @@
+// Subcompaction tests
+//
+// With Options::max_subcompactions above 1, a compaction splits its key
+// range at input file boundaries and merges the subranges on parallel
+// threads, each into SSTables of its own. The outputs cover disjoint key
+// ranges, are installed together, and hold exactly what a single-threaded
+// compaction would.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn options(max_subcompactions: usize) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        max_subcompactions,
+        ..Options::default()
+    }
+}
+
+/// Four flushes over overlapping slices of the key space, with
+/// overwrites and deletes, then a full compaction.
+fn load(db: &DB) {
+    for batch in 0..4u32 {
+        for i in batch * 400..batch * 400 + 600 {
+            db.put(&key(i), format!("v{batch}_{i}").as_bytes()).unwrap();
+        }
+        for i in (batch * 400..batch * 400 + 100).step_by(7) {
+            db.delete(&key(i)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    db.compact_range(None, None).unwrap();
+}
+
+fn scan(db: &DB) -> Vec<(Vec<u8>, Vec<u8>)> {
+    let mut iter = db.iter().unwrap();
+    let mut entries = Vec::new();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    entries
+}
+
+fn l1_files(db: &DB) -> Vec<SSTableMeta> {
+    let files = db.live_files_metadata();
+    assert!(files.iter().all(|meta| meta.level == 1), "{files:?}");
+    files
+}
+
+// =============================================================================
+// Test 1: Parallel subranges produce the same data as one thread
+// =============================================================================
+#[test]
+fn subcompactions_match_single_threaded_result() {
+    let serial_dir = tempdir().unwrap();
+    let serial = DB::open(serial_dir.path(), options(1)).unwrap();
+    load(&serial);
+    assert_eq!(l1_files(&serial).len(), 1);
+
+    let parallel_dir = tempdir().unwrap();
+    let parallel = DB::open(parallel_dir.path(), options(4)).unwrap();
+    load(&parallel);
+
+    assert_eq!(scan(&parallel), scan(&serial));
+    let entries: u64 = l1_files(&parallel).iter().map(|f| f.entry_count).sum();
+    assert_eq!(entries, l1_files(&serial)[0].entry_count);
+}
+
+// =============================================================================
+// Test 2: Each subrange writes its own files, over disjoint key ranges
+// =============================================================================
+#[test]
+fn subcompaction_outputs_are_disjoint() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(4)).unwrap();
+    load(&db);
+
+    let mut files = l1_files(&db);
+    assert!(files.len() > 1, "expected several outputs, got {files:?}");
+    files.sort_by(|a, b| a.min_key.cmp(&b.min_key));
+    for pair in files.windows(2) {
+        assert!(pair[0].max_key < pair[1].min_key, "{pair:?}");
+    }
+}
+
+// =============================================================================
+// Test 3: All outputs are recorded together and survive reopen
+// =============================================================================
+#[test]
+fn subcompaction_outputs_survive_reopen() {
+    let dir = tempdir().unwrap();
+    let (expected, ids) = {
+        let db = DB::open(dir.path(), options(3)).unwrap();
+        load(&db);
+        let mut ids: Vec<u64> = l1_files(&db).iter().map(|f| f.id).collect();
+        ids.sort();
+        (scan(&db), ids)
+    };
+
+    let db = DB::open(dir.path(), options(3)).unwrap();
+    let mut reopened: Vec<u64> = l1_files(&db).iter().map(|f| f.id).collect();
+    reopened.sort();
+    assert_eq!(reopened, ids);
+    assert_eq!(scan(&db), expected);
+}
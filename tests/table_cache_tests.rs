@@ -0,0 +1,160 @@
+// Table cache tests
+//
+// SSTables are opened on first read and kept open in an LRU bounded by
+// Options::max_open_files, instead of being reopened (footer, index and
+// filter re-read) on every get and iterator.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use lsm_engine::cache::BlockCache;
+use lsm_engine::cache::table_cache::TableCache;
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::paths::TablePaths;
+use lsm_engine::sstable::reader::FileAccess;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// Tables 1..=count, each holding its own id as a key.
+fn build_tables(dir: &Path, count: u64) {
+    for id in 1..=count {
+        let path = dir.join(format!("{:06}.sst", id));
+        let mut builder = SSTableBuilder::new(&path, id, 1).unwrap();
+        builder.add(&key(id as u32), b"value").unwrap();
+        builder.finish().unwrap();
+    }
+}
+
+fn table_cache(dir: &Path, max_open_files: usize) -> TableCache {
+    let block_cache = Arc::new(Mutex::new(BlockCache::new(1024 * 1024)));
+    TableCache::new(
+        TablePaths::single(dir),
+        max_open_files,
+        block_cache,
+        FileAccess::Buffered,
+    )
+}
+
+// =============================================================================
+// Test 1: Tables are opened once and the least recently used is evicted
+// =============================================================================
+#[test]
+fn lru_of_open_tables() {
+    let dir = tempdir().unwrap();
+    build_tables(dir.path(), 3);
+    let cache = table_cache(dir.path(), 2);
+
+    let first = cache.get(1, 0).unwrap();
+    assert!(Arc::ptr_eq(&first, &cache.get(1, 0).unwrap()));
+    cache.get(2, 0).unwrap();
+    cache.get(1, 0).unwrap(); // 2 is now least recently used
+    cache.get(3, 0).unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // 1 stayed open; 2 was closed and is reopened on demand
+    assert!(Arc::ptr_eq(&first, &cache.get(1, 0).unwrap()));
+    let reopened = cache.get(2, 0).unwrap();
+    assert_eq!(reopened.get(&key(2)).unwrap().unwrap(), b"value".as_slice());
+
+    cache.evict(2);
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get(99, 0).is_err());
+}
+
+// =============================================================================
+// Test 2: Tables are shared across reader threads
+// =============================================================================
+#[test]
+fn concurrent_readers() {
+    let dir = tempdir().unwrap();
+    build_tables(dir.path(), 8);
+    let cache = Arc::new(table_cache(dir.path(), 4));
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let cache = Arc::clone(&cache);
+            thread::spawn(move || {
+                for round in 0..200u64 {
+                    let id = (t + round) % 8 + 1;
+                    let sst = cache.get(id, 0).unwrap();
+                    assert!(sst.get(&key(id as u32)).unwrap().is_some());
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert!(cache.len() <= 4);
+}
+
+// =============================================================================
+// Test 3: DB reads stay within max_open_files
+// =============================================================================
+#[test]
+fn db_respects_max_open_files() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        max_open_files: 3,
+        disable_auto_compactions: true,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..10 {
+        db.put(&key(i), format!("v{i}").as_bytes()).unwrap();
+        db.flush().unwrap();
+    }
+
+    for _ in 0..2 {
+        for i in 0..10 {
+            assert_eq!(
+                db.get(&key(i)).unwrap().unwrap(),
+                format!("v{i}").into_bytes()
+            );
+        }
+    }
+    let stats = db.stats();
+    assert_eq!(stats.num_sstables_per_level[0], 10);
+    assert_eq!(stats.open_tables, 3);
+
+    let mut iter = db.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 10);
+    assert_eq!(db.stats().open_tables, 3);
+}
+
+// =============================================================================
+// Test 4: Compaction closes the tables it replaced
+// =============================================================================
+#[test]
+fn compaction_evicts_inputs() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..4 {
+        db.put(&key(i), b"value").unwrap();
+        db.flush().unwrap();
+    }
+    for i in 0..4 {
+        assert!(db.get(&key(i)).unwrap().is_some());
+    }
+    assert_eq!(db.stats().open_tables, 4);
+
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.stats().open_tables, 0);
+    assert!(db.get(&key(2)).unwrap().is_some());
+    assert_eq!(db.stats().open_tables, 1);
+}
@@ -0,0 +1,163 @@
+// Table properties tests
+//
+// Every SSTable carries a properties block: built-in stats gathered while
+// it's built, plus whatever pluggable TablePropertiesCollectors return.
+
+use std::sync::Arc;
+
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::properties::{
+    TableProperties, TablePropertiesCollector, TablePropertiesCollectorFactory,
+};
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{DB, Error, Options};
+use tempfile::tempdir;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+/// Counts keys starting with "user:" and records the largest value.
+#[derive(Default)]
+struct UserKeyCollector {
+    user_keys: u64,
+    max_value_len: usize,
+}
+
+impl TablePropertiesCollector for UserKeyCollector {
+    fn add(&mut self, key: &[u8], value: &[u8]) {
+        if key.starts_with(b"user:") {
+            self.user_keys += 1;
+        }
+        self.max_value_len = self.max_value_len.max(value.len());
+    }
+
+    fn finish(&mut self) -> Vec<(String, Vec<u8>)> {
+        vec![
+            ("user_keys".into(), self.user_keys.to_le_bytes().to_vec()),
+            (
+                "max_value_len".into(),
+                (self.max_value_len as u64).to_le_bytes().to_vec(),
+            ),
+        ]
+    }
+}
+
+struct UserKeyCollectorFactory;
+
+impl TablePropertiesCollectorFactory for UserKeyCollectorFactory {
+    fn create(&self) -> Box<dyn TablePropertiesCollector> {
+        Box::new(UserKeyCollector::default())
+    }
+}
+
+fn property_u64(sst: &SSTable, name: &str) -> u64 {
+    let value = &sst.properties().unwrap().user_collected_properties[name];
+    u64::from_le_bytes(value.as_slice().try_into().unwrap())
+}
+
+// =============================================================================
+// Test 1: Built-in stats count entries, tombstones, raw sizes and blocks
+// =============================================================================
+#[test]
+fn builtin_properties() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 256).unwrap();
+    for i in 0..500 {
+        // Every fifth entry is a tombstone
+        let value: &[u8] = if i % 5 == 0 { b"" } else { b"value" };
+        builder.add(&key(i), value).unwrap();
+    }
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    let props = sst.properties().unwrap();
+    assert_eq!(props.num_entries, 500);
+    assert_eq!(props.num_deletions, 100);
+    assert_eq!(props.raw_key_size, 500 * 9);
+    assert_eq!(props.raw_value_size, 400 * 5);
+    assert_eq!(props.num_data_blocks, sst.num_blocks() as u64);
+    assert!(props.data_size > 0);
+    assert!(props.index_size > 0);
+    assert!(props.filter_size > 0);
+    assert!(props.data_size + props.index_size + props.filter_size < sst.meta().file_size);
+    assert!(props.user_collected_properties.is_empty());
+}
+
+// =============================================================================
+// Test 2: Custom collectors see every entry and their output is stored
+// =============================================================================
+#[test]
+fn custom_collector() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 4096)
+        .unwrap()
+        .with_properties_collector(Box::new(UserKeyCollector::default()));
+    builder.add(b"order:1", b"x").unwrap();
+    builder.add(b"user:1", b"alice").unwrap();
+    builder.add(b"user:2", b"bob-with-a-long-value").unwrap();
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    assert_eq!(property_u64(&sst, "user_keys"), 2);
+    assert_eq!(property_u64(&sst, "max_value_len"), 21);
+}
+
+// =============================================================================
+// Test 3: Options::table_properties_collectors applies to flushed tables
+// =============================================================================
+#[test]
+fn db_collectors_option() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        table_properties_collectors: vec![Arc::new(UserKeyCollectorFactory)],
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..10 {
+        db.put(format!("user:{i}").as_bytes(), b"v").unwrap();
+        db.put(format!("other:{i}").as_bytes(), b"v").unwrap();
+    }
+    db.delete(b"other:3").unwrap();
+    db.flush().unwrap();
+    drop(db);
+
+    let sst_path = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "sst"))
+        .unwrap();
+    let sst = SSTable::open(&sst_path).unwrap();
+    assert_eq!(property_u64(&sst, "user_keys"), 10);
+    assert_eq!(sst.properties().unwrap().num_deletions, 1);
+}
+
+// =============================================================================
+// Test 4: Property encoding round-trips, and truncation is detected
+// =============================================================================
+#[test]
+fn properties_encoding_round_trip() {
+    let mut props = TableProperties {
+        num_entries: 10,
+        num_deletions: 2,
+        raw_key_size: 30,
+        raw_value_size: 70,
+        num_data_blocks: 1,
+        data_size: 120,
+        index_size: 20,
+        filter_size: 16,
+        ..TableProperties::default()
+    };
+    props
+        .user_collected_properties
+        .insert("name".into(), b"value".to_vec());
+
+    let encoded = props.encode();
+    assert_eq!(TableProperties::decode(&encoded).unwrap(), props);
+    assert!(matches!(
+        TableProperties::decode(&encoded[..encoded.len() - 1]),
+        Err(Error::Corruption(_))
+    ));
+}
@@ -0,0 +1,117 @@
+// Tailing iterator tests
+//
+// DB::tailing_iter() keeps observing writes: next() past the end re-checks
+// the DB instead of staying invalid.
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::{DB, Options, ReadOptions};
+use tempfile::tempdir;
+
+fn drain(iter: &mut dyn StorageIterator) -> Vec<Vec<u8>> {
+    let mut keys = Vec::new();
+    while iter.is_valid() {
+        keys.push(iter.key().to_vec());
+        iter.next().unwrap();
+    }
+    keys
+}
+
+// =============================================================================
+// Test 1: Keys written after reaching the end are picked up by next()
+// =============================================================================
+#[test]
+fn tailing_sees_new_writes() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"e1", b"v").unwrap();
+
+    let mut iter = db.tailing_iter(&ReadOptions::default()).unwrap();
+    assert_eq!(drain(&mut iter), vec![b"e1".to_vec()]);
+
+    // Nothing new yet — polling stays invalid
+    iter.next().unwrap();
+    assert!(!iter.is_valid());
+
+    db.put(b"e2", b"v").unwrap();
+    db.put(b"e3", b"v").unwrap();
+    iter.next().unwrap();
+    assert_eq!(drain(&mut iter), vec![b"e2".to_vec(), b"e3".to_vec()]);
+}
+
+// =============================================================================
+// Test 2: Starting from an empty DB
+// =============================================================================
+#[test]
+fn tailing_from_empty_db() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+
+    let mut iter = db.tailing_iter(&ReadOptions::default()).unwrap();
+    assert!(!iter.is_valid());
+
+    db.put(b"a", b"1").unwrap();
+    iter.next().unwrap();
+    assert_eq!(iter.key(), b"a");
+    assert_eq!(iter.value(), b"1");
+}
+
+// =============================================================================
+// Test 3: Keys behind the cursor are not replayed
+// =============================================================================
+#[test]
+fn tailing_only_moves_forward() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"m", b"v").unwrap();
+
+    let mut iter = db.tailing_iter(&ReadOptions::default()).unwrap();
+    assert_eq!(drain(&mut iter), vec![b"m".to_vec()]);
+
+    db.put(b"a", b"v").unwrap(); // behind the cursor
+    db.put(b"z", b"v").unwrap();
+    iter.next().unwrap();
+    assert_eq!(drain(&mut iter), vec![b"z".to_vec()]);
+}
+
+// =============================================================================
+// Test 4: New data that was flushed to an SSTable is still observed
+// =============================================================================
+#[test]
+fn tailing_sees_flushed_writes() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"k1", b"v").unwrap();
+
+    let mut iter = db.tailing_iter(&ReadOptions::default()).unwrap();
+    assert_eq!(drain(&mut iter), vec![b"k1".to_vec()]);
+
+    db.put(b"k2", b"v").unwrap();
+    db.flush().unwrap();
+    iter.next().unwrap();
+    assert_eq!(iter.key(), b"k2");
+}
+
+// =============================================================================
+// Test 5: Seek past the end waits for keys at or after the target
+// =============================================================================
+#[test]
+fn tailing_seek_past_end() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    db.put(b"a", b"v").unwrap();
+
+    let mut iter = db
+        .tailing_iter(&ReadOptions {
+            iterate_upper_bound: Some(b"q".to_vec()),
+            ..ReadOptions::default()
+        })
+        .unwrap();
+    iter.seek(b"m").unwrap();
+    assert!(!iter.is_valid());
+
+    db.put(b"b", b"v").unwrap(); // before the seek target
+    db.put(b"m", b"v").unwrap();
+    db.put(b"r", b"v").unwrap(); // past the upper bound
+    iter.next().unwrap();
+    assert_eq!(drain(&mut iter), vec![b"m".to_vec()]);
+}
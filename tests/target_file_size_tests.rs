@@ -0,0 +1,153 @@
+// Target output file size tests
+//
+// Flush and compaction cut their output into SSTables of about
+// Options::target_file_size_base bytes (times target_file_size_multiplier
+// per level below L1) instead of writing one file per job.
+
+use lsm_engine::sstable::builder::TableOptions;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+const TARGET: u64 = 16 * 1024;
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:05}", i).into_bytes()
+}
+
+fn value(i: u32) -> Vec<u8> {
+    vec![(i % 251) as u8; 200]
+}
+
+fn options() -> Options {
+    Options {
+        target_file_size_base: TARGET,
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+fn files_at(db: &DB, level: u32) -> Vec<SSTableMeta> {
+    let mut files: Vec<_> = db
+        .live_files_metadata()
+        .into_iter()
+        .filter(|meta| meta.level == level)
+        .collect();
+    files.sort_by(|a, b| a.min_key.cmp(&b.min_key));
+    files
+}
+
+/// Files are near the target size and cover disjoint, ascending ranges.
+fn assert_cut(files: &[SSTableMeta], target: u64) {
+    assert!(
+        files.len() > 1,
+        "expected several files, got {}",
+        files.len()
+    );
+    for file in files {
+        assert!(file.file_size < 2 * target, "{} bytes", file.file_size);
+    }
+    for pair in files.windows(2) {
+        assert!(pair[0].max_key < pair[1].min_key);
+    }
+}
+
+// =============================================================================
+// Test 1: Flush writes several L0 tables that survive reopen
+// =============================================================================
+#[test]
+fn flush_cuts_output_at_target_size() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..500 {
+            db.put(&key(i), &value(i)).unwrap();
+        }
+        db.flush().unwrap();
+
+        let l0 = files_at(&db, 0);
+        assert_cut(&l0, TARGET);
+        assert_eq!(l0.iter().map(|f| f.entry_count).sum::<u64>(), 500);
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert!(files_at(&db, 0).len() > 1);
+    for i in 0..500 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i)), "key {i}");
+    }
+}
+
+// =============================================================================
+// Test 2: Compaction output is cut too, sized by the level multiplier
+// =============================================================================
+#[test]
+fn compaction_cuts_output_per_level() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            target_file_size_base: 64 * 1024 * 1024,
+            ..options()
+        },
+    )
+    .unwrap();
+    // One table per flush with the large target
+    for batch in 0..4 {
+        for i in (batch..500).step_by(4) {
+            db.put(&key(i), &value(i)).unwrap();
+        }
+        db.flush().unwrap();
+    }
+    assert_eq!(files_at(&db, 0).len(), 4);
+    drop(db);
+
+    // Reopen with a 4KB target; the multiplier only applies below L1
+    let db = DB::open(
+        dir.path(),
+        Options {
+            target_file_size_base: TARGET / 4,
+            target_file_size_multiplier: 4,
+            ..options()
+        },
+    )
+    .unwrap();
+    db.compact_range(None, None).unwrap();
+    assert!(files_at(&db, 0).is_empty());
+    let l1 = files_at(&db, 1);
+    assert_cut(&l1, TARGET / 4);
+    for i in 0..500 {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(value(i)), "key {i}");
+    }
+
+    let table_options = TableOptions {
+        target_file_size_base: TARGET,
+        target_file_size_multiplier: 4,
+        ..TableOptions::default()
+    };
+    assert_eq!(table_options.target_file_size(0), TARGET);
+    assert_eq!(table_options.target_file_size(1), TARGET);
+    assert_eq!(table_options.target_file_size(3), TARGET * 16);
+    assert_eq!(TableOptions::default().target_file_size(6), u64::MAX);
+}
+
+// =============================================================================
+// Test 3: A compaction that drops every entry writes no table
+// =============================================================================
+#[test]
+fn compaction_of_only_tombstones_writes_nothing() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    for i in 0..50 {
+        db.put(&key(i), &value(i)).unwrap();
+    }
+    db.flush().unwrap();
+    for i in 0..50 {
+        db.delete(&key(i)).unwrap();
+    }
+    db.flush().unwrap();
+
+    db.compact_range(None, None).unwrap();
+    assert!(db.live_files_metadata().is_empty());
+    assert_eq!(db.get(&key(7)).unwrap(), None);
+}
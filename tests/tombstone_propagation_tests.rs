@@ -1,19 +1,22 @@
 // M23: Tombstone Propagation Correctness Tests
 //
 // Verifies that tombstones (empty values marking deleted keys) are:
-//   - KEPT when compacting above a level that still has the key
-//   - DROPPED only at the bottommost level (no deeper overlapping SSTables)
+//   - KEPT when compacting above a level that may still have the key
+//   - DROPPED at the bottommost level (no deeper overlapping SSTables), or
+//     wherever no deeper SSTable's key range contains the key
 //
 // The "zombie data" bug: if a tombstone is dropped at L1 but the key still
 // exists at L2, the old value at L2 resurfaces — a silent correctness failure.
 
 use std::sync::Arc;
 
-use lsm_engine::compaction::scheduler::CompactionScheduler;
+use lsm_engine::compaction::scheduler::{
+    CompactionJobOptions, CompactionScheduler, run_compaction,
+};
 use lsm_engine::compaction::size_tiered::SizeTieredStrategy;
 use lsm_engine::iterator::StorageIterator;
 use lsm_engine::manifest::version::VersionSet;
-use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::builder::{SSTableBuilder, TableOptions};
 use lsm_engine::sstable::reader::SSTable;
 use lsm_engine::{DB, Options};
 use tempfile::tempdir;
@@ -263,8 +266,8 @@ fn all_tombstones_bottommost_compaction() {
 }
 
 // =============================================================================
-// Test 6: DB-level: put in L0, delete in L0, compact only L0→L1 (not bottommost
-// due to max_levels=3), tombstone persists in L1
+// Test 6: DB-level: put in L0, delete in L0, compact only L0→L1 (not bottommost:
+// L2 holds an older version), tombstone persists in L1
 // =============================================================================
 #[test]
 fn put_delete_flush_both_tombstone_persists_in_l1() {
@@ -286,18 +289,8 @@ fn put_delete_flush_both_tombstone_persists_in_l1() {
         v.levels[0].push(meta);
     }
 
-    // L0 SSTable 2: key_x = "" (tombstone) — newer flush
-    // MergeIterator treats lower index as newer (higher priority).
-    // SizeTieredStrategy clones L0 as-is for inputs, so sst1 (index 0)
-    // wins the merge over sst2 (index 1). This means the VALUE wins
-    // over the tombstone in this direct-manipulation test.
-    //
-    // In real DB usage, the read path checks L0 newest-first (iter().rev()),
-    // so the tombstone would correctly shadow the value. The merge iterator
-    // doesn't know about L0 ordering — it just uses array index as priority.
-    //
-    // This test verifies that whichever entry wins the merge gets propagated
-    // correctly to L1 (kept, not dropped, because L2 could exist).
+    // L0 SSTable 2: key_x = "" (tombstone) — newer flush, so it shadows
+    // the value in the merge and must be propagated to L1.
     let sst2_id = 802u64;
     {
         let path = db_path.join(format!("{:06}.sst", sst2_id));
@@ -311,6 +304,21 @@ fn put_delete_flush_both_tombstone_persists_in_l1() {
         v.levels[0].push(meta);
     }
 
+    // L2 SSTable: an older key_x the tombstone must keep shadowing, so the
+    // L0→L1 compaction is not bottommost
+    let sst3_id = 803u64;
+    {
+        let path = db_path.join(format!("{:06}.sst", sst3_id));
+        let mut builder = SSTableBuilder::new(&path, sst3_id, 4096).unwrap();
+        builder.add(b"key_x", b"ancient").unwrap();
+        let mut meta = builder.finish().unwrap();
+        meta.level = 2;
+
+        let current = vs.current();
+        let mut v = current.write().unwrap();
+        v.levels[2].push(meta);
+    }
+
     // Compact with threshold=2 to trigger L0→L1
     let strategy = Arc::new(SizeTieredStrategy::new(2));
     let scheduler =
@@ -320,7 +328,7 @@ fn put_delete_flush_both_tombstone_persists_in_l1() {
     std::thread::sleep(std::time::Duration::from_millis(300));
     scheduler.shutdown().unwrap();
 
-    // L1 should have the tombstone (not bottommost because max_levels=3)
+    // L1 should have the tombstone (not bottommost: L2 overlaps it)
     let current = vs.current();
     let v = current.read().unwrap();
 
@@ -334,13 +342,80 @@ fn put_delete_flush_both_tombstone_persists_in_l1() {
         while iter.is_valid() {
             if iter.key() == b"key_x" {
                 found = true;
-                // MergeIterator gives priority to lower index (sst1 at index 0),
-                // so the value "value" wins the merge. The key insight is that
-                // the entry is correctly propagated to L1 regardless of which
-                // version won — it wasn't incorrectly dropped.
+                assert!(iter.value().is_empty(), "the newer tombstone should win");
             }
             iter.next().unwrap();
         }
         assert!(found, "key_x should be in L1 after compaction");
     }
 }
+
+// =============================================================================
+// Test 7: Above a deeper level, tombstones for keys outside every deeper
+// SSTable's range are dropped; the rest are kept
+// =============================================================================
+#[test]
+fn tombstones_dropped_per_key_above_deeper_levels() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path();
+    let vs = VersionSet::new(3);
+
+    // L2: old values in [key_m, key_p]
+    let l2_id = 901u64;
+    {
+        let path = db_path.join(format!("{:06}.sst", l2_id));
+        let mut builder = SSTableBuilder::new(&path, l2_id, 4096).unwrap();
+        builder.add(b"key_m", b"old").unwrap();
+        builder.add(b"key_p", b"old").unwrap();
+        let mut meta = builder.finish().unwrap();
+        meta.level = 2;
+        vs.current().write().unwrap().levels[2].push(meta);
+    }
+
+    // L0: tombstones inside and outside that range, and a live value
+    let l0_id = 902u64;
+    {
+        let path = db_path.join(format!("{:06}.sst", l0_id));
+        let mut builder = SSTableBuilder::new(&path, l0_id, 4096).unwrap();
+        builder.add(b"key_a", &[]).unwrap();
+        builder.add(b"key_n", &[]).unwrap();
+        builder.add(b"key_z", b"live").unwrap();
+        let mut meta = builder.finish().unwrap();
+        meta.level = 0;
+        vs.current().write().unwrap().levels[0].push(meta);
+    }
+
+    // L0→L1 overlaps L2, so it isn't bottommost as a whole
+    let strategy = SizeTieredStrategy::new(1);
+    let table_options = TableOptions::default();
+    assert!(
+        run_compaction(
+            &vs,
+            &strategy,
+            db_path,
+            &table_options,
+            &CompactionJobOptions::default(),
+        )
+        .unwrap()
+        .is_some()
+    );
+
+    let current = vs.current();
+    let v = current.read().unwrap();
+    let l1_path = db_path.join(format!("{:06}.sst", v.level(1)[0].id));
+    let sst = SSTable::open(&l1_path).unwrap();
+    let mut iter = sst.iter().unwrap();
+    let mut entries = Vec::new();
+    while iter.is_valid() {
+        entries.push((iter.key().to_vec(), iter.value().to_vec()));
+        iter.next().unwrap();
+    }
+    assert_eq!(
+        entries,
+        vec![
+            (b"key_n".to_vec(), Vec::new()),
+            (b"key_z".to_vec(), b"live".to_vec()),
+        ],
+        "only the tombstone shadowing L2 should survive"
+    );
+}
@@ -0,0 +1,217 @@
+// Two-phase commit tests
+//
+// DB::write_prepared() logs a batch under a transaction id without
+// applying it; commit_prepared() applies it and rollback_prepared()
+// discards it. Batches still prepared at a crash are reinstated on reopen,
+// even after the WAL that first logged them was flushed.
+
+use lsm_engine::wal::{RecordType, SyncPolicy, WALRecord};
+use lsm_engine::{CompactionStyle, DB, Error, Options, WriteBatch};
+use tempfile::tempdir;
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+fn batch(entries: &[(&[u8], Option<&[u8]>)]) -> WriteBatch {
+    let mut batch = WriteBatch::new();
+    for (key, value) in entries {
+        match value {
+            Some(value) => batch.put(key, value),
+            None => batch.delete(key),
+        };
+    }
+    batch
+}
+
+// =============================================================================
+// Test 1: Prepare and Commit records round-trip their batch
+// =============================================================================
+#[test]
+fn batch_records_round_trip() {
+    let writes = vec![
+        WALRecord::put(b"a".to_vec(), b"1".to_vec()),
+        WALRecord::delete(b"b".to_vec()),
+    ];
+    for record in [
+        WALRecord::prepare(b"tx".to_vec(), &writes),
+        WALRecord::commit(b"tx".to_vec(), &writes),
+    ] {
+        let decoded = WALRecord::decode(&record.encode()).unwrap();
+        assert_eq!(decoded.key, b"tx");
+        let batch = decoded.batch().unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].record_type, RecordType::Put);
+        assert_eq!(
+            (&batch[0].key[..], &batch[0].value[..]),
+            (&b"a"[..], &b"1"[..])
+        );
+        assert_eq!(batch[1].record_type, RecordType::Delete);
+        assert_eq!(batch[1].key, b"b");
+    }
+    assert!(WALRecord::rollback(b"tx".to_vec()).batch().is_err());
+}
+
+// =============================================================================
+// Test 2: A prepared batch is invisible until committed
+// =============================================================================
+#[test]
+fn commit_applies_prepared_batch() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"gone", b"old").unwrap();
+
+    db.write_prepared(
+        b"tx1",
+        batch(&[(b"k1", Some(b"v1")), (b"k2", Some(b"v2")), (b"gone", None)]),
+    )
+    .unwrap();
+    assert_eq!(db.get(b"k1").unwrap(), None);
+    assert_eq!(db.get(b"gone").unwrap(), Some(b"old".to_vec()));
+    assert_eq!(db.prepared_transactions(), vec![b"tx1".to_vec()]);
+
+    db.commit_prepared(b"tx1").unwrap();
+    assert_eq!(db.get(b"k1").unwrap(), Some(b"v1".to_vec()));
+    assert_eq!(db.get(b"k2").unwrap(), Some(b"v2".to_vec()));
+    assert_eq!(db.get(b"gone").unwrap(), None);
+    assert!(db.prepared_transactions().is_empty());
+}
+
+// =============================================================================
+// Test 3: Rollback discards; unknown and duplicate ids are rejected
+// =============================================================================
+#[test]
+fn rollback_and_invalid_ids() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+
+    db.write_prepared(b"tx", batch(&[(b"k", Some(b"v"))]))
+        .unwrap();
+    let err = db
+        .write_prepared(b"tx", batch(&[(b"k", Some(b"other"))]))
+        .unwrap_err();
+    assert!(matches!(err, Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput));
+
+    db.rollback_prepared(b"tx").unwrap();
+    assert_eq!(db.get(b"k").unwrap(), None);
+    assert!(matches!(db.commit_prepared(b"tx"), Err(Error::NotFound)));
+    assert!(matches!(db.rollback_prepared(b"tx"), Err(Error::NotFound)));
+}
+
+// =============================================================================
+// Test 4: Recovery reinstates prepared batches and replays decided ones
+// =============================================================================
+#[test]
+fn recovery_reinstates_prepared() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.write_prepared(b"committed", batch(&[(b"a", Some(b"1"))]))
+            .unwrap();
+        db.write_prepared(b"pending", batch(&[(b"b", Some(b"2"))]))
+            .unwrap();
+        db.write_prepared(b"aborted", batch(&[(b"c", Some(b"3"))]))
+            .unwrap();
+        db.commit_prepared(b"committed").unwrap();
+        db.rollback_prepared(b"aborted").unwrap();
+        db.put(b"d", b"4").unwrap();
+        // Dropped without close: recovery replays the WAL
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.prepared_transactions(), vec![b"pending".to_vec()]);
+    assert_eq!(db.get(b"a").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+    assert_eq!(db.get(b"c").unwrap(), None);
+    assert_eq!(db.get(b"d").unwrap(), Some(b"4".to_vec()));
+
+    db.commit_prepared(b"pending").unwrap();
+    assert_eq!(db.get(b"b").unwrap(), Some(b"2".to_vec()));
+}
+
+// =============================================================================
+// Test 5: A prepared batch outlives the flush of the WAL that logged it
+// =============================================================================
+#[test]
+fn prepared_survives_flush() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.put(b"before", b"x").unwrap();
+        db.write_prepared(b"tx", batch(&[(b"k", Some(b"v"))]))
+            .unwrap();
+        db.flush().unwrap();
+        db.put(b"after", b"y").unwrap();
+        db.flush().unwrap();
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.prepared_transactions(), vec![b"tx".to_vec()]);
+    db.commit_prepared(b"tx").unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"v".to_vec()));
+}
+
+// =============================================================================
+// Test 6: Committed writes take consecutive sequence numbers
+// =============================================================================
+#[test]
+fn committed_writes_in_updates() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"first", b"0").unwrap();
+    db.write_prepared(b"tx", batch(&[(b"k1", Some(b"1")), (b"k2", None)]))
+        .unwrap();
+    db.put(b"middle", b"m").unwrap();
+    db.commit_prepared(b"tx").unwrap();
+    db.put(b"last", b"z").unwrap();
+
+    let updates: Vec<_> = db
+        .get_updates_since(0)
+        .unwrap()
+        .map(|u| u.unwrap())
+        .collect();
+    let keys: Vec<_> = updates.iter().map(|u| u.record.key.clone()).collect();
+    assert_eq!(
+        keys,
+        vec![
+            b"first".to_vec(),
+            b"middle".to_vec(),
+            b"k1".to_vec(),
+            b"k2".to_vec(),
+            b"last".to_vec()
+        ]
+    );
+    let sequences: Vec<_> = updates.iter().map(|u| u.sequence).collect();
+    assert_eq!(sequences, vec![1, 2, 3, 4, 5]);
+    assert_eq!(updates[3].record.record_type, RecordType::Delete);
+}
+
+// =============================================================================
+// Test 7: Rollback is synced before it takes effect, whatever the policy
+// =============================================================================
+#[test]
+fn rollback_synced_under_batched_policy() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        sync_policy: SyncPolicy::EveryNWrites(1000),
+        ..options()
+    };
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.write_prepared(b"tx", batch(&[(b"k", Some(b"v"))]))
+            .unwrap();
+        let before = db.stats().wal_syncs.manual_syncs;
+        db.rollback_prepared(b"tx").unwrap();
+        assert_eq!(db.stats().wal_syncs.manual_syncs - before, 1);
+        assert!(db.prepared_transactions().is_empty());
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert!(db.prepared_transactions().is_empty());
+    assert_eq!(db.get(b"k").unwrap(), None);
+    assert!(matches!(db.commit_prepared(b"tx"), Err(Error::NotFound)));
+}
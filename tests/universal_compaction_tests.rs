@@ -0,0 +1,146 @@
+// Universal compaction tests
+//
+// UniversalStrategy treats each L0 SSTable and each non-empty level below
+// as a sorted run and, once there are enough runs, merges runs of similar
+// size: everything when space amplification gets too high, else the first
+// window of similarly sized runs, else the newest runs. Outputs fill the
+// levels from the bottom up.
+
+use lsm_engine::compaction::CompactionStrategy;
+use lsm_engine::compaction::universal::UniversalStrategy;
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn make_sst(id: u64, level: u32, file_size: u64) -> SSTableMeta {
+    SSTableMeta {
+        id,
+        level,
+        min_key: b"a".to_vec(),
+        max_key: b"z".to_vec(),
+        file_size,
+        entry_count: 100,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
+    }
+}
+
+/// Seven levels: L0 from `l0` (oldest first), then `deeper` as
+/// (level, size) single-file runs.
+fn make_levels(l0: &[u64], deeper: &[(u32, u64)]) -> Vec<Vec<SSTableMeta>> {
+    let mut levels = vec![Vec::new(); 7];
+    for (i, size) in l0.iter().enumerate() {
+        levels[0].push(make_sst(i as u64 + 1, 0, *size));
+    }
+    for (level, size) in deeper {
+        levels[*level as usize].push(make_sst(100 + *level as u64, *level, *size));
+    }
+    levels
+}
+
+fn input_ids(strategy: &UniversalStrategy, levels: &[Vec<SSTableMeta>]) -> (Vec<u64>, u32) {
+    let task = strategy
+        .pick_compaction(levels)
+        .expect("expected a compaction");
+    let mut ids: Vec<u64> = task.inputs.iter().map(|s| s.id).collect();
+    ids.sort();
+    (ids, task.output_level)
+}
+
+// =============================================================================
+// Test 1: Below the run trigger nothing happens; space amplification
+// merges everything into the bottom level
+// =============================================================================
+#[test]
+fn space_amplification_merges_all_runs() {
+    let strategy = UniversalStrategy::new(4, 7);
+    assert!(
+        strategy
+            .pick_compaction(&make_levels(&[100, 100, 100], &[]))
+            .is_none()
+    );
+
+    let levels = make_levels(&[100, 100, 100, 100], &[]);
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2, 3, 4], 6));
+
+    // Newer runs at 300% of the oldest level run
+    let levels = make_levels(&[100, 100, 100], &[(6, 100)]);
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2, 3, 106], 6));
+}
+
+// =============================================================================
+// Test 2: Similarly sized runs merge into the level above the next run
+// =============================================================================
+#[test]
+fn size_ratio_merges_similar_runs() {
+    let strategy = UniversalStrategy::new(4, 7);
+    let levels = make_levels(&[100, 100, 100], &[(6, 10_000)]);
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2, 3], 5));
+
+    // The newest run is too small to pull in the next; the window starts
+    // after it and ends at the oldest L0 file
+    let levels = make_levels(&[500, 500, 10], &[(5, 5_000), (6, 100_000)]);
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2], 4));
+
+    // A wider size ratio lets a larger run join
+    let strategy = UniversalStrategy::new(4, 7).with_size_ratio(100);
+    let levels = make_levels(&[100, 100, 100], &[(5, 500), (6, 100_000)]);
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2, 3, 105], 5));
+}
+
+// =============================================================================
+// Test 3: Without similar runs, the newest runs merge to cut the count
+// =============================================================================
+#[test]
+fn too_many_runs_merges_newest() {
+    let strategy = UniversalStrategy::new(4, 7);
+    // Runs, newest first: 1, 10, 100 (L0), 1000 (L5), 100000 (L6)
+    let levels = make_levels(&[100, 10, 1], &[(5, 1_000), (6, 100_000)]);
+    // The two newest would leave the oldest L0 file behind, newer than
+    // the output, so all three go, to the level above L5
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2, 3], 4));
+
+    // No empty level above L1 to take L0 output: L1 joins the merge
+    let levels = make_levels(&[100, 10, 1], &[(1, 1_000), (6, 100_000)]);
+    assert_eq!(input_ids(&strategy, &levels), (vec![1, 2, 3, 101], 1));
+}
+
+// =============================================================================
+// Test 4: Through the DB, with results surviving a reopen
+// =============================================================================
+#[test]
+fn db_universal_compaction() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        compaction_style: CompactionStyle::Universal,
+        level0_file_num_compaction_trigger: 3,
+        ..Options::default()
+    };
+    let key = |i: u32| format!("key_{:04}", i).into_bytes();
+
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for batch in 0..6u32 {
+            for i in 0..100 {
+                db.put(&key(i), format!("v{batch}").as_bytes()).unwrap();
+            }
+            db.flush().unwrap();
+            db.compact_range(None, None).unwrap();
+        }
+        // Compaction keeps the run count under the trigger
+        let files = db.live_files_metadata();
+        let l0_runs = files.iter().filter(|f| f.level == 0).count();
+        let mut levels: Vec<u32> = files.iter().map(|f| f.level).filter(|l| *l > 0).collect();
+        levels.sort();
+        levels.dedup();
+        assert!(l0_runs + levels.len() < 3, "{files:?}");
+        assert!(levels.iter().all(|l| *l >= 5), "{levels:?}");
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    for i in 0..100 {
+        assert_eq!(db.get(&key(i)).unwrap().as_deref(), Some(&b"v5"[..]));
+    }
+}
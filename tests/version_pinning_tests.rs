@@ -0,0 +1,155 @@
+// Version pinning tests
+//
+// Iterators and snapshots hold the Version they read from; compaction
+// defers deleting SSTables until no pinned version lists them.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::manifest::version::{Version, VersionSet};
+use lsm_engine::sstable::footer::SSTableMeta;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+/// Size-tiered so compact_range() merges L0 files.
+fn open_db(path: &Path) -> DB {
+    let opts = Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        ..Options::default()
+    };
+    DB::open(path, opts).unwrap()
+}
+
+fn sst_files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".sst"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn make_sst(id: u64) -> SSTableMeta {
+    SSTableMeta {
+        id,
+        level: 0,
+        min_key: b"a".to_vec(),
+        max_key: b"z".to_vec(),
+        file_size: 0,
+        entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
+    }
+}
+
+fn version_with(ids: &[u64]) -> Version {
+    let mut v = Version::new(4);
+    v.levels[0].extend(ids.iter().map(|&id| make_sst(id)));
+    v
+}
+
+// =============================================================================
+// Test 1: Obsolete files wait until no reader holds a version listing them
+// =============================================================================
+#[test]
+fn obsolete_files_deferred_while_pinned() {
+    let vs = VersionSet::new(4);
+    vs.install(version_with(&[1, 2]));
+
+    let pinned = vs.current();
+    vs.install(version_with(&[3]));
+    assert!(vs.take_deletable_files().is_empty());
+    assert_eq!(vs.num_pinned_versions(), 1);
+
+    drop(pinned);
+    let mut deletable = vs.take_deletable_files();
+    deletable.sort();
+    assert_eq!(deletable, vec![1, 2]);
+    assert_eq!(vs.num_pinned_versions(), 0);
+
+    // Each file is handed out once
+    assert!(vs.take_deletable_files().is_empty());
+}
+
+// =============================================================================
+// Test 2: A live iterator keeps compacted-away SSTables on disk
+// =============================================================================
+#[test]
+fn iterator_keeps_files_through_compaction() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    for i in 0..30u32 {
+        db.put(format!("k{:02}", i).as_bytes(), b"v").unwrap();
+        if i % 10 == 9 {
+            db.flush().unwrap();
+        }
+    }
+    let before = sst_files(dir.path());
+    assert_eq!(before.len(), 3);
+
+    let mut iter = db.iter().unwrap();
+    db.compact_range(None, None).unwrap();
+
+    // Inputs are still on disk while the iterator reads them
+    let during = sst_files(dir.path());
+    assert!(before.iter().all(|f| during.contains(f)));
+
+    let mut count = 0;
+    while iter.is_valid() {
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 30);
+
+    drop(iter);
+    let after = sst_files(dir.path());
+    assert!(before.iter().all(|f| !after.contains(f)));
+    assert_eq!(db.iter().unwrap().key(), b"k00");
+}
+
+// =============================================================================
+// Test 3: Snapshots pin their version too
+// =============================================================================
+#[test]
+fn snapshot_pins_version() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    db.put(b"a", b"1").unwrap();
+    db.flush().unwrap();
+    db.put(b"a", b"2").unwrap();
+    db.flush().unwrap();
+
+    let snapshot = db.snapshot();
+    db.compact_range(None, None).unwrap();
+    assert_eq!(db.version_set.num_pinned_versions(), 1);
+    assert_eq!(snapshot.get(b"a").unwrap().unwrap(), b"2");
+
+    drop(snapshot);
+    assert_eq!(db.version_set.num_pinned_versions(), 0);
+}
+
+// =============================================================================
+// Test 4: Iterators record the sequence number they read at
+// =============================================================================
+#[test]
+fn iterator_sequence_number() {
+    let dir = tempdir().unwrap();
+    let db = open_db(dir.path());
+    db.put(b"a", b"1").unwrap();
+
+    let mut iter = db.iter().unwrap();
+    let seq = iter.sequence();
+    assert_eq!(
+        seq,
+        db.next_sequence.load(std::sync::atomic::Ordering::SeqCst)
+    );
+
+    db.put(b"b", b"2").unwrap();
+    assert_eq!(iter.sequence(), seq);
+    iter.refresh().unwrap();
+    assert!(iter.sequence() > seq);
+}
@@ -11,6 +11,10 @@ fn make_sst(id: u64, level: u32, min_key: &[u8], max_key: &[u8]) -> SSTableMeta
         max_key: max_key.to_vec(),
         file_size: 0,
         entry_count: 0,
+        creation_time: 0,
+        smallest_seqno: 0,
+        largest_seqno: 0,
+        path_id: 0,
     }
 }
 
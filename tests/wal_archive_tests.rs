@@ -0,0 +1,135 @@
+// WAL archive tests
+//
+// With Options::wal_ttl or Options::wal_size_limit_bytes set, flushed WALs
+// are moved to the `archive` directory instead of being deleted, and kept
+// until they're older than the TTL or the archive is over the size limit.
+// Expired WALs are pruned after every flush and on open.
+
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use lsm_engine::wal::RecordType;
+use lsm_engine::wal::archive::{ARCHIVE_DIR, WALArchive};
+use lsm_engine::wal::reader::WALReader;
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+fn options(wal_ttl: Duration, wal_size_limit_bytes: u64) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        wal_ttl,
+        wal_size_limit_bytes,
+        recycle_log_file_num: 2,
+        ..Options::default()
+    }
+}
+
+/// Write 20 keys tagged with `round`, then flush.
+fn write_round(db: &DB, round: u32) {
+    for i in 0..20u32 {
+        db.put(format!("r{round}_{i:02}").as_bytes(), &[b'v'; 100])
+            .unwrap();
+    }
+    db.flush().unwrap();
+}
+
+/// Keys written to the WAL at `path`.
+fn logged_keys(path: &Path) -> Vec<Vec<u8>> {
+    WALReader::new(path)
+        .unwrap()
+        .iter()
+        .map(|r| r.unwrap())
+        .filter(|r| r.record_type != RecordType::Sequence)
+        .map(|r| r.key)
+        .collect()
+}
+
+fn wal_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "wal"))
+        .count()
+}
+
+// =============================================================================
+// Test 1: Flushed WALs are archived, in order and readable
+// =============================================================================
+#[test]
+fn flushed_wals_archived() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(HOUR, 0)).unwrap();
+    for round in 0..3 {
+        write_round(&db, round);
+    }
+
+    // Archiving turns recycling off: only the active WAL is left
+    assert_eq!(wal_count(dir.path()), 1);
+    let archive = WALArchive::new(dir.path(), HOUR, 0);
+    let files = archive.wal_files().unwrap();
+    assert_eq!(files.len(), 3);
+    assert!(files.windows(2).all(|w| w[0].0 < w[1].0));
+    for (round, (_, path)) in files.iter().enumerate() {
+        assert!(path.starts_with(dir.path().join(ARCHIVE_DIR)));
+        let keys = logged_keys(path);
+        assert_eq!(keys.len(), 20);
+        assert_eq!(keys[0], format!("r{round}_00").into_bytes());
+    }
+}
+
+// =============================================================================
+// Test 2: The oldest WALs are pruned past the size limit
+// =============================================================================
+#[test]
+fn size_limit_prunes_oldest() {
+    let dir = tempdir().unwrap();
+    // Each WAL is a bit over 2KB: room for two
+    let db = DB::open(dir.path(), options(Duration::ZERO, 5000)).unwrap();
+    for round in 0..5 {
+        write_round(&db, round);
+    }
+
+    let archive = WALArchive::new(dir.path(), Duration::ZERO, 5000);
+    let files = archive.wal_files().unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(logged_keys(&files[0].1)[0], b"r3_00".to_vec());
+    assert_eq!(db.get(b"r0_05").unwrap(), Some(vec![b'v'; 100]));
+}
+
+// =============================================================================
+// Test 3: WALs past the TTL are pruned on open
+// =============================================================================
+#[test]
+fn ttl_prunes_on_open() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options(HOUR, 0)).unwrap();
+        for round in 0..2 {
+            write_round(&db, round);
+        }
+    }
+    let archive = WALArchive::new(dir.path(), HOUR, 0);
+    let files = archive.wal_files().unwrap();
+    assert_eq!(files.len(), 2);
+    assert!(archive.expired(SystemTime::now()).unwrap().is_empty());
+
+    // Age the oldest past the TTL
+    File::options()
+        .write(true)
+        .open(&files[0].1)
+        .unwrap()
+        .set_modified(SystemTime::now() - 2 * HOUR)
+        .unwrap();
+    assert_eq!(
+        archive.expired(SystemTime::now()).unwrap(),
+        vec![files[0].1.clone()]
+    );
+
+    let _db = DB::open(dir.path(), options(HOUR, 0)).unwrap();
+    let left = archive.wal_files().unwrap();
+    assert_eq!(left, files[1..].to_vec());
+}
@@ -0,0 +1,134 @@
+// Block-format WAL tests
+//
+// With WALOptions::block_format, records are framed as fragments in 32KB
+// blocks. A torn or corrupted block is skipped on replay and the records
+// in later blocks are still recovered, with their sequence numbers.
+
+use std::path::Path;
+
+use lsm_engine::wal::block::BLOCK_SIZE;
+use lsm_engine::wal::reader::WALReader;
+use lsm_engine::wal::writer::WALManager;
+use lsm_engine::wal::{RecordType, WALOptions, WALRecord};
+use lsm_engine::{ChecksumType, CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn blocks() -> WALOptions {
+    WALOptions {
+        block_format: true,
+        ..WALOptions::default()
+    }
+}
+
+fn read_puts(path: &Path) -> Vec<WALRecord> {
+    WALReader::new(path)
+        .unwrap()
+        .iter()
+        .map(|r| r.unwrap())
+        .filter(|r| r.record_type == RecordType::Put)
+        .collect()
+}
+
+/// Overwrite `len` bytes at `offset` of `path` with garbage.
+fn damage(path: &Path, offset: usize, len: usize) {
+    let mut data = std::fs::read(path).unwrap();
+    for byte in &mut data[offset..offset + len] {
+        *byte ^= 0x5a;
+    }
+    std::fs::write(path, data).unwrap();
+}
+
+// =============================================================================
+// Test 1: Records of every size, spanning blocks, read back in order
+// =============================================================================
+#[test]
+fn records_span_blocks() {
+    let dir = tempdir().unwrap();
+    let mut manager = WALManager::with_options(dir.path(), blocks()).unwrap();
+    let sizes = [0, 10, BLOCK_SIZE - 100, 3 * BLOCK_SIZE, 5, BLOCK_SIZE];
+    for (i, &size) in sizes.iter().enumerate() {
+        let record = WALRecord::put(format!("key{i}").into_bytes(), vec![i as u8; size]);
+        manager.append(i as u64 + 1, &record).unwrap();
+    }
+    manager.active_writer().sync().unwrap();
+
+    let records = read_puts(manager.active_path());
+    assert_eq!(records.len(), sizes.len());
+    for (i, record) in records.iter().enumerate() {
+        assert_eq!(record.key, format!("key{i}").into_bytes());
+        assert_eq!(record.value, vec![i as u8; sizes[i]]);
+    }
+}
+
+// =============================================================================
+// Test 2: A damaged block is skipped; later blocks are still read
+// =============================================================================
+#[test]
+fn damaged_block_skipped() {
+    let dir = tempdir().unwrap();
+    let mut manager = WALManager::with_options(dir.path(), blocks()).unwrap();
+    // ~1KB records: about 32 per block, 4 blocks
+    for i in 0..128u64 {
+        let record = WALRecord::put(format!("key{i:03}").into_bytes(), vec![b'v'; 1000]);
+        manager.append(i + 1, &record).unwrap();
+    }
+    manager.active_writer().sync().unwrap();
+    let path = manager.active_path().to_path_buf();
+    drop(manager);
+
+    damage(&path, BLOCK_SIZE + 500, 16);
+    let keys: Vec<_> = read_puts(&path).into_iter().map(|r| r.key).collect();
+    assert!(keys.len() < 128, "records in the damaged block are lost");
+    assert!(keys.len() > 128 - 40, "only one block is lost");
+    assert_eq!(keys.first().unwrap(), b"key000");
+    assert_eq!(keys.last().unwrap(), b"key127");
+}
+
+// =============================================================================
+// Test 3: Recovery replays past a torn block, numbering writes correctly
+// =============================================================================
+#[test]
+fn recovery_skips_torn_block() {
+    let dir = tempdir().unwrap();
+    let options = || Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        wal_block_format: true,
+        checksum: ChecksumType::Crc32c,
+        ..Options::default()
+    };
+    let wal = {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..200u32 {
+            db.put(format!("key{i:03}").as_bytes(), &[b'v'; 600])
+                .unwrap();
+        }
+        let mut wals: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "wal"))
+            .collect();
+        wals.sort();
+        wals.pop().unwrap()
+        // Dropped without close: recovery replays the WAL
+    };
+    damage(&wal, 2 * BLOCK_SIZE + 100, 8);
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert!(db.get(b"key000").unwrap().is_some());
+    assert!(db.get(b"key199").unwrap().is_some());
+    let missing = (0..200u32)
+        .filter(|i| db.get(format!("key{i:03}").as_bytes()).unwrap().is_none())
+        .count();
+    assert!(missing > 0 && missing < 60, "missing {missing}");
+
+    // Writes after the damage kept their numbers: the next write follows
+    // the last one logged
+    let updates: Vec<_> = db
+        .get_updates_since(190)
+        .unwrap()
+        .map(|u| u.unwrap())
+        .collect();
+    assert_eq!(updates.first().unwrap().sequence, 190);
+    assert_eq!(updates.first().unwrap().record.key, b"key189");
+}
@@ -0,0 +1,121 @@
+// Size-based WAL rotation tests
+//
+// With WALOptions::max_wal_size (Options::max_wal_size), WALManager::append()
+// switches to a new WAL whenever the active one reaches that size, without
+// waiting for a memtable flush. The WALs it switched away from are replayed
+// in order on recovery and deleted with the memtable's last WAL once it's
+// flushed.
+
+use std::path::Path;
+
+use lsm_engine::wal::reader::WALReader;
+use lsm_engine::wal::writer::WALManager;
+use lsm_engine::wal::{RecordType, WALOptions, WALRecord};
+use lsm_engine::{CompactionStyle, DB, Options};
+use tempfile::tempdir;
+
+fn wal_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "wal"))
+        .count()
+}
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        max_wal_size: 4096,
+        ..Options::default()
+    }
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{i:04}").into_bytes()
+}
+
+// =============================================================================
+// Test 1: append() switches WALs at the size; take_size_rotated() hands
+// them over in order
+// =============================================================================
+#[test]
+fn append_switches_at_max_size() {
+    let dir = tempdir().unwrap();
+    let options = WALOptions {
+        max_wal_size: 1024,
+        ..WALOptions::default()
+    };
+    let mut manager = WALManager::with_options(dir.path(), options).unwrap();
+    let first_id = manager.active_wal_id();
+    for i in 0..50u32 {
+        manager
+            .append(u64::from(i) + 1, &WALRecord::put(key(i), vec![b'v'; 100]))
+            .unwrap();
+    }
+
+    let rotated = manager.take_size_rotated();
+    // ~120 bytes per record: a switch every 9 records
+    assert_eq!(rotated.len(), 5);
+    assert_eq!(manager.active_wal_id(), first_id + 5);
+    assert!(manager.take_size_rotated().is_empty());
+
+    // Each WAL starts with the sequence number of its first write
+    let mut keys = Vec::new();
+    let mut firsts = Vec::new();
+    for path in rotated
+        .iter()
+        .map(|p| p.as_path())
+        .chain([manager.active_path()])
+    {
+        assert!(std::fs::metadata(path).unwrap().len() < 1024 + 200);
+        for record in WALReader::new(path).unwrap().iter() {
+            let record = record.unwrap();
+            match record.record_type {
+                RecordType::Sequence => firsts.push(record.sequence_number().unwrap()),
+                _ => keys.push(record.key),
+            }
+        }
+    }
+    assert_eq!(keys, (0..50).map(key).collect::<Vec<_>>());
+    assert_eq!(firsts, vec![1, 10, 19, 28, 37, 46]);
+}
+
+// =============================================================================
+// Test 2: Recovery replays every WAL of the memtable
+// =============================================================================
+#[test]
+fn size_rotated_wals_recovered() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        for i in 0..200 {
+            db.put(&key(i), format!("value_{i}").as_bytes()).unwrap();
+        }
+        db.delete(&key(7)).unwrap();
+        assert!(wal_count(dir.path()) > 1);
+    }
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    for i in 0..200 {
+        let expected = (i != 7).then(|| format!("value_{i}").into_bytes());
+        assert_eq!(db.get(&key(i)).unwrap(), expected, "key {i}");
+    }
+}
+
+// =============================================================================
+// Test 3: A flush deletes all of the memtable's WALs
+// =============================================================================
+#[test]
+fn flush_deletes_size_rotated_wals() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    for i in 0..200 {
+        db.put(&key(i), &[b'v'; 50]).unwrap();
+    }
+    assert!(wal_count(dir.path()) > 2);
+
+    db.flush().unwrap();
+    assert_eq!(wal_count(dir.path()), 1);
+    assert_eq!(db.get(&key(150)).unwrap(), Some(vec![b'v'; 50]));
+}
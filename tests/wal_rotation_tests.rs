@@ -1,10 +1,16 @@
 // M09: WAL Rotation tests
 // Tests for WAL file rotation on memtable flush.
 
+use std::sync::Arc;
+
+use lsm_engine::delete_scheduler::DeleteScheduler;
+use lsm_engine::manifest::version::VersionSet;
 use lsm_engine::wal::SyncPolicy;
+use lsm_engine::wal::WALOptions;
 use lsm_engine::wal::WALRecord;
 use lsm_engine::wal::reader::WALReader;
 use lsm_engine::wal::writer::WALManager;
+use lsm_engine::{DB, Options};
 
 // =============================================================================
 // Test 1: Rotate creates a new WAL file, old one still exists
@@ -119,3 +125,68 @@ fn multiple_rotations_correct_file_count() {
         .count();
     assert_eq!(wal_count, 2);
 }
+
+// =============================================================================
+// Test 5: Create, rotate (recycled too) and delete in a new nested directory,
+// each synced into the directory
+// =============================================================================
+#[test]
+fn rotation_in_new_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal_dir = dir.path().join("a").join("b");
+    let options = WALOptions {
+        recycle_log_file_num: 1,
+        ..WALOptions::default()
+    };
+    let mut manager = WALManager::with_options(&wal_dir, options).unwrap();
+    assert!(manager.active_path().starts_with(&wal_dir));
+
+    let old = manager.rotate().unwrap();
+    assert!(manager.recycle_wal(&old));
+    let reused = manager.rotate().unwrap();
+    assert!(!old.exists());
+    WALManager::delete_wal(&reused).unwrap();
+    assert!(!reused.exists());
+    assert!(manager.active_path().exists());
+}
+
+// =============================================================================
+// Test 6: VersionSet deletes WALs directly or through the trash
+// =============================================================================
+#[test]
+fn version_set_deletes_wals() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal = dir.path().join("000001.wal");
+    std::fs::write(&wal, b"flushed").unwrap();
+    VersionSet::new(7).delete_wal(&wal).unwrap();
+    assert!(!wal.exists());
+
+    std::fs::write(&wal, b"flushed").unwrap();
+    let scheduler = Arc::new(DeleteScheduler::new(1024 * 1024));
+    VersionSet::new(7)
+        .with_delete_scheduler(scheduler)
+        .delete_wal(&wal)
+        .unwrap();
+    assert!(!wal.exists());
+}
+
+// =============================================================================
+// Test 7: Flushed WALs left behind by a crash are deleted on open
+// =============================================================================
+#[test]
+fn open_deletes_leftover_flushed_wals() {
+    let dir = tempfile::tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), Options::default()).unwrap();
+        db.put(b"key", b"value").unwrap();
+        db.flush().unwrap();
+    }
+    // A WAL older than the flushed ones, as if the flush had crashed
+    // before deleting it
+    let leftover = dir.path().join("000000.wal");
+    std::fs::write(&leftover, b"").unwrap();
+
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert!(!leftover.exists());
+    assert_eq!(db.get(b"key").unwrap(), Some(b"value".to_vec()));
+}
@@ -0,0 +1,96 @@
+// WAL sync metrics tests
+//
+// Every WAL fsync is counted by what triggered it (the sync policy's
+// per-write or batched syncs, the background timer, or an explicit
+// request) and timed in a latency histogram, reported in Stats::wal_syncs.
+
+use std::time::Duration;
+
+use lsm_engine::wal::SyncPolicy;
+use lsm_engine::{DB, Options, WriteOptions};
+use tempfile::tempdir;
+
+fn open(dir: &std::path::Path, sync_policy: SyncPolicy) -> DB {
+    let options = Options {
+        sync_policy,
+        ..Options::default()
+    };
+    DB::open(dir, options).unwrap()
+}
+
+// =============================================================================
+// Test 1: EveryWrite syncs once per write, each timed
+// =============================================================================
+#[test]
+fn every_write_syncs_counted() {
+    let dir = tempdir().unwrap();
+    let db = open(dir.path(), SyncPolicy::EveryWrite);
+    let before = db.stats().wal_syncs;
+
+    for i in 0..10u32 {
+        db.put(format!("key{i}").as_bytes(), b"v").unwrap();
+    }
+
+    let syncs = db.stats().wal_syncs;
+    // The first write also logs its Sequence record
+    assert_eq!(syncs.every_write_syncs - before.every_write_syncs, 11);
+    assert_eq!(syncs.batch_syncs, 0);
+    assert_eq!(syncs.timer_syncs, 0);
+    assert_eq!(
+        syncs.latency.count(),
+        syncs.every_write_syncs + syncs.manual_syncs
+    );
+    assert_eq!(
+        syncs.latency.buckets().iter().sum::<u64>(),
+        syncs.latency.count()
+    );
+    assert!(syncs.latency.max() >= syncs.latency.mean());
+    assert!(syncs.latency.percentile(50.0) <= syncs.latency.percentile(99.0));
+    assert!(syncs.latency.percentile(100.0) <= syncs.latency.max());
+}
+
+// =============================================================================
+// Test 2: Batched, manual and per-write syncs are told apart
+// =============================================================================
+#[test]
+fn batch_and_manual_syncs_counted() {
+    let dir = tempdir().unwrap();
+    let db = open(dir.path(), SyncPolicy::EveryNWrites(5));
+    let manual_before = db.stats().wal_syncs.manual_syncs;
+
+    // 20 writes plus the Sequence record: 21 appends, 4 batch syncs
+    for i in 0..20u32 {
+        db.put(format!("key{i}").as_bytes(), b"v").unwrap();
+    }
+    db.flush_wal(true).unwrap();
+    let synced = WriteOptions {
+        sync: true,
+        ..WriteOptions::default()
+    };
+    db.put_with_options(b"commit", b"done", &synced).unwrap();
+
+    let syncs = db.stats().wal_syncs;
+    assert_eq!(syncs.batch_syncs, 4);
+    assert_eq!(syncs.manual_syncs - manual_before, 2);
+    assert_eq!(syncs.every_write_syncs, 0);
+}
+
+// =============================================================================
+// Test 3: Background timer syncs are counted as such
+// =============================================================================
+#[test]
+fn timer_syncs_counted() {
+    let dir = tempdir().unwrap();
+    let db = open(dir.path(), SyncPolicy::EveryNMillis(10));
+
+    db.put(b"key", b"value").unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while db.stats().wal_syncs.timer_syncs == 0 {
+        assert!(std::time::Instant::now() < deadline, "no timer sync");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    let syncs = db.stats().wal_syncs;
+    assert_eq!(syncs.every_write_syncs, 0);
+    assert_eq!(syncs.batch_syncs, 0);
+}
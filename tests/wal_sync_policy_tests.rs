@@ -1,9 +1,11 @@
 // M10: Configurable SyncPolicy tests
 // Tests that each sync policy behaves correctly.
 
+use std::time::{Duration, Instant};
+
 use lsm_engine::wal::SyncPolicy;
 use lsm_engine::wal::reader::WALReader;
-use lsm_engine::wal::writer::WALWriter;
+use lsm_engine::wal::writer::{WALManager, WALWriter};
 use lsm_engine::wal::{RecordType, WALRecord};
 
 fn make_record(i: usize) -> WALRecord {
@@ -124,3 +126,120 @@ fn all_policies_produce_readable_files() {
         }
     }
 }
+
+/// Poll until the active WAL has no unsynced writes, for up to 2s.
+fn wait_synced(manager: &mut WALManager) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while Instant::now() < deadline {
+        if manager.active_writer().writes_since_sync() == 0 {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+// =============================================================================
+// Test 4: EveryNMillis — the manager's timer syncs, across rotations
+// =============================================================================
+#[test]
+fn every_n_millis_synced_in_background() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut manager = WALManager::new(dir.path(), SyncPolicy::EveryNMillis(20)).unwrap();
+
+    for i in 0..3 {
+        manager.active_writer().append(&make_record(i)).unwrap();
+    }
+    assert!(wait_synced(&mut manager), "timer should sync the WAL");
+
+    // The timer follows the new WAL after a rotation
+    manager.rotate().unwrap();
+    for i in 3..6 {
+        manager.active_writer().append(&make_record(i)).unwrap();
+    }
+    assert!(wait_synced(&mut manager), "timer should sync the new WAL");
+
+    // A writer without a manager has no timer
+    let path = dir.path().join("standalone.wal");
+    let mut writer = WALWriter::new(&path, SyncPolicy::EveryNMillis(20)).unwrap();
+    writer.append(&make_record(0)).unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(writer.writes_since_sync(), 1);
+}
+
+// =============================================================================
+// Test 5: Dropping the manager stops the timer without waiting it out
+// =============================================================================
+#[test]
+fn every_n_millis_stops_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut manager = WALManager::new(dir.path(), SyncPolicy::EveryNMillis(60_000)).unwrap();
+    manager.active_writer().append(&make_record(0)).unwrap();
+    let path = manager.active_path().to_path_buf();
+
+    let start = Instant::now();
+    drop(manager);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    let records: Vec<WALRecord> = WALReader::new(&path)
+        .unwrap()
+        .iter()
+        .map(|r| r.unwrap())
+        .collect();
+    assert_eq!(records.len(), 1);
+}
+
+// =============================================================================
+// Test 6: Changing the policy syncs pending writes and moves the timer
+// =============================================================================
+#[test]
+fn set_sync_policy_at_runtime() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut manager = WALManager::new(dir.path(), SyncPolicy::EveryNMillis(60_000)).unwrap();
+    manager.active_writer().append(&make_record(0)).unwrap();
+    assert_eq!(manager.active_writer().writes_since_sync(), 1);
+
+    // Tightening syncs what's pending, then every write
+    manager.set_sync_policy(SyncPolicy::EveryWrite).unwrap();
+    assert_eq!(manager.active_writer().writes_since_sync(), 0);
+    manager.active_writer().append(&make_record(1)).unwrap();
+    assert_eq!(manager.active_writer().writes_since_sync(), 0);
+
+    // Relaxing lets writes build up, across a rotation too
+    manager
+        .set_sync_policy(SyncPolicy::EveryNWrites(10))
+        .unwrap();
+    manager.rotate().unwrap();
+    manager.active_writer().append(&make_record(2)).unwrap();
+    assert_eq!(manager.active_writer().writes_since_sync(), 1);
+
+    // A timer started at runtime syncs in the background
+    manager
+        .set_sync_policy(SyncPolicy::EveryNMillis(20))
+        .unwrap();
+    manager.active_writer().append(&make_record(3)).unwrap();
+    assert!(wait_synced(&mut manager), "new timer should sync the WAL");
+}
+
+// =============================================================================
+// Test 7: DB::set_sync_policy switches the DB's WAL
+// =============================================================================
+#[test]
+fn db_set_sync_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let db = lsm_engine::DB::open(
+        dir.path(),
+        lsm_engine::Options {
+            sync_policy: SyncPolicy::EveryNWrites(1000),
+            ..lsm_engine::Options::default()
+        },
+    )
+    .unwrap();
+    db.put(b"a", b"1").unwrap();
+    assert_eq!(db.stats().wal_syncs.every_write_syncs, 0);
+
+    db.set_sync_policy(SyncPolicy::EveryWrite).unwrap();
+    db.put(b"b", b"2").unwrap();
+    db.put(b"c", b"3").unwrap();
+    assert_eq!(db.stats().wal_syncs.every_write_syncs, 2);
+}
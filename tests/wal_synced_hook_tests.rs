@@ -0,0 +1,197 @@
+// WAL synced hook tests
+//
+// DB::set_wal_synced_hook() registers a callback handed each logged write
+// once an fsync has made it durable, for a replication agent to ship the
+// log without polling the WAL files. Updates arrive in log order with the
+// sequence numbers get_updates_since() gives them.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use lsm_engine::db::updates::WALUpdate;
+use lsm_engine::wal::RecordType;
+use lsm_engine::wal::writer::WALSyncedHook;
+use lsm_engine::{CompactionStyle, DB, Options, WriteBatch};
+use tempfile::tempdir;
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+/// A hook recording each delivery, and what it recorded.
+fn recorder() -> (WALSyncedHook, Arc<Mutex<Vec<Vec<WALUpdate>>>>) {
+    let deliveries = Arc::new(Mutex::new(Vec::new()));
+    let hook: WALSyncedHook = {
+        let deliveries = Arc::clone(&deliveries);
+        Arc::new(move |updates: &[WALUpdate]| {
+            deliveries.lock().unwrap().push(updates.to_vec());
+        })
+    };
+    (hook, deliveries)
+}
+
+fn keys(deliveries: &Mutex<Vec<Vec<WALUpdate>>>) -> Vec<(u64, Vec<u8>)> {
+    deliveries
+        .lock()
+        .unwrap()
+        .iter()
+        .flatten()
+        .map(|u| (u.sequence, u.record.key.clone()))
+        .collect()
+}
+
+// =============================================================================
+// Test 1: With EveryWrite each write is delivered on its own
+// =============================================================================
+#[test]
+fn every_write_delivers_each_write() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"before", b"x").unwrap();
+
+    let (hook, deliveries) = recorder();
+    db.set_wal_synced_hook(Some(hook));
+    db.put(b"a", b"1").unwrap();
+    db.delete(b"b").unwrap();
+
+    let delivered = deliveries.lock().unwrap().clone();
+    assert_eq!(delivered.len(), 2);
+    assert_eq!(delivered[0][0].sequence, 2);
+    assert_eq!(delivered[0][0].record.key, b"a");
+    assert_eq!(delivered[0][0].record.value, b"1");
+    assert_eq!(delivered[1][0].sequence, 3);
+    assert_eq!(delivered[1][0].record.record_type, RecordType::Delete);
+}
+
+// =============================================================================
+// Test 2: Batched syncs deliver nothing until the fsync, then all of it
+// =============================================================================
+#[test]
+fn batched_sync_delivers_after_fsync() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            sync_policy: lsm_engine::wal::SyncPolicy::EveryNWrites(100),
+            ..options()
+        },
+    )
+    .unwrap();
+    let (hook, deliveries) = recorder();
+    db.set_wal_synced_hook(Some(hook));
+
+    db.put(b"k1", b"v").unwrap();
+    db.put(b"k2", b"v").unwrap();
+    db.put(b"k3", b"v").unwrap();
+    assert!(deliveries.lock().unwrap().is_empty());
+
+    db.flush_wal(true).unwrap();
+    assert_eq!(deliveries.lock().unwrap().len(), 1);
+    assert_eq!(
+        keys(&deliveries),
+        vec![
+            (1, b"k1".to_vec()),
+            (2, b"k2".to_vec()),
+            (3, b"k3".to_vec())
+        ]
+    );
+}
+
+// =============================================================================
+// Test 3: The background syncer delivers with EveryNMillis
+// =============================================================================
+#[test]
+fn timer_sync_delivers() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            sync_policy: lsm_engine::wal::SyncPolicy::EveryNMillis(10),
+            ..options()
+        },
+    )
+    .unwrap();
+    let (hook, deliveries) = recorder();
+    db.set_wal_synced_hook(Some(hook));
+    db.put(b"k", b"v").unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while deliveries.lock().unwrap().is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(keys(&deliveries), vec![(1, b"k".to_vec())]);
+}
+
+// =============================================================================
+// Test 4: Commits deliver their writes; Prepare and Rollback nothing
+// =============================================================================
+#[test]
+fn commit_delivers_batch() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    let (hook, deliveries) = recorder();
+    db.set_wal_synced_hook(Some(hook));
+
+    let mut batch = WriteBatch::new();
+    batch.put(b"t1", b"1").delete(b"t2");
+    db.write_prepared(b"tx", batch).unwrap();
+    let mut aborted = WriteBatch::new();
+    aborted.put(b"never", b"x");
+    db.write_prepared(b"aborted", aborted).unwrap();
+    db.rollback_prepared(b"aborted").unwrap();
+    assert!(deliveries.lock().unwrap().is_empty());
+
+    db.commit_prepared(b"tx").unwrap();
+    db.put(b"after", b"y").unwrap();
+    assert_eq!(
+        keys(&deliveries),
+        vec![
+            (1, b"t1".to_vec()),
+            (2, b"t2".to_vec()),
+            (3, b"after".to_vec())
+        ]
+    );
+}
+
+// =============================================================================
+// Test 5: Deliveries match get_updates_since() across WAL rotations, and
+// stop once the hook is removed
+// =============================================================================
+#[test]
+fn matches_updates_since_across_rotation() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(
+        dir.path(),
+        Options {
+            wal_ttl: Duration::from_secs(3600),
+            ..options()
+        },
+    )
+    .unwrap();
+    let (hook, deliveries) = recorder();
+    db.set_wal_synced_hook(Some(hook));
+
+    db.put(b"a", b"1").unwrap();
+    db.flush().unwrap();
+    db.put(b"b", b"2").unwrap();
+    db.delete(b"a").unwrap();
+
+    let logged: Vec<_> = db
+        .get_updates_since(0)
+        .unwrap()
+        .map(|u| {
+            let u = u.unwrap();
+            (u.sequence, u.record.key)
+        })
+        .collect();
+    assert_eq!(logged.len(), 3);
+    assert_eq!(keys(&deliveries), logged);
+
+    db.set_wal_synced_hook(None);
+    db.put(b"c", b"3").unwrap();
+    assert_eq!(keys(&deliveries).len(), 3);
+}
@@ -134,3 +134,59 @@ fn write_delete_record() {
     assert_eq!(decoded.record_type, RecordType::Delete);
     assert_eq!(decoded.key, b"gone");
 }
+
+// =============================================================================
+// Test 6: Reopening an existing WAL resumes the offset at its end
+// =============================================================================
+#[test]
+fn offset_restored_on_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("test.wal");
+    let record = WALRecord::put(b"key".to_vec(), b"value".to_vec());
+    let size = record.encoded_size() as u64;
+
+    {
+        let mut writer = WALWriter::new(&path, SyncPolicy::EveryWrite).unwrap();
+        writer.append(&record).unwrap();
+        writer.append(&record).unwrap();
+    }
+
+    let mut writer = WALWriter::new(&path, SyncPolicy::EveryWrite).unwrap();
+    assert_eq!(writer.offset(), size * 2);
+    writer.append(&record).unwrap();
+    assert_eq!(writer.offset(), size * 3);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), size * 3);
+}
+
+// =============================================================================
+// Test 7: truncate_to drops a torn tail so appends follow the last good record
+// =============================================================================
+#[test]
+fn truncate_to_repairs_torn_tail() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("test.wal");
+    let good = WALRecord::put(b"good".to_vec(), b"1".to_vec());
+    let good_size = good.encoded_size() as u64;
+
+    {
+        let mut writer = WALWriter::new(&path, SyncPolicy::EveryWrite).unwrap();
+        writer.append(&good).unwrap();
+    }
+    // Half of a second record, as a crash mid-write leaves it
+    let torn = WALRecord::put(b"torn".to_vec(), b"2".to_vec()).encode();
+    let mut data = std::fs::read(&path).unwrap();
+    data.extend_from_slice(&torn[..torn.len() / 2]);
+    std::fs::write(&path, &data).unwrap();
+
+    let mut writer = WALWriter::new(&path, SyncPolicy::EveryWrite).unwrap();
+    assert!(writer.truncate_to(writer.offset() + 1).is_err());
+    writer.truncate_to(good_size).unwrap();
+    assert_eq!(writer.offset(), good_size);
+    writer
+        .append(&WALRecord::put(b"next".to_vec(), b"3".to_vec()))
+        .unwrap();
+
+    let reader = lsm_engine::wal::reader::WALReader::new(&path).unwrap();
+    let keys: Vec<_> = reader.iter().map(|r| r.unwrap().key).collect();
+    assert_eq!(keys, vec![b"good".to_vec(), b"next".to_vec()]);
+}
@@ -0,0 +1,141 @@
+// Write batch tests
+//
+// DB::write() applies a WriteBatch of puts and deletes in order, logged
+// as a single Batch record: recovery replays every write in it or, if
+// the record was torn by a crash, none of them.
+
+use lsm_engine::wal::{RecordType, WALRecord};
+use lsm_engine::{CompactionStyle, DB, Options, WriteBatch};
+use tempfile::tempdir;
+
+fn options() -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: A Batch record round-trips its mixed writes
+// =============================================================================
+#[test]
+fn batch_record_round_trip() {
+    let writes = vec![
+        WALRecord::put(b"a".to_vec(), b"1".to_vec()),
+        WALRecord::delete(b"b".to_vec()),
+        WALRecord::put(b"c".to_vec(), b"3".to_vec()),
+    ];
+    let decoded = WALRecord::decode(&WALRecord::write_batch(&writes).encode()).unwrap();
+    assert_eq!(decoded.record_type, RecordType::Batch);
+    let types: Vec<_> = decoded
+        .writes()
+        .unwrap()
+        .iter()
+        .map(|w| w.record_type)
+        .collect();
+    assert_eq!(
+        types,
+        vec![RecordType::Put, RecordType::Delete, RecordType::Put]
+    );
+}
+
+// =============================================================================
+// Test 2: Writes apply in batch order
+// =============================================================================
+#[test]
+fn writes_apply_in_order() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"old", b"x").unwrap();
+
+    let mut batch = WriteBatch::new();
+    batch
+        .put(b"k", b"1")
+        .delete(b"k")
+        .delete(b"old")
+        .put(b"old", b"y")
+        .put(b"new", b"z");
+    db.write(batch).unwrap();
+
+    assert_eq!(db.get(b"k").unwrap(), None);
+    assert_eq!(db.get(b"old").unwrap(), Some(b"y".to_vec()));
+    assert_eq!(db.get(b"new").unwrap(), Some(b"z".to_vec()));
+    db.write(WriteBatch::new()).unwrap();
+}
+
+// =============================================================================
+// Test 3: Recovery replays a whole batch, and none of a torn one
+// =============================================================================
+#[test]
+fn recovery_is_all_or_nothing() {
+    let dir = tempdir().unwrap();
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        db.put(b"a", b"1").unwrap();
+        let mut batch = WriteBatch::new();
+        batch.put(b"b", b"2").delete(b"a");
+        db.write(batch).unwrap();
+    }
+    {
+        let db = DB::open(dir.path(), options()).unwrap();
+        assert_eq!(db.get(b"a").unwrap(), None);
+        assert_eq!(db.get(b"b").unwrap(), Some(b"2".to_vec()));
+        db.put(b"a", b"again").unwrap();
+        db.flush().unwrap();
+
+        let mut batch = WriteBatch::new();
+        batch.put(b"c", b"3").delete(b"a");
+        db.write(batch).unwrap();
+    }
+
+    // Tear the batch record, as a crash mid-write would
+    let wal = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "wal"))
+        .max()
+        .unwrap();
+    let len = std::fs::metadata(&wal).unwrap().len();
+    let file = std::fs::OpenOptions::new().write(true).open(&wal).unwrap();
+    file.set_len(len - 3).unwrap();
+    drop(file);
+
+    let db = DB::open(dir.path(), options()).unwrap();
+    assert_eq!(db.get(b"c").unwrap(), None);
+    assert_eq!(db.get(b"a").unwrap(), Some(b"again".to_vec()));
+}
+
+// =============================================================================
+// Test 4: Batched writes take consecutive sequence numbers
+// =============================================================================
+#[test]
+fn batch_writes_in_updates() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options()).unwrap();
+    db.put(b"first", b"0").unwrap();
+    let mut batch = WriteBatch::new();
+    batch.put(b"k1", b"1").delete(b"k2");
+    db.write(batch).unwrap();
+    db.put(b"last", b"z").unwrap();
+
+    let updates: Vec<_> = db
+        .get_updates_since(0)
+        .unwrap()
+        .map(|u| u.unwrap())
+        .collect();
+    let logged: Vec<_> = updates
+        .iter()
+        .map(|u| (u.sequence, u.record.key.clone()))
+        .collect();
+    assert_eq!(
+        logged,
+        vec![
+            (1, b"first".to_vec()),
+            (2, b"k1".to_vec()),
+            (3, b"k2".to_vec()),
+            (4, b"last".to_vec())
+        ]
+    );
+    assert_eq!(updates[2].record.record_type, RecordType::Delete);
+}
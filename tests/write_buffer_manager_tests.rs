@@ -0,0 +1,102 @@
+// Write buffer manager tests
+//
+// A WriteBufferManager caps the memory of the memtables of every DB
+// sharing it (Options::write_buffer_manager): over budget, the largest
+// active memtable among them is frozen and flushed, whichever DB it's in.
+
+use std::sync::Arc;
+
+use lsm_engine::write_buffer_manager::WriteBufferManager;
+use lsm_engine::{DB, Options};
+use tempfile::tempdir;
+
+fn options(manager: &Arc<WriteBufferManager>) -> Options {
+    Options {
+        // Far above the budget: only the manager triggers flushes
+        memtable_size: 64 * 1024 * 1024,
+        write_buffer_manager: Some(Arc::clone(manager)),
+        disable_auto_compactions: true,
+        ..Options::default()
+    }
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("key_{:06}", i).into_bytes()
+}
+
+// =============================================================================
+// Test 1: Memtable memory is charged on write and credited on flush
+// =============================================================================
+#[test]
+fn charges_and_credits_memtables() {
+    let manager = Arc::new(WriteBufferManager::new(64 * 1024 * 1024));
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(&manager)).unwrap();
+    assert_eq!(manager.memory_usage(), 0);
+
+    for i in 0..100 {
+        db.put(&key(i), &[b'v'; 100]).unwrap();
+    }
+    let charged = manager.memory_usage();
+    assert!(charged >= 100 * 110, "{charged}");
+    assert_eq!(manager.mutable_memtable_memory_usage(), charged);
+    assert_eq!(db.stats().memtable_size, charged);
+
+    db.flush().unwrap();
+    assert_eq!(manager.memory_usage(), 0);
+    assert_eq!(manager.mutable_memtable_memory_usage(), 0);
+
+    db.put(b"k", b"v").unwrap();
+    assert!(manager.memory_usage() > 0);
+    drop(db);
+    assert_eq!(manager.memory_usage(), 0);
+}
+
+// =============================================================================
+// Test 2: Going over budget flushes long before memtable_size
+// =============================================================================
+#[test]
+fn over_budget_flushes() {
+    let manager = Arc::new(WriteBufferManager::new(64 * 1024));
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(&manager)).unwrap();
+    assert!(!manager.should_flush());
+
+    for i in 0..2000 {
+        db.put(&key(i), &[b'v'; 200]).unwrap();
+        assert!(manager.mutable_memtable_memory_usage() < 2 * 64 * 1024);
+    }
+    db.wait_for_background_jobs().unwrap();
+    assert!(db.live_files_metadata().len() >= 2);
+    for i in (0..2000).step_by(97) {
+        assert_eq!(db.get(&key(i)).unwrap(), Some(vec![b'v'; 200]));
+    }
+}
+
+// =============================================================================
+// Test 3: The largest memtable is flushed, even in another DB
+// =============================================================================
+#[test]
+fn flushes_largest_across_dbs() {
+    let manager = Arc::new(WriteBufferManager::new(256 * 1024));
+    let (dir_a, dir_b) = (tempdir().unwrap(), tempdir().unwrap());
+    let a = DB::open(dir_a.path(), options(&manager)).unwrap();
+    let b = DB::open(dir_b.path(), options(&manager)).unwrap();
+
+    // A fills most of the budget, then goes quiet
+    let mut i = 0;
+    while manager.mutable_memtable_memory_usage() < 200 * 1024 {
+        a.put(&key(i), &[b'a'; 500]).unwrap();
+        i += 1;
+    }
+    // B's small writes tip it over: A's memtable is the one flushed
+    let mut j = 0;
+    while a.live_files_metadata().is_empty() && j < 1000 {
+        b.put(&key(j), &[b'b'; 100]).unwrap();
+        j += 1;
+        a.wait_for_background_jobs().unwrap();
+    }
+    assert!(!a.live_files_metadata().is_empty());
+    assert!(b.live_files_metadata().is_empty());
+    assert_eq!(a.get(&key(0)).unwrap(), Some(vec![b'a'; 500]));
+}
@@ -0,0 +1,69 @@
+// Per-write sync tests
+//
+// WriteOptions::sync fsyncs the WAL before the write returns, whatever the
+// SyncPolicy, so a single critical write can be made durable amid bulk
+// writes synced less often. It can't be combined with disable_wal.
+
+use lsm_engine::wal::SyncPolicy;
+use lsm_engine::{CompactionStyle, DB, Error, Options, WriteOptions};
+use tempfile::tempdir;
+
+fn options(sync_policy: SyncPolicy) -> Options {
+    Options {
+        compaction_style: CompactionStyle::SizeTiered,
+        disable_auto_compactions: true,
+        sync_policy,
+        ..Options::default()
+    }
+}
+
+// =============================================================================
+// Test 1: sync with disable_wal is rejected before anything is written
+// =============================================================================
+#[test]
+fn sync_without_wal_rejected() {
+    let dir = tempdir().unwrap();
+    let db = DB::open(dir.path(), options(SyncPolicy::EveryWrite)).unwrap();
+    let both = WriteOptions {
+        disable_wal: true,
+        sync: true,
+    };
+
+    let err = db.put_with_options(b"key", b"value", &both).unwrap_err();
+    assert!(matches!(err, Error::Io(e) if e.kind() == std::io::ErrorKind::InvalidInput));
+    assert!(db.delete_with_options(b"key", &both).is_err());
+    assert_eq!(db.get(b"key").unwrap(), None);
+}
+
+// =============================================================================
+// Test 2: Synced writes mixed with bulk ones under a relaxed policy
+// =============================================================================
+#[test]
+fn synced_writes_amid_bulk_writes() {
+    let dir = tempdir().unwrap();
+    let synced = WriteOptions {
+        sync: true,
+        ..WriteOptions::default()
+    };
+    {
+        let db = DB::open(dir.path(), options(SyncPolicy::EveryNMillis(60_000))).unwrap();
+        for batch in 0..3u32 {
+            for i in 0..50u32 {
+                db.put(format!("b{batch}_{i:02}").as_bytes(), b"v").unwrap();
+            }
+            db.put_with_options(format!("commit_{batch}").as_bytes(), b"done", &synced)
+                .unwrap();
+        }
+        db.delete_with_options(b"b0_00", &synced).unwrap();
+    }
+
+    let db = DB::open(dir.path(), options(SyncPolicy::EveryWrite)).unwrap();
+    for batch in 0..3u32 {
+        assert_eq!(
+            db.get(format!("commit_{batch}").as_bytes()).unwrap(),
+            Some(b"done".to_vec())
+        );
+    }
+    assert_eq!(db.get(b"b2_49").unwrap(), Some(b"v".to_vec()));
+    assert_eq!(db.get(b"b0_00").unwrap(), None);
+}
@@ -0,0 +1,149 @@
+// Zstd dictionary compression tests
+//
+// With a dictionary size set, SSTableBuilder trains a zstd dictionary from
+// the table's own blocks, stores it in the meta block, and compresses
+// every block against it.
+
+use std::path::Path;
+
+use lsm_engine::iterator::StorageIterator;
+use lsm_engine::sstable::builder::SSTableBuilder;
+use lsm_engine::sstable::compression::{compress_with_dict, decompress_with_dict};
+use lsm_engine::sstable::reader::SSTable;
+use lsm_engine::{CompressionType, DB, Error, Options};
+use tempfile::tempdir;
+
+/// Short JSON values: little redundancy inside one small block, lots
+/// across blocks.
+fn json_value(i: u32) -> Vec<u8> {
+    let status = ["active", "pending", "suspended"][i as usize % 3];
+    format!(
+        "{{\"user_id\":{i},\"status\":\"{status}\",\"region\":\"eu-west-{}\",\"tier\":\"standard\"}}",
+        i % 4
+    )
+    .into_bytes()
+}
+
+fn key(i: u32) -> Vec<u8> {
+    format!("user:{:06}", i).into_bytes()
+}
+
+fn build_table(path: &Path, max_dict_bytes: usize) -> u64 {
+    let mut builder = SSTableBuilder::new(path, 1, 256)
+        .unwrap()
+        .with_compression(CompressionType::Zstd)
+        .with_zstd_dictionary(max_dict_bytes);
+    for i in 0..5000 {
+        builder.add(&key(i), &json_value(i)).unwrap();
+    }
+    builder.finish().unwrap().file_size
+}
+
+// =============================================================================
+// Test 1: Dictionary round-trip, and the wrong dictionary is corruption
+// =============================================================================
+#[test]
+fn dict_round_trip() {
+    let samples: Vec<Vec<u8>> = (0..500).map(json_value).collect();
+    let dict = zstd::dict::from_samples(&samples, 2048).unwrap();
+
+    let data = json_value(42);
+    let compressed = compress_with_dict(&data, &dict).unwrap();
+    assert!(compressed.len() < data.len());
+    assert_eq!(decompress_with_dict(&compressed, &dict).unwrap(), data);
+
+    let other = zstd::dict::from_samples(&samples[..250], 1024).unwrap();
+    assert!(matches!(
+        decompress_with_dict(&compressed, &other),
+        Err(Error::Corruption(_))
+    ));
+}
+
+// =============================================================================
+// Test 2: Dictionary tables are smaller than plain zstd and read back
+// =============================================================================
+#[test]
+fn dictionary_beats_plain_zstd() {
+    let dir = tempdir().unwrap();
+    let plain_path = dir.path().join("000001.sst");
+    let dict_path = dir.path().join("000002.sst");
+    let plain = build_table(&plain_path, 0);
+    let with_dict = build_table(&dict_path, 4096);
+    assert!(with_dict < plain, "{with_dict} vs {plain}");
+
+    let sst = SSTable::open(&dict_path).unwrap();
+    assert!(sst.compression_dict().is_some());
+    assert!(
+        SSTable::open(&plain_path)
+            .unwrap()
+            .compression_dict()
+            .is_none()
+    );
+
+    assert_eq!(sst.get(&key(1234)).unwrap().unwrap(), json_value(1234));
+    let mut iter = sst.iter().unwrap();
+    let mut count = 0;
+    while iter.is_valid() {
+        assert_eq!(iter.value(), json_value(count).as_slice());
+        count += 1;
+        iter.next().unwrap();
+    }
+    assert_eq!(count, 5000);
+}
+
+// =============================================================================
+// Test 3: Too little data to train falls back to plain zstd
+// =============================================================================
+#[test]
+fn tiny_table_skips_dictionary() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("000001.sst");
+    let mut builder = SSTableBuilder::new(&path, 1, 4096)
+        .unwrap()
+        .with_compression(CompressionType::Zstd)
+        .with_zstd_dictionary(4096);
+    builder.add(b"a", &json_value(1)).unwrap();
+    builder.finish().unwrap();
+
+    let sst = SSTable::open(&path).unwrap();
+    assert!(sst.compression_dict().is_none());
+    assert_eq!(sst.get(b"a").unwrap().unwrap(), json_value(1));
+}
+
+// =============================================================================
+// Test 4: Options::zstd_max_dict_bytes applies to flushed tables
+// =============================================================================
+#[test]
+fn db_flush_trains_dictionary() {
+    let dir = tempdir().unwrap();
+    let opts = Options {
+        compression: CompressionType::Zstd,
+        zstd_max_dict_bytes: 4096,
+        block_size: 512,
+        ..Options::default()
+    };
+    let db = DB::open(dir.path(), opts).unwrap();
+    for i in 0..2000 {
+        db.put(&key(i), &json_value(i)).unwrap();
+    }
+    db.flush().unwrap();
+
+    let sst_path = std::fs::read_dir(dir.path())
+        .unwrap()
+        .flatten()
+        .map(|e| e.path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "sst"))
+        .unwrap();
+    assert!(
+        SSTable::open(&sst_path)
+            .unwrap()
+            .compression_dict()
+            .is_some()
+    );
+    assert_eq!(db.get(&key(777)).unwrap().unwrap(), json_value(777));
+
+    // Reopen: the dictionary is read back from the meta block
+    drop(db);
+    let db = DB::open(dir.path(), Options::default()).unwrap();
+    assert_eq!(db.get(&key(1999)).unwrap().unwrap(), json_value(1999));
+}